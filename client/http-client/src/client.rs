@@ -27,28 +27,35 @@
 use std::sync::Arc;
 use std::time::Duration;
 
-use crate::transport::HttpTransportClient;
+use crate::cookies::CookieJar;
+use crate::transport::{HttpTransportClient, TransportConfig};
 use crate::types::{ErrorResponse, Id, NotificationSer, ParamsSer, RequestSer, Response};
 use async_trait::async_trait;
-use jsonrpsee_core::client::{CertificateStore, ClientT, IdKind, RequestIdManager, Subscription, SubscriptionClientT};
-use jsonrpsee_core::{Error, TEN_MB_SIZE_BYTES};
+use hyper::HeaderMap;
+use jsonrpsee_core::client::batch::{call_error, parse_batch_response, RawCallResult};
+use jsonrpsee_core::client::{
+	BatchRequestBuilder, BatchResponse, CertificateStore, ClientT, IdKind, LoadBalancing, MultiClient, Proxy,
+	RequestIdManager, Subscription, SubscriptionClientT,
+};
+use jsonrpsee_core::Error;
 use rustc_hash::FxHashMap;
 use serde::de::DeserializeOwned;
 
 /// Http Client Builder.
 #[derive(Debug)]
 pub struct HttpClientBuilder {
-	max_request_body_size: u32,
+	transport: TransportConfig,
 	request_timeout: Duration,
 	max_concurrent_requests: usize,
-	certificate_store: CertificateStore,
 	id_kind: IdKind,
+	load_balancing: LoadBalancing,
+	unhealthy_cooldown: Duration,
 }
 
 impl HttpClientBuilder {
 	/// Sets the maximum size of a request body in bytes (default is 10 MiB).
 	pub fn max_request_body_size(mut self, size: u32) -> Self {
-		self.max_request_body_size = size;
+		self.transport.max_request_body_size = size;
 		self
 	}
 
@@ -66,7 +73,7 @@ impl HttpClientBuilder {
 
 	/// Set which certificate store to use.
 	pub fn certificate_store(mut self, certificate_store: CertificateStore) -> Self {
-		self.certificate_store = certificate_store;
+		self.transport.certificate_store = certificate_store;
 		self
 	}
 
@@ -76,10 +83,61 @@ impl HttpClientBuilder {
 		self
 	}
 
+	/// Set custom headers passed to the server with every request (default is none).
+	///
+	/// The `Content-Type` and `Accept` headers are always `application/json`.
+	pub fn set_headers(mut self, headers: HeaderMap) -> Self {
+		self.transport.headers = headers;
+		self
+	}
+
+	/// Send the requests through a proxy, except for the hosts it bypasses (default is none).
+	pub fn proxy(mut self, proxy: Proxy) -> Self {
+		self.transport.proxy = Some(proxy);
+		self
+	}
+
+	/// Store the cookies set by the server in `jar` and send them back with the next requests (default is no
+	/// cookies).
+	pub fn cookie_jar(mut self, jar: CookieJar) -> Self {
+		self.transport.cookies = Some(jar);
+		self
+	}
+
+	/// Set how a client built with [`HttpClientBuilder::build_multi`] picks the endpoint of a call
+	/// (default is round-robin).
+	pub fn load_balancing(mut self, load_balancing: LoadBalancing) -> Self {
+		self.load_balancing = load_balancing;
+		self
+	}
+
+	/// Set for how long a client built with [`HttpClientBuilder::build_multi`] skips an endpoint after a call
+	/// failed to reach it (default is 30 seconds).
+	pub fn unhealthy_cooldown(mut self, cooldown: Duration) -> Self {
+		self.unhealthy_cooldown = cooldown;
+		self
+	}
+
 	/// Build the HTTP client with target to connect to.
 	pub fn build(self, target: impl AsRef<str>) -> Result<HttpClient, Error> {
-		let transport = HttpTransportClient::new(target, self.max_request_body_size, self.certificate_store)
-			.map_err(|e| Error::Transport(e.into()))?;
+		self.client(target)
+	}
+
+	/// Build a client spreading its calls over several targets and failing over between them, see [`MultiClient`].
+	pub fn build_multi<T: AsRef<str>>(
+		self,
+		targets: impl IntoIterator<Item = T>,
+	) -> Result<MultiClient<HttpClient>, Error> {
+		let clients = targets.into_iter().map(|target| self.client(target)).collect::<Result<Vec<_>, _>>()?;
+		if clients.is_empty() {
+			return Err(Error::Custom("No target to connect to".into()));
+		}
+		Ok(MultiClient::new(clients).balancing(self.load_balancing).cooldown(self.unhealthy_cooldown))
+	}
+
+	fn client(&self, target: impl AsRef<str>) -> Result<HttpClient, Error> {
+		let transport =
+			HttpTransportClient::new(target, self.transport.clone()).map_err(|e| Error::Transport(e.into()))?;
 		Ok(HttpClient {
 			transport,
 			id_manager: Arc::new(RequestIdManager::new(self.max_concurrent_requests, self.id_kind)),
@@ -91,11 +149,12 @@ impl HttpClientBuilder {
 impl Default for HttpClientBuilder {
 	fn default() -> Self {
 		Self {
-			max_request_body_size: TEN_MB_SIZE_BYTES,
+			transport: TransportConfig::default(),
 			request_timeout: Duration::from_secs(60),
 			max_concurrent_requests: 256,
-			certificate_store: CertificateStore::Native,
 			id_kind: IdKind::Number,
+			load_balancing: LoadBalancing::default(),
+			unhealthy_cooldown: Duration::from_secs(30),
 		}
 	}
 }
@@ -111,20 +170,42 @@ pub struct HttpClient {
 	id_manager: Arc<RequestIdManager>,
 }
 
-#[async_trait]
-impl ClientT for HttpClient {
-	async fn notification<'a>(&self, method: &'a str, params: Option<ParamsSer<'a>>) -> Result<(), Error> {
-		let notif = NotificationSer::new(method, params);
-		let fut = self.transport.send(serde_json::to_string(&notif).map_err(Error::ParseError)?);
-		match tokio::time::timeout(self.request_timeout, fut).await {
-			Ok(Ok(ok)) => Ok(ok),
-			Err(_) => Err(Error::RequestTimeout),
-			Ok(Err(e)) => Err(Error::Transport(e.into())),
-		}
+impl HttpClient {
+	/// Send a method call like [`ClientT::request`], which fails with [`Error::RequestTimeout`] if no response was
+	/// received within `timeout` instead of the request timeout of the client.
+	pub async fn request_with_timeout<'a, R>(
+		&self,
+		method: &'a str,
+		params: Option<ParamsSer<'a>>,
+		timeout: Duration,
+	) -> Result<R, Error>
+	where
+		R: DeserializeOwned,
+	{
+		self.send_request(method, params, timeout, None).await
 	}
 
-	/// Perform a request towards the server.
-	async fn request<'a, R>(&self, method: &'a str, params: Option<ParamsSer<'a>>) -> Result<R, Error>
+	/// Send a method call like [`ClientT::request`] with custom `headers`, which replace the headers of the client
+	/// with the same name.
+	pub async fn request_with_headers<'a, R>(
+		&self,
+		method: &'a str,
+		params: Option<ParamsSer<'a>>,
+		headers: &HeaderMap,
+	) -> Result<R, Error>
+	where
+		R: DeserializeOwned,
+	{
+		self.send_request(method, params, self.request_timeout, Some(headers)).await
+	}
+
+	async fn send_request<'a, R>(
+		&self,
+		method: &'a str,
+		params: Option<ParamsSer<'a>>,
+		timeout: Duration,
+		headers: Option<&HeaderMap>,
+	) -> Result<R, Error>
 	where
 		R: DeserializeOwned,
 	{
@@ -132,8 +213,9 @@ impl ClientT for HttpClient {
 		let id = guard.inner();
 		let request = RequestSer::new(&id, method, params);
 
-		let fut = self.transport.send_and_read_body(serde_json::to_string(&request).map_err(Error::ParseError)?);
-		let body = match tokio::time::timeout(self.request_timeout, fut).await {
+		let fut =
+			self.transport.send_and_read_body(serde_json::to_string(&request).map_err(Error::ParseError)?, headers);
+		let body = match tokio::time::timeout(timeout, fut).await {
 			Ok(Ok(body)) => body,
 			Err(_e) => {
 				return Err(Error::RequestTimeout);
@@ -157,11 +239,48 @@ impl ClientT for HttpClient {
 			Err(Error::InvalidRequestId)
 		}
 	}
+}
+
+#[async_trait]
+impl ClientT for HttpClient {
+	async fn notification<'a>(&self, method: &'a str, params: Option<ParamsSer<'a>>) -> Result<(), Error> {
+		let notif = NotificationSer::new(method, params);
+		let fut = self.transport.send(serde_json::to_string(&notif).map_err(Error::ParseError)?);
+		match tokio::time::timeout(self.request_timeout, fut).await {
+			Ok(Ok(ok)) => Ok(ok),
+			Err(_) => Err(Error::RequestTimeout),
+			Ok(Err(e)) => Err(Error::Transport(e.into())),
+		}
+	}
+
+	/// Perform a request towards the server.
+	async fn request<'a, R>(&self, method: &'a str, params: Option<ParamsSer<'a>>) -> Result<R, Error>
+	where
+		R: DeserializeOwned,
+	{
+		self.request_with_timeout(method, params, self.request_timeout).await
+	}
 
 	async fn batch_request<'a, R>(&self, batch: Vec<(&'a str, Option<ParamsSer<'a>>)>) -> Result<Vec<R>, Error>
 	where
 		R: DeserializeOwned + Default + Clone,
 	{
+		self.send_batch(batch)
+			.await?
+			.into_iter()
+			.map(|result| serde_json::from_value(result?).map_err(Error::ParseError))
+			.collect()
+	}
+
+	async fn batch<'a>(&self, batch: BatchRequestBuilder<'a>) -> Result<BatchResponse, Error> {
+		let (calls, decoder) = batch.into_parts();
+		decoder.decode(self.send_batch(calls).await?)
+	}
+}
+
+impl HttpClient {
+	/// Send a batch of calls, returns the result or the error of each call in order.
+	async fn send_batch<'a>(&self, batch: Vec<(&'a str, Option<ParamsSer<'a>>)>) -> Result<Vec<RawCallResult>, Error> {
 		let guard = self.id_manager.next_request_ids(batch.len())?;
 		let ids: Vec<Id> = guard.inner();
 
@@ -176,7 +295,8 @@ impl ClientT for HttpClient {
 			request_set.insert(&ids[pos], pos);
 		}
 
-		let fut = self.transport.send_and_read_body(serde_json::to_string(&batch_request).map_err(Error::ParseError)?);
+		let fut =
+			self.transport.send_and_read_body(serde_json::to_string(&batch_request).map_err(Error::ParseError)?, None);
 
 		let body = match tokio::time::timeout(self.request_timeout, fut).await {
 			Ok(Ok(body)) => body,
@@ -184,22 +304,25 @@ impl ClientT for HttpClient {
 			Ok(Err(e)) => return Err(Error::Transport(e.into())),
 		};
 
-		let rps: Vec<Response<_>> =
-			serde_json::from_slice(&body).map_err(|_| match serde_json::from_slice::<ErrorResponse>(&body) {
-				Ok(e) => Error::Request(e.to_string()),
-				Err(e) => Error::ParseError(e),
-			})?;
+		let rps = parse_batch_response(&body).map_err(|_| match serde_json::from_slice::<ErrorResponse>(&body) {
+			Ok(e) => Error::Request(e.to_string()),
+			Err(e) => Error::ParseError(e),
+		})?;
 
-		// NOTE: `R::default` is placeholder and will be replaced in loop below.
-		let mut responses = vec![R::default(); ordered_requests.len()];
-		for rp in rps {
-			let pos = match request_set.get(&rp.id) {
+		// NOTE: `None` is placeholder and will be replaced in loop below.
+		let mut responses = vec![None; ordered_requests.len()];
+		for (id, result) in rps {
+			let pos = match request_set.get(&id) {
 				Some(pos) => *pos,
 				None => return Err(Error::InvalidRequestId),
 			};
-			responses[pos] = rp.result
+			responses[pos] = Some(result);
 		}
-		Ok(responses)
+		responses
+			.into_iter()
+			.zip(ids.iter())
+			.map(|(result, id)| Ok(result.ok_or(Error::InvalidRequestId)?.map_err(|err| call_error(id.clone(), err))))
+			.collect()
 	}
 }
 
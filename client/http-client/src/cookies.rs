@@ -0,0 +1,291 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, SystemTime};
+
+use hyper::header::{HeaderMap, HeaderValue, SET_COOKIE};
+use hyper::Uri;
+
+/// Second-level public suffixes, under which anyone can register a domain, so that they are rejected as the domain of
+/// a cookie like the top-level domains. This isn't the whole public suffix list, only the most common ones.
+const PUBLIC_SUFFIXES: &[&str] = &[
+	"ac.uk",
+	"co.uk",
+	"gov.uk",
+	"org.uk",
+	"com.au",
+	"net.au",
+	"org.au",
+	"co.jp",
+	"ne.jp",
+	"or.jp",
+	"co.nz",
+	"co.za",
+	"co.in",
+	"com.br",
+	"com.cn",
+	"com.mx",
+	"com.tr",
+	"github.io",
+	"herokuapp.com",
+	"appspot.com",
+];
+
+/// Cookies set by the servers, such as session cookies, which are sent back with the next requests to them.
+///
+/// Clones of a jar share its cookies.
+#[derive(Debug, Clone, Default)]
+pub struct CookieJar {
+	cookies: Arc<Mutex<Vec<Cookie>>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Cookie {
+	name: String,
+	value: String,
+	/// Domain of the cookie, without leading dot.
+	domain: String,
+	/// Whether the cookie is sent to `domain` only, rather than to its subdomains as well.
+	host_only: bool,
+	path: String,
+	/// Whether the cookie is sent over HTTPS only.
+	secure: bool,
+	expires: Option<SystemTime>,
+}
+
+impl CookieJar {
+	/// Create an empty cookie jar.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Store a cookie as if `url` set it with the `Set-Cookie` header value `set_cookie`, such as
+	/// `session=1234; Path=/; Max-Age=3600`. Returns `false` if the cookie is invalid or rejected.
+	pub fn set(&self, url: &str, set_cookie: &str) -> bool {
+		match url.parse() {
+			Ok(uri) => self.store(&uri, set_cookie),
+			Err(_) => false,
+		}
+	}
+
+	/// Returns the value of the `Cookie` header sent with a request to `url`, if any cookie matches it.
+	pub fn get(&self, url: &str) -> Option<String> {
+		let uri = url.parse().ok()?;
+		self.header(&uri).and_then(|value| value.to_str().ok().map(ToOwned::to_owned))
+	}
+
+	/// Remove all the cookies.
+	pub fn clear(&self) {
+		self.cookies().clear();
+	}
+
+	/// Store the cookies set by a response from `uri`.
+	pub(crate) fn store_response(&self, uri: &Uri, headers: &HeaderMap) {
+		for set_cookie in headers.get_all(SET_COOKIE) {
+			if let Ok(set_cookie) = set_cookie.to_str() {
+				self.store(uri, set_cookie);
+			}
+		}
+	}
+
+	/// Returns the `Cookie` header for a request to `uri`, if any cookie matches it.
+	pub(crate) fn header(&self, uri: &Uri) -> Option<HeaderValue> {
+		let host = uri.host()?.to_ascii_lowercase();
+		let now = SystemTime::now();
+		let mut cookies = self.cookies();
+		cookies.retain(|cookie| cookie.expires.is_none_or(|expires| expires > now));
+		let header = cookies
+			.iter()
+			.filter(|cookie| cookie.matches(&host, uri.path(), uri.scheme_str() == Some("https")))
+			.map(|cookie| format!("{}={}", cookie.name, cookie.value))
+			.collect::<Vec<_>>()
+			.join("; ");
+		match header.is_empty() {
+			true => None,
+			false => HeaderValue::from_str(&header).ok(),
+		}
+	}
+
+	fn store(&self, uri: &Uri, set_cookie: &str) -> bool {
+		let cookie = match uri.host().and_then(|host| Cookie::parse(&host.to_ascii_lowercase(), uri.path(), set_cookie))
+		{
+			Some(cookie) => cookie,
+			None => return false,
+		};
+		let mut cookies = self.cookies();
+		cookies.retain(|c| (&c.name, &c.domain, &c.path) != (&cookie.name, &cookie.domain, &cookie.path));
+		// An expiry in the past removes the cookie.
+		if cookie.expires.is_none_or(|expires| expires > SystemTime::now()) {
+			cookies.push(cookie);
+		}
+		true
+	}
+
+	fn cookies(&self) -> MutexGuard<'_, Vec<Cookie>> {
+		self.cookies.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+	}
+}
+
+impl Cookie {
+	/// Parse the `Set-Cookie` header value `set_cookie` of a response from `host` to a request for `path`.
+	fn parse(host: &str, path: &str, set_cookie: &str) -> Option<Self> {
+		let mut attributes = set_cookie.split(';');
+		let (name, value) = attributes.next()?.split_once('=')?;
+		let name = name.trim();
+		if name.is_empty() {
+			return None;
+		}
+		let mut cookie = Cookie {
+			name: name.to_owned(),
+			value: value.trim().trim_matches('"').to_owned(),
+			domain: host.to_owned(),
+			host_only: true,
+			path: default_path(path).to_owned(),
+			secure: false,
+			expires: None,
+		};
+		let mut max_age = None;
+
+		for attribute in attributes {
+			let (key, value) = attribute.split_once('=').unwrap_or((attribute, ""));
+			let value = value.trim();
+			match key.trim().to_ascii_lowercase().as_str() {
+				"domain" if !value.is_empty() => {
+					let domain = value.trim_start_matches('.').to_ascii_lowercase();
+					// A server may only set cookies for its own domain or a parent domain of it, which can't be a
+					// public suffix, and a server reached by IP address only for itself.
+					let is_ip = host.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>().is_ok();
+					if is_ip || is_public_suffix(&domain) {
+						if domain != host {
+							return None;
+						}
+						continue;
+					}
+					if !domain_matches(host, &domain) {
+						return None;
+					}
+					cookie.domain = domain;
+					cookie.host_only = false;
+				}
+				"path" if value.starts_with('/') => cookie.path = value.to_owned(),
+				"secure" => cookie.secure = true,
+				"max-age" => max_age = value.parse::<i64>().ok(),
+				"expires" => cookie.expires = httpdate::parse_http_date(value).ok().or(cookie.expires),
+				_ => (),
+			}
+		}
+		// Max-Age has precedence over Expires.
+		if let Some(max_age) = max_age {
+			cookie.expires = Some(match u64::try_from(max_age) {
+				Ok(secs) if secs > 0 => SystemTime::now() + Duration::from_secs(secs),
+				_ => SystemTime::UNIX_EPOCH,
+			});
+		}
+		Some(cookie)
+	}
+
+	fn matches(&self, host: &str, path: &str, https: bool) -> bool {
+		let domain = match self.host_only {
+			true => host == self.domain,
+			false => domain_matches(host, &self.domain),
+		};
+		domain && path_matches(path, &self.path) && (https || !self.secure)
+	}
+}
+
+/// Returns whether `host` is `domain` or one of its subdomains.
+fn domain_matches(host: &str, domain: &str) -> bool {
+	host == domain || host.strip_suffix(domain).is_some_and(|subdomain| subdomain.ends_with('.'))
+}
+
+/// Returns whether `domain` is a top-level domain or one of the [`PUBLIC_SUFFIXES`].
+fn is_public_suffix(domain: &str) -> bool {
+	!domain.contains('.') || PUBLIC_SUFFIXES.contains(&domain)
+}
+
+/// Returns whether `path` is `cookie_path` or below it.
+fn path_matches(path: &str, cookie_path: &str) -> bool {
+	path.strip_prefix(cookie_path)
+		.is_some_and(|rest| cookie_path.ends_with('/') || rest.is_empty() || rest.starts_with('/'))
+}
+
+/// Returns the path of the cookies without `Path` attribute: the directory of the path of the request.
+fn default_path(path: &str) -> &str {
+	match path.rfind('/') {
+		Some(0) | None => "/",
+		Some(end) => &path[..end],
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::CookieJar;
+
+	#[test]
+	fn cookies_match_their_domain_and_path() {
+		let jar = CookieJar::new();
+		assert!(jar.set("http://rpc.example.com:80/api/v1", "session=abc; HttpOnly"));
+		assert!(jar.set("http://rpc.example.com:80/", "shared=1; Domain=.example.com; Path=/"));
+		assert!(jar.set("http://rpc.example.com:80/", "secret=2; Secure"));
+		assert!(!jar.set("http://rpc.example.com:80/", "other=3; Domain=example.org"));
+
+		assert_eq!(jar.get("http://rpc.example.com:80/api/v2").unwrap(), "session=abc; shared=1");
+		assert_eq!(jar.get("http://rpc.example.com:80/").unwrap(), "shared=1");
+		assert_eq!(jar.get("https://rpc.example.com:443/").unwrap(), "shared=1; secret=2");
+		assert_eq!(jar.get("http://other.example.com:80/api").unwrap(), "shared=1");
+		assert_eq!(jar.get("http://example.org:80/"), None);
+	}
+
+	#[test]
+	fn cookies_are_not_set_for_public_suffixes_or_ip_addresses() {
+		let jar = CookieJar::new();
+		assert!(!jar.set("http://rpc.example.com:80/", "a=1; Domain=.com"));
+		assert!(!jar.set("http://rpc.example.co.uk:80/", "b=2; Domain=co.uk"));
+		assert!(!jar.set("http://1.2.3.4:80/", "c=3; Domain=2.3.4"));
+		assert_eq!(jar.get("http://other.example.com:80/"), None);
+
+		// A public suffix or an IP address is the domain of the cookies it sets for itself, without subdomains.
+		assert!(jar.set("http://localhost:80/", "d=4; Domain=localhost"));
+		assert!(jar.set("http://1.2.3.4:80/", "e=5; Domain=1.2.3.4"));
+		assert_eq!(jar.get("http://localhost:80/").unwrap(), "d=4");
+		assert_eq!(jar.get("http://sub.localhost:80/"), None);
+		assert_eq!(jar.get("http://1.2.3.4:80/").unwrap(), "e=5");
+	}
+
+	#[test]
+	fn cookies_are_replaced_and_expire() {
+		let jar = CookieJar::new();
+		jar.set("http://localhost:9933/", "session=abc");
+		jar.set("http://localhost:9933/", "session=def; Expires=Wed, 21 Oct 2099 07:28:00 GMT");
+		assert_eq!(jar.get("http://localhost:9933/").unwrap(), "session=def");
+		jar.set("http://localhost:9933/", "session=def; Max-Age=0");
+		assert_eq!(jar.get("http://localhost:9933/"), None);
+		jar.set("http://localhost:9933/", "session=ghi; Expires=Wed, 21 Oct 2015 07:28:00 GMT");
+		assert_eq!(jar.get("http://localhost:9933/"), None);
+	}
+}
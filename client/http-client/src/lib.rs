@@ -35,6 +35,7 @@
 //! [`async-std`](https://docs.rs/async-std/), [`smol`](https://docs.rs/smol) and similar.
 
 mod client;
+mod cookies;
 
 /// HTTP transport.
 pub mod transport;
@@ -43,4 +44,7 @@ pub mod transport;
 mod tests;
 
 pub use client::{HttpClient, HttpClientBuilder};
+pub use cookies::CookieJar;
+pub use hyper::header::{HeaderMap, HeaderName, HeaderValue};
+pub use jsonrpsee_core::client::{LoadBalancing, MultiClient, Proxy, ProxyKind};
 pub use jsonrpsee_types as types;
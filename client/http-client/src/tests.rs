@@ -26,7 +26,7 @@
 
 use crate::types::error::{ErrorCode, ErrorObject, ErrorResponse};
 use crate::types::ParamsSer;
-use crate::HttpClientBuilder;
+use crate::{CookieJar, HeaderMap, HeaderValue, HttpClientBuilder, Proxy};
 use jsonrpsee_core::client::{ClientT, IdKind};
 use jsonrpsee_core::rpc_params;
 use jsonrpsee_core::Error;
@@ -164,6 +164,91 @@ async fn run_batch_request_with_response<'a>(
 	client.batch_request(batch).with_default_timeout().await.unwrap()
 }
 
+#[tokio::test]
+async fn custom_headers_are_sent() {
+	let (server_addr, mut headers) =
+		http_server_recording_headers(ok_response("hello".into(), Id::Num(0)), HeaderMap::new())
+			.with_default_timeout()
+			.await
+			.unwrap();
+	let uri = format!("http://{}", server_addr);
+	let mut client_headers = HeaderMap::new();
+	client_headers.insert("authorization", HeaderValue::from_static("Bearer client"));
+	client_headers.insert("x-api-key", HeaderValue::from_static("42"));
+	let client = HttpClientBuilder::default().set_headers(client_headers).build(&uri).unwrap();
+
+	let _: String = client.request("say_hello", None).with_default_timeout().await.unwrap().unwrap();
+	let sent = headers.try_recv().unwrap();
+	assert_eq!(sent["authorization"], "Bearer client");
+	assert_eq!(sent["x-api-key"], "42");
+	assert_eq!(sent["content-type"], "application/json");
+
+	let mut call_headers = HeaderMap::new();
+	call_headers.insert("authorization", HeaderValue::from_static("Bearer call"));
+	call_headers.insert("x-trace-id", HeaderValue::from_static("abc"));
+	// The hardcoded response has the ID of the first call.
+	let res = client.request_with_headers::<String>("say_hello", None, &call_headers).with_default_timeout().await;
+	assert!(matches!(res.unwrap(), Err(Error::InvalidRequestId)));
+	let sent = headers.try_recv().unwrap();
+	assert_eq!(sent.get_all("authorization").iter().collect::<Vec<_>>(), ["Bearer call"]);
+	assert_eq!(sent["x-api-key"], "42");
+	assert_eq!(sent["x-trace-id"], "abc");
+}
+
+#[tokio::test]
+async fn cookies_are_sent_back() {
+	let mut response_headers = HeaderMap::new();
+	response_headers.append("set-cookie", HeaderValue::from_static("session=abc; Path=/; HttpOnly"));
+	response_headers.append("set-cookie", HeaderValue::from_static("theme=dark"));
+	let (server_addr, mut headers) =
+		http_server_recording_headers(ok_response("hello".into(), Id::Num(0)), response_headers)
+			.with_default_timeout()
+			.await
+			.unwrap();
+	let uri = format!("http://{}", server_addr);
+	let jar = CookieJar::new();
+	let client = HttpClientBuilder::default().cookie_jar(jar.clone()).build(&uri).unwrap();
+
+	let _: String = client.request("say_hello", None).with_default_timeout().await.unwrap().unwrap();
+	assert!(headers.try_recv().unwrap().get("cookie").is_none());
+	assert_eq!(jar.get(&uri).unwrap(), "session=abc; theme=dark");
+	// The hardcoded response has the ID of the first call.
+	let _ = client.request::<String>("say_hello", None).with_default_timeout().await.unwrap();
+	assert_eq!(headers.try_recv().unwrap()["cookie"], "session=abc; theme=dark");
+
+	// Without a jar, no cookies are stored.
+	let client = HttpClientBuilder::default().build(&uri).unwrap();
+	for _ in 0..2 {
+		let _ = client.request::<String>("say_hello", None).with_default_timeout().await.unwrap();
+		assert!(headers.try_recv().unwrap().get("cookie").is_none());
+	}
+}
+
+#[tokio::test]
+async fn connects_through_proxies() {
+	let server_addr = http_server_with_hardcoded_response(ok_response("hello".into(), Id::Num(0)))
+		.with_default_timeout()
+		.await
+		.unwrap();
+	let uri = format!("http://{}", server_addr);
+
+	for (proxy_addr, mut targets, scheme) in
+		[(http_connect_proxy().await, "http"), (socks5_proxy().await, "socks5")].map(|((addr, rx), s)| (addr, rx, s))
+	{
+		let proxy = Proxy::new(format!("{}://{}", scheme, proxy_addr)).unwrap();
+		let client = HttpClientBuilder::default().proxy(proxy).build(&uri).unwrap();
+		let response: String = client.request("say_hello", None).with_default_timeout().await.unwrap().unwrap();
+		assert_eq!(response, "hello");
+		assert_eq!(targets.try_recv().unwrap(), server_addr.to_string());
+	}
+
+	let (proxy_addr, mut targets) = http_connect_proxy().await;
+	let proxy = Proxy::new(format!("http://{}", proxy_addr)).unwrap().no_proxy(["127.0.0.1"]);
+	let client = HttpClientBuilder::default().proxy(proxy).build(&uri).unwrap();
+	let _: String = client.request("say_hello", None).with_default_timeout().await.unwrap().unwrap();
+	assert!(targets.try_recv().is_err());
+}
+
 async fn run_request_with_response(response: String) -> Result<String, Error> {
 	let server_addr = http_server_with_hardcoded_response(response).with_default_timeout().await.unwrap();
 	let uri = format!("http://{}", server_addr);
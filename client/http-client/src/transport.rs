@@ -6,11 +6,18 @@
 // that we need to be guaranteed that hyper doesn't re-use an existing connection if we ever reset
 // the JSON-RPC request id to a value that might have already been used.
 
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use crate::cookies::CookieJar;
 use hyper::client::{Client, HttpConnector};
-use hyper::Uri;
-use jsonrpsee_core::client::CertificateStore;
+use hyper::service::Service;
+use hyper::{HeaderMap, Uri};
+use jsonrpsee_core::client::{CertificateStore, Proxy};
 use jsonrpsee_core::error::GenericTransportError;
 use jsonrpsee_core::http_helpers;
+use jsonrpsee_core::TEN_MB_SIZE_BYTES;
 use thiserror::Error;
 
 const CONTENT_TYPE_JSON: &str = "application/json";
@@ -19,9 +26,53 @@ const CONTENT_TYPE_JSON: &str = "application/json";
 enum HyperClient {
 	/// Hyper client with https connector.
 	#[cfg(feature = "tls")]
-	Https(Client<hyper_rustls::HttpsConnector<HttpConnector>>),
+	Https(Client<hyper_rustls::HttpsConnector<Connector>>),
 	/// Hyper client with http connector.
-	Http(Client<HttpConnector>),
+	Http(Client<Connector>),
+}
+
+/// Connector opening the TCP connections directly or through the proxy.
+#[derive(Debug, Clone)]
+struct Connector {
+	http: HttpConnector,
+	proxy: Option<Proxy>,
+}
+
+impl Connector {
+	fn new(proxy: Option<Proxy>) -> Self {
+		let mut http = HttpConnector::new();
+		// The scheme is checked by the transport, and TLS is negotiated by the wrapping connector.
+		http.enforce_http(false);
+		Self { http, proxy }
+	}
+}
+
+impl Service<Uri> for Connector {
+	type Response = tokio::net::TcpStream;
+	type Error = Box<dyn std::error::Error + Send + Sync>;
+	type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+	fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+		self.http.poll_ready(cx).map_err(Into::into)
+	}
+
+	fn call(&mut self, uri: Uri) -> Self::Future {
+		let host = uri.host().unwrap_or_default().to_owned();
+		match self.proxy.clone().filter(|proxy| !proxy.bypasses(&host)) {
+			Some(proxy) => {
+				let port = uri.port_u16().unwrap_or(if uri.scheme_str() == Some("https") { 443 } else { 80 });
+				Box::pin(async move {
+					let stream = proxy.connect(&host, port).await?;
+					stream.set_nodelay(true)?;
+					Ok(stream)
+				})
+			}
+			None => {
+				let connect = self.http.call(uri);
+				Box::pin(async move { connect.await.map_err(Into::into) })
+			}
+		}
+	}
 }
 
 impl HyperClient {
@@ -43,15 +94,43 @@ pub struct HttpTransportClient {
 	client: HyperClient,
 	/// Configurable max request body size
 	max_request_body_size: u32,
+	/// Custom headers to pass with every request.
+	headers: HeaderMap,
+	/// Cookies stored from the responses and sent back with the requests, if enabled.
+	cookies: Option<CookieJar>,
+}
+
+/// Configuration of a [`HttpTransportClient`].
+#[derive(Debug, Clone)]
+pub(crate) struct TransportConfig {
+	/// Max size of a request body in bytes.
+	pub(crate) max_request_body_size: u32,
+	/// Certificates the servers are verified against over HTTPS.
+	pub(crate) certificate_store: CertificateStore,
+	/// Custom headers to pass with every request.
+	pub(crate) headers: HeaderMap,
+	/// Proxy the requests are sent through, if any.
+	pub(crate) proxy: Option<Proxy>,
+	/// Cookies stored from the responses and sent back with the requests, if enabled.
+	pub(crate) cookies: Option<CookieJar>,
+}
+
+impl Default for TransportConfig {
+	fn default() -> Self {
+		Self {
+			max_request_body_size: TEN_MB_SIZE_BYTES,
+			certificate_store: CertificateStore::Native,
+			headers: HeaderMap::new(),
+			proxy: None,
+			cookies: None,
+		}
+	}
 }
 
 impl HttpTransportClient {
 	/// Initializes a new HTTP client.
-	pub(crate) fn new(
-		target: impl AsRef<str>,
-		max_request_body_size: u32,
-		cert_store: CertificateStore,
-	) -> Result<Self, Error> {
+	pub(crate) fn new(target: impl AsRef<str>, config: TransportConfig) -> Result<Self, Error> {
+		let TransportConfig { max_request_body_size, certificate_store, headers, proxy, cookies } = config;
 		let target: Uri = target.as_ref().parse().map_err(|e| Error::Url(format!("Invalid URL: {}", e)))?;
 		if target.port_u16().is_none() {
 			return Err(Error::Url("Port number is missing in the URL".into()));
@@ -59,13 +138,12 @@ impl HttpTransportClient {
 
 		let client = match target.scheme_str() {
 			Some("http") => {
-				let connector = HttpConnector::new();
-				let client = Client::builder().build::<_, hyper::Body>(connector);
+				let client = Client::builder().build::<_, hyper::Body>(Connector::new(proxy));
 				HyperClient::Http(client)
 			}
 			#[cfg(feature = "tls")]
 			Some("https") => {
-				let connector = match cert_store {
+				let connector = match certificate_store {
 					CertificateStore::Native => {
 						hyper_rustls::HttpsConnectorBuilder::new().with_native_roots().https_or_http().enable_http1()
 					}
@@ -74,7 +152,7 @@ impl HttpTransportClient {
 					}
 					_ => return Err(Error::InvalidCertficateStore),
 				};
-				let client = Client::builder().build::<_, hyper::Body>(connector.build());
+				let client = Client::builder().build::<_, hyper::Body>(connector.wrap_connector(Connector::new(proxy)));
 				HyperClient::Https(client)
 			}
 			_ => {
@@ -85,23 +163,43 @@ impl HttpTransportClient {
 				return Err(Error::Url(err.into()));
 			}
 		};
-		Ok(Self { target, client, max_request_body_size })
+		Ok(Self { target, client, max_request_body_size, headers, cookies })
 	}
 
-	async fn inner_send(&self, body: String) -> Result<hyper::Response<hyper::Body>, Error> {
+	async fn inner_send(
+		&self,
+		body: String,
+		headers: Option<&HeaderMap>,
+	) -> Result<hyper::Response<hyper::Body>, Error> {
 		tracing::debug!("send: {}", body);
 
 		if body.len() > self.max_request_body_size as usize {
 			return Err(Error::RequestTooLarge);
 		}
 
-		let req = hyper::Request::post(&self.target)
-			.header(hyper::header::CONTENT_TYPE, hyper::header::HeaderValue::from_static(CONTENT_TYPE_JSON))
-			.header(hyper::header::ACCEPT, hyper::header::HeaderValue::from_static(CONTENT_TYPE_JSON))
-			.body(From::from(body))
-			.expect("URI and request headers are valid; qed");
+		let mut req =
+			hyper::Request::post(&self.target).body(From::from(body)).expect("URI and request headers are valid; qed");
+		let req_headers = req.headers_mut();
+		*req_headers = self.headers.clone();
+		// The headers of the call replace the headers of the client with the same name.
+		if let Some(headers) = headers {
+			for name in headers.keys() {
+				req_headers.remove(name);
+			}
+			for (name, value) in headers {
+				req_headers.append(name, value.clone());
+			}
+		}
+		req_headers.insert(hyper::header::CONTENT_TYPE, hyper::header::HeaderValue::from_static(CONTENT_TYPE_JSON));
+		req_headers.insert(hyper::header::ACCEPT, hyper::header::HeaderValue::from_static(CONTENT_TYPE_JSON));
+		if let Some(cookie) = self.cookies.as_ref().and_then(|cookies| cookies.header(&self.target)) {
+			req_headers.insert(hyper::header::COOKIE, cookie);
+		}
 
 		let response = self.client.request(req).await.map_err(|e| Error::Http(Box::new(e)))?;
+		if let Some(cookies) = &self.cookies {
+			cookies.store_response(&self.target, response.headers());
+		}
 		if response.status().is_success() {
 			Ok(response)
 		} else {
@@ -110,8 +208,8 @@ impl HttpTransportClient {
 	}
 
 	/// Send serialized message and wait until all bytes from the HTTP message body have been read.
-	pub(crate) async fn send_and_read_body(&self, body: String) -> Result<Vec<u8>, Error> {
-		let response = self.inner_send(body).await?;
+	pub(crate) async fn send_and_read_body(&self, body: String, headers: Option<&HeaderMap>) -> Result<Vec<u8>, Error> {
+		let response = self.inner_send(body, headers).await?;
 		let (parts, body) = response.into_parts();
 		let (body, _) = http_helpers::read_body(&parts.headers, body, self.max_request_body_size).await?;
 		Ok(body)
@@ -119,7 +217,7 @@ impl HttpTransportClient {
 
 	/// Send serialized message without reading the HTTP message body.
 	pub(crate) async fn send(&self, body: String) -> Result<(), Error> {
-		let _ = self.inner_send(body).await?;
+		let _ = self.inner_send(body, None).await?;
 		Ok(())
 	}
 }
@@ -170,7 +268,7 @@ where
 
 #[cfg(test)]
 mod tests {
-	use super::{CertificateStore, Error, HttpTransportClient};
+	use super::{CertificateStore, Error, HttpTransportClient, TransportConfig};
 
 	fn assert_target(
 		client: &HttpTransportClient,
@@ -189,36 +287,59 @@ mod tests {
 
 	#[test]
 	fn invalid_http_url_rejected() {
-		let err = HttpTransportClient::new("ws://localhost:9933", 80, CertificateStore::Native).unwrap_err();
+		let err = HttpTransportClient::new(
+			"ws://localhost:9933",
+			TransportConfig { max_request_body_size: 80, ..Default::default() },
+		)
+		.unwrap_err();
 		assert!(matches!(err, Error::Url(_)));
 	}
 
 	#[cfg(feature = "tls")]
 	#[test]
 	fn https_works() {
-		let client = HttpTransportClient::new("https://localhost:9933", 80, CertificateStore::Native).unwrap();
+		let client = HttpTransportClient::new(
+			"https://localhost:9933",
+			TransportConfig { max_request_body_size: 80, ..Default::default() },
+		)
+		.unwrap();
 		assert_target(&client, "localhost", "https", "/", 9933, 80);
 	}
 
 	#[cfg(not(feature = "tls"))]
 	#[test]
 	fn https_fails_without_tls_feature() {
-		let err = HttpTransportClient::new("https://localhost:9933", 80, CertificateStore::Native).unwrap_err();
+		let err = HttpTransportClient::new(
+			"https://localhost:9933",
+			TransportConfig { max_request_body_size: 80, ..Default::default() },
+		)
+		.unwrap_err();
 		assert!(matches!(err, Error::Url(_)));
 	}
 
 	#[test]
 	fn faulty_port() {
-		let err = HttpTransportClient::new("http://localhost:-43", 80, CertificateStore::Native).unwrap_err();
+		let err = HttpTransportClient::new(
+			"http://localhost:-43",
+			TransportConfig { max_request_body_size: 80, ..Default::default() },
+		)
+		.unwrap_err();
 		assert!(matches!(err, Error::Url(_)));
-		let err = HttpTransportClient::new("http://localhost:-99999", 80, CertificateStore::Native).unwrap_err();
+		let err = HttpTransportClient::new(
+			"http://localhost:-99999",
+			TransportConfig { max_request_body_size: 80, ..Default::default() },
+		)
+		.unwrap_err();
 		assert!(matches!(err, Error::Url(_)));
 	}
 
 	#[test]
 	fn url_with_path_works() {
-		let client =
-			HttpTransportClient::new("http://localhost:9944/my-special-path", 1337, CertificateStore::Native).unwrap();
+		let client = HttpTransportClient::new(
+			"http://localhost:9944/my-special-path",
+			TransportConfig { max_request_body_size: 1337, ..Default::default() },
+		)
+		.unwrap();
 		assert_target(&client, "localhost", "http", "/my-special-path", 9944, 1337);
 	}
 
@@ -226,8 +347,11 @@ mod tests {
 	fn url_with_query_works() {
 		let client = HttpTransportClient::new(
 			"http://127.0.0.1:9999/my?name1=value1&name2=value2",
-			u32::MAX,
-			CertificateStore::WebPki,
+			TransportConfig {
+				max_request_body_size: u32::MAX,
+				certificate_store: CertificateStore::WebPki,
+				..Default::default()
+			},
 		)
 		.unwrap();
 		assert_target(&client, "127.0.0.1", "http", "/my?name1=value1&name2=value2", 9999, u32::MAX);
@@ -235,15 +359,26 @@ mod tests {
 
 	#[test]
 	fn url_with_fragment_is_ignored() {
-		let client =
-			HttpTransportClient::new("http://127.0.0.1:9944/my.htm#ignore", 999, CertificateStore::Native).unwrap();
+		let client = HttpTransportClient::new(
+			"http://127.0.0.1:9944/my.htm#ignore",
+			TransportConfig { max_request_body_size: 999, ..Default::default() },
+		)
+		.unwrap();
 		assert_target(&client, "127.0.0.1", "http", "/my.htm", 9944, 999);
 	}
 
 	#[tokio::test]
 	async fn request_limit_works() {
 		let eighty_bytes_limit = 80;
-		let client = HttpTransportClient::new("http://localhost:9933", 80, CertificateStore::WebPki).unwrap();
+		let client = HttpTransportClient::new(
+			"http://localhost:9933",
+			TransportConfig {
+				max_request_body_size: 80,
+				certificate_store: CertificateStore::WebPki,
+				..Default::default()
+			},
+		)
+		.unwrap();
 		assert_eq!(client.max_request_body_size, eighty_bytes_limit);
 
 		let body = "a".repeat(81);
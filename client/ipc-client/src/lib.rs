@@ -0,0 +1,218 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+#![warn(missing_debug_implementations, missing_docs, unreachable_pub)]
+
+//! # jsonrpsee-ipc-client
+//!
+//! `jsonrpsee-ipc-client` is a [JSON RPC](https://www.jsonrpc.org/specification) IPC client library that's is built for `async/await`.
+//!
+//! ## Async runtime support
+//!
+//! This library uses `tokio` as the runtime and does not support other runtimes.
+
+pub use jsonrpsee_core::client::Client as IpcClient;
+pub use jsonrpsee_core::framing::Framing;
+pub use jsonrpsee_types as types;
+
+use std::path::Path;
+use std::time::Duration;
+
+use jsonrpsee_client_transport::ipc::{IpcTransportClientBuilder, Receiver, Sender};
+use jsonrpsee_core::client::{ClientBuilder, IdKind};
+use jsonrpsee_core::{Error, TEN_MB_SIZE_BYTES};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::ToSocketAddrs;
+
+/// Builder for [`IpcClient`].
+///
+/// # Examples
+///
+/// ```no_run
+///
+/// use jsonrpsee_ipc_client::IpcClientBuilder;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     // build client
+///     let client = IpcClientBuilder::default()
+///          .build("/tmp/jsonrpsee.ipc")
+///          .await
+///          .unwrap();
+///
+///     // use client....
+/// }
+///
+/// ```
+#[derive(Clone, Debug)]
+pub struct IpcClientBuilder {
+	max_request_body_size: u32,
+	request_timeout: Duration,
+	connection_timeout: Duration,
+	max_concurrent_requests: usize,
+	max_notifs_per_subscription: usize,
+	id_kind: IdKind,
+	framing: Framing,
+}
+
+impl Default for IpcClientBuilder {
+	fn default() -> Self {
+		Self {
+			max_request_body_size: TEN_MB_SIZE_BYTES,
+			request_timeout: Duration::from_secs(60),
+			connection_timeout: Duration::from_secs(10),
+			max_concurrent_requests: 256,
+			max_notifs_per_subscription: 1024,
+			id_kind: IdKind::Number,
+			framing: Framing::default(),
+		}
+	}
+}
+
+impl IpcClientBuilder {
+	/// See documentation [`IpcTransportClientBuilder::max_request_body_size`] (default is 10 MB).
+	pub fn max_request_body_size(mut self, size: u32) -> Self {
+		self.max_request_body_size = size;
+		self
+	}
+
+	/// See documentation [`ClientBuilder::request_timeout`] (default is 60 seconds).
+	pub fn request_timeout(mut self, timeout: Duration) -> Self {
+		self.request_timeout = timeout;
+		self
+	}
+
+	/// See documentation [`IpcTransportClientBuilder::connection_timeout`] (default is 10 seconds).
+	pub fn connection_timeout(mut self, timeout: Duration) -> Self {
+		self.connection_timeout = timeout;
+		self
+	}
+
+	/// See documentation [`ClientBuilder::max_concurrent_requests`] (default is 256).
+	pub fn max_concurrent_requests(mut self, max: usize) -> Self {
+		self.max_concurrent_requests = max;
+		self
+	}
+
+	/// See documentation [`ClientBuilder::max_notifs_per_subscription`] (default is 1024).
+	pub fn max_notifs_per_subscription(mut self, max: usize) -> Self {
+		self.max_notifs_per_subscription = max;
+		self
+	}
+
+	/// See documentation for [`ClientBuilder::id_format`] (default is Number).
+	pub fn id_format(mut self, kind: IdKind) -> Self {
+		self.id_kind = kind;
+		self
+	}
+
+	/// See documentation for [`IpcTransportClientBuilder::framing`] (default is [`Framing::Lines`]).
+	pub fn framing(mut self, framing: Framing) -> Self {
+		self.framing = framing;
+		self
+	}
+
+	/// Build the client connected to the server listening at `path`, see [`IpcTransportClientBuilder::build`].
+	///
+	/// ## Panics
+	///
+	/// Panics if being called outside of `tokio` runtime context.
+	pub async fn build(self, path: impl AsRef<Path>) -> Result<IpcClient, Error> {
+		let transport_builder = IpcTransportClientBuilder {
+			connection_timeout: self.connection_timeout,
+			max_request_body_size: self.max_request_body_size,
+			framing: self.framing,
+		};
+
+		let (sender, receiver) = transport_builder.build(path).await.map_err(|e| Error::Transport(e.into()))?;
+		Ok(self.client(sender, receiver))
+	}
+
+	/// Build the client connected to the server listening on the TCP socket at `addr`, see
+	/// [`IpcTransportClientBuilder::build_tcp`].
+	///
+	/// ## Panics
+	///
+	/// Panics if being called outside of `tokio` runtime context.
+	pub async fn build_tcp(self, addr: impl ToSocketAddrs) -> Result<IpcClient, Error> {
+		let transport_builder = IpcTransportClientBuilder {
+			connection_timeout: self.connection_timeout,
+			max_request_body_size: self.max_request_body_size,
+			framing: self.framing,
+		};
+
+		let (sender, receiver) = transport_builder.build_tcp(addr).await.map_err(|e| Error::Transport(e.into()))?;
+		Ok(self.client(sender, receiver))
+	}
+
+	/// Build the client exchanging the messages with a server over pipes, such as a child process serving over its
+	/// standard input and output, see [`IpcTransportClientBuilder::build_with_io`].
+	///
+	/// ```no_run
+	/// use std::process::Stdio;
+	/// use jsonrpsee_ipc_client::IpcClientBuilder;
+	///
+	/// #[tokio::main]
+	/// async fn main() {
+	///     let mut child = tokio::process::Command::new("./server")
+	///         .stdin(Stdio::piped())
+	///         .stdout(Stdio::piped())
+	///         .spawn()
+	///         .unwrap();
+	///     let (stdin, stdout) = (child.stdin.take().unwrap(), child.stdout.take().unwrap());
+	///     let client = IpcClientBuilder::default().build_with_io(stdout, stdin);
+	///
+	///     // use client....
+	/// }
+	/// ```
+	///
+	/// ## Panics
+	///
+	/// Panics if being called outside of `tokio` runtime context.
+	pub fn build_with_io<R, W>(self, reader: R, writer: W) -> IpcClient
+	where
+		R: AsyncRead + Send + Unpin + 'static,
+		W: AsyncWrite + Send + Unpin + 'static,
+	{
+		let transport_builder = IpcTransportClientBuilder {
+			connection_timeout: self.connection_timeout,
+			max_request_body_size: self.max_request_body_size,
+			framing: self.framing,
+		};
+
+		let (sender, receiver) = transport_builder.build_with_io(reader, writer);
+		self.client(sender, receiver)
+	}
+
+	fn client(self, sender: Sender, receiver: Receiver) -> IpcClient {
+		ClientBuilder::default()
+			.max_notifs_per_subscription(self.max_notifs_per_subscription)
+			.request_timeout(self.request_timeout)
+			.max_concurrent_requests(self.max_concurrent_requests)
+			.id_format(self.id_kind)
+			.build(sender, receiver)
+	}
+}
@@ -0,0 +1,362 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use jsonrpsee_core::async_trait;
+use jsonrpsee_core::client::{TransportReceiverT, TransportSenderT};
+pub use jsonrpsee_core::framing::Framing;
+use jsonrpsee_core::TEN_MB_SIZE_BYTES;
+use thiserror::Error;
+use tokio::io::{
+	AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader, BufWriter, ReadBuf, ReadHalf,
+	WriteHalf,
+};
+#[cfg(windows)]
+use tokio::net::windows::named_pipe::{ClientOptions, NamedPipeClient as LocalStream};
+#[cfg(unix)]
+use tokio::net::UnixStream as LocalStream;
+use tokio::net::{TcpStream, ToSocketAddrs};
+
+/// Stream of the connection to the server.
+enum Stream {
+	/// IPC connection.
+	Local(LocalStream),
+	/// TCP connection.
+	Tcp(TcpStream),
+	/// Pipes, such as the standard input and output of a child process.
+	Stdio(Box<dyn AsyncRead + Send + Unpin>, Box<dyn AsyncWrite + Send + Unpin>),
+}
+
+impl fmt::Debug for Stream {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Local(stream) => f.debug_tuple("Local").field(stream).finish(),
+			Self::Tcp(stream) => f.debug_tuple("Tcp").field(stream).finish(),
+			Self::Stdio(..) => f.write_str("Stdio"),
+		}
+	}
+}
+
+/// Sending end of IPC transport.
+#[derive(Debug)]
+pub struct Sender {
+	inner: BufWriter<WriteHalf<Stream>>,
+	framing: Framing,
+}
+
+/// Receiving end of IPC transport.
+#[derive(Debug)]
+pub struct Receiver {
+	inner: BufReader<ReadHalf<Stream>>,
+	max_response_size: usize,
+	framing: Framing,
+}
+
+/// Builder for an IPC transport [`Sender`] and [`Receiver`] pair.
+///
+/// The messages are exchanged with the server as lines of JSON, over a Unix domain socket or a named pipe on Windows,
+/// or over a TCP socket.
+#[derive(Debug)]
+pub struct IpcTransportClientBuilder {
+	/// Timeout for the connection.
+	pub connection_timeout: Duration,
+	/// Max payload size
+	pub max_request_body_size: u32,
+	/// How the messages are delimited.
+	pub framing: Framing,
+}
+
+impl Default for IpcTransportClientBuilder {
+	fn default() -> Self {
+		Self {
+			max_request_body_size: TEN_MB_SIZE_BYTES,
+			connection_timeout: Duration::from_secs(10),
+			framing: Framing::default(),
+		}
+	}
+}
+
+impl IpcTransportClientBuilder {
+	/// Set max request body size (default is 10 MB).
+	pub fn max_request_body_size(mut self, size: u32) -> Self {
+		self.max_request_body_size = size;
+		self
+	}
+
+	/// Set connection timeout (default is 10 seconds).
+	pub fn connection_timeout(mut self, timeout: Duration) -> Self {
+		self.connection_timeout = timeout;
+		self
+	}
+
+	/// Set how the messages are delimited, as configured on the server (default is [`Framing::Lines`]).
+	pub fn framing(mut self, framing: Framing) -> Self {
+		self.framing = framing;
+		self
+	}
+
+	/// Try to connect to the server listening at `path`: the path of a Unix domain socket, or the name of a pipe
+	/// on Windows such as `\\.\pipe\jsonrpsee`.
+	pub async fn build(self, path: impl AsRef<Path>) -> Result<(Sender, Receiver), IpcError> {
+		let connect = connect(path.as_ref());
+		let stream = match tokio::time::timeout(self.connection_timeout, connect).await {
+			Ok(stream) => stream.map_err(IpcError::Connect)?,
+			Err(_) => return Err(IpcError::Timeout),
+		};
+		Ok(self.split(Stream::Local(stream)))
+	}
+
+	/// Try to connect to the server listening on the TCP socket at `addr`, with the same framing as IPC endpoints.
+	pub async fn build_tcp(self, addr: impl ToSocketAddrs) -> Result<(Sender, Receiver), IpcError> {
+		let stream = match tokio::time::timeout(self.connection_timeout, TcpStream::connect(addr)).await {
+			Ok(stream) => stream.map_err(IpcError::Connect)?,
+			Err(_) => return Err(IpcError::Timeout),
+		};
+		// Messages are small and written at once.
+		stream.set_nodelay(true).map_err(IpcError::Connect)?;
+		Ok(self.split(Stream::Tcp(stream)))
+	}
+
+	/// Exchange the messages with a server over pipes, such as a child process serving over its standard input and
+	/// output: `writer` is its standard input and `reader` its standard output.
+	pub fn build_with_io<R, W>(self, reader: R, writer: W) -> (Sender, Receiver)
+	where
+		R: AsyncRead + Send + Unpin + 'static,
+		W: AsyncWrite + Send + Unpin + 'static,
+	{
+		self.split(Stream::Stdio(Box::new(reader), Box::new(writer)))
+	}
+
+	fn split(&self, stream: Stream) -> (Sender, Receiver) {
+		let (reader, writer) = tokio::io::split(stream);
+
+		(
+			Sender { inner: BufWriter::new(writer), framing: self.framing },
+			Receiver {
+				inner: BufReader::new(reader),
+				max_response_size: self.max_request_body_size as usize,
+				framing: self.framing,
+			},
+		)
+	}
+}
+
+impl AsyncRead for Stream {
+	fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+		match self.get_mut() {
+			Self::Local(stream) => Pin::new(stream).poll_read(cx, buf),
+			Self::Tcp(stream) => Pin::new(stream).poll_read(cx, buf),
+			Self::Stdio(reader, _) => Pin::new(reader).poll_read(cx, buf),
+		}
+	}
+}
+
+impl AsyncWrite for Stream {
+	fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+		match self.get_mut() {
+			Self::Local(stream) => Pin::new(stream).poll_write(cx, buf),
+			Self::Tcp(stream) => Pin::new(stream).poll_write(cx, buf),
+			Self::Stdio(_, writer) => Pin::new(writer).poll_write(cx, buf),
+		}
+	}
+
+	fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+		match self.get_mut() {
+			Self::Local(stream) => Pin::new(stream).poll_flush(cx),
+			Self::Tcp(stream) => Pin::new(stream).poll_flush(cx),
+			Self::Stdio(_, writer) => Pin::new(writer).poll_flush(cx),
+		}
+	}
+
+	fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+		match self.get_mut() {
+			Self::Local(stream) => Pin::new(stream).poll_shutdown(cx),
+			Self::Tcp(stream) => Pin::new(stream).poll_shutdown(cx),
+			Self::Stdio(_, writer) => Pin::new(writer).poll_shutdown(cx),
+		}
+	}
+}
+
+#[cfg(unix)]
+async fn connect(path: &Path) -> io::Result<LocalStream> {
+	LocalStream::connect(path).await
+}
+
+#[cfg(windows)]
+async fn connect(path: &Path) -> io::Result<LocalStream> {
+	/// All the instances of the pipe are serving other clients, the server creates a new one shortly.
+	const ERROR_PIPE_BUSY: i32 = 231;
+
+	loop {
+		match ClientOptions::new().open(path) {
+			Err(err) if err.raw_os_error() == Some(ERROR_PIPE_BUSY) => {}
+			result => return result,
+		}
+		tokio::time::sleep(Duration::from_millis(50)).await;
+	}
+}
+
+/// Error that can occur when connecting to the server or exchanging messages with it.
+#[derive(Debug, Error)]
+pub enum IpcError {
+	/// Error when connecting to the server.
+	#[error("Error when connecting to the server: {0}")]
+	Connect(#[source] io::Error),
+
+	/// Timeout while trying to connect.
+	#[error("Connection timeout exceeded")]
+	Timeout,
+
+	/// Error on the established connection.
+	#[error("IPC connection error: {0}")]
+	Io(#[from] io::Error),
+
+	/// The server closed the connection.
+	#[error("IPC connection closed")]
+	Closed,
+
+	/// The server sent a message larger than the max size.
+	#[error("Message too large, max is {0} bytes")]
+	MessageTooLarge(usize),
+
+	/// The server sent a message which is not valid UTF-8.
+	#[error("Message is not valid UTF-8")]
+	InvalidUtf8,
+}
+
+#[async_trait]
+impl TransportSenderT for Sender {
+	type Error = IpcError;
+
+	/// Sends out a request. Returns a `Future` that finishes when the request has been
+	/// successfully sent.
+	async fn send(&mut self, body: String) -> Result<(), IpcError> {
+		tracing::debug!("send: {}", body);
+		match self.framing {
+			Framing::Lines => {
+				self.inner.write_all(body.as_bytes()).await?;
+				self.inner.write_all(b"\n").await?;
+			}
+			Framing::ContentLength => {
+				self.inner.write_all(format!("Content-Length: {}\r\n\r\n", body.len()).as_bytes()).await?;
+				self.inner.write_all(body.as_bytes()).await?;
+			}
+		}
+		self.inner.flush().await?;
+		Ok(())
+	}
+
+	/// Close the connection.
+	async fn close(&mut self) -> Result<(), IpcError> {
+		self.inner.shutdown().await.map_err(Into::into)
+	}
+}
+
+#[async_trait]
+impl TransportReceiverT for Receiver {
+	type Error = IpcError;
+
+	/// Returns a `Future` resolving when the server sent us something back.
+	async fn receive(&mut self) -> Result<String, IpcError> {
+		match self.framing {
+			Framing::Lines => self.receive_line().await,
+			Framing::ContentLength => self.receive_content().await,
+		}
+	}
+}
+
+impl Receiver {
+	async fn receive_line(&mut self) -> Result<String, IpcError> {
+		let mut message = Vec::new();
+		loop {
+			let available = self.inner.fill_buf().await?;
+			if available.is_empty() {
+				return Err(IpcError::Closed);
+			}
+			let (line, used, complete) = match available.iter().position(|b| *b == b'\n') {
+				Some(end) => (&available[..end], end + 1, true),
+				None => (available, available.len(), false),
+			};
+			if message.len() + line.len() > self.max_response_size {
+				return Err(IpcError::MessageTooLarge(self.max_response_size));
+			}
+			message.extend_from_slice(line);
+			self.inner.consume(used);
+
+			if complete && !message.iter().all(u8::is_ascii_whitespace) {
+				return String::from_utf8(message).map_err(|_| IpcError::InvalidUtf8);
+			}
+			if complete {
+				message.clear();
+			}
+		}
+	}
+
+	async fn receive_content(&mut self) -> Result<String, IpcError> {
+		let malformed = || IpcError::Io(io::Error::new(io::ErrorKind::InvalidData, "malformed message header"));
+		let mut content_length = None;
+		loop {
+			let mut line = String::new();
+			if (&mut self.inner).take(MAX_HEADER_SIZE).read_line(&mut line).await? == 0 {
+				return Err(IpcError::Closed);
+			}
+			if !line.ends_with('\n') {
+				return Err(malformed());
+			}
+			let line = line.trim_end();
+			if line.is_empty() {
+				match content_length {
+					Some(_) => break,
+					None => continue,
+				}
+			}
+			let (name, value) = line.split_once(':').ok_or_else(malformed)?;
+			if name.trim().eq_ignore_ascii_case("Content-Length") {
+				content_length = Some(value.trim().parse::<usize>().map_err(|_| malformed())?);
+			}
+		}
+
+		let content_length = content_length.unwrap_or_default();
+		if content_length > self.max_response_size {
+			return Err(IpcError::MessageTooLarge(self.max_response_size));
+		}
+		let mut message = vec![0; content_length];
+		match self.inner.read_exact(&mut message).await {
+			Ok(_) => String::from_utf8(message).map_err(|_| IpcError::InvalidUtf8),
+			Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => Err(IpcError::Closed),
+			Err(err) => Err(err.into()),
+		}
+	}
+}
+
+/// Max size of a header line of a message framed by [`Framing::ContentLength`].
+const MAX_HEADER_SIZE: u64 = 1024;
@@ -32,3 +32,11 @@
 /// Websocket transport
 #[cfg(feature = "ws")]
 pub mod ws;
+
+/// IPC transport, over Unix domain sockets or named pipes on Windows.
+#[cfg(feature = "ipc")]
+pub mod ipc;
+
+/// WebSocket transport backed by the browser API, for `wasm32-unknown-unknown`.
+#[cfg(feature = "web")]
+pub mod web;
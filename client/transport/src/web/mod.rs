@@ -0,0 +1,202 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use futures::channel::{mpsc, oneshot};
+use futures::StreamExt;
+use jsonrpsee_core::async_trait;
+use jsonrpsee_core::client::{TransportReceiverT, TransportSenderT};
+use thiserror::Error;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{BinaryType, CloseEvent, Event, MessageEvent, WebSocket};
+
+/// Sending end of the browser WebSocket transport.
+#[derive(Debug)]
+pub struct Sender {
+	to_socket: mpsc::UnboundedSender<String>,
+}
+
+/// Receiving end of the browser WebSocket transport.
+#[derive(Debug)]
+pub struct Receiver {
+	from_socket: mpsc::UnboundedReceiver<Result<String, WebError>>,
+}
+
+/// Error that can occur when connecting to the server or exchanging messages with it.
+#[derive(Debug, Error)]
+pub enum WebError {
+	/// Error when connecting to the server.
+	#[error("Error when connecting to the server: {0}")]
+	Connect(String),
+
+	/// The connection was closed.
+	#[error("WebSocket connection closed, code {code}: {reason}")]
+	Closed {
+		/// Close code sent by the server, or set by the browser.
+		code: u16,
+		/// Reason of the closure, may be empty.
+		reason: String,
+	},
+
+	/// The server sent a binary message which is not valid UTF-8.
+	#[error("Message is not valid UTF-8")]
+	InvalidUtf8,
+}
+
+/// Connect to the server at `url`, such as `wss://localhost:443`, with the WebSocket API of the browser.
+///
+/// The browser owns the connection: the headers, TLS certificates and redirections can't be configured, and the
+/// socket is driven by a task on the event loop of the page or worker.
+pub async fn connect(url: impl AsRef<str>) -> Result<(Sender, Receiver), WebError> {
+	let (opened_tx, opened_rx) = oneshot::channel();
+	let (to_socket, from_front) = mpsc::unbounded();
+	let (to_front, from_socket) = mpsc::unbounded();
+
+	wasm_bindgen_futures::spawn_local(run(url.as_ref().to_owned(), opened_tx, from_front, to_front));
+
+	match opened_rx.await {
+		Ok(Ok(())) => Ok((Sender { to_socket }, Receiver { from_socket })),
+		Ok(Err(err)) => Err(err),
+		Err(_) => Err(WebError::Connect("The connection task was dropped".into())),
+	}
+}
+
+/// Own the socket: report its opening through `opened`, forward the messages it receives to `to_front` and send the
+/// ones from `from_front`, until the sending end is closed or dropped.
+async fn run(
+	url: String,
+	opened: oneshot::Sender<Result<(), WebError>>,
+	mut from_front: mpsc::UnboundedReceiver<String>,
+	to_front: mpsc::UnboundedSender<Result<String, WebError>>,
+) {
+	let socket = match WebSocket::new(&url) {
+		Ok(socket) => socket,
+		Err(err) => {
+			let _ = opened.send(Err(WebError::Connect(describe(&err))));
+			return;
+		}
+	};
+	socket.set_binary_type(BinaryType::Arraybuffer);
+
+	let opened = Rc::new(RefCell::new(Some(opened)));
+	let on_open = {
+		let opened = opened.clone();
+		Closure::<dyn FnMut(Event)>::new(move |_| {
+			if let Some(opened) = opened.borrow_mut().take() {
+				let _ = opened.send(Ok(()));
+			}
+		})
+	};
+	let on_message = {
+		let to_front = to_front.clone();
+		Closure::<dyn FnMut(MessageEvent)>::new(move |event: MessageEvent| {
+			let data = event.data();
+			let message = match data.as_string() {
+				Some(text) => Ok(text),
+				None => match data.dyn_into::<js_sys::ArrayBuffer>() {
+					Ok(buffer) => {
+						String::from_utf8(js_sys::Uint8Array::new(&buffer).to_vec()).map_err(|_| WebError::InvalidUtf8)
+					}
+					Err(_) => return,
+				},
+			};
+			let _ = to_front.unbounded_send(message);
+		})
+	};
+	// The browser doesn't tell why a connection failed: a close event always follows, with the details it has.
+	let on_close = Closure::<dyn FnMut(CloseEvent)>::new(move |event: CloseEvent| {
+		let (code, reason) = (event.code(), event.reason());
+		match opened.borrow_mut().take() {
+			Some(opened) => {
+				let _ = opened.send(Err(WebError::Connect(format!("closed with code {}: {}", code, reason))));
+			}
+			None => {
+				let _ = to_front.unbounded_send(Err(WebError::Closed { code, reason }));
+			}
+		}
+	});
+	socket.set_onopen(Some(on_open.as_ref().unchecked_ref()));
+	socket.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+	socket.set_onclose(Some(on_close.as_ref().unchecked_ref()));
+
+	while let Some(message) = from_front.next().await {
+		if let Err(err) = socket.send_with_str(&message) {
+			tracing::warn!("Failed to send a message: {}", describe(&err));
+		}
+	}
+
+	// The closures are dropped with this task, so the socket must not call them anymore.
+	socket.set_onopen(None);
+	socket.set_onmessage(None);
+	socket.set_onclose(None);
+	let _ = socket.close();
+}
+
+/// Describe an exception thrown by the WebSocket API.
+fn describe(err: &JsValue) -> String {
+	match err.dyn_ref::<js_sys::Error>() {
+		Some(err) => err.message().into(),
+		None => format!("{:?}", err),
+	}
+}
+
+#[async_trait]
+impl TransportSenderT for Sender {
+	type Error = WebError;
+
+	/// Sends out a request, once the socket is open. The messages are sent in order.
+	async fn send(&mut self, body: String) -> Result<(), WebError> {
+		tracing::debug!("send: {}", body);
+		self.to_socket
+			.unbounded_send(body)
+			.map_err(|_| WebError::Closed { code: CLOSED_ABNORMALLY, reason: String::new() })
+	}
+
+	/// Close the connection.
+	async fn close(&mut self) -> Result<(), WebError> {
+		self.to_socket.close_channel();
+		Ok(())
+	}
+}
+
+#[async_trait]
+impl TransportReceiverT for Receiver {
+	type Error = WebError;
+
+	/// Returns a `Future` resolving when the server sent us something back.
+	async fn receive(&mut self) -> Result<String, WebError> {
+		match self.from_socket.next().await {
+			Some(message) => message,
+			None => Err(WebError::Closed { code: CLOSED_ABNORMALLY, reason: String::new() }),
+		}
+	}
+}
+
+/// Close code of a connection which was lost without a close frame.
+const CLOSED_ABNORMALLY: u16 = 1006;
@@ -26,12 +26,15 @@
 
 mod stream;
 
+use std::future::Future;
 use std::io;
 use std::net::{SocketAddr, ToSocketAddrs};
 use std::time::Duration;
 
 use futures::io::{BufReader, BufWriter};
-use jsonrpsee_core::client::{CertificateStore, TransportReceiverT, TransportSenderT};
+use jsonrpsee_core::client::{CertificateStore, Proxy, TransportReceiverT, TransportSenderT};
+#[cfg(feature = "deflate")]
+use jsonrpsee_core::deflate::DeflateConfig;
 use jsonrpsee_core::TEN_MB_SIZE_BYTES;
 use jsonrpsee_core::{async_trait, Cow};
 use soketto::connection;
@@ -69,6 +72,11 @@ pub struct WsTransportClientBuilder<'a> {
 	pub max_request_body_size: u32,
 	/// Max number of redirections.
 	pub max_redirections: usize,
+	/// Configuration of the `permessage-deflate` extension, if compression is offered to the server.
+	#[cfg(feature = "deflate")]
+	pub deflate: Option<DeflateConfig>,
+	/// Proxy to connect through, if any.
+	pub proxy: Option<Proxy>,
 }
 
 impl<'a> Default for WsTransportClientBuilder<'a> {
@@ -79,6 +87,9 @@ impl<'a> Default for WsTransportClientBuilder<'a> {
 			connection_timeout: Duration::from_secs(10),
 			headers: Vec::new(),
 			max_redirections: 5,
+			#[cfg(feature = "deflate")]
+			deflate: None,
+			proxy: None,
 		}
 	}
 }
@@ -116,6 +127,40 @@ impl<'a> WsTransportClientBuilder<'a> {
 		self.max_redirections = redirect;
 		self
 	}
+
+	/// Offer the `permessage-deflate` extension to the server, compressing the messages if it accepts it
+	/// (default is no compression).
+	#[cfg(feature = "deflate")]
+	pub fn deflate(mut self, config: DeflateConfig) -> Self {
+		self.deflate = Some(config);
+		self
+	}
+
+	/// Connect through a proxy, except to the hosts it bypasses (default is none).
+	///
+	/// For `wss` URLs, TLS is negotiated with the server over the tunnel through the proxy.
+	pub fn proxy(mut self, proxy: Proxy) -> Self {
+		self.proxy = Some(proxy);
+		self
+	}
+}
+
+/// How to open the TCP connection to a target.
+#[derive(Debug, Clone, Copy)]
+enum Route<'a> {
+	/// Directly to one of the resolved addresses of the target.
+	Direct(SocketAddr),
+	/// Through the proxy, which resolves the host name of the target itself.
+	Proxy(&'a Proxy),
+}
+
+impl<'a> Route<'a> {
+	async fn open(self, target: &Target) -> io::Result<TcpStream> {
+		match self {
+			Self::Direct(sockaddr) => TcpStream::connect(sockaddr).await,
+			Self::Proxy(proxy) => proxy.connect(&target.host, target.port).await,
+		}
+	}
 }
 
 /// Stream mode, either plain TCP or TLS.
@@ -234,24 +279,32 @@ impl<'a> WsTransportClientBuilder<'a> {
 		for _ in 0..self.max_redirections {
 			tracing::debug!("Connecting to target: {:?}", target);
 
-			// The sockaddrs might get reused if the server replies with a relative URI.
-			let sockaddrs = std::mem::take(&mut target.sockaddrs);
-			for sockaddr in &sockaddrs {
+			let routes: Vec<Route> = match self.proxy.as_ref().filter(|proxy| !proxy.bypasses(&target.host)) {
+				Some(proxy) => vec![Route::Proxy(proxy)],
+				None => target
+					.host_header
+					.to_socket_addrs()
+					.map_err(WsHandshakeError::ResolutionFailed)?
+					.map(Route::Direct)
+					.collect(),
+			};
+			for route in routes {
 				#[cfg(feature = "tls")]
-				let tcp_stream = match connect(*sockaddr, self.connection_timeout, &target.host, connector.as_ref()).await {
+				let tcp_stream = match connect(route.open(&target), self.connection_timeout, &target.host, connector.as_ref()).await
+				{
 					Ok(stream) => stream,
 					Err(e) => {
-						tracing::debug!("Failed to connect to sockaddr: {:?}", sockaddr);
+						tracing::debug!("Failed to connect to {:?}", route);
 						err = Some(Err(e));
 						continue;
 					}
 				};
 
 				#[cfg(not(feature = "tls"))]
-				let tcp_stream = match connect(*sockaddr, self.connection_timeout).await {
+				let tcp_stream = match connect(route.open(&target), self.connection_timeout).await {
 					Ok(stream) => stream,
 					Err(e) => {
-						tracing::debug!("Failed to connect to sockaddr: {:?}", sockaddr);
+						tracing::debug!("Failed to connect to {:?}", route);
 						err = Some(Err(e));
 						continue;
 					}
@@ -264,6 +317,10 @@ impl<'a> WsTransportClientBuilder<'a> {
 				);
 
 				client.set_headers(&self.headers);
+				#[cfg(feature = "deflate")]
+				if let Some(deflate) = &self.deflate {
+					client.add_extension(deflate.extension(soketto::Mode::Client));
+				}
 
 				// Perform the initial handshake.
 				match client.handshake().await {
@@ -326,7 +383,6 @@ impl<'a> WsTransportClientBuilder<'a> {
 											}
 										};
 									}
-									target.sockaddrs = sockaddrs;
 								}
 								break;
 							}
@@ -347,12 +403,11 @@ impl<'a> WsTransportClientBuilder<'a> {
 
 #[cfg(feature = "tls")]
 async fn connect(
-	sockaddr: SocketAddr,
+	socket: impl Future<Output = io::Result<TcpStream>>,
 	timeout_dur: Duration,
 	host: &str,
 	tls_connector: Option<&tokio_rustls::TlsConnector>,
 ) -> Result<EitherStream, WsHandshakeError> {
-	let socket = TcpStream::connect(sockaddr);
 	let timeout = tokio::time::sleep(timeout_dur);
 	tokio::select! {
 		socket = socket => {
@@ -374,8 +429,10 @@ async fn connect(
 }
 
 #[cfg(not(feature = "tls"))]
-async fn connect(sockaddr: SocketAddr, timeout_dur: Duration) -> Result<EitherStream, WsHandshakeError> {
-	let socket = TcpStream::connect(sockaddr);
+async fn connect(
+	socket: impl Future<Output = io::Result<TcpStream>>,
+	timeout_dur: Duration,
+) -> Result<EitherStream, WsHandshakeError> {
 	let timeout = tokio::time::sleep(timeout_dur);
 	tokio::select! {
 		socket = socket => {
@@ -417,10 +474,10 @@ impl From<soketto::connection::Error> for WsError {
 /// Represents a verified remote WebSocket address.
 #[derive(Debug, Clone)]
 pub struct Target {
-	/// Socket addresses resolved the host name.
-	sockaddrs: Vec<SocketAddr>,
 	/// The host name (domain or IP address).
 	host: String,
+	/// The port number.
+	port: u16,
 	/// The Host request header specifies the host and port number of the server to which the request is being sent.
 	host_header: String,
 	/// WebSocket stream mode, see [`Mode`] for further documentation.
@@ -453,14 +510,7 @@ impl TryFrom<Uri> for Target {
 		let host_header = format!("{}:{}", host, port);
 		let parts = uri.into_parts();
 		let path_and_query = parts.path_and_query.ok_or_else(|| WsHandshakeError::Url("No path in URL".into()))?;
-		let sockaddrs = host_header.to_socket_addrs().map_err(WsHandshakeError::ResolutionFailed)?;
-		Ok(Self {
-			sockaddrs: sockaddrs.collect(),
-			host,
-			host_header,
-			_mode,
-			path_and_query: path_and_query.to_string(),
-		})
+		Ok(Self { host, port, host_header, _mode, path_and_query: path_and_query.to_string() })
 	}
 }
 
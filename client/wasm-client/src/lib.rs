@@ -0,0 +1,123 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+#![warn(missing_debug_implementations, missing_docs, unreachable_pub)]
+
+//! # jsonrpsee-wasm-client
+//!
+//! `jsonrpsee-wasm-client` is a [JSON RPC](https://www.jsonrpc.org/specification) WebSocket client library for
+//! `wasm32-unknown-unknown`, backed by the WebSocket API of the browser. It implements the same
+//! [`ClientT`](jsonrpsee_core::client::ClientT) and [`SubscriptionClientT`](jsonrpsee_core::client::SubscriptionClientT)
+//! traits as the native clients.
+//!
+//! ## Async runtime support
+//!
+//! The client runs its tasks on the event loop of the page or worker, with `wasm-bindgen-futures`.
+
+pub use jsonrpsee_core::client::Client as WasmClient;
+pub use jsonrpsee_types as types;
+
+use std::time::Duration;
+
+use jsonrpsee_client_transport::web;
+use jsonrpsee_core::client::{ClientBuilder, IdKind};
+use jsonrpsee_core::Error;
+
+/// Builder for [`WasmClient`].
+///
+/// # Examples
+///
+/// ```no_run
+///
+/// use jsonrpsee_wasm_client::WasmClientBuilder;
+///
+/// async fn run() {
+///     // build client
+///     let client = WasmClientBuilder::default()
+///          .build("wss://localhost:443")
+///          .await
+///          .unwrap();
+///
+///     // use client....
+/// }
+///
+/// ```
+#[derive(Clone, Debug)]
+pub struct WasmClientBuilder {
+	request_timeout: Duration,
+	max_concurrent_requests: usize,
+	max_notifs_per_subscription: usize,
+	id_kind: IdKind,
+}
+
+impl Default for WasmClientBuilder {
+	fn default() -> Self {
+		Self {
+			request_timeout: Duration::from_secs(60),
+			max_concurrent_requests: 256,
+			max_notifs_per_subscription: 1024,
+			id_kind: IdKind::Number,
+		}
+	}
+}
+
+impl WasmClientBuilder {
+	/// See documentation [`ClientBuilder::request_timeout`] (default is 60 seconds).
+	pub fn request_timeout(mut self, timeout: Duration) -> Self {
+		self.request_timeout = timeout;
+		self
+	}
+
+	/// See documentation [`ClientBuilder::max_concurrent_requests`] (default is 256).
+	pub fn max_concurrent_requests(mut self, max: usize) -> Self {
+		self.max_concurrent_requests = max;
+		self
+	}
+
+	/// See documentation [`ClientBuilder::max_notifs_per_subscription`] (default is 1024).
+	pub fn max_notifs_per_subscription(mut self, max: usize) -> Self {
+		self.max_notifs_per_subscription = max;
+		self
+	}
+
+	/// See documentation for [`ClientBuilder::id_format`] (default is Number).
+	pub fn id_format(mut self, kind: IdKind) -> Self {
+		self.id_kind = kind;
+		self
+	}
+
+	/// Build the client with specified URL to connect to, such as `wss://localhost:443`.
+	pub async fn build(self, url: impl AsRef<str>) -> Result<WasmClient, Error> {
+		let (sender, receiver) = web::connect(url).await.map_err(|e| Error::Transport(e.into()))?;
+
+		Ok(ClientBuilder::default()
+			.max_notifs_per_subscription(self.max_notifs_per_subscription)
+			.request_timeout(self.request_timeout)
+			.max_concurrent_requests(self.max_concurrent_requests)
+			.id_format(self.id_kind)
+			.build(sender, receiver))
+	}
+}
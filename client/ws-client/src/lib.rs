@@ -38,8 +38,14 @@
 mod tests;
 
 pub use jsonrpsee_core::client::Client as WsClient;
+pub use jsonrpsee_core::client::{
+	Backoff, Proxy, ProxyKind, ReconnectingClient, ReconnectingSubscription, SubscriptionEvent,
+};
 pub use jsonrpsee_types as types;
 
+#[cfg(feature = "deflate")]
+pub use jsonrpsee_core::deflate::DeflateConfig;
+
 use std::time::Duration;
 
 use jsonrpsee_client_transport::ws::{Header, InvalidUri, Uri, WsTransportClientBuilder};
@@ -78,6 +84,11 @@ pub struct WsClientBuilder<'a> {
 	max_notifs_per_subscription: usize,
 	max_redirections: usize,
 	id_kind: IdKind,
+	#[cfg(feature = "deflate")]
+	deflate: Option<DeflateConfig>,
+	reconnect_backoff: Backoff,
+	max_reconnect_attempts: Option<u32>,
+	proxy: Option<Proxy>,
 }
 
 impl<'a> Default for WsClientBuilder<'a> {
@@ -92,6 +103,11 @@ impl<'a> Default for WsClientBuilder<'a> {
 			max_notifs_per_subscription: 1024,
 			max_redirections: 5,
 			id_kind: IdKind::Number,
+			#[cfg(feature = "deflate")]
+			deflate: None,
+			reconnect_backoff: Backoff::default(),
+			max_reconnect_attempts: None,
+			proxy: None,
 		}
 	}
 }
@@ -151,6 +167,35 @@ impl<'a> WsClientBuilder<'a> {
 		self
 	}
 
+	/// See documentation [`WsTransportClientBuilder::deflate`] (default is no compression).
+	#[cfg(feature = "deflate")]
+	pub fn deflate(mut self, config: DeflateConfig) -> Self {
+		self.deflate = Some(config);
+		self
+	}
+
+	/// See documentation [`WsTransportClientBuilder::proxy`] (default is none).
+	pub fn proxy(mut self, proxy: Proxy) -> Self {
+		self.proxy = Some(proxy);
+		self
+	}
+
+	/// Set the delays between the attempts to reconnect of a client built with
+	/// [`WsClientBuilder::build_reconnecting`] (default is exponential, from 100 milliseconds up to 10 seconds).
+	pub fn reconnect_backoff(mut self, backoff: Backoff) -> Self {
+		self.reconnect_backoff = backoff;
+		self
+	}
+
+	/// Set the number of failed attempts in a row after which a client built with
+	/// [`WsClientBuilder::build_reconnecting`] gives up reconnecting, until its next call (default is no limit).
+	///
+	/// Regardless of the limit, each call waits for the connection for at most the request timeout.
+	pub fn max_reconnect_attempts(mut self, max: u32) -> Self {
+		self.max_reconnect_attempts = Some(max);
+		self
+	}
+
 	/// Build the client with specified URL to connect to.
 	/// You must provide the port number in the URL.
 	///
@@ -164,6 +209,9 @@ impl<'a> WsClientBuilder<'a> {
 			headers: self.headers,
 			max_request_body_size: self.max_request_body_size,
 			max_redirections: self.max_redirections,
+			#[cfg(feature = "deflate")]
+			deflate: self.deflate,
+			proxy: self.proxy,
 		};
 
 		let uri: Uri = url.as_ref().parse().map_err(|e: InvalidUri| Error::Transport(e.into()))?;
@@ -177,3 +225,22 @@ impl<'a> WsClientBuilder<'a> {
 			.build(sender, receiver))
 	}
 }
+
+impl WsClientBuilder<'static> {
+	/// Build a client connected to the specified URL, which reconnects whenever the connection dropped and re-issues
+	/// its subscriptions, see [`ReconnectingClient`].
+	///
+	/// ## Panics
+	///
+	/// Panics if being called outside of `tokio` runtime context.
+	pub async fn build_reconnecting(self, url: impl Into<String>) -> Result<ReconnectingClient, Error> {
+		let url = url.into();
+		let backoff = self.reconnect_backoff;
+		let max_attempts = self.max_reconnect_attempts;
+		let connect = move || {
+			let (builder, url) = (self.clone(), url.clone());
+			async move { builder.build(url).await }
+		};
+		ReconnectingClient::new(connect, backoff, max_attempts).await
+	}
+}
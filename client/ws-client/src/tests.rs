@@ -27,7 +27,7 @@
 #![cfg(test)]
 use crate::types::error::{ErrorCode, ErrorObject, ErrorResponse};
 use crate::types::ParamsSer;
-use crate::WsClientBuilder;
+use crate::{Proxy, WsClientBuilder};
 use jsonrpsee_core::client::{ClientT, SubscriptionClientT};
 use jsonrpsee_core::client::{IdKind, Subscription};
 use jsonrpsee_core::rpc_params;
@@ -272,6 +272,37 @@ async fn run_batch_request_with_response<'a>(
 	client.batch_request(batch).with_default_timeout().await.unwrap()
 }
 
+#[tokio::test]
+async fn connects_through_proxies() {
+	let server = WebSocketTestServer::with_hardcoded_response(
+		"127.0.0.1:0".parse().unwrap(),
+		ok_response("hello".into(), Id::Num(0)),
+	)
+	.with_default_timeout()
+	.await
+	.unwrap();
+	let uri = format!("ws://{}", server.local_addr());
+	// The connections to the test server are kept open until the end, it spins on closed connections.
+	let mut clients = Vec::new();
+
+	for (proxy_addr, mut targets, scheme) in
+		[(http_connect_proxy().await, "http"), (socks5_proxy().await, "socks5")].map(|((addr, rx), s)| (addr, rx, s))
+	{
+		let proxy = Proxy::new(format!("{}://{}", scheme, proxy_addr)).unwrap();
+		let client = WsClientBuilder::default().proxy(proxy).build(&uri).with_default_timeout().await.unwrap().unwrap();
+		let response: String = client.request("say_hello", None).with_default_timeout().await.unwrap().unwrap();
+		assert_eq!(response, "hello");
+		assert_eq!(targets.try_recv().unwrap(), server.local_addr().to_string());
+		clients.push(client);
+	}
+
+	let (proxy_addr, mut targets) = http_connect_proxy().await;
+	let proxy = Proxy::new(format!("http://{}", proxy_addr)).unwrap().no_proxy(["127.0.0.1"]);
+	let client = WsClientBuilder::default().proxy(proxy).build(&uri).with_default_timeout().await.unwrap().unwrap();
+	let _: String = client.request("say_hello", None).with_default_timeout().await.unwrap().unwrap();
+	assert!(targets.try_recv().is_err());
+}
+
 async fn run_request_with_response(response: String) -> Result<String, Error> {
 	let server = WebSocketTestServer::with_hardcoded_response("127.0.0.1:0".parse().unwrap(), response)
 		.with_default_timeout()
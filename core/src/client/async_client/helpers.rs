@@ -27,27 +27,31 @@
 use std::time::Duration;
 
 use crate::client::async_client::manager::{RequestManager, RequestStatus};
-use crate::client::{RequestMessage, TransportSenderT};
+use crate::client::{RequestMessage, TransportSenderT, SUBSCRIPTION_ERROR};
 use crate::Error;
 
 use futures_channel::{mpsc, oneshot};
 use jsonrpsee_types::{
-	ErrorResponse, Id, Notification, ParamsSer, RequestSer, Response, SubscriptionId, SubscriptionResponse,
+	ErrorObjectOwned, ErrorResponse, Id, Notification, ParamsSer, RequestSer, Response, SubscriptionErrorPayload,
+	SubscriptionId, SubscriptionResponse,
 };
+use serde::Deserialize;
 use serde_json::Value as JsonValue;
 
-/// Attempts to process a batch response.
+/// Attempts to process a batch response, with the result or the error of each call.
 ///
 /// On success the result is sent to the frontend.
-pub(crate) fn process_batch_response(manager: &mut RequestManager, rps: Vec<Response<JsonValue>>) -> Result<(), Error> {
+pub(crate) fn process_batch_response(
+	manager: &mut RequestManager,
+	rps: Vec<(Id<'static>, Result<JsonValue, ErrorObjectOwned>)>,
+) -> Result<(), Error> {
 	let mut digest = Vec::with_capacity(rps.len());
-	let mut ordered_responses = vec![JsonValue::Null; rps.len()];
+	let mut ordered_responses = vec![Ok(JsonValue::Null); rps.len()];
 	let mut rps_unordered: Vec<_> = Vec::with_capacity(rps.len());
 
-	for rp in rps {
-		let id = rp.id.into_owned();
+	for (id, rp) in rps {
 		digest.push(id.clone());
-		rps_unordered.push((id, rp.result));
+		rps_unordered.push((id, rp));
 	}
 
 	digest.sort_unstable();
@@ -105,6 +109,20 @@ pub(crate) fn process_subscription_response(
 /// Returns Ok() if the response was successfully handled
 /// Returns Err() if there was no handler for the method
 pub(crate) fn process_notification(manager: &mut RequestManager, notif: Notification<JsonValue>) -> Result<(), Error> {
+	// Errors reported by the server on a subscription have an `error` member instead of a `result` member.
+	if let Ok(payload) = SubscriptionErrorPayload::<JsonValue>::deserialize(&notif.params) {
+		let sub_id = payload.subscription.into_owned();
+		if let Some(request_id) = manager.get_request_id_by_subscription_id(&sub_id) {
+			if let Some(send_back_sink) = manager.as_subscription_mut(&request_id) {
+				let error = serde_json::json!({ SUBSCRIPTION_ERROR: payload.error });
+				if let Err(err) = send_back_sink.try_send(error) {
+					tracing::error!("Dropping error of subscription {:?}: {:?}", sub_id, err);
+				}
+			}
+			return Ok(());
+		}
+	}
+
 	match manager.as_notification_handler_mut(notif.method.to_string()) {
 		Some(send_back_sink) => match send_back_sink.try_send(notif.params) {
 			Ok(()) => Ok(()),
@@ -228,7 +246,7 @@ pub(crate) async fn call_with_timeout<T>(
 	timeout: Duration,
 	rx: oneshot::Receiver<Result<T, Error>>,
 ) -> Result<Result<T, Error>, oneshot::Canceled> {
-	let timeout = tokio::time::sleep(timeout);
+	let timeout = super::rt::sleep(timeout);
 	tokio::select! {
 		res = rx => res,
 		_ = timeout => Ok(Err(Error::RequestTimeout))
@@ -36,7 +36,7 @@ use std::collections::{hash_map::Entry, HashMap};
 
 use crate::Error;
 use futures_channel::{mpsc, oneshot};
-use jsonrpsee_types::{Id, SubscriptionId};
+use jsonrpsee_types::{ErrorObjectOwned, Id, SubscriptionId};
 use rustc_hash::FxHashMap;
 use serde_json::value::Value as JsonValue;
 
@@ -61,7 +61,7 @@ pub(crate) enum RequestStatus {
 }
 
 type PendingCallOneshot = Option<oneshot::Sender<Result<JsonValue, Error>>>;
-type PendingBatchOneshot = oneshot::Sender<Result<Vec<JsonValue>, Error>>;
+type PendingBatchOneshot = oneshot::Sender<Result<Vec<Result<JsonValue, ErrorObjectOwned>>, Error>>;
 type PendingSubscriptionOneshot = oneshot::Sender<Result<(mpsc::Receiver<JsonValue>, SubscriptionId<'static>), Error>>;
 type SubscriptionSink = mpsc::Sender<JsonValue>;
 type UnsubscribeMethod = String;
@@ -1,11 +1,14 @@
 mod helpers;
 mod manager;
+pub(crate) mod reconnect;
+mod rt;
 
 use std::time::Duration;
 
+use crate::client::batch::{call_error, parse_batch_response, RawCallResult};
 use crate::client::{
-	BatchMessage, ClientT, RegisterNotificationMessage, RequestMessage, Subscription, SubscriptionClientT,
-	SubscriptionKind, SubscriptionMessage, TransportReceiverT, TransportSenderT,
+	BatchMessage, BatchRequestBuilder, BatchResponse, ClientT, RegisterNotificationMessage, RequestMessage,
+	Subscription, SubscriptionClientT, SubscriptionKind, SubscriptionMessage, TransportReceiverT, TransportSenderT,
 };
 use helpers::{
 	build_unsubscribe_message, call_with_timeout, process_batch_response, process_error_response, process_notification,
@@ -114,13 +117,14 @@ impl ClientBuilder {
 	///
 	/// ## Panics
 	///
-	/// Panics if being called outside of `tokio` runtime context.
+	/// Panics if being called outside of `tokio` runtime context, unless the `async-wasm-client` feature is enabled
+	/// on `wasm32`.
 	pub fn build<S: TransportSenderT, R: TransportReceiverT>(self, sender: S, receiver: R) -> Client {
 		let (to_back, from_front) = mpsc::channel(self.max_concurrent_requests);
 		let (err_tx, err_rx) = oneshot::channel();
 		let max_notifs_per_subscription = self.max_notifs_per_subscription;
 
-		tokio::spawn(async move {
+		rt::spawn(async move {
 			background_task(sender, receiver, from_front, err_tx, max_notifs_per_subscription).await;
 		});
 		Client {
@@ -152,6 +156,43 @@ impl Client {
 		!self.to_back.is_closed()
 	}
 
+	/// Send a method call like [`ClientT::request`], which fails with [`Error::RequestTimeout`] if no response was
+	/// received within `timeout` instead of the request timeout of the client.
+	pub async fn request_with_timeout<'a, R>(
+		&self,
+		method: &'a str,
+		params: Option<ParamsSer<'a>>,
+		timeout: Duration,
+	) -> Result<R, Error>
+	where
+		R: DeserializeOwned,
+	{
+		let (send_back_tx, send_back_rx) = oneshot::channel();
+		let guard = self.id_manager.next_request_id()?;
+		let id = guard.inner();
+
+		let raw = serde_json::to_string(&RequestSer::new(&id, method, params)).map_err(Error::ParseError)?;
+		tracing::trace!("[frontend]: send request: {:?}", raw);
+
+		if self
+			.to_back
+			.clone()
+			.send(FrontToBack::Request(RequestMessage { raw, id, send_back: Some(send_back_tx) }))
+			.await
+			.is_err()
+		{
+			return Err(self.read_error_from_backend().await);
+		}
+
+		let res = call_with_timeout(timeout, send_back_rx).await;
+		let json_value = match res {
+			Ok(Ok(v)) => v,
+			Ok(Err(err)) => return Err(err),
+			Err(_) => return Err(self.read_error_from_backend().await),
+		};
+		serde_json::from_value(json_value).map_err(Error::ParseError)
+	}
+
 	// Reads the error message from the backend thread.
 	async fn read_error_from_backend(&self) -> Error {
 		let mut err_lock = self.error.lock().await;
@@ -186,7 +227,7 @@ impl ClientT for Client {
 		let mut sender = self.to_back.clone();
 		let fut = sender.send(FrontToBack::Notification(raw));
 
-		let timeout = tokio::time::sleep(self.request_timeout);
+		let timeout = rt::sleep(self.request_timeout);
 
 		let res = tokio::select! {
 			x = fut => x,
@@ -203,36 +244,29 @@ impl ClientT for Client {
 	where
 		R: DeserializeOwned,
 	{
-		let (send_back_tx, send_back_rx) = oneshot::channel();
-		let guard = self.id_manager.next_request_id()?;
-		let id = guard.inner();
-
-		let raw = serde_json::to_string(&RequestSer::new(&id, method, params)).map_err(Error::ParseError)?;
-		tracing::trace!("[frontend]: send request: {:?}", raw);
-
-		if self
-			.to_back
-			.clone()
-			.send(FrontToBack::Request(RequestMessage { raw, id, send_back: Some(send_back_tx) }))
-			.await
-			.is_err()
-		{
-			return Err(self.read_error_from_backend().await);
-		}
-
-		let res = call_with_timeout(self.request_timeout, send_back_rx).await;
-		let json_value = match res {
-			Ok(Ok(v)) => v,
-			Ok(Err(err)) => return Err(err),
-			Err(_) => return Err(self.read_error_from_backend().await),
-		};
-		serde_json::from_value(json_value).map_err(Error::ParseError)
+		self.request_with_timeout(method, params, self.request_timeout).await
 	}
 
 	async fn batch_request<'a, R>(&self, batch: Vec<(&'a str, Option<ParamsSer<'a>>)>) -> Result<Vec<R>, Error>
 	where
 		R: DeserializeOwned + Default + Clone,
 	{
+		self.send_batch(batch)
+			.await?
+			.into_iter()
+			.map(|result| serde_json::from_value(result?).map_err(Error::ParseError))
+			.collect()
+	}
+
+	async fn batch<'a>(&self, batch: BatchRequestBuilder<'a>) -> Result<BatchResponse, Error> {
+		let (calls, decoder) = batch.into_parts();
+		decoder.decode(self.send_batch(calls).await?)
+	}
+}
+
+impl Client {
+	/// Send a batch of calls, returns the result or the error of each call in order.
+	async fn send_batch<'a>(&self, batch: Vec<(&'a str, Option<ParamsSer<'a>>)>) -> Result<Vec<RawCallResult>, Error> {
 		let guard = self.id_manager.next_request_ids(batch.len())?;
 		let batch_ids: Vec<Id> = guard.inner();
 		let mut batches = Vec::with_capacity(batch.len());
@@ -248,23 +282,22 @@ impl ClientT for Client {
 		if self
 			.to_back
 			.clone()
-			.send(FrontToBack::Batch(BatchMessage { raw, ids: batch_ids, send_back: send_back_tx }))
+			.send(FrontToBack::Batch(BatchMessage { raw, ids: batch_ids.clone(), send_back: send_back_tx }))
 			.await
 			.is_err()
 		{
 			return Err(self.read_error_from_backend().await);
 		}
 
-		let res = call_with_timeout(self.request_timeout, send_back_rx).await;
-		let json_values = match res {
-			Ok(Ok(v)) => v,
-			Ok(Err(err)) => return Err(err),
-			Err(_) => return Err(self.read_error_from_backend().await),
-		};
-
-		let values: Result<_, _> =
-			json_values.into_iter().map(|val| serde_json::from_value(val).map_err(Error::ParseError)).collect();
-		Ok(values?)
+		match call_with_timeout(self.request_timeout, send_back_rx).await {
+			Ok(Ok(results)) => Ok(results
+				.into_iter()
+				.zip(batch_ids)
+				.map(|(result, id)| result.map_err(|err| call_error(id, err)))
+				.collect()),
+			Ok(Err(err)) => Err(err),
+			Err(_) => Err(self.read_error_from_backend().await),
+		}
 	}
 }
 
@@ -495,7 +528,7 @@ async fn background_task<S: TransportSenderT, R: TransportReceiverT>(
 					let _ = process_notification(&mut manager, notif);
 				}
 				// Batch response.
-				else if let Ok(batch) = serde_json::from_str::<Vec<Response<_>>>(&raw) {
+				else if let Ok(batch) = parse_batch_response(raw.as_bytes()) {
 					tracing::debug!("[backend]: recv batch {:?}", batch);
 					if let Err(e) = process_batch_response(&mut manager, batch) {
 						let _ = front_error.send(e);
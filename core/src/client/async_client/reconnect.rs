@@ -0,0 +1,359 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Client re-establishing its connection when it drops, see [`ReconnectingClient`].
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task;
+use std::time::Duration;
+
+use crate::client::{Backoff, BatchRequestBuilder, BatchResponse, Client, ClientT, Subscription, SubscriptionClientT};
+use crate::error::Error;
+use async_trait::async_trait;
+use futures_util::future::{self, BoxFuture, Either};
+use futures_util::stream::{BoxStream, Stream, StreamExt};
+use jsonrpsee_types::ParamsSer;
+use serde::de::DeserializeOwned;
+use serde_json::Value as JsonValue;
+
+type Connect = Box<dyn Fn() -> BoxFuture<'static, Result<Client, Error>> + Send + Sync>;
+
+/// Client re-establishing its connection when it drops.
+///
+/// A dropped connection is detected by the next call, which connects again before being sent, retrying with the
+/// configured [`Backoff`]. A call waits for the connection for at most the request timeout of the dropped client,
+/// failing with [`Error::RequestTimeout`] otherwise. The calls which were in flight when the connection dropped fail
+/// with [`Error::RestartNeeded`].
+///
+/// The subscriptions made with [`ReconnectingClient::subscribe_reconnecting`] are re-issued on the new connection,
+/// with a [`SubscriptionEvent::Reconnected`] marking the gap in their notifications. The ones made with
+/// [`SubscriptionClientT`] end with the connection they were made on.
+#[derive(Clone)]
+pub struct ReconnectingClient {
+	shared: Arc<Shared>,
+}
+
+struct Shared {
+	connect: Connect,
+	backoff: Backoff,
+	max_attempts: Option<u32>,
+	current: Mutex<Current>,
+	/// Held during each attempt to reconnect, not while waiting for the next one, so that the calls reconnect one at
+	/// a time.
+	reconnecting: tokio::sync::Mutex<()>,
+}
+
+/// Current connection, counting the connections made so far.
+#[derive(Clone)]
+struct Current {
+	generation: u64,
+	client: Arc<Client>,
+}
+
+impl fmt::Debug for ReconnectingClient {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("ReconnectingClient")
+			.field("backoff", &self.shared.backoff)
+			.field("max_attempts", &self.shared.max_attempts)
+			.finish()
+	}
+}
+
+impl ReconnectingClient {
+	/// Connect with `connect`, which is called again to reconnect whenever the connection dropped.
+	///
+	/// Reconnecting gives up after `max_attempts` failed attempts in a row if set, the call which triggered it then
+	/// fails with the error of the last attempt and the next call tries again.
+	pub async fn new<F, Fut>(connect: F, backoff: Backoff, max_attempts: Option<u32>) -> Result<Self, Error>
+	where
+		F: Fn() -> Fut + Send + Sync + 'static,
+		Fut: Future<Output = Result<Client, Error>> + Send + 'static,
+	{
+		let connect: Connect = Box::new(move || Box::pin(connect()));
+		let client = connect().await?;
+		let current = Mutex::new(Current { generation: 0, client: Arc::new(client) });
+		let reconnecting = tokio::sync::Mutex::new(());
+		Ok(Self { shared: Arc::new(Shared { connect, backoff, max_attempts, current, reconnecting }) })
+	}
+
+	/// Checks if the client is currently connected.
+	pub async fn is_connected(&self) -> bool {
+		self.shared.current().client.is_connected()
+	}
+
+	/// Send a method call like [`Client::request_with_timeout`].
+	pub async fn request_with_timeout<'a, R>(
+		&self,
+		method: &'a str,
+		params: Option<ParamsSer<'a>>,
+		timeout: Duration,
+	) -> Result<R, Error>
+	where
+		R: DeserializeOwned,
+	{
+		self.shared.connected().await?.request_with_timeout(method, params, timeout).await
+	}
+
+	/// Subscribe like [`SubscriptionClientT::subscribe`], re-issuing the subscription whenever the client
+	/// reconnected.
+	pub async fn subscribe_reconnecting<'a, N>(
+		&self,
+		subscribe_method: &'a str,
+		params: Option<ParamsSer<'a>>,
+		unsubscribe_method: &'a str,
+	) -> Result<ReconnectingSubscription<N>, Error>
+	where
+		N: DeserializeOwned + Send + 'static,
+	{
+		let client = self.shared.connected().await?;
+		let subscription = client.subscribe(subscribe_method, params.clone(), unsubscribe_method).await?;
+		let state = Resubscribe {
+			shared: self.shared.clone(),
+			client,
+			subscription,
+			subscribe_method: subscribe_method.to_owned(),
+			params: params.map(OwnedParams::from),
+			unsubscribe_method: unsubscribe_method.to_owned(),
+		};
+		Ok(ReconnectingSubscription { inner: futures_util::stream::unfold(Some(state), Resubscribe::next).boxed() })
+	}
+}
+
+impl Shared {
+	fn current(&self) -> Current {
+		self.current.lock().expect("Current connection lock poisoned; qed").clone()
+	}
+
+	/// Returns the current client, connecting again first if the connection dropped.
+	///
+	/// Waits for at most the request timeout of the dropped client, failing with [`Error::RequestTimeout`].
+	async fn connected(&self) -> Result<Arc<Client>, Error> {
+		let current = self.current();
+		if current.client.is_connected() {
+			return Ok(current.client);
+		}
+
+		let reconnect = Box::pin(self.reconnect(current.generation));
+		let timeout = Box::pin(super::rt::sleep(current.client.request_timeout));
+		match future::select(reconnect, timeout).await {
+			Either::Left((result, _)) => result,
+			Either::Right(_) => Err(Error::RequestTimeout),
+		}
+	}
+
+	/// Connect again to replace the connection `generation`, unless another call did meanwhile.
+	async fn reconnect(&self, mut generation: u64) -> Result<Arc<Client>, Error> {
+		let mut retry = 0;
+		loop {
+			{
+				let _reconnecting = self.reconnecting.lock().await;
+				let current = self.current();
+				if current.generation != generation {
+					if current.client.is_connected() {
+						return Ok(current.client);
+					}
+					// The connection made by another call dropped already.
+					generation = current.generation;
+				}
+
+				tracing::debug!("Reconnecting, attempt {}", retry + 1);
+				match (self.connect)().await {
+					Ok(client) => {
+						let client = Arc::new(client);
+						let mut current = self.current.lock().expect("Current connection lock poisoned; qed");
+						current.generation += 1;
+						current.client = client.clone();
+						tracing::info!("Reconnected, connection {}", current.generation);
+						return Ok(client);
+					}
+					Err(err) if self.max_attempts.is_some_and(|max| retry + 1 >= max) => {
+						tracing::warn!("Giving up reconnecting after {} attempts: {:?}", retry + 1, err);
+						return Err(err);
+					}
+					Err(err) => tracing::debug!("Reconnecting failed: {:?}", err),
+				}
+			}
+			super::rt::sleep(self.backoff.delay(retry)).await;
+			retry = retry.saturating_add(1);
+		}
+	}
+}
+
+#[async_trait]
+impl ClientT for ReconnectingClient {
+	async fn notification<'a>(&self, method: &'a str, params: Option<ParamsSer<'a>>) -> Result<(), Error> {
+		self.shared.connected().await?.notification(method, params).await
+	}
+
+	async fn request<'a, R>(&self, method: &'a str, params: Option<ParamsSer<'a>>) -> Result<R, Error>
+	where
+		R: DeserializeOwned,
+	{
+		self.shared.connected().await?.request(method, params).await
+	}
+
+	async fn batch_request<'a, R>(&self, batch: Vec<(&'a str, Option<ParamsSer<'a>>)>) -> Result<Vec<R>, Error>
+	where
+		R: DeserializeOwned + Default + Clone,
+	{
+		self.shared.connected().await?.batch_request(batch).await
+	}
+
+	async fn batch<'a>(&self, batch: BatchRequestBuilder<'a>) -> Result<BatchResponse, Error> {
+		self.shared.connected().await?.batch(batch).await
+	}
+}
+
+#[async_trait]
+impl SubscriptionClientT for ReconnectingClient {
+	async fn subscribe<'a, N>(
+		&self,
+		subscribe_method: &'a str,
+		params: Option<ParamsSer<'a>>,
+		unsubscribe_method: &'a str,
+	) -> Result<Subscription<N>, Error>
+	where
+		N: DeserializeOwned,
+	{
+		self.shared.connected().await?.subscribe(subscribe_method, params, unsubscribe_method).await
+	}
+
+	async fn subscribe_to_method<'a, N>(&self, method: &'a str) -> Result<Subscription<N>, Error>
+	where
+		N: DeserializeOwned,
+	{
+		self.shared.connected().await?.subscribe_to_method(method).await
+	}
+}
+
+/// Item of a [`ReconnectingSubscription`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SubscriptionEvent<Notif> {
+	/// A notification of the subscription.
+	Notification(Notif),
+	/// The client reconnected and the subscription was re-issued, the notifications sent meanwhile are missed.
+	Reconnected,
+}
+
+/// Subscription re-issued whenever the [`ReconnectingClient`] reconnected.
+///
+/// The stream ends when the server closes the subscription, or when the subscription can't be re-issued, after
+/// yielding the error.
+pub struct ReconnectingSubscription<Notif> {
+	inner: BoxStream<'static, Result<SubscriptionEvent<Notif>, Error>>,
+}
+
+impl<Notif> fmt::Debug for ReconnectingSubscription<Notif> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("ReconnectingSubscription").finish()
+	}
+}
+
+impl<Notif> ReconnectingSubscription<Notif> {
+	/// Returns the next event of the subscription, like [`Subscription::next`].
+	#[allow(clippy::should_implement_trait)]
+	pub async fn next(&mut self) -> Option<Result<SubscriptionEvent<Notif>, Error>> {
+		StreamExt::next(self).await
+	}
+}
+
+impl<Notif> Stream for ReconnectingSubscription<Notif> {
+	type Item = Result<SubscriptionEvent<Notif>, Error>;
+
+	fn poll_next(mut self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> task::Poll<Option<Self::Item>> {
+		self.inner.poll_next_unpin(cx)
+	}
+}
+
+/// State of a [`ReconnectingSubscription`].
+struct Resubscribe<Notif> {
+	shared: Arc<Shared>,
+	client: Arc<Client>,
+	subscription: Subscription<Notif>,
+	subscribe_method: String,
+	params: Option<OwnedParams>,
+	unsubscribe_method: String,
+}
+
+impl<Notif: DeserializeOwned + Send + 'static> Resubscribe<Notif> {
+	async fn next(state: Option<Self>) -> Option<(Result<SubscriptionEvent<Notif>, Error>, Option<Self>)> {
+		let mut state = state?;
+		if let Some(item) = state.subscription.next().await {
+			return Some((item.map(SubscriptionEvent::Notification), Some(state)));
+		}
+		// The subscription was closed by the server, not by a dropped connection.
+		if state.client.is_connected() {
+			return None;
+		}
+
+		loop {
+			let client = match state.shared.connected().await {
+				Ok(client) => client,
+				Err(err) => return Some((Err(err), None)),
+			};
+			let params = state.params.as_ref().map(OwnedParams::as_params);
+			match client.subscribe(&state.subscribe_method, params, &state.unsubscribe_method).await {
+				Ok(subscription) => {
+					state.client = client;
+					state.subscription = subscription;
+					return Some((Ok(SubscriptionEvent::Reconnected), Some(state)));
+				}
+				// The new connection dropped already.
+				Err(Error::RestartNeeded(_)) if !client.is_connected() => continue,
+				Err(err) => return Some((Err(err), None)),
+			}
+		}
+	}
+}
+
+/// Params of a subscription, kept to re-issue it.
+enum OwnedParams {
+	Array(Vec<JsonValue>),
+	Map(BTreeMap<String, JsonValue>),
+}
+
+impl From<ParamsSer<'_>> for OwnedParams {
+	fn from(params: ParamsSer<'_>) -> Self {
+		match params {
+			ParamsSer::Array(params) => Self::Array(params),
+			ParamsSer::ArrayRef(params) => Self::Array(params.to_vec()),
+			ParamsSer::Map(params) => Self::Map(params.into_iter().map(|(k, v)| (k.to_owned(), v)).collect()),
+		}
+	}
+}
+
+impl OwnedParams {
+	fn as_params(&self) -> ParamsSer<'_> {
+		match self {
+			Self::Array(params) => ParamsSer::ArrayRef(params),
+			Self::Map(params) => ParamsSer::Map(params.iter().map(|(k, v)| (k.as_str(), v.clone())).collect()),
+		}
+	}
+}
@@ -0,0 +1,77 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Tasks and timers of the client: on the event loop of the browser with the `async-wasm-client` feature on
+//! `wasm32`, on `tokio` otherwise.
+
+use std::future::Future;
+use std::time::Duration;
+
+/// Spawn `fut` on the `tokio` runtime.
+///
+/// ## Panics
+///
+/// Panics if being called outside of `tokio` runtime context.
+#[cfg(all(feature = "async-client", not(all(feature = "async-wasm-client", target_arch = "wasm32"))))]
+pub(crate) fn spawn(fut: impl Future<Output = ()> + Send + 'static) {
+	tokio::spawn(fut);
+}
+
+/// Wait until `duration` has elapsed.
+#[cfg(all(feature = "async-client", not(all(feature = "async-wasm-client", target_arch = "wasm32"))))]
+pub(crate) async fn sleep(duration: Duration) {
+	tokio::time::sleep(duration).await
+}
+
+/// Spawn `fut` on the event loop of the browser.
+#[cfg(all(feature = "async-wasm-client", any(target_arch = "wasm32", not(feature = "async-client"))))]
+pub(crate) fn spawn(fut: impl Future<Output = ()> + 'static) {
+	wasm_bindgen_futures::spawn_local(fut);
+}
+
+/// Wait until `duration` has elapsed, with `setTimeout`.
+///
+/// The timer runs in a task of its own, so that the returned future is `Send` like the calls awaiting it.
+#[cfg(all(feature = "async-wasm-client", any(target_arch = "wasm32", not(feature = "async-client"))))]
+pub(crate) fn sleep(duration: Duration) -> impl Future<Output = ()> + Send {
+	use js_sys::{Function, Promise, Reflect};
+
+	let (tx, rx) = futures_channel::oneshot::channel::<()>();
+	wasm_bindgen_futures::spawn_local(async move {
+		let promise = Promise::new(&mut |resolve, _reject| {
+			let set_timeout: Function = Reflect::get(&js_sys::global(), &"setTimeout".into())
+				.expect("setTimeout is defined in browsers and workers; qed")
+				.into();
+			let millis = duration.as_millis().min(i32::MAX as u128) as i32;
+			let _ = set_timeout.call2(&js_sys::global(), &resolve, &millis.into());
+		});
+		let _ = wasm_bindgen_futures::JsFuture::from(promise).await;
+		let _ = tx.send(());
+	});
+	async move {
+		let _ = rx.await;
+	}
+}
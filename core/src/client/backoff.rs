@@ -0,0 +1,76 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Delays between the retries of an operation, such as reconnecting to a server.
+
+use std::time::Duration;
+
+/// Strategy of the delays between the retries of an operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backoff {
+	/// The same delay before every retry.
+	Fixed(Duration),
+	/// A delay doubling after every retry, from `initial` up to `max`.
+	Exponential {
+		/// Delay before the first retry.
+		initial: Duration,
+		/// Maximum delay.
+		max: Duration,
+	},
+}
+
+impl Default for Backoff {
+	fn default() -> Self {
+		Self::Exponential { initial: Duration::from_millis(100), max: Duration::from_secs(10) }
+	}
+}
+
+impl Backoff {
+	/// Returns the delay before the retry numbered `retry`, counting from 0.
+	pub fn delay(&self, retry: u32) -> Duration {
+		match *self {
+			Self::Fixed(delay) => delay,
+			Self::Exponential { initial, max } => {
+				let factor = 2_u32.checked_pow(retry).unwrap_or(u32::MAX);
+				initial.checked_mul(factor).map_or(max, |delay| delay.min(max))
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::Backoff;
+	use std::time::Duration;
+
+	#[test]
+	fn exponential_backoff_is_capped() {
+		let backoff = Backoff::Exponential { initial: Duration::from_millis(100), max: Duration::from_secs(1) };
+		let delays: Vec<_> = (0..5).map(|retry| backoff.delay(retry).as_millis()).collect();
+		assert_eq!(delays, vec![100, 200, 400, 800, 1000]);
+		assert_eq!(backoff.delay(u32::MAX), Duration::from_secs(1));
+	}
+}
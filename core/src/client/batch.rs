@@ -0,0 +1,272 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use std::any::Any;
+use std::fmt;
+use std::marker::PhantomData;
+
+use crate::Error;
+use jsonrpsee_types::{ErrorObjectOwned, ErrorResponse, Id, ParamsSer, Response};
+use serde::de::DeserializeOwned;
+use serde_json::value::RawValue;
+use serde_json::Value as JsonValue;
+
+/// Result or error of a call in a batch, before its decoding.
+///
+/// The error of a call answered with an error response is the same as the one of a single call, see
+/// [`call_error`].
+pub type RawCallResult = Result<JsonValue, Error>;
+
+/// Decode the result of a call into the type it was inserted with.
+type Decoder = fn(JsonValue) -> Result<Box<dyn Any + Send>, serde_json::Error>;
+
+/// Batch of calls whose results have different types, for [`ClientT::batch`](crate::client::ClientT::batch).
+///
+/// Each call inserted returns a [`BatchEntry`], which takes its result out of the [`BatchResponse`].
+///
+/// ```no_run
+/// use jsonrpsee_core::client::{BatchRequestBuilder, ClientT};
+/// use jsonrpsee_core::Error;
+///
+/// async fn block_and_name(client: &(impl ClientT + Sync)) -> Result<(), Error> {
+///     let mut batch = BatchRequestBuilder::new();
+///     let block = batch.insert::<u64>("chain_getBlockNumber", None);
+///     let name = batch.insert::<String>("system_name", None);
+///     let mut response = client.batch(batch).await?;
+///
+///     match response.take(block) {
+///         Ok(block) => println!("block: {}", block),
+///         Err(err) => println!("no block: {}", err),
+///     }
+///     let _name = response.take(name);
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct BatchRequestBuilder<'a> {
+	calls: Vec<(&'a str, Option<ParamsSer<'a>>)>,
+	decoders: Vec<Decoder>,
+}
+
+impl<'a> BatchRequestBuilder<'a> {
+	/// Create an empty batch.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Insert a call of `method`, whose result is decoded as `T`.
+	pub fn insert<T>(&mut self, method: &'a str, params: Option<ParamsSer<'a>>) -> BatchEntry<T>
+	where
+		T: DeserializeOwned + Send + 'static,
+	{
+		self.calls.push((method, params));
+		self.decoders.push(decode::<T>);
+		BatchEntry { index: self.calls.len() - 1, _marker: PhantomData }
+	}
+
+	/// Number of calls in the batch.
+	pub fn len(&self) -> usize {
+		self.calls.len()
+	}
+
+	/// Whether the batch has no calls.
+	pub fn is_empty(&self) -> bool {
+		self.calls.is_empty()
+	}
+
+	/// Method and parameters of the calls, in order.
+	pub fn calls_mut(&mut self) -> impl Iterator<Item = (&'a str, &mut Option<ParamsSer<'a>>)> {
+		self.calls.iter_mut().map(|(method, params)| (*method, params))
+	}
+
+	/// Split the batch into its calls to send and the decoder of their results, for the implementations of
+	/// [`ClientT`](crate::client::ClientT).
+	pub fn into_parts(self) -> (Vec<(&'a str, Option<ParamsSer<'a>>)>, BatchDecoder) {
+		(self.calls, BatchDecoder { decoders: self.decoders })
+	}
+}
+
+/// Decoder of the results of the calls of a [`BatchRequestBuilder`].
+#[derive(Debug)]
+pub struct BatchDecoder {
+	decoders: Vec<Decoder>,
+}
+
+impl BatchDecoder {
+	/// Decode the result of each call, in the order they were inserted.
+	///
+	/// Fails with [`Error::ParseError`] if a call succeeded with a result of another type, and with
+	/// [`Error::InvalidRequestId`] if `results` doesn't have one result per call.
+	pub fn decode(self, results: Vec<RawCallResult>) -> Result<BatchResponse, Error> {
+		if results.len() != self.decoders.len() {
+			return Err(Error::InvalidRequestId);
+		}
+		let entries = results
+			.into_iter()
+			.zip(self.decoders)
+			.map(|(result, decode)| match result {
+				Ok(value) => decode(value).map(|value| Some(Ok(value))).map_err(Error::ParseError),
+				Err(err) => Ok(Some(Err(err))),
+			})
+			.collect::<Result<_, _>>()?;
+		Ok(BatchResponse { entries })
+	}
+}
+
+/// Call inserted in a [`BatchRequestBuilder`], whose result is decoded as `T`.
+pub struct BatchEntry<T> {
+	index: usize,
+	_marker: PhantomData<fn() -> T>,
+}
+
+impl<T> fmt::Debug for BatchEntry<T> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("BatchEntry").field("index", &self.index).finish()
+	}
+}
+
+/// Response to a [`BatchRequestBuilder`], with the result or the error of each call.
+pub struct BatchResponse {
+	entries: Vec<Option<Result<Box<dyn Any + Send>, Error>>>,
+}
+
+impl BatchResponse {
+	/// Take the result of the call `entry` out of the response.
+	///
+	/// A call answered with an error response fails with the same [`Error::Request`] as a single call.
+	///
+	/// ## Panics
+	///
+	/// Panics if `entry` was inserted in another batch.
+	pub fn take<T: 'static>(&mut self, entry: BatchEntry<T>) -> Result<T, Error> {
+		let result =
+			self.entries.get_mut(entry.index).and_then(Option::take).expect("BatchEntry was inserted in another batch");
+		result.map(|value| *value.downcast().expect("BatchEntry was inserted in another batch"))
+	}
+
+	/// Number of calls in the batch.
+	pub fn len(&self) -> usize {
+		self.entries.len()
+	}
+
+	/// Whether the batch had no calls.
+	pub fn is_empty(&self) -> bool {
+		self.entries.is_empty()
+	}
+
+	/// Number of calls which failed.
+	pub fn num_failed(&self) -> usize {
+		self.entries.iter().filter(|entry| matches!(entry, Some(Err(_)))).count()
+	}
+}
+
+impl fmt::Debug for BatchResponse {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let entries: Vec<_> = self
+			.entries
+			.iter()
+			.map(|entry| match entry {
+				Some(Ok(_)) => Ok("<result>"),
+				Some(Err(err)) => Err(err),
+				None => Ok("<taken>"),
+			})
+			.collect();
+		f.debug_struct("BatchResponse").field("entries", &entries).finish()
+	}
+}
+
+/// Error of the call `id` answered with the error response `error`, the same as the one of a single call.
+pub fn call_error(id: Id<'_>, error: ErrorObjectOwned) -> Error {
+	Error::Request(ErrorResponse::new(error.borrow(), id).to_string())
+}
+
+/// Parse the response to a batch, with the id and the result or the error of each call, in the order of the
+/// response.
+#[allow(clippy::type_complexity)]
+pub fn parse_batch_response(
+	body: &[u8],
+) -> Result<Vec<(Id<'static>, Result<JsonValue, ErrorObjectOwned>)>, serde_json::Error> {
+	let entries: Vec<&RawValue> = serde_json::from_slice(body)?;
+	entries
+		.into_iter()
+		.map(|entry| match serde_json::from_str::<Response<JsonValue>>(entry.get()) {
+			Ok(response) => Ok((response.id.into_owned(), Ok(response.result))),
+			Err(_) => {
+				let response: ErrorResponse = serde_json::from_str(entry.get())?;
+				Ok((response.id.into_owned(), Err(response.error.into_owned())))
+			}
+		})
+		.collect()
+}
+
+fn decode<T: DeserializeOwned + Send + 'static>(value: JsonValue) -> Result<Box<dyn Any + Send>, serde_json::Error> {
+	Ok(Box::new(serde_json::from_value::<T>(value)?))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use jsonrpsee_types::error::ErrorCode;
+
+	#[test]
+	fn batch_response_decodes_each_call() {
+		let mut batch = BatchRequestBuilder::new();
+		let number = batch.insert::<u64>("number", None);
+		let name = batch.insert::<String>("name", None);
+		let missing = batch.insert::<String>("missing", None);
+		let (calls, decoder) = batch.into_parts();
+		assert_eq!(calls.len(), 3);
+
+		let body = br#"[
+			{"jsonrpc":"2.0","result":"jsonrpsee","id":1},
+			{"jsonrpc":"2.0","error":{"code":-32601,"message":"Method not found"},"id":2},
+			{"jsonrpc":"2.0","result":42,"id":0}
+		]"#;
+		let parsed = parse_batch_response(body).unwrap();
+		let ids: Vec<_> = parsed.iter().map(|(id, _)| id.clone()).collect();
+		assert_eq!(ids, vec![Id::Number(1), Id::Number(2), Id::Number(0)]);
+
+		let mut results: Vec<_> =
+			parsed.into_iter().map(|(id, result)| result.map_err(|err| call_error(id, err))).collect();
+		results.rotate_right(1);
+		let mut response = decoder.decode(results).unwrap();
+		assert_eq!(response.num_failed(), 1);
+		assert_eq!(response.take(number).unwrap(), 42);
+		assert_eq!(response.take(name).unwrap(), "jsonrpsee");
+		let err = response.take(missing).unwrap_err();
+		let expected = ErrorResponse::new(ErrorCode::MethodNotFound.into(), Id::Number(2)).to_string();
+		assert!(matches!(err, Error::Request(msg) if msg == expected));
+	}
+
+	#[test]
+	fn batch_response_rejects_results_of_another_type() {
+		let mut batch = BatchRequestBuilder::new();
+		batch.insert::<u64>("number", None);
+		let (_, decoder) = batch.into_parts();
+
+		assert!(matches!(decoder.decode(vec![Ok(JsonValue::from("not a number"))]), Err(Error::ParseError(_))));
+	}
+}
@@ -0,0 +1,162 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Middleware of the clients, see [`ClientMiddleware`].
+
+use crate::client::{BatchRequestBuilder, BatchResponse, ClientT, Subscription, SubscriptionClientT};
+use crate::error::Error;
+use async_trait::async_trait;
+use jsonrpsee_types::ParamsSer;
+use serde::de::DeserializeOwned;
+
+/// Defines a middleware of a client, called around each call it makes, see [`MiddlewareClient`].
+///
+/// Batches trigger the hooks once per call, subscribing triggers them for the subscribe call.
+pub trait ClientMiddleware: Send + Sync + Clone + 'static {
+	/// Intended to carry timestamp of a call, for example `std::time::Instant`. How the middleware
+	/// measures time, if at all, is entirely up to the implementation.
+	type Instant: Send + Copy;
+
+	/// Called before a call is serialized and sent, with its params, which the middleware may modify.
+	fn on_request(&self, method: &str, params: &mut Option<ParamsSer<'_>>) -> Self::Instant;
+
+	/// Called once the call completed, successfully or with `Err`.
+	fn on_response(&self, _method: &str, _result: Result<(), &Error>, _started_at: Self::Instant) {}
+}
+
+impl ClientMiddleware for () {
+	type Instant = ();
+
+	fn on_request(&self, _method: &str, _params: &mut Option<ParamsSer<'_>>) -> Self::Instant {}
+}
+
+impl<A, B> ClientMiddleware for (A, B)
+where
+	A: ClientMiddleware,
+	B: ClientMiddleware,
+{
+	type Instant = (A::Instant, B::Instant);
+
+	fn on_request(&self, method: &str, params: &mut Option<ParamsSer<'_>>) -> Self::Instant {
+		(self.0.on_request(method, params), self.1.on_request(method, params))
+	}
+
+	fn on_response(&self, method: &str, result: Result<(), &Error>, started_at: Self::Instant) {
+		self.0.on_response(method, result, started_at.0);
+		self.1.on_response(method, result, started_at.1);
+	}
+}
+
+/// Client calling a [`ClientMiddleware`] around each of its calls.
+#[derive(Debug, Clone)]
+pub struct MiddlewareClient<C, M> {
+	client: C,
+	middleware: M,
+}
+
+impl<C, M> MiddlewareClient<C, M> {
+	/// Call `middleware` around each call of `client`.
+	pub fn new(client: C, middleware: M) -> Self {
+		Self { client, middleware }
+	}
+
+	/// Returns the wrapped client, its calls bypass the middleware.
+	pub fn inner(&self) -> &C {
+		&self.client
+	}
+}
+
+#[async_trait]
+impl<C: ClientT + Send + Sync, M: ClientMiddleware> ClientT for MiddlewareClient<C, M> {
+	async fn notification<'a>(&self, method: &'a str, mut params: Option<ParamsSer<'a>>) -> Result<(), Error> {
+		let started_at = self.middleware.on_request(method, &mut params);
+		let res = self.client.notification(method, params).await;
+		self.middleware.on_response(method, res.as_ref().map(|_| ()), started_at);
+		res
+	}
+
+	async fn request<'a, R>(&self, method: &'a str, mut params: Option<ParamsSer<'a>>) -> Result<R, Error>
+	where
+		R: DeserializeOwned,
+	{
+		let started_at = self.middleware.on_request(method, &mut params);
+		let res = self.client.request(method, params).await;
+		self.middleware.on_response(method, res.as_ref().map(|_| ()), started_at);
+		res
+	}
+
+	async fn batch_request<'a, R>(&self, mut batch: Vec<(&'a str, Option<ParamsSer<'a>>)>) -> Result<Vec<R>, Error>
+	where
+		R: DeserializeOwned + Default + Clone,
+	{
+		let started_at: Vec<_> =
+			batch.iter_mut().map(|(method, params)| self.middleware.on_request(method, params)).collect();
+		let methods: Vec<&str> = batch.iter().map(|(method, _)| *method).collect();
+		let res = self.client.batch_request(batch).await;
+		for (method, started_at) in methods.into_iter().zip(started_at) {
+			self.middleware.on_response(method, res.as_ref().map(|_| ()), started_at);
+		}
+		res
+	}
+
+	async fn batch<'a>(&self, mut batch: BatchRequestBuilder<'a>) -> Result<BatchResponse, Error> {
+		let started_at: Vec<_> =
+			batch.calls_mut().map(|(method, params)| (method, self.middleware.on_request(method, params))).collect();
+		let res = self.client.batch(batch).await;
+		for (method, started_at) in started_at {
+			self.middleware.on_response(method, res.as_ref().map(|_| ()), started_at);
+		}
+		res
+	}
+}
+
+#[async_trait]
+impl<C: SubscriptionClientT + Send + Sync, M: ClientMiddleware> SubscriptionClientT for MiddlewareClient<C, M> {
+	async fn subscribe<'a, N>(
+		&self,
+		subscribe_method: &'a str,
+		mut params: Option<ParamsSer<'a>>,
+		unsubscribe_method: &'a str,
+	) -> Result<Subscription<N>, Error>
+	where
+		N: DeserializeOwned,
+	{
+		let started_at = self.middleware.on_request(subscribe_method, &mut params);
+		let res = self.client.subscribe(subscribe_method, params, unsubscribe_method).await;
+		self.middleware.on_response(subscribe_method, res.as_ref().map(|_| ()), started_at);
+		res
+	}
+
+	async fn subscribe_to_method<'a, N>(&self, method: &'a str) -> Result<Subscription<N>, Error>
+	where
+		N: DeserializeOwned,
+	{
+		let started_at = self.middleware.on_request(method, &mut None);
+		let res = self.client.subscribe_to_method(method).await;
+		self.middleware.on_response(method, res.as_ref().map(|_| ()), started_at);
+		res
+	}
+}
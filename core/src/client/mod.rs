@@ -37,7 +37,7 @@ use futures_channel::{mpsc, oneshot};
 use futures_util::future::FutureExt;
 use futures_util::sink::SinkExt;
 use futures_util::stream::{Stream, StreamExt};
-use jsonrpsee_types::{Id, ParamsSer, SubscriptionId};
+use jsonrpsee_types::{ErrorObjectOwned, Id, ParamsSer, SubscriptionId};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 
@@ -48,11 +48,41 @@ pub mod __reexports {
 }
 
 /// Async client abstraction that brings additional deps.
-#[cfg(feature = "async-client")]
+#[cfg(any(feature = "async-client", feature = "async-wasm-client"))]
 mod async_client;
 
+/// Delays between the retries of an operation.
+pub mod backoff;
+
+/// Batches of calls whose results have different types.
+pub mod batch;
+
+/// Retries of the failed calls of a client.
 #[cfg(feature = "async-client")]
+pub mod retry;
+
+/// Client spreading its calls over several endpoints.
+pub mod multi;
+
+/// Outbound proxies of the clients.
+#[cfg(not(target_arch = "wasm32"))]
+pub mod proxy;
+
+/// Middleware of the clients.
+pub mod middleware;
+
+#[cfg(any(feature = "async-client", feature = "async-wasm-client"))]
+pub use async_client::reconnect::{ReconnectingClient, ReconnectingSubscription, SubscriptionEvent};
+#[cfg(any(feature = "async-client", feature = "async-wasm-client"))]
 pub use async_client::{Client, ClientBuilder};
+pub use backoff::Backoff;
+pub use batch::{BatchEntry, BatchRequestBuilder, BatchResponse};
+pub use middleware::{ClientMiddleware, MiddlewareClient};
+pub use multi::{LoadBalancing, MultiClient};
+#[cfg(not(target_arch = "wasm32"))]
+pub use proxy::{Proxy, ProxyKind};
+#[cfg(feature = "async-client")]
+pub use retry::{Retry, RetryPolicy};
 
 /// [JSON-RPC](https://www.jsonrpc.org/specification) client interface that can make requests and notifications.
 #[async_trait]
@@ -74,6 +104,25 @@ pub trait ClientT {
 	async fn batch_request<'a, R>(&self, batch: Vec<(&'a str, Option<ParamsSer<'a>>)>) -> Result<Vec<R>, Error>
 	where
 		R: DeserializeOwned + Default + Clone;
+
+	/// Send a [batch request](https://www.jsonrpc.org/specification#batch) of calls whose results have different
+	/// types, see [`BatchRequestBuilder`].
+	///
+	/// Returns `Ok` if the server answered the batch, with the result or the error of each call.
+	/// Returns `Error` if the batch couldn't be sent or answered, or if a result has the wrong type.
+	///
+	/// The default implementation sends the calls one by one, with [`ClientT::request`].
+	async fn batch<'a>(&self, batch: BatchRequestBuilder<'a>) -> Result<BatchResponse, Error> {
+		let (calls, decoder) = batch.into_parts();
+		let mut results = Vec::with_capacity(calls.len());
+		for (method, params) in calls {
+			match self.request::<JsonValue>(method, params).await {
+				Err(err @ Error::Request(_)) => results.push(Err(err)),
+				result => results.push(Ok(result?)),
+			}
+		}
+		decoder.decode(results)
+	}
 }
 
 /// [JSON-RPC](https://www.jsonrpc.org/specification) client interface that can make requests, notifications and subscriptions.
@@ -164,6 +213,9 @@ pub enum SubscriptionKind {
 
 /// Internal type to detect whether a subscription response from
 /// the server was a valid notification or should be treated as an error.
+///
+/// The close notifications with the discriminator of [`SubscriptionClosed`] are detected before, so that they are
+/// never misclassified as a valid notification.
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(untagged)]
 pub enum NotifResponse<Notif> {
@@ -173,6 +225,10 @@ pub enum NotifResponse<Notif> {
 	Err(SubscriptionClosed),
 }
 
+/// Key of the single member of the values the background task of the async client forwards to a [`Subscription`]
+/// for the errors the server reports on it.
+pub(crate) const SUBSCRIPTION_ERROR: &str = "subscription_error";
+
 /// Active subscription on the client.
 ///
 /// It will automatically unsubscribe in the [`Subscription::drop`] so no need to explicitly call
@@ -211,8 +267,8 @@ pub struct BatchMessage {
 	pub raw: String,
 	/// Request IDs.
 	pub ids: Vec<Id<'static>>,
-	/// One-shot channel over which we send back the result of this request.
-	pub send_back: oneshot::Sender<Result<Vec<JsonValue>, Error>>,
+	/// One-shot channel over which we send back the result or the error of each call.
+	pub send_back: oneshot::Sender<Result<Vec<Result<JsonValue, ErrorObjectOwned>>, Error>>,
 }
 
 /// Request message.
@@ -301,10 +357,22 @@ where
 	type Item = Result<Notif, Error>;
 	fn poll_next(mut self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> task::Poll<Option<Self::Item>> {
 		let n = futures_util::ready!(self.notifs_rx.poll_next_unpin(cx));
-		let res = n.map(|n| match serde_json::from_value::<NotifResponse<Notif>>(n) {
-			Ok(NotifResponse::Ok(parsed)) => Ok(parsed),
-			Ok(NotifResponse::Err(e)) => Err(Error::SubscriptionClosed(e)),
-			Err(e) => Err(Error::ParseError(e)),
+		let res = n.map(|n| {
+			if n.get("subscription_closed") == Some(&JsonValue::Bool(true)) {
+				return match serde_json::from_value::<SubscriptionClosed>(n) {
+					Ok(e) => Err(Error::SubscriptionClosed(e)),
+					Err(e) => Err(Error::ParseError(e)),
+				};
+			}
+			// The subscription stays open after an error.
+			if let Some(error) = n.as_object().filter(|n| n.len() == 1).and_then(|n| n.get(SUBSCRIPTION_ERROR)) {
+				return Err(Error::SubscriptionError(error.clone()));
+			}
+			match serde_json::from_value::<NotifResponse<Notif>>(n) {
+				Ok(NotifResponse::Ok(parsed)) => Ok(parsed),
+				Ok(NotifResponse::Err(e)) => Err(Error::SubscriptionClosed(e)),
+				Err(e) => Err(Error::ParseError(e)),
+			}
 		});
 		task::Poll::Ready(res)
 	}
@@ -422,7 +490,7 @@ impl IdKind {
 
 #[cfg(test)]
 mod tests {
-	use super::{IdKind, RequestIdManager};
+	use super::*;
 
 	#[test]
 	fn request_id_guard_works() {
@@ -437,4 +505,49 @@ mod tests {
 
 		assert!(manager.next_request_id().is_ok());
 	}
+
+	struct OneByOne;
+
+	#[async_trait::async_trait]
+	impl ClientT for OneByOne {
+		async fn notification<'a>(&self, _: &'a str, _: Option<ParamsSer<'a>>) -> Result<(), Error> {
+			Ok(())
+		}
+
+		async fn request<'a, R: DeserializeOwned>(
+			&self,
+			method: &'a str,
+			_: Option<ParamsSer<'a>>,
+		) -> Result<R, Error> {
+			match method {
+				"number" => serde_json::from_value(JsonValue::from(42)).map_err(Error::ParseError),
+				"closed" => Err(Error::RestartNeeded("closed".into())),
+				_ => Err(Error::Request(
+					r#"{"jsonrpc":"2.0","error":{"code":-32601,"message":"Method not found"},"id":0}"#.into(),
+				)),
+			}
+		}
+
+		async fn batch_request<'a, R>(&self, _: Vec<(&'a str, Option<ParamsSer<'a>>)>) -> Result<Vec<R>, Error>
+		where
+			R: DeserializeOwned + Default + Clone,
+		{
+			unimplemented!()
+		}
+	}
+
+	#[tokio::test]
+	async fn default_batch_sends_the_calls_one_by_one() {
+		let mut batch = BatchRequestBuilder::new();
+		let number = batch.insert::<u64>("number", None);
+		let missing = batch.insert::<u64>("missing", None);
+		let mut response = OneByOne.batch(batch).await.unwrap();
+		assert_eq!(response.take(number).unwrap(), 42);
+		assert!(matches!(response.take(missing), Err(Error::Request(msg)) if msg.contains("Method not found")));
+
+		let mut batch = BatchRequestBuilder::new();
+		batch.insert::<u64>("number", None);
+		batch.insert::<u64>("closed", None);
+		assert!(matches!(OneByOne.batch(batch).await, Err(Error::RestartNeeded(_))));
+	}
 }
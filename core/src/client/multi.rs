@@ -0,0 +1,328 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Client spreading its calls over several endpoints, see [`MultiClient`].
+
+use std::fmt;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+
+use crate::client::{BatchRequestBuilder, BatchResponse, ClientT, Subscription, SubscriptionClientT};
+use crate::error::Error;
+use async_trait::async_trait;
+use jsonrpsee_types::ParamsSer;
+use serde::de::DeserializeOwned;
+
+#[cfg(target_arch = "wasm32")]
+use self::wasm::Instant;
+
+/// How [`MultiClient`] picks the endpoint of a call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LoadBalancing {
+	/// Take turns between the endpoints.
+	#[default]
+	RoundRobin,
+	/// Use the first endpoint, and the next ones in order only while the previous ones are unhealthy.
+	Failover,
+	/// Use the endpoint which answered the fastest recently.
+	LowestLatency,
+}
+
+#[derive(Debug, Default)]
+struct Health {
+	/// Until when the endpoint is skipped, since a call failed to reach it.
+	unhealthy_until: Option<Instant>,
+	/// Moving average of the durations of the calls.
+	latency: Option<Duration>,
+}
+
+struct Endpoint<C> {
+	client: C,
+	health: Mutex<Health>,
+}
+
+/// Client spreading its calls over several endpoints according to a [`LoadBalancing`] policy.
+///
+/// A call failing to reach its endpoint, with a transport error, a timeout or a dropped connection, marks the
+/// endpoint as unhealthy: it is then used only if no other endpoint is healthy, until the cooldown elapsed or a call
+/// succeeded on it. The call is retried on the next endpoint if it was not sent, see [`Error::is_transient`]; the
+/// calls which timed out or whose connection dropped may have been executed, they are retried only if enabled with
+/// [`MultiClient::retry_unanswered`]. Error responses of the server are returned as they are.
+///
+/// A subscription stays on the endpoint it was made on.
+pub struct MultiClient<C> {
+	endpoints: Vec<Endpoint<C>>,
+	balancing: LoadBalancing,
+	cooldown: Duration,
+	retry_unanswered: bool,
+	next: AtomicUsize,
+}
+
+impl<C> fmt::Debug for MultiClient<C> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("MultiClient")
+			.field("endpoints", &self.endpoints.len())
+			.field("balancing", &self.balancing)
+			.field("cooldown", &self.cooldown)
+			.field("retry_unanswered", &self.retry_unanswered)
+			.finish()
+	}
+}
+
+impl<C> MultiClient<C> {
+	/// Spread the calls over `clients`, one per endpoint, in a round-robin fashion.
+	///
+	/// ## Panics
+	///
+	/// Panics if `clients` is empty.
+	pub fn new(clients: Vec<C>) -> Self {
+		assert!(!clients.is_empty(), "MultiClient needs at least one endpoint");
+		let endpoints = clients.into_iter().map(|client| Endpoint { client, health: Mutex::default() }).collect();
+		Self {
+			endpoints,
+			balancing: LoadBalancing::default(),
+			cooldown: Duration::from_secs(30),
+			retry_unanswered: false,
+			next: AtomicUsize::new(0),
+		}
+	}
+
+	/// Set how the endpoint of a call is picked (default is round-robin).
+	pub fn balancing(mut self, balancing: LoadBalancing) -> Self {
+		self.balancing = balancing;
+		self
+	}
+
+	/// Set for how long an unhealthy endpoint is skipped (default is 30 seconds).
+	pub fn cooldown(mut self, cooldown: Duration) -> Self {
+		self.cooldown = cooldown;
+		self
+	}
+
+	/// Retry the calls which were sent but not answered on the next endpoint too, see [`Error::is_unanswered`]. The
+	/// endpoint may have executed them already, so this is only safe for idempotent methods.
+	pub fn retry_unanswered(mut self) -> Self {
+		self.retry_unanswered = true;
+		self
+	}
+
+	/// Returns the clients of the endpoints, in the order they were given.
+	pub fn endpoints(&self) -> impl Iterator<Item = &C> {
+		self.endpoints.iter().map(|endpoint| &endpoint.client)
+	}
+
+	/// Returns whether the endpoint at `index` is healthy, `false` if there is no such endpoint.
+	pub fn is_healthy(&self, index: usize) -> bool {
+		self.endpoints.get(index).is_some_and(|endpoint| is_healthy(&endpoint.health(), Instant::now()))
+	}
+
+	/// Returns the indices of the endpoints in the order they are tried: the healthy ones as picked by the policy,
+	/// then the unhealthy ones.
+	fn order(&self) -> Vec<usize> {
+		let now = Instant::now();
+		let len = self.endpoints.len();
+		let health: Vec<_> = self
+			.endpoints
+			.iter()
+			.map(|endpoint| {
+				let health = endpoint.health();
+				(is_healthy(&health, now), health.latency)
+			})
+			.collect();
+
+		let mut order: Vec<usize> = match self.balancing {
+			LoadBalancing::RoundRobin => {
+				let start = self.next.fetch_add(1, Ordering::Relaxed);
+				(0..len).map(|i| (start + i) % len).collect()
+			}
+			LoadBalancing::Failover => (0..len).collect(),
+			LoadBalancing::LowestLatency => {
+				let mut order: Vec<usize> = (0..len).collect();
+				// Endpoints without calls yet come first, to measure them.
+				order.sort_by_key(|&i| health[i].1.unwrap_or_default());
+				order
+			}
+		};
+		order.sort_by_key(|&i| !health[i].0);
+		order
+	}
+
+	async fn call<'a, T, F, Fut>(&'a self, call: F) -> Result<T, Error>
+	where
+		F: Fn(&'a C) -> Fut,
+		Fut: Future<Output = Result<T, Error>>,
+	{
+		let mut last_err = None;
+		for index in self.order() {
+			let endpoint = &self.endpoints[index];
+			let started = Instant::now();
+			let err = match call(&endpoint.client).await {
+				Ok(value) => {
+					endpoint.succeeded(started.elapsed());
+					return Ok(value);
+				}
+				Err(err) => err,
+			};
+			let unanswered = err.is_unanswered();
+			if !err.is_transient() && !unanswered {
+				return Err(err);
+			}
+			endpoint.health().unhealthy_until = Some(Instant::now() + self.cooldown);
+			if unanswered && !self.retry_unanswered {
+				return Err(err);
+			}
+			last_err = Some(err);
+		}
+		Err(last_err.expect("MultiClient has at least one endpoint; qed"))
+	}
+}
+
+impl<C> Endpoint<C> {
+	fn health(&self) -> std::sync::MutexGuard<'_, Health> {
+		self.health.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+	}
+
+	fn succeeded(&self, elapsed: Duration) {
+		let mut health = self.health();
+		health.unhealthy_until = None;
+		health.latency = Some(match health.latency {
+			Some(latency) => (latency * 4 + elapsed) / 5,
+			None => elapsed,
+		});
+	}
+}
+
+fn is_healthy(health: &Health, now: Instant) -> bool {
+	health.unhealthy_until.is_none_or(|until| until <= now)
+}
+
+#[async_trait]
+impl<C: ClientT + Send + Sync> ClientT for MultiClient<C> {
+	async fn notification<'a>(&self, method: &'a str, params: Option<ParamsSer<'a>>) -> Result<(), Error> {
+		self.call(|client| client.notification(method, params.clone())).await
+	}
+
+	async fn request<'a, R>(&self, method: &'a str, params: Option<ParamsSer<'a>>) -> Result<R, Error>
+	where
+		R: DeserializeOwned,
+	{
+		self.call(|client| client.request(method, params.clone())).await
+	}
+
+	async fn batch_request<'a, R>(&self, batch: Vec<(&'a str, Option<ParamsSer<'a>>)>) -> Result<Vec<R>, Error>
+	where
+		R: DeserializeOwned + Default + Clone,
+	{
+		self.call(|client| client.batch_request(batch.clone())).await
+	}
+
+	async fn batch<'a>(&self, batch: BatchRequestBuilder<'a>) -> Result<BatchResponse, Error> {
+		self.call(|client| client.batch(batch.clone())).await
+	}
+}
+
+#[async_trait]
+impl<C: SubscriptionClientT + Send + Sync> SubscriptionClientT for MultiClient<C> {
+	async fn subscribe<'a, N>(
+		&self,
+		subscribe_method: &'a str,
+		params: Option<ParamsSer<'a>>,
+		unsubscribe_method: &'a str,
+	) -> Result<Subscription<N>, Error>
+	where
+		N: DeserializeOwned,
+	{
+		self.call(|client| client.subscribe(subscribe_method, params.clone(), unsubscribe_method)).await
+	}
+
+	async fn subscribe_to_method<'a, N>(&self, method: &'a str) -> Result<Subscription<N>, Error>
+	where
+		N: DeserializeOwned,
+	{
+		self.call(|client| client.subscribe_to_method(method)).await
+	}
+}
+
+/// Clock of the browser, as [`std::time::Instant`] panics on `wasm32`.
+#[cfg(target_arch = "wasm32")]
+mod wasm {
+	use std::ops::Add;
+	use std::time::Duration;
+
+	/// Milliseconds since the epoch, as told by `Date.now()`.
+	#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+	pub(super) struct Instant(f64);
+
+	impl Instant {
+		pub(super) fn now() -> Self {
+			Self(js_sys::Date::now())
+		}
+
+		pub(super) fn elapsed(&self) -> Duration {
+			// The clock of the browser can go backwards.
+			Duration::from_secs_f64((Self::now().0 - self.0).max(0.0) / 1000.0)
+		}
+	}
+
+	impl Add<Duration> for Instant {
+		type Output = Self;
+
+		fn add(self, duration: Duration) -> Self {
+			Self(self.0 + duration.as_secs_f64() * 1000.0)
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{LoadBalancing, MultiClient};
+	use std::time::{Duration, Instant};
+
+	#[test]
+	fn order_puts_unhealthy_endpoints_last() {
+		let client = MultiClient::new(vec![(), (), ()]);
+		assert_eq!(client.order(), vec![0, 1, 2]);
+		assert_eq!(client.order(), vec![1, 2, 0]);
+		client.endpoints[2].health().unhealthy_until = Some(Instant::now() + Duration::from_secs(60));
+		assert!(!client.is_healthy(2));
+		assert_eq!(client.order(), vec![0, 1, 2]);
+		assert_eq!(client.order(), vec![0, 1, 2]);
+
+		let client = client.balancing(LoadBalancing::Failover);
+		client.endpoints[0].health().unhealthy_until = Some(Instant::now() + Duration::from_secs(60));
+		assert_eq!(client.order(), vec![1, 0, 2]);
+		client.endpoints[0].succeeded(Duration::from_millis(5));
+		assert_eq!(client.order(), vec![0, 1, 2]);
+
+		let client = client.balancing(LoadBalancing::LowestLatency);
+		client.endpoints[1].succeeded(Duration::from_millis(1));
+		assert_eq!(client.order(), vec![1, 0, 2]);
+	}
+}
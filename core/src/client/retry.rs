@@ -0,0 +1,218 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Retries of the failed calls of a client, see [`Retry`].
+
+use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
+
+use crate::client::{Backoff, BatchRequestBuilder, BatchResponse, ClientT, Subscription, SubscriptionClientT};
+use crate::error::Error;
+use async_trait::async_trait;
+use jsonrpsee_types::{ErrorResponse, ParamsSer};
+use serde::de::DeserializeOwned;
+
+type RetryOn = Arc<dyn Fn(&Error) -> bool + Send + Sync>;
+
+/// When and how often [`Retry`] retries a failed call.
+#[derive(Clone)]
+pub struct RetryPolicy {
+	max_attempts: u32,
+	backoff: Backoff,
+	retry_on: RetryOn,
+}
+
+impl fmt::Debug for RetryPolicy {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("RetryPolicy").field("max_attempts", &self.max_attempts).field("backoff", &self.backoff).finish()
+	}
+}
+
+impl Default for RetryPolicy {
+	fn default() -> Self {
+		Self { max_attempts: 3, backoff: Backoff::default(), retry_on: Arc::new(Error::is_transient) }
+	}
+}
+
+impl RetryPolicy {
+	/// Set the maximum number of attempts of a call, including the first one. Default is 3.
+	pub fn max_attempts(mut self, max: u32) -> Self {
+		self.max_attempts = max.max(1);
+		self
+	}
+
+	/// Set the delays between the attempts. Default is exponential, from 100 milliseconds up to 10 seconds.
+	pub fn backoff(mut self, backoff: Backoff) -> Self {
+		self.backoff = backoff;
+		self
+	}
+
+	/// Set which errors are retried. Default is the transient errors, see [`Error::is_transient`], so that a call is
+	/// never executed twice.
+	pub fn retry_on(mut self, retry_on: impl Fn(&Error) -> bool + Send + Sync + 'static) -> Self {
+		self.retry_on = Arc::new(retry_on);
+		self
+	}
+
+	/// Retry the transient errors and the error responses of the server with one of `codes`, such as the
+	/// `Server is busy` errors of the servers limiting their resources.
+	pub fn retry_on_codes(self, codes: impl Into<Vec<i32>>) -> Self {
+		let codes = codes.into();
+		self.retry_on(move |err| err.is_transient() || error_code(err).is_some_and(|code| codes.contains(&code)))
+	}
+
+	/// Retry the calls which were sent but not answered too, see [`Error::is_unanswered`]. The server may have
+	/// executed them already, so this is only safe for idempotent methods.
+	pub fn retry_unanswered(self) -> Self {
+		let retry_on = self.retry_on.clone();
+		self.retry_on(move |err| err.is_unanswered() || retry_on(err))
+	}
+
+	/// Returns whether the call failing with `err` is retried.
+	pub fn should_retry(&self, err: &Error) -> bool {
+		(self.retry_on)(err)
+	}
+}
+
+/// Returns the code of the error response of the server a call failed with.
+pub fn error_code(err: &Error) -> Option<i32> {
+	match err {
+		Error::Request(response) => {
+			serde_json::from_str::<ErrorResponse>(response).ok().map(|resp| resp.error.code.code())
+		}
+		_ => None,
+	}
+}
+
+/// Client retrying its failed method calls and batches according to a [`RetryPolicy`].
+///
+/// Notifications and subscriptions are not retried.
+///
+/// # Examples
+///
+/// ```
+/// use jsonrpsee::core::client::{ClientT, Retry, RetryPolicy};
+/// use jsonrpsee::core::server::in_memory::InMemoryClientBuilder;
+/// use jsonrpsee::types::error::ErrorCode;
+/// use jsonrpsee::RpcModule;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let mut module = RpcModule::new(());
+///     module.register_method("best_block", |_, _| Ok(42_u64)).unwrap();
+///
+///     let client = InMemoryClientBuilder::default().build(module);
+///     let policy = RetryPolicy::default().max_attempts(5).retry_on_codes([ErrorCode::ServerIsBusy.code()]);
+///     let client = Retry::new(client, policy);
+///     let block: u64 = client.request("best_block", None).await.unwrap();
+///     assert_eq!(block, 42);
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Retry<C> {
+	client: C,
+	policy: RetryPolicy,
+}
+
+impl<C> Retry<C> {
+	/// Retry the failed calls of `client` according to `policy`.
+	pub fn new(client: C, policy: RetryPolicy) -> Self {
+		Self { client, policy }
+	}
+
+	/// Returns the wrapped client, its calls are not retried.
+	pub fn inner(&self) -> &C {
+		&self.client
+	}
+
+	async fn retry<T, F, Fut>(&self, mut call: F) -> Result<T, Error>
+	where
+		F: FnMut() -> Fut,
+		Fut: Future<Output = Result<T, Error>>,
+	{
+		let mut attempt = 1;
+		loop {
+			let err = match call().await {
+				Ok(value) => return Ok(value),
+				Err(err) => err,
+			};
+			if attempt >= self.policy.max_attempts || !self.policy.should_retry(&err) {
+				return Err(err);
+			}
+			tracing::debug!("Retrying call failed at attempt {}: {:?}", attempt, err);
+			tokio::time::sleep(self.policy.backoff.delay(attempt - 1)).await;
+			attempt += 1;
+		}
+	}
+}
+
+#[async_trait]
+impl<C: ClientT + Send + Sync> ClientT for Retry<C> {
+	async fn notification<'a>(&self, method: &'a str, params: Option<ParamsSer<'a>>) -> Result<(), Error> {
+		self.client.notification(method, params).await
+	}
+
+	async fn request<'a, R>(&self, method: &'a str, params: Option<ParamsSer<'a>>) -> Result<R, Error>
+	where
+		R: DeserializeOwned,
+	{
+		self.retry(|| self.client.request(method, params.clone())).await
+	}
+
+	async fn batch_request<'a, R>(&self, batch: Vec<(&'a str, Option<ParamsSer<'a>>)>) -> Result<Vec<R>, Error>
+	where
+		R: DeserializeOwned + Default + Clone,
+	{
+		self.retry(|| self.client.batch_request(batch.clone())).await
+	}
+
+	async fn batch<'a>(&self, batch: BatchRequestBuilder<'a>) -> Result<BatchResponse, Error> {
+		self.retry(|| self.client.batch(batch.clone())).await
+	}
+}
+
+#[async_trait]
+impl<C: SubscriptionClientT + Send + Sync> SubscriptionClientT for Retry<C> {
+	async fn subscribe<'a, N>(
+		&self,
+		subscribe_method: &'a str,
+		params: Option<ParamsSer<'a>>,
+		unsubscribe_method: &'a str,
+	) -> Result<Subscription<N>, Error>
+	where
+		N: DeserializeOwned,
+	{
+		self.client.subscribe(subscribe_method, params, unsubscribe_method).await
+	}
+
+	async fn subscribe_to_method<'a, N>(&self, method: &'a str) -> Result<Subscription<N>, Error>
+	where
+		N: DeserializeOwned,
+	{
+		self.client.subscribe_to_method(method).await
+	}
+}
@@ -0,0 +1,185 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Support of the `permessage-deflate` WebSocket extension ([RFC 7692](https://www.rfc-editor.org/rfc/rfc7692)),
+//! compressing the messages of a connection when both ends negotiate it during the handshake.
+
+use soketto::base::Header;
+use soketto::extension::deflate::Deflate;
+use soketto::extension::{Extension, Param};
+use soketto::{BoxedError, Mode, Storage};
+
+/// Name of the parameter limiting the LZ77 window of the server.
+const SERVER_MAX_WINDOW_BITS: &str = "server_max_window_bits";
+
+/// Default size in bytes below which messages are sent uncompressed.
+pub const DEFAULT_THRESHOLD: usize = 1024;
+
+/// Configuration of the `permessage-deflate` extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeflateConfig {
+	max_window_bits: u8,
+	threshold: usize,
+}
+
+impl Default for DeflateConfig {
+	fn default() -> Self {
+		Self { max_window_bits: 15, threshold: DEFAULT_THRESHOLD }
+	}
+}
+
+impl DeflateConfig {
+	/// Limit the size of the LZ77 sliding window used to compress the messages sent, as a base-2 logarithm
+	/// (default is 15, a 32 KiB window). Smaller windows use less memory per connection but compress less.
+	///
+	/// The server advertises the limit in its handshake response, the client in its handshake request.
+	///
+	/// # Panics
+	///
+	/// Panics if `bits` is not within `9..=15`.
+	pub fn max_window_bits(mut self, bits: u8) -> Self {
+		assert!((9..=15).contains(&bits), "max window bits have to be within 9..=15");
+		self.max_window_bits = bits;
+		self
+	}
+
+	/// Send the messages smaller than `bytes` uncompressed (default is [`DEFAULT_THRESHOLD`]), since compressing them
+	/// costs more than it saves. `0` compresses all messages.
+	pub fn threshold(mut self, bytes: usize) -> Self {
+		self.threshold = bytes;
+		self
+	}
+
+	/// Returns the extension to add to the handshake of a connection on the given side.
+	pub fn extension(&self, mode: Mode) -> Box<dyn Extension + Send> {
+		let mut inner = Deflate::new(mode);
+		if mode == Mode::Client && self.max_window_bits < 15 {
+			inner.set_max_client_window_bits(self.max_window_bits);
+		}
+		Box::new(ThresholdDeflate { inner, mode, max_window_bits: self.max_window_bits, threshold: self.threshold })
+	}
+}
+
+/// [`Deflate`] extension leaving the messages below a threshold uncompressed, which the RFC allows per message.
+#[derive(Debug)]
+struct ThresholdDeflate {
+	inner: Deflate,
+	mode: Mode,
+	max_window_bits: u8,
+	threshold: usize,
+}
+
+impl Extension for ThresholdDeflate {
+	fn is_enabled(&self) -> bool {
+		self.inner.is_enabled()
+	}
+
+	fn name(&self) -> &str {
+		self.inner.name()
+	}
+
+	fn params(&self) -> &[Param<'_>] {
+		self.inner.params()
+	}
+
+	fn configure(&mut self, params: &[Param]) -> Result<(), BoxedError> {
+		if self.mode == Mode::Client || self.max_window_bits == 15 {
+			return self.inner.configure(params);
+		}
+
+		// The server may always answer with a smaller window than the one offered by the client, if any.
+		let mut params = params.to_vec();
+		match params.iter_mut().find(|p| p.name() == SERVER_MAX_WINDOW_BITS) {
+			Some(p) => {
+				if p.value().and_then(|v| v.parse::<u8>().ok()).is_some_and(|v| v > self.max_window_bits) {
+					p.set_value(Some(self.max_window_bits.to_string()));
+				}
+			}
+			None => {
+				let mut p = Param::new(SERVER_MAX_WINDOW_BITS);
+				p.set_value(Some(self.max_window_bits.to_string()));
+				params.push(p);
+			}
+		}
+		self.inner.configure(&params)
+	}
+
+	fn encode(&mut self, header: &mut Header, data: &mut Storage) -> Result<(), BoxedError> {
+		if data.as_ref().len() < self.threshold {
+			return Ok(());
+		}
+		self.inner.encode(header, data)
+	}
+
+	fn decode(&mut self, header: &mut Header, data: &mut Vec<u8>) -> Result<(), BoxedError> {
+		self.inner.decode(header, data)
+	}
+
+	fn reserved_bits(&self) -> (bool, bool, bool) {
+		self.inner.reserved_bits()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn param(name: &'static str, value: Option<&'static str>) -> Param<'static> {
+		let mut p = Param::new(name);
+		p.set_value(value);
+		p
+	}
+
+	#[test]
+	fn server_limits_its_window() {
+		let mut ext = DeflateConfig::default().max_window_bits(10).extension(Mode::Server);
+		ext.configure(&[param("client_max_window_bits", None)]).unwrap();
+		assert!(ext.is_enabled());
+		assert_eq!(ext.params(), &[param(SERVER_MAX_WINDOW_BITS, Some("10"))]);
+
+		let mut ext = DeflateConfig::default().max_window_bits(12).extension(Mode::Server);
+		ext.configure(&[param(SERVER_MAX_WINDOW_BITS, Some("11"))]).unwrap();
+		assert_eq!(ext.params(), &[param(SERVER_MAX_WINDOW_BITS, Some("11"))]);
+	}
+
+	#[test]
+	fn small_messages_are_not_compressed() {
+		let mut ext = DeflateConfig::default().threshold(16).extension(Mode::Server);
+		ext.configure(&[]).unwrap();
+
+		let mut header = Header::new(soketto::base::OpCode::Text);
+		let mut data = Storage::Owned(b"short".to_vec());
+		ext.encode(&mut header, &mut data).unwrap();
+		assert!(!header.is_rsv1());
+		assert_eq!(data.as_ref(), b"short");
+
+		let msg = "long enough to be compressed".repeat(4);
+		let mut data = Storage::Owned(msg.as_bytes().to_vec());
+		ext.encode(&mut header, &mut data).unwrap();
+		assert!(header.is_rsv1());
+		assert!(data.as_ref().len() < msg.len());
+	}
+}
@@ -26,7 +26,7 @@
 
 use std::fmt;
 
-use jsonrpsee_types::error::CallError;
+use jsonrpsee_types::error::{CallError, ErrorObject, ErrorWithData};
 use serde::{Deserialize, Serialize};
 
 /// Convenience type for displaying errors.
@@ -100,6 +100,9 @@ pub enum Error {
 	/// Subscription got closed.
 	#[error("Subscription closed: {0:?}")]
 	SubscriptionClosed(SubscriptionClosed),
+	/// Error reported by the server on a subscription, which stays open.
+	#[error("Subscription error: {0}")]
+	SubscriptionError(serde_json::Value),
 	/// Request timeout
 	#[error("Request timeout")]
 	RequestTimeout,
@@ -127,12 +130,23 @@ pub enum Error {
 	/// Failed to register a resource due to a maximum number of resources already registered
 	#[error("Maximum number of resources reached")]
 	MaxResourcesReached,
+	/// One or more errors found when building an RPC module.
+	#[error("Invalid RPC module: {0:?}")]
+	InvalidModule(Vec<Error>),
+	/// A timeout was set on a method which can't time out, naming its kind.
+	#[error("Only asynchronous and blocking methods can time out, not {0} ones")]
+	TimeoutNotSupported(&'static str),
 	/// Custom error.
 	#[error("Custom error: {0}")]
 	Custom(String),
 	/// Not implemented for HTTP clients.
 	#[error("Not implemented")]
 	HttpNotImplemented,
+	/// Error that carries a hint about which HTTP status code the transport should respond with.
+	///
+	/// The client still receives the JSON-RPC error of the wrapped error; transports other than HTTP ignore the hint.
+	#[error("{1}")]
+	WithStatusHint(u16, Box<Error>),
 }
 
 impl Error {
@@ -145,6 +159,24 @@ impl Error {
 	{
 		Error::Call(CallError::from_std_error(err))
 	}
+
+	/// Attach a hint of the HTTP status code (such as `429` or `503`) to respond with when this error is returned
+	/// from a method call over HTTP.
+	pub fn with_status_hint(self, status: u16) -> Self {
+		Error::WithStatusHint(status, Box::new(self))
+	}
+
+	/// Returns whether this error of a client is transient and the call was not executed: a transport error, which
+	/// the clients report when they failed to send the call.
+	pub fn is_transient(&self) -> bool {
+		matches!(self, Error::Transport(_))
+	}
+
+	/// Returns whether the call failing with this error of a client was sent but not answered: it timed out, or its
+	/// connection dropped while it was in flight. The server may have executed it.
+	pub fn is_unanswered(&self) -> bool {
+		matches!(self, Error::RequestTimeout | Error::RestartNeeded(_))
+	}
 }
 
 /// A type with a special `subscription_closed` field to detect that
@@ -153,12 +185,43 @@ impl Error {
 ///
 /// This is included in the `result field` of the SubscriptionResponse
 /// when an error is reported by the server.
-#[derive(Deserialize, Serialize, Debug, PartialEq)]
+///
+/// The `subscription_closed` field is only serialized if opted in with [`SubscriptionClosed::with_discriminator`],
+/// as peers denying unknown fields reject it. Without it, an item shaped like a close reason can't be told apart.
+#[derive(Deserialize, Serialize, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct SubscriptionClosed {
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	subscription_closed: Option<ClosedMarker>,
 	reason: SubscriptionClosedReason,
 }
 
+/// Compares the close reasons, regardless of whether the discriminator is serialized.
+impl PartialEq for SubscriptionClosed {
+	fn eq(&self, other: &Self) -> bool {
+		self.reason == other.reason
+	}
+}
+
+/// Discriminator of [`SubscriptionClosed`], always serialized as `true` and only deserialized from `true`.
+#[derive(Debug, PartialEq)]
+struct ClosedMarker;
+
+impl Serialize for ClosedMarker {
+	fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.serialize_bool(true)
+	}
+}
+
+impl<'de> Deserialize<'de> for ClosedMarker {
+	fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		match bool::deserialize(deserializer)? {
+			true => Ok(ClosedMarker),
+			false => Err(serde::de::Error::invalid_value(serde::de::Unexpected::Bool(false), &"true")),
+		}
+	}
+}
+
 impl From<SubscriptionClosedReason> for SubscriptionClosed {
 	fn from(reason: SubscriptionClosedReason) -> Self {
 		Self::new(reason)
@@ -168,7 +231,19 @@ impl From<SubscriptionClosedReason> for SubscriptionClosed {
 impl SubscriptionClosed {
 	/// Create a new [`SubscriptionClosed`].
 	pub fn new(reason: SubscriptionClosedReason) -> Self {
-		Self { reason }
+		Self { subscription_closed: None, reason }
+	}
+
+	/// Serialize the `subscription_closed` discriminator field, for the peers to reliably tell the close apart from
+	/// the items of the subscription.
+	pub fn with_discriminator(mut self) -> Self {
+		self.subscription_closed = Some(ClosedMarker);
+		self
+	}
+
+	/// Returns whether the `subscription_closed` discriminator field is set.
+	pub fn has_discriminator(&self) -> bool {
+		self.subscription_closed.is_some()
 	}
 
 	/// Get the close reason.
@@ -179,7 +254,7 @@ impl SubscriptionClosed {
 
 /// A type to represent when a subscription gets closed
 /// by either the server or client side.
-#[derive(Deserialize, Serialize, Debug, PartialEq)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 pub enum SubscriptionClosedReason {
 	/// The subscription was closed by calling the unsubscribe method.
 	Unsubscribed,
@@ -203,6 +278,18 @@ pub enum GenericTransportError<T: std::error::Error + Send + Sync> {
 	Inner(T),
 }
 
+impl<'a> From<ErrorObject<'a>> for Error {
+	fn from(err: ErrorObject<'a>) -> Self {
+		Error::Call(err.into())
+	}
+}
+
+impl<T: serde::Serialize> From<ErrorWithData<T>> for Error {
+	fn from(err: ErrorWithData<T>) -> Self {
+		Error::Call(err.into())
+	}
+}
+
 impl From<std::io::Error> for Error {
 	fn from(io_err: std::io::Error) -> Error {
 		Error::Transport(io_err.into())
@@ -237,11 +324,16 @@ mod tests {
 			(r#"{"reason":"Unsubscribed"}"#, SubscriptionClosedReason::Unsubscribed.into()),
 			(r#"{"reason":"ConnectionReset"}"#, SubscriptionClosedReason::ConnectionReset.into()),
 			(r#"{"reason":{"Server":"hoho"}}"#, SubscriptionClosedReason::Server("hoho".into()).into()),
+			(
+				r#"{"subscription_closed":true,"reason":{"Server":"hoho"}}"#,
+				SubscriptionClosed::new(SubscriptionClosedReason::Server("hoho".into())).with_discriminator(),
+			),
 		];
 
 		for (s, d) in items {
 			let dsr: SubscriptionClosed = serde_json::from_str(s).unwrap();
 			assert_eq!(dsr, d);
+			assert_eq!(dsr.has_discriminator(), d.has_discriminator());
 			let ser = serde_json::to_string(&d).unwrap();
 			assert_eq!(ser, s);
 		}
@@ -252,4 +344,12 @@ mod tests {
 		let ser = r#"{"reason":"Unsubscribed","deny":1}"#;
 		assert!(serde_json::from_str::<SubscriptionClosed>(ser).is_err());
 	}
+
+	#[test]
+	fn subscription_closed_discriminator_is_true() {
+		let closed = serde_json::from_str::<SubscriptionClosed>(r#"{"reason":"Unsubscribed"}"#).unwrap();
+		assert!(!closed.has_discriminator());
+		assert!(serde_json::from_str::<SubscriptionClosed>(r#"{"subscription_closed":false,"reason":"Unsubscribed"}"#)
+			.is_err());
+	}
 }
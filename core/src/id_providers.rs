@@ -61,6 +61,43 @@ impl IdProvider for RandomStringIdProvider {
 	}
 }
 
+/// Generates random `0x`-prefixed hex strings of `len` bytes as subscription ID, for instance `"0x4f0a9c2d"`.
+#[derive(Debug, Clone)]
+pub struct HexIdProvider {
+	len: usize,
+}
+
+impl HexIdProvider {
+	/// Create a new hex string provider, generating IDs of `len` random bytes.
+	pub fn new(len: usize) -> Self {
+		Self { len }
+	}
+}
+
+impl IdProvider for HexIdProvider {
+	fn next_id(&self) -> SubscriptionId<'static> {
+		let mut rng = rand::thread_rng();
+		let mut id = String::with_capacity(2 + 2 * self.len);
+		id.push_str("0x");
+		for _ in 0..self.len {
+			id.push_str(&format!("{:02x}", rng.gen::<u8>()));
+		}
+		id.into()
+	}
+}
+
+/// Generates random (version 4) UUIDs as subscription ID, for instance `"67e55044-10b1-426f-9247-bb680e5fe0c8"`.
+#[cfg(feature = "uuid")]
+#[derive(Debug, Clone)]
+pub struct UuidIdProvider;
+
+#[cfg(feature = "uuid")]
+impl IdProvider for UuidIdProvider {
+	fn next_id(&self) -> SubscriptionId<'static> {
+		uuid::Uuid::new_v4().to_hyphenated().to_string().into()
+	}
+}
+
 /// No-op implementation to be used for servers that don't support subscriptions.
 #[derive(Debug, Clone)]
 pub struct NoopIdProvider;
@@ -70,3 +107,30 @@ impl IdProvider for NoopIdProvider {
 		0.into()
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn hex_id_provider_works() {
+		let id = HexIdProvider::new(8).next_id();
+		match id {
+			SubscriptionId::Str(id) => {
+				assert_eq!(id.len(), 18);
+				assert!(id.starts_with("0x"));
+				assert!(id[2..].chars().all(|c| c.is_ascii_hexdigit() && !c.is_ascii_uppercase()));
+			}
+			_ => panic!("Expected a string ID, got: {:?}", id),
+		}
+	}
+
+	#[cfg(feature = "uuid")]
+	#[test]
+	fn uuid_id_provider_works() {
+		match UuidIdProvider.next_id() {
+			SubscriptionId::Str(id) => assert!(uuid::Uuid::parse_str(&id).is_ok()),
+			id => panic!("Expected a string ID, got: {:?}", id),
+		}
+	}
+}
@@ -37,10 +37,15 @@ pub mod traits;
 /// Middleware trait and implementation.
 pub mod middleware;
 
+pub mod framing;
+
 /// Shared hyper helpers.
 #[cfg(feature = "http-helpers")]
 pub mod http_helpers;
 
+#[cfg(feature = "deflate")]
+pub mod deflate;
+
 /// Different ways of setting the "id" in JSON-RPC responses and results.
 #[cfg(feature = "server")]
 pub mod id_providers;
@@ -64,6 +69,8 @@ pub type RpcResult<T> = std::result::Result<T, Error>;
 #[doc(hidden)]
 pub mod __reexports {
 	pub use async_trait::async_trait;
+	#[cfg(feature = "openrpc")]
+	pub use schemars;
 	pub use serde;
 	pub use serde_json;
 }
@@ -26,6 +26,120 @@
 
 //! Middleware for `jsonrpsee` servers.
 
+use std::net::SocketAddr;
+
+use crate::Error;
+use async_trait::async_trait;
+use serde_json::value::RawValue;
+
+/// Transport a connection was made over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransportKind {
+	/// WebSocket connection.
+	WebSocket,
+	/// HTTP connection.
+	Http,
+	/// IPC connection, over a Unix domain socket or a named pipe on Windows.
+	Ipc,
+	/// Raw TCP connection, with the same framing as IPC connections.
+	Tcp,
+	/// Standard input and output of the process, or other pipes the server was given.
+	Stdio,
+	/// In-memory client bound directly to the methods.
+	InMemory,
+}
+
+/// Details of the connection a call was made on.
+///
+/// Passed to [`Middleware::on_connect_with_details`] and stored in the `ConnectionState` of the connection, where method calls
+/// and subscriptions can read it with `ConnectionState::details`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ConnectionDetails {
+	conn_id: usize,
+	peer_addr: SocketAddr,
+	transport: TransportKind,
+	protocol: Option<String>,
+	tls: Option<TlsDetails>,
+}
+
+impl ConnectionDetails {
+	/// Create the details of the connection `conn_id` to `peer_addr` over `transport`, without a protocol.
+	pub fn new(conn_id: usize, peer_addr: SocketAddr, transport: TransportKind) -> Self {
+		Self { conn_id, peer_addr, transport, protocol: None, tls: None }
+	}
+
+	/// Set the protocol negotiated with the peer.
+	pub fn with_protocol(mut self, protocol: impl Into<String>) -> Self {
+		self.protocol = Some(protocol.into());
+		self
+	}
+
+	/// Set the TLS session the connection was made over.
+	pub fn with_tls(mut self, tls: TlsDetails) -> Self {
+		self.tls = Some(tls);
+		self
+	}
+
+	/// Id of the connection, unique among the open connections of the server.
+	pub fn conn_id(&self) -> usize {
+		self.conn_id
+	}
+
+	/// Address of the remote peer, unspecified for IPC and stdio connections.
+	pub fn peer_addr(&self) -> SocketAddr {
+		self.peer_addr
+	}
+
+	/// Transport the connection was made over.
+	pub fn transport(&self) -> TransportKind {
+		self.transport
+	}
+
+	/// Protocol negotiated with the peer: the HTTP version for HTTP connections, the subprotocol for WebSocket
+	/// connections if one was negotiated.
+	pub fn protocol(&self) -> Option<&str> {
+		self.protocol.as_deref()
+	}
+
+	/// TLS session of the connection, if the server terminated TLS.
+	pub fn tls(&self) -> Option<&TlsDetails> {
+		self.tls.as_ref()
+	}
+}
+
+/// Details of the TLS session of a connection.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct TlsDetails {
+	pub(crate) server_name: Option<String>,
+	pub(crate) version: Option<String>,
+	pub(crate) alpn_protocol: Option<Vec<u8>>,
+	pub(crate) client_certificate: bool,
+}
+
+impl TlsDetails {
+	/// Server name the client asked for with SNI, if any.
+	pub fn server_name(&self) -> Option<&str> {
+		self.server_name.as_deref()
+	}
+
+	/// Version of TLS negotiated with the client, such as `TLSv1_3`.
+	pub fn version(&self) -> Option<&str> {
+		self.version.as_deref()
+	}
+
+	/// Protocol negotiated with ALPN, if any.
+	pub fn alpn_protocol(&self) -> Option<&[u8]> {
+		self.alpn_protocol.as_deref()
+	}
+
+	/// Whether the client authenticated with a certificate.
+	pub fn client_certificate(&self) -> bool {
+		self.client_certificate
+	}
+}
+
 /// Defines a middleware with callbacks during the RPC request life-cycle. The primary use case for
 /// this is to collect timings for a larger metrics collection solution but the only constraints on
 /// the associated type is that it be [`Send`] and [`Copy`], giving users some freedom to do what
@@ -39,16 +153,26 @@ pub trait Middleware: Send + Sync + Clone + 'static {
 	/// measures time, if at all, is entirely up to the implementation.
 	type Instant: Send + Copy;
 
-	/// Called when a new client connects (WebSocket only)
+	/// Called when a new client connects (WebSocket and IPC only)
 	fn on_connect(&self) {}
 
+	/// Called when a new client connects with the details of its connection (WebSocket and IPC only), calls
+	/// [`Middleware::on_connect`] by default.
+	fn on_connect_with_details(&self, _details: &ConnectionDetails) {
+		self.on_connect();
+	}
+
 	/// Called when a new JSON-RPC comes to the server.
 	fn on_request(&self) -> Self::Instant;
 
 	/// Called on each JSON-RPC method call, batch requests will trigger `on_call` multiple times.
-	fn on_call(&self, _name: &str) {}
+	///
+	/// Receives the raw params of the call and the details of the connection it was made on.
+	fn on_call(&self, _name: &str, _params: Option<&RawValue>, _details: &ConnectionDetails) {}
 
 	/// Called on each JSON-RPC method completion, batch requests will trigger `on_result` multiple times.
+	///
+	/// Not called for the methods that aren't registered, so `name` is always the name of a registered method.
 	fn on_result(&self, _name: &str, _success: bool, _started_at: Self::Instant) {}
 
 	/// Called once the JSON-RPC request is finished and response is sent to the output buffer.
@@ -56,6 +180,12 @@ pub trait Middleware: Send + Sync + Clone + 'static {
 
 	/// Called when a client disconnects (WebSocket only)
 	fn on_disconnect(&self) {}
+
+	/// Called when a subscription is opened (WebSocket only)
+	fn on_subscribe(&self) {}
+
+	/// Called when a subscription is closed, by the client or the server (WebSocket only)
+	fn on_unsubscribe(&self) {}
 }
 
 impl Middleware for () {
@@ -71,13 +201,23 @@ where
 {
 	type Instant = (A::Instant, B::Instant);
 
+	fn on_connect(&self) {
+		self.0.on_connect();
+		self.1.on_connect();
+	}
+
+	fn on_connect_with_details(&self, details: &ConnectionDetails) {
+		self.0.on_connect_with_details(details);
+		self.1.on_connect_with_details(details);
+	}
+
 	fn on_request(&self) -> Self::Instant {
 		(self.0.on_request(), self.1.on_request())
 	}
 
-	fn on_call(&self, name: &str) {
-		self.0.on_call(name);
-		self.1.on_call(name);
+	fn on_call(&self, name: &str, params: Option<&RawValue>, details: &ConnectionDetails) {
+		self.0.on_call(name, params, details);
+		self.1.on_call(name, params, details);
 	}
 
 	fn on_result(&self, name: &str, success: bool, started_at: Self::Instant) {
@@ -89,4 +229,48 @@ where
 		self.0.on_response(started_at.0);
 		self.1.on_response(started_at.1);
 	}
+
+	fn on_disconnect(&self) {
+		self.0.on_disconnect();
+		self.1.on_disconnect();
+	}
+
+	fn on_subscribe(&self) {
+		self.0.on_subscribe();
+		self.1.on_subscribe();
+	}
+
+	fn on_unsubscribe(&self) {
+		self.0.on_unsubscribe();
+		self.1.on_unsubscribe();
+	}
+}
+
+/// Middleware with asynchronous hooks awaited around the execution of each method call, without blocking the
+/// connection, for instance to authorize the calls with a remote service.
+///
+/// Set with [`WsServerBuilder::set_async_middleware`](../../jsonrpsee_ws_server/struct.WsServerBuilder.html#method.set_async_middleware)
+/// or [`HttpServerBuilder::set_async_middleware`](../../jsonrpsee_http_server/struct.HttpServerBuilder.html#method.set_async_middleware).
+/// Subscribe calls are covered too, their callback is executed once the call is authorized.
+#[async_trait]
+pub trait AsyncMiddleware: Send + Sync + 'static {
+	/// Called before the method is executed, with the same arguments as [`Middleware::on_call`].
+	/// The call is answered with the returned error instead of being executed.
+	async fn on_call(
+		&self,
+		_name: &str,
+		_params: Option<&RawValue>,
+		_details: &ConnectionDetails,
+	) -> Result<(), Error> {
+		Ok(())
+	}
+
+	/// Called once the method was executed, or rejected by [`AsyncMiddleware::on_call`], with whether it succeeded.
+	async fn on_result(&self, _name: &str, _success: bool) {}
+}
+
+impl std::fmt::Debug for dyn AsyncMiddleware {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.write_str("AsyncMiddleware")
+	}
 }
@@ -0,0 +1,196 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! # Connection state
+//!
+//! Storage scoped to a single connection, for stateful sessions where a call relies on the outcome of earlier calls
+//! made on the same connection, such as a login.
+//!
+//! The WebSocket server creates a [`ConnectionState`](crate::server::connection_state::ConnectionState) when a
+//! connection is opened and drops it when the connection is closed. HTTP is stateless, so every request made over HTTP
+//! gets a fresh state dropped once the request completes. Both servers store the
+//! [`ConnectionDetails`](crate::middleware::ConnectionDetails) of the connection, such as the address of the peer, in
+//! the state.
+//!
+//! The WebSocket server cancels the asynchronous calls in flight when the connection is closed by the client. Work
+//! done outside of the call, such as in a task it spawned, can observe the closing with
+//! [`ConnectionState::closed`](crate::server::connection_state::ConnectionState::closed).
+//! It is made available to methods registered with
+//! [`RpcModule::register_method_with_connection_state`](crate::server::rpc_module::RpcModule::register_method_with_connection_state),
+//! while the other methods and the subscription callbacks get it with
+//! [`ConnectionState::current`](crate::server::connection_state::ConnectionState::current).
+
+use std::any::{Any, TypeId};
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use rustc_hash::FxHashMap;
+use tokio::sync::Notify;
+
+use crate::middleware::ConnectionDetails;
+
+tokio::task_local! {
+	static CONNECTION_STATE: Arc<ConnectionState>;
+}
+
+/// Values stored for a connection, holding at most one value per type.
+#[derive(Debug, Default)]
+pub struct ConnectionState {
+	values: Mutex<FxHashMap<TypeId, Box<dyn Any + Send + Sync>>>,
+	details: Option<ConnectionDetails>,
+	is_closed: AtomicBool,
+	closed: Notify,
+}
+
+impl ConnectionState {
+	/// Create a new, empty [`ConnectionState`].
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Create a new, empty [`ConnectionState`] for the connection with the given details.
+	pub fn with_details(details: ConnectionDetails) -> Self {
+		Self { details: Some(details), ..Default::default() }
+	}
+
+	/// Returns the details of the connection, `None` if the state wasn't created by a server.
+	pub fn details(&self) -> Option<&ConnectionDetails> {
+		self.details.as_ref()
+	}
+
+	/// Store a value, returning the value of the same type stored previously if any.
+	pub fn insert<T: Any + Send + Sync>(&self, value: T) -> Option<T> {
+		let previous = self.values.lock().insert(TypeId::of::<T>(), Box::new(value))?;
+		previous.downcast().ok().map(|value| *value)
+	}
+
+	/// Returns a clone of the value of the given type, if stored.
+	pub fn get<T: Any + Send + Sync + Clone>(&self) -> Option<T> {
+		self.values.lock().get(&TypeId::of::<T>())?.downcast_ref().cloned()
+	}
+
+	/// Remove the value of the given type, returning it if it was stored.
+	pub fn remove<T: Any + Send + Sync>(&self) -> Option<T> {
+		let value = self.values.lock().remove(&TypeId::of::<T>())?;
+		value.downcast().ok().map(|value| *value)
+	}
+
+	/// Returns whether a value of the given type is stored.
+	pub fn contains<T: Any + Send + Sync>(&self) -> bool {
+		self.values.lock().contains_key(&TypeId::of::<T>())
+	}
+
+	/// Mark the connection as closed, waking up the tasks waiting on [`ConnectionState::closed`].
+	///
+	/// This is called by the servers when the connection is closed.
+	pub fn close(&self) {
+		self.is_closed.store(true, Ordering::SeqCst);
+		self.closed.notify_waiters();
+	}
+
+	/// Returns whether the connection was closed.
+	pub fn is_closed(&self) -> bool {
+		self.is_closed.load(Ordering::SeqCst)
+	}
+
+	/// Wait for the connection to be closed, for instance to stop work done on behalf of the client.
+	pub async fn closed(&self) {
+		let closed = self.closed.notified();
+		if self.is_closed() {
+			return;
+		}
+		closed.await;
+	}
+
+	/// Returns the state of the connection the method call or subscription being executed was made on, `None` if
+	/// called outside of a call made on a connection.
+	///
+	/// Subscriptions and synchronous methods only have access to it while their callback is executed, so the callbacks
+	/// spawning tasks have to pass the returned state to them.
+	pub fn current() -> Option<Arc<Self>> {
+		CONNECTION_STATE.try_with(Arc::clone).ok()
+	}
+
+	/// Execute `fut` with the given connection state available with [`ConnectionState::current`].
+	pub fn scope<F: Future>(state: Arc<Self>, fut: F) -> impl Future<Output = F::Output> {
+		CONNECTION_STATE.scope(state, fut)
+	}
+
+	/// Execute the synchronous `f` with the given connection state available with [`ConnectionState::current`].
+	pub fn sync_scope<R>(state: Arc<Self>, f: impl FnOnce() -> R) -> R {
+		CONNECTION_STATE.sync_scope(state, f)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::ConnectionState;
+	use std::sync::Arc;
+
+	#[test]
+	fn values_are_stored_per_type() {
+		let state = ConnectionState::new();
+		assert_eq!(state.insert(1_u32), None);
+		assert_eq!(state.insert(String::from("user")), None);
+		assert_eq!(state.insert(2_u32), Some(1));
+
+		assert_eq!(state.get::<u32>(), Some(2));
+		assert_eq!(state.get::<String>().as_deref(), Some("user"));
+		assert!(!state.contains::<u64>());
+
+		assert_eq!(state.remove::<u32>(), Some(2));
+		assert!(!state.contains::<u32>());
+	}
+
+	#[tokio::test]
+	async fn state_is_only_available_in_scope() {
+		assert!(ConnectionState::current().is_none());
+
+		let state = Arc::new(ConnectionState::new());
+		state.insert(7_u8);
+		let current = ConnectionState::scope(state, async { ConnectionState::current() }).await;
+		assert_eq!(current.and_then(|state| state.get::<u8>()), Some(7));
+	}
+
+	#[tokio::test]
+	async fn closing_wakes_up_waiters() {
+		let state = Arc::new(ConnectionState::new());
+		let waiter = tokio::spawn({
+			let state = state.clone();
+			async move { state.closed().await }
+		});
+		tokio::task::yield_now().await;
+
+		assert!(!state.is_closed());
+		state.close();
+		waiter.await.unwrap();
+		assert!(state.is_closed());
+		// Resolves right away once closed.
+		state.closed().await;
+	}
+}
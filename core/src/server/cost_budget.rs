@@ -0,0 +1,128 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Cost budget of the method calls made on a connection.
+//!
+//! Only the servers keeping connections open enforce it, the WebSocket and IPC servers. The HTTP server doesn't, as
+//! its clients can open a new connection, with a new budget, for every request.
+
+use std::time::Instant;
+
+use crate::server::rpc_module::MethodCallback;
+use jsonrpsee_types::error::{ErrorCode, ErrorObject, COST_BUDGET_EXCEEDED_CODE, COST_BUDGET_EXCEEDED_MSG};
+use parking_lot::Mutex;
+
+/// Configuration of the cost budget of each connection.
+#[derive(Debug, Clone, Copy)]
+pub struct CostBudget {
+	/// Max units of the budget, which the budget of a new connection starts with.
+	pub capacity: u32,
+	/// Units added back to the budget per second, up to the capacity.
+	pub refill_per_sec: u32,
+}
+
+/// Cost budget of a connection, a token bucket the calls take the cost of the method they call from.
+#[derive(Debug)]
+pub(crate) struct ConnectionBudget(Option<Mutex<TokenBucket>>);
+
+impl ConnectionBudget {
+	/// Create the budget of a new connection, the calls are unlimited if `budget` is `None`.
+	pub(crate) fn new(budget: Option<CostBudget>) -> Self {
+		Self(budget.map(|budget| Mutex::new(TokenBucket::new(budget, Instant::now()))))
+	}
+
+	/// Take the cost of a call to the method from the budget, returns `false` if the budget is exceeded, in which
+	/// case the call must be rejected with [`budget_exceeded`].
+	pub(crate) fn try_spend(&self, method: &MethodCallback) -> bool {
+		match &self.0 {
+			Some(bucket) => bucket.lock().try_spend(method.cost(), Instant::now()),
+			None => true,
+		}
+	}
+}
+
+/// Error answered to the calls exceeding the budget.
+pub(crate) fn budget_exceeded() -> ErrorObject<'static> {
+	ErrorObject {
+		code: ErrorCode::ServerError(COST_BUDGET_EXCEEDED_CODE),
+		message: COST_BUDGET_EXCEEDED_MSG.into(),
+		data: None,
+	}
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+	budget: CostBudget,
+	/// Units left as of `refilled_at`.
+	available: f64,
+	refilled_at: Instant,
+}
+
+impl TokenBucket {
+	fn new(budget: CostBudget, now: Instant) -> Self {
+		Self { budget, available: budget.capacity as f64, refilled_at: now }
+	}
+
+	fn try_spend(&mut self, cost: u32, now: Instant) -> bool {
+		let elapsed = now.saturating_duration_since(self.refilled_at).as_secs_f64();
+		let refilled = self.available + elapsed * self.budget.refill_per_sec as f64;
+		self.available = refilled.min(self.budget.capacity as f64);
+		self.refilled_at = now;
+
+		if self.available < cost as f64 {
+			return false;
+		}
+		self.available -= cost as f64;
+		true
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{CostBudget, TokenBucket};
+	use std::time::{Duration, Instant};
+
+	#[test]
+	fn budget_is_spent_and_refilled_up_to_the_capacity() {
+		let start = Instant::now();
+		let mut bucket = TokenBucket::new(CostBudget { capacity: 10, refill_per_sec: 2 }, start);
+
+		assert!(bucket.try_spend(8, start));
+		assert!(!bucket.try_spend(3, start));
+		assert!(bucket.try_spend(2, start));
+		assert!(!bucket.try_spend(1, start));
+
+		// Half a second refills one unit.
+		let later = start + Duration::from_millis(500);
+		assert!(bucket.try_spend(1, later));
+		assert!(!bucket.try_spend(1, later));
+
+		// Refilling stops at the capacity.
+		let much_later = later + Duration::from_secs(60);
+		assert!(!bucket.try_spend(11, much_later));
+		assert!(bucket.try_spend(10, much_later));
+	}
+}
@@ -0,0 +1,106 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! # Deadlines
+//!
+//! Clients can attach a deadline hint to a call with the `deadline_ms` field of the request object, an extension
+//! of the JSON-RPC specification holding the number of milliseconds the client is willing to wait for the response:
+//!
+//! ```json
+//! {"jsonrpc":"2.0","method":"fetch_blocks","params":[1000],"id":1,"deadline_ms":1500}
+//! ```
+//!
+//! The deadline is measured from the moment the server receives the call, and is made available to asynchronous
+//! methods with [`Deadline::current`](crate::server::deadline::Deadline::current) so that they can give up on downstream work that would exceed it.
+
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use futures_util::future::Either;
+
+tokio::task_local! {
+	static DEADLINE: Deadline;
+}
+
+/// The point in time by which the client expects the response to a call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Deadline(Instant);
+
+impl Deadline {
+	/// Create a deadline `millis` milliseconds from now.
+	pub fn after_millis(millis: u64) -> Self {
+		Self(Instant::now() + Duration::from_millis(millis))
+	}
+
+	/// Returns the deadline of the asynchronous method call being executed, `None` if the client didn't provide
+	/// one or if called outside of an asynchronous method call.
+	///
+	/// Note that the deadline is not propagated to tasks spawned by the method.
+	pub fn current() -> Option<Self> {
+		DEADLINE.try_with(|deadline| *deadline).ok()
+	}
+
+	/// Returns the point in time of the deadline.
+	pub fn instant(&self) -> Instant {
+		self.0
+	}
+
+	/// Returns the time left until the deadline, zero if it expired.
+	pub fn remaining(&self) -> Duration {
+		self.0.saturating_duration_since(Instant::now())
+	}
+
+	/// Returns whether the deadline expired.
+	pub fn is_expired(&self) -> bool {
+		self.0 <= Instant::now()
+	}
+
+	/// Execute `fut` with the given deadline, if any, available with [`Deadline::current`].
+	pub fn scope<F: Future>(deadline: Option<Self>, fut: F) -> impl Future<Output = F::Output> {
+		match deadline {
+			Some(deadline) => Either::Left(DEADLINE.scope(deadline, fut)),
+			None => Either::Right(fut),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::Deadline;
+
+	#[tokio::test]
+	async fn deadline_is_only_available_in_scope() {
+		assert_eq!(Deadline::current(), None);
+
+		let deadline = Deadline::after_millis(1_000);
+		let current = Deadline::scope(Some(deadline), async { Deadline::current() }).await;
+		assert_eq!(current, Some(deadline));
+		assert!(!deadline.is_expired());
+
+		assert_eq!(Deadline::scope(None, async { Deadline::current() }).await, None);
+		assert!(Deadline::after_millis(0).is_expired());
+	}
+}
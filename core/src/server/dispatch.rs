@@ -0,0 +1,393 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Dispatch of the messages received on a connection to the methods, shared by the servers.
+
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use crate::id_providers::RandomIntegerIdProvider;
+use crate::middleware::{AsyncMiddleware, ConnectionDetails, Middleware};
+use crate::server::connection_state::ConnectionState;
+use crate::server::cost_budget::{budget_exceeded, ConnectionBudget, CostBudget};
+use crate::server::deadline::Deadline;
+use crate::server::helpers::{collect_batch_response, prepare_error, BoundedSubscriptions, MethodFilter, MethodSink};
+use crate::server::resource_limiting::Resources;
+use crate::server::rpc_module::{
+	subscribe_with_async_middleware, ConnState, ConnectionId, MethodCallback, MethodKind, Methods,
+};
+use crate::traits::IdProvider;
+use futures_util::future::{join_all, BoxFuture, FutureExt};
+use jsonrpsee_types::error::ErrorCode;
+use jsonrpsee_types::{Id, Params, Request};
+use tokio::sync::Notify;
+
+/// Default maximum subscriptions per connection allowed.
+const MAX_SUBSCRIPTIONS_PER_CONNECTION: u32 = 1024;
+
+/// Dispatches the calls and batches received on a connection to the methods, answering them on the [`MethodSink`]
+/// of the connection.
+///
+/// A connection gets its own dispatcher, holding the state shared by its calls: its resources, subscriptions, cost
+/// budget and [`ConnectionState`].
+pub struct Dispatcher<M> {
+	conn_id: ConnectionId,
+	details: ConnectionDetails,
+	sink: MethodSink,
+	middleware: M,
+	resources: Option<Resources>,
+	id_provider: Arc<dyn IdProvider>,
+	connection_state: Arc<ConnectionState>,
+	close_notify: Arc<Notify>,
+	bounded_subscriptions: BoundedSubscriptions,
+	budget: ConnectionBudget,
+	method_filter: Option<MethodFilter>,
+	async_middleware: Option<Arc<dyn AsyncMiddleware>>,
+	call_spans: bool,
+}
+
+impl<M> std::fmt::Debug for Dispatcher<M> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("Dispatcher")
+			.field("conn_id", &self.conn_id)
+			.field("details", &self.details)
+			.field("resources", &self.resources)
+			.field("bounded_subscriptions", &self.bounded_subscriptions)
+			.field("budget", &self.budget)
+			.field("method_filter", &self.method_filter)
+			.field("async_middleware", &self.async_middleware)
+			.field("call_spans", &self.call_spans)
+			.finish()
+	}
+}
+
+impl<M: Middleware> Dispatcher<M> {
+	/// Create the dispatcher of the connection described by `details`, answering the calls on `sink`.
+	///
+	/// The connection can have up to 1024 subscriptions open at the same time, getting their IDs from the
+	/// [`RandomIntegerIdProvider`], and its calls claim no resources.
+	pub fn new(details: ConnectionDetails, sink: MethodSink, middleware: M) -> Self {
+		Self {
+			conn_id: details.conn_id(),
+			connection_state: Arc::new(ConnectionState::with_details(details.clone())),
+			details,
+			sink,
+			bounded_subscriptions: subscriptions_observed_by(&middleware, MAX_SUBSCRIPTIONS_PER_CONNECTION),
+			middleware,
+			resources: None,
+			id_provider: Arc::new(RandomIntegerIdProvider),
+			close_notify: Arc::new(Notify::new()),
+			budget: ConnectionBudget::new(None),
+			method_filter: None,
+			async_middleware: None,
+			call_spans: false,
+		}
+	}
+
+	/// Claim the resources of the methods from `resources`, with which the methods must be initialized.
+	pub fn with_resources(mut self, resources: Resources) -> Self {
+		self.resources = Some(resources);
+		self
+	}
+
+	/// Get the IDs of the subscriptions from `id_provider`.
+	pub fn with_id_provider(mut self, id_provider: Arc<dyn IdProvider>) -> Self {
+		self.id_provider = id_provider;
+		self
+	}
+
+	/// Limit the number of subscriptions open at the same time to `max`.
+	pub fn with_max_subscriptions(mut self, max: u32) -> Self {
+		self.bounded_subscriptions = subscriptions_observed_by(&self.middleware, max);
+		self
+	}
+
+	/// Reject the calls exceeding the cost `budget` of the connection, if any.
+	pub fn with_cost_budget(mut self, budget: Option<CostBudget>) -> Self {
+		self.budget = ConnectionBudget::new(budget);
+		self
+	}
+
+	/// Answer the calls to the methods `filter` doesn't allow as if they didn't exist, if any.
+	pub fn with_method_filter(mut self, filter: Option<MethodFilter>) -> Self {
+		self.method_filter = filter;
+		self
+	}
+
+	/// Await the hooks of `middleware` around the execution of each call, if any.
+	pub fn with_async_middleware(mut self, middleware: Option<Arc<dyn AsyncMiddleware>>) -> Self {
+		self.async_middleware = middleware;
+		self
+	}
+
+	/// Execute each call in its own `rpc_call` tracing span if `enabled`.
+	pub fn with_call_spans(mut self, enabled: bool) -> Self {
+		self.call_spans = enabled;
+		self
+	}
+
+	/// Returns the notification to send once the connection is closed, which closes its subscriptions.
+	pub fn close_notify(&self) -> Arc<Notify> {
+		self.close_notify.clone()
+	}
+
+	/// Close the [`ConnectionState`] of the connection, once it's closed.
+	pub fn close(&self) {
+		self.connection_state.close();
+	}
+
+	/// Dispatch the message `data`, a single call or a batch, to `methods`.
+	///
+	/// The synchronous calls are answered right away, otherwise the future answering the calls is returned, to be
+	/// driven to completion by the transport.
+	pub fn dispatch(&self, methods: &Methods, data: &[u8]) -> Option<BoxFuture<'static, ()>> {
+		let middleware = &self.middleware;
+		let request_start = middleware.on_request();
+
+		match data.first() {
+			Some(b'{') => match serde_json::from_slice::<Request>(data) {
+				Ok(req) => {
+					tracing::debug!("recv method call={}", req.method);
+					tracing::trace!("recv: req={:?}", req);
+
+					match self.execute(methods, req, &self.sink, request_start) {
+						Some(call) => {
+							let middleware = middleware.clone();
+							Some(
+								async move {
+									call.await;
+									middleware.on_response(request_start);
+								}
+								.boxed(),
+							)
+						}
+						None => {
+							middleware.on_response(request_start);
+							None
+						}
+					}
+				}
+				Err(_) => {
+					let (id, code) = prepare_error(data);
+					self.sink.send_error(id, code.into());
+					middleware.on_response(request_start);
+					None
+				}
+			},
+			Some(b'[') => match serde_json::from_slice::<Vec<Request>>(data) {
+				Ok(batch) if !batch.is_empty() => {
+					tracing::debug!("recv batch len={}", batch.len());
+					tracing::trace!("recv: batch={:?}", batch);
+
+					// Batch responses must be sent back as a single message so we read the results from each
+					// request in the batch and read the results off of a new channel, `rx_batch`, and then send the
+					// complete batch response back to the client.
+					let (sink_batch, mut rx_batch) = self.sink.batch_sink();
+					let calls: Vec<_> = batch
+						.into_iter()
+						.filter_map(|req| self.execute(methods, req, &sink_batch, request_start))
+						.collect();
+					drop(sink_batch);
+
+					let sink = self.sink.clone();
+					let middleware = middleware.clone();
+					Some(
+						async move {
+							join_all(calls).await;
+							rx_batch.close();
+							let results = collect_batch_response(rx_batch).await;
+
+							if let Err(err) = sink.send_raw(results) {
+								tracing::error!("Error sending batch response to the client: {:?}", err)
+							} else {
+								middleware.on_response(request_start);
+							}
+						}
+						.boxed(),
+					)
+				}
+				Ok(_) => {
+					self.sink.send_error(Id::Null, ErrorCode::InvalidRequest.into());
+					middleware.on_response(request_start);
+					None
+				}
+				Err(_) => {
+					let (id, code) = prepare_error(data);
+					self.sink.send_error(id, code.into());
+					middleware.on_response(request_start);
+					None
+				}
+			},
+			_ => {
+				self.sink.send_error(Id::Null, ErrorCode::ParseError.into());
+				None
+			}
+		}
+	}
+
+	/// Resolve the method `name` if the connection can call it, wrapped in the async middleware and call span.
+	fn resolve<'a>(&self, methods: &'a Methods, name: &str) -> Option<(&'static str, Cow<'a, MethodCallback>)> {
+		if !self.method_filter.as_ref().is_none_or(|filter| filter.allows(name, &self.details)) {
+			return None;
+		}
+		let (name, mut method) = methods.resolve(name)?;
+		if let Some(async_middleware) = &self.async_middleware {
+			method = Cow::Owned(method.with_async_middleware(name, async_middleware.clone(), self.details.clone()));
+		}
+		if self.call_spans {
+			method = Cow::Owned(method.with_call_span(name, self.conn_id));
+		}
+		Some((name, method))
+	}
+
+	/// Execute the call `req`, responding on `sink`. Returns the future of the call if it's asynchronous.
+	fn execute(
+		&self,
+		methods: &Methods,
+		req: Request,
+		sink: &MethodSink,
+		request_start: M::Instant,
+	) -> Option<BoxFuture<'static, ()>> {
+		let conn_id = self.conn_id;
+		let middleware = &self.middleware;
+		let id = req.id.clone();
+		let params = Params::new(req.params.map(|params| params.get()));
+		let echo_sink = sink.with_echoed_params(req.params);
+		let sink = echo_sink.as_ref().unwrap_or(sink);
+
+		middleware.on_call(&req.method, req.params, &self.details);
+
+		let (name, method) = match self.resolve(methods, &req.method) {
+			Some(resolved) => resolved,
+			None => {
+				sink.send_error(req.id, ErrorCode::MethodNotFound.into());
+				return None;
+			}
+		};
+		if !self.budget.try_spend(&method) {
+			sink.send_error(req.id, budget_exceeded());
+			middleware.on_result(name, false, request_start);
+			return None;
+		}
+		let guard = match self.resources.as_ref().map(|resources| method.claim(name, resources)).transpose() {
+			Ok(guard) => guard,
+			Err(err) => {
+				tracing::error!("[Methods::execute_with_resources] failed to lock resources: {:?}", err);
+				sink.send_error(req.id, ErrorCode::ServerIsBusy.into());
+				middleware.on_result(name, false, request_start);
+				return None;
+			}
+		};
+
+		match method.inner() {
+			MethodKind::Sync(callback) => {
+				let state = self.connection_state.clone();
+				let result = ConnectionState::sync_scope(state, || (callback)(id, params, sink));
+				middleware.on_result(name, result, request_start);
+				drop(guard);
+				None
+			}
+			MethodKind::Async(callback) => {
+				let sink = sink.clone();
+				let id = id.into_owned();
+				let params = params.into_owned();
+				let call = (callback)(id.clone(), params, sink.clone(), conn_id, guard);
+				let call = methods.cancellable_call(conn_id, id, sink, call);
+				let call = Deadline::scope(req.deadline_ms().map(Deadline::after_millis), call);
+				let call = ConnectionState::scope(self.connection_state.clone(), call);
+				let call = methods.track_call(call);
+				let middleware = middleware.clone();
+
+				Some(
+					async move {
+						let result = call.await;
+						middleware.on_result(name, result, request_start);
+					}
+					.boxed(),
+				)
+			}
+			MethodKind::Subscription(callback) => match &self.async_middleware {
+				Some(async_middleware) => {
+					let async_middleware = async_middleware.clone();
+					let callback = callback.clone();
+					let close_notify = self.close_notify.clone();
+					let id_provider = self.id_provider.clone();
+					let bounded_subscriptions = self.bounded_subscriptions.clone();
+					let state = self.connection_state.clone();
+					let details = self.details.clone();
+					let middleware = middleware.clone();
+					let sink = sink.clone();
+					let id = id.into_owned();
+					let params = params.into_owned();
+
+					Some(
+						async move {
+							let conn_state = ConnState::new(conn_id, close_notify, &*id_provider)
+								.with_bounded_subscriptions(&bounded_subscriptions);
+							let subscribe = |id, params, sink: &MethodSink| {
+								ConnectionState::sync_scope(state, || callback(id, params, sink, conn_state))
+							};
+							let result = subscribe_with_async_middleware(
+								&*async_middleware,
+								name,
+								id,
+								params,
+								&sink,
+								&details,
+								subscribe,
+							)
+							.await;
+							middleware.on_result(name, result, request_start);
+							drop(guard);
+						}
+						.boxed(),
+					)
+				}
+				None => {
+					let conn_state = ConnState::new(conn_id, self.close_notify.clone(), &*self.id_provider)
+						.with_bounded_subscriptions(&self.bounded_subscriptions);
+					let state = self.connection_state.clone();
+					let result = ConnectionState::sync_scope(state, || callback(id, params, sink, conn_state));
+					middleware.on_result(name, result, request_start);
+					drop(guard);
+					None
+				}
+			},
+		}
+	}
+}
+
+/// Limit of `max` subscriptions, reporting them to `middleware` as they are opened and closed.
+fn subscriptions_observed_by<M: Middleware>(middleware: &M, max: u32) -> BoundedSubscriptions {
+	let middleware = middleware.clone();
+	BoundedSubscriptions::new(max).with_observer(move |opened| {
+		if opened {
+			middleware.on_subscribe()
+		} else {
+			middleware.on_unsubscribe()
+		}
+	})
+}
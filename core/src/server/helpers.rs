@@ -25,16 +25,24 @@
 // DEALINGS IN THE SOFTWARE.
 
 use std::io;
+use std::sync::atomic::{AtomicBool, AtomicU16, AtomicU64, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
 
+use crate::middleware::ConnectionDetails;
 use crate::{to_json_raw_value, Error};
 use futures_channel::mpsc;
 use futures_util::StreamExt;
 use jsonrpsee_types::error::{
 	CallError, ErrorCode, ErrorObject, ErrorResponse, CALL_EXECUTION_FAILED_CODE, OVERSIZED_RESPONSE_CODE,
-	OVERSIZED_RESPONSE_MSG, UNKNOWN_ERROR_CODE,
+	OVERSIZED_RESPONSE_MSG, REQUEST_TIMEOUT_CODE, REQUEST_TIMEOUT_MSG, UNKNOWN_ERROR_CODE,
 };
 use jsonrpsee_types::{Id, InvalidRequest, Response};
+use parking_lot::Mutex;
 use serde::Serialize;
+use serde_json::value::RawValue;
+use serde_json::Value as JsonValue;
+use tokio::sync::{Notify, OwnedSemaphorePermit, Semaphore};
 
 /// Bounded writer that allows writing at most `max_len` bytes.
 ///
@@ -81,6 +89,124 @@ impl<'a> io::Write for &'a mut BoundedWriter {
 	}
 }
 
+/// Checks the result of a call before it is sent, returning a description of the mismatch if it doesn't have the
+/// expected shape.
+#[derive(Clone)]
+pub struct ResponseCheck(Arc<CheckFn>);
+
+type CheckFn = dyn Fn(&serde_json::Value) -> Result<(), String> + Send + Sync;
+
+impl ResponseCheck {
+	/// Create a new [`ResponseCheck`].
+	pub fn new(check: impl Fn(&serde_json::Value) -> Result<(), String> + Send + Sync + 'static) -> Self {
+		Self(Arc::new(check))
+	}
+}
+
+impl std::fmt::Debug for ResponseCheck {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.write_str("ResponseCheck")
+	}
+}
+
+/// Decides which methods can be called on a connection, given the method name and the details of the connection.
+///
+/// Calls to filtered methods are answered as if the method didn't exist.
+#[derive(Clone)]
+pub struct MethodFilter(Arc<FilterFn>);
+
+type FilterFn = dyn Fn(&str, &ConnectionDetails) -> bool + Send + Sync;
+
+impl MethodFilter {
+	/// Create a new [`MethodFilter`], `filter` returns `true` for the methods that can be called.
+	pub fn new(filter: impl Fn(&str, &ConnectionDetails) -> bool + Send + Sync + 'static) -> Self {
+		Self(Arc::new(filter))
+	}
+
+	/// Returns whether the method can be called on the connection.
+	pub fn allows(&self, method_name: &str, details: &ConnectionDetails) -> bool {
+		(self.0)(method_name, details)
+	}
+}
+
+impl std::fmt::Debug for MethodFilter {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.write_str("MethodFilter")
+	}
+}
+
+/// Number of messages sent on a [`MethodSink`] and delivered by the transport.
+#[derive(Debug, Default)]
+struct Delivery {
+	enqueued: AtomicU64,
+	delivered: AtomicU64,
+	/// Notified when a message is delivered, making room in the buffer.
+	room: Notify,
+	/// Called when a message is delivered, until they are dropped.
+	listeners: Mutex<Vec<Weak<dyn DeliveryListener>>>,
+}
+
+/// Listener called by the transport after each message it delivered, see [`MethodSink::add_delivery_listener`].
+pub(crate) trait DeliveryListener: Send + Sync {
+	/// Called after a message was delivered to the client.
+	fn on_delivered(&self);
+}
+
+/// Interval at which senders waiting for room in the buffer of a [`MethodSink`] check whether it got closed.
+const ROOM_CLOSED_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// What to do with the messages sent synchronously on a [`MethodSink`] whose buffer is full, see
+/// [`MethodSink::with_buffer_capacity`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BufferFullPolicy {
+	/// Queue the messages anyway; only the messages sent with [`MethodSink::send`] wait for room in the buffer.
+	///
+	/// The buffer is then a soft limit which the messages sent synchronously can grow past without bound.
+	Queue,
+	/// Close the sink, which disconnects the client too slow to consume its messages.
+	Disconnect,
+}
+
+/// Handle for the transport to report the delivery of the messages sent on a [`MethodSink`],
+/// see [`MethodSink::track_delivery`].
+#[derive(Debug, Clone)]
+pub struct DeliveryCounter(Arc<Delivery>);
+
+impl DeliveryCounter {
+	/// Report that the oldest message not delivered yet was delivered to the client.
+	pub fn mark_delivered(&self) {
+		self.0.delivered.fetch_add(1, Ordering::Relaxed);
+		self.0.room.notify_one();
+
+		let listeners: Vec<_> = {
+			let mut listeners = self.0.listeners.lock();
+			listeners.retain(|listener| listener.strong_count() > 0);
+			listeners.iter().filter_map(Weak::upgrade).collect()
+		};
+		// Called without the lock held, as they may send messages on the sink.
+		for listener in listeners {
+			listener.on_delivered();
+		}
+	}
+}
+
+/// Response with the non-standard `warning` field, see [`MethodSink::with_deprecation_warning`].
+#[derive(Serialize)]
+struct ResponseWithWarning<'a, T> {
+	#[serde(flatten)]
+	response: Response<'a, T>,
+	warning: &'a str,
+}
+
+/// Response parsed to be checked, which unlike [`Response`] tolerates the `warning` field.
+#[cfg(debug_assertions)]
+#[derive(serde::Deserialize)]
+struct CheckedResponse<'a> {
+	result: serde_json::Value,
+	#[serde(borrow)]
+	id: Id<'a>,
+}
+
 /// Sink that is used to send back the result to the server for a specific method.
 #[derive(Clone, Debug)]
 pub struct MethodSink {
@@ -88,33 +214,299 @@ pub struct MethodSink {
 	tx: mpsc::UnboundedSender<String>,
 	/// Max response size in bytes for a executed call.
 	max_response_size: u32,
+	/// Status code hint for the transport, `0` if not set.
+	status_hint: Arc<AtomicU16>,
+	/// Check of the responses sent, only set in debug builds.
+	response_check: Option<ResponseCheck>,
+	/// Delivery of the messages sent, only set if tracked by the transport.
+	delivery: Option<Arc<Delivery>>,
+	/// Max number of messages waiting to be delivered and what to do when it's reached, only set if bounded.
+	buffer: Option<(usize, BufferFullPolicy)>,
+	/// Whether the transport allows deprecation warnings to be attached to the responses.
+	deprecation_warnings: bool,
+	/// Deprecation warning attached to the responses, only set if allowed by the transport.
+	warning: Option<&'static str>,
+	/// Whether the transport allows the params of the calls to be echoed in their error responses.
+	params_echo: bool,
+	/// Params of the call echoed in the error responses, only set if allowed by the transport.
+	echoed_params: Option<Box<RawValue>>,
+	/// Set once the call timed out, after which the messages are discarded. Only set for calls with a timeout.
+	timed_out: Option<Arc<AtomicBool>>,
+	/// Set once a response is sent, only tracked if the caller needs to know whether the call was answered.
+	answered: Option<Arc<AtomicBool>>,
+	/// Whether the close notifications of the subscriptions carry the `subscription_closed` discriminator.
+	close_discriminator: bool,
 }
 
 impl MethodSink {
 	/// Create a new `MethodSink` with unlimited response size
 	pub fn new(tx: mpsc::UnboundedSender<String>) -> Self {
-		MethodSink { tx, max_response_size: u32::MAX }
+		MethodSink {
+			tx,
+			max_response_size: u32::MAX,
+			status_hint: Arc::new(AtomicU16::new(0)),
+			response_check: None,
+			delivery: None,
+			buffer: None,
+			deprecation_warnings: false,
+			warning: None,
+			params_echo: false,
+			echoed_params: None,
+			timed_out: None,
+			answered: None,
+			close_discriminator: false,
+		}
 	}
 
 	/// Create a new `MethodSink` with a limited response size
 	pub fn new_with_limit(tx: mpsc::UnboundedSender<String>, max_response_size: u32) -> Self {
-		MethodSink { tx, max_response_size }
+		MethodSink {
+			tx,
+			max_response_size,
+			status_hint: Arc::new(AtomicU16::new(0)),
+			response_check: None,
+			delivery: None,
+			buffer: None,
+			deprecation_warnings: false,
+			warning: None,
+			params_echo: false,
+			echoed_params: None,
+			timed_out: None,
+			answered: None,
+			close_discriminator: false,
+		}
+	}
+
+	/// Track the delivery of the messages sent on this sink and its clones, returning the handle for the transport
+	/// to report each message it delivered to the client, in order.
+	///
+	/// This lets subscriptions limit the number of their notifications waiting to be delivered, see
+	/// [`RpcModule::register_subscription_with_queue_limit`](crate::server::rpc_module::RpcModule::register_subscription_with_queue_limit).
+	pub fn track_delivery(&mut self) -> DeliveryCounter {
+		let delivery = self.delivery.get_or_insert_with(Default::default);
+		DeliveryCounter(delivery.clone())
+	}
+
+	/// Call `listener` after each message delivered by the transport, as long as it's alive. Does nothing if delivery
+	/// is not tracked.
+	pub(crate) fn add_delivery_listener(&self, listener: Weak<dyn DeliveryListener>) {
+		if let Some(delivery) = &self.delivery {
+			delivery.listeners.lock().push(listener);
+		}
+	}
+
+	/// Returns the number of messages delivered by the transport, `None` if delivery is not tracked.
+	pub(crate) fn delivered(&self) -> Option<u64> {
+		self.delivery.as_ref().map(|delivery| delivery.delivered.load(Ordering::Relaxed))
+	}
+
+	/// Apply backpressure once `capacity` messages sent on this sink and its clones are waiting to be delivered.
+	///
+	/// The messages sent with [`MethodSink::send`] wait for room in the buffer, whereas `policy` tells what to do
+	/// with the messages sent synchronously while the buffer is full. The channel of the sink stays unbounded, so
+	/// only [`BufferFullPolicy::Disconnect`] keeps the messages waiting to be delivered under `capacity`.
+	///
+	/// The transport must report the delivery of the messages, see [`MethodSink::track_delivery`], otherwise the
+	/// buffer is never considered full; the WebSocket and IPC servers do so.
+	pub fn with_buffer_capacity(mut self, capacity: usize, policy: BufferFullPolicy) -> Self {
+		self.buffer = Some((capacity, policy));
+		self
+	}
+
+	/// Returns the number of messages waiting to be delivered by the transport, `None` if delivery is not tracked.
+	pub fn buffered(&self) -> Option<u64> {
+		self.delivery.as_ref().map(|delivery| {
+			let delivered = delivery.delivered.load(Ordering::Relaxed);
+			delivery.enqueued.load(Ordering::Relaxed).saturating_sub(delivered)
+		})
+	}
+
+	fn is_buffer_full(&self) -> bool {
+		match (self.buffer, self.buffered()) {
+			(Some((capacity, _)), Some(buffered)) => buffered >= capacity as u64,
+			_ => false,
+		}
+	}
+
+	/// Wait until there's room for a message in the buffer, or the sink is closed.
+	pub(crate) async fn wait_for_room(&self) {
+		let delivery = match (&self.buffer, &self.delivery) {
+			(Some(_), Some(delivery)) => delivery,
+			_ => return,
+		};
+
+		while self.is_buffer_full() && !self.is_closed() {
+			// Each delivery wakes up a waiting sender, but check regularly whether the sink got closed meanwhile.
+			let _ = tokio::time::timeout(ROOM_CLOSED_CHECK_INTERVAL, delivery.room.notified()).await;
+		}
+	}
+
+	/// Apply the [`BufferFullPolicy`] to a message about to be sent synchronously.
+	fn check_buffer(&self) {
+		if let Some((capacity, BufferFullPolicy::Disconnect)) = self.buffer {
+			if self.is_buffer_full() && !self.is_closed() {
+				tracing::warn!("Closing the sink of a client too slow to consume its messages, buffer of {}", capacity);
+				self.close();
+			}
+		}
+	}
+
+	/// Send a raw message like [`MethodSink::send_raw`], returning its sequence number if delivery is tracked.
+	pub(crate) fn send_raw_tracked(&self, raw_json: String) -> Result<Option<u64>, mpsc::TrySendError<String>> {
+		self.check_buffer();
+		self.enqueue(raw_json)
+	}
+
+	fn enqueue(&self, raw_json: String) -> Result<Option<u64>, mpsc::TrySendError<String>> {
+		if self.is_timed_out() {
+			tracing::debug!("Discarding message of a call which timed out");
+			return Ok(None);
+		}
+		let seq = self.next_seq();
+		self.tx.unbounded_send(raw_json).map(|()| seq)
+	}
+
+	/// Send a raw JSON-RPC message to the client once there's room for it in the buffer, see
+	/// [`MethodSink::with_buffer_capacity`]. If the buffer is not bounded, the message is sent right away.
+	///
+	/// Like [`MethodSink::send_raw`], this does not verify the validity of the JSON being sent.
+	pub async fn send(&self, raw_json: String) -> Result<(), Error> {
+		self.wait_for_room().await;
+		self.enqueue(raw_json).map(|_| ()).map_err(|err| Error::Internal(err.into_send_error()))
+	}
+
+	fn next_seq(&self) -> Option<u64> {
+		self.delivery.as_ref().map(|delivery| delivery.enqueued.fetch_add(1, Ordering::Relaxed) + 1)
+	}
+
+	/// Returns a `MethodSink` sending on the same channel, which panics if a response fails the given check.
+	///
+	/// The check is only run in debug builds, in release builds this returns the sink unchanged.
+	pub fn with_response_check(&self, check: ResponseCheck) -> Self {
+		let mut sink = self.clone();
+		if cfg!(debug_assertions) {
+			sink.response_check = Some(check);
+		}
+		sink
+	}
+
+	/// Returns a `MethodSink` sending on the same channel, which discards the messages once `timed_out` is set.
+	///
+	/// This lets a call which timed out keep running, like a blocking method, without sending its response after
+	/// the timeout error.
+	pub(crate) fn with_timeout_flag(&self, timed_out: Arc<AtomicBool>) -> Self {
+		let mut sink = self.clone();
+		sink.timed_out = Some(timed_out);
+		sink
+	}
+
+	/// Returns a `MethodSink` sending on the same channel, along with a flag set once a response (or error response)
+	/// is sent on it or its clones, to tell whether the call was answered.
+	pub(crate) fn with_answered_flag(&self) -> (Self, Arc<AtomicBool>) {
+		let mut sink = self.clone();
+		let answered = sink.answered.get_or_insert_with(Default::default).clone();
+		(sink, answered)
+	}
+
+	fn is_timed_out(&self) -> bool {
+		self.timed_out.as_ref().is_some_and(|timed_out| timed_out.load(Ordering::Relaxed))
 	}
 
 	/// Returns whether this channel is closed without needing a context.
 	pub fn is_closed(&self) -> bool {
-		self.tx.is_closed()
+		self.tx.is_closed() || self.is_timed_out()
+	}
+
+	/// Set a hint of the HTTP status code to respond with.
+	///
+	/// It's up to the transport to use it, the HTTP server reads it for single calls whereas the WebSocket server
+	/// ignores it.
+	pub fn set_status_hint(&self, status: u16) {
+		self.status_hint.store(status, Ordering::Relaxed);
+	}
+
+	/// Get the status code hint if one was set.
+	pub fn status_hint(&self) -> Option<u16> {
+		match self.status_hint.load(Ordering::Relaxed) {
+			0 => None,
+			status => Some(status),
+		}
 	}
 
 	/// Send a JSON-RPC response to the client. If the serialization of `result` exceeds `max_response_size`,
 	/// an error will be sent instead.
 	pub fn send_response(&self, id: Id, result: impl Serialize) -> bool {
+		match self.response_frame(id, result) {
+			Ok(json) => self.send_frame(json).is_ok(),
+			Err(Some(json)) => {
+				let _ = self.send_frame(json);
+				false
+			}
+			Err(None) => false,
+		}
+	}
+
+	/// Send a JSON-RPC response to the client like [`MethodSink::send_response`], but returns the message that was
+	/// sent; either the response or the error sent instead if the response couldn't be serialized.
+	///
+	/// This is useful for logging the exact messages sent to the client.
+	pub fn send_response_with_frame(&self, id: Id, result: impl Serialize) -> Result<String, Error> {
+		let json = match self.response_frame(id, result) {
+			Ok(json) | Err(Some(json)) => json,
+			Err(None) => return Err(Error::Custom("Failed to serialize the response".into())),
+		};
+		self.send_frame(json.clone())?;
+		Ok(json)
+	}
+
+	/// Send a JSON-RPC error to the client
+	pub fn send_error(&self, id: Id, error: ErrorObject) -> bool {
+		if let Some(json) = error_frame(id, error) {
+			let _ = self.send_frame(json);
+		}
+
+		false
+	}
+
+	/// Helper for sending the general purpose `Error` as a JSON-RPC errors to the client
+	pub fn send_call_error(&self, id: Id, err: Error) -> bool {
+		if let Some(json) = self.call_error_frame(id, err) {
+			let _ = self.send_frame(json);
+		}
+
+		false
+	}
+
+	/// Send the general purpose `Error` as a JSON-RPC error to the client like [`MethodSink::send_call_error`],
+	/// but returns the message that was sent.
+	pub fn send_call_error_with_frame(&self, id: Id, err: Error) -> Result<String, Error> {
+		let json = self
+			.call_error_frame(id, err)
+			.ok_or_else(|| Error::Custom("Failed to serialize the error response".into()))?;
+		self.send_frame(json.clone())?;
+		Ok(json)
+	}
+
+	/// Serialize the response to a call.
+	///
+	/// If that fails, the serialized error response to send instead is returned as the error,
+	/// or `None` if that couldn't be serialized either.
+	fn response_frame(&self, id: Id, result: impl Serialize) -> Result<String, Option<String>> {
 		let mut writer = BoundedWriter::new(self.max_response_size as usize);
 
-		let json = match serde_json::to_writer(&mut writer, &Response::new(result, id.clone())) {
+		let response = Response::new(result, id.clone());
+		let serialized = match self.warning {
+			Some(warning) => serde_json::to_writer(&mut writer, &ResponseWithWarning { response, warning }),
+			None => serde_json::to_writer(&mut writer, &response),
+		};
+
+		match serialized {
 			Ok(_) => {
 				// Safety - serde_json does not emit invalid UTF-8.
-				unsafe { String::from_utf8_unchecked(writer.into_bytes()) }
+				let json = unsafe { String::from_utf8_unchecked(writer.into_bytes()) };
+				#[cfg(debug_assertions)]
+				self.check_response(&json);
+				Ok(json)
 			}
 			Err(err) => {
 				tracing::error!("Error serializing response: {:?}", err);
@@ -126,61 +518,156 @@ impl MethodSink {
 						message: OVERSIZED_RESPONSE_MSG.into(),
 						data: data.as_deref(),
 					};
-					return self.send_error(id, err);
+					Err(error_frame(id, err))
 				} else {
-					return self.send_error(id, ErrorCode::InternalError.into());
+					Err(error_frame(id, ErrorCode::InternalError.into()))
 				}
 			}
-		};
+		}
+	}
 
-		if let Err(err) = self.tx.unbounded_send(json) {
-			tracing::error!("Error sending response to the client: {:?}", err);
-			false
-		} else {
-			true
+	/// Allow deprecation warnings to be attached to the responses sent on this sink and its clones, see
+	/// [`MethodSink::with_deprecation_warning`].
+	///
+	/// This is opt-in for the transports, since the `warning` field is not part of the JSON-RPC specification and
+	/// is rejected by strict clients.
+	pub fn with_deprecation_warnings(&self) -> Self {
+		let mut sink = self.clone();
+		sink.deprecation_warnings = true;
+		sink
+	}
+
+	/// Serialize the `subscription_closed` discriminator in the close notifications of the subscriptions opened with
+	/// this sink and its clones, see [`SubscriptionClosed::with_discriminator`](crate::error::SubscriptionClosed::with_discriminator).
+	///
+	/// This is opt-in for the transports, since the field is rejected by clients denying unknown fields.
+	pub fn with_close_discriminator(&self) -> Self {
+		let mut sink = self.clone();
+		sink.close_discriminator = true;
+		sink
+	}
+
+	/// Returns whether the close notifications of the subscriptions carry the `subscription_closed` discriminator.
+	pub(crate) fn close_discriminator(&self) -> bool {
+		self.close_discriminator
+	}
+
+	/// Returns a `MethodSink` sending on the same channel, which attaches the given deprecation notice as the
+	/// `warning` field of the responses it sends.
+	///
+	/// The notice is only attached if the transport allowed it with [`MethodSink::with_deprecation_warnings`],
+	/// otherwise this returns the sink unchanged.
+	pub fn with_deprecation_warning(&self, warning: &'static str) -> Self {
+		let mut sink = self.clone();
+		if sink.deprecation_warnings {
+			sink.warning = Some(warning);
 		}
+		sink
 	}
 
-	/// Send a JSON-RPC error to the client
-	pub fn send_error(&self, id: Id, error: ErrorObject) -> bool {
-		let json = match serde_json::to_string(&ErrorResponse::new(error, id)) {
-			Ok(json) => json,
-			Err(err) => {
-				tracing::error!("Error serializing error message: {:?}", err);
+	/// Returns a `MethodSink` with the same settings collecting the responses of the calls of a batch on a channel
+	/// of its own, to be sent back as a single message with [`collect_batch_response`].
+	pub fn batch_sink(&self) -> (Self, mpsc::UnboundedReceiver<String>) {
+		let (tx, rx) = mpsc::unbounded();
+		let mut sink = MethodSink::new_with_limit(tx, self.max_response_size);
+		sink.response_check = self.response_check.clone();
+		sink.deprecation_warnings = self.deprecation_warnings;
+		sink.params_echo = self.params_echo;
+		sink.close_discriminator = self.close_discriminator;
+		(sink, rx)
+	}
 
-				return false;
-			}
-		};
+	/// Returns a `MethodSink` sending on the same channel, which is allowed to echo the params of the calls in their
+	/// error responses, see [`MethodSink::with_echoed_params`].
+	pub fn with_params_echo(&self) -> Self {
+		let mut sink = self.clone();
+		sink.params_echo = true;
+		sink
+	}
 
-		if let Err(err) = self.tx.unbounded_send(json) {
-			tracing::error!("Could not send error response to the client: {:?}", err)
+	/// Returns a `MethodSink` sending on the same channel, which attaches the given params of a call to the error
+	/// responses sent by [`MethodSink::send_call_error`] as the `params` member of the error `data`:
+	///
+	/// ```json
+	/// {"jsonrpc":"2.0","error":{"code":-32000,"message":"Unknown block","data":{"params":[1234]}},"id":1}
+	/// ```
+	///
+	/// The params are only attached to errors without `data` or with an object `data` not having a `params` member.
+	///
+	/// Returns `None` if the transport didn't allow it with [`MethodSink::with_params_echo`], in which case this sink
+	/// is to be used as is; this avoids copying the params of every call when not echoing them.
+	pub fn with_echoed_params(&self, params: Option<&RawValue>) -> Option<Self> {
+		if !self.params_echo {
+			return None;
 		}
 
-		false
+		let mut sink = self.clone();
+		sink.echoed_params = Some(match params {
+			Some(params) => params.to_owned(),
+			None => RawValue::from_string("null".into()).expect("Valid JSON; qed"),
+		});
+		Some(sink)
 	}
 
-	/// Helper for sending the general purpose `Error` as a JSON-RPC errors to the client
-	pub fn send_call_error(&self, id: Id, err: Error) -> bool {
+	/// Run the response check on a serialized response, if any.
+	#[cfg(debug_assertions)]
+	fn check_response(&self, json: &str) {
+		if let Some(ResponseCheck(check)) = &self.response_check {
+			let response: CheckedResponse =
+				serde_json::from_str(json).expect("Serialized by MethodSink just before; qed");
+			if let Err(mismatch) = check(&response.result) {
+				tracing::error!("Response to call {:?} failed the check: {}", response.id, mismatch);
+				panic!("Response to call {:?} failed the check: {}", response.id, mismatch);
+			}
+		}
+	}
+
+	/// Serialize the general purpose `Error` as a JSON-RPC error response.
+	fn call_error_frame(&self, id: Id, err: Error) -> Option<String> {
 		let (code, message, data) = match err {
 			Error::Call(CallError::InvalidParams(e)) => (ErrorCode::InvalidParams, e.to_string(), None),
-			Error::Call(CallError::Failed(e)) => {
-				(ErrorCode::ServerError(CALL_EXECUTION_FAILED_CODE), e.to_string(), None)
-			}
+			Error::Call(CallError::Failed(e)) => match e.downcast_ref::<serde_json::Error>() {
+				Some(e) => serde_error_details(e),
+				None => (ErrorCode::ServerError(CALL_EXECUTION_FAILED_CODE), e.to_string(), None),
+			},
 			Error::Call(CallError::Custom { code, message, data }) => (code.into(), message, data),
+			Error::ParseError(e) => serde_error_details(&e),
+			Error::RequestTimeout => {
+				(ErrorCode::ServerError(REQUEST_TIMEOUT_CODE), REQUEST_TIMEOUT_MSG.to_owned(), None)
+			}
+			Error::WithStatusHint(status, err) => {
+				self.set_status_hint(status);
+				return self.call_error_frame(id, *err);
+			}
 			// This should normally not happen because the most common use case is to
 			// return `Error::Call` in `register_async_method`.
 			e => (ErrorCode::ServerError(UNKNOWN_ERROR_CODE), e.to_string(), None),
 		};
 
+		let data = match &self.echoed_params {
+			Some(params) => echo_params(data, params),
+			None => data,
+		};
 		let err = ErrorObject { code, message: message.into(), data: data.as_deref() };
 
-		self.send_error(id, err)
+		error_frame(id, err)
+	}
+
+	fn send_frame(&self, json: String) -> Result<(), Error> {
+		if let Some(answered) = &self.answered {
+			answered.store(true, Ordering::Relaxed);
+		}
+		self.check_buffer();
+		self.enqueue(json).map(|_| ()).map_err(|err| {
+			tracing::error!("Could not send message to the client: {:?}", err);
+			Error::Internal(err.into_send_error())
+		})
 	}
 
 	/// Send a raw JSON-RPC message to the client, `MethodSink` does not check verify the validity
 	/// of the JSON being sent.
 	pub fn send_raw(&self, raw_json: String) -> Result<(), mpsc::TrySendError<String>> {
-		self.tx.unbounded_send(raw_json)
+		self.send_raw_tracked(raw_json).map(|_| ())
 	}
 
 	/// Close the channel for any further messages.
@@ -189,6 +676,69 @@ impl MethodSink {
 	}
 }
 
+/// Details of a (de)serialization failure in a method call, reported in the `data` of the error.
+///
+/// Only the kind of failure, its position and the name of the field involved are reported, never the
+/// value that failed to (de)serialize as it may contain data the client is not supposed to see.
+#[derive(Serialize)]
+struct SerdeErrorDetails<'a> {
+	category: &'static str,
+	line: usize,
+	column: usize,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	field: Option<&'a str>,
+}
+
+fn serde_error_details(err: &serde_json::Error) -> (ErrorCode, String, Option<Box<serde_json::value::RawValue>>) {
+	let category = match err.classify() {
+		serde_json::error::Category::Io => "io",
+		serde_json::error::Category::Syntax => "syntax",
+		serde_json::error::Category::Data => "data",
+		serde_json::error::Category::Eof => "eof",
+	};
+	// Serde reports missing, unknown and duplicate fields as "... field `name`...".
+	let msg = err.to_string();
+	let field = msg.split_once("field `").and_then(|(_, rest)| rest.split_once('`')).map(|(field, _)| field);
+
+	let details = SerdeErrorDetails { category, line: err.line(), column: err.column(), field };
+	let message = match field {
+		Some(field) => format!("Serialization failed: {} error at field `{}`", category, field),
+		None => format!("Serialization failed: {} error at line {} column {}", category, err.line(), err.column()),
+	};
+
+	(ErrorCode::ServerError(CALL_EXECUTION_FAILED_CODE), message, to_json_raw_value(&details).ok())
+}
+
+/// Add the params of a call to the `data` of its error, see [`MethodSink::with_echoed_params`].
+fn echo_params(data: Option<Box<RawValue>>, params: &RawValue) -> Option<Box<RawValue>> {
+	let mut object = match &data {
+		None => serde_json::Map::new(),
+		Some(raw) => match serde_json::from_str(raw.get()) {
+			Ok(JsonValue::Object(object)) if !object.contains_key("params") => object,
+			_ => return data,
+		},
+	};
+
+	match serde_json::from_str(params.get()) {
+		Ok(params) => {
+			object.insert("params".into(), params);
+			to_json_raw_value(&object).ok()
+		}
+		Err(_) => data,
+	}
+}
+
+/// Serialize a JSON-RPC error response, returns `None` if the serialization failed.
+fn error_frame(id: Id, error: ErrorObject) -> Option<String> {
+	match serde_json::to_string(&ErrorResponse::new(error, id)) {
+		Ok(json) => Some(json),
+		Err(err) => {
+			tracing::error!("Error serializing error message: {:?}", err);
+			None
+		}
+	}
+}
+
 /// Figure out if this is a sufficiently complete request that we can extract an [`Id`] out of, or just plain
 /// unparseable garbage.
 pub fn prepare_error(data: &[u8]) -> (Id<'_>, ErrorCode) {
@@ -198,6 +748,75 @@ pub fn prepare_error(data: &[u8]) -> (Id<'_>, ErrorCode) {
 	}
 }
 
+/// Called with `true` when a subscription is opened and with `false` when it's closed.
+type SubscriptionObserver = Arc<dyn Fn(bool) + Send + Sync>;
+
+/// Limits the number of subscriptions opened on a connection at the same time.
+#[derive(Clone)]
+pub struct BoundedSubscriptions {
+	permits: Arc<Semaphore>,
+	max: u32,
+	observer: Option<SubscriptionObserver>,
+}
+
+impl std::fmt::Debug for BoundedSubscriptions {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("BoundedSubscriptions").field("permits", &self.permits).field("max", &self.max).finish()
+	}
+}
+
+impl BoundedSubscriptions {
+	/// Create a new limit of `max` subscriptions.
+	pub fn new(max: u32) -> Self {
+		// Clamped to the max permits of a semaphore, only reachable on 32-bit targets.
+		let permits = (max as usize).min(usize::MAX >> 3);
+		Self { permits: Arc::new(Semaphore::new(permits)), max, observer: None }
+	}
+
+	/// Call `observer` with `true` when a permit is acquired and with `false` when it's released, for instance to
+	/// count the subscriptions opened on the server.
+	pub fn with_observer(mut self, observer: impl Fn(bool) + Send + Sync + 'static) -> Self {
+		self.observer = Some(Arc::new(observer));
+		self
+	}
+
+	/// Acquire a permit for a new subscription, released when dropped.
+	///
+	/// Returns `None` if `max` subscriptions hold a permit already.
+	pub fn acquire(&self) -> Option<SubscriptionPermit> {
+		let permit = self.permits.clone().try_acquire_owned().ok()?;
+		if let Some(observer) = &self.observer {
+			observer(true);
+		}
+		Some(SubscriptionPermit { _permit: permit, observer: self.observer.clone() })
+	}
+
+	/// Returns the max number of subscriptions.
+	pub fn max(&self) -> u32 {
+		self.max
+	}
+}
+
+/// Permit of a subscription counted by [`BoundedSubscriptions`].
+pub struct SubscriptionPermit {
+	_permit: OwnedSemaphorePermit,
+	observer: Option<SubscriptionObserver>,
+}
+
+impl std::fmt::Debug for SubscriptionPermit {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("SubscriptionPermit").finish_non_exhaustive()
+	}
+}
+
+impl Drop for SubscriptionPermit {
+	fn drop(&mut self) {
+		if let Some(observer) = &self.observer {
+			observer(false);
+		}
+	}
+}
+
 /// Read all the results of all method calls in a batch request from the ['Stream']. Format the result into a single
 /// `String` appropriately wrapped in `[`/`]`.
 pub async fn collect_batch_response(rx: mpsc::UnboundedReceiver<String>) -> String {
@@ -218,7 +837,22 @@ pub async fn collect_batch_response(rx: mpsc::UnboundedReceiver<String>) -> Stri
 
 #[cfg(test)]
 mod tests {
-	use super::{BoundedWriter, Id, Response};
+	use super::{BoundedSubscriptions, BoundedWriter, BufferFullPolicy, CallError, Error, Id, MethodSink, Response};
+	use futures_channel::mpsc;
+	use futures_util::{FutureExt, StreamExt};
+
+	#[test]
+	fn concurrent_subscriptions_are_bounded() {
+		let bounded = BoundedSubscriptions::new(4);
+		let permits: Vec<_> = std::thread::scope(|scope| {
+			let threads: Vec<_> = (0..16).map(|_| scope.spawn(|| bounded.acquire())).collect();
+			threads.into_iter().filter_map(|thread| thread.join().unwrap()).collect()
+		});
+		assert_eq!(permits.len(), 4);
+
+		drop(permits);
+		assert!(bounded.acquire().is_some());
+	}
 
 	#[test]
 	fn bounded_serializer_work() {
@@ -235,4 +869,114 @@ mod tests {
 		// NOTE: `"` is part of the serialization so 101 characters.
 		assert!(serde_json::to_writer(&mut writer, &"x".repeat(99)).is_err());
 	}
+
+	#[test]
+	fn serde_errors_are_reported_without_values() {
+		#[derive(Debug, serde::Deserialize)]
+		#[allow(dead_code)]
+		struct Account {
+			name: String,
+			balance: u64,
+		}
+
+		let (tx, _rx) = mpsc::unbounded();
+		let sink = MethodSink::new(tx);
+
+		let err = serde_json::from_str::<Account>(r#"{"name":"secret"}"#).unwrap_err();
+		let frame = sink.call_error_frame(Id::Number(1), err.into()).unwrap();
+		assert_eq!(
+			frame,
+			r#"{"jsonrpc":"2.0","error":{"code":-32000,"message":"Serialization failed: data error at field `balance`","data":{"category":"data","line":1,"column":17,"field":"balance"}},"id":1}"#
+		);
+
+		let err = serde_json::from_str::<Account>(r#"{"name":"secret","balance":"secret"}"#).unwrap_err();
+		let frame = sink.call_error_frame(Id::Number(2), CallError::Failed(err.into()).into()).unwrap();
+		assert!(!frame.contains("secret"));
+		assert_eq!(
+			frame,
+			r#"{"jsonrpc":"2.0","error":{"code":-32000,"message":"Serialization failed: data error at line 1 column 35","data":{"category":"data","line":1,"column":35}},"id":2}"#
+		);
+	}
+
+	#[tokio::test]
+	async fn full_buffer_applies_backpressure_or_disconnects() {
+		let (tx, mut rx) = mpsc::unbounded();
+		let mut sink = MethodSink::new(tx).with_buffer_capacity(2, BufferFullPolicy::Queue);
+		let delivered = sink.track_delivery();
+
+		sink.send("1".into()).await.unwrap();
+		sink.send_raw("2".into()).unwrap();
+		assert_eq!(sink.buffered(), Some(2));
+
+		// The buffer is full: synchronous sends are queued anyway whereas asynchronous ones wait for room.
+		sink.send_raw("3".into()).unwrap();
+		let mut waiting = Box::pin(sink.send("4".into()));
+		assert!((&mut waiting).now_or_never().is_none());
+
+		for expected in ["1", "2"] {
+			assert_eq!(rx.next().await.unwrap(), expected);
+			delivered.mark_delivered();
+		}
+		waiting.await.unwrap();
+		assert_eq!(sink.buffered(), Some(2));
+
+		// The client is disconnected when the buffer is full.
+		let sink = sink.with_buffer_capacity(2, BufferFullPolicy::Disconnect);
+		assert!(!sink.send_response(Id::Number(1), "too much"));
+		assert!(sink.is_closed());
+		assert_eq!(rx.next().await.unwrap(), "3");
+		assert_eq!(rx.next().await.unwrap(), "4");
+		assert!(rx.next().await.is_none());
+	}
+
+	#[tokio::test]
+	async fn send_with_frame_returns_sent_message() {
+		let (tx, mut rx) = mpsc::unbounded();
+		let sink = MethodSink::new_with_limit(tx, 100);
+
+		let frame = sink.send_response_with_frame(Id::Number(1), "success").unwrap();
+		assert_eq!(frame, r#"{"jsonrpc":"2.0","result":"success","id":1}"#);
+		assert_eq!(rx.next().await.unwrap(), frame);
+
+		// The oversized error is sent instead.
+		let frame = sink.send_response_with_frame(Id::Number(2), "x".repeat(100)).unwrap();
+		assert!(frame.contains("Exceeded max limit 100"));
+		assert_eq!(rx.next().await.unwrap(), frame);
+
+		let err = Error::Call(CallError::Failed(anyhow::anyhow!("failed")));
+		let frame = sink.send_call_error_with_frame(Id::Number(3), err).unwrap();
+		assert_eq!(frame, r#"{"jsonrpc":"2.0","error":{"code":-32000,"message":"failed"},"id":3}"#);
+		assert_eq!(rx.next().await.unwrap(), frame);
+
+		rx.close();
+		assert!(sink.send_response_with_frame(Id::Number(4), "success").is_err());
+	}
+
+	#[test]
+	fn params_are_echoed_in_call_errors_if_allowed() {
+		let (tx, _rx) = mpsc::unbounded();
+		let sink = MethodSink::new(tx);
+		let params = serde_json::value::RawValue::from_string("[1,\"two\"]".into()).unwrap();
+		assert!(sink.with_echoed_params(Some(&params)).is_none());
+
+		let sink = sink.with_params_echo().with_echoed_params(Some(&params)).unwrap();
+		let frame = sink.call_error_frame(Id::Number(1), Error::Custom("Failed".into())).unwrap();
+		assert_eq!(
+			frame,
+			r#"{"jsonrpc":"2.0","error":{"code":-32001,"message":"Custom error: Failed","data":{"params":[1,"two"]}},"id":1}"#
+		);
+
+		let err = serde_json::from_str::<u64>(r#""two""#).unwrap_err();
+		let frame = sink.call_error_frame(Id::Number(2), err.into()).unwrap();
+		assert_eq!(
+			frame,
+			r#"{"jsonrpc":"2.0","error":{"code":-32000,"message":"Serialization failed: data error at line 1 column 5","data":{"category":"data","column":5,"line":1,"params":[1,"two"]}},"id":2}"#
+		);
+
+		// Data that isn't an object is left untouched.
+		let data = Some(serde_json::value::RawValue::from_string("42".into()).unwrap());
+		let err = CallError::Custom { code: 1, message: "Failed".into(), data };
+		let frame = sink.call_error_frame(Id::Number(3), err.into()).unwrap();
+		assert_eq!(frame, r#"{"jsonrpc":"2.0","error":{"code":1,"message":"Failed","data":42},"id":3}"#);
+	}
 }
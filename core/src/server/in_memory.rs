@@ -0,0 +1,224 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! # In-memory client
+//!
+//! [`InMemoryClientBuilder`](crate::server::in_memory::InMemoryClientBuilder) builds a
+//! [`Client`](crate::client::Client) bound directly to [`Methods`](crate::server::rpc_module::Methods), which
+//! dispatches its calls in-process without any socket, for integration tests and embedded use. The client is a
+//! connection of its own, as if it was connected to a server: its subscriptions get their IDs from the
+//! [`IdProvider`](crate::traits::IdProvider) of the connection, are removed by their unsubscribe calls and are
+//! notified when the client is dropped.
+
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::client::{Client, ClientBuilder, IdKind, TransportReceiverT, TransportSenderT};
+use crate::id_providers::RandomIntegerIdProvider;
+use crate::middleware::{ConnectionDetails, TransportKind};
+use crate::server::dispatch::Dispatcher;
+use crate::server::helpers::MethodSink;
+use crate::server::rpc_module::{ConnectionId, Methods};
+use crate::traits::IdProvider;
+use async_trait::async_trait;
+use futures_channel::mpsc;
+use futures_util::stream::{FuturesUnordered, StreamExt};
+
+/// Builder for a [`Client`] bound to [`Methods`], see the [module documentation](self).
+///
+/// # Examples
+///
+/// ```
+/// use jsonrpsee::core::client::ClientT;
+/// use jsonrpsee::core::server::in_memory::InMemoryClientBuilder;
+/// use jsonrpsee::RpcModule;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let mut module = RpcModule::new(());
+///     module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+///
+///     let client = InMemoryClientBuilder::default().build(module);
+///     let response: String = client.request("say_hello", None).await.unwrap();
+///     assert_eq!(response, "hello");
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct InMemoryClientBuilder {
+	request_timeout: Duration,
+	max_concurrent_requests: usize,
+	max_notifs_per_subscription: usize,
+	id_kind: IdKind,
+	max_subscriptions_per_connection: u32,
+	id_provider: Arc<dyn IdProvider>,
+}
+
+impl Default for InMemoryClientBuilder {
+	fn default() -> Self {
+		Self {
+			request_timeout: Duration::from_secs(60),
+			max_concurrent_requests: 256,
+			max_notifs_per_subscription: 1024,
+			id_kind: IdKind::Number,
+			max_subscriptions_per_connection: 1024,
+			id_provider: Arc::new(RandomIntegerIdProvider),
+		}
+	}
+}
+
+impl InMemoryClientBuilder {
+	/// See documentation [`ClientBuilder::request_timeout`] (default is 60 seconds).
+	pub fn request_timeout(mut self, timeout: Duration) -> Self {
+		self.request_timeout = timeout;
+		self
+	}
+
+	/// See documentation [`ClientBuilder::max_concurrent_requests`] (default is 256).
+	pub fn max_concurrent_requests(mut self, max: usize) -> Self {
+		self.max_concurrent_requests = max;
+		self
+	}
+
+	/// See documentation [`ClientBuilder::max_notifs_per_subscription`] (default is 1024).
+	pub fn max_notifs_per_subscription(mut self, max: usize) -> Self {
+		self.max_notifs_per_subscription = max;
+		self
+	}
+
+	/// See documentation for [`ClientBuilder::id_format`] (default is Number).
+	pub fn id_format(mut self, kind: IdKind) -> Self {
+		self.id_kind = kind;
+		self
+	}
+
+	/// Set the maximum number of subscriptions the client can have open at the same time, like the servers do for
+	/// a connection. Default is 1024.
+	pub fn max_subscriptions_per_connection(mut self, max: u32) -> Self {
+		self.max_subscriptions_per_connection = max;
+		self
+	}
+
+	/// Configure the `subscription ID` provider of the connection.
+	///
+	/// Default: [`RandomIntegerIdProvider`].
+	pub fn set_id_provider<I: IdProvider + 'static>(mut self, id_provider: I) -> Self {
+		self.id_provider = Arc::new(id_provider);
+		self
+	}
+
+	/// Build the client bound to `methods`.
+	///
+	/// ## Panics
+	///
+	/// Panics if being called outside of `tokio` runtime context.
+	pub fn build(self, methods: impl Into<Methods>) -> Client {
+		let (to_methods, requests) = mpsc::unbounded();
+		let (tx, responses) = mpsc::unbounded();
+		// In-memory connections have no address.
+		let details =
+			ConnectionDetails::new(next_conn_id(), SocketAddr::from(([0, 0, 0, 0], 0)), TransportKind::InMemory);
+		let sink = MethodSink::new(tx).with_close_discriminator();
+		let dispatcher = Dispatcher::new(details, sink.clone(), ())
+			.with_id_provider(self.id_provider)
+			.with_max_subscriptions(self.max_subscriptions_per_connection);
+		tokio::spawn(serve(dispatcher, sink, methods.into(), requests));
+
+		ClientBuilder::default()
+			.request_timeout(self.request_timeout)
+			.max_concurrent_requests(self.max_concurrent_requests)
+			.max_notifs_per_subscription(self.max_notifs_per_subscription)
+			.id_format(self.id_kind)
+			.build(Sender(to_methods), Receiver(responses))
+	}
+}
+
+/// ID of the next in-memory connection. Counts down from the maximum to stay clear of the IDs the servers give to
+/// their connections, in case the methods are shared with a server.
+fn next_conn_id() -> ConnectionId {
+	static NEXT: AtomicUsize = AtomicUsize::new(ConnectionId::MAX);
+	NEXT.fetch_sub(1, Ordering::Relaxed)
+}
+
+/// The in-memory connection was closed.
+#[derive(Debug, thiserror::Error)]
+#[error("The in-memory connection is closed")]
+pub(crate) struct Closed;
+
+/// Sending end of the client, to the methods.
+pub(crate) struct Sender(mpsc::UnboundedSender<String>);
+
+/// Receiving end of the client, from the methods.
+pub(crate) struct Receiver(mpsc::UnboundedReceiver<String>);
+
+#[async_trait]
+impl TransportSenderT for Sender {
+	type Error = Closed;
+
+	async fn send(&mut self, msg: String) -> Result<(), Closed> {
+		self.0.unbounded_send(msg).map_err(|_| Closed)
+	}
+
+	async fn close(&mut self) -> Result<(), Closed> {
+		self.0.close_channel();
+		Ok(())
+	}
+}
+
+#[async_trait]
+impl TransportReceiverT for Receiver {
+	type Error = Closed;
+
+	async fn receive(&mut self) -> Result<String, Closed> {
+		self.0.next().await.ok_or(Closed)
+	}
+}
+
+/// Dispatch the messages of the client until it's dropped.
+async fn serve(
+	dispatcher: Dispatcher<()>,
+	sink: MethodSink,
+	methods: Methods,
+	mut requests: mpsc::UnboundedReceiver<String>,
+) {
+	let mut calls = FuturesUnordered::new();
+	loop {
+		tokio::select! {
+			request = requests.next() => match request {
+				Some(request) => calls.extend(dispatcher.dispatch(&methods, request.as_bytes())),
+				None => break,
+			},
+			Some(()) = calls.next(), if !calls.is_empty() => {}
+		}
+	}
+
+	// Nobody is left to read the responses, the running calls are cancelled.
+	drop(calls);
+	dispatcher.close();
+	sink.close();
+	dispatcher.close_notify().notify_one();
+}
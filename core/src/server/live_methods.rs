@@ -0,0 +1,116 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! # Live methods
+//!
+//! The methods a running server dispatches its calls to, which can be swapped or patched without restarting the
+//! server, for instance to roll out a feature or to load a plugin. Each connection picks up the new methods on its
+//! next call, while the calls already being executed complete with the methods they started with.
+
+use std::sync::Arc;
+
+use crate::server::resource_limiting::Resources;
+use crate::server::rpc_module::Methods;
+use crate::Error;
+use tokio::sync::watch;
+
+/// Methods of a running server which can be replaced while it's running, handed out by the server handles.
+#[derive(Debug, Clone)]
+pub struct LiveMethods {
+	tx: Arc<watch::Sender<Methods>>,
+	/// Resources of the server, initialized for the methods swapped in.
+	resources: Resources,
+}
+
+impl LiveMethods {
+	/// Create live methods without any method, using the resources of the server.
+	pub fn new(resources: Resources) -> Self {
+		let (tx, _) = watch::channel(Methods::new());
+		Self { tx: Arc::new(tx), resources }
+	}
+
+	/// Returns the current methods.
+	pub fn current(&self) -> Methods {
+		self.tx.borrow().clone()
+	}
+
+	/// Replace the methods all at once, returning the previous ones.
+	///
+	/// Fails without replacing the methods if their resources can't be initialized.
+	pub fn swap(&self, methods: impl Into<Methods>) -> Result<Methods, Error> {
+		let methods = methods.into().initialize_resources(&self.resources)?;
+		Ok(self.tx.send_replace(methods))
+	}
+
+	/// Patch the current methods with `patch`, for instance to merge a module into them or to remove some methods.
+	///
+	/// The patch is applied to a copy of the current methods, which replaces them only if `patch` succeeds. Other
+	/// swaps and patches wait for this one to complete, so `patch` must not access these live methods itself.
+	pub fn patch<F>(&self, patch: F) -> Result<(), Error>
+	where
+		F: FnOnce(&mut Methods) -> Result<(), Error>,
+	{
+		let mut result = Ok(());
+		self.tx.send_if_modified(|current| {
+			let mut methods = current.clone();
+			match patch(&mut methods).and_then(|()| methods.initialize_resources(&self.resources)) {
+				Ok(methods) => {
+					*current = methods;
+					true
+				}
+				Err(err) => {
+					result = Err(err);
+					false
+				}
+			}
+		});
+		result
+	}
+
+	/// Returns a receiver of the methods for a new connection.
+	pub fn receiver(&self) -> MethodsReceiver {
+		let mut rx = self.tx.subscribe();
+		let methods = rx.borrow_and_update().clone();
+		MethodsReceiver { rx, methods }
+	}
+}
+
+/// The methods of a connection, kept up to date with the [`LiveMethods`] of the server.
+#[derive(Debug)]
+pub struct MethodsReceiver {
+	rx: watch::Receiver<Methods>,
+	methods: Methods,
+}
+
+impl MethodsReceiver {
+	/// Returns the current methods, updated if they were replaced since the last call.
+	pub fn current(&mut self) -> &Methods {
+		if self.rx.has_changed().unwrap_or(false) {
+			self.methods = self.rx.borrow_and_update().clone();
+		}
+		&self.methods
+	}
+}
@@ -26,9 +26,31 @@
 
 //! Shared modules for the JSON-RPC servers.
 
+/// Storage scoped to a connection.
+pub mod connection_state;
+/// Cost budget of the method calls made on a connection.
+pub mod cost_budget;
+/// Deadlines of calls provided by the clients.
+pub mod deadline;
+/// Dispatch of the messages received on a connection to the methods.
+pub mod dispatch;
+/// Utilities for handling async code.
+pub mod future;
 /// Helpers.
 pub mod helpers;
+/// Client bound directly to methods, without any socket.
+#[cfg(feature = "async-client")]
+pub mod in_memory;
+/// Methods of a running server, which can be replaced without restarting it.
+pub mod live_methods;
+/// Rate limiting. Cap how often methods can be called to ensure servers are not flooded.
+pub mod rate_limiting;
+/// Test double recording the calls dispatched to methods.
+pub mod recording;
 /// Resource limiting. Create generic "resources" and configure their limits to ensure servers are not overloaded.
 pub mod resource_limiting;
 /// JSON-RPC "modules" group sets of methods that belong together and handles method/subscription registration.
 pub mod rpc_module;
+/// TLS termination of the servers.
+#[cfg(feature = "tls")]
+pub mod tls;
@@ -0,0 +1,176 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! # Rate Limiting
+//!
+//! Unlike [resource limiting](crate::server::resource_limiting), which caps how many calls run at the same time,
+//! rate limiting caps how often a method can be called, for all the connections of the server.
+//!
+//! Each method rate limited with [`MethodResourcesBuilder::rate_limit`](crate::server::rpc_module::MethodResourcesBuilder::rate_limit)
+//! has a token bucket which holds up to `burst` tokens and is refilled with `per_second` tokens every second. Each
+//! call takes a token from the bucket, and is answered with a `Rate limit exceeded` error with code `-32009` if the
+//! bucket is empty, without being executed. The error tells the client when to try again:
+//!
+//! ```json
+//! {"jsonrpc":"2.0","error":{"code":-32009,"message":"Rate limit exceeded, try again later","data":{"retry_after_ms":250}},"id":1}
+//! ```
+//!
+//! The cost budget of the WebSocket server is the complementary limit: it caps how much a single connection calls,
+//! all methods together, with each call weighted by the
+//! [`cost`](crate::server::rpc_module::MethodResourcesBuilder::cost) of its method. A call to a rate limited method
+//! on a server with a cost budget must pass both, and is rejected by whichever is exceeded first.
+
+use std::time::{Duration, Instant};
+
+use jsonrpsee_types::error::{ErrorCode, ErrorObject, RATE_LIMIT_EXCEEDED_CODE, RATE_LIMIT_EXCEEDED_MSG};
+use serde_json::value::RawValue;
+
+/// Rate limit of the calls to a method.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+	burst: u32,
+	per_second: u32,
+}
+
+impl RateLimit {
+	/// Allow `per_second` calls per second, with bursts of as many calls.
+	///
+	/// ## Panics
+	///
+	/// Panics if `per_second` is zero, which would reject all the calls forever.
+	pub fn per_second(per_second: u32) -> Self {
+		assert!(per_second > 0, "RateLimit::per_second must be greater than zero");
+		Self { burst: per_second, per_second }
+	}
+
+	/// Allow bursts of `burst` calls at once.
+	///
+	/// ## Panics
+	///
+	/// Panics if `burst` is zero, which would reject all the calls forever.
+	pub fn burst(mut self, burst: u32) -> Self {
+		assert!(burst > 0, "RateLimit::burst must be greater than zero");
+		self.burst = burst;
+		self
+	}
+
+	/// Max number of calls that can be made at once, which the bucket starts with.
+	pub fn max_burst(&self) -> u32 {
+		self.burst
+	}
+
+	/// Number of calls allowed per second once the burst is used up.
+	pub fn calls_per_second(&self) -> u32 {
+		self.per_second
+	}
+}
+
+/// Token bucket the calls to a rate limited method take a token from.
+#[derive(Debug)]
+pub(crate) struct TokenBucket {
+	limit: RateLimit,
+	/// Tokens left as of `refilled_at`.
+	available: f64,
+	refilled_at: Instant,
+}
+
+impl TokenBucket {
+	pub(crate) fn new(limit: RateLimit, now: Instant) -> Self {
+		Self { limit, available: limit.burst as f64, refilled_at: now }
+	}
+
+	/// Take a token from the bucket, or return how long to wait until one is available.
+	pub(crate) fn try_take(&mut self, now: Instant) -> Result<(), Duration> {
+		let elapsed = now.saturating_duration_since(self.refilled_at).as_secs_f64();
+		let refilled = self.available + elapsed * self.limit.per_second as f64;
+		self.available = refilled.min(self.limit.burst as f64);
+		self.refilled_at = now;
+
+		if self.available >= 1.0 {
+			self.available -= 1.0;
+			return Ok(());
+		}
+		Err(Duration::from_secs_f64((1.0 - self.available) / self.limit.per_second as f64))
+	}
+}
+
+/// Data of the error answered to the calls exceeding the rate limit, see [`rate_limit_exceeded`].
+pub(crate) fn retry_after_data(retry_after: Duration) -> Box<RawValue> {
+	let retry_after_ms = u64::try_from(retry_after.as_millis()).unwrap_or(u64::MAX);
+	serde_json::value::to_raw_value(&serde_json::json!({ "retry_after_ms": retry_after_ms }))
+		.expect("valid json infallible; qed")
+}
+
+/// Error answered to the calls exceeding the rate limit, with the data built by [`retry_after_data`].
+pub(crate) fn rate_limit_exceeded(data: &RawValue) -> ErrorObject<'_> {
+	ErrorObject {
+		code: ErrorCode::ServerError(RATE_LIMIT_EXCEEDED_CODE),
+		message: RATE_LIMIT_EXCEEDED_MSG.into(),
+		data: Some(data),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{RateLimit, TokenBucket};
+	use std::time::{Duration, Instant};
+
+	#[test]
+	fn bucket_is_emptied_and_refilled_up_to_the_burst() {
+		let start = Instant::now();
+		let mut bucket = TokenBucket::new(RateLimit::per_second(2).burst(3), start);
+
+		assert_eq!(bucket.try_take(start), Ok(()));
+		assert_eq!(bucket.try_take(start), Ok(()));
+		assert_eq!(bucket.try_take(start), Ok(()));
+		assert_eq!(bucket.try_take(start), Err(Duration::from_millis(500)));
+
+		// A quarter of a second refills half a token.
+		let later = start + Duration::from_millis(250);
+		assert_eq!(bucket.try_take(later), Err(Duration::from_millis(250)));
+		let later = later + Duration::from_millis(250);
+		assert_eq!(bucket.try_take(later), Ok(()));
+
+		// Refilling stops at the burst.
+		let much_later = later + Duration::from_secs(60);
+		for _ in 0..3 {
+			assert_eq!(bucket.try_take(much_later), Ok(()));
+		}
+		assert!(bucket.try_take(much_later).is_err());
+	}
+
+	#[test]
+	#[should_panic(expected = "RateLimit::per_second must be greater than zero")]
+	fn zero_calls_per_second_is_rejected() {
+		RateLimit::per_second(0);
+	}
+
+	#[test]
+	#[should_panic(expected = "RateLimit::burst must be greater than zero")]
+	fn zero_burst_is_rejected() {
+		RateLimit::per_second(1).burst(0);
+	}
+}
@@ -0,0 +1,130 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! # Recording dispatches
+//!
+//! [`RecordingMethods`](crate::server::recording::RecordingMethods) is a test double wrapping
+//! [`Methods`](crate::server::rpc_module::Methods), which records every call dispatched through it to be asserted on
+//! once a test scenario ran. Clones share the records, so a clone can be handed to the methods of another module to
+//! record the calls they dispatch downstream.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::server::rpc_module::Methods;
+use crate::traits::ToRpcParams;
+use crate::Error;
+use futures_channel::mpsc;
+use jsonrpsee_types::{Id, Request, Response};
+use parking_lot::Mutex;
+use serde::de::DeserializeOwned;
+use serde_json::Value as JsonValue;
+
+/// A call dispatched through [`RecordingMethods`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct DispatchRecord {
+	/// Name of the method called.
+	pub method: String,
+	/// Params of the call, `null` if the call had no params.
+	pub params: JsonValue,
+	/// Response to the call, either a JSON-RPC response or error object.
+	pub response: JsonValue,
+	/// Time it took to dispatch the call and receive its response.
+	pub duration: Duration,
+}
+
+/// [`Methods`] recording the calls dispatched through it, see the [module documentation](self).
+#[derive(Debug, Clone)]
+pub struct RecordingMethods {
+	methods: Methods,
+	records: Arc<Mutex<Vec<DispatchRecord>>>,
+}
+
+impl RecordingMethods {
+	/// Wrap the given methods to record the calls dispatched to them.
+	pub fn new(methods: impl Into<Methods>) -> Self {
+		Self { methods: methods.into(), records: Default::default() }
+	}
+
+	/// Returns the wrapped methods, calls dispatched to them directly are not recorded.
+	pub fn methods(&self) -> &Methods {
+		&self.methods
+	}
+
+	/// Call a method and record the call, like [`Methods::call`].
+	pub async fn call<Params: ToRpcParams, T: DeserializeOwned>(
+		&self,
+		method: &str,
+		params: Params,
+	) -> Result<T, Error> {
+		let params = params.to_rpc_params()?;
+		let req = Request::new(method.into(), Some(&params), Id::Number(0));
+		let resp = self.dispatch(req).await.0;
+		if let Ok(res) = serde_json::from_str::<Response<T>>(&resp) {
+			return Ok(res.result);
+		}
+		Err(Error::Request(resp))
+	}
+
+	/// Make a request by using raw JSON and record the call, like [`Methods::raw_json_request`].
+	///
+	/// Only the response to a subscription is recorded, not its notifications.
+	pub async fn raw_json_request(&self, call: &str) -> Result<(String, mpsc::UnboundedReceiver<String>), Error> {
+		let req: Request = serde_json::from_str(call)?;
+		Ok(self.dispatch(req).await)
+	}
+
+	/// Returns the calls recorded so far, in the order they completed.
+	pub fn records(&self) -> Vec<DispatchRecord> {
+		self.records.lock().clone()
+	}
+
+	/// Returns the calls recorded so far and clears the records.
+	pub fn take_records(&self) -> Vec<DispatchRecord> {
+		std::mem::take(&mut *self.records.lock())
+	}
+
+	/// Returns the names of the methods called so far, in the order the calls completed.
+	pub fn method_names(&self) -> Vec<String> {
+		self.records.lock().iter().map(|record| record.method.clone()).collect()
+	}
+
+	async fn dispatch(&self, req: Request<'_>) -> (String, mpsc::UnboundedReceiver<String>) {
+		let method = req.method.to_string();
+		let params = match req.params {
+			Some(params) => serde_json::from_str(params.get()).unwrap_or(JsonValue::Null),
+			None => JsonValue::Null,
+		};
+
+		let started_at = Instant::now();
+		let (resp, rx, _) = self.methods.inner_call(req).await;
+		let duration = started_at.elapsed();
+
+		let response = serde_json::from_str(&resp).unwrap_or(JsonValue::Null);
+		self.records.lock().push(DispatchRecord { method, params, response, duration });
+		(resp, rx)
+	}
+}
@@ -48,6 +48,10 @@
 //! Setting the execution cost to `0` equates to the method effectively not being limited by a given resource. Likewise setting the
 //! `capacity` to `0` disables any limiting for a given resource.
 //!
+//! A resource can also be given a capacity per connection with the `register_resource_per_connection` method of the
+//! servers, so that a single connection can't claim all of it. The calls are then checked against both the capacity
+//! of the server and the one of their connection.
+//!
 //! To specify a different than default number of units a method should use, use the `resources` argument in the
 //! `#[method]` attribute:
 //!
@@ -110,8 +114,12 @@ pub type ResourceVec<T> = ArrayVec<T, RESOURCE_COUNT>;
 pub struct Resources {
 	/// Resources currently in use by executing calls. 0 for unused resource kinds.
 	totals: Arc<Mutex<ResourceTable>>,
+	/// Resources currently in use by executing calls of the connection, if scoped to one, see [`Resources::for_connection`].
+	connection_totals: Option<Arc<Mutex<ResourceTable>>>,
 	/// Max capacity for all resource kinds
 	pub capacities: ResourceTable,
+	/// Max capacity for all resource kinds per connection (0 means no limit per connection)
+	pub connection_capacities: ResourceTable,
 	/// Default value for all resource kinds; unless a method has a resource limit defined, this is the cost of a call (0 means no default limit)
 	pub defaults: ResourceTable,
 	/// Labels for every registered resource
@@ -122,6 +130,18 @@ impl Resources {
 	/// Register a new resource kind. Errors if `label` is already registered, or if the total number of
 	/// registered resources would exceed 8.
 	pub fn register(&mut self, label: &'static str, capacity: u16, default: u16) -> Result<(), Error> {
+		self.register_per_connection(label, capacity, 0, default)
+	}
+
+	/// Register a new resource kind like [`Resources::register`], which the calls of a single connection can claim at
+	/// most `connection_capacity` units of at the same time.
+	pub fn register_per_connection(
+		&mut self,
+		label: &'static str,
+		capacity: u16,
+		connection_capacity: u16,
+		default: u16,
+	) -> Result<(), Error> {
 		if self.labels.iter().any(|&l| l == label) {
 			return Err(Error::ResourceNameAlreadyTaken(label));
 		}
@@ -131,21 +151,58 @@ impl Resources {
 		self.labels.try_push(label).map_err(|_| Error::MaxResourcesReached)?;
 
 		self.capacities[idx] = capacity;
+		self.connection_capacities[idx] = connection_capacity;
 		self.defaults[idx] = default;
 
 		Ok(())
 	}
 
+	/// Returns whether any resource kind has a capacity per connection.
+	pub fn has_connection_capacities(&self) -> bool {
+		self.connection_capacities.iter().any(|&capacity| capacity != 0)
+	}
+
+	/// Returns the resources to be used by the calls of a new connection, which share the totals of the server
+	/// but also count the resources claimed on this connection against [`Resources::connection_capacities`].
+	pub fn for_connection(&self) -> Self {
+		let connection_totals = self.has_connection_capacities().then(|| Arc::new(Mutex::new([0; RESOURCE_COUNT])));
+		Self { connection_totals, ..self.clone() }
+	}
+
 	/// Attempt to claim `units` units for each resource, incrementing current totals.
 	/// If successful, returns a [`ResourceGuard`] which decrements the totals by the same
 	/// amounts once dropped.
+	///
+	/// If the resources are scoped to a connection, the units are checked against both the capacities of the
+	/// server and the ones of the connection.
 	pub fn claim(&self, units: ResourceTable) -> Result<ResourceGuard, Error> {
+		let mut connection_totals = self.connection_totals.as_ref().map(|totals| totals.lock());
 		let mut totals = self.totals.lock();
-		let mut sum = *totals;
 
-		for (idx, sum) in sum.iter_mut().enumerate() {
+		let sum = self.add_units(*totals, units, &self.capacities)?;
+		let connection_sum = match connection_totals.as_deref() {
+			Some(connection_totals) => Some(self.add_units(*connection_totals, units, &self.connection_capacities)?),
+			None => None,
+		};
+
+		*totals = sum;
+		if let (Some(connection_totals), Some(connection_sum)) = (connection_totals.as_deref_mut(), connection_sum) {
+			*connection_totals = connection_sum;
+		}
+
+		Ok(ResourceGuard { totals: self.totals.clone(), connection_totals: self.connection_totals.clone(), units })
+	}
+
+	/// Add `units` to `totals`, errors if a capacity would be exceeded. A capacity of `0` means no limit.
+	fn add_units(
+		&self,
+		mut totals: ResourceTable,
+		units: ResourceTable,
+		capacities: &ResourceTable,
+	) -> Result<ResourceTable, Error> {
+		for (idx, sum) in totals.iter_mut().enumerate() {
 			match sum.checked_add(units[idx]) {
-				Some(s) if s <= self.capacities[idx] => *sum = s,
+				Some(s) if capacities[idx] == 0 || s <= capacities[idx] => *sum = s,
 				_ => {
 					let label = self.labels.get(idx).copied().unwrap_or("<UNKNOWN>");
 
@@ -154,9 +211,7 @@ impl Resources {
 			}
 		}
 
-		*totals = sum;
-
-		Ok(ResourceGuard { totals: self.totals.clone(), units })
+		Ok(totals)
 	}
 }
 
@@ -164,11 +219,17 @@ impl Resources {
 #[derive(Debug)]
 pub struct ResourceGuard {
 	totals: Arc<Mutex<ResourceTable>>,
+	connection_totals: Option<Arc<Mutex<ResourceTable>>>,
 	units: ResourceTable,
 }
 
 impl Drop for ResourceGuard {
 	fn drop(&mut self) {
+		if let Some(connection_totals) = &self.connection_totals {
+			for (sum, claimed) in connection_totals.lock().iter_mut().zip(self.units) {
+				*sum -= claimed;
+			}
+		}
 		for (sum, claimed) in self.totals.lock().iter_mut().zip(self.units) {
 			*sum -= claimed;
 		}
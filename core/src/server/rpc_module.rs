@@ -24,29 +24,51 @@
 // IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
+use std::borrow::Cow;
 use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::convert::Infallible;
 use std::fmt::{self, Debug};
 use std::future::Future;
 use std::ops::{Deref, DerefMut};
-use std::sync::Arc;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock, Weak};
+use std::time::{Duration, Instant};
 
 use crate::error::{Error, SubscriptionClosed, SubscriptionClosedReason};
 use crate::id_providers::RandomIntegerIdProvider;
-use crate::server::helpers::MethodSink;
+use crate::middleware::{AsyncMiddleware, ConnectionDetails};
+use crate::server::connection_state::ConnectionState;
+use crate::server::deadline::Deadline;
+use crate::server::helpers::{
+	BoundedSubscriptions, DeliveryCounter, DeliveryListener, MethodSink, ResponseCheck, SubscriptionPermit,
+};
+use crate::server::rate_limiting::{rate_limit_exceeded, retry_after_data, RateLimit, TokenBucket};
 use crate::server::resource_limiting::{ResourceGuard, ResourceTable, ResourceVec, Resources};
+use crate::to_json_raw_value;
 use crate::traits::{IdProvider, ToRpcParams};
+use bytes::Bytes;
 use futures_channel::{mpsc, oneshot};
-use futures_util::future::Either;
+use futures_util::future::{AbortHandle, Abortable, Either};
 use futures_util::pin_mut;
 use futures_util::{future::BoxFuture, FutureExt, Stream, StreamExt};
-use jsonrpsee_types::error::{ErrorCode, CALL_EXECUTION_FAILED_CODE};
+use jsonrpsee_types::error::{
+	CallError, ErrorCode, ErrorObject, ErrorResponse, CALL_CANCELLED_CODE, CALL_CANCELLED_MSG,
+	CALL_EXECUTION_FAILED_CODE, METHOD_DISABLED_CODE, METHOD_DISABLED_MSG, METHOD_MOVED_CODE, METHOD_MOVED_MSG,
+	STREAMING_CALL_IN_PROGRESS_MSG, TOO_MANY_SUBSCRIPTIONS_CODE, TOO_MANY_SUBSCRIPTIONS_MSG,
+};
 use jsonrpsee_types::{
-	Id, Params, Request, Response, SubscriptionId as RpcSubscriptionId, SubscriptionPayload, SubscriptionResponse,
+	Id, InvalidRequest, Notification, Params, Request, Response, StreamChunk, StreamResponse, SubscriptionErrorPayload,
+	SubscriptionErrorResponse, SubscriptionId as RpcSubscriptionId, SubscriptionPayload, SubscriptionResponse,
 };
 use parking_lot::Mutex;
-use rustc_hash::FxHashMap;
-use serde::{de::DeserializeOwned, Serialize};
-use tokio::sync::Notify;
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::value::RawValue;
+use serde_json::Value as JsonValue;
+use tokio::sync::{watch, Notify};
+use tracing::Instrument;
 
 /// A `MethodCallback` is an RPC endpoint, callable with a standard JSON-RPC request,
 /// implemented as a function pointer to a `Fn` function taking four arguments:
@@ -79,6 +101,21 @@ pub struct ConnState<'a> {
 	pub close_notify: Arc<Notify>,
 	/// ID provider.
 	pub id_provider: &'a dyn IdProvider,
+	/// Limit of the subscriptions opened on the connection, unbounded if `None`.
+	bounded_subscriptions: Option<&'a BoundedSubscriptions>,
+}
+
+impl<'a> ConnState<'a> {
+	/// Create the state of the connection `conn_id`, whose subscriptions are unbounded.
+	pub fn new(conn_id: ConnectionId, close_notify: Arc<Notify>, id_provider: &'a dyn IdProvider) -> Self {
+		Self { conn_id, close_notify, id_provider, bounded_subscriptions: None }
+	}
+
+	/// Limit the subscriptions opened on the connection.
+	pub fn with_bounded_subscriptions(mut self, bounded_subscriptions: &'a BoundedSubscriptions) -> Self {
+		self.bounded_subscriptions = Some(bounded_subscriptions);
+		self
+	}
 }
 
 impl<'a> std::fmt::Debug for ConnState<'a> {
@@ -87,7 +124,196 @@ impl<'a> std::fmt::Debug for ConnState<'a> {
 	}
 }
 
-type Subscribers = Arc<Mutex<FxHashMap<SubscriptionKey, (MethodSink, oneshot::Receiver<()>)>>>;
+/// Active subscriptions with the sink, a receiver to detect that the subscription was dropped, the last activity if
+/// idle subscriptions time out and the permit counting the subscription in the limit of its connection.
+type Subscribers = Arc<Mutex<FxHashMap<SubscriptionKey, Subscriber>>>;
+
+type Subscriber = (MethodSink, oneshot::Receiver<()>, Option<LastActivity>, Option<SubscriptionPermit>);
+
+/// The subscriptions which can be reused, by connection and key of their params, see
+/// [`RpcModule::register_reusable_subscription`].
+type ReusableSubscriptions = Arc<Mutex<FxHashMap<ReuseKey, ReusableSubscription>>>;
+
+/// Connection and key of the params of a reusable subscription.
+type ReuseKey = (ConnectionId, String);
+
+/// State of a reusable subscription.
+#[derive(Debug)]
+enum ReusableSubscription {
+	/// Neither accepted nor rejected yet, with the equivalent subscription calls waiting for it.
+	Pending(RpcSubscriptionId<'static>, Vec<(Id<'static>, MethodSink)>),
+	/// Accepted.
+	Active(RpcSubscriptionId<'static>),
+}
+
+/// Time of the last notification sent or client interaction on a subscription.
+type LastActivity = Arc<Mutex<Instant>>;
+
+/// Options of a subscription, set by the different `register_subscription` methods.
+#[derive(Default)]
+struct SubscriptionOptions {
+	/// Key of the params of the subscription calls, to reuse subscriptions.
+	reuse_key: Option<ParamsKey>,
+	/// Time after which idle subscriptions are closed.
+	idle_timeout: Option<Duration>,
+	/// Max number of notifications waiting to be delivered per subscription, and what to do when it's reached.
+	buffer: Option<(usize, LagPolicy)>,
+	/// Time after which subscriptions neither accepted nor rejected are answered with a timeout error.
+	setup_timeout: Option<Duration>,
+}
+
+/// Key of the params of a subscription call, the calls with the same key are equivalent.
+type ParamsKey = Arc<dyn Fn(&Params) -> Option<String> + Send + Sync>;
+
+/// The async calls currently being executed.
+type InFlightCalls = Arc<Mutex<InFlight>>;
+
+/// Abort handles of the async calls currently being executed, keyed by connection and request ID.
+///
+/// The handles are kept with a sequence number, as a client may make several calls with the same request ID.
+#[derive(Debug, Default)]
+struct InFlight {
+	calls: FxHashMap<(ConnectionId, Id<'static>), Vec<(u64, AbortHandle)>>,
+	/// Calls tracked by another set once merged into it, see [`Methods::merge`].
+	merged_into: Option<InFlightCalls>,
+}
+
+/// Sequence number of the next async call tracked, unique across the sets of calls so that they can be merged.
+static NEXT_IN_FLIGHT_SEQ: AtomicU64 = AtomicU64::new(0);
+
+/// Run `f` on the set of calls that `in_flight` was merged into, or `in_flight` itself if it wasn't merged.
+fn with_in_flight_root<R>(in_flight: &InFlightCalls, f: impl FnOnce(&mut InFlight) -> R) -> R {
+	let mut in_flight = in_flight.clone();
+	loop {
+		let mut guard = in_flight.lock();
+		match guard.merged_into.clone() {
+			Some(merged_into) => {
+				drop(guard);
+				in_flight = merged_into;
+			}
+			None => return f(&mut guard),
+		}
+	}
+}
+
+/// Track the calls of `other` in `in_flight`, including the ones made later on.
+fn merge_in_flight(in_flight: &InFlightCalls, other: &InFlightCalls) {
+	let root = |in_flight: &InFlightCalls| {
+		let mut root = in_flight.clone();
+		loop {
+			let merged_into = root.lock().merged_into.clone();
+			match merged_into {
+				Some(merged_into) => root = merged_into,
+				None => return root,
+			}
+		}
+	};
+	let (in_flight, other) = (root(in_flight), root(other));
+	if Arc::ptr_eq(&in_flight, &other) {
+		return;
+	}
+
+	let mut other = other.lock();
+	let calls = std::mem::take(&mut other.calls);
+	other.merged_into = Some(in_flight.clone());
+	let mut in_flight = in_flight.lock();
+	for (key, handles) in calls {
+		in_flight.calls.entry(key).or_default().extend(handles);
+	}
+}
+
+/// Removes an async call from the calls in flight when dropped.
+struct InFlightEntry {
+	in_flight: InFlightCalls,
+	key: (ConnectionId, Id<'static>),
+	seq: u64,
+}
+
+impl Drop for InFlightEntry {
+	fn drop(&mut self) {
+		with_in_flight_root(&self.in_flight, |in_flight| {
+			if let Some(handles) = in_flight.calls.get_mut(&self.key) {
+				handles.retain(|(seq, _)| *seq != self.seq);
+				if handles.is_empty() {
+					in_flight.calls.remove(&self.key);
+				}
+			}
+		});
+	}
+}
+
+/// Number of calls made to each method since the counters were last reset, see [`Methods::call_counters`].
+type CallCounters = Arc<Mutex<FxHashMap<&'static str, u64>>>;
+
+/// Number of async calls currently being executed, see [`Methods::quiesce`].
+#[derive(Debug, Default)]
+struct PendingCalls {
+	count: AtomicUsize,
+	drained: Notify,
+}
+
+/// Counts an async call as pending until dropped.
+struct PendingCall(Arc<PendingCalls>);
+
+impl PendingCall {
+	fn new(pending: Arc<PendingCalls>) -> Self {
+		pending.count.fetch_add(1, Ordering::SeqCst);
+		Self(pending)
+	}
+}
+
+impl Drop for PendingCall {
+	fn drop(&mut self) {
+		if self.0.count.fetch_sub(1, Ordering::SeqCst) == 1 {
+			self.0.drained.notify_waiters();
+		}
+	}
+}
+
+/// Builds the callback of a method on its first call, see [`RpcModule::register_lazy`].
+type LazyFactory = Arc<dyn Fn(&str) -> Option<MethodCallback> + Send + Sync>;
+
+/// Methods registered lazily, keyed by method name.
+#[derive(Clone, Default)]
+struct LazyMethods {
+	methods: Arc<FxHashMap<&'static str, LazyMethod>>,
+	/// Resources to initialize the built callbacks with, set by [`Methods::initialize_resources`].
+	resources: Option<Resources>,
+}
+
+/// Method registered lazily, along with its callback once built.
+#[derive(Clone)]
+struct LazyMethod {
+	/// Name the factory is called with, without the prefix the method was mounted under, if any.
+	factory_name: &'static str,
+	factory: LazyFactory,
+	/// Callback built on the first call, `None` if the factory doesn't know the method.
+	///
+	/// Concurrent first calls to the method wait for the callback to be built, so it's built once.
+	built: Arc<OnceLock<Option<MethodCallback>>>,
+}
+
+impl LazyMethods {
+	fn is_empty(&self) -> bool {
+		self.methods.is_empty()
+	}
+
+	/// Drop the callbacks built so far, for instance to build them again with other resources.
+	fn reset(&mut self) {
+		for method in Arc::make_mut(&mut self.methods).values_mut() {
+			method.built = Default::default();
+		}
+	}
+}
+
+impl Debug for LazyMethods {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let built: Vec<_> =
+			self.methods.iter().filter(|(_, method)| method.built.get().is_some()).map(|(name, _)| name).collect();
+		let names: Vec<_> = self.methods.keys().collect();
+		f.debug_struct("LazyMethods").field("names", &names).field("built", &built).finish()
+	}
+}
 
 /// Represent a unique subscription entry based on [`RpcSubscriptionId`] and [`ConnectionId`].
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
@@ -107,6 +333,28 @@ pub enum MethodKind {
 	Subscription(SubscriptionMethod),
 }
 
+/// The kind of a [`MethodKind`], without the handler.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MethodKindTag {
+	/// Synchronous method handler.
+	Sync,
+	/// Asynchronous method handler.
+	Async,
+	/// Subscription method handler
+	Subscription,
+}
+
+impl MethodKind {
+	/// Returns the kind of the handler.
+	pub fn tag(&self) -> MethodKindTag {
+		match self {
+			MethodKind::Sync(_) => MethodKindTag::Sync,
+			MethodKind::Async(_) => MethodKindTag::Async,
+			MethodKind::Subscription(_) => MethodKindTag::Subscription,
+		}
+	}
+}
+
 /// Information about resources the method uses during its execution. Initialized when the the server starts.
 #[derive(Clone, Debug)]
 enum MethodResources {
@@ -122,6 +370,86 @@ enum MethodResources {
 pub struct MethodCallback {
 	callback: MethodKind,
 	resources: MethodResources,
+	/// Deprecation notice of the method, if deprecated.
+	deprecation: Option<&'static str>,
+	/// Description of the method, if registered from a [`MethodSpec`] with one.
+	description: Option<&'static str>,
+	/// Whether the method is enabled, shared by the clones of the callback.
+	enabled: Enabled,
+	/// Units of the cost budget of the connection used by a call.
+	cost: u32,
+}
+
+/// Cost of the methods unless set with [`MethodResourcesBuilder::cost`].
+pub const DEFAULT_METHOD_COST: u32 = 1;
+
+/// Whether a method is enabled, shared by the clones of its callback.
+#[derive(Clone)]
+enum Enabled {
+	/// Set with [`Methods::set_enabled`].
+	Flag(Arc<AtomicBool>),
+	/// Read from a watched value, see [`RpcModule::bind_enabled`].
+	Watched(Arc<dyn Fn() -> bool + Send + Sync>),
+}
+
+impl Default for Enabled {
+	fn default() -> Self {
+		Enabled::Flag(Arc::new(AtomicBool::new(true)))
+	}
+}
+
+impl Debug for Enabled {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Enabled::Flag(flag) => f.debug_tuple("Flag").field(flag).finish(),
+			Enabled::Watched(_) => f.write_str("Watched"),
+		}
+	}
+}
+
+/// Awaits [`AsyncMiddleware::on_call`], answering the call with the error if it's rejected. Returns whether the call
+/// can be executed.
+async fn before_call(
+	middleware: &dyn AsyncMiddleware,
+	name: &str,
+	id: &Id<'_>,
+	params: &Params<'_>,
+	sink: &MethodSink,
+	details: &ConnectionDetails,
+) -> bool {
+	// The params were parsed as JSON already.
+	let params = params.as_str().and_then(|params| serde_json::from_str::<&RawValue>(params).ok());
+
+	match middleware.on_call(name, params, details).await {
+		Ok(()) => true,
+		Err(err) => {
+			sink.send_call_error(id.clone(), err);
+			middleware.on_result(name, false).await;
+			false
+		}
+	}
+}
+
+/// Executes the callback of a subscribe call to the method `name` with `subscribe` once [`AsyncMiddleware::on_call`]
+/// authorized it, then awaits [`AsyncMiddleware::on_result`]. Returns whether the call succeeded.
+///
+/// Used by the servers, as the callbacks of subscriptions are synchronous and left as they are by
+/// [`MethodCallback::with_async_middleware`].
+pub async fn subscribe_with_async_middleware<'a>(
+	middleware: &dyn AsyncMiddleware,
+	name: &str,
+	id: Id<'a>,
+	params: Params<'a>,
+	sink: &MethodSink,
+	details: &ConnectionDetails,
+	subscribe: impl FnOnce(Id<'a>, Params<'a>, &MethodSink) -> bool,
+) -> bool {
+	if !before_call(middleware, name, &id, &params, sink, details).await {
+		return false;
+	}
+	let result = subscribe(id, params, sink);
+	middleware.on_result(name, result).await;
+	result
 }
 
 /// Result of a method, either direct value or a future of one.
@@ -154,6 +482,141 @@ impl<'a> MethodResourcesBuilder<'a> {
 		self.build.try_push((label, units)).map_err(|_| Error::MaxResourcesReached)?;
 		Ok(self)
 	}
+
+	/// Define how many units of the cost budget of the connection a call to the method uses, [`DEFAULT_METHOD_COST`]
+	/// by default.
+	///
+	/// It's up to the transport to enforce the budget, the WebSocket and IPC servers do so if configured to, whereas
+	/// the HTTP server ignores it since its clients can get a new budget with every connection.
+	pub fn cost(self, units: u32) -> Self {
+		self.callback.cost = units;
+		self
+	}
+
+	/// Abort the calls to the method running for longer than `timeout`, answering them with a `Request timeout` error.
+	///
+	/// This applies to the asynchronous and blocking methods. A blocking method can't be interrupted and runs to
+	/// completion, but its response is discarded. See [`RpcModule::register_pending_subscription_with_timeout`] for
+	/// the setup of subscriptions.
+	///
+	/// Fails with [`Error::TimeoutNotSupported`] if the method is synchronous, as it runs to completion within the
+	/// call, or a subscription.
+	pub fn timeout(self, timeout: Duration) -> Result<Self, Error> {
+		let cb = match self.callback.callback.clone() {
+			MethodKind::Async(cb) => cb,
+			MethodKind::Sync(_) => return Err(Error::TimeoutNotSupported("synchronous")),
+			MethodKind::Subscription(_) => return Err(Error::TimeoutNotSupported("subscription")),
+		};
+		self.callback.callback = MethodKind::Async(Arc::new(move |id, params, sink, conn_id, claimed| {
+			let timed_out = Arc::new(AtomicBool::new(false));
+			let call = (cb)(id.clone(), params, sink.with_timeout_flag(timed_out.clone()), conn_id, claimed);
+			async move {
+				match tokio::time::timeout(timeout, call).await {
+					Ok(result) => result,
+					Err(_) => {
+						tracing::debug!("Call with id={:?} timed out after {:?}", id, timeout);
+						timed_out.store(true, Ordering::Relaxed);
+						sink.send_call_error(id, Error::RequestTimeout)
+					}
+				}
+			}
+			.boxed()
+		}));
+		Ok(self)
+	}
+
+	/// Attach a guard admitting the calls to the method based on their params.
+	///
+	/// The guard runs before the callback, and a call it rejects is answered with the returned error without
+	/// running the callback. Guards attached by successive calls run in order.
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	/// use jsonrpsee::{RpcModule, types::error::{ErrorCode, ErrorObject}};
+	///
+	/// let mut module = RpcModule::new(());
+	/// module
+	///     .register_method("get_logs", |params, _| {
+	///         let (from, to): (u64, u64) = params.parse()?;
+	///         Ok(to - from)
+	///     })
+	///     .unwrap()
+	///     .guard(|params| match params.parse::<(u64, u64)>() {
+	///         Ok((from, to)) if to.saturating_sub(from) > 1_000 => Err(ErrorObject {
+	///             code: ErrorCode::InvalidParams,
+	///             message: "Block range too large".into(),
+	///             data: None,
+	///         }),
+	///         _ => Ok(()),
+	///     });
+	/// ```
+	pub fn guard<G>(self, guard: G) -> Self
+	where
+		G: Fn(&Params) -> Result<(), ErrorObject<'static>> + Send + Sync + 'static,
+	{
+		let guard = Arc::new(guard);
+		self.callback.callback = match self.callback.callback.clone() {
+			MethodKind::Sync(cb) => MethodKind::Sync(Arc::new(move |id, params, sink| match guard(&params) {
+				Ok(()) => (cb)(id, params, sink),
+				Err(err) => sink.send_error(id, err),
+			})),
+			MethodKind::Async(cb) => {
+				MethodKind::Async(Arc::new(move |id, params, sink, conn_id, claimed| match guard(&params) {
+					Ok(()) => (cb)(id, params, sink, conn_id, claimed),
+					Err(err) => futures_util::future::ready(sink.send_error(id, err)).boxed(),
+				}))
+			}
+			MethodKind::Subscription(cb) => {
+				MethodKind::Subscription(Arc::new(move |id, params, sink, conn| match guard(&params) {
+					Ok(()) => (cb)(id, params, sink, conn),
+					Err(err) => sink.send_error(id, err),
+				}))
+			}
+		};
+		self
+	}
+
+	/// Limit how often the method can be called, across all the connections, see
+	/// [`rate_limiting`](crate::server::rate_limiting).
+	///
+	/// The calls exceeding the limit are answered with a `Rate limit exceeded` error telling when to try again,
+	/// without running the callback.
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	/// use jsonrpsee::{RpcModule, core::server::rate_limiting::RateLimit};
+	///
+	/// let mut module = RpcModule::new(());
+	/// module
+	///     .register_method("faucet_drip", |_, _| Ok("dripped"))
+	///     .unwrap()
+	///     .rate_limit(RateLimit::per_second(5).burst(20));
+	/// ```
+	pub fn rate_limit(self, limit: RateLimit) -> Self {
+		let bucket = Arc::new(Mutex::new(TokenBucket::new(limit, Instant::now())));
+		let try_take = move || bucket.lock().try_take(Instant::now()).map_err(retry_after_data);
+		self.callback.callback = match self.callback.callback.clone() {
+			MethodKind::Sync(cb) => MethodKind::Sync(Arc::new(move |id, params, sink| match try_take() {
+				Ok(()) => (cb)(id, params, sink),
+				Err(data) => sink.send_error(id, rate_limit_exceeded(&data)),
+			})),
+			MethodKind::Async(cb) => {
+				MethodKind::Async(Arc::new(move |id, params, sink, conn_id, claimed| match try_take() {
+					Ok(()) => (cb)(id, params, sink, conn_id, claimed),
+					Err(data) => futures_util::future::ready(sink.send_error(id, rate_limit_exceeded(&data))).boxed(),
+				}))
+			}
+			MethodKind::Subscription(cb) => {
+				MethodKind::Subscription(Arc::new(move |id, params, sink, conn| match try_take() {
+					Ok(()) => (cb)(id, params, sink, conn),
+					Err(data) => sink.send_error(id, rate_limit_exceeded(&data)),
+				}))
+			}
+		};
+		self
+	}
 }
 
 impl<'a> Drop for MethodResourcesBuilder<'a> {
@@ -163,18 +626,39 @@ impl<'a> Drop for MethodResourcesBuilder<'a> {
 }
 
 impl MethodCallback {
-	fn new_sync(callback: SyncMethod) -> Self {
-		MethodCallback { callback: MethodKind::Sync(callback), resources: MethodResources::Uninitialized([].into()) }
+	/// Create a new synchronous method callback, which does not use any resources.
+	pub fn new_sync(callback: SyncMethod) -> Self {
+		MethodCallback {
+			callback: MethodKind::Sync(callback),
+			resources: MethodResources::Uninitialized([].into()),
+			deprecation: None,
+			description: None,
+			enabled: Enabled::default(),
+			cost: DEFAULT_METHOD_COST,
+		}
 	}
 
-	fn new_async(callback: AsyncMethod<'static>) -> Self {
-		MethodCallback { callback: MethodKind::Async(callback), resources: MethodResources::Uninitialized([].into()) }
+	/// Create a new asynchronous method callback, which does not use any resources.
+	pub fn new_async(callback: AsyncMethod<'static>) -> Self {
+		MethodCallback {
+			callback: MethodKind::Async(callback),
+			resources: MethodResources::Uninitialized([].into()),
+			deprecation: None,
+			description: None,
+			enabled: Enabled::default(),
+			cost: DEFAULT_METHOD_COST,
+		}
 	}
 
-	fn new_subscription(callback: SubscriptionMethod) -> Self {
+	/// Create a new subscription method callback, which does not use any resources.
+	pub fn new_subscription(callback: SubscriptionMethod) -> Self {
 		MethodCallback {
 			callback: MethodKind::Subscription(callback),
 			resources: MethodResources::Uninitialized([].into()),
+			deprecation: None,
+			description: None,
+			enabled: Enabled::default(),
+			cost: DEFAULT_METHOD_COST,
 		}
 	}
 
@@ -191,6 +675,161 @@ impl MethodCallback {
 	pub fn inner(&self) -> &MethodKind {
 		&self.callback
 	}
+
+	/// Returns the kind of the callback.
+	pub fn kind(&self) -> MethodKindTag {
+		self.callback.tag()
+	}
+
+	/// Returns the units of the cost budget of the connection a call to the method uses.
+	pub fn cost(&self) -> u32 {
+		self.cost
+	}
+
+	/// Returns the deprecation notice of the method, `None` if it's not deprecated.
+	pub fn deprecation(&self) -> Option<&'static str> {
+		self.deprecation
+	}
+
+	/// Returns the description of the method, if it was registered from a [`MethodSpec`] with one.
+	pub fn description(&self) -> Option<&'static str> {
+		self.description
+	}
+
+	/// Returns whether the method is enabled, see [`Methods::set_enabled`] and [`RpcModule::bind_enabled`].
+	pub fn is_enabled(&self) -> bool {
+		match &self.enabled {
+			Enabled::Flag(flag) => flag.load(Ordering::Relaxed),
+			Enabled::Watched(enabled) => enabled(),
+		}
+	}
+
+	/// Returns the callback to dispatch a call to: a clone of this one if the method is enabled, or one answering
+	/// with a `Method temporarily unavailable` error if it's disabled.
+	fn for_dispatch(&self) -> Cow<'_, MethodCallback> {
+		if self.is_enabled() {
+			return Cow::Borrowed(self);
+		}
+
+		let mut disabled = self.clone();
+		disabled.callback = MethodKind::Sync(Arc::new(|id, _, sink| {
+			let err = ErrorObject {
+				code: ErrorCode::ServerError(METHOD_DISABLED_CODE),
+				message: METHOD_DISABLED_MSG.into(),
+				data: None,
+			};
+			sink.send_error(id, err)
+		}));
+		Cow::Owned(disabled)
+	}
+
+	/// Returns the callback of the method `name` awaiting the hooks of the [`AsyncMiddleware`] around its execution,
+	/// for calls made on the connection with the given `details`.
+	///
+	/// Synchronous methods are turned into asynchronous ones. The callbacks of subscriptions are left as they are,
+	/// the servers execute them with [`subscribe_with_async_middleware`].
+	pub fn with_async_middleware(
+		&self,
+		name: &'static str,
+		middleware: Arc<dyn AsyncMiddleware>,
+		details: ConnectionDetails,
+	) -> MethodCallback {
+		let details = Arc::new(details);
+		let mut wrapped = self.clone();
+		wrapped.callback = match self.callback.clone() {
+			MethodKind::Sync(cb) => MethodKind::Async(Arc::new(move |id, params, sink, _, claimed| {
+				let cb = cb.clone();
+				let middleware = middleware.clone();
+				let details = details.clone();
+				async move {
+					let _claimed = claimed;
+					if !before_call(&*middleware, name, &id, &params, &sink, &details).await {
+						return false;
+					}
+					let result = (cb)(id, params, &sink);
+					middleware.on_result(name, result).await;
+					result
+				}
+				.boxed()
+			})),
+			MethodKind::Async(cb) => MethodKind::Async(Arc::new(move |id, params, sink, conn_id, claimed| {
+				let cb = cb.clone();
+				let middleware = middleware.clone();
+				let details = details.clone();
+				async move {
+					if !before_call(&*middleware, name, &id, &params, &sink, &details).await {
+						return false;
+					}
+					let result = (cb)(id, params, sink, conn_id, claimed).await;
+					middleware.on_result(name, result).await;
+					result
+				}
+				.boxed()
+			})),
+			MethodKind::Subscription(cb) => MethodKind::Subscription(cb),
+		};
+		wrapped
+	}
+
+	/// Returns the callback of the method `name` executing each call in its own `rpc_call` [`tracing::Span`].
+	///
+	/// The span follows the OpenTelemetry conventions for RPC: it carries the method name, the connection ID and the
+	/// request ID as fields and its `otel.status_code` is set from the result of the call once it's answered. The
+	/// notifications of a subscription are traced in `rpc_notification` spans nested under the span of its call.
+	pub fn with_call_span(&self, name: &'static str, conn_id: ConnectionId) -> MethodCallback {
+		let mut wrapped = self.clone();
+		wrapped.callback = match self.callback.clone() {
+			MethodKind::Sync(cb) => MethodKind::Sync(Arc::new(move |id, params, sink| {
+				let span = call_span(name, conn_id, &id);
+				let result = span.in_scope(|| (cb)(id, params, sink));
+				record_call_status(&span, result);
+				result
+			})),
+			MethodKind::Async(cb) => MethodKind::Async(Arc::new(move |id, params, sink, conn_id, claimed| {
+				let span = call_span(name, conn_id, &id);
+				let fut = (cb)(id, params, sink, conn_id, claimed).instrument(span.clone());
+				async move {
+					let result = fut.await;
+					record_call_status(&span, result);
+					result
+				}
+				.boxed()
+			})),
+			MethodKind::Subscription(cb) => MethodKind::Subscription(Arc::new(move |id, params, sink, conn| {
+				let span = call_span(name, conn.conn_id, &id);
+				let result = span.in_scope(|| (cb)(id, params, sink, conn));
+				record_call_status(&span, result);
+				result
+			})),
+		};
+		wrapped
+	}
+
+	/// Initialize the resources used by the callback. This method has no effect if called more than once.
+	fn initialize_resources(&mut self, method_name: &'static str, resources: &Resources) -> Result<(), Error> {
+		if let MethodResources::Uninitialized(uninit) = &self.resources {
+			let mut map = resources.defaults;
+
+			for &(label, units) in uninit.iter() {
+				let idx = match resources.labels.iter().position(|&l| l == label) {
+					Some(idx) => idx,
+					None => return Err(Error::ResourceNameNotFoundForMethod(label, method_name)),
+				};
+
+				// If resource capacity set to `0`, both for the server and per connection, we ignore the unit value
+				// of the method and set it to `0` as well, effectively making the resource unlimited.
+				if resources.capacities[idx] == 0 && resources.connection_capacities[idx] == 0 {
+					map[idx] = 0;
+				} else {
+					map[idx] = units;
+				}
+			}
+
+			self.resources = MethodResources::Initialized(map);
+		}
+
+		Ok(())
+	}
 }
 
 impl Debug for MethodKind {
@@ -203,10 +842,61 @@ impl Debug for MethodKind {
 	}
 }
 
+/// Creates the span of a call to the method `name`, see [`MethodCallback::with_call_span`].
+fn call_span(name: &'static str, conn_id: ConnectionId, id: &Id) -> tracing::Span {
+	let request_id = serde_json::to_string(id).unwrap_or_default();
+	tracing::info_span!(
+		"rpc_call",
+		otel.name = name,
+		otel.kind = "server",
+		otel.status_code = tracing::field::Empty,
+		rpc.system = "jsonrpc",
+		rpc.method = name,
+		rpc.jsonrpc.request_id = %request_id,
+		conn_id,
+	)
+}
+
+/// Sets the status of the span of a call from whether it was answered successfully.
+fn record_call_status(span: &tracing::Span, success: bool) {
+	span.record("otel.status_code", if success { "OK" } else { "ERROR" });
+}
+
+/// Name of the method serving the OpenRPC document of a server, see [`Methods::register_discover`].
+pub const DISCOVER_METHOD_NAME: &str = "rpc.discover";
+
+/// Version of the OpenRPC specification followed by [`Methods::openrpc_document`].
+const OPENRPC_VERSION: &str = "1.2.6";
+
 /// Reference-counted, clone-on-write collection of synchronous and asynchronous methods.
 #[derive(Default, Debug, Clone)]
 pub struct Methods {
 	callbacks: Arc<FxHashMap<&'static str, MethodCallback>>,
+	/// In-flight async calls, only tracked if cancellation is enabled.
+	in_flight: Option<InFlightCalls>,
+	/// Calls made to each method, only counted if call counters are enabled.
+	call_counters: Option<CallCounters>,
+	/// Methods built on their first call.
+	lazy: LazyMethods,
+	/// Method names of the subscriptions, keyed by subscribe method name.
+	subscriptions: Arc<FxHashMap<&'static str, SubscriptionTriple>>,
+	/// Active subscriptions, keyed by subscribe method name.
+	subscribers: Arc<FxHashMap<&'static str, Subscribers>>,
+	/// Method each alias was registered for, keyed by alias.
+	aliases: Arc<FxHashMap<&'static str, &'static str>>,
+	/// Async calls being executed, shared by the clones of these methods.
+	pending: Arc<PendingCalls>,
+}
+
+/// Method names of a publish/subscribe interface.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SubscriptionTriple {
+	/// Name of the method to call to initiate a subscription.
+	pub subscribe: &'static str,
+	/// Name of the method used in the notifications of the subscription.
+	pub notification: &'static str,
+	/// Name of the method to call to terminate a subscription.
+	pub unsubscribe: &'static str,
 }
 
 impl Methods {
@@ -215,8 +905,8 @@ impl Methods {
 		Self::default()
 	}
 
-	fn verify_method_name(&mut self, name: &'static str) -> Result<(), Error> {
-		if self.callbacks.contains_key(name) {
+	fn verify_method_name(&self, name: &str) -> Result<(), Error> {
+		if self.callbacks.contains_key(name) || self.lazy.methods.contains_key(name) {
 			return Err(Error::MethodAlreadyRegistered(name.into()));
 		}
 
@@ -230,6 +920,9 @@ impl Methods {
 		name: &'static str,
 		callback: MethodCallback,
 	) -> Result<&mut MethodCallback, Error> {
+		if self.lazy.methods.contains_key(name) {
+			return Err(Error::MethodAlreadyRegistered(name.into()));
+		}
 		match self.mut_callbacks().entry(name) {
 			Entry::Occupied(_) => Err(Error::MethodAlreadyRegistered(name.into())),
 			Entry::Vacant(vacant) => Ok(vacant.insert(callback)),
@@ -241,26 +934,13 @@ impl Methods {
 		let callbacks = self.mut_callbacks();
 
 		for (&method_name, callback) in callbacks.iter_mut() {
-			if let MethodResources::Uninitialized(uninit) = &callback.resources {
-				let mut map = resources.defaults;
-
-				for &(label, units) in uninit.iter() {
-					let idx = match resources.labels.iter().position(|&l| l == label) {
-						Some(idx) => idx,
-						None => return Err(Error::ResourceNameNotFoundForMethod(label, method_name)),
-					};
-
-					// If resource capacity set to `0`, we ignore the unit value of the method
-					// and set it to `0` as well, effectively making the resource unlimited.
-					if resources.capacities[idx] == 0 {
-						map[idx] = 0;
-					} else {
-						map[idx] = units;
-					}
-				}
+			callback.initialize_resources(method_name, resources)?;
+		}
 
-				callback.resources = MethodResources::Initialized(map);
-			}
+		// Lazy methods are initialized when built, drop the ones built so far without resources.
+		if !self.lazy.is_empty() {
+			self.lazy.reset();
+			self.lazy.resources = Some(resources.clone());
 		}
 
 		Ok(self)
@@ -273,13 +953,44 @@ impl Methods {
 
 	/// Merge two [`Methods`]'s by adding all [`MethodCallback`]s from `other` into `self`.
 	/// Fails if any of the methods in `other` is present already.
+	///
+	/// If cancellation is enabled on `other` but not on `self`, `self` takes over the in-flight calls of `other`.
+	/// The same goes for the call counters. If it's enabled on both, the calls of `other` are tracked by `self` from
+	/// then on, so that the cancel methods registered on either cancel the calls made to both.
 	pub fn merge(&mut self, other: impl Into<Methods>) -> Result<(), Error> {
 		let mut other = other.into();
 
-		for name in other.callbacks.keys() {
+		for name in other.callbacks.keys().chain(other.lazy.methods.keys()) {
 			self.verify_method_name(name)?;
 		}
 
+		match (&self.in_flight, other.in_flight.take()) {
+			(Some(in_flight), Some(other)) => merge_in_flight(in_flight, &other),
+			(None, other) => self.in_flight = other,
+			(Some(_), None) => {}
+		}
+
+		if self.call_counters.is_none() {
+			self.call_counters = other.call_counters.take();
+		}
+
+		if !other.subscriptions.is_empty() {
+			let subscriptions = Arc::make_mut(&mut self.subscriptions);
+			subscriptions.extend(other.subscriptions.iter().map(|(&name, &triple)| (name, triple)));
+			let subscribers = Arc::make_mut(&mut self.subscribers);
+			subscribers.extend(other.subscribers.iter().map(|(&name, subscribers)| (name, subscribers.clone())));
+		}
+
+		if !other.aliases.is_empty() {
+			Arc::make_mut(&mut self.aliases).extend(other.aliases.iter().map(|(&alias, &name)| (alias, name)));
+		}
+
+		if !other.lazy.is_empty() {
+			let lazy = Arc::make_mut(&mut self.lazy.methods);
+			lazy.extend(other.lazy.methods.iter().map(|(&name, method)| (name, method.clone())));
+			self.lazy.reset();
+		}
+
 		let callbacks = self.mut_callbacks();
 
 		for (name, callback) in other.mut_callbacks().drain() {
@@ -289,20 +1000,149 @@ impl Methods {
 		Ok(())
 	}
 
+	/// Merge two [`Methods`]'s like [`Methods::merge`], but mounts the methods of `other` under `prefix`, for
+	/// instance `admin_` to add `admin_peers` for the method `peers` of `other`.
+	///
+	/// The subscribe and unsubscribe methods of the subscriptions are prefixed alike, as are the methods registered
+	/// with [`RpcModule::register_lazy`]. The method names used in the notifications of the subscriptions are kept
+	/// as they are. Fails if any of the prefixed methods is present already.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use jsonrpsee::RpcModule;
+	///
+	/// let mut admin = RpcModule::new(());
+	/// admin.register_method("peers", |_, _| Ok(0_u32)).unwrap();
+	///
+	/// let mut module = RpcModule::new(());
+	/// module.merge_with_prefix("admin_", admin).unwrap();
+	/// assert!(module.method("admin_peers").is_some());
+	/// assert!(module.method("peers").is_none());
+	/// ```
+	pub fn merge_with_prefix(&mut self, prefix: &str, other: impl Into<Methods>) -> Result<(), Error> {
+		let other = other.into();
+		// Verified before interning the prefixed names, to not keep the names of a merge that fails.
+		for name in other.callbacks.keys().chain(other.lazy.methods.keys()) {
+			self.verify_method_name(&format!("{}{}", prefix, name))?;
+		}
+		self.merge(other.with_prefix(prefix))
+	}
+
+	/// Rename all methods by prepending `prefix` to their names.
+	fn with_prefix(mut self, prefix: &str) -> Self {
+		let prefixed = |name: &str| intern_method_name(format!("{}{}", prefix, name));
+
+		let callbacks = self.mut_callbacks().drain().map(|(name, callback)| (prefixed(name), callback)).collect();
+		self.callbacks = Arc::new(callbacks);
+
+		if !self.subscriptions.is_empty() {
+			let subscriptions = self
+				.subscriptions
+				.values()
+				.map(|triple| {
+					let triple = SubscriptionTriple {
+						subscribe: prefixed(triple.subscribe),
+						notification: triple.notification,
+						unsubscribe: prefixed(triple.unsubscribe),
+					};
+					(triple.subscribe, triple)
+				})
+				.collect();
+			self.subscriptions = Arc::new(subscriptions);
+			let subscribers =
+				self.subscribers.iter().map(|(&name, subscribers)| (prefixed(name), subscribers.clone())).collect();
+			self.subscribers = Arc::new(subscribers);
+		}
+
+		if !self.aliases.is_empty() {
+			let aliases = self.aliases.iter().map(|(&alias, &name)| (prefixed(alias), prefixed(name))).collect();
+			self.aliases = Arc::new(aliases);
+		}
+
+		if !self.lazy.is_empty() {
+			let lazy = self.lazy.methods.iter().map(|(&name, method)| (prefixed(name), method.clone())).collect();
+			self.lazy.methods = Arc::new(lazy);
+			self.lazy.reset();
+		}
+
+		self
+	}
+
 	/// Returns the method callback.
 	pub fn method(&self, method_name: &str) -> Option<&MethodCallback> {
 		self.callbacks.get(method_name)
 	}
 
+	/// Returns the kind of the method, `None` if it's not registered.
+	pub fn method_kind(&self, method_name: &str) -> Option<MethodKindTag> {
+		self.lookup(method_name).map(|(_, callback)| callback.kind())
+	}
+
 	/// Returns the method callback along with its name. The returned name is same as the
 	/// `method_name`, but its lifetime bound is `'static`.
 	pub fn method_with_name(&self, method_name: &str) -> Option<(&'static str, &MethodCallback)> {
 		self.callbacks.get_key_value(method_name).map(|(k, v)| (*k, v))
 	}
 
-	/// Helper to call a method on the `RPC module` without having to spin up a server.
-	///
-	/// The params must be serializable as JSON array, see [`ToRpcParams`] for further documentation.
+	/// Returns the method callback along with its name like [`Methods::method_with_name`], but also looks up
+	/// the methods registered with [`RpcModule::register_lazy`], building the callback on the first call.
+	///
+	/// This is meant to dispatch a call to the method, which is counted if call counters are enabled. The callback is
+	/// borrowed, unless the method is disabled, see [`Methods::set_enabled`].
+	pub fn resolve(&self, method_name: &str) -> Option<(&'static str, Cow<'_, MethodCallback>)> {
+		let (name, callback) = self.lookup(method_name)?;
+
+		if let Some(counters) = &self.call_counters {
+			*counters.lock().entry(name).or_default() += 1;
+		}
+
+		Some((name, callback))
+	}
+
+	fn lookup(&self, method_name: &str) -> Option<(&'static str, Cow<'_, MethodCallback>)> {
+		if let Some((name, callback)) = self.method_with_name(method_name) {
+			return Some((name, callback.for_dispatch()));
+		}
+
+		let (&name, lazy) = self.lazy.methods.get_key_value(method_name)?;
+		let callback = lazy.built.get_or_init(|| {
+			let mut callback = (lazy.factory)(lazy.factory_name)?;
+			if let Some(resources) = &self.lazy.resources {
+				if let Err(err) = callback.initialize_resources(name, resources) {
+					tracing::error!("[Methods::resolve] failed to initialize resources of lazy method: {:?}", err);
+					return None;
+				}
+			}
+			Some(callback)
+		});
+
+		callback.as_ref().map(|callback| (name, callback.for_dispatch()))
+	}
+
+	/// Enable or disable a method, for instance during a maintenance window.
+	///
+	/// Calls to a disabled method are answered with a `Method temporarily unavailable` error instead of being
+	/// executed, while its registration and resources are kept for when it's enabled again. This applies to all
+	/// clones of these methods, including the ones started by a server, and to the aliases of the method.
+	///
+	/// Fails for the methods bound to a watched value with [`RpcModule::bind_enabled`].
+	pub fn set_enabled(&self, method_name: &str, enabled: bool) -> Result<(), Error> {
+		match self.callbacks.get(method_name).map(|callback| &callback.enabled) {
+			Some(Enabled::Flag(flag)) => {
+				flag.store(enabled, Ordering::Relaxed);
+				Ok(())
+			}
+			Some(Enabled::Watched(_)) => {
+				Err(Error::Custom(format!("Method `{}` is enabled by a watched value", method_name)))
+			}
+			None => Err(Error::MethodNotFound(method_name.into())),
+		}
+	}
+
+	/// Helper to call a method on the `RPC module` without having to spin up a server.
+	///
+	/// The params must be serializable as JSON array, see [`ToRpcParams`] for further documentation.
 	///
 	/// Returns the decoded value of the `result field` in JSON-RPC response if succesful.
 	///
@@ -337,6 +1177,125 @@ impl Methods {
 		Err(Error::Request(resp))
 	}
 
+	/// Helper to call a method on the `RPC module` without having to spin up a server like [`Methods::call`], but
+	/// returns the error object of a failed call as a [`CallError::Custom`] instead of the raw JSON response.
+	///
+	/// This lets the code, message and data of the error be checked without parsing the response.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// #[tokio::main]
+	/// async fn main() {
+	///     use jsonrpsee::{core::Error, types::error::CallError, RpcModule};
+	///
+	///     let mut module = RpcModule::new(());
+	///     module.register_method("echo_call", |params, _| {
+	///         params.one::<u64>().map_err(Into::into)
+	///     }).unwrap();
+	///
+	///     let err = module.call_with_error::<_, u64>("echo_call", ["not a number"]).await.unwrap_err();
+	///     assert!(matches!(err, Error::Call(CallError::Custom { code: -32602, .. })));
+	/// }
+	/// ```
+	pub async fn call_with_error<Params: ToRpcParams, T: DeserializeOwned>(
+		&self,
+		method: &str,
+		params: Params,
+	) -> Result<T, Error> {
+		let params = params.to_rpc_params()?;
+		let req = Request::new(method.into(), Some(&params), Id::Number(0));
+		tracing::trace!("[Methods::call_with_error] Calling method: {:?}, params: {:?}", method, params);
+		let (resp, _, _) = self.inner_call(req).await;
+		if let Ok(res) = serde_json::from_str::<Response<T>>(&resp) {
+			return Ok(res.result);
+		}
+		if let Ok(err) = serde_json::from_str::<ErrorResponse>(&resp) {
+			return Err(Error::Call(err.error.into()));
+		}
+		Err(Error::Request(resp))
+	}
+
+	/// Helper to call a method on the `RPC module` without having to spin up a server like [`Methods::call`], also
+	/// returning the kind of the method that handled the call.
+	///
+	/// The method is looked up once, so the kind is the one of the callback that produced the result even if the
+	/// method is replaced concurrently.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// #[tokio::main]
+	/// async fn main() {
+	///     use jsonrpsee::{RpcModule, types::EmptyParams};
+	///     use jsonrpsee::core::server::rpc_module::MethodKindTag;
+	///
+	///     let mut module = RpcModule::new(());
+	///     module.register_async_method("echo_call", |params, _| async move { params.one::<u64>().map_err(Into::into) }).unwrap();
+	///
+	///     let (echo, kind): (u64, _) = module.call_described("echo_call", [1_u64]).await.unwrap();
+	///     assert_eq!((echo, kind), (1, MethodKindTag::Async));
+	/// }
+	/// ```
+	pub async fn call_described<Params: ToRpcParams, T: DeserializeOwned>(
+		&self,
+		method: &str,
+		params: Params,
+	) -> Result<(T, MethodKindTag), Error> {
+		let params = params.to_rpc_params()?;
+		let req = Request::new(method.into(), Some(&params), Id::Number(0));
+		tracing::trace!("[Methods::call_described] Calling method: {:?}, params: {:?}", method, params);
+		let callback = self.resolve(method).map(|(_, callback)| callback);
+		let kind = callback.as_deref().map(MethodCallback::kind);
+		let (tx_sink, rx_sink) = mpsc::unbounded();
+		let (resp, _, _) = self
+			.dispatch_with_sink(req, callback.as_deref(), MethodSink::new(tx_sink), rx_sink, Arc::new(Notify::new()))
+			.await;
+		if let (Ok(res), Some(kind)) = (serde_json::from_str::<Response<T>>(&resp), kind) {
+			return Ok((res.result, kind));
+		}
+		Err(Error::Request(resp))
+	}
+
+	/// Helper to call a method on the `RPC module` without having to spin up a server, for callers that don't know
+	/// the type of the result ahead of time.
+	///
+	/// The params must be serializable as JSON array, see [`ToRpcParams`] for further documentation.
+	///
+	/// Returns the `result` field of the JSON-RPC response as a JSON value if succesful, and the error object of the
+	/// response as a [`CallError::Custom`] otherwise.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// #[tokio::main]
+	/// async fn main() {
+	///     use jsonrpsee::RpcModule;
+	///     use serde_json::json;
+	///
+	///     let mut module = RpcModule::new(());
+	///     module.register_method("echo_call", |params, _| {
+	///         params.one::<u64>().map_err(Into::into)
+	///     }).unwrap();
+	///
+	///     let echo = module.call_value("echo_call", [1_u64]).await.unwrap();
+	///     assert_eq!(echo, json!(1));
+	/// }
+	/// ```
+	pub async fn call_value<Params: ToRpcParams>(&self, method: &str, params: Params) -> Result<JsonValue, Error> {
+		let params = params.to_rpc_params()?;
+		let req = Request::new(method.into(), Some(&params), Id::Number(0));
+		tracing::trace!("[Methods::call_value] Calling method: {:?}, params: {:?}", method, params);
+		let (resp, _, _) = self.inner_call(req).await;
+		if let Ok(res) = serde_json::from_str::<Response<JsonValue>>(&resp) {
+			return Ok(res.result);
+		}
+		if let Ok(err) = serde_json::from_str::<ErrorResponse>(&resp) {
+			return Err(Error::Call(err.error.into()));
+		}
+		Err(Error::Request(resp))
+	}
+
 	/// Make a request (JSON-RPC method call or subscription) by using raw JSON.
 	///
 	/// Returns the raw JSON response to the call and a stream to receive notifications if the call was a subscription.
@@ -371,26 +1330,204 @@ impl Methods {
 		Ok((resp, rx))
 	}
 
+	/// Make a batch request by using raw JSON, like [`Methods::raw_json_request`] for a JSON array of calls.
+	///
+	/// The calls are executed concurrently and the batch response holds their responses in the order of the calls.
+	/// Like the servers, an empty batch is answered with a single `Invalid request` error and each invalid entry with
+	/// its own `Invalid request` error. Notifications aren't answered, the batch response is empty if it only holds
+	/// notifications. Returns the batch response and a stream of the notifications of the subscriptions made in the
+	/// batch.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// #[tokio::main]
+	/// async fn main() {
+	///     use jsonrpsee::RpcModule;
+	///
+	///     let mut module = RpcModule::new(());
+	///     module.register_method("echo_call", |params, _| {
+	///         params.one::<u64>().map_err(Into::into)
+	///     }).unwrap();
+	///
+	///     let batch = r#"[{"jsonrpc":"2.0","method":"echo_call","params":[1],"id":0},{"jsonrpc":"2.0","method":"nope","id":1}]"#;
+	///     let (resp, _) = module.raw_batch_request(batch).await.unwrap();
+	///     assert_eq!(
+	///         resp,
+	///         r#"[{"jsonrpc":"2.0","result":1,"id":0},{"jsonrpc":"2.0","error":{"code":-32601,"message":"Method not found"},"id":1}]"#
+	///     );
+	/// }
+	/// ```
+	pub async fn raw_batch_request(&self, batch: &str) -> Result<(String, impl Stream<Item = String> + Unpin), Error> {
+		tracing::trace!("[Methods::raw_batch_request] {:?}", batch);
+		let batch: Vec<&RawValue> = serde_json::from_str(batch)?;
+		if batch.is_empty() {
+			let resp = serde_json::to_string(&ErrorResponse::new(ErrorCode::InvalidRequest.into(), Id::Null))?;
+			return Ok((resp, futures_util::stream::select_all(Vec::new())));
+		}
+
+		type Notif<'a> = Notification<'a, Option<&'a RawValue>>;
+
+		let results = futures_util::future::join_all(batch.into_iter().map(|entry| async move {
+			if let Ok(req) = serde_json::from_str::<Request>(entry.get()) {
+				let (resp, rx, _) = self.inner_call(req).await;
+				Some((resp, Some(rx)))
+			} else if serde_json::from_str::<Notif>(entry.get()).is_ok() {
+				None
+			} else {
+				let id = serde_json::from_str::<InvalidRequest>(entry.get()).map_or(Id::Null, |req| req.id);
+				let resp = serde_json::to_string(&ErrorResponse::new(ErrorCode::InvalidRequest.into(), id))
+					.expect("valid json infallible; qed");
+				Some((resp, None))
+			}
+		}))
+		.await;
+
+		let (responses, notifications): (Vec<_>, Vec<_>) = results.into_iter().flatten().unzip();
+		let resp = if responses.is_empty() { String::new() } else { format!("[{}]", responses.join(",")) };
+		Ok((resp, futures_util::stream::select_all(notifications.into_iter().flatten())))
+	}
+
+	/// Call a method reusing the buffers of `scratch`, for loops making a large number of calls such as fuzzers and
+	/// benchmarks.
+	///
+	/// The params are read from [`CallScratch::params_mut`] and the call is made without params if it's empty.
+	/// Returns the raw JSON response to the call.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// #[tokio::main]
+	/// async fn main() {
+	///     use jsonrpsee::core::server::rpc_module::CallScratch;
+	///     use jsonrpsee::RpcModule;
+	///     use std::fmt::Write;
+	///
+	///     let mut module = RpcModule::new(());
+	///     module.register_method("echo_call", |params, _| {
+	///         params.one::<u64>().map_err(Into::into)
+	///     }).unwrap();
+	///
+	///     let mut scratch = CallScratch::new();
+	///     for n in 0..3_u64 {
+	///         scratch.reset();
+	///         write!(scratch.params_mut(), "[{}]", n).unwrap();
+	///         let resp = module.call_with_scratch("echo_call", &mut scratch).await.unwrap();
+	///         assert_eq!(resp, format!(r#"{{"jsonrpc":"2.0","result":{},"id":0}}"#, n));
+	///     }
+	/// }
+	/// ```
+	pub async fn call_with_scratch(&self, method: &str, scratch: &mut CallScratch) -> Result<String, Error> {
+		let params = match scratch.params.as_str() {
+			"" => None,
+			params => Some(serde_json::from_str::<&RawValue>(params)?),
+		};
+		let req = Request::new(method.into(), params, Id::Number(0));
+		let rx_sink = match scratch.rx_sink.take() {
+			Some(rx_sink) => rx_sink,
+			// The previous call was dropped before completing.
+			None => {
+				let (tx_sink, rx_sink) = mpsc::unbounded();
+				scratch.sink = MethodSink::new(tx_sink);
+				rx_sink
+			}
+		};
+		let (resp, rx_sink, _) =
+			self.inner_call_with_sink(req, scratch.sink.clone(), rx_sink, scratch.close_notify.clone()).await;
+		scratch.rx_sink = Some(rx_sink);
+		Ok(resp)
+	}
+
+	/// Make a request (JSON-RPC method call or subscription) by using raw JSON bytes, for byte oriented pipelines.
+	///
+	/// Like [`Methods::raw_json_request`] but the request is parsed from `call` without going through a `String`, and
+	/// the response and notifications are returned as [`Bytes`]. They are still serialized to a `String` by the
+	/// [`MethodSink`] first, which is then converted to [`Bytes`], so this doesn't save the allocation of the
+	/// responses.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// #[tokio::main]
+	/// async fn main() {
+	///     use jsonrpsee::RpcModule;
+	///
+	///     let mut module = RpcModule::new(());
+	///     module.register_method("echo_call", |params, _| {
+	///         params.one::<u64>().map_err(Into::into)
+	///     }).unwrap();
+	///
+	///     let (resp, _) = module.call_bytes(br#"{"jsonrpc":"2.0","method":"echo_call","params":[1],"id":0}"#).await.unwrap();
+	///     assert_eq!(&resp[..], br#"{"jsonrpc":"2.0","result":1,"id":0}"#);
+	/// }
+	/// ```
+	pub async fn call_bytes(&self, call: &[u8]) -> Result<(Bytes, impl Stream<Item = Bytes> + Unpin), Error> {
+		tracing::trace!("[Methods::call_bytes] {} bytes", call.len());
+		// The request borrows its method and params from `call`, without going through a `String`.
+		let req: Request = serde_json::from_slice(call)?;
+		let (resp, rx, _) = self.inner_call(req).await;
+		Ok((Bytes::from(resp), rx.map(Bytes::from)))
+	}
+
 	/// Execute a callback.
-	async fn inner_call(&self, req: Request<'_>) -> RawRpcResponse {
-		let (tx_sink, mut rx_sink) = mpsc::unbounded();
-		let sink = MethodSink::new(tx_sink);
+	pub(crate) async fn inner_call(&self, req: Request<'_>) -> RawRpcResponse {
+		let (tx_sink, rx_sink) = mpsc::unbounded();
+		let sink = MethodSink::new(tx_sink).with_close_discriminator();
+		self.inner_call_with_sink(req, sink, rx_sink, Arc::new(Notify::new())).await
+	}
+
+	/// Execute a callback, sending the response and notifications on the given sink.
+	async fn inner_call_with_sink(
+		&self,
+		req: Request<'_>,
+		sink: MethodSink,
+		rx_sink: mpsc::UnboundedReceiver<String>,
+		notify: Arc<Notify>,
+	) -> RawRpcResponse {
+		let callback = self.resolve(&req.method).map(|(_, callback)| callback);
+		self.dispatch_with_sink(req, callback.as_deref(), sink, rx_sink, notify).await
+	}
+
+	/// Execute the given callback, resolved for the call beforehand, sending the response and notifications on the
+	/// given sink.
+	async fn dispatch_with_sink(
+		&self,
+		req: Request<'_>,
+		callback: Option<&MethodCallback>,
+		sink: MethodSink,
+		mut rx_sink: mpsc::UnboundedReceiver<String>,
+		notify: Arc<Notify>,
+	) -> RawRpcResponse {
 		let id = req.id.clone();
 		let params = Params::new(req.params.map(|params| params.get()));
-		let notify = Arc::new(Notify::new());
 
-		let _result = match self.method(&req.method).map(|c| &c.callback) {
-			None => sink.send_error(req.id, ErrorCode::MethodNotFound.into()),
-			Some(MethodKind::Sync(cb)) => (cb)(id, params, &sink),
-			Some(MethodKind::Async(cb)) => (cb)(id.into_owned(), params.into_owned(), sink, 0, None).await,
-			Some(MethodKind::Subscription(cb)) => {
-				let close_notify = notify.clone();
-				let conn_state = ConnState { conn_id: 0, close_notify, id_provider: &RandomIntegerIdProvider };
-				(cb)(id, params, &sink, conn_state)
+		// The sink is dropped at the end of this block so that the channel closes if the callback never responds.
+		let _result = {
+			let sink = sink;
+			match callback.map(|c| &c.callback) {
+				None => sink.send_error(id, ErrorCode::MethodNotFound.into()),
+				Some(MethodKind::Sync(cb)) => (cb)(id, params, &sink),
+				Some(MethodKind::Async(cb)) => {
+					let deadline = req.deadline_ms().map(Deadline::after_millis);
+					let call = Deadline::scope(deadline, (cb)(id.into_owned(), params.into_owned(), sink, 0, None));
+					self.track_call(call).await
+				}
+				Some(MethodKind::Subscription(cb)) => {
+					let close_notify = notify.clone();
+					let conn_state = ConnState::new(0, close_notify, &RandomIntegerIdProvider);
+					(cb)(id, params, &sink, conn_state)
+				}
 			}
 		};
 
-		let resp = rx_sink.next().await.expect("tx and rx still alive; qed");
+		let resp = match rx_sink.next().await {
+			Some(resp) => resp,
+			None => {
+				tracing::error!("Method call with id {:?} finished without a response", req.id);
+				let err = ErrorResponse::new(ErrorCode::InternalError.into(), req.id);
+				serde_json::to_string(&err).expect("Valid JSON; qed")
+			}
+		};
 
 		(resp, rx_sink, notify)
 	}
@@ -416,7 +1553,7 @@ impl Methods {
 	///
 	///     let mut sub = module.subscribe("hi", EmptyParams::new()).await.unwrap();
 	///     // In this case we ignore the subscription ID,
-	///     let (sub_resp, _sub_id) = sub.next::<String>().await.unwrap().unwrap();
+	///     let (sub_resp, _sub_id) = sub.next::<String>().await.into_item().unwrap();
 	///     assert_eq!(&sub_resp, "one answer");
 	/// }
 	/// ```
@@ -424,69 +1561,564 @@ impl Methods {
 		let params = params.to_rpc_params()?;
 		let req = Request::new(sub_method.into(), Some(&params), Id::Number(0));
 		tracing::trace!("[Methods::subscribe] Calling subscription method: {:?}, params: {:?}", sub_method, params);
-		let (response, rx, close_notify) = self.inner_call(req).await;
+		let (tx_sink, rx_sink) = mpsc::unbounded();
+		let mut sink = MethodSink::new(tx_sink).with_close_discriminator();
+		let delivered = sink.track_delivery();
+		let (response, rx, close_notify) = self.inner_call_with_sink(req, sink, rx_sink, Arc::new(Notify::new())).await;
+		delivered.mark_delivered();
 		let subscription_response = serde_json::from_str::<Response<RpcSubscriptionId>>(&response)?;
 		let sub_id = subscription_response.result.into_owned();
 		let close_notify = Some(close_notify);
-		Ok(Subscription { sub_id, rx, close_notify })
+		Ok(Subscription { sub_id, rx, close_notify, delivered })
 	}
 
-	/// Returns an `Iterator` with all the method names registered on this server.
-	pub fn method_names(&self) -> impl Iterator<Item = &'static str> + '_ {
-		self.callbacks.keys().copied()
-	}
-}
+	/// Helper to call a streaming method on the `RPC module` without having to spin up a server.
+	///
+	/// The params must be serializable as JSON array, see [`ToRpcParams`] for further documentation.
+	///
+	/// Returns every chunk sent by the method, in order, once the call completed.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// #[tokio::main]
+	/// async fn main() {
+	///     use jsonrpsee::{RpcModule, types::EmptyParams};
+	///
+	///     let mut module = RpcModule::new(());
+	///     module.register_streaming_method("count", "count_chunk", |_, sink, _| async move {
+	///         for i in 0..3_u64 {
+	///             sink.send(&i)?;
+	///         }
+	///         Ok(())
+	///     }).unwrap();
+	///
+	///     let chunks: Vec<u64> = module.call_streaming("count", EmptyParams::new()).await.unwrap();
+	///     assert_eq!(chunks, vec![0, 1, 2]);
+	/// }
+	/// ```
+	pub async fn call_streaming<Params: ToRpcParams, T: DeserializeOwned>(
+		&self,
+		method: &str,
+		params: Params,
+	) -> Result<Vec<T>, Error> {
+		let params = params.to_rpc_params()?;
+		let req = Request::new(method.into(), Some(&params), Id::Number(0));
+		tracing::trace!("[Methods::call_streaming] Calling method: {:?}, params: {:?}", method, params);
+		let (mut msg, mut rx, _) = self.inner_call(req).await;
+		let mut chunks = Vec::new();
 
-impl<Context> Deref for RpcModule<Context> {
-	type Target = Methods;
+		loop {
+			match StreamSink::parse_message::<T>(&msg) {
+				Some(StreamMessage::Chunk(chunk)) => chunks.push(chunk),
+				Some(StreamMessage::End) => return Ok(chunks),
+				None => return Err(Error::Request(msg)),
+			}
 
-	fn deref(&self) -> &Methods {
-		&self.methods
+			msg = match rx.next().await {
+				Some(msg) => msg,
+				None => return Err(Error::Custom("Stream closed before the call completed".into())),
+			};
+		}
 	}
-}
 
-impl<Context> DerefMut for RpcModule<Context> {
-	fn deref_mut(&mut self) -> &mut Methods {
-		&mut self.methods
-	}
-}
+	/// Remove a method along with its aliases, returning its callback or `None` if it isn't registered.
+	///
+	/// If `method_name` is the subscribe or the unsubscribe method of a subscription, the other one is removed as well
+	/// and its active subscriptions are closed with [`SubscriptionClosedReason::Server`]`("method removed")`.
+	///
+	/// Like registering methods, this doesn't affect the clones of these methods a server was started with. The
+	/// active subscriptions are however shared with them, so they are closed on the server too.
+	pub fn remove_method(&mut self, method_name: &str) -> Option<MethodCallback> {
+		let (name, callback) = self.mut_callbacks().remove_entry(method_name)?;
+		let mut removed = vec![name];
 
-/// Sets of JSON-RPC methods can be organized into a "module"s that are in turn registered on the server or,
-/// alternatively, merged with other modules to construct a cohesive API. [`RpcModule`] wraps an additional context
-/// argument that can be used to access data during call execution.
-#[derive(Debug, Clone)]
-pub struct RpcModule<Context> {
-	ctx: Arc<Context>,
-	methods: Methods,
-}
+		let subscribe = match self.subscriptions.contains_key(name) {
+			true => Some(name),
+			false => {
+				self.subscriptions.values().find(|triple| triple.unsubscribe == name).map(|triple| triple.subscribe)
+			}
+		};
+		if let Some(subscribe) = subscribe {
+			let triple = Arc::make_mut(&mut self.subscriptions).remove(subscribe).expect("Found above; qed");
+			let other = if name == triple.subscribe { triple.unsubscribe } else { triple.subscribe };
+			self.mut_callbacks().remove(other);
+			removed.push(other);
 
-impl<Context> RpcModule<Context> {
-	/// Create a new module with a given shared `Context`.
-	pub fn new(ctx: Context) -> Self {
-		Self { ctx: Arc::new(ctx), methods: Default::default() }
+			if let Some(subscribers) = Arc::make_mut(&mut self.subscribers).remove(triple.subscribe) {
+				close_subscriptions(&subscribers, triple.notification, "method removed");
+			}
+		}
+
+		let stale_aliases: Vec<_> = self
+			.aliases
+			.iter()
+			.filter(|(alias, aliased)| removed.contains(alias) || removed.contains(aliased))
+			.map(|(&alias, _)| alias)
+			.collect();
+		for alias in stale_aliases {
+			Arc::make_mut(&mut self.aliases).remove(alias);
+			self.mut_callbacks().remove(alias);
+		}
+
+		Some(callback)
 	}
 
-	/// Transform a module into an `RpcModule<()>` (unit context).
-	pub fn remove_context(self) -> RpcModule<()> {
-		let mut module = RpcModule::new(());
-		module.methods = self.methods;
-		module
+	/// Returns the method names of the publish/subscribe interface initiated by calling `subscribe_method_name`,
+	/// `None` if it is not the subscribe method of a subscription.
+	pub fn subscription_info(&self, subscribe_method_name: &str) -> Option<SubscriptionTriple> {
+		self.subscriptions.get(subscribe_method_name).copied()
 	}
-}
 
-impl<Context> From<RpcModule<Context>> for Methods {
-	fn from(module: RpcModule<Context>) -> Methods {
-		module.methods
+	/// Returns an `Iterator` with all the method names registered on this server.
+	pub fn method_names(&self) -> impl Iterator<Item = &'static str> + '_ {
+		self.callbacks.keys().copied()
 	}
-}
 
-impl<Context: Send + Sync + 'static> RpcModule<Context> {
-	/// Register a new synchronous RPC method, which computes the response with the given callback.
-	pub fn register_method<R, F>(
-		&mut self,
-		method_name: &'static str,
-		callback: F,
-	) -> Result<MethodResourcesBuilder, Error>
+	/// Report the resources claimed by each method against the capacities of `resources`, as JSON.
+	///
+	/// The report maps each label to its capacity, and each method to the units of every label it claims:
+	///
+	/// ```json
+	/// {
+	///   "capacities": { "cpu": 8, "io": 4 },
+	///   "methods": { "say_hello": { "cpu": 1, "io": 0 } }
+	/// }
+	/// ```
+	///
+	/// Methods not initialized yet are reported with the units they would claim once initialized with
+	/// `resources`, or the error preventing their initialization.
+	pub fn resource_map_json(&self, resources: &Resources) -> JsonValue {
+		let labels = resources.labels.iter().enumerate();
+		let capacities: serde_json::Map<_, _> =
+			labels.clone().map(|(idx, label)| (label.to_string(), resources.capacities[idx].into())).collect();
+
+		let methods: serde_json::Map<_, _> = self
+			.callbacks
+			.iter()
+			.map(|(&name, callback)| {
+				let mut callback = callback.clone();
+				let usage = match (callback.initialize_resources(name, resources), callback.resources) {
+					(Ok(()), MethodResources::Initialized(units)) => JsonValue::Object(
+						labels.clone().map(|(idx, label)| (label.to_string(), units[idx].into())).collect(),
+					),
+					(Err(err), _) => serde_json::json!({ "error": err.to_string() }),
+					(Ok(()), MethodResources::Uninitialized(_)) => unreachable!("Initialized just above; qed"),
+				};
+				(name.to_string(), usage)
+			})
+			.collect();
+
+		serde_json::json!({ "capacities": capacities, "methods": methods })
+	}
+
+	/// Generate an [OpenRPC](https://spec.open-rpc.org) document describing these methods, served by the
+	/// `rpc.discover` method registered with [`Methods::register_discover`].
+	///
+	/// The methods are listed by name with their description and whether they are deprecated. Aliases are listed
+	/// in the `x-aliases` extension field of the method they were registered for, and the subscribe methods of
+	/// subscriptions have an `x-subscription` extension field naming their notification and unsubscribe methods:
+	///
+	/// ```json
+	/// {
+	///   "name": "sub_ticks",
+	///   "params": [],
+	///   "result": { "name": "result", "schema": {} },
+	///   "x-subscription": { "notification": "s_ticks", "unsubscribe": "unsub_ticks" }
+	/// }
+	/// ```
+	///
+	/// The params and result of the methods are not known, so they are listed without schema. The methods
+	/// registered with [`RpcModule::register_lazy`] are not listed.
+	pub fn openrpc_document(&self, title: &str, version: &str) -> JsonValue {
+		let mut aliases: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+		for (&alias, &name) in self.aliases.iter() {
+			aliases.entry(name).or_default().push(alias);
+		}
+
+		let mut names: Vec<_> =
+			self.callbacks.keys().copied().filter(|name| !self.aliases.contains_key(name)).collect();
+		names.sort_unstable();
+
+		let methods: Vec<_> = names
+			.into_iter()
+			.map(|name| {
+				let callback = &self.callbacks[name];
+				let mut method = serde_json::json!({
+					"name": name,
+					"params": [],
+					"result": { "name": "result", "schema": {} },
+				});
+				if let Some(description) = callback.description() {
+					method["description"] = description.into();
+				}
+				if callback.deprecation().is_some() {
+					method["deprecated"] = true.into();
+				}
+				if let Some(aliases) = aliases.get_mut(name) {
+					aliases.sort_unstable();
+					method["x-aliases"] = serde_json::json!(aliases);
+				}
+				if let Some(triple) = self.subscriptions.get(name) {
+					method["x-subscription"] =
+						serde_json::json!({ "notification": triple.notification, "unsubscribe": triple.unsubscribe });
+				}
+				method
+			})
+			.collect();
+
+		serde_json::json!({
+			"openrpc": OPENRPC_VERSION,
+			"info": { "title": title, "version": version },
+			"methods": methods,
+		})
+	}
+
+	/// Register the `rpc.discover` method, returning the [OpenRPC](https://spec.open-rpc.org) document of these
+	/// methods generated by [`Methods::openrpc_document`] with the given title and version of the API.
+	///
+	/// The document is generated when registering, so this should be called once all the other methods are
+	/// registered or merged: the methods added later don't show up in it.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// #[tokio::main]
+	/// async fn main() {
+	///     use jsonrpsee::{RpcModule, types::EmptyParams};
+	///
+	///     let mut module = RpcModule::new(());
+	///     module.register_method("say_hello", |_, _| Ok("lo")).unwrap();
+	///     module.register_alias("hello", "say_hello").unwrap();
+	///     module.register_discover("Greeter", "1.0.0").unwrap();
+	///
+	///     let doc: serde_json::Value = module.call("rpc.discover", EmptyParams::new()).await.unwrap();
+	///     assert_eq!(doc["info"]["title"], "Greeter");
+	///     assert_eq!(doc["methods"][0]["name"], "say_hello");
+	///     assert_eq!(doc["methods"][0]["x-aliases"], serde_json::json!(["hello"]));
+	/// }
+	/// ```
+	pub fn register_discover(&mut self, title: &str, version: &str) -> Result<(), Error> {
+		self.verify_method_name(DISCOVER_METHOD_NAME)?;
+		let document = to_json_raw_value(&self.openrpc_document(title, version))?;
+
+		self.verify_and_insert(
+			DISCOVER_METHOD_NAME,
+			MethodCallback::new_sync(Arc::new(move |id, _, sink| sink.send_response(id, &*document))),
+		)?;
+
+		Ok(())
+	}
+
+	/// Enable counting the calls made to each method, see [`Methods::call_counters`].
+	///
+	/// This has no effect if called more than once.
+	pub fn enable_call_counters(&mut self) {
+		if self.call_counters.is_none() {
+			self.call_counters = Some(Default::default());
+		}
+	}
+
+	/// Returns the number of calls made to each method since the counters were last reset, methods that
+	/// weren't called being left out.
+	///
+	/// This is empty if call counters aren't enabled with [`Methods::enable_call_counters`]. The counters are
+	/// shared by all clones of these methods, including the ones started by a server.
+	pub fn call_counters(&self) -> HashMap<&'static str, u64> {
+		match &self.call_counters {
+			Some(counters) => counters.lock().iter().map(|(&name, &count)| (name, count)).collect(),
+			None => HashMap::new(),
+		}
+	}
+
+	/// Reset the call counters of all methods to zero, for instance after reporting them with
+	/// [`Methods::call_counters`].
+	pub fn reset_counters(&self) {
+		if let Some(counters) = &self.call_counters {
+			counters.lock().clear();
+		}
+	}
+
+	/// Enable cancellation of in-flight async calls, see [`Methods::cancel`].
+	///
+	/// This has no effect if called more than once.
+	pub fn enable_cancellation(&mut self) {
+		if self.in_flight.is_none() {
+			self.in_flight = Some(Default::default());
+		}
+	}
+
+	/// Register a method that cancels an in-flight async call made on the same connection.
+	///
+	/// The method takes the ID of the request to cancel as its only parameter and returns whether a call was
+	/// cancelled. The cancelled call responds with a `Call cancelled` error.
+	///
+	/// Cancellation is bound to these [`Methods`], so register the method on the methods started by the server
+	/// or on a module merged into it.
+	pub fn register_cancel_method(&mut self, method_name: &'static str) -> Result<(), Error> {
+		self.enable_cancellation();
+		let in_flight = self.in_flight.clone().expect("Cancellation enabled above; qed");
+
+		self.verify_and_insert(
+			method_name,
+			MethodCallback::new_async(Arc::new(move |id, params, sink, conn_id, _| {
+				let result = match params.one::<Id>() {
+					Ok(call_id) => {
+						let cancelled = cancel_in_flight(&in_flight, conn_id, call_id);
+						sink.send_response(id, cancelled)
+					}
+					Err(err) => sink.send_call_error(id, err.into()),
+				};
+				futures_util::future::ready(result).boxed()
+			})),
+		)?;
+
+		Ok(())
+	}
+
+	/// Cancel the async call with the given request ID made on the given connection.
+	///
+	/// Returns `true` if the call was found and aborted, in which case a `Call cancelled` error is sent as the
+	/// response to the call. All the calls in flight with this ID are cancelled if the client made several.
+	/// Returns `false` if cancellation is not enabled or no such call is in flight.
+	pub fn cancel(&self, conn_id: ConnectionId, id: Id) -> bool {
+		match self.in_flight.as_ref() {
+			Some(in_flight) => cancel_in_flight(in_flight, conn_id, id),
+			None => false,
+		}
+	}
+
+	/// Make the future of an async call cancellable by [`Methods::cancel`], if cancellation is enabled.
+	///
+	/// This is used by the servers to track the async calls made on a connection.
+	pub fn cancellable_call(
+		&self,
+		conn_id: ConnectionId,
+		id: Id<'static>,
+		sink: MethodSink,
+		call: BoxFuture<'static, bool>,
+	) -> BoxFuture<'static, bool> {
+		let in_flight = match self.in_flight.clone() {
+			Some(in_flight) => in_flight,
+			None => return call,
+		};
+
+		let (handle, registration) = AbortHandle::new_pair();
+		let key = (conn_id, id);
+		let seq = NEXT_IN_FLIGHT_SEQ.fetch_add(1, Ordering::Relaxed);
+		with_in_flight_root(&in_flight, |in_flight| {
+			in_flight.calls.entry(key.clone()).or_default().push((seq, handle))
+		});
+
+		async move {
+			// The call is no longer in flight once it completed or got dropped, for instance with its connection.
+			let entry = InFlightEntry { in_flight, key: key.clone(), seq };
+			let result = Abortable::new(call, registration).await;
+			drop(entry);
+
+			match result {
+				Ok(result) => result,
+				Err(_) => {
+					tracing::debug!("Call with id={:?} on connection {} cancelled", key.1, key.0);
+					let err = ErrorObject {
+						code: ErrorCode::ServerError(CALL_CANCELLED_CODE),
+						message: CALL_CANCELLED_MSG.into(),
+						data: None,
+					};
+					sink.send_error(key.1, err)
+				}
+			}
+		}
+		.boxed()
+	}
+}
+
+impl Methods {
+	/// Count the future of an async call as pending until it completes or is dropped, see [`Methods::quiesce`].
+	///
+	/// This is used by the servers to track the async calls being executed.
+	pub fn track_call<F: Future>(&self, call: F) -> impl Future<Output = F::Output> {
+		let pending = PendingCall::new(self.pending.clone());
+		async move {
+			let _pending = pending;
+			call.await
+		}
+	}
+
+	/// Returns the number of async calls currently being executed on these methods and their clones.
+	pub fn pending_calls(&self) -> usize {
+		self.pending.count.load(Ordering::SeqCst)
+	}
+
+	/// Wait for the async calls being executed to complete, for a graceful shutdown.
+	///
+	/// Resolves to `true` once no async call is being executed on these methods or their clones, for instance the
+	/// methods started by a server, or to `false` if that didn't happen within `timeout`. Synchronous calls complete
+	/// as they are made and are never waited on.
+	///
+	/// This doesn't stop the server from accepting new calls, so stop it first to drain the calls. Subscriptions
+	/// are not waited on either, they are closed when the server is stopped.
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	/// use std::time::Duration;
+	/// use jsonrpsee::{RpcModule, core::server::rpc_module::Methods, ws_server::WsServerBuilder};
+	///
+	/// #[tokio::main]
+	/// async fn main() {
+	///     let server = WsServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+	///     let methods: Methods = RpcModule::new(()).into();
+	///     let handle = server.start(methods.clone()).unwrap();
+	///
+	///     // Later, on shutdown.
+	///     handle.stop().unwrap();
+	///     if !methods.quiesce(Duration::from_secs(10)).await {
+	///         eprintln!("{} calls still in flight", methods.pending_calls());
+	///     }
+	/// }
+	/// ```
+	pub fn quiesce(&self, timeout: Duration) -> impl Future<Output = bool> {
+		let pending = self.pending.clone();
+		let drained = async move {
+			loop {
+				// Created before reading the count to not miss a notification in between.
+				let drained = pending.drained.notified();
+				if pending.count.load(Ordering::SeqCst) == 0 {
+					return;
+				}
+				drained.await;
+			}
+		};
+		tokio::time::timeout(timeout, drained).map(|result| result.is_ok())
+	}
+}
+
+/// Returns the `'static` version of a method name built at runtime, such as a prefixed name.
+///
+/// The names are leaked, but only once each, so that merging the same methods again doesn't leak more memory.
+fn intern_method_name(name: String) -> &'static str {
+	static NAMES: OnceLock<Mutex<FxHashSet<&'static str>>> = OnceLock::new();
+
+	let mut names = NAMES.get_or_init(Default::default).lock();
+	match names.get(name.as_str()) {
+		Some(&name) => name,
+		None => {
+			let name: &'static str = Box::leak(name.into_boxed_str());
+			names.insert(name);
+			name
+		}
+	}
+}
+
+/// Cancel the calls in flight with the given request ID, a client may have made several.
+fn cancel_in_flight(in_flight: &InFlightCalls, conn_id: ConnectionId, id: Id) -> bool {
+	match with_in_flight_root(in_flight, |in_flight| in_flight.calls.remove(&(conn_id, id.into_owned()))) {
+		Some(handles) => {
+			for (_, handle) in handles {
+				handle.abort();
+			}
+			true
+		}
+		None => false,
+	}
+}
+
+/// Buffers reused across calls made with [`Methods::call_with_scratch`], to keep the per call allocations low.
+///
+/// A scratch is meant to be reused by a single loop making one call at a time, and [`CallScratch::reset`] should be
+/// called before preparing the params of the next call. It's not suited for subscriptions, the notifications share
+/// the channel used to receive the responses.
+#[derive(Debug)]
+pub struct CallScratch {
+	params: String,
+	sink: MethodSink,
+	rx_sink: Option<mpsc::UnboundedReceiver<String>>,
+	close_notify: Arc<Notify>,
+}
+
+impl CallScratch {
+	/// Create a new, empty [`CallScratch`].
+	pub fn new() -> Self {
+		let (tx_sink, rx_sink) = mpsc::unbounded();
+		Self {
+			params: String::new(),
+			sink: MethodSink::new(tx_sink),
+			rx_sink: Some(rx_sink),
+			close_notify: Arc::new(Notify::new()),
+		}
+	}
+
+	/// Buffer to write the JSON encoded params of the next call to, keeping its capacity across calls.
+	pub fn params_mut(&mut self) -> &mut String {
+		&mut self.params
+	}
+
+	/// Clear the params and drop any message left over from the previous call.
+	pub fn reset(&mut self) {
+		self.params.clear();
+		if let Some(rx_sink) = self.rx_sink.as_mut() {
+			while rx_sink.try_recv().is_ok() {}
+		}
+	}
+}
+
+impl Default for CallScratch {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<Context> Deref for RpcModule<Context> {
+	type Target = Methods;
+
+	fn deref(&self) -> &Methods {
+		&self.methods
+	}
+}
+
+impl<Context> DerefMut for RpcModule<Context> {
+	fn deref_mut(&mut self) -> &mut Methods {
+		&mut self.methods
+	}
+}
+
+/// Sets of JSON-RPC methods can be organized into a "module"s that are in turn registered on the server or,
+/// alternatively, merged with other modules to construct a cohesive API. [`RpcModule`] wraps an additional context
+/// argument that can be used to access data during call execution.
+#[derive(Debug, Clone)]
+pub struct RpcModule<Context> {
+	ctx: Arc<Context>,
+	methods: Methods,
+}
+
+impl<Context> RpcModule<Context> {
+	/// Create a new module with a given shared `Context`.
+	pub fn new(ctx: Context) -> Self {
+		Self { ctx: Arc::new(ctx), methods: Default::default() }
+	}
+
+	/// Transform a module into an `RpcModule<()>` (unit context).
+	pub fn remove_context(self) -> RpcModule<()> {
+		let mut module = RpcModule::new(());
+		module.methods = self.methods;
+		module
+	}
+}
+
+impl<Context> From<RpcModule<Context>> for Methods {
+	fn from(module: RpcModule<Context>) -> Methods {
+		module.methods
+	}
+}
+
+impl<Context: Send + Sync + 'static> RpcModule<Context> {
+	/// Register a new synchronous RPC method, which computes the response with the given callback.
+	pub fn register_method<R, F>(
+		&mut self,
+		method_name: &'static str,
+		callback: F,
+	) -> Result<MethodResourcesBuilder, Error>
 	where
 		Context: Send + Sync + 'static,
 		R: Serialize,
@@ -581,6 +2213,66 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 		Ok(MethodResourcesBuilder { build: ResourceVec::new(), callback })
 	}
 
+	/// Register a new RPC method with access to the [`ConnectionState`] of the connection the call was made on,
+	/// which computes the response with the given callback.
+	///
+	/// The values stored in the connection state are available to later calls made on the same connection, and are
+	/// dropped when the connection is closed. A call made without a connection, such as with [`Methods::call`], gets
+	/// an empty state that is dropped once it completes.
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	/// use jsonrpsee::RpcModule;
+	///
+	/// #[derive(Clone)]
+	/// struct User(String);
+	///
+	/// let mut module = RpcModule::new(());
+	/// module.register_method_with_connection_state("login", |params, state, _| {
+	///     state.insert(User(params.one()?));
+	///     Ok(())
+	/// }).unwrap();
+	/// module.register_method_with_connection_state("whoami", |_, state, _| {
+	///     Ok(state.get::<User>().map(|user| user.0))
+	/// }).unwrap();
+	/// ```
+	pub fn register_method_with_connection_state<R, F>(
+		&mut self,
+		method_name: &'static str,
+		callback: F,
+	) -> Result<MethodResourcesBuilder<'_>, Error>
+	where
+		R: Serialize,
+		F: Fn(Params, &ConnectionState, &Context) -> Result<R, Error> + Send + Sync + 'static,
+	{
+		let ctx = self.ctx.clone();
+		let callback = Arc::new(callback);
+		let callback = self.methods.verify_and_insert(
+			method_name,
+			MethodCallback::new_async(Arc::new(move |id, params, sink, _, claimed| {
+				let ctx = ctx.clone();
+				let callback = callback.clone();
+				let future = async move {
+					// The connection state is only available while the future is polled by the server.
+					let state = ConnectionState::current().unwrap_or_default();
+					let result = match callback(params, &state, &*ctx) {
+						Ok(res) => sink.send_response(id, res),
+						Err(err) => sink.send_call_error(id, err),
+					};
+
+					// Release claimed resources
+					drop(claimed);
+
+					result
+				};
+				future.boxed()
+			})),
+		)?;
+
+		Ok(MethodResourcesBuilder { build: ResourceVec::new(), callback })
+	}
+
 	/// Register a new publish/subscribe interface using JSON-RPC notifications.
 	///
 	/// It implements the [ethereum pubsub specification](https://geth.ethereum.org/docs/rpc/pubsub)
@@ -605,6 +2297,11 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 	///     - [`SubscriptionSink`]: A sink to send messages to the subscriber.
 	///     - Context: Any type that can be embedded into the [`RpcModule`].
 	///
+	/// The error returned by the callback is sent to the subscriber as an error response to the subscription call,
+	/// keeping the code, message and data of an [`ErrorObject`] or [`CallError::Custom`] error. The subscription is
+	/// accepted before the callback is invoked, use [`RpcModule::register_pending_subscription`] to answer the call
+	/// with the error instead.
+	///
 	/// # Examples
 	///
 	/// ```no_run
@@ -632,53 +2329,527 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 		Context: Send + Sync + 'static,
 		F: Fn(Params, SubscriptionSink, Arc<Context>) -> Result<(), Error> + Send + Sync + 'static,
 	{
-		if subscribe_method_name == unsubscribe_method_name {
-			return Err(Error::SubscriptionNameConflict(subscribe_method_name.into()));
-		}
-
-		self.methods.verify_method_name(subscribe_method_name)?;
-		self.methods.verify_method_name(unsubscribe_method_name)?;
-
-		let ctx = self.ctx.clone();
-		let subscribers = Subscribers::default();
-
-		// Subscribe
-		{
-			let subscribers = subscribers.clone();
-			self.methods.mut_callbacks().insert(
-				subscribe_method_name,
-				MethodCallback::new_subscription(Arc::new(move |id, params, method_sink, conn| {
-					let (conn_tx, conn_rx) = oneshot::channel::<()>();
-
-					let sub_id = {
-						let sub_id: RpcSubscriptionId = conn.id_provider.next_id().into_owned();
-						let uniq_sub = SubscriptionKey { conn_id: conn.conn_id, sub_id: sub_id.clone() };
-
-						subscribers.lock().insert(uniq_sub, (method_sink.clone(), conn_rx));
-
-						sub_id
-					};
-
-					method_sink.send_response(id.clone(), &sub_id);
+		self.register_subscription_inner(
+			subscribe_method_name,
+			notif_method_name,
+			unsubscribe_method_name,
+			SubscriptionOptions::default(),
+			accept_first(callback),
+		)
+	}
 
-					let sink = SubscriptionSink {
-						inner: method_sink.clone(),
-						close_notify: Some(conn.close_notify),
-						method: notif_method_name,
-						subscribers: subscribers.clone(),
-						uniq_sub: SubscriptionKey { conn_id: conn.conn_id, sub_id },
-						is_connected: Some(conn_tx),
-					};
-					if let Err(err) = callback(params, sink, ctx.clone()) {
-						tracing::error!(
-							"subscribe call '{}' failed: {:?}, request id={:?}",
-							subscribe_method_name,
-							err,
-							id
+	/// Register a publish/subscribe interface like [`RpcModule::register_pending_subscription`], but whose callback
+	/// is asynchronous so that setting up the subscription can `.await` before accepting it, for instance to fetch the
+	/// initial state to send.
+	///
+	/// If the future returned by `callback` fails before accepting the subscription, the subscription call is answered
+	/// with the error. If it fails after accepting it, the subscription is closed with the error as reason once the
+	/// future dropped its [`SubscriptionSink`]. The future is dropped when the connection is closed.
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	///
+	/// use jsonrpsee_core::server::rpc_module::RpcModule;
+	///
+	/// let mut ctx = RpcModule::new(99_usize);
+	/// ctx.register_async_subscription("sub", "notif_name", "unsub", |params, pending, ctx| async move {
+	///     let x: usize = params.one()?;
+	///     let initial = tokio::task::spawn_blocking(move || x + *ctx).await.unwrap();
+	///     if let Some(mut sink) = pending.accept() {
+	///         sink.send(&initial)?;
+	///     }
+	///     Ok(())
+	/// });
+	/// ```
+	pub fn register_async_subscription<Fun, Fut>(
+		&mut self,
+		subscribe_method_name: &'static str,
+		notif_method_name: &'static str,
+		unsubscribe_method_name: &'static str,
+		callback: Fun,
+	) -> Result<(), Error>
+	where
+		Context: Send + Sync + 'static,
+		Fun: Fn(Params<'static>, PendingSubscription, Arc<Context>) -> Fut + Send + Sync + 'static,
+		Fut: Future<Output = Result<(), Error>> + Send + 'static,
+	{
+		self.register_subscription_inner(
+			subscribe_method_name,
+			notif_method_name,
+			unsubscribe_method_name,
+			SubscriptionOptions::default(),
+			move |params, pending, ctx| {
+				let state = pending.0.clone();
+				let dropped_sink: SetupSlot = Arc::new(Mutex::new(None));
+				let sink = match state.lock().as_mut() {
+					Some(inner) => {
+						inner.setup = Some(Arc::downgrade(&dropped_sink));
+						inner.sink.clone()
+					}
+					None => return Ok(()),
+				};
+				let setup = callback(params.into_owned(), pending, ctx);
+				tokio::spawn(async move {
+					let closed = connection_closed(&sink);
+					pin_mut!(setup, closed);
+					let result = match futures_util::future::select(setup, closed).await {
+						Either::Left((result, _)) => result,
+						Either::Right(((), _)) => {
+							tracing::debug!(
+								"async subscribe call '{}' dropped with its connection",
+								subscribe_method_name
+							);
+							Ok(())
+						}
+					};
+
+					let dropped_sink = dropped_sink.lock().take();
+					let unanswered = state.lock().take();
+					match (result, unanswered) {
+						(Ok(()), Some(inner)) => inner.fail_unanswered(),
+						(Ok(()), None) => {}
+						(Err(err), Some(inner)) => {
+							tracing::error!("async subscribe call '{}' failed: {:?}", subscribe_method_name, err);
+							inner.fail(err);
+						}
+						(Err(err), None) => {
+							tracing::error!("async subscription '{}' failed: {:?}", subscribe_method_name, err);
+							if let Some(mut sink) = dropped_sink {
+								sink.close(&SubscriptionClosedReason::Server(err.to_string()).into());
+							}
+						}
+					}
+				});
+				Ok(())
+			},
+		)
+	}
+
+	/// Register a publish/subscribe interface like [`RpcModule::register_subscription`], but which lets `callback`
+	/// decide whether to accept the subscription or to answer the subscription call with an error, for instance if
+	/// the params are invalid or the subscriber is not authorized.
+	///
+	/// Unlike [`RpcModule::register_subscription`] where the subscription ID is returned before the callback is
+	/// invoked, the subscription call is answered when [`PendingSubscription::accept`] or
+	/// [`PendingSubscription::reject`] is called, which may happen after `callback` returned.
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	///
+	/// use jsonrpsee_core::server::rpc_module::RpcModule;
+	/// use jsonrpsee_types::error::CallError;
+	///
+	/// let mut ctx = RpcModule::new(());
+	/// ctx.register_pending_subscription("sub", "notif_name", "unsub", |params, pending, _| {
+	///     let topic: String = match params.one() {
+	///         Ok(topic) => topic,
+	///         Err(err) => return pending.reject(err),
+	///     };
+	///     if topic.is_empty() {
+	///         return pending.reject(CallError::InvalidParams(anyhow::anyhow!("Empty topic")));
+	///     }
+	///     if let Some(mut sink) = pending.accept() {
+	///         std::thread::spawn(move || sink.send(&topic));
+	///     }
+	/// });
+	/// ```
+	pub fn register_pending_subscription<F>(
+		&mut self,
+		subscribe_method_name: &'static str,
+		notif_method_name: &'static str,
+		unsubscribe_method_name: &'static str,
+		callback: F,
+	) -> Result<(), Error>
+	where
+		Context: Send + Sync + 'static,
+		F: Fn(Params, PendingSubscription, Arc<Context>) + Send + Sync + 'static,
+	{
+		self.register_subscription_inner(
+			subscribe_method_name,
+			notif_method_name,
+			unsubscribe_method_name,
+			SubscriptionOptions::default(),
+			move |params, pending, ctx| {
+				callback(params, pending, ctx);
+				Ok(())
+			},
+		)
+	}
+
+	/// Register a publish/subscribe interface like [`RpcModule::register_pending_subscription`], but which answers
+	/// the subscription calls with a `Request timeout` error if they are neither accepted nor rejected within
+	/// `setup_timeout`, for instance because the setup of the subscription waits on a slow backend.
+	///
+	/// [`PendingSubscription::accept`] returns `None` once the subscription call timed out.
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	///
+	/// use std::time::Duration;
+	/// use jsonrpsee_core::server::rpc_module::RpcModule;
+	///
+	/// let mut ctx = RpcModule::new(());
+	/// ctx.register_pending_subscription_with_timeout("sub", "notif_name", "unsub", Duration::from_secs(5), |_, pending, _| {
+	///     tokio::spawn(async move {
+	///         let initial = tokio::task::spawn_blocking(|| 42).await.unwrap();
+	///         if let Some(mut sink) = pending.accept() {
+	///             let _ = sink.send(&initial);
+	///         }
+	///     });
+	/// });
+	/// ```
+	pub fn register_pending_subscription_with_timeout<F>(
+		&mut self,
+		subscribe_method_name: &'static str,
+		notif_method_name: &'static str,
+		unsubscribe_method_name: &'static str,
+		setup_timeout: Duration,
+		callback: F,
+	) -> Result<(), Error>
+	where
+		Context: Send + Sync + 'static,
+		F: Fn(Params, PendingSubscription, Arc<Context>) + Send + Sync + 'static,
+	{
+		self.register_subscription_inner(
+			subscribe_method_name,
+			notif_method_name,
+			unsubscribe_method_name,
+			SubscriptionOptions { setup_timeout: Some(setup_timeout), ..Default::default() },
+			move |params, pending, ctx| {
+				callback(params, pending, ctx);
+				Ok(())
+			},
+		)
+	}
+
+	/// Register a publish/subscribe interface like [`RpcModule::register_subscription`], but which closes
+	/// subscriptions idle for `idle_timeout`.
+	///
+	/// A subscription is idle if no notification was sent on it and the client didn't subscribe to it
+	/// again (see [`RpcModule::register_reusable_subscription`]). Idle subscriptions are closed with
+	/// [`SubscriptionClosedReason::Server`]`("idle timeout")`, and sending on their [`SubscriptionSink`] fails
+	/// afterwards.
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	///
+	/// use std::time::Duration;
+	/// use jsonrpsee_core::server::rpc_module::RpcModule;
+	///
+	/// let mut ctx = RpcModule::new(());
+	/// ctx.register_subscription_with_idle_timeout(
+	///     "sub",
+	///     "notif_name",
+	///     "unsub",
+	///     Duration::from_secs(60),
+	///     |_, mut sink, _| {
+	///         std::thread::spawn(move || sink.send(&"only notification"));
+	///         Ok(())
+	///     },
+	/// );
+	/// ```
+	pub fn register_subscription_with_idle_timeout<F>(
+		&mut self,
+		subscribe_method_name: &'static str,
+		notif_method_name: &'static str,
+		unsubscribe_method_name: &'static str,
+		idle_timeout: Duration,
+		callback: F,
+	) -> Result<(), Error>
+	where
+		Context: Send + Sync + 'static,
+		F: Fn(Params, SubscriptionSink, Arc<Context>) -> Result<(), Error> + Send + Sync + 'static,
+	{
+		self.register_subscription_inner(
+			subscribe_method_name,
+			notif_method_name,
+			unsubscribe_method_name,
+			SubscriptionOptions { idle_timeout: Some(idle_timeout), ..Default::default() },
+			accept_first(callback),
+		)
+	}
+
+	/// Register a publish/subscribe interface like [`RpcModule::register_subscription`], but which closes
+	/// subscriptions of clients too slow to consume their notifications.
+	///
+	/// If `max_pending` notifications of a subscription are waiting to be delivered to the client, the subscription
+	/// is closed with [`SubscriptionClosedReason::Server`]`("slow consumer")` instead of queueing more. This is
+	/// [`RpcModule::register_subscription_with_buffer`] with [`LagPolicy::Close`].
+	///
+	/// The limit is only enforced if the transport reports the delivery of the messages, see
+	/// [`MethodSink::track_delivery`]; the WebSocket server does so for subscriptions which are not part of a batch.
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	///
+	/// use jsonrpsee_core::server::rpc_module::RpcModule;
+	///
+	/// let mut ctx = RpcModule::new(());
+	/// ctx.register_subscription_with_queue_limit("sub", "notif_name", "unsub", 64, |_, sink, _| {
+	///     let stream = futures_util::stream::iter(0_u64..);
+	///     tokio::spawn(sink.pipe_from_stream(stream));
+	///     Ok(())
+	/// });
+	/// ```
+	pub fn register_subscription_with_queue_limit<F>(
+		&mut self,
+		subscribe_method_name: &'static str,
+		notif_method_name: &'static str,
+		unsubscribe_method_name: &'static str,
+		max_pending: usize,
+		callback: F,
+	) -> Result<(), Error>
+	where
+		Context: Send + Sync + 'static,
+		F: Fn(Params, SubscriptionSink, Arc<Context>) -> Result<(), Error> + Send + Sync + 'static,
+	{
+		self.register_subscription_with_buffer(
+			subscribe_method_name,
+			notif_method_name,
+			unsubscribe_method_name,
+			max_pending,
+			LagPolicy::Close,
+			callback,
+		)
+	}
+
+	/// Register a publish/subscribe interface like [`RpcModule::register_subscription`], but which buffers at most
+	/// `capacity` notifications per subscription while they wait to be delivered to the client, counting the ones
+	/// already handed to the transport, and applies `policy` to the notifications sent while the buffer is full.
+	///
+	/// [`SubscriptionSink::try_send`] tells the producer what was done with each notification, whereas
+	/// [`SubscriptionSink::send`] only fails if the subscription is closed.
+	///
+	/// The buffer is only bounded if the transport reports the delivery of the messages, see
+	/// [`MethodSink::track_delivery`]; the WebSocket server does so for subscriptions which are not part of a batch.
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	///
+	/// use jsonrpsee_core::server::rpc_module::{LagPolicy, RpcModule};
+	///
+	/// let mut ctx = RpcModule::new(());
+	/// ctx.register_subscription_with_buffer("sub", "notif_name", "unsub", 16, LagPolicy::DropOldest, |_, sink, _| {
+	///     let stream = futures_util::stream::iter(0_u64..);
+	///     tokio::spawn(sink.pipe_from_stream(stream));
+	///     Ok(())
+	/// });
+	/// ```
+	pub fn register_subscription_with_buffer<F>(
+		&mut self,
+		subscribe_method_name: &'static str,
+		notif_method_name: &'static str,
+		unsubscribe_method_name: &'static str,
+		capacity: usize,
+		policy: LagPolicy,
+		callback: F,
+	) -> Result<(), Error>
+	where
+		Context: Send + Sync + 'static,
+		F: Fn(Params, SubscriptionSink, Arc<Context>) -> Result<(), Error> + Send + Sync + 'static,
+	{
+		self.register_subscription_inner(
+			subscribe_method_name,
+			notif_method_name,
+			unsubscribe_method_name,
+			SubscriptionOptions { buffer: Some((capacity, policy)), ..Default::default() },
+			accept_first(callback),
+		)
+	}
+
+	/// Register a publish/subscribe interface like [`RpcModule::register_subscription`], but which reuses an existing
+	/// subscription on the same connection if the params of the subscription call are equivalent to those of
+	/// the existing one.
+	///
+	/// The `key` closure returns the key of the params of a call, such as the topic subscribed to, or `None` if the
+	/// subscription must not be reused. If a subscription with the same key is active on the connection, its ID is
+	/// returned and `callback` is not invoked.
+	///
+	/// Note that all subscribe calls that reused a subscription share it, so unsubscribing once terminates it.
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	///
+	/// use jsonrpsee_core::server::rpc_module::RpcModule;
+	///
+	/// let mut ctx = RpcModule::new(());
+	/// ctx.register_reusable_subscription(
+	///     "sub",
+	///     "notif_name",
+	///     "unsub",
+	///     |params| params.one::<String>().ok(),
+	///     |params, mut sink, _| {
+	///         let topic: String = params.one()?;
+	///         std::thread::spawn(move || sink.send(&topic));
+	///         Ok(())
+	///     },
+	/// );
+	/// ```
+	pub fn register_reusable_subscription<K, F>(
+		&mut self,
+		subscribe_method_name: &'static str,
+		notif_method_name: &'static str,
+		unsubscribe_method_name: &'static str,
+		key: K,
+		callback: F,
+	) -> Result<(), Error>
+	where
+		Context: Send + Sync + 'static,
+		K: Fn(&Params) -> Option<String> + Send + Sync + 'static,
+		F: Fn(Params, SubscriptionSink, Arc<Context>) -> Result<(), Error> + Send + Sync + 'static,
+	{
+		self.register_subscription_inner(
+			subscribe_method_name,
+			notif_method_name,
+			unsubscribe_method_name,
+			SubscriptionOptions { reuse_key: Some(Arc::new(key)), ..Default::default() },
+			accept_first(callback),
+		)
+	}
+
+	fn register_subscription_inner<F>(
+		&mut self,
+		subscribe_method_name: &'static str,
+		notif_method_name: &'static str,
+		unsubscribe_method_name: &'static str,
+		options: SubscriptionOptions,
+		callback: F,
+	) -> Result<(), Error>
+	where
+		Context: Send + Sync + 'static,
+		F: Fn(Params, PendingSubscription, Arc<Context>) -> Result<(), Error> + Send + Sync + 'static,
+	{
+		let SubscriptionOptions { reuse_key, idle_timeout, buffer, setup_timeout } = options;
+
+		if subscribe_method_name == unsubscribe_method_name {
+			return Err(Error::SubscriptionNameConflict(subscribe_method_name.into()));
+		}
+
+		self.methods.verify_method_name(subscribe_method_name)?;
+		self.methods.verify_method_name(unsubscribe_method_name)?;
+
+		Arc::make_mut(&mut self.methods.subscriptions).insert(
+			subscribe_method_name,
+			SubscriptionTriple {
+				subscribe: subscribe_method_name,
+				notification: notif_method_name,
+				unsubscribe: unsubscribe_method_name,
+			},
+		);
+
+		let ctx = self.ctx.clone();
+		let subscribers = Subscribers::default();
+		Arc::make_mut(&mut self.methods.subscribers).insert(subscribe_method_name, subscribers.clone());
+		let reusable = ReusableSubscriptions::default();
+
+		// Subscribe
+		{
+			let subscribers = subscribers.clone();
+			self.methods.mut_callbacks().insert(
+				subscribe_method_name,
+				MethodCallback::new_subscription(Arc::new(move |id, params, method_sink, conn| {
+					let (method_sink, answered) = method_sink.with_answered_flag();
+					let reuse_key = reuse_key.as_ref().and_then(|key| key(&params)).map(|key| (conn.conn_id, key));
+					let uniq_sub =
+						SubscriptionKey { conn_id: conn.conn_id, sub_id: conn.id_provider.next_id().into_owned() };
+					if let Some(reuse_key) = &reuse_key {
+						let mut reusable = reusable.lock();
+						match reusable.get_mut(reuse_key) {
+							// Answered once the equivalent subscription is accepted or rejected.
+							Some(ReusableSubscription::Pending(_, waiting)) => {
+								tracing::debug!("Waiting for an equivalent subscription for request id={:?}", id);
+								waiting.push((id.into_owned(), method_sink));
+								return true;
+							}
+							Some(ReusableSubscription::Active(sub_id)) => {
+								let uniq_sub = SubscriptionKey { conn_id: conn.conn_id, sub_id: sub_id.clone() };
+								if let Some((_, _, last_activity, _)) = subscribers.lock().get(&uniq_sub) {
+									// Subscribing again counts as activity of the client.
+									if let Some(last_activity) = last_activity {
+										*last_activity.lock() = Instant::now();
+									}
+									drop(reusable);
+									tracing::debug!(
+										"Reusing subscription {:?} for request id={:?}",
+										uniq_sub.sub_id,
+										id
+									);
+									return method_sink.send_response(id, &uniq_sub.sub_id);
+								}
+							}
+							None => {}
+						}
+						// Inserted before releasing the lock for the equivalent calls made in the meantime to wait for
+						// this subscription instead of creating their own.
+						reusable.insert(
+							reuse_key.clone(),
+							ReusableSubscription::Pending(uniq_sub.sub_id.clone(), Vec::new()),
 						);
-						method_sink.send_error(id, ErrorCode::ServerError(CALL_EXECUTION_FAILED_CODE).into())
-					} else {
-						true
+					}
+
+					let permit = match conn.bounded_subscriptions.map(|bounded| (bounded, bounded.acquire())) {
+						None => None,
+						Some((_, Some(permit))) => Some(permit),
+						Some((bounded, None)) => {
+							tracing::warn!(
+								"Rejecting subscription call with id {:?}, the connection has {} subscriptions already",
+								id,
+								bounded.max()
+							);
+							let err = ErrorObject {
+								code: ErrorCode::ServerError(TOO_MANY_SUBSCRIPTIONS_CODE),
+								message: TOO_MANY_SUBSCRIPTIONS_MSG.into(),
+								data: None,
+							};
+							if let Some(reuse_key) = &reuse_key {
+								fail_waiting(take_waiting(&reusable, reuse_key, &uniq_sub.sub_id), err.clone());
+							}
+							return method_sink.send_error(id, err);
+						}
+					};
+
+					let pending = PendingSubscription(Arc::new(Mutex::new(Some(InnerPendingSubscription {
+						sink: method_sink.clone(),
+						id: id.clone().into_owned(),
+						uniq_sub,
+						close_notify: conn.close_notify,
+						method: notif_method_name,
+						subscribers: subscribers.clone(),
+						reuse: reuse_key.map(|key| (reusable.clone(), key)),
+						idle_timeout,
+						buffer,
+						version: subscription_version(&params),
+						permit,
+						span: tracing::Span::current(),
+						setup: None,
+					}))));
+					if let Some(setup_timeout) = setup_timeout {
+						tokio::spawn(reject_when_late(Arc::downgrade(&pending.0), setup_timeout));
+					}
+
+					match callback(params, pending, ctx.clone()) {
+						Ok(()) => true,
+						Err(err) => {
+							tracing::error!(
+								"subscribe call '{}' failed: {:?}, request id={:?}",
+								subscribe_method_name,
+								err,
+								id
+							);
+							// Once accepted, the subscription is closed as its sink was dropped, the call having been
+							// answered with the subscription ID.
+							if !answered.load(Ordering::Relaxed) {
+								method_sink.send_call_error(id, err);
+							}
+							false
+						}
 					}
 				})),
 			);
@@ -703,32 +2874,1301 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 					};
 					let sub_id = sub_id.into_owned();
 
-					let result = subscribers
-						.lock()
-						.remove(&SubscriptionKey { conn_id: conn.conn_id, sub_id: sub_id.clone() })
-						.is_some();
+					let result = subscribers
+						.lock()
+						.remove(&SubscriptionKey { conn_id: conn.conn_id, sub_id: sub_id.clone() })
+						.is_some();
+
+					sink.send_response(id, result)
+				})),
+			);
+		}
+
+		Ok(())
+	}
+
+	/// Register a new streaming RPC method, which sends its result back as a sequence of chunks.
+	///
+	/// Each chunk sent on the [`StreamSink`] is delivered as a `notif_method_name` notification carrying the
+	/// ID of the call. Once the future returned by `callback` completes, its result is sent as the regular
+	/// response to the call, which marks the end of the stream.
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	///
+	/// use jsonrpsee_core::server::rpc_module::RpcModule;
+	///
+	/// let mut module = RpcModule::new(());
+	/// module.register_streaming_method("read_lines", "read_lines_chunk", |params, sink, _| async move {
+	///     let lines: Vec<String> = params.parse()?;
+	///     for line in &lines {
+	///         sink.send(line)?;
+	///     }
+	///     Ok(lines.len())
+	/// });
+	/// ```
+	pub fn register_streaming_method<R, Fun, Fut>(
+		&mut self,
+		method_name: &'static str,
+		notif_method_name: &'static str,
+		callback: Fun,
+	) -> Result<MethodResourcesBuilder<'_>, Error>
+	where
+		R: Serialize + Send + Sync + 'static,
+		Fut: Future<Output = Result<R, Error>> + Send,
+		Fun: (Fn(Params<'static>, StreamSink, Arc<Context>) -> Fut) + Copy + Send + Sync + 'static,
+	{
+		self.register_streaming_method_inner(method_name, notif_method_name, None, callback)
+	}
+
+	/// Register a new streaming RPC method like [`RpcModule::register_streaming_method`], whose chunks are
+	/// flow controlled by the client.
+	///
+	/// The client may receive `initial_credit` chunks of a call, and grants more by calling `credit_method_name`
+	/// on the same connection with the ID of the streaming call and the number of additional chunks it can accept:
+	///
+	/// ```json
+	/// {"jsonrpc":"2.0","method":"read_lines_credit","params":[1, 16],"id":2}
+	/// ```
+	///
+	/// The credit call returns whether the streaming call was found. The callback sends its chunks with
+	/// [`StreamSink::send_when_ready`], which waits for credit when it's exhausted, whereas [`StreamSink::send`]
+	/// ignores the credit.
+	///
+	/// Note that [`Methods::call_streaming`] doesn't grant credit, so it only receives `initial_credit` chunks.
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	///
+	/// use jsonrpsee_core::server::rpc_module::RpcModule;
+	///
+	/// let mut module = RpcModule::new(());
+	/// module.register_flow_controlled_streaming_method(
+	///     "read_lines",
+	///     "read_lines_chunk",
+	///     "read_lines_credit",
+	///     16,
+	///     |params, sink, _| async move {
+	///         let lines: Vec<String> = params.parse()?;
+	///         for line in &lines {
+	///             sink.send_when_ready(line).await?;
+	///         }
+	///         Ok(lines.len())
+	///     },
+	/// );
+	/// ```
+	pub fn register_flow_controlled_streaming_method<R, Fun, Fut>(
+		&mut self,
+		method_name: &'static str,
+		notif_method_name: &'static str,
+		credit_method_name: &'static str,
+		initial_credit: u64,
+		callback: Fun,
+	) -> Result<MethodResourcesBuilder<'_>, Error>
+	where
+		R: Serialize + Send + Sync + 'static,
+		Fut: Future<Output = Result<R, Error>> + Send,
+		Fun: (Fn(Params<'static>, StreamSink, Arc<Context>) -> Fut) + Copy + Send + Sync + 'static,
+	{
+		if method_name == credit_method_name {
+			return Err(Error::MethodAlreadyRegistered(credit_method_name.into()));
+		}
+		// Both names are verified first, to not leave the credit method registered if the streaming method can't be.
+		self.methods.verify_method_name(method_name)?;
+		self.methods.verify_method_name(credit_method_name)?;
+
+		let credits = StreamCredits::default();
+		{
+			let credits = credits.clone();
+			self.methods.verify_and_insert(
+				credit_method_name,
+				MethodCallback::new_async(Arc::new(move |id, params, sink, conn_id, _| {
+					let result = match params.parse::<(Id, u64)>() {
+						Ok((call_id, granted)) => {
+							let credit = credits.lock().get(&(conn_id, call_id.into_owned())).cloned();
+							if let Some(credit) = &credit {
+								credit.grant(granted);
+							}
+							sink.send_response(id, credit.is_some())
+						}
+						Err(err) => sink.send_call_error(id, err.into()),
+					};
+					futures_util::future::ready(result).boxed()
+				})),
+			)?;
+		}
+
+		self.register_streaming_method_inner(method_name, notif_method_name, Some((credits, initial_credit)), callback)
+	}
+
+	fn register_streaming_method_inner<R, Fun, Fut>(
+		&mut self,
+		method_name: &'static str,
+		notif_method_name: &'static str,
+		flow_control: Option<(StreamCredits, u64)>,
+		callback: Fun,
+	) -> Result<MethodResourcesBuilder<'_>, Error>
+	where
+		R: Serialize + Send + Sync + 'static,
+		Fut: Future<Output = Result<R, Error>> + Send,
+		Fun: (Fn(Params<'static>, StreamSink, Arc<Context>) -> Fut) + Copy + Send + Sync + 'static,
+	{
+		let ctx = self.ctx.clone();
+		let callback = self.methods.verify_and_insert(
+			method_name,
+			MethodCallback::new_async(Arc::new(move |id, params, sink, conn_id, claimed| {
+				let ctx = ctx.clone();
+				let credit = match flow_control.as_ref() {
+					Some((credits, initial_credit)) => {
+						match StreamCreditGuard::new(credits.clone(), (conn_id, id.clone()), *initial_credit) {
+							Some(guard) => Some(guard),
+							// The credit calls couldn't tell both streaming calls apart.
+							None => {
+								let err = ErrorObject {
+									code: ErrorCode::InvalidRequest,
+									message: STREAMING_CALL_IN_PROGRESS_MSG.into(),
+									data: None,
+								};
+								return futures_util::future::ready(sink.send_error(id, err)).boxed();
+							}
+						}
+					}
+					None => None,
+				};
+				let stream_sink = StreamSink {
+					inner: sink.clone(),
+					method: notif_method_name,
+					id: id.clone(),
+					credit: credit.as_ref().map(|guard| guard.credit.clone()),
+				};
+				let future = async move {
+					let result = match callback(params, stream_sink, ctx).await {
+						Ok(res) => sink.send_response(id, res),
+						Err(err) => sink.send_call_error(id, err),
+					};
+
+					// Stop accepting credit for the call.
+					drop(credit);
+					// Release claimed resources
+					drop(claimed);
+
+					result
+				};
+				future.boxed()
+			})),
+		)?;
+
+		Ok(MethodResourcesBuilder { build: ResourceVec::new(), callback })
+	}
+
+	/// Register methods whose callbacks are built on their first call, for large APIs of rarely used methods.
+	///
+	/// When the first call is made to one of the methods `names`, `factory` is called with the method name to build
+	/// its callback, `None` meaning the method doesn't exist. The built callback is kept for subsequent calls.
+	/// Concurrent first calls to the same method build it only once. Only the callbacks of the `names` are ever
+	/// built and kept, whatever the method names of the calls. Fails if any of the `names` is already registered.
+	///
+	/// Lazily built methods use the default resources, and don't show up in [`Methods::method_names`].
+	///
+	/// # Examples
+	///
+	/// ```
+	/// #[tokio::main]
+	/// async fn main() {
+	///     use std::sync::Arc;
+	///     use jsonrpsee::{RpcModule, types::EmptyParams};
+	///     use jsonrpsee::core::server::rpc_module::MethodCallback;
+	///
+	///     let mut module = RpcModule::new(());
+	///     module
+	///         .register_lazy(["echo_hello", "echo_world"], |name| {
+	///             let word = name.strip_prefix("echo_")?.to_owned();
+	///             Some(MethodCallback::new_sync(Arc::new(move |id, _, sink| sink.send_response(id, &word))))
+	///         })
+	///         .unwrap();
+	///
+	///     let word: String = module.call("echo_hello", EmptyParams::new()).await.unwrap();
+	///     assert_eq!(&word, "hello");
+	/// }
+	/// ```
+	pub fn register_lazy<I, F>(&mut self, names: I, factory: F) -> Result<(), Error>
+	where
+		I: IntoIterator<Item = &'static str>,
+		F: Fn(&str) -> Option<MethodCallback> + Send + Sync + 'static,
+	{
+		let names: Vec<_> = names.into_iter().collect();
+		for (idx, &name) in names.iter().enumerate() {
+			self.methods.verify_method_name(name)?;
+			if names[..idx].contains(&name) {
+				return Err(Error::MethodAlreadyRegistered(name.into()));
+			}
+		}
+
+		let factory: LazyFactory = Arc::new(factory);
+		let lazy = Arc::make_mut(&mut self.methods.lazy.methods);
+		for name in names {
+			let method = LazyMethod { factory_name: name, factory: factory.clone(), built: Default::default() };
+			lazy.insert(name, method);
+		}
+
+		Ok(())
+	}
+
+	/// Register a health method, which reports the [`HealthStatus`] returned by `check`.
+	///
+	/// The `uptime_secs` of the status, if not set by `check`, is filled with the time elapsed since the
+	/// method was registered.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// #[tokio::main]
+	/// async fn main() {
+	///     use jsonrpsee::{RpcModule, types::EmptyParams};
+	///     use jsonrpsee::core::server::rpc_module::HealthStatus;
+	///
+	///     let mut module = RpcModule::new(3_usize);
+	///     module.register_health("system_health", |peers| {
+	///         HealthStatus::new(*peers > 0).with_detail("peers", *peers)
+	///     }).unwrap();
+	///
+	///     let health: HealthStatus = module.call("system_health", EmptyParams::new()).await.unwrap();
+	///     assert!(health.healthy);
+	///     assert_eq!(health.details["peers"], 3);
+	/// }
+	/// ```
+	pub fn register_health<F>(&mut self, method_name: &'static str, check: F) -> Result<(), Error>
+	where
+		F: Fn(&Context) -> HealthStatus + Send + Sync + 'static,
+	{
+		let started_at = Instant::now();
+		self.register_method(method_name, move |_, ctx| {
+			let mut status = check(ctx);
+			status.uptime_secs.get_or_insert_with(|| started_at.elapsed().as_secs());
+			Ok(status)
+		})?;
+
+		Ok(())
+	}
+
+	/// Register a check of the responses of an existing method, to catch methods returning results of the wrong
+	/// shape during development.
+	///
+	/// In debug builds, sending a response for which `check` returns an error panics with the returned
+	/// description. In release builds, `check` is never run. Responses of subscriptions are not checked.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use jsonrpsee_core::server::rpc_module::RpcModule;
+	///
+	/// let mut module = RpcModule::new(());
+	/// module.register_method("block_number", |_, _| Ok(42_u64)).unwrap();
+	/// module.register_response_check("block_number", |result| match result.is_u64() {
+	///     true => Ok(()),
+	///     false => Err(format!("Expected a block number, got: {}", result)),
+	/// }).unwrap();
+	/// ```
+	pub fn register_response_check<F>(&mut self, method_name: &'static str, check: F) -> Result<(), Error>
+	where
+		F: Fn(&JsonValue) -> Result<(), String> + Send + Sync + 'static,
+	{
+		let callback = match self.methods.mut_callbacks().get_mut(method_name) {
+			Some(callback) => callback,
+			None => return Err(Error::MethodNotFound(method_name.into())),
+		};
+
+		if cfg!(debug_assertions) {
+			let check = ResponseCheck::new(check);
+			match callback.callback.clone() {
+				MethodKind::Sync(cb) => {
+					callback.callback = MethodKind::Sync(Arc::new(move |id, params, sink| {
+						(cb)(id, params, &sink.with_response_check(check.clone()))
+					}));
+				}
+				MethodKind::Async(cb) => {
+					callback.callback = MethodKind::Async(Arc::new(move |id, params, sink, conn_id, claimed| {
+						(cb)(id, params, sink.with_response_check(check.clone()), conn_id, claimed)
+					}));
+				}
+				MethodKind::Subscription(_) => (),
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Catch the panics of the callback of an existing subscription, instead of letting them unwind into the server.
+	///
+	/// A call whose callback panics before the subscription is accepted or rejected is answered with an error. If
+	/// it was accepted already, the subscription is closed as its [`SubscriptionSink`] is dropped while unwinding,
+	/// the call having been answered with the subscription ID. Returns an error if `subscribe_method_name` isn't the
+	/// name of a subscribe method.
+	///
+	/// This has no effect if panics abort the process.
+	pub fn catch_subscription_panics(&mut self, subscribe_method_name: &'static str) -> Result<(), Error> {
+		if !self.methods.subscriptions.contains_key(subscribe_method_name) {
+			return Err(Error::MethodNotFound(subscribe_method_name.into()));
+		}
+		let callback = match self.methods.mut_callbacks().get_mut(subscribe_method_name) {
+			Some(callback) => callback,
+			None => return Err(Error::MethodNotFound(subscribe_method_name.into())),
+		};
+
+		if let MethodKind::Subscription(cb) = callback.callback.clone() {
+			callback.callback = MethodKind::Subscription(Arc::new(move |id, params, sink, conn| {
+				let call_id = id.clone().into_owned();
+				let (tracked, answered) = sink.with_answered_flag();
+				match std::panic::catch_unwind(AssertUnwindSafe(|| (cb)(id, params, &tracked, conn))) {
+					Ok(result) => result,
+					Err(_) => {
+						tracing::error!(
+							"subscribe call '{}' panicked, request id={:?}",
+							subscribe_method_name,
+							call_id
+						);
+						// Answered with the subscription ID already, the subscription was closed while unwinding.
+						if answered.load(Ordering::Relaxed) {
+							return false;
+						}
+						let err = ErrorObject {
+							code: ErrorCode::ServerError(CALL_EXECUTION_FAILED_CODE),
+							message: "Subscription callback panicked".into(),
+							data: None,
+						};
+						sink.send_error(call_id, err)
+					}
+				}
+			}));
+		}
+
+		Ok(())
+	}
+
+	/// Mark an existing method as deprecated with the given notice, for instance naming the method to use instead.
+	/// Its aliases are deprecated along with it. Returns an error if the method is already deprecated.
+	///
+	/// Calls to the method are logged with the notice, and servers that enable deprecation warnings attach it to the
+	/// response as a non-standard `warning` field:
+	///
+	/// ```json
+	/// {"jsonrpc":"2.0","result":42,"id":1,"warning":"Use `chain_getBlockNumber` instead"}
+	/// ```
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	/// use jsonrpsee::RpcModule;
+	///
+	/// let mut module = RpcModule::new(());
+	/// module.register_method("block_number", |_, _| Ok(42)).unwrap();
+	/// module.deprecate_method("block_number", "Use `chain_getBlockNumber` instead").unwrap();
+	/// ```
+	pub fn deprecate_method(&mut self, method_name: &'static str, notice: &'static str) -> Result<(), Error> {
+		let callback = match self.methods.mut_callbacks().get_mut(method_name) {
+			Some(callback) => callback,
+			None => return Err(Error::MethodNotFound(method_name.into())),
+		};
+
+		if callback.deprecation.is_some() {
+			return Err(Error::Custom(format!("Method `{}` is already deprecated", method_name)));
+		}
+
+		let wrapped = match callback.callback.clone() {
+			MethodKind::Sync(cb) => MethodKind::Sync(Arc::new(move |id, params, sink| {
+				tracing::warn!("Deprecated method `{}` called: {}", method_name, notice);
+				(cb)(id, params, &sink.with_deprecation_warning(notice))
+			})),
+			MethodKind::Async(cb) => MethodKind::Async(Arc::new(move |id, params, sink, conn_id, claimed| {
+				tracing::warn!("Deprecated method `{}` called: {}", method_name, notice);
+				(cb)(id, params, sink.with_deprecation_warning(notice), conn_id, claimed)
+			})),
+			MethodKind::Subscription(cb) => MethodKind::Subscription(Arc::new(move |id, params, sink, conn| {
+				tracing::warn!("Deprecated method `{}` called: {}", method_name, notice);
+				(cb)(id, params, &sink.with_deprecation_warning(notice), conn)
+			})),
+		};
+
+		// The aliases share the callback of the method, they are deprecated along with it.
+		let aliases: Vec<_> =
+			self.methods.aliases.iter().filter(|(_, &name)| name == method_name).map(|(&alias, _)| alias).collect();
+		let callbacks = self.methods.mut_callbacks();
+		for name in aliases.into_iter().chain([method_name]) {
+			if let Some(callback) = callbacks.get_mut(name) {
+				callback.deprecation = Some(notice);
+				callback.callback = wrapped.clone();
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Register an alias for an existing_method. Alias uniqueness is enforced.
+	pub fn register_alias(&mut self, alias: &'static str, existing_method: &'static str) -> Result<(), Error> {
+		self.methods.verify_method_name(alias)?;
+
+		let callback = match self.methods.callbacks.get(existing_method) {
+			Some(callback) => callback.clone(),
+			None => return Err(Error::MethodNotFound(existing_method.into())),
+		};
+
+		self.methods.mut_callbacks().insert(alias, callback);
+		Arc::make_mut(&mut self.methods.aliases).insert(alias, existing_method);
+
+		Ok(())
+	}
+
+	/// Bind whether a method is enabled to a value watched with a [`tokio::sync::watch`] channel, for instance the
+	/// configuration of the server, so that the method is enabled or disabled as soon as a new value is sent.
+	///
+	/// The `enabled` closure is run on the latest value for every call to the method, which costs a borrow of the
+	/// value; that's a shared read lock, only contended while a new value is being sent. Disabled methods behave as
+	/// described in [`Methods::set_enabled`], which can't be used anymore on the method. This applies to the aliases
+	/// of the method as well.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use jsonrpsee::RpcModule;
+	/// use tokio::sync::watch;
+	///
+	/// struct Config {
+	///     debug_methods: bool,
+	/// }
+	///
+	/// let (config_tx, config_rx) = watch::channel(Config { debug_methods: false });
+	/// let mut module = RpcModule::new(());
+	/// module.register_method("debug_dump", |_, _| Ok("state")).unwrap();
+	/// module.bind_enabled("debug_dump", config_rx, |config| config.debug_methods).unwrap();
+	/// assert!(!module.method("debug_dump").unwrap().is_enabled());
+	///
+	/// config_tx.send(Config { debug_methods: true }).unwrap();
+	/// assert!(module.method("debug_dump").unwrap().is_enabled());
+	/// ```
+	pub fn bind_enabled<T, F>(
+		&mut self,
+		method_name: &'static str,
+		rx: watch::Receiver<T>,
+		enabled: F,
+	) -> Result<(), Error>
+	where
+		T: Send + Sync + 'static,
+		F: Fn(&T) -> bool + Send + Sync + 'static,
+	{
+		let flag = match self.methods.callbacks.get(method_name).map(|callback| &callback.enabled) {
+			Some(Enabled::Flag(flag)) => flag.clone(),
+			Some(Enabled::Watched(_)) => {
+				return Err(Error::Custom(format!("Method `{}` is already enabled by a watched value", method_name)))
+			}
+			None => return Err(Error::MethodNotFound(method_name.into())),
+		};
+
+		let watched = Enabled::Watched(Arc::new(move || enabled(&rx.borrow())));
+		// The aliases share the flag of the method.
+		for callback in self.methods.mut_callbacks().values_mut() {
+			if matches!(&callback.enabled, Enabled::Flag(other) if Arc::ptr_eq(other, &flag)) {
+				callback.enabled = watched.clone();
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Register a method that was renamed, answering the calls to its old name with a `Method moved` error naming
+	/// the new one, so that clients can retry the call with it:
+	///
+	/// ```json
+	/// {"jsonrpc":"2.0","error":{"code":-32004,"message":"Method moved","data":{"moved_to":"chain_getBlock"}},"id":1}
+	/// ```
+	///
+	/// Unlike an alias, this makes the rename visible to the clients. The new method doesn't need to be registered on
+	/// this module.
+	pub fn register_moved(&mut self, old_name: &'static str, new_name: &'static str) -> Result<(), Error> {
+		let data = to_json_raw_value(&serde_json::json!({ "moved_to": new_name }))?;
+
+		self.methods.verify_and_insert(
+			old_name,
+			MethodCallback::new_sync(Arc::new(move |id, _, sink| {
+				let err = ErrorObject {
+					code: ErrorCode::ServerError(METHOD_MOVED_CODE),
+					message: METHOD_MOVED_MSG.into(),
+					data: Some(&data),
+				};
+				sink.send_error(id, err)
+			})),
+		)?;
+
+		Ok(())
+	}
+
+	/// Register the methods described by the given specs, see [`MethodSpec`].
+	///
+	/// The specs are validated up front: the names must be unique and not registered yet, the number of resources
+	/// per method must not exceed the maximum, and the kind must be sync or async and match the handler. The
+	/// resource labels are validated when the module is started on a server.
+	///
+	/// Either all the methods are registered, or none of them and [`Error::InvalidModule`] is returned with all the
+	/// errors found.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use jsonrpsee::core::server::rpc_module::{MethodKindTag, MethodSpec, MethodSpecHandler, RpcModule};
+	///
+	/// let mut module = RpcModule::new(());
+	/// let mut hello = MethodSpec::new("say_hello", MethodKindTag::Sync);
+	/// hello.description = Some("Say hello");
+	/// hello.resources.push(("cpu", 1));
+	/// hello.handler = Some(MethodSpecHandler::Sync(Box::new(|_, _| Ok("lo".into()))));
+	/// let planned = MethodSpec::new("say_goodbye", MethodKindTag::Async);
+	///
+	/// module.register_all(vec![hello, planned]).unwrap();
+	/// assert_eq!(module.method("say_hello").unwrap().description(), Some("Say hello"));
+	///
+	/// let err = module.register_all(vec![MethodSpec::new("say_hello", MethodKindTag::Sync)]).unwrap_err();
+	/// assert!(matches!(err, jsonrpsee::core::Error::InvalidModule(errs) if errs.len() == 1));
+	/// ```
+	pub fn register_all(&mut self, specs: Vec<MethodSpec<Context>>) -> Result<(), Error> {
+		let mut errors = Vec::new();
+		let mut names = FxHashSet::default();
+		let mut method_resources = Vec::with_capacity(specs.len());
+
+		for spec in specs.iter() {
+			if !names.insert(spec.name) || self.methods.method(spec.name).is_some() {
+				errors.push(Error::MethodAlreadyRegistered(spec.name.into()));
+			}
+
+			let mut build = ResourceVec::new();
+			for &resource in spec.resources.iter() {
+				if build.try_push(resource).is_err() {
+					errors.push(Error::MaxResourcesReached);
+					break;
+				}
+			}
+			method_resources.push(build);
+
+			match (spec.kind, spec.handler.as_ref()) {
+				(MethodKindTag::Subscription, _) => errors.push(Error::Custom(format!(
+					"Method `{}` is a subscription, which can't be registered from a spec",
+					spec.name
+				))),
+				(MethodKindTag::Sync, Some(MethodSpecHandler::Async(_)))
+				| (MethodKindTag::Async, Some(MethodSpecHandler::Sync(_))) => errors.push(Error::Custom(format!(
+					"Method `{}` is of kind {:?}, but has a handler of another kind",
+					spec.name, spec.kind
+				))),
+				_ => (),
+			}
+		}
+
+		if !errors.is_empty() {
+			return Err(Error::InvalidModule(errors));
+		}
+
+		// Restored if any registration fails, so that the module is left untouched.
+		let callbacks = self.methods.callbacks.clone();
+
+		for (spec, build) in specs.into_iter().zip(method_resources) {
+			let mut callback = self.spec_callback(spec.name, spec.kind, spec.handler);
+			callback.resources = MethodResources::Uninitialized(build[..].into());
+			callback.description = spec.description;
+
+			if let Err(err) = self.methods.verify_and_insert(spec.name, callback) {
+				self.methods.callbacks = callbacks;
+				return Err(Error::InvalidModule(vec![err]));
+			}
+		}
+
+		Ok(())
+	}
+
+	fn spec_callback(
+		&self,
+		name: &'static str,
+		kind: MethodKindTag,
+		handler: Option<MethodSpecHandler<Context>>,
+	) -> MethodCallback {
+		let ctx = self.ctx.clone();
+		let not_implemented =
+			move || Error::Call(CallError::Failed(anyhow::anyhow!("Method `{}` is not implemented", name)));
+
+		match (handler, kind) {
+			(Some(MethodSpecHandler::Sync(handler)), _) => {
+				MethodCallback::new_sync(Arc::new(move |id, params, sink| match handler(params, &*ctx) {
+					Ok(res) => sink.send_response(id, res),
+					Err(err) => sink.send_call_error(id, err),
+				}))
+			}
+			(Some(MethodSpecHandler::Async(handler)), _) => {
+				let handler: Arc<AsyncSpecHandler<Context>> = handler.into();
+				MethodCallback::new_async(Arc::new(move |id, params, sink, _, claimed| {
+					let future = handler(params, ctx.clone());
+					async move {
+						let result = match future.await {
+							Ok(res) => sink.send_response(id, res),
+							Err(err) => sink.send_call_error(id, err),
+						};
+
+						// Release claimed resources
+						drop(claimed);
+
+						result
+					}
+					.boxed()
+				}))
+			}
+			(None, MethodKindTag::Async) => MethodCallback::new_async(Arc::new(move |id, _, sink, _, _| {
+				futures_util::future::ready(sink.send_call_error(id, not_implemented())).boxed()
+			})),
+			(None, _) => {
+				MethodCallback::new_sync(Arc::new(move |id, _, sink| sink.send_call_error(id, not_implemented())))
+			}
+		}
+	}
+}
+
+/// Handler of a synchronous method registered from a [`MethodSpec`].
+pub type SyncSpecHandler<Context> = dyn Fn(Params, &Context) -> Result<JsonValue, Error> + Send + Sync;
+
+/// Handler of an asynchronous method registered from a [`MethodSpec`].
+pub type AsyncSpecHandler<Context> =
+	dyn Fn(Params<'static>, Arc<Context>) -> BoxFuture<'static, Result<JsonValue, Error>> + Send + Sync;
+
+/// Handler of a method registered from a [`MethodSpec`].
+pub enum MethodSpecHandler<Context> {
+	/// Synchronous method handler.
+	Sync(Box<SyncSpecHandler<Context>>),
+	/// Asynchronous method handler.
+	Async(Box<AsyncSpecHandler<Context>>),
+}
+
+impl<Context> Debug for MethodSpecHandler<Context> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			MethodSpecHandler::Sync(_) => f.write_str("<sync handler>"),
+			MethodSpecHandler::Async(_) => f.write_str("<async handler>"),
+		}
+	}
+}
+
+/// Description of a method to register with [`RpcModule::register_all`], for servers building their methods from
+/// data rather than code.
+#[derive(Debug)]
+pub struct MethodSpec<Context> {
+	/// Name of the method.
+	pub name: &'static str,
+	/// Kind of the method, either sync or async.
+	pub kind: MethodKindTag,
+	/// Units of the named resources the method uses during its execution.
+	pub resources: Vec<(&'static str, u16)>,
+	/// Description of the method, see [`MethodCallback::description`].
+	pub description: Option<&'static str>,
+	/// Handler of the method, which must be of the same kind. Without one, the calls are answered with a
+	/// `Method ... is not implemented` error.
+	pub handler: Option<MethodSpecHandler<Context>>,
+}
+
+impl<Context> MethodSpec<Context> {
+	/// Create a new spec of a method without resources, description and handler.
+	pub fn new(name: &'static str, kind: MethodKindTag) -> Self {
+		Self { name, kind, resources: Vec::new(), description: None, handler: None }
+	}
+}
+
+/// Health of the server, reported by the method registered with [`RpcModule::register_health`].
+///
+/// Serialized as a JSON object with the `healthy` and `uptime_secs` fields along with the `details`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HealthStatus {
+	/// Whether the server is healthy.
+	pub healthy: bool,
+	/// Time since the server started, in seconds.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub uptime_secs: Option<u64>,
+	/// Additional fields to report.
+	#[serde(flatten)]
+	pub details: BTreeMap<String, JsonValue>,
+}
+
+impl HealthStatus {
+	/// Create a new [`HealthStatus`] without details.
+	pub fn new(healthy: bool) -> Self {
+		Self { healthy, uptime_secs: None, details: BTreeMap::new() }
+	}
+
+	/// Add a field to report, the value is omitted if it can't be serialized.
+	pub fn with_detail(mut self, name: impl Into<String>, value: impl Serialize) -> Self {
+		if let Ok(value) = serde_json::to_value(value) {
+			self.details.insert(name.into(), value);
+		}
+		self
+	}
+}
+
+/// Builder for an [`RpcModule`] that collects all registrations and validates them at once in
+/// [`ModuleBuilder::build`], reporting every error found rather than just the first one.
+///
+/// # Examples
+///
+/// ```
+/// use jsonrpsee::core::server::rpc_module::ModuleBuilder;
+///
+/// let module = ModuleBuilder::new(())
+///     .method("say_hello", |_, _| Ok("lo"))
+///     .resource("cpu", 1)
+///     .async_method("say_hello_async", |_, _| async { Ok("lo") })
+///     .subscription("sub", "notif", "unsub", |_, _, _| Ok(()))
+///     .alias("hello", "say_hello")
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(module.method_names().count(), 5);
+///
+/// let err = ModuleBuilder::new(())
+///     .method("foo", |_, _| Ok(()))
+///     .method("foo", |_, _| Ok(()))
+///     .subscription("sub", "notif", "sub", |_, _, _| Ok(()))
+///     .build()
+///     .unwrap_err();
+///
+/// assert!(matches!(err, jsonrpsee::core::Error::InvalidModule(errs) if errs.len() == 2));
+/// ```
+#[derive(Debug)]
+pub struct ModuleBuilder<Context> {
+	module: RpcModule<Context>,
+	/// Name of the most recently registered method that subsequent resources are attached to.
+	last_method: Option<&'static str>,
+	/// Whether the most recent registration failed, its resources are skipped as the failure is already reported.
+	last_failed: bool,
+	/// Resources per method, applied when building.
+	method_resources: Vec<(&'static str, ResourceVec<(&'static str, u16)>)>,
+	/// Aliases to register when building, once all methods are present.
+	aliases: Vec<(&'static str, &'static str)>,
+	/// Resources to validate the resource labels against.
+	resources: Option<Resources>,
+	errors: Vec<Error>,
+}
+
+impl<Context: Send + Sync + 'static> ModuleBuilder<Context> {
+	/// Create a new builder for a module with a given shared `Context`.
+	pub fn new(ctx: Context) -> Self {
+		Self {
+			module: RpcModule::new(ctx),
+			last_method: None,
+			last_failed: false,
+			method_resources: Vec::new(),
+			aliases: Vec::new(),
+			resources: None,
+			errors: Vec::new(),
+		}
+	}
+
+	/// Validate the resource labels used by the methods against the given [`Resources`] when building.
+	///
+	/// Without this only the number of resources per method is validated, unknown labels are detected
+	/// once the module is started on a server.
+	pub fn validate_resources(mut self, resources: &Resources) -> Self {
+		self.resources = Some(resources.clone());
+		self
+	}
+
+	/// Register a synchronous RPC method, see [`RpcModule::register_method`].
+	pub fn method<R, F>(mut self, method_name: &'static str, callback: F) -> Self
+	where
+		R: Serialize,
+		F: Fn(Params, &Context) -> Result<R, Error> + Send + Sync + 'static,
+	{
+		let res = self.module.register_method(method_name, callback).map(drop);
+		self.on_registered(method_name, res)
+	}
+
+	/// Register an asynchronous RPC method, see [`RpcModule::register_async_method`].
+	pub fn async_method<R, Fun, Fut>(mut self, method_name: &'static str, callback: Fun) -> Self
+	where
+		R: Serialize + Send + Sync + 'static,
+		Fut: Future<Output = Result<R, Error>> + Send,
+		Fun: (Fn(Params<'static>, Arc<Context>) -> Fut) + Copy + Send + Sync + 'static,
+	{
+		let res = self.module.register_async_method(method_name, callback).map(drop);
+		self.on_registered(method_name, res)
+	}
+
+	/// Register a blocking RPC method, see [`RpcModule::register_blocking_method`].
+	pub fn blocking_method<R, F>(mut self, method_name: &'static str, callback: F) -> Self
+	where
+		R: Serialize,
+		F: Fn(Params, Arc<Context>) -> Result<R, Error> + Copy + Send + Sync + 'static,
+	{
+		let res = self.module.register_blocking_method(method_name, callback).map(drop);
+		self.on_registered(method_name, res)
+	}
+
+	/// Register a subscription, see [`RpcModule::register_subscription`].
+	///
+	/// Resources defined after this are attached to the subscribe method.
+	pub fn subscription<F>(
+		mut self,
+		subscribe_method_name: &'static str,
+		notif_method_name: &'static str,
+		unsubscribe_method_name: &'static str,
+		callback: F,
+	) -> Self
+	where
+		F: Fn(Params, SubscriptionSink, Arc<Context>) -> Result<(), Error> + Send + Sync + 'static,
+	{
+		let res = self.module.register_subscription(
+			subscribe_method_name,
+			notif_method_name,
+			unsubscribe_method_name,
+			callback,
+		);
+		self.on_registered(subscribe_method_name, res)
+	}
+
+	/// Register an alias for a method, see [`RpcModule::register_alias`].
+	///
+	/// Aliases are resolved when building, so the existing method may be registered after the alias.
+	pub fn alias(mut self, alias: &'static str, existing_method: &'static str) -> Self {
+		self.aliases.push((alias, existing_method));
+		self
+	}
+
+	/// Define how many units of a given named resource the most recently registered method uses during its
+	/// execution.
+	///
+	/// Ignored if the most recent registration failed, the builder reports that failure instead.
+	pub fn resource(mut self, label: &'static str, units: u16) -> Self {
+		if self.last_failed {
+			return self;
+		}
+		if self.last_method.is_none() {
+			self.errors.push(Error::Custom(format!("Resource `{}` is not attached to any method", label)));
+			return self;
+		}
+
+		let (_, build) = self.method_resources.last_mut().expect("Entry is pushed for each method; qed");
+		if build.try_push((label, units)).is_err() {
+			self.errors.push(Error::MaxResourcesReached);
+		}
+
+		self
+	}
+
+	fn on_registered(mut self, method_name: &'static str, res: Result<(), Error>) -> Self {
+		match res {
+			Ok(()) => {
+				self.last_method = Some(method_name);
+				self.last_failed = false;
+				self.method_resources.push((method_name, ResourceVec::new()));
+			}
+			Err(err) => {
+				self.last_method = None;
+				self.last_failed = true;
+				self.errors.push(err);
+			}
+		}
+		self
+	}
+
+	/// Finalize the module, returns [`Error::InvalidModule`] with all errors found if any registration was invalid.
+	pub fn build(mut self) -> Result<RpcModule<Context>, Error> {
+		if let Some(resources) = self.resources.as_ref() {
+			for (method_name, build) in self.method_resources.iter() {
+				for &(label, _) in build.iter() {
+					if !resources.labels.contains(&label) {
+						self.errors.push(Error::ResourceNameNotFoundForMethod(label, method_name));
+					}
+				}
+			}
+		}
+
+		let callbacks = self.module.methods.mut_callbacks();
+		for (method_name, build) in self.method_resources {
+			if let Some(callback) = callbacks.get_mut(method_name) {
+				callback.resources = MethodResources::Uninitialized(build[..].into());
+			}
+		}
+
+		for (alias, existing_method) in self.aliases {
+			if let Err(err) = self.module.register_alias(alias, existing_method) {
+				self.errors.push(err);
+			}
+		}
+
+		if self.errors.is_empty() {
+			Ok(self.module)
+		} else {
+			Err(Error::InvalidModule(self.errors))
+		}
+	}
+}
+
+/// Sends the chunks of the result of a streaming method call.
+///
+/// See [`RpcModule::register_streaming_method`] for details.
+#[derive(Debug, Clone)]
+pub struct StreamSink {
+	/// Sink.
+	inner: MethodSink,
+	/// Method name of the chunk notifications.
+	method: &'static str,
+	/// ID of the call.
+	id: Id<'static>,
+	/// Credit granted by the client, if the method is flow controlled.
+	credit: Option<Arc<StreamCredit>>,
+}
+
+/// How often a streaming call waiting for credit checks whether the connection was closed.
+const CREDIT_CLOSED_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Number of chunks a flow controlled streaming call may still send.
+#[derive(Debug, Default)]
+struct StreamCredit {
+	available: Mutex<u64>,
+	granted: Notify,
+}
+
+impl StreamCredit {
+	fn grant(&self, chunks: u64) {
+		let mut available = self.available.lock();
+		*available = available.saturating_add(chunks);
+		self.granted.notify_waiters();
+	}
+
+	/// Take one chunk of credit, returns `false` if none is left.
+	fn take(&self) -> bool {
+		let mut available = self.available.lock();
+		match available.checked_sub(1) {
+			Some(left) => {
+				*available = left;
+				true
+			}
+			None => false,
+		}
+	}
+}
+
+/// Credit of the flow controlled streaming calls in progress, keyed by connection and request ID.
+type StreamCredits = Arc<Mutex<FxHashMap<(ConnectionId, Id<'static>), Arc<StreamCredit>>>>;
+
+/// Registers the credit of a streaming call until dropped.
+struct StreamCreditGuard {
+	credits: StreamCredits,
+	key: (ConnectionId, Id<'static>),
+	credit: Arc<StreamCredit>,
+}
+
+impl StreamCreditGuard {
+	/// Register the credit of a streaming call, `None` if a streaming call with the same ID is in progress on the
+	/// connection.
+	fn new(credits: StreamCredits, key: (ConnectionId, Id<'static>), initial_credit: u64) -> Option<Self> {
+		let credit = Arc::new(StreamCredit { available: Mutex::new(initial_credit), granted: Notify::new() });
+		match credits.lock().entry(key.clone()) {
+			Entry::Occupied(_) => return None,
+			Entry::Vacant(entry) => entry.insert(credit.clone()),
+		};
+		Some(Self { credits, key, credit })
+	}
+}
+
+impl Drop for StreamCreditGuard {
+	fn drop(&mut self) {
+		self.credits.lock().remove(&self.key);
+	}
+}
+
+/// A message received while a streaming method call is in progress.
+enum StreamMessage<T> {
+	/// A chunk of the result.
+	Chunk(T),
+	/// The response to the call, terminating the stream.
+	End,
+}
+
+impl StreamSink {
+	/// Send a chunk of the result back to the caller.
+	pub fn send<T: Serialize>(&self, chunk: &T) -> Result<(), Error> {
+		let msg = serde_json::to_string(&StreamResponse::new(
+			self.method.into(),
+			StreamChunk { id: self.id.clone(), chunk },
+		))?;
+		self.inner.send_raw(msg).map_err(|e| Error::Internal(e.into_send_error()))
+	}
+
+	/// Send a chunk of the result back to the caller once the client granted credit for it, if the method is
+	/// flow controlled, see [`RpcModule::register_flow_controlled_streaming_method`].
+	///
+	/// Otherwise this is the same as [`StreamSink::send`]. Returns an error if the connection is closed while
+	/// waiting for credit.
+	pub async fn send_when_ready<T: Serialize>(&self, chunk: &T) -> Result<(), Error> {
+		if let Some(credit) = &self.credit {
+			loop {
+				// Created before taking credit to not miss a grant in between.
+				let granted = credit.granted.notified();
+				if credit.take() {
+					break;
+				}
+				if self.is_closed() {
+					return Err(Error::Custom("Connection closed while waiting for credit".into()));
+				}
+				let _ = tokio::time::timeout(CREDIT_CLOSED_CHECK_INTERVAL, granted).await;
+			}
+		}
+
+		self.send(chunk)
+	}
+
+	/// Returns whether the connection the call was made on is closed.
+	pub fn is_closed(&self) -> bool {
+		self.inner.is_closed()
+	}
+
+	/// Parse a message sent during a streaming method call, `None` if it is neither a chunk nor a
+	/// successful response to the call.
+	fn parse_message<T: DeserializeOwned>(msg: &str) -> Option<StreamMessage<T>> {
+		if let Ok(notif) = serde_json::from_str::<StreamResponse<T>>(msg) {
+			return Some(StreamMessage::Chunk(notif.params.chunk));
+		}
+		serde_json::from_str::<Response<serde_json::Value>>(msg).ok().map(|_| StreamMessage::End)
+	}
+}
+
+/// A subscription which is neither accepted nor rejected yet, see [`RpcModule::register_pending_subscription`].
+///
+/// If dropped without being accepted or rejected, the subscription call is answered with an error.
+#[derive(Debug)]
+pub struct PendingSubscription(Arc<Mutex<Option<InnerPendingSubscription>>>);
+
+#[derive(Debug)]
+struct InnerPendingSubscription {
+	/// Sink to answer the subscription call on.
+	sink: MethodSink,
+	/// ID of the subscription call.
+	id: Id<'static>,
+	/// Unique subscription, created if it's accepted.
+	uniq_sub: SubscriptionKey,
+	/// Get notified when the subscriber leaves.
+	close_notify: Arc<Notify>,
+	/// Method name of the notifications.
+	method: &'static str,
+	/// Shared Mutex of subscriptions for this method.
+	subscribers: Subscribers,
+	/// Key the subscription is reused with, see [`RpcModule::register_reusable_subscription`].
+	reuse: Option<(ReusableSubscriptions, ReuseKey)>,
+	/// Time after which the subscription is closed if idle.
+	idle_timeout: Option<Duration>,
+	/// Max number of notifications waiting to be delivered, and what to do when it's reached.
+	buffer: Option<(usize, LagPolicy)>,
+	/// Payload version requested by the subscriber.
+	version: Option<u32>,
+	/// Counts the subscription in the limit of its connection, if bounded.
+	permit: Option<SubscriptionPermit>,
+	/// Span the subscription call was executed in.
+	span: tracing::Span,
+	/// Where the sink is handed over if dropped during the setup of an async subscription, see [`SetupSlot`].
+	setup: Option<Weak<Mutex<Option<SubscriptionSink>>>>,
+}
+
+/// Sink of an async subscription dropped by its setup, which closes it once it knows whether the setup failed, see
+/// [`RpcModule::register_async_subscription`].
+type SetupSlot = Arc<Mutex<Option<SubscriptionSink>>>;
+
+/// How often the setup of an async subscription checks whether the connection was closed.
+const SETUP_CLOSED_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Resolves once the connection `sink` sends on is closed.
+async fn connection_closed(sink: &MethodSink) {
+	while !sink.is_closed() {
+		tokio::time::sleep(SETUP_CLOSED_CHECK_INTERVAL).await;
+	}
+}
+
+impl PendingSubscription {
+	/// Accept the subscription, answering the subscription call with the subscription ID.
+	///
+	/// Returns the sink to send notifications on, or `None` if the subscriber went away in the meantime.
+	pub fn accept(self) -> Option<SubscriptionSink> {
+		let InnerPendingSubscription {
+			sink,
+			id,
+			uniq_sub,
+			close_notify,
+			method,
+			subscribers,
+			reuse,
+			idle_timeout,
+			buffer,
+			version,
+			permit,
+			span,
+			setup,
+		} = self.0.lock().take()?;
+
+		let failed = |reuse: Option<(ReusableSubscriptions, ReuseKey)>| {
+			if let Some((reusable, key)) = reuse {
+				let err = ErrorCode::ServerError(CALL_EXECUTION_FAILED_CODE).into();
+				fail_waiting(take_waiting(&reusable, &key, &uniq_sub.sub_id), err);
+			}
+			None
+		};
+		if sink.is_closed() {
+			return failed(reuse);
+		}
+
+		let (conn_tx, conn_rx) = oneshot::channel::<()>();
+		let last_activity = idle_timeout.map(|_| Arc::new(Mutex::new(Instant::now())));
+		subscribers.lock().insert(uniq_sub.clone(), (sink.clone(), conn_rx, last_activity.clone(), permit));
+
+		if !sink.send_response(id, &uniq_sub.sub_id) {
+			subscribers.lock().remove(&uniq_sub);
+			return failed(reuse);
+		}
+		if let Some((reusable, key)) = &reuse {
+			let waiting = {
+				let mut reusable = reusable.lock();
+				match reusable.get_mut(key) {
+					Some(entry @ ReusableSubscription::Pending(..)) if entry.sub_id() == &uniq_sub.sub_id => {
+						match std::mem::replace(entry, ReusableSubscription::Active(uniq_sub.sub_id.clone())) {
+							ReusableSubscription::Pending(_, waiting) => waiting,
+							ReusableSubscription::Active(_) => unreachable!("matched as pending; qed"),
+						}
+					}
+					_ => Vec::new(),
+				}
+			};
+			for (id, sink) in waiting {
+				tracing::debug!("Reusing subscription {:?} for request id={:?}", uniq_sub.sub_id, id);
+				sink.send_response(id, &uniq_sub.sub_id);
+			}
+		}
+
+		if let (Some(idle_timeout), Some(last_activity)) = (idle_timeout, last_activity.clone()) {
+			tokio::spawn(close_when_idle(subscribers.clone(), uniq_sub.clone(), method, last_activity, idle_timeout));
+		}
 
-					sink.send_response(id, result)
-				})),
-			);
+		let pending = buffer.map(|(max, policy)| {
+			let pending = PendingNotifications { max, policy, seqs: VecDeque::new(), parked: VecDeque::new() };
+			Arc::new(SubscriptionBuffer {
+				pending: Mutex::new(pending),
+				sink: sink.clone(),
+				subscribers: subscribers.clone(),
+				uniq_sub: uniq_sub.clone(),
+			})
+		});
+		if let Some(buffer) = pending.as_ref().filter(|buffer| buffer.pending.lock().policy == LagPolicy::DropOldest) {
+			let buffer: Arc<dyn DeliveryListener> = buffer.clone();
+			sink.add_delivery_listener(Arc::downgrade(&buffer));
 		}
 
-		Ok(())
+		Some(SubscriptionSink {
+			inner: sink,
+			close_notify: Some(close_notify),
+			method,
+			subscribers,
+			uniq_sub,
+			is_connected: Some(conn_tx),
+			last_activity,
+			pending,
+			reuse,
+			version,
+			span,
+			setup,
+		})
 	}
 
-	/// Register an alias for an existing_method. Alias uniqueness is enforced.
-	pub fn register_alias(&mut self, alias: &'static str, existing_method: &'static str) -> Result<(), Error> {
-		self.methods.verify_method_name(alias)?;
+	/// Reject the subscription, answering the subscription call with the given error instead of a subscription ID.
+	pub fn reject(self, err: impl Into<Error>) {
+		if let Some(inner) = self.0.lock().take() {
+			inner.fail(err.into());
+		}
+	}
 
-		let callback = match self.methods.callbacks.get(existing_method) {
-			Some(callback) => callback.clone(),
-			None => return Err(Error::MethodNotFound(existing_method.into())),
-		};
+	/// Returns the payload version requested by the subscriber, see [`SubscriptionSink::version`].
+	pub fn version(&self) -> Option<u32> {
+		self.0.lock().as_ref().and_then(|inner| inner.version)
+	}
+}
 
-		self.methods.mut_callbacks().insert(alias, callback);
+impl Drop for PendingSubscription {
+	fn drop(&mut self) {
+		let mut inner = self.0.lock();
+		// The setup of an async subscription answers the call once it knows whether it failed.
+		let unanswered = if inner.as_ref().is_some_and(|inner| inner.setup.is_none()) { inner.take() } else { None };
+		drop(inner);
+		if let Some(inner) = unanswered {
+			inner.fail_unanswered();
+		}
+	}
+}
 
-		Ok(())
+impl InnerPendingSubscription {
+	/// Answer the subscription call and the equivalent calls waiting for it with a generic error, as it was neither
+	/// accepted nor rejected.
+	fn fail_unanswered(self) {
+		tracing::warn!("Subscription call with id {:?} was neither accepted nor rejected", self.id);
+		let err: ErrorObject = ErrorCode::ServerError(CALL_EXECUTION_FAILED_CODE).into();
+		if let Some((reusable, key)) = &self.reuse {
+			fail_waiting(take_waiting(reusable, key, &self.uniq_sub.sub_id), err.clone());
+		}
+		self.sink.send_error(self.id, err);
 	}
+
+	/// Answer the subscription call and the equivalent calls waiting for it with the given error.
+	fn fail(self, err: Error) {
+		let frame = self.sink.send_call_error_with_frame(self.id, err);
+		if let Some((reusable, key)) = &self.reuse {
+			let waiting = take_waiting(reusable, key, &self.uniq_sub.sub_id);
+			match frame.as_deref().map(serde_json::from_str::<ErrorResponse>) {
+				Ok(Ok(response)) => fail_waiting(waiting, response.error),
+				_ => fail_waiting(waiting, ErrorCode::ServerError(CALL_EXECUTION_FAILED_CODE).into()),
+			}
+		}
+	}
+}
+
+impl ReusableSubscription {
+	fn sub_id(&self) -> &RpcSubscriptionId<'static> {
+		match self {
+			Self::Pending(sub_id, _) | Self::Active(sub_id) => sub_id,
+		}
+	}
+}
+
+/// Remove the reusable subscription `sub_id` if it's still pending, returning the equivalent subscription calls waiting
+/// for it.
+fn take_waiting(
+	reusable: &ReusableSubscriptions,
+	key: &ReuseKey,
+	sub_id: &RpcSubscriptionId<'static>,
+) -> Vec<(Id<'static>, MethodSink)> {
+	let mut reusable = reusable.lock();
+	match reusable.get(key) {
+		Some(ReusableSubscription::Pending(pending, _)) if pending == sub_id => match reusable.remove(key) {
+			Some(ReusableSubscription::Pending(_, waiting)) => waiting,
+			_ => unreachable!("matched as pending; qed"),
+		},
+		_ => Vec::new(),
+	}
+}
+
+/// Answer the subscription calls waiting for an equivalent subscription that failed with the given error.
+fn fail_waiting(waiting: Vec<(Id<'static>, MethodSink)>, err: ErrorObject) {
+	for (id, sink) in waiting {
+		sink.send_error(id, err.clone());
+	}
+}
+
+/// What [`SubscriptionSink::pipe_from_try_stream`] does with an item that is an error or fails to be serialized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamErrorPolicy {
+	/// Close the subscription, sending the error as the reason to the subscriber.
+	Close,
+	/// Skip the item and keep piping the stream.
+	Skip,
+	/// Stop piping the stream and return the error.
+	Abort,
 }
 
 /// Represents a single subscription.
@@ -748,16 +4188,194 @@ pub struct SubscriptionSink {
 	///
 	/// None - implies that the subscription as been closed.
 	is_connected: Option<oneshot::Sender<()>>,
+	/// Time of the last activity, only tracked if idle subscriptions time out.
+	last_activity: Option<LastActivity>,
+	/// Notifications waiting to be delivered, only tracked if their number is limited. Kept alive here while the
+	/// transport forwards the parked notifications, see [`LagPolicy::DropOldest`].
+	pending: Option<Arc<SubscriptionBuffer>>,
+	/// Key the subscription is reused with, removed when the subscription is closed.
+	reuse: Option<(ReusableSubscriptions, ReuseKey)>,
+	/// Payload version requested by the subscriber.
+	version: Option<u32>,
+	/// Span the subscription call was executed in, the notifications are traced under it.
+	span: tracing::Span,
+	/// Where the sink is handed over if dropped during the setup of an async subscription, see [`SetupSlot`].
+	setup: Option<Weak<Mutex<Option<SubscriptionSink>>>>,
+}
+
+/// Notifications of a subscription waiting to be delivered to the client.
+#[derive(Debug)]
+struct PendingNotifications {
+	/// Max number of notifications waiting to be delivered.
+	max: usize,
+	/// What to do with the notifications sent while `max` notifications are waiting to be delivered.
+	policy: LagPolicy,
+	/// Sequence numbers of the notifications sent on the [`MethodSink`] that were possibly not delivered yet.
+	seqs: VecDeque<u64>,
+	/// Notifications not sent on the [`MethodSink`] yet because the transport has its share of the buffer, only
+	/// used with [`LagPolicy::DropOldest`].
+	parked: VecDeque<String>,
+}
+
+impl PendingNotifications {
+	/// Max number of notifications handed to the transport. With [`LagPolicy::DropOldest`], one slot of the buffer
+	/// is kept for the parked notifications, which can be dropped unlike the ones handed to the transport.
+	fn max_sent(&self) -> usize {
+		match self.policy {
+			LagPolicy::DropOldest if self.max > 1 => self.max - 1,
+			_ => self.max,
+		}
+	}
+
+	/// Forget the notifications delivered by the transport, returns whether the transport has its share of the
+	/// buffer, see [`PendingNotifications::max_sent`].
+	fn is_full(&mut self, delivered: u64) -> bool {
+		while self.seqs.front().is_some_and(|&seq| seq <= delivered) {
+			self.seqs.pop_front();
+		}
+		self.seqs.len() >= self.max_sent()
+	}
+
+	/// Send the parked notifications on `sink` while there's room for them.
+	fn forward_parked(&mut self, sink: &MethodSink) {
+		let delivered = match sink.delivered() {
+			Some(delivered) => delivered,
+			None => return,
+		};
+		while !self.parked.is_empty() && !self.is_full(delivered) {
+			let msg = self.parked.pop_front().expect("checked above; qed");
+			match sink.send_raw_tracked(msg) {
+				Ok(Some(seq)) => self.seqs.push_back(seq),
+				Ok(None) => {}
+				Err(_) => self.parked.clear(),
+			}
+		}
+	}
+}
+
+/// Buffer of a subscription, which forwards its parked notifications as the transport delivers the others.
+#[derive(Debug)]
+struct SubscriptionBuffer {
+	pending: Mutex<PendingNotifications>,
+	/// Sink the parked notifications are sent on.
+	sink: MethodSink,
+	subscribers: Subscribers,
+	uniq_sub: SubscriptionKey,
+}
+
+impl DeliveryListener for SubscriptionBuffer {
+	fn on_delivered(&self) {
+		let mut pending = self.pending.lock();
+		if pending.parked.is_empty() {
+			return;
+		}
+		if self.sink.is_closed() || !self.subscribers.lock().contains_key(&self.uniq_sub) {
+			pending.parked.clear();
+		} else {
+			pending.forward_parked(&self.sink);
+		}
+	}
+}
+
+/// What to do with the notifications sent on a subscription whose buffer is full, that is when the client lags
+/// behind, see [`RpcModule::register_subscription_with_buffer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LagPolicy {
+	/// Queue the notification and drop the oldest one not sent to the client yet.
+	///
+	/// The notifications handed to the transport can't be dropped anymore, so only `capacity - 1` of them are,
+	/// while the others are kept in the subscription and sent as the client catches up. With a `capacity` of 1
+	/// there's no room to keep any and the notification is dropped, like with [`LagPolicy::DropNewest`].
+	DropOldest,
+	/// Drop the notification.
+	DropNewest,
+	/// Close the subscription with [`SubscriptionClosedReason::Server`]`("slow consumer")`.
+	Close,
+}
+
+/// What was done with a notification sent with [`SubscriptionSink::try_send`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SendOutcome {
+	/// The notification was queued to be delivered to the client.
+	Sent,
+	/// The buffer of the subscription was full and the notification was dropped, see [`LagPolicy::DropNewest`].
+	Dropped,
+	/// The buffer of the subscription was full and its oldest notification was dropped to queue this one, see
+	/// [`LagPolicy::DropOldest`].
+	DroppedOldest,
+}
+
+/// What to do with a notification about to be sent, given the buffer of the subscription.
+enum Admission {
+	Send(String),
+	Parked(SendOutcome),
+	Dropped,
+	Close,
 }
 
 impl SubscriptionSink {
 	/// Send a message back to subscribers.
 	pub fn send<T: Serialize>(&mut self, result: &T) -> Result<(), Error> {
+		self.try_send(result).map(|_| ())
+	}
+
+	/// Send a message back to subscribers like [`SubscriptionSink::send`], returning what was done with it if the
+	/// buffer of the subscription is full, see [`RpcModule::register_subscription_with_buffer`].
+	///
+	/// With [`LagPolicy::Close`], the subscription is closed and this fails instead.
+	pub fn try_send<T: Serialize>(&mut self, result: &T) -> Result<SendOutcome, Error> {
 		if self.is_closed() {
 			return Err(Error::SubscriptionClosed(SubscriptionClosedReason::ConnectionReset.into()));
 		}
 		let msg = self.build_message(result)?;
-		self.inner_send(msg).map_err(Into::into)
+		self.inner_send(msg)
+	}
+
+	/// Send a result that is already serialized, for instance relayed from another server, which is written as is
+	/// in the notification instead of being parsed and serialized again.
+	///
+	/// A [`RawValue`] is valid JSON, use [`RawValue::from_string`] to check a string before sending it.
+	pub fn send_raw(&mut self, result: &RawValue) -> Result<(), Error> {
+		self.send(&result)
+	}
+
+	/// Send a notification prepared once for many subscribers, see [`PreparedNotification`].
+	///
+	/// Fails if the notification was prepared for another method than the one of this subscription.
+	pub fn send_prepared(&mut self, prepared: &PreparedNotification) -> Result<(), Error> {
+		if self.is_closed() {
+			return Err(Error::SubscriptionClosed(SubscriptionClosedReason::ConnectionReset.into()));
+		}
+		if &*prepared.method != self.method {
+			return Err(Error::Custom(format!(
+				"Notification prepared for `{}` sent on a subscription to `{}`",
+				prepared.method, self.method
+			)));
+		}
+		let msg = prepared.frame(&self.uniq_sub.sub_id)?;
+		self.inner_send(msg).map(|_| ())
+	}
+
+	/// Send an error to the subscribers without closing the subscription, for instance to report a transient failure
+	/// of the feed.
+	///
+	/// The notification has an `error` member instead of the `result` member of the notifications sent by
+	/// [`SubscriptionSink::send`] and [`SubscriptionSink::close`]:
+	///
+	/// ```json
+	/// {"jsonrpc":"2.0","method":"new_heads","params":{"subscription":1,"error":{"message":"Skipped block 12"}}}
+	/// ```
+	///
+	/// The subscriptions of the clients yield it as [`Error::SubscriptionError`] and keep receiving the notifications.
+	pub fn send_error<E: Serialize>(&mut self, err: &E) -> Result<(), Error> {
+		if self.is_closed() {
+			return Err(Error::SubscriptionClosed(SubscriptionClosedReason::ConnectionReset.into()));
+		}
+		let msg = serde_json::to_string(&SubscriptionErrorResponse::new(
+			self.method.into(),
+			SubscriptionErrorPayload { subscription: self.uniq_sub.sub_id.clone(), error: err },
+		))?;
+		self.inner_send(msg).map(|_| ())
 	}
 
 	/// Consumes the `SubscriptionSink` and reads data from the `stream` and sends back data on the subscription
@@ -779,10 +4397,37 @@ impl SubscriptionSink {
 	///     Ok(())
 	/// });
 	/// ```
-	pub async fn pipe_from_stream<S, T>(mut self, mut stream: S) -> Result<(), Error>
+	pub async fn pipe_from_stream<S, T>(self, stream: S) -> Result<(), Error>
 	where
 		S: Stream<Item = T> + Unpin,
 		T: Serialize,
+	{
+		self.pipe_from_try_stream(stream.map(Ok::<_, Infallible>), StreamErrorPolicy::Abort).await
+	}
+
+	/// Like [`SubscriptionSink::pipe_from_stream`], for a stream of fallible items.
+	///
+	/// The `policy` decides what to do with an item that is an error or fails to be serialized.
+	/// Returns `Err(_)` if an item failed and the policy is [`StreamErrorPolicy::Abort`], `Ok(())` otherwise.
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	///
+	/// use jsonrpsee_core::server::rpc_module::{RpcModule, StreamErrorPolicy};
+	///
+	/// let mut m = RpcModule::new(());
+	/// m.register_subscription("sub", "_", "unsub", |params, mut sink, _| {
+	///     let stream = futures_util::stream::iter(vec![Ok(1_u32), Err("Missing block"), Ok(3)]);
+	///     tokio::spawn(sink.pipe_from_try_stream(stream, StreamErrorPolicy::Skip));
+	///     Ok(())
+	/// });
+	/// ```
+	pub async fn pipe_from_try_stream<S, T, E>(mut self, mut stream: S, policy: StreamErrorPolicy) -> Result<(), Error>
+	where
+		S: Stream<Item = Result<T, E>> + Unpin,
+		T: Serialize,
+		E: fmt::Display,
 	{
 		if let Some(close_notify) = self.close_notify.clone() {
 			let mut stream_item = stream.next();
@@ -791,17 +4436,40 @@ impl SubscriptionSink {
 			loop {
 				match futures_util::future::select(stream_item, closed_fut).await {
 					// The app sent us a value to send back to the subscribers
-					Either::Left((Some(result), next_closed_fut)) => {
-						match self.send(&result) {
-							Ok(_) => (),
-							Err(Error::SubscriptionClosed(close_reason)) => {
-								self.close(&close_reason);
-								break Ok(());
+					Either::Left((Some(item), next_closed_fut)) => {
+						let failure = match item {
+							Ok(result) => {
+								self.inner.wait_for_room().await;
+								match self.send(&result) {
+									Ok(_) => None,
+									Err(Error::SubscriptionClosed(close_reason)) => {
+										self.close(&close_reason);
+										break Ok(());
+									}
+									Err(err) => Some((err.to_string(), err)),
+								}
 							}
 							Err(err) => {
-								break Err(err);
+								let reason = err.to_string();
+								Some((reason.clone(), Error::Custom(reason)))
 							}
 						};
+						if let Some((reason, err)) = failure {
+							match policy {
+								StreamErrorPolicy::Close => {
+									self.close(&SubscriptionClosedReason::Server(reason).into());
+									break Ok(());
+								}
+								StreamErrorPolicy::Skip => {
+									tracing::debug!(
+										"Skipping item of subscription {:?}: {}",
+										self.uniq_sub.sub_id,
+										reason
+									);
+								}
+								StreamErrorPolicy::Abort => break Err(err),
+							}
+						}
 						stream_item = stream.next();
 						closed_fut = next_closed_fut;
 					}
@@ -825,19 +4493,101 @@ impl SubscriptionSink {
 		self.inner.is_closed() || self.close_notify.is_none()
 	}
 
+	/// Returns the version of the notification payloads requested by the subscriber, if any.
+	///
+	/// By convention, the subscriber requests a version with a `version` field holding an unsigned integer in the
+	/// subscribe params, which must then be a JSON object. The handler can branch on it to serialize payloads the
+	/// subscriber understands, keeping older subscribers working as the payloads evolve:
+	///
+	/// ```
+	/// use jsonrpsee_core::server::rpc_module::RpcModule;
+	/// use serde_json::json;
+	///
+	/// let mut module = RpcModule::new(());
+	/// module.register_subscription("sub_block", "s_block", "unsub_block", |_, mut sink, _| {
+	///     let payload = match sink.version() {
+	///         Some(v) if v >= 2 => json!({ "number": 1, "hash": "0xab" }),
+	///         _ => json!(1),
+	///     };
+	///     sink.send(&payload)?;
+	///     Ok(())
+	/// }).unwrap();
+	/// ```
+	pub fn version(&self) -> Option<u32> {
+		self.version
+	}
+
 	fn build_message<T: Serialize>(&self, result: &T) -> Result<String, Error> {
-		serde_json::to_string(&SubscriptionResponse::new(
-			self.method.into(),
-			SubscriptionPayload { subscription: self.uniq_sub.sub_id.clone(), result },
-		))
-		.map_err(Into::into)
+		build_subscription_message(self.method, &self.uniq_sub.sub_id, result)
+	}
+
+	/// Apply the [`LagPolicy`] of the subscription to a notification about to be sent.
+	fn admit(&mut self, msg: String) -> Admission {
+		let pending = match &self.pending {
+			Some(pending) if self.is_connected.is_some() => pending,
+			_ => return Admission::Send(msg),
+		};
+		let mut guard = pending.pending.lock();
+		// Read with the lock held, a delivery after this forwards the notification parked below.
+		let delivered = match self.inner.delivered() {
+			Some(delivered) => delivered,
+			None => return Admission::Send(msg),
+		};
+
+		// The parked notifications go first.
+		if guard.parked.is_empty() && !guard.is_full(delivered) {
+			return Admission::Send(msg);
+		}
+
+		match guard.policy {
+			LagPolicy::Close => Admission::Close,
+			LagPolicy::DropNewest => Admission::Dropped,
+			LagPolicy::DropOldest => {
+				let outcome = if guard.seqs.len() + guard.parked.len() < guard.max {
+					SendOutcome::Sent
+				} else if guard.parked.pop_front().is_some() {
+					SendOutcome::DroppedOldest
+				} else {
+					return Admission::Dropped;
+				};
+				guard.parked.push_back(msg);
+				Admission::Parked(outcome)
+			}
+		}
 	}
 
-	fn inner_send(&mut self, msg: String) -> Result<(), Error> {
+	fn inner_send(&mut self, msg: String) -> Result<SendOutcome, Error> {
+		let _span = (!self.span.is_none())
+			.then(|| tracing::info_span!(parent: &self.span, "rpc_notification", rpc.method = self.method).entered());
+		let msg = match self.admit(msg) {
+			Admission::Send(msg) => msg,
+			Admission::Parked(outcome) => return Ok(outcome),
+			Admission::Dropped => {
+				tracing::debug!("Dropping notification of slow consumer: {:?}", self.uniq_sub.sub_id);
+				return Ok(SendOutcome::Dropped);
+			}
+			Admission::Close => {
+				tracing::debug!("Closing subscription of slow consumer: {:?}", self.uniq_sub.sub_id);
+				self.close_with_custom_message("slow consumer");
+				return Err(Error::SubscriptionClosed(SubscriptionClosedReason::Server("slow consumer".into()).into()));
+			}
+		};
+
 		let res = match self.is_connected.as_ref() {
 			Some(conn) if !conn.is_canceled() => {
+				if let Some(last_activity) = &self.last_activity {
+					*last_activity.lock() = Instant::now();
+				}
 				// unbounded send only fails if the receiver has been dropped.
-				self.inner.send_raw(msg).map_err(|_| Some(SubscriptionClosedReason::ConnectionReset))
+				match self.inner.send_raw_tracked(msg) {
+					Ok(seq) => {
+						if let (Some(pending), Some(seq)) = (&self.pending, seq) {
+							pending.pending.lock().seqs.push_back(seq);
+						}
+						Ok(SendOutcome::Sent)
+					}
+					Err(_) => Err(Some(SubscriptionClosedReason::ConnectionReset)),
+				}
 			}
 			Some(_) => Err(Some(SubscriptionClosedReason::Unsubscribed)),
 			// NOTE(niklasad1): this should be unreachable, after the first error is detected the subscription is closed.
@@ -869,11 +4619,23 @@ impl SubscriptionSink {
 
 	fn inner_close(&mut self, close_reason: Option<&SubscriptionClosed>) {
 		self.is_connected.take();
-		if let Some((sink, _)) = self.subscribers.lock().remove(&self.uniq_sub) {
+		if let Some((reusable, key)) = self.reuse.take() {
+			let mut reusable = reusable.lock();
+			if reusable.get(&key).map(ReusableSubscription::sub_id) == Some(&self.uniq_sub.sub_id) {
+				reusable.remove(&key);
+			}
+		}
+		let removed = self.subscribers.lock().remove(&self.uniq_sub);
+		if let Some((sink, ..)) = removed {
 			tracing::debug!("Closing subscription: {:?} reason: {:?}", self.uniq_sub.sub_id, close_reason);
+			// The parked notifications are sent before the close notification, regardless of the buffer.
+			if let Some(pending) = &self.pending {
+				for msg in pending.pending.lock().parked.drain(..) {
+					let _ = sink.send_raw(msg);
+				}
+			}
 			if let Some(close_reason) = close_reason {
-				let msg = self.build_message(close_reason).expect("valid json infallible; qed");
-				let _ = sink.send_raw(msg);
+				let _ = sink.send_raw(build_close_message(&sink, self.method, &self.uniq_sub.sub_id, close_reason));
 			}
 		}
 	}
@@ -881,17 +4643,304 @@ impl SubscriptionSink {
 
 impl Drop for SubscriptionSink {
 	fn drop(&mut self) {
+		if let Some(dropped_sink) = self.setup.take().and_then(|slot| slot.upgrade()) {
+			*dropped_sink.lock() = Some(SubscriptionSink {
+				inner: self.inner.clone(),
+				close_notify: self.close_notify.take(),
+				method: self.method,
+				uniq_sub: self.uniq_sub.clone(),
+				subscribers: self.subscribers.clone(),
+				is_connected: self.is_connected.take(),
+				last_activity: self.last_activity.take(),
+				pending: self.pending.take(),
+				reuse: self.reuse.take(),
+				version: self.version,
+				span: self.span.clone(),
+				setup: None,
+			});
+			return;
+		}
 		let err = SubscriptionClosedReason::Server("No close reason provided".into()).into();
 		self.inner_close(Some(&err));
 	}
 }
 
+/// A subscription notification whose result is serialized once, to be sent to many subscribers with
+/// [`SubscriptionSink::send_prepared`] without serializing it again for each of them.
+///
+/// The frame sent by [`SubscriptionSink::send`] only differs across subscribers by the subscription ID, so the frame
+/// is kept as the part before the ID, `{"jsonrpc":"2.0","method":<method>,"params":{"subscription":`, and the part
+/// after it, `,"result":<result>}}`, and the serialized ID of each subscriber is spliced in between. This gives the
+/// same frame as [`SubscriptionSink::send`].
+///
+/// Clones share the serialized frame.
+#[derive(Debug, Clone)]
+pub struct PreparedNotification {
+	method: Arc<str>,
+	/// Frame up to the subscription ID.
+	head: Arc<str>,
+	/// Frame after the subscription ID.
+	tail: Arc<str>,
+}
+
+impl PreparedNotification {
+	/// Prepare a notification of the given method with the given result.
+	pub fn new<T: Serialize>(method: &str, result: &T) -> Result<Self, Error> {
+		Self::with_serialized_result(method, &serde_json::to_string(result)?)
+	}
+
+	/// Prepare a notification of the given method with a result that is already serialized.
+	fn with_serialized_result(method: &str, result: &str) -> Result<Self, Error> {
+		let head =
+			format!(r#"{{"jsonrpc":"2.0","method":{},"params":{{"subscription":"#, serde_json::to_string(method)?);
+		let tail = format!(r#","result":{}}}}}"#, result);
+		Ok(Self { method: method.into(), head: head.into(), tail: tail.into() })
+	}
+
+	/// Returns the method of the notification.
+	pub fn method(&self) -> &str {
+		&self.method
+	}
+
+	/// Build the frame of the notification for the given subscription.
+	fn frame(&self, sub_id: &RpcSubscriptionId) -> Result<String, Error> {
+		let mut frame = String::with_capacity(self.head.len() + self.tail.len() + 24);
+		frame.push_str(&self.head);
+		frame.push_str(&serde_json::to_string(sub_id)?);
+		frame.push_str(&self.tail);
+		Ok(frame)
+	}
+}
+
+/// The subscribers of a feed, to send each item to all of them, or to the ones matching a filter, serializing the
+/// item once instead of once per subscriber as [`SubscriptionSink::send`] does.
+///
+/// Each subscriber is added with a key, for instance the topic it subscribed to, which the filter of
+/// [`SubscriptionFanout::send_matching`] is given. The subscriptions that are closed are removed when an item is sent.
+///
+/// Clones share the subscribers, so the fanout can be kept in the context of the module to add the subscribers from
+/// the subscription callback, and by the producer of the feed to send the items.
+pub struct SubscriptionFanout<K = ()> {
+	subscribers: Arc<Mutex<Vec<(K, SubscriptionSink)>>>,
+}
+
+impl<K> SubscriptionFanout<K> {
+	/// Create a fanout without subscribers.
+	pub fn new() -> Self {
+		Self { subscribers: Arc::new(Mutex::new(Vec::new())) }
+	}
+
+	/// Add a subscriber with the given key.
+	pub fn add(&self, key: K, sink: SubscriptionSink) {
+		self.subscribers.lock().push((key, sink));
+	}
+
+	/// Returns the number of subscribers, including the ones closed since the last item was sent.
+	pub fn len(&self) -> usize {
+		self.subscribers.lock().len()
+	}
+
+	/// Returns `true` if there are no subscribers.
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// Send an item to all the subscribers, see [`SubscriptionFanout::send_matching`].
+	pub fn send<T: Serialize>(&self, item: &T) -> Result<usize, Error> {
+		self.send_matching(item, |_| true)
+	}
+
+	/// Send an item to the subscribers whose key matches the filter, and returns the number of subscribers it was
+	/// sent to.
+	///
+	/// The item is serialized once, and the frame is only rebuilt for each subscriber to splice in its subscription
+	/// ID, see [`PreparedNotification`]. The subscribers the item couldn't be sent to are closed, and removed with
+	/// the next item.
+	pub fn send_matching<T: Serialize>(&self, item: &T, mut filter: impl FnMut(&K) -> bool) -> Result<usize, Error> {
+		let result = serde_json::to_string(item)?;
+		// Subscribers added by the same subscription method share the notification.
+		let mut prepared: Vec<PreparedNotification> = Vec::new();
+		let mut sent = 0;
+		let mut subscribers = self.subscribers.lock();
+		subscribers.retain(|(_, sink)| !sink.is_closed());
+		for (_, sink) in subscribers.iter_mut().filter(|(key, _)| filter(key)) {
+			let idx = match prepared.iter().position(|p| &*p.method == sink.method) {
+				Some(idx) => idx,
+				None => {
+					prepared.push(PreparedNotification::with_serialized_result(sink.method, &result)?);
+					prepared.len() - 1
+				}
+			};
+			if sink.send_prepared(&prepared[idx]).is_ok() {
+				sent += 1;
+			}
+		}
+		Ok(sent)
+	}
+}
+
+impl<K> Clone for SubscriptionFanout<K> {
+	fn clone(&self) -> Self {
+		Self { subscribers: self.subscribers.clone() }
+	}
+}
+
+impl<K> Default for SubscriptionFanout<K> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<K> std::fmt::Debug for SubscriptionFanout<K> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("SubscriptionFanout").field("subscribers", &self.len()).finish()
+	}
+}
+
+/// Adapt a subscription callback to be invoked with the sink of the subscription, accepted beforehand.
+fn accept_first<Context, F>(callback: F) -> impl Fn(Params, PendingSubscription, Arc<Context>) -> Result<(), Error>
+where
+	F: Fn(Params, SubscriptionSink, Arc<Context>) -> Result<(), Error>,
+{
+	move |params, pending, ctx| match pending.accept() {
+		Some(sink) => callback(params, sink, ctx),
+		// The subscriber went away.
+		None => Ok(()),
+	}
+}
+
+/// The `version` field of the subscribe params, see [`SubscriptionSink::version`].
+#[derive(Deserialize)]
+struct SubscriptionVersion {
+	version: Option<u32>,
+}
+
+fn subscription_version(params: &Params) -> Option<u32> {
+	if !params.is_object() {
+		return None;
+	}
+	params.parse_map::<SubscriptionVersion>().ok()?.version
+}
+
+fn build_subscription_message<T: Serialize>(
+	method: &str,
+	sub_id: &RpcSubscriptionId<'static>,
+	result: &T,
+) -> Result<String, Error> {
+	serde_json::to_string(&SubscriptionResponse::new(
+		method.into(),
+		SubscriptionPayload { subscription: sub_id.clone(), result },
+	))
+	.map_err(Into::into)
+}
+
+/// Build the notification closing a subscription, with the discriminator if `sink` opted in to it.
+fn build_close_message(
+	sink: &MethodSink,
+	method: &str,
+	sub_id: &RpcSubscriptionId<'static>,
+	close_reason: &SubscriptionClosed,
+) -> String {
+	let msg = if sink.close_discriminator() && !close_reason.has_discriminator() {
+		let reason = close_reason.close_reason().clone();
+		build_subscription_message(method, sub_id, &SubscriptionClosed::new(reason).with_discriminator())
+	} else {
+		build_subscription_message(method, sub_id, close_reason)
+	};
+	msg.expect("valid json infallible; qed")
+}
+
+/// Answer the subscription call with a timeout error if it is neither accepted nor rejected after `setup_timeout`.
+async fn reject_when_late(pending: std::sync::Weak<Mutex<Option<InnerPendingSubscription>>>, setup_timeout: Duration) {
+	tokio::time::sleep(setup_timeout).await;
+
+	let inner = match pending.upgrade() {
+		Some(pending) => pending.lock().take(),
+		None => return,
+	};
+	if let Some(inner) = inner {
+		tracing::debug!("Subscription call with id {:?} timed out after {:?}", inner.id, setup_timeout);
+		inner.fail(Error::RequestTimeout);
+	}
+}
+
+/// Close the subscription once it was idle for `idle_timeout`, exits early if it is closed in the meantime.
+async fn close_when_idle(
+	subscribers: Subscribers,
+	uniq_sub: SubscriptionKey,
+	method: &'static str,
+	last_activity: LastActivity,
+	idle_timeout: Duration,
+) {
+	loop {
+		let deadline = *last_activity.lock() + idle_timeout;
+		tokio::time::sleep_until(deadline.into()).await;
+
+		let mut subscribers = subscribers.lock();
+
+		if !subscribers.contains_key(&uniq_sub) {
+			return;
+		}
+
+		if last_activity.lock().elapsed() < idle_timeout {
+			continue;
+		}
+
+		// Removing the subscription makes the `SubscriptionSink` fail to send.
+		if let Some((sink, ..)) = subscribers.remove(&uniq_sub) {
+			tracing::debug!("Closing idle subscription: {:?}", uniq_sub.sub_id);
+			let close_reason: SubscriptionClosed = SubscriptionClosedReason::Server("idle timeout".into()).into();
+			let _ = sink.send_raw(build_close_message(&sink, method, &uniq_sub.sub_id, &close_reason));
+		}
+
+		return;
+	}
+}
+
+/// Close all the given subscriptions with [`SubscriptionClosedReason::Server`] and the given message.
+fn close_subscriptions(subscribers: &Subscribers, method: &'static str, message: &str) {
+	let close_reason: SubscriptionClosed = SubscriptionClosedReason::Server(message.into()).into();
+
+	// Removing the subscriptions makes their `SubscriptionSink` fail to send.
+	for (uniq_sub, (sink, ..)) in subscribers.lock().drain() {
+		tracing::debug!("Closing subscription {:?}: {}", uniq_sub.sub_id, message);
+		let _ = sink.send_raw(build_close_message(&sink, method, &uniq_sub.sub_id, &close_reason));
+	}
+}
+
+/// Next message of a [`Subscription`], see [`Subscription::next`].
+#[derive(Debug)]
+pub enum SubNext<T> {
+	/// Notification decoded as `T`, along with the subscription ID.
+	Item(T, RpcSubscriptionId<'static>),
+	/// Error sent with [`SubscriptionSink::send_error`], the subscription stays open.
+	Error(JsonValue),
+	/// The subscription was closed for the given reason, either by the server or with [`Subscription::close`] which
+	/// is reported as [`SubscriptionClosedReason::ConnectionReset`].
+	Closed(SubscriptionClosed),
+	/// The notification couldn't be decoded as `T`.
+	DecodeError(serde_json::Error),
+	/// No more messages will be received.
+	Ended,
+}
+
+impl<T> SubNext<T> {
+	/// Returns the decoded notification and the subscription ID, `None` for the other messages.
+	pub fn into_item(self) -> Option<(T, RpcSubscriptionId<'static>)> {
+		match self {
+			SubNext::Item(item, sub_id) => Some((item, sub_id)),
+			_ => None,
+		}
+	}
+}
+
 /// Wrapper struct that maintains a subscription "mainly" for testing.
 #[derive(Debug)]
 pub struct Subscription {
 	close_notify: Option<Arc<Notify>>,
 	rx: mpsc::UnboundedReceiver<String>,
 	sub_id: RpcSubscriptionId<'static>,
+	delivered: DeliveryCounter,
 }
 
 impl Subscription {
@@ -907,26 +4956,51 @@ impl Subscription {
 		&self.sub_id
 	}
 
-	/// Returns `Some((val, sub_id))` for the next element of type T from the underlying stream,
-	/// otherwise `None` if the subscription was closed.
-	///
-	/// # Panics
-	///
-	/// If the decoding the value as `T` fails.
-	pub async fn next<T: DeserializeOwned>(&mut self) -> Option<Result<(T, RpcSubscriptionId<'static>), Error>> {
+	/// Get an owned copy of the subscription ID.
+	pub fn subscription_id_owned(&self) -> RpcSubscriptionId<'static> {
+		self.sub_id.clone()
+	}
+
+	/// Returns the subscription ID as a number if it's numeric, otherwise `None`.
+	pub fn id_as_u64(&self) -> Option<u64> {
+		match self.sub_id {
+			RpcSubscriptionId::Num(id) => Some(id),
+			RpcSubscriptionId::Str(_) => None,
+		}
+	}
+
+	/// Returns the subscription ID as a string slice if it's a string, otherwise `None`.
+	pub fn id_as_str(&self) -> Option<&str> {
+		match &self.sub_id {
+			RpcSubscriptionId::Str(id) => Some(id.as_ref()),
+			RpcSubscriptionId::Num(_) => None,
+		}
+	}
+
+	/// Returns the next message of the subscription, with the notifications decoded as `T`.
+	pub async fn next<T: DeserializeOwned>(&mut self) -> SubNext<T> {
 		if self.close_notify.is_none() {
 			tracing::debug!("[Subscription::next] Closed.");
-			return Some(Err(Error::SubscriptionClosed(SubscriptionClosedReason::ConnectionReset.into())));
-		}
-		let raw = self.rx.next().await?;
-		let res = match serde_json::from_str::<SubscriptionResponse<T>>(&raw) {
-			Ok(r) => Ok((r.params.result, r.params.subscription.into_owned())),
-			Err(_) => match serde_json::from_str::<SubscriptionResponse<SubscriptionClosed>>(&raw) {
-				Ok(e) => Err(Error::SubscriptionClosed(e.params.result)),
-				Err(e) => Err(e.into()),
-			},
+			return SubNext::Closed(SubscriptionClosedReason::ConnectionReset.into());
+		}
+		let raw = match self.rx.next().await {
+			Some(raw) => raw,
+			None => return SubNext::Ended,
+		};
+		self.delivered.mark_delivered();
+		// Close notifications with a discriminator are checked first so that they are never decoded as `T`.
+		let closed = match serde_json::from_str::<SubscriptionResponse<SubscriptionClosed>>(&raw) {
+			Ok(closed) if closed.params.result.has_discriminator() => return SubNext::Closed(closed.params.result),
+			closed => closed.ok().map(|closed| closed.params.result),
 		};
-		Some(res)
+		if let Ok(err) = serde_json::from_str::<SubscriptionErrorResponse<JsonValue>>(&raw) {
+			return SubNext::Error(err.params.error);
+		}
+		let notif = serde_json::from_str::<SubscriptionResponse<T>>(&raw);
+		match notif {
+			Ok(r) => SubNext::Item(r.params.result, r.params.subscription.into_owned()),
+			Err(e) => closed.map_or(SubNext::DecodeError(e), SubNext::Closed),
+		}
 	}
 }
 
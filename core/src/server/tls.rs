@@ -0,0 +1,227 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Configuration of the TLS termination of the servers, with [`rustls`](tokio_rustls::rustls).
+
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::middleware::TlsDetails;
+use crate::Error;
+use rustls_pemfile::Item;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_rustls::rustls::server::{AllowAnyAnonymousOrAuthenticatedClient, AllowAnyAuthenticatedClient};
+use tokio_rustls::rustls::{Certificate, PrivateKey, RootCertStore, ServerConfig};
+use tokio_rustls::server::TlsStream;
+
+/// Default time a client has to complete the TLS handshake.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// Default max number of TLS handshakes in progress at the same time.
+const MAX_PENDING_HANDSHAKES: usize = 128;
+
+/// Certificate, private key and options the servers terminate TLS with.
+#[derive(Clone)]
+pub struct TlsConfig {
+	cert_chain: Vec<Certificate>,
+	private_key: PrivateKey,
+	alpn_protocols: Option<Vec<Vec<u8>>>,
+	client_auth: Option<(RootCertStore, bool)>,
+	handshake_timeout: Duration,
+	max_pending_handshakes: usize,
+}
+
+impl fmt::Debug for TlsConfig {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("TlsConfig")
+			.field("cert_chain", &self.cert_chain.len())
+			.field("alpn_protocols", &self.alpn_protocols)
+			.field("client_auth", &self.client_auth.as_ref().map(|(roots, required)| (roots.len(), required)))
+			.field("handshake_timeout", &self.handshake_timeout)
+			.field("max_pending_handshakes", &self.max_pending_handshakes)
+			.finish()
+	}
+}
+
+impl TlsConfig {
+	/// Create the configuration from a PEM encoded certificate chain, starting with the certificate of the server,
+	/// and a PEM encoded PKCS#8, PKCS#1 (RSA) or SEC1 (EC) private key.
+	pub fn from_pem(cert_chain: &[u8], private_key: &[u8]) -> Result<Self, Error> {
+		let cert_chain: Vec<_> = read_pem(cert_chain)?
+			.into_iter()
+			.filter_map(|item| match item {
+				Item::X509Certificate(der) => Some(Certificate(der)),
+				_ => None,
+			})
+			.collect();
+		if cert_chain.is_empty() {
+			return Err(Error::Custom("No certificate found in the PEM certificate chain".into()));
+		}
+
+		let private_key = read_pem(private_key)?
+			.into_iter()
+			.find_map(|item| match item {
+				Item::PKCS8Key(der) | Item::RSAKey(der) | Item::ECKey(der) => Some(PrivateKey(der)),
+				_ => None,
+			})
+			.ok_or_else(|| Error::Custom("No private key found in the PEM private key".into()))?;
+
+		Ok(Self {
+			cert_chain,
+			private_key,
+			alpn_protocols: None,
+			client_auth: None,
+			handshake_timeout: HANDSHAKE_TIMEOUT,
+			max_pending_handshakes: MAX_PENDING_HANDSHAKES,
+		})
+	}
+
+	/// Create the configuration from the files of a PEM encoded certificate chain and private key, see
+	/// [`TlsConfig::from_pem`].
+	pub fn from_pem_files(cert_chain: impl AsRef<Path>, private_key: impl AsRef<Path>) -> Result<Self, Error> {
+		Self::from_pem(&std::fs::read(cert_chain)?, &std::fs::read(private_key)?)
+	}
+
+	/// Set the protocols offered with ALPN, in order of preference (default is the protocols the server supports).
+	pub fn alpn_protocols(mut self, protocols: Vec<Vec<u8>>) -> Self {
+		self.alpn_protocols = Some(protocols);
+		self
+	}
+
+	/// Set the time a client has to complete the TLS handshake before its connection is dropped (default is 10
+	/// seconds).
+	pub fn handshake_timeout(mut self, timeout: Duration) -> Self {
+		self.handshake_timeout = timeout;
+		self
+	}
+
+	/// Set the max number of TLS handshakes in progress at the same time, the servers stop accepting connections
+	/// until one of them completes once it's reached (default is 128).
+	///
+	/// # Panics
+	///
+	/// Panics if `max` is `0`.
+	pub fn max_pending_handshakes(mut self, max: usize) -> Self {
+		assert!(max > 0, "max_pending_handshakes must be greater than 0");
+		self.max_pending_handshakes = max;
+		self
+	}
+
+	/// Verify the certificates of the clients against the PEM encoded certificates of the authorities trusted to
+	/// sign them. If `required`, the clients without a certificate are rejected, otherwise only the clients sending
+	/// an invalid certificate are.
+	pub fn client_auth_pem(mut self, trusted_authorities: &[u8], required: bool) -> Result<Self, Error> {
+		let mut roots = RootCertStore::empty();
+		for item in read_pem(trusted_authorities)? {
+			if let Item::X509Certificate(der) = item {
+				roots.add(&Certificate(der)).map_err(|err| Error::Custom(format!("Invalid certificate: {}", err)))?;
+			}
+		}
+		if roots.is_empty() {
+			return Err(Error::Custom("No certificate found in the PEM trusted authorities".into()));
+		}
+		self.client_auth = Some((roots, required));
+		Ok(self)
+	}
+
+	/// Build the acceptor terminating TLS on the connections, offering the `default_alpn_protocols` with ALPN unless
+	/// other protocols were configured.
+	pub fn acceptor(&self, default_alpn_protocols: &[&[u8]]) -> Result<TlsAcceptor, Error> {
+		let builder = ServerConfig::builder().with_safe_defaults();
+		let builder = match &self.client_auth {
+			Some((roots, true)) => builder.with_client_cert_verifier(AllowAnyAuthenticatedClient::new(roots.clone())),
+			Some((roots, false)) => {
+				builder.with_client_cert_verifier(AllowAnyAnonymousOrAuthenticatedClient::new(roots.clone()))
+			}
+			None => builder.with_no_client_auth(),
+		};
+		let mut config = builder
+			.with_single_cert(self.cert_chain.clone(), self.private_key.clone())
+			.map_err(|err| Error::Custom(format!("Invalid TLS certificate or private key: {}", err)))?;
+		config.alpn_protocols = match &self.alpn_protocols {
+			Some(protocols) => protocols.clone(),
+			None => default_alpn_protocols.iter().map(|protocol| protocol.to_vec()).collect(),
+		};
+		Ok(TlsAcceptor {
+			inner: tokio_rustls::TlsAcceptor::from(Arc::new(config)),
+			handshake_timeout: self.handshake_timeout,
+			max_pending_handshakes: self.max_pending_handshakes,
+		})
+	}
+}
+
+/// Acceptor terminating TLS on the connections, built with [`TlsConfig::acceptor`].
+#[derive(Clone)]
+pub struct TlsAcceptor {
+	inner: tokio_rustls::TlsAcceptor,
+	handshake_timeout: Duration,
+	max_pending_handshakes: usize,
+}
+
+impl fmt::Debug for TlsAcceptor {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("TlsAcceptor")
+			.field("handshake_timeout", &self.handshake_timeout)
+			.field("max_pending_handshakes", &self.max_pending_handshakes)
+			.finish()
+	}
+}
+
+impl TlsAcceptor {
+	/// Perform the TLS handshake on `stream`, failing with [`io::ErrorKind::TimedOut`] if the client doesn't complete
+	/// it within the handshake timeout.
+	pub async fn accept<IO>(&self, stream: IO) -> io::Result<TlsStream<IO>>
+	where
+		IO: AsyncRead + AsyncWrite + Unpin,
+	{
+		match tokio::time::timeout(self.handshake_timeout, self.inner.accept(stream)).await {
+			Ok(result) => result,
+			Err(_) => Err(io::Error::new(io::ErrorKind::TimedOut, "TLS handshake timed out")),
+		}
+	}
+
+	/// Returns the max number of TLS handshakes in progress at the same time.
+	pub fn max_pending_handshakes(&self) -> usize {
+		self.max_pending_handshakes
+	}
+}
+
+/// Returns the details of the TLS session of `stream`.
+pub fn session_details<IO>(stream: &TlsStream<IO>) -> TlsDetails {
+	let session = stream.get_ref().1;
+	TlsDetails {
+		server_name: session.sni_hostname().map(str::to_owned),
+		version: session.protocol_version().map(|version| format!("{:?}", version)),
+		alpn_protocol: session.alpn_protocol().map(<[u8]>::to_vec),
+		client_certificate: session.peer_certificates().is_some(),
+	}
+}
+
+fn read_pem(pem: &[u8]) -> Result<Vec<Item>, Error> {
+	rustls_pemfile::read_all(&mut &*pem).map_err(|err| Error::Custom(format!("Invalid PEM: {}", err)))
+}
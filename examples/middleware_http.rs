@@ -27,7 +27,8 @@
 use std::net::SocketAddr;
 use std::time::Instant;
 
-use jsonrpsee::core::{client::ClientT, middleware};
+use jsonrpsee::core::middleware::{self, ConnectionDetails};
+use jsonrpsee::core::{client::ClientT, JsonRawValue};
 use jsonrpsee::http_client::HttpClientBuilder;
 use jsonrpsee::http_server::{HttpServerBuilder, HttpServerHandle, RpcModule};
 
@@ -41,8 +42,8 @@ impl middleware::Middleware for Timings {
 		Instant::now()
 	}
 
-	fn on_call(&self, name: &str) {
-		println!("[Middleware::on_call] '{}'", name);
+	fn on_call(&self, name: &str, params: Option<&JsonRawValue>, details: &ConnectionDetails) {
+		println!("[Middleware::on_call] '{}' from {}, params: {:?}", name, details.peer_addr(), params);
 	}
 
 	fn on_result(&self, name: &str, succeess: bool, started_at: Self::Instant) {
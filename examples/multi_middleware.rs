@@ -30,7 +30,8 @@ use std::net::SocketAddr;
 use std::process::Command;
 use std::time::Instant;
 
-use jsonrpsee::core::{client::ClientT, middleware};
+use jsonrpsee::core::middleware::{self, ConnectionDetails};
+use jsonrpsee::core::{client::ClientT, JsonRawValue};
 use jsonrpsee::rpc_params;
 use jsonrpsee::ws_client::WsClientBuilder;
 use jsonrpsee::ws_server::{RpcModule, WsServerBuilder};
@@ -46,8 +47,8 @@ impl middleware::Middleware for Timings {
 		Instant::now()
 	}
 
-	fn on_call(&self, name: &str) {
-		println!("[Timings] They called '{}'", name);
+	fn on_call(&self, name: &str, _params: Option<&JsonRawValue>, details: &ConnectionDetails) {
+		println!("[Timings] They called '{}' on connection {}", name, details.conn_id());
 	}
 
 	fn on_result(&self, name: &str, succeess: bool, started_at: Self::Instant) {
@@ -44,17 +44,23 @@ pub struct AccessControl {
 	continue_on_invalid_cors: bool,
 }
 
+/// Returns the `Host` header of the request or, over HTTP/2 which has none, the authority of its URI.
+fn read_host(request: &hyper::Request<hyper::Body>) -> Option<&str> {
+	http_helpers::read_header_value(request.headers(), "host")
+		.or_else(|| request.uri().authority().map(|authority| authority.as_str()))
+}
+
 impl AccessControl {
 	/// Validate incoming request by http HOST
 	pub fn deny_host(&self, request: &hyper::Request<hyper::Body>) -> bool {
-		!hosts::is_host_valid(http_helpers::read_header_value(request.headers(), "host"), &self.allowed_hosts)
+		!hosts::is_host_valid(read_host(request), &self.allowed_hosts)
 	}
 
 	/// Validate incoming request by CORS origin
 	pub fn deny_cors_origin(&self, request: &hyper::Request<hyper::Body>) -> bool {
 		let header = cors::get_cors_allow_origin(
 			http_helpers::read_header_value(request.headers(), "origin"),
-			http_helpers::read_header_value(request.headers(), "host"),
+			read_host(request),
 			&self.allowed_origins,
 		)
 		.map(|origin| {
@@ -0,0 +1,203 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Incoming connections of the server, over plain TCP or TLS.
+
+use std::fmt;
+use std::io;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use hyper::server::accept::Accept;
+use hyper::server::conn::{AddrIncoming, AddrStream};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+#[cfg(feature = "tls")]
+use futures_util::future::BoxFuture;
+#[cfg(feature = "tls")]
+use futures_util::stream::{FuturesUnordered, StreamExt};
+use jsonrpsee_core::middleware::TlsDetails;
+#[cfg(feature = "tls")]
+use jsonrpsee_core::server::tls::{self, TlsAcceptor};
+
+/// Stream of a connection.
+pub(crate) enum HttpStream {
+	/// Plain TCP stream.
+	Plain(AddrStream),
+	/// TLS stream.
+	#[cfg(feature = "tls")]
+	Tls(Box<tokio_rustls::server::TlsStream<AddrStream>>),
+}
+
+impl HttpStream {
+	/// Returns the address of the peer.
+	pub(crate) fn remote_addr(&self) -> SocketAddr {
+		match self {
+			Self::Plain(stream) => stream.remote_addr(),
+			#[cfg(feature = "tls")]
+			Self::Tls(stream) => stream.get_ref().0.remote_addr(),
+		}
+	}
+
+	/// Returns the details of the TLS session, if TLS was terminated.
+	pub(crate) fn tls(&self) -> Option<TlsDetails> {
+		match self {
+			Self::Plain(_) => None,
+			#[cfg(feature = "tls")]
+			Self::Tls(stream) => Some(tls::session_details(stream)),
+		}
+	}
+}
+
+/// Accepts the connections of the server, terminating TLS if enabled.
+///
+/// The TLS handshakes are driven concurrently so that a slow client doesn't hold up the others, up to the max
+/// number of pending handshakes of the acceptor. The connections failing or timing out their handshake are dropped
+/// without stopping the server.
+pub(crate) struct Incoming {
+	inner: AddrIncoming,
+	#[cfg(feature = "tls")]
+	tls: Option<TlsAcceptor>,
+	#[cfg(feature = "tls")]
+	handshakes: FuturesUnordered<BoxFuture<'static, io::Result<tokio_rustls::server::TlsStream<AddrStream>>>>,
+	/// Set once the listener doesn't accept connections anymore.
+	#[cfg(feature = "tls")]
+	listener_ended: bool,
+}
+
+impl fmt::Debug for Incoming {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		#[cfg(feature = "tls")]
+		let tls = self.tls.is_some();
+		#[cfg(not(feature = "tls"))]
+		let tls = false;
+		f.debug_struct("Incoming").field("inner", &self.inner).field("tls", &tls).finish()
+	}
+}
+
+impl Incoming {
+	/// Accept plain connections.
+	pub(crate) fn new(inner: AddrIncoming) -> Self {
+		Self {
+			inner,
+			#[cfg(feature = "tls")]
+			tls: None,
+			#[cfg(feature = "tls")]
+			handshakes: FuturesUnordered::new(),
+			#[cfg(feature = "tls")]
+			listener_ended: false,
+		}
+	}
+
+	/// Terminate TLS on the accepted connections.
+	#[cfg(feature = "tls")]
+	pub(crate) fn tls(mut self, acceptor: TlsAcceptor) -> Self {
+		self.tls = Some(acceptor);
+		self
+	}
+
+	#[cfg(feature = "tls")]
+	fn poll_tls(&mut self, cx: &mut Context<'_>) -> Poll<Option<io::Result<HttpStream>>> {
+		let tls = match &self.tls {
+			Some(tls) => tls,
+			None => return Poll::Ready(None),
+		};
+
+		loop {
+			// Once the max number of pending handshakes is reached, the connections are left waiting in the listener
+			// until a handshake completes.
+			while !self.listener_ended && self.handshakes.len() < tls.max_pending_handshakes() {
+				match Pin::new(&mut self.inner).poll_accept(cx) {
+					Poll::Ready(Some(Ok(stream))) => {
+						let tls = tls.clone();
+						self.handshakes.push(Box::pin(async move { tls.accept(stream).await }));
+					}
+					Poll::Ready(Some(Err(e))) => return Poll::Ready(Some(Err(e))),
+					Poll::Ready(None) => self.listener_ended = true,
+					Poll::Pending => break,
+				}
+			}
+
+			match self.handshakes.poll_next_unpin(cx) {
+				Poll::Ready(Some(Ok(stream))) => return Poll::Ready(Some(Ok(HttpStream::Tls(Box::new(stream))))),
+				Poll::Ready(Some(Err(e))) => tracing::debug!("TLS handshake failed: {}", e),
+				Poll::Ready(None) if self.listener_ended => return Poll::Ready(None),
+				Poll::Ready(None) | Poll::Pending => return Poll::Pending,
+			}
+		}
+	}
+}
+
+impl Accept for Incoming {
+	type Conn = HttpStream;
+	type Error = io::Error;
+
+	fn poll_accept(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<io::Result<HttpStream>>> {
+		let this = self.get_mut();
+		#[cfg(feature = "tls")]
+		if this.tls.is_some() {
+			return this.poll_tls(cx);
+		}
+		Pin::new(&mut this.inner).poll_accept(cx).map_ok(HttpStream::Plain)
+	}
+}
+
+impl AsyncRead for HttpStream {
+	fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+		match self.get_mut() {
+			Self::Plain(stream) => Pin::new(stream).poll_read(cx, buf),
+			#[cfg(feature = "tls")]
+			Self::Tls(stream) => Pin::new(stream).poll_read(cx, buf),
+		}
+	}
+}
+
+impl AsyncWrite for HttpStream {
+	fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+		match self.get_mut() {
+			Self::Plain(stream) => Pin::new(stream).poll_write(cx, buf),
+			#[cfg(feature = "tls")]
+			Self::Tls(stream) => Pin::new(stream).poll_write(cx, buf),
+		}
+	}
+
+	fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+		match self.get_mut() {
+			Self::Plain(stream) => Pin::new(stream).poll_flush(cx),
+			#[cfg(feature = "tls")]
+			Self::Tls(stream) => Pin::new(stream).poll_flush(cx),
+		}
+	}
+
+	fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+		match self.get_mut() {
+			Self::Plain(stream) => Pin::new(stream).poll_shutdown(cx),
+			#[cfg(feature = "tls")]
+			Self::Tls(stream) => Pin::new(stream).poll_shutdown(cx),
+		}
+	}
+}
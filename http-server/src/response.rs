@@ -108,6 +108,12 @@ pub fn ok_response(body: String) -> hyper::Response<hyper::Body> {
 	from_template(hyper::StatusCode::OK, body, JSON)
 }
 
+/// Create a valid JSON response with the given status code, falls back to `200 OK` if the status code is invalid.
+pub fn response_with_status(status: u16, body: String) -> hyper::Response<hyper::Body> {
+	let status = hyper::StatusCode::from_u16(status).unwrap_or(hyper::StatusCode::OK);
+	from_template(status, body, JSON)
+}
+
 /// Create a response for unsupported content type.
 pub fn unsupported_content_type() -> hyper::Response<hyper::Body> {
 	from_template(
@@ -24,12 +24,15 @@
 // IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
+use std::borrow::Cow;
 use std::cmp;
 use std::future::Future;
 use std::net::{SocketAddr, TcpListener, ToSocketAddrs};
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{Context, Poll};
 
+use crate::incoming::{HttpStream, Incoming};
 use crate::response::{internal_error, malformed};
 use crate::{response, AccessControl};
 use futures_channel::mpsc;
@@ -40,10 +43,15 @@ use hyper::service::{make_service_fn, service_fn};
 use hyper::{Error as HyperError, Method};
 use jsonrpsee_core::error::{Error, GenericTransportError};
 use jsonrpsee_core::http_helpers::{self, read_body};
-use jsonrpsee_core::middleware::Middleware;
-use jsonrpsee_core::server::helpers::{collect_batch_response, prepare_error, MethodSink};
+use jsonrpsee_core::middleware::{AsyncMiddleware, ConnectionDetails, Middleware, TransportKind};
+use jsonrpsee_core::server::connection_state::ConnectionState;
+use jsonrpsee_core::server::deadline::Deadline;
+use jsonrpsee_core::server::helpers::{collect_batch_response, prepare_error, MethodFilter, MethodSink};
+use jsonrpsee_core::server::live_methods::LiveMethods;
 use jsonrpsee_core::server::resource_limiting::Resources;
-use jsonrpsee_core::server::rpc_module::{MethodKind, Methods};
+use jsonrpsee_core::server::rpc_module::{MethodCallback, MethodKind, Methods};
+#[cfg(feature = "tls")]
+use jsonrpsee_core::server::tls::{TlsAcceptor, TlsConfig};
 use jsonrpsee_core::TEN_MB_SIZE_BYTES;
 use jsonrpsee_types::error::ErrorCode;
 use jsonrpsee_types::{Id, Notification, Params, Request};
@@ -57,6 +65,21 @@ pub struct Builder<M = ()> {
 	resources: Resources,
 	max_request_body_size: u32,
 	keep_alive: bool,
+	/// Max number of concurrent streams of the HTTP/2 connections.
+	http2_max_concurrent_streams: u32,
+	/// Whether to attach deprecation notices to responses.
+	deprecation_warnings: bool,
+	/// Whether to echo the params of the calls in their error responses.
+	echo_params_in_errors: bool,
+	/// Filter of the methods that can be called, if any.
+	method_filter: Option<MethodFilter>,
+	/// Middleware awaited around the execution of the method calls, if any.
+	async_middleware: Option<Arc<dyn AsyncMiddleware>>,
+	/// Whether to execute each method call in its own tracing span.
+	call_spans: bool,
+	/// Configuration of the TLS termination, if enabled.
+	#[cfg(feature = "tls")]
+	tls: Option<TlsConfig>,
 	/// Custom tokio runtime to run the server on.
 	tokio_runtime: Option<tokio::runtime::Handle>,
 	middleware: M,
@@ -69,6 +92,14 @@ impl Default for Builder {
 			resources: Resources::default(),
 			access_control: AccessControl::default(),
 			keep_alive: true,
+			http2_max_concurrent_streams: 200,
+			deprecation_warnings: false,
+			echo_params_in_errors: false,
+			method_filter: None,
+			async_middleware: None,
+			call_spans: false,
+			#[cfg(feature = "tls")]
+			tls: None,
 			tokio_runtime: None,
 			middleware: (),
 		}
@@ -114,6 +145,14 @@ impl<M> Builder<M> {
 			resources: self.resources,
 			access_control: self.access_control,
 			keep_alive: self.keep_alive,
+			http2_max_concurrent_streams: self.http2_max_concurrent_streams,
+			deprecation_warnings: self.deprecation_warnings,
+			echo_params_in_errors: self.echo_params_in_errors,
+			method_filter: self.method_filter,
+			async_middleware: self.async_middleware,
+			call_spans: self.call_spans,
+			#[cfg(feature = "tls")]
+			tls: self.tls,
 			tokio_runtime: self.tokio_runtime,
 			middleware,
 		}
@@ -139,6 +178,90 @@ impl<M> Builder<M> {
 		self
 	}
 
+	/// Sets the max number of concurrent streams, that is of calls in flight, of each HTTP/2 connection.
+	///
+	/// The server speaks HTTP/2 to the clients connecting with prior knowledge (h2c) and, with TLS, to the clients
+	/// negotiating `h2` with ALPN, so that they can multiplex their calls over one connection.
+	///
+	/// Default is 200.
+	pub fn http2_max_concurrent_streams(mut self, max: u32) -> Self {
+		self.http2_max_concurrent_streams = max;
+		self
+	}
+
+	/// Attach the deprecation notice of deprecated methods to their responses as a `warning` field, see
+	/// [`RpcModule::deprecate_method`](../jsonrpsee_core/server/rpc_module/struct.RpcModule.html#method.deprecate_method).
+	///
+	/// The field is not part of the JSON-RPC specification, so only enable this if the clients tolerate it.
+	///
+	/// Default is false.
+	pub fn deprecation_warnings(mut self, enabled: bool) -> Self {
+		self.deprecation_warnings = enabled;
+		self
+	}
+
+	/// Echo the params of the calls in their error responses as the `params` member of the error `data`, see
+	/// [`MethodSink::with_echoed_params`](../jsonrpsee_core/server/helpers/struct.MethodSink.html#method.with_echoed_params).
+	///
+	/// This is meant for debugging failing calls during development: the params may contain sensitive input which
+	/// then ends up in the error responses and possibly in the logs of the clients.
+	///
+	/// Default is false.
+	pub fn echo_params_in_errors(mut self, enabled: bool) -> Self {
+		self.echo_params_in_errors = enabled;
+		self
+	}
+
+	/// Only expose the methods for which `filter` returns `true` to a connection, given the method name and the
+	/// details of the connection. Calls to the other methods are answered with a `Method not found` error, as if
+	/// they didn't exist.
+	///
+	/// Default: all methods can be called.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use jsonrpsee_http_server::HttpServerBuilder;
+	///
+	/// // Hide the `admin_` methods from the clients not connecting from the local host.
+	/// let builder = HttpServerBuilder::default()
+	///     .method_filter(|name, details| !name.starts_with("admin_") || details.peer_addr().ip().is_loopback());
+	/// ```
+	pub fn method_filter(mut self, filter: impl Fn(&str, &ConnectionDetails) -> bool + Send + Sync + 'static) -> Self {
+		self.method_filter = Some(MethodFilter::new(filter));
+		self
+	}
+
+	/// Await the hooks of an [`AsyncMiddleware`] around the execution of each method call, for instance to authorize
+	/// the calls with a remote service. Calls rejected by [`AsyncMiddleware::on_call`] are answered with its error.
+	///
+	/// Default: no asynchronous middleware.
+	pub fn set_async_middleware(mut self, middleware: impl AsyncMiddleware) -> Self {
+		self.async_middleware = Some(Arc::new(middleware));
+		self
+	}
+
+	/// Execute each method call in its own `rpc_call` tracing span, with the method name and the request ID as fields
+	/// and an `otel.status_code` set from the result of the call. The connection ID field is always `0` over HTTP.
+	///
+	/// The spans follow the OpenTelemetry conventions, so they can be exported with `tracing-opentelemetry`.
+	///
+	/// Default: disabled.
+	pub fn call_spans(mut self, enabled: bool) -> Self {
+		self.call_spans = enabled;
+		self
+	}
+
+	/// Terminate TLS on the connections with the given certificate, serving `https://` URLs. The server offers
+	/// `h2` and `http/1.1` with ALPN unless [`TlsConfig::alpn_protocols`] says otherwise.
+	///
+	/// Default: plain connections.
+	#[cfg(feature = "tls")]
+	pub fn tls(mut self, config: TlsConfig) -> Self {
+		self.tls = Some(config);
+		self
+	}
+
 	/// Register a new resource kind. Errors if `label` is already registered, or if the number of
 	/// registered resources on this server instance would exceed 8.
 	///
@@ -150,6 +273,20 @@ impl<M> Builder<M> {
 		Ok(self)
 	}
 
+	/// Register a new resource kind like [`Self::register_resource`], of which the calls of a single connection can
+	/// claim at most `connection_capacity` units at the same time, so that one connection can't starve the others.
+	pub fn register_resource_per_connection(
+		mut self,
+		label: &'static str,
+		capacity: u16,
+		connection_capacity: u16,
+		default: u16,
+	) -> Result<Self, Error> {
+		self.resources.register_per_connection(label, capacity, connection_capacity, default)?;
+
+		Ok(self)
+	}
+
 	/// Configure a custom [`tokio::runtime::Handle`] to run the server on.
 	///
 	/// Default: [`tokio::spawn`]
@@ -175,9 +312,15 @@ impl<M> Builder<M> {
 	/// ```
 	pub fn build(self, addrs: impl ToSocketAddrs) -> Result<Server<M>, Error> {
 		let mut err: Option<Error> = None;
+		#[cfg(feature = "tls")]
+		let tls = self.tls.as_ref().map(|tls| tls.acceptor(&[b"h2", b"http/1.1"])).transpose()?;
 
 		for addr in addrs.to_socket_addrs()? {
-			let (listener, local_addr) = match self.inner_builder(addr) {
+			#[cfg(feature = "tls")]
+			let listener = self.inner_builder(addr, tls.clone());
+			#[cfg(not(feature = "tls"))]
+			let listener = self.inner_builder(addr);
+			let (listener, local_addr) = match listener {
 				Ok(res) => res,
 				Err(e) => {
 					err = Some(e);
@@ -191,6 +334,11 @@ impl<M> Builder<M> {
 				access_control: self.access_control,
 				max_request_body_size: self.max_request_body_size,
 				resources: self.resources,
+				deprecation_warnings: self.deprecation_warnings,
+				echo_params_in_errors: self.echo_params_in_errors,
+				method_filter: self.method_filter,
+				async_middleware: self.async_middleware,
+				call_spans: self.call_spans,
 				tokio_runtime: self.tokio_runtime,
 				middleware: self.middleware,
 			});
@@ -203,7 +351,8 @@ impl<M> Builder<M> {
 	fn inner_builder(
 		&self,
 		addr: SocketAddr,
-	) -> Result<(hyper::server::Builder<hyper::server::conn::AddrIncoming>, Option<SocketAddr>), Error> {
+		#[cfg(feature = "tls")] tls: Option<TlsAcceptor>,
+	) -> Result<(HyperBuilder<Incoming>, Option<SocketAddr>), Error> {
 		let domain = Domain::for_address(addr);
 		let socket = Socket::new(domain, Type::STREAM, None)?;
 		socket.set_nodelay(true)?;
@@ -216,16 +365,23 @@ impl<M> Builder<M> {
 		socket.listen(128)?;
 		let listener: TcpListener = socket.into();
 		let local_addr = listener.local_addr().ok();
-		let listener = hyper::Server::from_tcp(listener)?;
-		Ok((listener, local_addr))
+		let incoming = Incoming::new(AddrIncoming::from_listener(tokio::net::TcpListener::from_std(listener)?)?);
+		#[cfg(feature = "tls")]
+		let incoming = match tls {
+			Some(tls) => incoming.tls(tls),
+			None => incoming,
+		};
+		let builder = hyper::Server::builder(incoming).http2_max_concurrent_streams(self.http2_max_concurrent_streams);
+		Ok((builder, local_addr))
 	}
 }
 
-/// Handle used to run or stop the server.
+/// Handle used to run or stop the server, and to replace its methods while it's running.
 #[derive(Debug)]
 pub struct ServerHandle {
 	stop_sender: mpsc::Sender<()>,
 	pub(crate) handle: Option<tokio::task::JoinHandle<()>>,
+	methods: LiveMethods,
 }
 
 impl ServerHandle {
@@ -237,6 +393,21 @@ impl ServerHandle {
 			_ => Err(Error::AlreadyStopped),
 		}
 	}
+
+	/// Replace the methods of the server all at once, returning the previous ones.
+	///
+	/// The requests received afterwards are dispatched to the new methods, see [`LiveMethods::swap`].
+	pub fn swap_methods(&self, methods: impl Into<Methods>) -> Result<Methods, Error> {
+		self.methods.swap(methods)
+	}
+
+	/// Patch the methods of the server, for instance to merge a module into them, see [`LiveMethods::patch`].
+	pub fn patch_methods<F>(&self, patch: F) -> Result<(), Error>
+	where
+		F: FnOnce(&mut Methods) -> Result<(), Error>,
+	{
+		self.methods.patch(patch)
+	}
 }
 
 impl Future for ServerHandle {
@@ -256,7 +427,7 @@ impl Future for ServerHandle {
 #[derive(Debug)]
 pub struct Server<M = ()> {
 	/// Hyper server.
-	listener: HyperBuilder<AddrIncoming>,
+	listener: HyperBuilder<Incoming>,
 	/// Local address
 	local_addr: Option<SocketAddr>,
 	/// Max request body size.
@@ -265,6 +436,16 @@ pub struct Server<M = ()> {
 	access_control: AccessControl,
 	/// Tracker for currently used resources on the server
 	resources: Resources,
+	/// Whether to attach deprecation notices to responses.
+	deprecation_warnings: bool,
+	/// Whether to echo the params of the calls in their error responses.
+	echo_params_in_errors: bool,
+	/// Filter of the methods that can be called, if any.
+	method_filter: Option<MethodFilter>,
+	/// Middleware awaited around the execution of the method calls, if any.
+	async_middleware: Option<Arc<dyn AsyncMiddleware>>,
+	/// Whether to execute each method call in its own tracing span.
+	call_spans: bool,
 	/// Custom tokio runtime to run the server on.
 	tokio_runtime: Option<tokio::runtime::Handle>,
 	middleware: M,
@@ -277,27 +458,46 @@ impl<M: Middleware> Server<M> {
 	}
 
 	/// Start the server.
+	///
+	/// The methods can be replaced while the server is running with [`ServerHandle::swap_methods`].
 	pub fn start(mut self, methods: impl Into<Methods>) -> Result<ServerHandle, Error> {
 		let max_request_body_size = self.max_request_body_size;
+		let deprecation_warnings = self.deprecation_warnings;
+		let echo_params_in_errors = self.echo_params_in_errors;
+		let method_filter = self.method_filter;
+		let async_middleware = self.async_middleware;
+		let call_spans = self.call_spans;
 		let access_control = self.access_control;
 		let (tx, mut rx) = mpsc::channel(1);
 		let listener = self.listener;
 		let resources = self.resources;
 		let middleware = self.middleware;
-		let methods = methods.into().initialize_resources(&resources)?;
-
-		let make_service = make_service_fn(move |_| {
-			let methods = methods.clone();
+		let live_methods = LiveMethods::new(resources.clone());
+		live_methods.swap(methods)?;
+		let handle_methods = live_methods.clone();
+
+		let mut next_conn_id = 0;
+		let make_service = make_service_fn(move |conn: &HttpStream| {
+			let peer_addr = conn.remote_addr();
+			let tls = conn.tls();
+			let conn_id = next_conn_id;
+			next_conn_id += 1;
+			let live_methods = live_methods.clone();
 			let access_control = access_control.clone();
-			let resources = resources.clone();
+			let resources = resources.for_connection();
 			let middleware = middleware.clone();
+			let method_filter = method_filter.clone();
+			let async_middleware = async_middleware.clone();
 
 			async move {
 				Ok::<_, HyperError>(service_fn(move |request| {
-					let methods = methods.clone();
+					let methods = live_methods.current();
+					let tls = tls.clone();
 					let access_control = access_control.clone();
 					let resources = resources.clone();
 					let middleware = middleware.clone();
+					let method_filter = method_filter.clone();
+					let async_middleware = async_middleware.clone();
 
 					// Run some validation on the http request, then read the body and try to deserialize it into one of
 					// two cases: a single RPC request or a batch of RPC requests.
@@ -335,14 +535,44 @@ impl<M: Middleware> Server<M> {
 							// to be read in a browser.
 							Method::POST if content_type_is_json(&request) => {
 								let origin = return_origin_if_different_from_host(request.headers()).cloned();
-								let mut res = process_validated_request(
+								let mut details = ConnectionDetails::new(conn_id, peer_addr, TransportKind::Http)
+									.with_protocol(format!("{:?}", request.version()));
+								if let Some(tls) = tls {
+									details = details.with_tls(tls);
+								}
+								let state = Arc::new(ConnectionState::with_details(details.clone()));
+								let resolve = {
+									let details = details.clone();
+									resolver(move |methods: &Methods, name: &str| {
+										if !method_filter.as_ref().is_none_or(|filter| filter.allows(name, &details)) {
+											return None;
+										}
+										let (name, mut method) = methods.resolve(name)?;
+										if let Some(async_middleware) = &async_middleware {
+											method = Cow::Owned(method.with_async_middleware(
+												name,
+												async_middleware.clone(),
+												details.clone(),
+											));
+										}
+										if call_spans {
+											method = Cow::Owned(method.with_call_span(name, 0));
+										}
+										Some((name, method))
+									})
+								};
+								let process = process_validated_request(
 									request,
 									middleware,
 									methods,
 									resources,
 									max_request_body_size,
-								)
-								.await?;
+									deprecation_warnings,
+									echo_params_in_errors,
+									details,
+									resolve,
+								);
+								let mut res = ConnectionState::scope(state, process).await?;
 
 								if let Some(origin) = origin {
 									res.headers_mut().insert("access-control-allow-origin", origin);
@@ -368,7 +598,7 @@ impl<M: Middleware> Server<M> {
 			let _ = server.with_graceful_shutdown(async move { rx.next().await.map_or((), |_| ()) }).await;
 		});
 
-		Ok(ServerHandle { handle: Some(handle), stop_sender: tx })
+		Ok(ServerHandle { handle: Some(handle), stop_sender: tx, methods: handle_methods })
 	}
 }
 
@@ -422,6 +652,14 @@ fn is_json(content_type: Option<&hyper::header::HeaderValue>) -> bool {
 	}
 }
 
+/// Coerce `resolve` to a resolver of the callbacks borrowed from the [`Methods`], which closures can't be inferred as.
+fn resolver<F>(resolve: F) -> F
+where
+	F: for<'a> Fn(&'a Methods, &str) -> Option<(&'static str, Cow<'a, MethodCallback>)>,
+{
+	resolve
+}
+
 /// Process a verified request, it implies a POST request with content type JSON.
 async fn process_validated_request(
 	request: hyper::Request<hyper::Body>,
@@ -429,6 +667,10 @@ async fn process_validated_request(
 	methods: Methods,
 	resources: Resources,
 	max_request_body_size: u32,
+	deprecation_warnings: bool,
+	echo_params_in_errors: bool,
+	details: ConnectionDetails,
+	resolve: impl for<'a> Fn(&'a Methods, &str) -> Option<(&'static str, Cow<'a, MethodCallback>)>,
 ) -> Result<hyper::Response<hyper::Body>, HyperError> {
 	let (parts, body) = request.into_parts();
 
@@ -446,20 +688,34 @@ async fn process_validated_request(
 
 	// NOTE(niklasad1): it's a channel because it's needed for batch requests.
 	let (tx, mut rx) = mpsc::unbounded::<String>();
-	let sink = MethodSink::new_with_limit(tx, max_request_body_size);
+	let mut sink = MethodSink::new_with_limit(tx, max_request_body_size);
+	if deprecation_warnings {
+		sink = sink.with_deprecation_warnings();
+	}
+	if echo_params_in_errors {
+		sink = sink.with_params_echo();
+	}
 
 	type Notif<'a> = Notification<'a, Option<&'a RawValue>>;
 
+	// Status code hint set by the method, only respected for single calls.
+	let mut status_hint = None;
+
 	// Single request or notification
 	if is_single {
 		if let Ok(req) = serde_json::from_slice::<Request>(&body) {
 			let method = req.method.as_ref();
-			middleware.on_call(method);
+			middleware.on_call(method, req.params, &details);
 
 			let id = req.id.clone();
 			let params = Params::new(req.params.map(|params| params.get()));
+			let echo_sink = sink.with_echoed_params(req.params);
+			let sink = echo_sink.as_ref().unwrap_or(&sink);
 
-			let result = match methods.method_with_name(method) {
+			let resolved = resolve(&methods, method);
+			// The results of calls to unknown methods are not reported, their names come from the client.
+			let name = resolved.as_ref().map(|(name, _)| *name);
+			let result = match resolved {
 				None => {
 					sink.send_error(req.id, ErrorCode::MethodNotFound.into());
 					false
@@ -467,7 +723,7 @@ async fn process_validated_request(
 				Some((name, method_callback)) => match method_callback.inner() {
 					MethodKind::Sync(callback) => match method_callback.claim(&req.method, &resources) {
 						Ok(guard) => {
-							let result = (callback)(id, params, &sink);
+							let result = (callback)(id, params, sink);
 							drop(guard);
 							result
 						}
@@ -479,9 +735,9 @@ async fn process_validated_request(
 					},
 					MethodKind::Async(callback) => match method_callback.claim(name, &resources) {
 						Ok(guard) => {
-							let result =
-								(callback)(id.into_owned(), params.into_owned(), sink.clone(), 0, Some(guard)).await;
-							result
+							let deadline = req.deadline_ms().map(Deadline::after_millis);
+							let call = (callback)(id.into_owned(), params.into_owned(), sink.clone(), 0, Some(guard));
+							methods.track_call(Deadline::scope(deadline, call)).await
 						}
 						Err(err) => {
 							tracing::error!("[Methods::execute_with_resources] failed to lock resources: {:?}", err);
@@ -496,7 +752,10 @@ async fn process_validated_request(
 					}
 				},
 			};
-			middleware.on_result(&req.method, result, request_start);
+			if let Some(name) = name {
+				middleware.on_result(name, result, request_start);
+			}
+			status_hint = sink.status_hint();
 		} else if let Ok(_req) = serde_json::from_slice::<Notif>(&body) {
 			return Ok::<_, HyperError>(response::ok_response("".into()));
 		} else {
@@ -507,12 +766,19 @@ async fn process_validated_request(
 	} else if let Ok(batch) = serde_json::from_slice::<Vec<Request>>(&body) {
 		if !batch.is_empty() {
 			let middleware = &middleware;
+			let resolve = &resolve;
+			let details = &details;
 
 			join_all(batch.into_iter().filter_map(move |req| {
+				middleware.on_call(&req.method, req.params, details);
+
 				let id = req.id.clone();
 				let params = Params::new(req.params.map(|params| params.get()));
+				let echo_sink = sink.with_echoed_params(req.params);
+				let sink = echo_sink.as_ref().unwrap_or(&sink);
 
-				match methods.method_with_name(&req.method) {
+				let resolved = resolve(&methods, &req.method);
+				match resolved {
 					None => {
 						sink.send_error(req.id, ErrorCode::MethodNotFound.into());
 						None
@@ -520,7 +786,7 @@ async fn process_validated_request(
 					Some((name, method_callback)) => match method_callback.inner() {
 						MethodKind::Sync(callback) => match method_callback.claim(name, &resources) {
 							Ok(guard) => {
-								let result = (callback)(id, params, &sink);
+								let result = (callback)(id, params, sink);
 								middleware.on_result(name, result, request_start);
 								drop(guard);
 								None
@@ -541,9 +807,12 @@ async fn process_validated_request(
 								let id = id.into_owned();
 								let params = params.into_owned();
 								let callback = callback.clone();
+								let deadline = req.deadline_ms().map(Deadline::after_millis);
+								let methods = methods.clone();
 
 								Some(async move {
-									let result = (callback)(id, params, sink, 0, Some(guard)).await;
+									let call = (callback)(id, params, sink, 0, Some(guard));
+									let result = methods.track_call(Deadline::scope(deadline, call)).await;
 									middleware.on_result(name, result, request_start);
 								})
 							}
@@ -560,7 +829,7 @@ async fn process_validated_request(
 						MethodKind::Subscription(_) => {
 							tracing::error!("Subscriptions not supported on HTTP");
 							sink.send_error(req.id, ErrorCode::InternalError.into());
-							middleware.on_result(&req.method, false, request_start);
+							middleware.on_result(name, false, request_start);
 							None
 						}
 					},
@@ -595,5 +864,8 @@ async fn process_validated_request(
 	};
 	tracing::debug!("[service_fn] sending back: {:?}", &response[..cmp::min(response.len(), 1024)]);
 	middleware.on_response(request_start);
-	Ok(response::ok_response(response))
+	match status_hint {
+		Some(status) => Ok(response::response_with_status(status, response)),
+		None => Ok(response::ok_response(response)),
+	}
 }
@@ -79,6 +79,13 @@ async fn server() -> (SocketAddr, ServerHandle) {
 		})
 		.unwrap();
 
+	module
+		.register_method("rate_limited", |_, ctx| {
+			ctx.err().map_err(|e| Error::Call(CallError::Failed(e)).with_status_hint(429))?;
+			Ok("ok")
+		})
+		.unwrap();
+
 	let server_handle = server.start(module).unwrap();
 	(addr, server_handle)
 }
@@ -190,6 +197,41 @@ async fn async_method_call_with_ok_context() {
 	assert_eq!(response.body, ok_response("ok".into(), Id::Num(1)));
 }
 
+#[tokio::test]
+async fn status_hint_sets_http_status() {
+	let (addr, _handle) = server().with_default_timeout().await.unwrap();
+	let uri = to_http_uri(addr);
+
+	let req = r#"{"jsonrpc":"2.0","method":"rate_limited","id":1}"#;
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::TOO_MANY_REQUESTS);
+	assert_eq!(response.body, call_execution_failed("RPC context failed", Id::Num(1)));
+
+	// The hint is ignored for batches.
+	let req = r#"[{"jsonrpc":"2.0","method":"rate_limited","id":1}]"#;
+	let response = http_request(req.into(), uri).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn deprecation_warnings_are_opt_in() {
+	let mut module = RpcModule::new(());
+	module.register_method("old_hello", |_, _| Ok("lo")).unwrap();
+	module.deprecate_method("old_hello", "Use `say_hello` instead").unwrap();
+
+	let req = r#"{"jsonrpc":"2.0","method":"old_hello","id":1}"#;
+	let with_warning = r#"{"jsonrpc":"2.0","result":"lo","id":1,"warning":"Use `say_hello` instead"}"#;
+
+	for (enabled, expected) in [(true, with_warning), (false, r#"{"jsonrpc":"2.0","result":"lo","id":1}"#)] {
+		let server = HttpServerBuilder::default().deprecation_warnings(enabled).build("127.0.0.1:0").unwrap();
+		let uri = to_http_uri(server.local_addr().unwrap());
+		let _handle = server.start(module.clone()).unwrap();
+
+		let response = http_request(req.into(), uri).with_default_timeout().await.unwrap().unwrap();
+		assert_eq!(response.body, expected);
+	}
+}
+
 #[tokio::test]
 async fn valid_batched_method_calls() {
 	let _ = env_logger::try_init();
@@ -405,3 +447,148 @@ async fn run_forever() {
 	server_handle.handle.take();
 	server_handle.with_timeout(TIMEOUT).await.unwrap();
 }
+
+#[tokio::test]
+async fn params_are_echoed_in_errors_if_enabled() {
+	let mut module = RpcModule::new(());
+	module.register_method("fail", |_, _| Err::<(), _>(Error::Custom("Unknown block".into()))).unwrap();
+	module
+		.register_async_method("async_fail", |_, _| async { Err::<(), _>(Error::Custom("Unknown block".into())) })
+		.unwrap();
+
+	let req =
+		r#"[{"jsonrpc":"2.0","method":"fail","params":[1234],"id":1},{"jsonrpc":"2.0","method":"async_fail","id":2}]"#;
+	let echoed = r#"[{"jsonrpc":"2.0","error":{"code":-32001,"message":"Custom error: Unknown block","data":{"params":[1234]}},"id":1},{"jsonrpc":"2.0","error":{"code":-32001,"message":"Custom error: Unknown block","data":{"params":null}},"id":2}]"#;
+	let not_echoed = r#"[{"jsonrpc":"2.0","error":{"code":-32001,"message":"Custom error: Unknown block"},"id":1},{"jsonrpc":"2.0","error":{"code":-32001,"message":"Custom error: Unknown block"},"id":2}]"#;
+
+	for (enabled, expected) in [(true, echoed), (false, not_echoed)] {
+		let server = HttpServerBuilder::default().echo_params_in_errors(enabled).build("127.0.0.1:0").unwrap();
+		let uri = to_http_uri(server.local_addr().unwrap());
+		let _handle = server.start(module.clone()).unwrap();
+
+		let response = http_request(req.into(), uri).with_default_timeout().await.unwrap().unwrap();
+		assert_eq!(response.body, expected);
+	}
+}
+
+#[tokio::test]
+async fn methods_can_be_swapped_while_running() {
+	let mut module = RpcModule::new(());
+	module.register_method("version", |_, _| Ok("v1")).unwrap();
+
+	let server = HttpServerBuilder::default().build("127.0.0.1:0").unwrap();
+	let uri = to_http_uri(server.local_addr().unwrap());
+	let handle = server.start(module).unwrap();
+
+	let req = r#"{"jsonrpc":"2.0","method":"version","id":1}"#;
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, ok_response("v1".into(), Id::Num(1)));
+
+	let mut module = RpcModule::new(());
+	module.register_method("version", |_, _| Ok("v2")).unwrap();
+	let previous = handle.swap_methods(module).unwrap();
+	assert!(previous.method("version").is_some());
+
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, ok_response("v2".into(), Id::Num(1)));
+
+	// A failing patch leaves the methods unchanged.
+	let err = handle
+		.patch_methods(|methods| {
+			methods.remove_method("version");
+			Err(Error::Custom("Rolled back".into()))
+		})
+		.unwrap_err();
+	assert!(matches!(err, Error::Custom(msg) if msg == "Rolled back"));
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, ok_response("v2".into(), Id::Num(1)));
+
+	handle
+		.patch_methods(|methods| {
+			let mut module = RpcModule::new(());
+			module.register_method("added", |_, _| Ok("added")).unwrap();
+			methods.merge(module)
+		})
+		.unwrap();
+	let req = r#"{"jsonrpc":"2.0","method":"added","id":2}"#;
+	let response = http_request(req.into(), uri).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, ok_response("added".into(), Id::Num(2)));
+}
+
+#[tokio::test]
+async fn filtered_methods_are_not_found() {
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("lo")).unwrap();
+	module.register_method("admin_peers", |_, _| Ok(0)).unwrap();
+
+	let server = HttpServerBuilder::default()
+		.method_filter(|name, details| !name.starts_with("admin_") && details.peer_addr().ip().is_loopback())
+		.build("127.0.0.1:0")
+		.unwrap();
+	let uri = to_http_uri(server.local_addr().unwrap());
+	let _handle = server.start(module).unwrap();
+
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#;
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, ok_response("lo".into(), Id::Num(1)));
+
+	let req = r#"{"jsonrpc":"2.0","method":"admin_peers","id":2}"#;
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, method_not_found(Id::Num(2)));
+
+	let req = r#"[{"jsonrpc":"2.0","method":"admin_peers","id":3},{"jsonrpc":"2.0","method":"say_hello","id":4}]"#;
+	let response = http_request(req.into(), uri).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, format!("[{},{}]", method_not_found(Id::Num(3)), ok_response("lo".into(), Id::Num(4))));
+}
+
+#[tokio::test]
+async fn connection_details_are_available_to_callbacks() {
+	use jsonrpsee_core::server::connection_state::ConnectionState;
+
+	let mut module = RpcModule::new(());
+	module
+		.register_async_method("peer", |_, _| async move {
+			let state = ConnectionState::current().expect("Called on a connection");
+			let details = state.details().expect("Set by the server");
+			Ok((
+				details.peer_addr().ip().to_string(),
+				format!("{:?}", details.transport()),
+				details.protocol().map(str::to_owned),
+			))
+		})
+		.unwrap();
+
+	let server = HttpServerBuilder::default().build("127.0.0.1:0").unwrap();
+	let uri = to_http_uri(server.local_addr().unwrap());
+	let _handle = server.start(module).unwrap();
+
+	let req = r#"{"jsonrpc":"2.0","method":"peer","id":1}"#;
+	let response = http_request(req.into(), uri).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, ok_response(serde_json::json!(["127.0.0.1", "Http", "HTTP/1.1"]), Id::Num(1)));
+}
+
+#[tokio::test]
+async fn http2_prior_knowledge_works() {
+	let server = HttpServerBuilder::default().build("127.0.0.1:0").unwrap();
+	let addr = server.local_addr().unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("lo")).unwrap();
+	let _handle = server.start(module).unwrap();
+	let uri = to_http_uri(addr);
+
+	// Many calls multiplexed over a single HTTP/2 connection.
+	let client = hyper::Client::builder().http2_only(true).build_http::<hyper::Body>();
+	let calls = (0..32).map(|id| {
+		let req = hyper::Request::post(uri.clone())
+			.header(hyper::header::CONTENT_TYPE, "application/json")
+			.body(hyper::Body::from(call("say_hello", Vec::<()>::new(), Id::Num(id))))
+			.unwrap();
+		client.request(req)
+	});
+	for (id, response) in futures_util::future::join_all(calls).await.into_iter().enumerate() {
+		let response = response.unwrap();
+		assert_eq!(response.version(), hyper::Version::HTTP_2);
+		let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+		assert_eq!(String::from_utf8(body.to_vec()).unwrap(), ok_response("lo".into(), Id::Num(id as u64)));
+	}
+}
@@ -0,0 +1,312 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Endpoints the server listens on: a Unix domain socket or a named pipe on Windows, or a TCP socket. The server can
+//! also serve a single connection over the standard input and output.
+
+use std::fmt;
+use std::io;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use jsonrpsee_core::middleware::TransportKind;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::{TcpListener, TcpStream};
+
+#[cfg(unix)]
+use self::unix as local;
+#[cfg(windows)]
+use self::windows as local;
+
+/// Listener accepting the connections on the endpoint.
+#[derive(Debug)]
+pub(crate) enum Listener {
+	/// IPC endpoint.
+	Local(local::Listener),
+	/// TCP socket.
+	Tcp(TcpListener),
+	/// Single connection, accepted once.
+	Stdio(Option<Stream>),
+}
+
+/// Stream of a connection.
+pub(crate) enum Stream {
+	/// IPC connection.
+	Local(local::Stream),
+	/// TCP connection.
+	Tcp(TcpStream),
+	/// Standard input and output, or other pipes.
+	Stdio(StdioStream),
+}
+
+/// Reading and writing ends of a [`Stream::Stdio`].
+pub(crate) struct StdioStream {
+	pub(crate) reader: Box<dyn AsyncRead + Send + Unpin>,
+	pub(crate) writer: Box<dyn AsyncWrite + Send + Unpin>,
+}
+
+impl Listener {
+	/// Listen on the IPC endpoint `path`.
+	pub(crate) fn bind_local(path: &Path) -> io::Result<Self> {
+		local::Listener::bind(path).map(Self::Local)
+	}
+
+	/// Path of the IPC endpoint, if listening on one.
+	pub(crate) fn path(&self) -> Option<&Path> {
+		match self {
+			Self::Local(listener) => Some(listener.path()),
+			Self::Tcp(_) | Self::Stdio(_) => None,
+		}
+	}
+
+	/// Address of the TCP socket, if listening on one.
+	pub(crate) fn local_addr(&self) -> Option<io::Result<SocketAddr>> {
+		match self {
+			Self::Local(_) | Self::Stdio(_) => None,
+			Self::Tcp(listener) => Some(listener.local_addr()),
+		}
+	}
+
+	/// Whether the listener accepts a single connection, the server then stops once it is closed.
+	pub(crate) fn is_single(&self) -> bool {
+		matches!(self, Self::Stdio(_))
+	}
+
+	/// Poll for the next connection.
+	pub(crate) fn poll_accept(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<Stream>> {
+		match self {
+			Self::Local(listener) => listener.poll_accept(cx).map_ok(Stream::Local),
+			Self::Tcp(listener) => listener.poll_accept(cx).map_ok(|(stream, _)| {
+				// Messages are small and written at once.
+				let _ = stream.set_nodelay(true);
+				Stream::Tcp(stream)
+			}),
+			Self::Stdio(stream) => match stream.take() {
+				Some(stream) => Poll::Ready(Ok(stream)),
+				None => Poll::Pending,
+			},
+		}
+	}
+}
+
+impl Stream {
+	/// Address of the peer, only TCP connections have one.
+	pub(crate) fn peer_addr(&self) -> Option<SocketAddr> {
+		match self {
+			Self::Local(_) | Self::Stdio(_) => None,
+			Self::Tcp(stream) => stream.peer_addr().ok(),
+		}
+	}
+
+	/// Transport of the connection.
+	pub(crate) fn transport(&self) -> TransportKind {
+		match self {
+			Self::Local(_) => TransportKind::Ipc,
+			Self::Tcp(_) => TransportKind::Tcp,
+			Self::Stdio(_) => TransportKind::Stdio,
+		}
+	}
+}
+
+impl fmt::Debug for Stream {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Local(stream) => f.debug_tuple("Local").field(stream).finish(),
+			Self::Tcp(stream) => f.debug_tuple("Tcp").field(stream).finish(),
+			Self::Stdio(_) => f.write_str("Stdio"),
+		}
+	}
+}
+
+impl AsyncRead for Stream {
+	fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+		match self.get_mut() {
+			Self::Local(stream) => Pin::new(stream).poll_read(cx, buf),
+			Self::Tcp(stream) => Pin::new(stream).poll_read(cx, buf),
+			Self::Stdio(stream) => Pin::new(&mut stream.reader).poll_read(cx, buf),
+		}
+	}
+}
+
+impl AsyncWrite for Stream {
+	fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+		match self.get_mut() {
+			Self::Local(stream) => Pin::new(stream).poll_write(cx, buf),
+			Self::Tcp(stream) => Pin::new(stream).poll_write(cx, buf),
+			Self::Stdio(stream) => Pin::new(&mut stream.writer).poll_write(cx, buf),
+		}
+	}
+
+	fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+		match self.get_mut() {
+			Self::Local(stream) => Pin::new(stream).poll_flush(cx),
+			Self::Tcp(stream) => Pin::new(stream).poll_flush(cx),
+			Self::Stdio(stream) => Pin::new(&mut stream.writer).poll_flush(cx),
+		}
+	}
+
+	fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+		match self.get_mut() {
+			Self::Local(stream) => Pin::new(stream).poll_shutdown(cx),
+			Self::Tcp(stream) => Pin::new(stream).poll_shutdown(cx),
+			Self::Stdio(stream) => Pin::new(&mut stream.writer).poll_shutdown(cx),
+		}
+	}
+}
+
+#[cfg(unix)]
+mod unix {
+	use std::io;
+	use std::os::unix::fs::FileTypeExt;
+	use std::path::{Path, PathBuf};
+	use std::task::{Context, Poll};
+
+	use tokio::net::{UnixListener, UnixStream};
+
+	/// Stream of a connection.
+	pub(crate) type Stream = UnixStream;
+
+	/// Listener accepting the connections on the endpoint.
+	#[derive(Debug)]
+	pub(crate) struct Listener {
+		inner: UnixListener,
+		path: PathBuf,
+	}
+
+	impl Listener {
+		/// Bind the Unix domain socket at `path`, replacing the stale socket left there by a previous server if any.
+		/// Fails with [`io::ErrorKind::AddrInUse`] if a server still listens on it.
+		pub(crate) fn bind(path: &Path) -> io::Result<Self> {
+			if let Ok(metadata) = std::fs::symlink_metadata(path) {
+				if metadata.file_type().is_socket() {
+					// Only a socket nobody listens on refuses the connections.
+					match std::os::unix::net::UnixStream::connect(path) {
+						Ok(_) => {
+							let msg = format!("A server already listens on {}", path.display());
+							return Err(io::Error::new(io::ErrorKind::AddrInUse, msg));
+						}
+						Err(err) if err.kind() == io::ErrorKind::ConnectionRefused => std::fs::remove_file(path)?,
+						Err(err) => return Err(err),
+					}
+				}
+			}
+			let inner = UnixListener::bind(path)?;
+			Ok(Self { inner, path: path.to_owned() })
+		}
+
+		/// Path of the endpoint.
+		pub(crate) fn path(&self) -> &Path {
+			&self.path
+		}
+
+		/// Poll for the next connection.
+		pub(crate) fn poll_accept(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<Stream>> {
+			self.inner.poll_accept(cx).map_ok(|(stream, _)| stream)
+		}
+	}
+
+	impl Drop for Listener {
+		fn drop(&mut self) {
+			let _ = std::fs::remove_file(&self.path);
+		}
+	}
+}
+
+#[cfg(windows)]
+mod windows {
+	use std::fmt;
+	use std::future::Future;
+	use std::io;
+	use std::path::{Path, PathBuf};
+	use std::pin::Pin;
+	use std::task::{Context, Poll};
+
+	use tokio::net::windows::named_pipe::{NamedPipeServer, ServerOptions};
+
+	/// Stream of a connection.
+	pub(crate) type Stream = NamedPipeServer;
+
+	type Connecting = Pin<Box<dyn Future<Output = io::Result<NamedPipeServer>> + Send>>;
+
+	/// Listener accepting the connections on the endpoint.
+	///
+	/// Each instance of a named pipe serves a single client, so an instance waits for the next client while the
+	/// connected ones are served.
+	pub(crate) struct Listener {
+		connecting: Connecting,
+		path: PathBuf,
+	}
+
+	impl fmt::Debug for Listener {
+		fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+			f.debug_struct("Listener").field("path", &self.path).finish()
+		}
+	}
+
+	impl Listener {
+		/// Create the named pipe `path`, such as `\\.\pipe\jsonrpsee`. Fails if another server has created it.
+		pub(crate) fn bind(path: &Path) -> io::Result<Self> {
+			let instance = ServerOptions::new().first_pipe_instance(true).create(path)?;
+			Ok(Self { connecting: connect(instance), path: path.to_owned() })
+		}
+
+		/// Path of the endpoint.
+		pub(crate) fn path(&self) -> &Path {
+			&self.path
+		}
+
+		/// Poll for the next connection.
+		pub(crate) fn poll_accept(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<Stream>> {
+			let connected = match self.connecting.as_mut().poll(cx) {
+				Poll::Ready(connected) => connected,
+				Poll::Pending => return Poll::Pending,
+			};
+			// The next instance should exist before this one is handed out, for the clients not to find no pipe.
+			self.connecting = match ServerOptions::new().create(&self.path) {
+				Ok(next) => connect(next),
+				Err(_) => create_and_connect(self.path.clone()),
+			};
+			Poll::Ready(connected)
+		}
+	}
+
+	fn connect(instance: NamedPipeServer) -> Connecting {
+		Box::pin(async move {
+			instance.connect().await?;
+			Ok(instance)
+		})
+	}
+
+	fn create_and_connect(path: PathBuf) -> Connecting {
+		Box::pin(async move {
+			let instance = ServerOptions::new().create(&path)?;
+			instance.connect().await?;
+			Ok(instance)
+		})
+	}
+}
@@ -0,0 +1,239 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Framing of the messages: by default each message is a single line of JSON, terminated by a newline. See
+//! [`Framing`] for the alternative.
+
+use std::io;
+
+use jsonrpsee_core::framing::Framing;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Error while reading a message.
+#[derive(Debug)]
+pub(crate) enum ReadError {
+	/// The peer closed the connection.
+	Closed,
+	/// The message was larger than the maximum size, it was skipped.
+	TooLarge,
+	/// I/O error.
+	Io(io::Error),
+}
+
+/// Read the next message into `buf`.
+pub(crate) async fn read_message<R>(
+	reader: &mut R,
+	buf: &mut Vec<u8>,
+	max_size: usize,
+	framing: Framing,
+) -> Result<(), ReadError>
+where
+	R: AsyncBufRead + Unpin,
+{
+	match framing {
+		Framing::Lines => read_line(reader, buf, max_size).await,
+		Framing::ContentLength => read_content(reader, buf, max_size).await,
+	}
+}
+
+/// Read the next line into `buf`, without its terminating newline. Empty lines are skipped.
+async fn read_line<R>(reader: &mut R, buf: &mut Vec<u8>, max_size: usize) -> Result<(), ReadError>
+where
+	R: AsyncBufRead + Unpin,
+{
+	let mut too_large = false;
+	loop {
+		let available = reader.fill_buf().await.map_err(ReadError::Io)?;
+		if available.is_empty() {
+			return Err(ReadError::Closed);
+		}
+
+		let (line, used, complete) = match available.iter().position(|b| *b == b'\n') {
+			Some(end) => (&available[..end], end + 1, true),
+			None => (available, available.len(), false),
+		};
+		if !too_large {
+			if buf.len() + line.len() > max_size {
+				too_large = true;
+				buf.clear();
+			} else {
+				buf.extend_from_slice(line);
+			}
+		}
+		reader.consume(used);
+
+		if complete {
+			if too_large {
+				return Err(ReadError::TooLarge);
+			}
+			if buf.last() == Some(&b'\r') {
+				buf.pop();
+			}
+			if !buf.iter().all(u8::is_ascii_whitespace) {
+				return Ok(());
+			}
+			buf.clear();
+		}
+	}
+}
+
+/// Max size of a header line of a message framed by [`Framing::ContentLength`].
+const MAX_HEADER_SIZE: usize = 1024;
+
+/// Read the headers and then the content of the next message into `buf`.
+async fn read_content<R>(reader: &mut R, buf: &mut Vec<u8>, max_size: usize) -> Result<(), ReadError>
+where
+	R: AsyncBufRead + Unpin,
+{
+	// A malformed header terminates the connection, the start of the next message couldn't be found.
+	let malformed = || ReadError::Io(io::Error::new(io::ErrorKind::InvalidData, "malformed message header"));
+	let mut content_length = None;
+	loop {
+		let mut line = Vec::new();
+		if !read_line_raw(reader, &mut line, MAX_HEADER_SIZE).await? {
+			return Err(malformed());
+		}
+		if line.last() == Some(&b'\r') {
+			line.pop();
+		}
+		if line.is_empty() {
+			match content_length {
+				Some(_) => break,
+				// Blank lines between the messages.
+				None => continue,
+			}
+		}
+		let line = std::str::from_utf8(&line).map_err(|_| malformed())?;
+		let (name, value) = line.split_once(':').ok_or_else(malformed)?;
+		if name.trim().eq_ignore_ascii_case("Content-Length") {
+			content_length = Some(value.trim().parse::<usize>().map_err(|_| malformed())?);
+		}
+	}
+
+	let content_length = content_length.unwrap_or_default();
+	let mut content = reader.take(content_length as u64);
+	if content_length > max_size {
+		tokio::io::copy(&mut content, &mut tokio::io::sink()).await.map_err(ReadError::Io)?;
+		return Err(ReadError::TooLarge);
+	}
+	content.read_to_end(buf).await.map_err(ReadError::Io)?;
+	if buf.len() < content_length {
+		return Err(ReadError::Closed);
+	}
+	Ok(())
+}
+
+/// Read a line into `buf`, without its terminating newline. Returns `false` if the line is larger than `max_size`.
+async fn read_line_raw<R>(reader: &mut R, buf: &mut Vec<u8>, max_size: usize) -> Result<bool, ReadError>
+where
+	R: AsyncBufRead + Unpin,
+{
+	loop {
+		let available = reader.fill_buf().await.map_err(ReadError::Io)?;
+		if available.is_empty() {
+			return Err(ReadError::Closed);
+		}
+		let (line, used, complete) = match available.iter().position(|b| *b == b'\n') {
+			Some(end) => (&available[..end], end + 1, true),
+			None => (available, available.len(), false),
+		};
+		if buf.len() + line.len() > max_size {
+			return Ok(false);
+		}
+		buf.extend_from_slice(line);
+		reader.consume(used);
+		if complete {
+			return Ok(true);
+		}
+	}
+}
+
+/// Write `message` with its framing, and flush it.
+pub(crate) async fn write_message<W>(writer: &mut W, message: &str, framing: Framing) -> io::Result<()>
+where
+	W: AsyncWrite + Unpin,
+{
+	match framing {
+		Framing::Lines => {
+			writer.write_all(message.as_bytes()).await?;
+			writer.write_all(b"\n").await?;
+		}
+		Framing::ContentLength => {
+			writer.write_all(format!("Content-Length: {}\r\n\r\n", message.len()).as_bytes()).await?;
+			writer.write_all(message.as_bytes()).await?;
+		}
+	}
+	writer.flush().await
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{read_message, write_message, Framing, ReadError};
+
+	#[tokio::test]
+	async fn messages_are_split_on_newlines() {
+		let mut reader: &[u8] = b"{\"a\":1}\n\n  \r\n[1,2]\r\n{\"too\":\"large\"}\n{\"partial\"";
+		let mut buf = Vec::new();
+
+		read_message(&mut reader, &mut buf, 10, Framing::Lines).await.unwrap();
+		assert_eq!(buf, b"{\"a\":1}");
+		buf.clear();
+		read_message(&mut reader, &mut buf, 10, Framing::Lines).await.unwrap();
+		assert_eq!(buf, b"[1,2]");
+		buf.clear();
+		assert!(matches!(read_message(&mut reader, &mut buf, 10, Framing::Lines).await, Err(ReadError::TooLarge)));
+		buf.clear();
+		assert!(matches!(read_message(&mut reader, &mut buf, 20, Framing::Lines).await, Err(ReadError::Closed)));
+	}
+
+	#[tokio::test]
+	async fn messages_are_preceded_by_their_length() {
+		let mut written = Vec::new();
+		write_message(&mut written, "{\"a\":1}", Framing::ContentLength).await.unwrap();
+		assert_eq!(written, b"Content-Length: 7\r\n\r\n{\"a\":1}");
+
+		written.extend_from_slice(b"\r\ncontent-length:13\r\nContent-Type: application/json\r\n\r\n{\"too\":\"big\"}");
+		written.extend_from_slice(b"Content-Length: 5\r\n\r\n[1,2]Content-Length: 5\r\n\r\n[1");
+		let mut reader = &written[..];
+		let mut buf = Vec::new();
+
+		read_message(&mut reader, &mut buf, 10, Framing::ContentLength).await.unwrap();
+		assert_eq!(buf, b"{\"a\":1}");
+		buf.clear();
+		assert!(matches!(
+			read_message(&mut reader, &mut buf, 10, Framing::ContentLength).await,
+			Err(ReadError::TooLarge)
+		));
+		buf.clear();
+		read_message(&mut reader, &mut buf, 10, Framing::ContentLength).await.unwrap();
+		assert_eq!(buf, b"[1,2]");
+		buf.clear();
+		assert!(matches!(
+			read_message(&mut reader, &mut buf, 10, Framing::ContentLength).await,
+			Err(ReadError::Closed)
+		));
+	}
+}
@@ -0,0 +1,55 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+#![warn(missing_debug_implementations, missing_docs, unreachable_pub)]
+
+//! # jsonrpsee-ipc-server
+//!
+//! `jsonrpsee-ipc-server` is a [JSON RPC](https://www.jsonrpc.org/specification) IPC server library that's is built for `async/await`.
+//!
+//! The server listens on a Unix domain socket, or on a named pipe on Windows, and exchanges the messages as lines of
+//! JSON. The methods and subscriptions behave like on the WebSocket server.
+//!
+//! The server can also listen on a plain TCP socket with the same framing, for peers on other hosts without the
+//! overhead of HTTP or WebSocket, or serve a single connection over the standard input and output, for a server
+//! embedded in a process driven by its parent. The messages can be framed as in the Language Server Protocol instead,
+//! see [`Framing`].
+
+mod endpoint;
+mod framing;
+mod server;
+
+#[cfg(test)]
+mod tests;
+
+pub use jsonrpsee_core::framing::Framing;
+pub use jsonrpsee_core::server::future::{ServerHandle as IpcServerHandle, ShutdownWaiter as IpcShutdownWaiter};
+pub use jsonrpsee_core::server::helpers::BufferFullPolicy;
+pub use jsonrpsee_core::server::rpc_module::{PendingSubscription, RpcModule, SubscriptionSink};
+pub use jsonrpsee_core::{id_providers::*, traits::IdProvider};
+pub use jsonrpsee_types as types;
+pub use server::{Builder as IpcServerBuilder, Server as IpcServer};
+pub use tracing;
@@ -0,0 +1,632 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use std::future::Future;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use crate::endpoint::{Listener, StdioStream, Stream};
+use crate::framing::{read_message, write_message, ReadError};
+use crate::types::error::{ErrorCode, ErrorResponse};
+use crate::types::Id;
+use futures_channel::mpsc;
+use futures_util::future::FutureExt;
+use futures_util::stream::StreamExt;
+use jsonrpsee_core::framing::Framing;
+use jsonrpsee_core::id_providers::RandomIntegerIdProvider;
+use jsonrpsee_core::middleware::{AsyncMiddleware, ConnectionDetails, Middleware};
+use jsonrpsee_core::server::cost_budget::CostBudget;
+use jsonrpsee_core::server::dispatch::Dispatcher;
+use jsonrpsee_core::server::future::{FutureDriver, ServerHandle, StopMonitor};
+use jsonrpsee_core::server::helpers::{BufferFullPolicy, MethodFilter, MethodSink};
+use jsonrpsee_core::server::live_methods::{LiveMethods, MethodsReceiver};
+use jsonrpsee_core::server::resource_limiting::Resources;
+use jsonrpsee_core::server::rpc_module::{ConnectionId, Methods};
+use jsonrpsee_core::traits::IdProvider;
+use jsonrpsee_core::{Error, TEN_MB_SIZE_BYTES};
+use tokio::io::{AsyncRead, AsyncWrite, BufReader, BufWriter};
+use tokio::net::{TcpListener, ToSocketAddrs};
+
+/// Default maximum connections allowed.
+const MAX_CONNECTIONS: u64 = 100;
+
+/// Default maximum subscriptions per connection allowed.
+const MAX_SUBSCRIPTIONS_PER_CONNECTION: u32 = 1024;
+
+/// An IPC JSON RPC server.
+pub struct Server<M> {
+	listener: Listener,
+	cfg: Settings,
+	stop_monitor: StopMonitor,
+	resources: Resources,
+	middleware: M,
+	id_provider: Arc<dyn IdProvider>,
+	/// Methods dispatched to, which can be replaced while the server is running.
+	methods: LiveMethods,
+}
+
+impl<M> std::fmt::Debug for Server<M> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("Server")
+			.field("listener", &self.listener)
+			.field("cfg", &self.cfg)
+			.field("stop_monitor", &self.stop_monitor)
+			.field("id_provider", &self.id_provider)
+			.field("resources", &self.resources)
+			.field("methods", &self.methods)
+			.finish()
+	}
+}
+
+impl<M: Middleware> Server<M> {
+	/// Returns the path of the endpoint the server listens on. Errors if the server listens on a TCP socket.
+	pub fn local_path(&self) -> Result<&Path, Error> {
+		self.listener.path().ok_or_else(|| Error::Custom("The server listens on a TCP socket".into()))
+	}
+
+	/// Returns the address of the TCP socket the server listens on. Errors if the server listens on an IPC endpoint.
+	pub fn local_addr(&self) -> Result<SocketAddr, Error> {
+		match self.listener.local_addr() {
+			Some(addr) => addr.map_err(Into::into),
+			None => Err(Error::Custom("The server listens on an IPC endpoint".into())),
+		}
+	}
+
+	/// Returns the handle to stop the running server.
+	pub fn server_handle(&self) -> ServerHandle {
+		self.stop_monitor.handle(self.methods.clone())
+	}
+
+	/// Start responding to connections requests. This will run on the tokio runtime until the server is stopped.
+	///
+	/// The methods can be replaced while the server is running with [`ServerHandle::swap_methods`].
+	pub fn start(mut self, methods: impl Into<Methods>) -> Result<ServerHandle, Error> {
+		self.methods.swap(methods)?;
+		let handle = self.server_handle();
+
+		match self.cfg.tokio_runtime.take() {
+			Some(rt) => rt.spawn(self.start_inner()),
+			None => tokio::spawn(self.start_inner()),
+		};
+
+		Ok(handle)
+	}
+
+	async fn start_inner(self) {
+		let stop_monitor = self.stop_monitor;
+		let cfg = self.cfg;
+
+		let mut id: ConnectionId = 0;
+		let mut connections = FutureDriver::default();
+		let mut incoming = Monitored::new(Incoming(self.listener), &stop_monitor);
+
+		loop {
+			match connections.select_with(&mut incoming).await {
+				Ok(stream) => {
+					if connections.count() >= cfg.max_connections as usize {
+						tracing::warn!("Too many connections. Try again in a while.");
+						tokio::spawn(reject_connection(stream, cfg.framing));
+						continue;
+					}
+
+					tracing::info!("Accepting new connection, {}/{}", connections.count() + 1, cfg.max_connections);
+
+					connections.add(Box::pin(background_task(
+						stream,
+						id,
+						self.methods.receiver(),
+						self.resources.for_connection(),
+						&cfg,
+						stop_monitor.clone(),
+						self.middleware.clone(),
+						self.id_provider.clone(),
+					)));
+
+					id = id.wrapping_add(1);
+					if incoming.future.0.is_single() {
+						break;
+					}
+				}
+				Err(MonitoredError::Selector(err)) => {
+					tracing::error!("Error while awaiting a new connection: {:?}", err);
+				}
+				Err(MonitoredError::Shutdown) => break,
+			}
+		}
+
+		// Stop accepting connections, the endpoint is removed. A server of a single connection stops once it is
+		// closed.
+		drop(incoming);
+		connections.await
+	}
+}
+
+/// Answer a connection exceeding the max connections with a `Server is busy` error, then close it.
+async fn reject_connection(mut stream: Stream, framing: Framing) {
+	let error = ErrorResponse::new(ErrorCode::ServerIsBusy.into(), Id::Null).to_string();
+	if let Err(err) = write_message(&mut stream, &error, framing).await {
+		tracing::debug!("Failed to reject the connection: {:?}", err);
+	}
+}
+
+/// This is a glorified select listening for new messages, while also checking the `stop_receiver` signal.
+struct Monitored<'a, F> {
+	future: F,
+	stop_monitor: &'a StopMonitor,
+}
+
+impl<'a, F> Monitored<'a, F> {
+	fn new(future: F, stop_monitor: &'a StopMonitor) -> Self {
+		Monitored { future, stop_monitor }
+	}
+}
+
+enum MonitoredError<E> {
+	Shutdown,
+	Selector(E),
+}
+
+struct Incoming(Listener);
+
+impl<'a> Future for Monitored<'a, Incoming> {
+	type Output = Result<Stream, MonitoredError<std::io::Error>>;
+
+	fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+		let this = Pin::into_inner(self);
+
+		if this.stop_monitor.shutdown_requested() {
+			return Poll::Ready(Err(MonitoredError::Shutdown));
+		}
+
+		this.future.0.poll_accept(cx).map_err(MonitoredError::Selector)
+	}
+}
+
+impl<'a, 'f, F, T, E> Future for Monitored<'a, Pin<&'f mut F>>
+where
+	F: Future<Output = Result<T, E>>,
+{
+	type Output = Result<T, MonitoredError<E>>;
+
+	fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+		let this = Pin::into_inner(self);
+
+		if this.stop_monitor.shutdown_requested() {
+			return Poll::Ready(Err(MonitoredError::Shutdown));
+		}
+
+		this.future.poll_unpin(cx).map_err(MonitoredError::Selector)
+	}
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn background_task<M: Middleware>(
+	stream: Stream,
+	conn_id: ConnectionId,
+	mut live_methods: MethodsReceiver,
+	resources: Resources,
+	cfg: &Settings,
+	stop_server: StopMonitor,
+	middleware: M,
+	id_provider: Arc<dyn IdProvider>,
+) -> Result<(), Error> {
+	// Only TCP connections have an address.
+	let peer_addr = stream.peer_addr().unwrap_or_else(|| SocketAddr::from(([0, 0, 0, 0], 0)));
+	let details = ConnectionDetails::new(conn_id, peer_addr, stream.transport());
+	let framing = cfg.framing;
+	let (reader, writer) = tokio::io::split(stream);
+	let mut reader = BufReader::new(reader);
+	let mut writer = BufWriter::new(writer);
+	let max_request_body_size = cfg.max_request_body_size;
+
+	let (tx, mut rx) = mpsc::unbounded::<String>();
+	let mut sink = MethodSink::new_with_limit(tx, max_request_body_size);
+	if let Some((capacity, policy)) = cfg.message_buffer {
+		sink = sink.with_buffer_capacity(capacity, policy);
+	}
+	let delivered = sink.track_delivery();
+	let stop_server2 = stop_server.clone();
+
+	middleware.on_connect_with_details(&details);
+	let dispatcher = Dispatcher::new(details, sink.clone(), middleware.clone())
+		.with_resources(resources)
+		.with_id_provider(id_provider)
+		.with_max_subscriptions(cfg.max_subscriptions_per_connection)
+		.with_cost_budget(cfg.cost_budget)
+		.with_method_filter(cfg.method_filter.clone())
+		.with_async_middleware(cfg.async_middleware.clone())
+		.with_call_spans(cfg.call_spans);
+	let close_notify_server_stop = dispatcher.close_notify();
+
+	// Send results back to the client.
+	tokio::spawn(async move {
+		while !stop_server2.shutdown_requested() {
+			match rx.next().await {
+				Some(response) => {
+					tracing::debug!("send {} bytes", response.len());
+					tracing::trace!("send: {}", response);
+					// If the message can't be written then terminate the connection.
+					if let Err(err) = write_message(&mut writer, &response, framing).await {
+						tracing::error!("IPC transport error: {:?}; terminate connection", err);
+						break;
+					}
+					delivered.mark_delivered();
+				}
+				None => break,
+			}
+		}
+
+		close_notify_server_stop.notify_one();
+	});
+
+	let mut data = Vec::with_capacity(100);
+	let mut method_executors = FutureDriver::default();
+
+	let result = loop {
+		data.clear();
+
+		{
+			// Need the extra scope to drop this pinned future and reclaim access to `data`
+			let receive = read_message(&mut reader, &mut data, max_request_body_size as usize, framing);
+
+			tokio::pin!(receive);
+
+			if let Err(err) = method_executors.select_with(Monitored::new(receive, &stop_server)).await {
+				match err {
+					MonitoredError::Selector(ReadError::Closed) => {
+						tracing::debug!("IPC transport: remote peer terminated the connection: {}", conn_id);
+						sink.close();
+						break Ok(());
+					}
+					MonitoredError::Selector(ReadError::TooLarge) => {
+						tracing::warn!(
+							"IPC transport error: message is too big (max is {} bytes)",
+							max_request_body_size
+						);
+						sink.send_error(Id::Null, ErrorCode::OversizedRequest.into());
+						continue;
+					}
+					MonitoredError::Selector(ReadError::Io(err)) => {
+						tracing::error!("IPC transport error: {:?} => terminating connection {}", err, conn_id);
+						sink.close();
+						break Err(err.into());
+					}
+					MonitoredError::Shutdown => break Ok(()),
+				};
+			};
+		};
+
+		tracing::debug!("recv {} bytes", data.len());
+
+		if let Some(call) = dispatcher.dispatch(live_methods.current(), &data) {
+			method_executors.add(call);
+		}
+	};
+
+	middleware.on_disconnect();
+	dispatcher.close();
+
+	// **NOTE** Do not return early in this function. The running methods need to be driven to completion or dropped
+	// here to guarantee proper drop behaviour.
+	if stop_server.shutdown_requested() {
+		// Drive all running methods to completion.
+		method_executors.await;
+	} else {
+		// Nobody is left to read the responses, cancel the running methods.
+		tracing::debug!("Cancelling {} running methods of connection {}", method_executors.count(), conn_id);
+		drop(method_executors);
+	}
+
+	result
+}
+
+/// JSON-RPC IPC server settings.
+#[derive(Debug, Clone)]
+struct Settings {
+	/// Maximum size in bytes of a request.
+	max_request_body_size: u32,
+	/// Maximum number of incoming connections allowed.
+	max_connections: u64,
+	/// Maximum number of subscriptions opened on a connection at the same time.
+	max_subscriptions_per_connection: u32,
+	/// Custom tokio runtime to run the server on.
+	tokio_runtime: Option<tokio::runtime::Handle>,
+	/// How the messages are delimited.
+	framing: Framing,
+	/// Cost budget of each connection, if enabled.
+	cost_budget: Option<CostBudget>,
+	/// Max number of messages waiting to be sent to each connection and what to do when it's reached, if bounded.
+	message_buffer: Option<(usize, BufferFullPolicy)>,
+	/// Filter of the methods a connection can call, if any.
+	method_filter: Option<MethodFilter>,
+	/// Asynchronous middleware awaited around the execution of each call, if any.
+	async_middleware: Option<Arc<dyn AsyncMiddleware>>,
+	/// Whether each call is executed in its own tracing span.
+	call_spans: bool,
+}
+
+impl Default for Settings {
+	fn default() -> Self {
+		Self {
+			max_request_body_size: TEN_MB_SIZE_BYTES,
+			max_connections: MAX_CONNECTIONS,
+			max_subscriptions_per_connection: MAX_SUBSCRIPTIONS_PER_CONNECTION,
+			tokio_runtime: None,
+			framing: Framing::default(),
+			cost_budget: None,
+			message_buffer: None,
+			method_filter: None,
+			async_middleware: None,
+			call_spans: false,
+		}
+	}
+}
+
+/// Builder to configure and create a JSON-RPC IPC server
+#[derive(Debug)]
+pub struct Builder<M = ()> {
+	settings: Settings,
+	resources: Resources,
+	middleware: M,
+	id_provider: Arc<dyn IdProvider>,
+}
+
+impl Default for Builder {
+	fn default() -> Self {
+		Builder {
+			settings: Settings::default(),
+			resources: Resources::default(),
+			middleware: (),
+			id_provider: Arc::new(RandomIntegerIdProvider),
+		}
+	}
+}
+
+impl Builder {
+	/// Create a default server builder.
+	pub fn new() -> Self {
+		Self::default()
+	}
+}
+
+impl<M> Builder<M> {
+	/// Set the maximum size of a request body in bytes. Default is 10 MiB.
+	pub fn max_request_body_size(mut self, size: u32) -> Self {
+		self.settings.max_request_body_size = size;
+		self
+	}
+
+	/// Set the maximum number of connections allowed. Default is 100.
+	pub fn max_connections(mut self, max: u64) -> Self {
+		self.settings.max_connections = max;
+		self
+	}
+
+	/// Set the maximum number of subscriptions a connection can have open at the same time, the subscription calls
+	/// exceeding it are answered with a `Too many subscriptions on the connection` error. Default is 1024.
+	pub fn max_subscriptions_per_connection(mut self, max: u32) -> Self {
+		self.settings.max_subscriptions_per_connection = max;
+		self
+	}
+
+	/// Set how the messages are delimited. Default is [`Framing::Lines`], one JSON message per line.
+	pub fn framing(mut self, framing: Framing) -> Self {
+		self.settings.framing = framing;
+		self
+	}
+
+	/// Enable a cost budget per connection, rejecting the calls exceeding it with a `Cost budget exceeded` error.
+	///
+	/// Each call takes the cost of the method it calls from the budget of its connection, see
+	/// [`MethodResourcesBuilder::cost`](../jsonrpsee_core/server/rpc_module/struct.MethodResourcesBuilder.html#method.cost).
+	/// The budget of a connection starts with `capacity` units, and `refill_per_sec` units are added back to it every
+	/// second up to the capacity.
+	///
+	/// Default: disabled, the calls are not limited.
+	pub fn cost_budget(mut self, capacity: u32, refill_per_sec: u32) -> Self {
+		self.settings.cost_budget = Some(CostBudget { capacity, refill_per_sec });
+		self
+	}
+
+	/// Limit the number of messages waiting to be sent to each connection to `capacity`, `policy` telling what to
+	/// do with the messages sent when the buffer of a connection is full, as on the WebSocket server.
+	///
+	/// Default: unbounded.
+	pub fn message_buffer_capacity(mut self, capacity: usize, policy: BufferFullPolicy) -> Self {
+		self.settings.message_buffer = Some((capacity, policy));
+		self
+	}
+
+	/// Only expose the methods for which `filter` returns `true` to a connection, given the method name and the
+	/// details of the connection. Calls to the other methods are answered with a `Method not found` error, as if
+	/// they didn't exist.
+	///
+	/// Default: all methods can be called.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use jsonrpsee_core::middleware::TransportKind;
+	/// use jsonrpsee_ipc_server::IpcServerBuilder;
+	///
+	/// // Hide the `admin_` methods from the clients connecting over TCP.
+	/// let builder = IpcServerBuilder::default()
+	///     .method_filter(|name, details| !name.starts_with("admin_") || details.transport() != TransportKind::Tcp);
+	/// ```
+	pub fn method_filter(mut self, filter: impl Fn(&str, &ConnectionDetails) -> bool + Send + Sync + 'static) -> Self {
+		self.settings.method_filter = Some(MethodFilter::new(filter));
+		self
+	}
+
+	/// Await the hooks of an [`AsyncMiddleware`] around the execution of each method call, for instance to authorize
+	/// the calls with a remote service. Calls rejected by [`AsyncMiddleware::on_call`] are answered with its error.
+	///
+	/// Default: no asynchronous middleware.
+	pub fn set_async_middleware(mut self, middleware: impl AsyncMiddleware) -> Self {
+		self.settings.async_middleware = Some(Arc::new(middleware));
+		self
+	}
+
+	/// Execute each method call in its own `rpc_call` tracing span, as on the WebSocket server.
+	///
+	/// Default: disabled.
+	pub fn call_spans(mut self, enabled: bool) -> Self {
+		self.settings.call_spans = enabled;
+		self
+	}
+
+	/// Register a new resource kind. Errors if `label` is already registered, or if the number of
+	/// registered resources on this server instance would exceed 8.
+	///
+	/// See the module documentation for [`resurce_limiting`](../jsonrpsee_utils/server/resource_limiting/index.html#resource-limiting)
+	/// for details.
+	pub fn register_resource(mut self, label: &'static str, capacity: u16, default: u16) -> Result<Self, Error> {
+		self.resources.register(label, capacity, default)?;
+		Ok(self)
+	}
+
+	/// Register a new resource kind like [`Self::register_resource`], of which the calls of a single connection can
+	/// claim at most `connection_capacity` units at the same time, so that one connection can't starve the others.
+	pub fn register_resource_per_connection(
+		mut self,
+		label: &'static str,
+		capacity: u16,
+		connection_capacity: u16,
+		default: u16,
+	) -> Result<Self, Error> {
+		self.resources.register_per_connection(label, capacity, connection_capacity, default)?;
+		Ok(self)
+	}
+
+	/// Add a middleware to the builder [`Middleware`](../jsonrpsee_core/middleware/trait.Middleware.html).
+	pub fn set_middleware<T: Middleware>(self, middleware: T) -> Builder<T> {
+		Builder { settings: self.settings, resources: self.resources, middleware, id_provider: self.id_provider }
+	}
+
+	/// Configure a custom [`tokio::runtime::Handle`] to run the server on.
+	///
+	/// Default: [`tokio::spawn`]
+	pub fn custom_tokio_runtime(mut self, rt: tokio::runtime::Handle) -> Self {
+		self.settings.tokio_runtime = Some(rt);
+		self
+	}
+
+	/// Configure custom `subscription ID` provider for the server to use
+	/// to when getting new subscription calls.
+	///
+	/// Default: [`RandomIntegerIdProvider`].
+	pub fn set_id_provider<I: IdProvider + 'static>(mut self, id_provider: I) -> Self {
+		self.id_provider = Arc::new(id_provider);
+		self
+	}
+
+	/// Finalize the configuration of the server, listening on the Unix domain socket at `path`. A stale socket left at
+	/// `path` by a previous server is replaced, but this fails if another server still listens on it. The socket is
+	/// removed when the server stops.
+	///
+	/// On Windows the server listens on the named pipe `path` instead, such as `\\.\pipe\jsonrpsee`, and fails if
+	/// another server already listens on it.
+	///
+	/// ```rust
+	/// #[tokio::main]
+	/// async fn main() {
+	///   # #[cfg(unix)]
+	///   let path = std::env::temp_dir().join("jsonrpsee-ipc-server-doc.ipc");
+	///   # #[cfg(windows)]
+	///   # let path = std::path::PathBuf::from(r"\\.\pipe\jsonrpsee-ipc-server-doc");
+	///   let server = jsonrpsee_ipc_server::IpcServerBuilder::default().build(&path).await.unwrap();
+	///   assert_eq!(server.local_path().unwrap(), path);
+	/// }
+	/// ```
+	pub async fn build(self, path: impl AsRef<Path>) -> Result<Server<M>, Error> {
+		let path: PathBuf = path.as_ref().to_owned();
+		let listener = Listener::bind_local(&path)?;
+		Ok(self.finish(listener))
+	}
+
+	/// Finalize the configuration of the server, listening on a TCP socket instead of an IPC endpoint. Messages
+	/// are framed as on IPC endpoints: one JSON message per line.
+	///
+	/// ```rust
+	/// #[tokio::main]
+	/// async fn main() {
+	///   let server = jsonrpsee_ipc_server::IpcServerBuilder::default().build_tcp("127.0.0.1:0").await.unwrap();
+	///   assert!(server.local_addr().unwrap().ip().is_loopback());
+	/// }
+	/// ```
+	pub async fn build_tcp(self, addrs: impl ToSocketAddrs) -> Result<Server<M>, Error> {
+		let listener = TcpListener::bind(addrs).await?;
+		Ok(self.finish(Listener::Tcp(listener)))
+	}
+
+	/// Finalize the configuration of the server, serving a single connection over the standard input and output of
+	/// the process, for instance to be driven by a parent process. The server stops once the standard input is
+	/// closed, and nothing else should be written to the standard output meanwhile.
+	///
+	/// ```rust,no_run
+	/// use jsonrpsee_ipc_server::{IpcServerBuilder, RpcModule};
+	///
+	/// #[tokio::main]
+	/// async fn main() {
+	///   let server = IpcServerBuilder::default().build_stdio();
+	///   let handle = server.start(RpcModule::new(())).unwrap();
+	///   // Exit once the parent process closes the standard input.
+	///   handle.await;
+	/// }
+	/// ```
+	pub fn build_stdio(self) -> Server<M> {
+		self.build_with_io(tokio::io::stdin(), tokio::io::stdout())
+	}
+
+	/// Finalize the configuration of the server, serving a single connection reading the messages from `reader`
+	/// and writing the responses to `writer`, such as the pipes of a child process. The server stops once `reader`
+	/// is closed.
+	pub fn build_with_io<R, W>(self, reader: R, writer: W) -> Server<M>
+	where
+		R: AsyncRead + Send + Unpin + 'static,
+		W: AsyncWrite + Send + Unpin + 'static,
+	{
+		let stream = Stream::Stdio(StdioStream { reader: Box::new(reader), writer: Box::new(writer) });
+		self.finish(Listener::Stdio(Some(stream)))
+	}
+
+	fn finish(self, listener: Listener) -> Server<M> {
+		let stop_monitor = StopMonitor::new();
+		let resources = self.resources;
+		Server {
+			listener,
+			cfg: self.settings,
+			stop_monitor,
+			methods: LiveMethods::new(resources.clone()),
+			resources,
+			middleware: self.middleware,
+			id_provider: self.id_provider,
+		}
+	}
+}
@@ -0,0 +1,330 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+#![cfg(test)]
+
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::types::error::{COST_BUDGET_EXCEEDED_CODE, SERVER_IS_BUSY_CODE};
+use crate::{Framing, IpcServerBuilder, IpcServerHandle, RpcModule};
+use jsonrpsee_core::Error;
+use jsonrpsee_test_utils::helpers::*;
+use jsonrpsee_test_utils::mocks::Id;
+use jsonrpsee_test_utils::TimeoutFutureExt;
+use serde_json::Value as JsonValue;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+#[cfg(windows)]
+use tokio::net::windows::named_pipe::{ClientOptions, NamedPipeClient as Stream};
+use tokio::net::TcpStream;
+#[cfg(unix)]
+use tokio::net::UnixStream as Stream;
+use tracing_subscriber::{EnvFilter, FmtSubscriber};
+
+fn init_logger() {
+	let _ = FmtSubscriber::builder().with_env_filter(EnvFilter::from_default_env()).try_init();
+}
+
+/// Unique path of an endpoint for a test.
+fn endpoint_path() -> PathBuf {
+	static NEXT: AtomicUsize = AtomicUsize::new(0);
+	let id = NEXT.fetch_add(1, Ordering::Relaxed);
+	let name = format!("jsonrpsee-ipc-server-{}-{}", std::process::id(), id);
+	#[cfg(unix)]
+	let path = std::env::temp_dir().join(name);
+	#[cfg(windows)]
+	let path = PathBuf::from(format!(r"\\.\pipe\{}", name));
+	path
+}
+
+#[cfg(unix)]
+async fn connect(path: &PathBuf) -> std::io::Result<Stream> {
+	Stream::connect(path).await
+}
+
+#[cfg(windows)]
+async fn connect(path: &PathBuf) -> std::io::Result<Stream> {
+	ClientOptions::new().open(path)
+}
+
+/// Client exchanging raw lines with the server.
+struct IpcTestClient<S = Stream> {
+	stream: BufReader<S>,
+}
+
+impl IpcTestClient {
+	async fn connect(path: &PathBuf) -> Self {
+		Self { stream: BufReader::new(connect(path).await.unwrap()) }
+	}
+}
+
+impl IpcTestClient<TcpStream> {
+	async fn connect_tcp(addr: SocketAddr) -> Self {
+		Self { stream: BufReader::new(TcpStream::connect(addr).await.unwrap()) }
+	}
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> IpcTestClient<S> {
+	async fn send(&mut self, message: &str) {
+		let stream = self.stream.get_mut();
+		stream.write_all(message.as_bytes()).await.unwrap();
+		stream.write_all(b"\n").await.unwrap();
+	}
+
+	async fn receive(&mut self) -> String {
+		let mut line = String::new();
+		self.stream.read_line(&mut line).with_default_timeout().await.unwrap().unwrap();
+		line.trim_end().to_owned()
+	}
+
+	async fn send_request(&mut self, message: &str) -> String {
+		self.send(message).await;
+		self.receive().await
+	}
+}
+
+fn module() -> RpcModule<()> {
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	module
+		.register_async_method("add_async", |params, _| async move {
+			let params: Vec<u64> = params.parse()?;
+			Ok(params.into_iter().sum::<u64>())
+		})
+		.unwrap();
+	module
+		.register_subscription("subscribe_hello", "subscribe_hello", "unsubscribe_hello", |_, mut sink, _| {
+			tokio::spawn(async move {
+				while sink.send(&"hello").is_ok() {
+					tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+				}
+			});
+			Ok(())
+		})
+		.unwrap();
+	module
+}
+
+async fn server() -> (PathBuf, IpcServerHandle) {
+	let path = endpoint_path();
+	let server = IpcServerBuilder::default().max_request_body_size(200).build(&path).await.unwrap();
+	let handle = server.start(module()).unwrap();
+	(path, handle)
+}
+
+async fn tcp_server() -> (SocketAddr, IpcServerHandle) {
+	let server = IpcServerBuilder::default().max_request_body_size(200).build_tcp("127.0.0.1:0").await.unwrap();
+	let addr = server.local_addr().unwrap();
+	let handle = server.start(module()).unwrap();
+	(addr, handle)
+}
+
+#[tokio::test]
+async fn method_calls_work() {
+	init_logger();
+	let (path, _handle) = server().await;
+	let mut client = IpcTestClient::connect(&path).await;
+
+	let response = client.send_request(&call("say_hello", Vec::<()>::new(), Id::Num(1))).await;
+	assert_eq!(response, ok_response("hello".into(), Id::Num(1)));
+	let response = client.send_request(&call("add_async", vec![1, 2], Id::Num(2))).await;
+	assert_eq!(response, ok_response(3.into(), Id::Num(2)));
+	let response = client.send_request(&call("unknown", Vec::<()>::new(), Id::Num(3))).await;
+	assert_eq!(response, method_not_found(Id::Num(3)));
+}
+
+#[tokio::test]
+async fn batch_calls_work() {
+	let (path, _handle) = server().await;
+	let mut client = IpcTestClient::connect(&path).await;
+
+	let batch = r#"[{"jsonrpc":"2.0","method":"say_hello","id":1},{"jsonrpc":"2.0","method":"add_async","params":[1,2],"id":2}]"#;
+	let response = client.send_request(batch).await;
+	let response: Vec<JsonValue> = serde_json::from_str(&response).unwrap();
+	assert_eq!(response.len(), 2);
+	assert!(response.contains(&serde_json::from_str(&ok_response("hello".into(), Id::Num(1))).unwrap()));
+	assert!(response.contains(&serde_json::from_str(&ok_response(3.into(), Id::Num(2))).unwrap()));
+}
+
+#[tokio::test]
+async fn invalid_messages_are_answered() {
+	let (path, _handle) = server().await;
+	let mut client = IpcTestClient::connect(&path).await;
+
+	assert_eq!(client.send_request("garbage").await, parse_error(Id::Null));
+	assert_eq!(client.send_request(&format!("[{}]", "1".repeat(300))).await, oversized_request());
+	// The connection is still usable.
+	let response = client.send_request(&call("say_hello", Vec::<()>::new(), Id::Num(1))).await;
+	assert_eq!(response, ok_response("hello".into(), Id::Num(1)));
+}
+
+#[tokio::test]
+async fn subscriptions_work() {
+	let (path, _handle) = server().await;
+	let mut client = IpcTestClient::connect(&path).await;
+
+	let response = client.send_request(&call("subscribe_hello", Vec::<()>::new(), Id::Num(1))).await;
+	let response: JsonValue = serde_json::from_str(&response).unwrap();
+	let sub_id = response["result"].clone();
+	assert!(!sub_id.is_null());
+
+	for _ in 0..2 {
+		let notification: JsonValue = serde_json::from_str(&client.receive().await).unwrap();
+		assert_eq!(notification["method"], "subscribe_hello");
+		assert_eq!(notification["params"]["subscription"], sub_id);
+		assert_eq!(notification["params"]["result"], "hello");
+	}
+}
+
+#[tokio::test]
+async fn many_clients_are_served() {
+	let (path, _handle) = server().await;
+
+	let mut clients = Vec::new();
+	for _ in 0..4 {
+		clients.push(IpcTestClient::connect(&path).await);
+	}
+	for (id, client) in clients.iter_mut().enumerate() {
+		let response = client.send_request(&call("say_hello", Vec::<()>::new(), Id::Num(id as u64))).await;
+		assert_eq!(response, ok_response("hello".into(), Id::Num(id as u64)));
+	}
+}
+
+#[tokio::test]
+async fn stop_removes_the_endpoint() {
+	let (path, handle) = server().await;
+	#[cfg(unix)]
+	assert!(path.exists());
+
+	handle.stop().unwrap().with_default_timeout().await.unwrap();
+	#[cfg(unix)]
+	assert!(!path.exists());
+	assert!(connect(&path).await.is_err());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn only_a_stale_socket_is_replaced() {
+	let (path, handle) = server().await;
+
+	// The endpoint of a running server is left alone.
+	let err = IpcServerBuilder::default().build(&path).await.unwrap_err();
+	assert!(matches!(err, Error::Transport(ref err) if err.to_string().contains("already listens")), "{:?}", err);
+	let mut client = IpcTestClient::connect(&path).await;
+	let response = client.send_request(&call("say_hello", Vec::<()>::new(), Id::Num(1))).await;
+	assert_eq!(response, ok_response("hello".into(), Id::Num(1)));
+	drop(client);
+	handle.stop().unwrap().with_default_timeout().await.unwrap();
+
+	// A socket nobody listens on anymore is replaced.
+	let stale = std::os::unix::net::UnixListener::bind(&path).unwrap();
+	drop(stale);
+	let server = IpcServerBuilder::default().build(&path).await.unwrap();
+	let _handle = server.start(module()).unwrap();
+	let mut client = IpcTestClient::connect(&path).await;
+	let response = client.send_request(&call("say_hello", Vec::<()>::new(), Id::Num(1))).await;
+	assert_eq!(response, ok_response("hello".into(), Id::Num(1)));
+}
+
+#[tokio::test]
+async fn connections_over_the_limit_are_answered_with_an_error() {
+	let path = endpoint_path();
+	let server = IpcServerBuilder::default().max_connections(1).build(&path).await.unwrap();
+	let _handle = server.start(module()).unwrap();
+
+	let mut client = IpcTestClient::connect(&path).await;
+	let response = client.send_request(&call("say_hello", Vec::<()>::new(), Id::Num(1))).await;
+	assert_eq!(response, ok_response("hello".into(), Id::Num(1)));
+
+	let mut rejected = IpcTestClient::connect(&path).await;
+	let response: JsonValue = serde_json::from_str(&rejected.receive().await).unwrap();
+	assert_eq!(response["error"]["code"], SERVER_IS_BUSY_CODE);
+	assert_eq!(response["id"], JsonValue::Null);
+}
+
+#[tokio::test]
+async fn filtered_methods_are_not_found() {
+	let path = endpoint_path();
+	let server = IpcServerBuilder::default()
+		.method_filter(|name, _| name != "say_hello")
+		.cost_budget(1, 0)
+		.build(&path)
+		.await
+		.unwrap();
+	let _handle = server.start(module()).unwrap();
+	let mut client = IpcTestClient::connect(&path).await;
+
+	let response = client.send_request(&call("say_hello", Vec::<()>::new(), Id::Num(1))).await;
+	assert_eq!(response, method_not_found(Id::Num(1)));
+	let response = client.send_request(&call("add_async", vec![1, 2], Id::Num(2))).await;
+	assert_eq!(response, ok_response(3.into(), Id::Num(2)));
+	// The budget of the connection is spent.
+	let response: JsonValue =
+		serde_json::from_str(&client.send_request(&call("add_async", vec![1, 2], Id::Num(3))).await).unwrap();
+	assert_eq!(response["error"]["code"], COST_BUDGET_EXCEEDED_CODE);
+}
+
+#[tokio::test]
+async fn tcp_method_calls_and_subscriptions_work() {
+	let (addr, handle) = tcp_server().await;
+	let mut client = IpcTestClient::connect_tcp(addr).await;
+
+	let response = client.send_request(&call("add_async", vec![1, 2], Id::Num(1))).await;
+	assert_eq!(response, ok_response(3.into(), Id::Num(1)));
+	assert_eq!(client.send_request("garbage").await, parse_error(Id::Null));
+
+	let response = client.send_request(&call("subscribe_hello", Vec::<()>::new(), Id::Num(2))).await;
+	let response: JsonValue = serde_json::from_str(&response).unwrap();
+	let notification: JsonValue = serde_json::from_str(&client.receive().await).unwrap();
+	assert_eq!(notification["params"]["subscription"], response["result"]);
+
+	handle.stop().unwrap().with_default_timeout().await.unwrap();
+	assert!(TcpStream::connect(addr).await.is_err());
+}
+
+#[tokio::test]
+async fn io_with_content_length_framing_works() {
+	let (client, server) = tokio::io::duplex(1024);
+	let (reader, writer) = tokio::io::split(server);
+	let server = IpcServerBuilder::default().framing(Framing::ContentLength).build_with_io(reader, writer);
+	let handle = server.start(module()).unwrap();
+	let mut client = BufReader::new(client);
+
+	let request = call("add_async", vec![1, 2], Id::Num(1));
+	let message = format!("Content-Length: {}\r\n\r\n{}", request.len(), request);
+	client.get_mut().write_all(message.as_bytes()).await.unwrap();
+	let mut header = String::new();
+	client.read_line(&mut header).with_default_timeout().await.unwrap().unwrap();
+	let length: usize = header.trim_end().strip_prefix("Content-Length: ").unwrap().parse().unwrap();
+	let mut content = vec![0; length + 2];
+	client.read_exact(&mut content).await.unwrap();
+	assert_eq!(&content[2..], ok_response(3.into(), Id::Num(1)).as_bytes());
+
+	// The server stops once its input is closed.
+	drop(client);
+	handle.with_default_timeout().await.unwrap();
+}
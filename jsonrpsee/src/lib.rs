@@ -39,6 +39,12 @@
 //! - **`http-server`** - JSON-RPC server functionality over HTTP protocol.
 //! - **`ws-client`** - JSON-RPC client functionality over WebSocket protocol.
 //! - **`ws-server`** - JSON-RPC server functionality over WebSocket protocol.
+//! - **`ipc-client`** - JSON-RPC client functionality over IPC (Unix domain sockets, named pipes on Windows), raw TCP
+//!   or the pipes of a child process.
+//! - **`wasm-client`** - JSON-RPC client functionality over the WebSocket API of the browser, for
+//!   `wasm32-unknown-unknown`.
+//! - **`ipc-server`** - JSON-RPC server functionality over IPC (Unix domain sockets, named pipes on Windows), raw
+//!   TCP or the standard input and output.
 //! - **`macros`** - JSON-RPC API generation convenience by derive macros.
 //! - **`client`** - Enables `http-client` and `ws-client` features.
 //! - **`server`** - Enables `http-server` and `ws-server` features.
@@ -46,6 +52,10 @@
 //! - **`async-client`** - Enables the async client without any transport.
 //! - **`client-ws-transport`** - Enables `ws` transport with TLS.
 //! - **`client-ws-transport-no-tls`** - Enables `ws` transport without TLS.
+//! - **`client-ipc-transport`** - Enables `ipc` transport.
+//! - **`client-web-transport`** - Enables `web` transport, backed by the WebSocket API of the browser.
+//! - **`deflate`** - Enables the `permessage-deflate` compression of the enabled WebSocket client and server.
+//! - **`server-tls`** - Enables the TLS termination of the enabled HTTP and WebSocket servers.
 
 /// JSON-RPC HTTP client.
 #[cfg(feature = "jsonrpsee-http-client")]
@@ -55,11 +65,25 @@ pub use jsonrpsee_http_client as http_client;
 #[cfg(feature = "jsonrpsee-ws-client")]
 pub use jsonrpsee_ws_client as ws_client;
 
+/// JSON-RPC IPC client.
+#[cfg(feature = "jsonrpsee-ipc-client")]
+pub use jsonrpsee_ipc_client as ipc_client;
+
+/// JSON-RPC WebSocket client for the browser.
+#[cfg(feature = "jsonrpsee-wasm-client")]
+pub use jsonrpsee_wasm_client as wasm_client;
+
 #[cfg(feature = "jsonrpsee-client-transport")]
 pub use jsonrpsee_client_transport as client_transport;
 
 /// JSON-RPC client convenience macro to build params.
-#[cfg(any(feature = "async-client", feature = "http-client", feature = "ws-client"))]
+#[cfg(any(
+	feature = "async-client",
+	feature = "http-client",
+	feature = "ws-client",
+	feature = "ipc-client",
+	feature = "wasm-client"
+))]
 pub use jsonrpsee_core::rpc_params;
 
 /// JSON-RPC HTTP server.
@@ -70,25 +94,36 @@ pub use jsonrpsee_http_server as http_server;
 #[cfg(feature = "jsonrpsee-ws-server")]
 pub use jsonrpsee_ws_server as ws_server;
 
+/// JSON-RPC IPC server.
+#[cfg(feature = "jsonrpsee-ipc-server")]
+pub use jsonrpsee_ipc_server as ipc_server;
+
 /// Procedural macros for JSON-RPC implementations.
 #[cfg(feature = "jsonrpsee-proc-macros")]
 pub use jsonrpsee_proc_macros as proc_macros;
 
+/// Prometheus metrics middleware for the servers.
+#[cfg(feature = "jsonrpsee-metrics")]
+pub use jsonrpsee_metrics as metrics;
+
 /// Common types used to implement JSON-RPC server and client.
 #[cfg(feature = "jsonrpsee-types")]
 pub use jsonrpsee_types as types;
 
 /// Set of RPC methods that can be mounted to the server.
-#[cfg(any(feature = "http-server", feature = "ws-server"))]
-pub use jsonrpsee_core::server::rpc_module::{RpcModule, SubscriptionSink};
+#[cfg(any(feature = "http-server", feature = "ws-server", feature = "ipc-server"))]
+pub use jsonrpsee_core::server::rpc_module::{PendingSubscription, RpcModule, SubscriptionSink};
 
 #[cfg(any(
 	feature = "http-server",
 	feature = "ws-server",
+	feature = "ipc-server",
 	feature = "client",
 	feature = "async-client",
 	feature = "http-client",
-	feature = "ws-client"
+	feature = "ws-client",
+	feature = "ipc-client",
+	feature = "wasm-client"
 ))]
 pub use jsonrpsee_core as core;
 
@@ -97,3 +132,6 @@ pub use http_server::tracing;
 
 #[cfg(all(feature = "ws-server", not(feature = "http-server")))]
 pub use ws_server::tracing;
+
+#[cfg(all(feature = "ipc-server", not(any(feature = "http-server", feature = "ws-server"))))]
+pub use ipc_server::tracing;
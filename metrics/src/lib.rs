@@ -0,0 +1,173 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+#![warn(missing_debug_implementations, missing_docs, unreachable_pub)]
+
+//! # jsonrpsee-metrics
+//!
+//! `jsonrpsee-metrics` is a [`Middleware`] for the jsonrpsee servers recording [Prometheus](https://prometheus.io)
+//! metrics of the calls, connections and subscriptions.
+//!
+//! ```
+//! use jsonrpsee_metrics::RpcMetrics;
+//!
+//! let metrics = RpcMetrics::new().unwrap();
+//! // Pass the metrics to `WsServerBuilder::set_middleware` or `HttpServerBuilder::set_middleware`, and serve the
+//! // registry to Prometheus.
+//! let registry = metrics.registry().clone();
+//! ```
+
+use std::time::Instant;
+
+use jsonrpsee_core::middleware::Middleware;
+use prometheus::{HistogramOpts, HistogramVec, IntCounterVec, IntGauge, Opts, Registry};
+
+pub use prometheus;
+
+/// Prometheus metrics of a jsonrpsee server, recorded as a [`Middleware`].
+///
+/// Records:
+///
+/// - `jsonrpsee_calls_total`: number of calls, per method;
+/// - `jsonrpsee_call_errors_total`: number of calls that failed, per method;
+/// - `jsonrpsee_call_duration_seconds`: histogram of the time taken by the calls, per method;
+/// - `jsonrpsee_active_connections`: number of open connections (WebSocket only);
+/// - `jsonrpsee_active_subscriptions`: number of open subscriptions (WebSocket only).
+///
+/// Calls to methods that aren't registered are not recorded, so that clients can't create an unbounded number of
+/// method labels.
+#[derive(Debug, Clone)]
+pub struct RpcMetrics {
+	registry: Registry,
+	calls: IntCounterVec,
+	errors: IntCounterVec,
+	durations: HistogramVec,
+	connections: IntGauge,
+	subscriptions: IntGauge,
+}
+
+impl RpcMetrics {
+	/// Create the metrics, registered in a new registry returned by [`RpcMetrics::registry`].
+	pub fn new() -> Result<Self, prometheus::Error> {
+		Self::with_registry(Registry::new())
+	}
+
+	/// Create the metrics, registered in `registry`, for instance the registry of the other metrics of the
+	/// application. Fails if the registry has metrics with the same names already.
+	pub fn with_registry(registry: Registry) -> Result<Self, prometheus::Error> {
+		let calls = IntCounterVec::new(Opts::new("jsonrpsee_calls_total", "Number of calls"), &["method"])?;
+		let errors =
+			IntCounterVec::new(Opts::new("jsonrpsee_call_errors_total", "Number of calls that failed"), &["method"])?;
+		let durations = HistogramVec::new(
+			HistogramOpts::new("jsonrpsee_call_duration_seconds", "Time taken by the calls in seconds"),
+			&["method"],
+		)?;
+		let connections = IntGauge::new("jsonrpsee_active_connections", "Number of open connections")?;
+		let subscriptions = IntGauge::new("jsonrpsee_active_subscriptions", "Number of open subscriptions")?;
+
+		registry.register(Box::new(calls.clone()))?;
+		registry.register(Box::new(errors.clone()))?;
+		registry.register(Box::new(durations.clone()))?;
+		registry.register(Box::new(connections.clone()))?;
+		registry.register(Box::new(subscriptions.clone()))?;
+
+		Ok(Self { registry, calls, errors, durations, connections, subscriptions })
+	}
+
+	/// Returns the registry of the metrics, to gather them for Prometheus.
+	pub fn registry(&self) -> &Registry {
+		&self.registry
+	}
+}
+
+impl Middleware for RpcMetrics {
+	type Instant = Instant;
+
+	fn on_connect(&self) {
+		self.connections.inc();
+	}
+
+	fn on_request(&self) -> Instant {
+		Instant::now()
+	}
+
+	fn on_result(&self, name: &str, success: bool, started_at: Instant) {
+		self.calls.with_label_values(&[name]).inc();
+		if !success {
+			self.errors.with_label_values(&[name]).inc();
+		}
+		self.durations.with_label_values(&[name]).observe(started_at.elapsed().as_secs_f64());
+	}
+
+	fn on_disconnect(&self) {
+		self.connections.dec();
+	}
+
+	fn on_subscribe(&self) {
+		self.subscriptions.inc();
+	}
+
+	fn on_unsubscribe(&self) {
+		self.subscriptions.dec();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::RpcMetrics;
+	use jsonrpsee_core::middleware::Middleware;
+	use prometheus::Encoder;
+
+	#[test]
+	fn calls_are_recorded_per_method() {
+		let metrics = RpcMetrics::new().unwrap();
+		let started_at = metrics.on_request();
+		metrics.on_result("say_hello", true, started_at);
+		metrics.on_result("say_hello", false, started_at);
+		metrics.on_result("add", true, started_at);
+		metrics.on_subscribe();
+		metrics.on_subscribe();
+		metrics.on_unsubscribe();
+
+		assert_eq!(metrics.calls.with_label_values(&["say_hello"]).get(), 2);
+		assert_eq!(metrics.errors.with_label_values(&["say_hello"]).get(), 1);
+		assert_eq!(metrics.errors.with_label_values(&["add"]).get(), 0);
+		assert_eq!(metrics.durations.with_label_values(&["add"]).get_sample_count(), 1);
+		assert_eq!(metrics.subscriptions.get(), 1);
+
+		let mut text = Vec::new();
+		prometheus::TextEncoder::new().encode(&metrics.registry().gather(), &mut text).unwrap();
+		let text = String::from_utf8(text).unwrap();
+		assert!(text.contains(r#"jsonrpsee_calls_total{method="say_hello"} 2"#), "{}", text);
+		assert!(text.contains("jsonrpsee_active_subscriptions 1"), "{}", text);
+	}
+
+	#[test]
+	fn metrics_can_share_a_registry_once() {
+		let metrics = RpcMetrics::new().unwrap();
+		assert!(RpcMetrics::with_registry(metrics.registry().clone()).is_err());
+	}
+}
@@ -181,9 +181,45 @@ pub(crate) fn extract_doc_comments(attrs: &[syn::Attribute]) -> TokenStream2 {
 	quote! ( #(#docs)* )
 }
 
+/// Returns the text of the doc comments, one line per attribute, or `None` if there are none.
+pub(crate) fn doc_comment_text(attrs: &[syn::Attribute]) -> Option<String> {
+	let lines: Vec<_> = attrs
+		.iter()
+		.filter(|attr| attr.path.is_ident("doc"))
+		.filter_map(|attr| match attr.parse_meta() {
+			Ok(syn::Meta::NameValue(syn::MetaNameValue { lit: syn::Lit::Str(doc), .. })) => Some(doc.value()),
+			_ => None,
+		})
+		.collect();
+
+	let text = lines.iter().map(|line| line.strip_prefix(' ').unwrap_or(line)).collect::<Vec<_>>().join("\n");
+	let text = text.trim();
+	(!text.is_empty()).then(|| text.to_owned())
+}
+
+/// Returns the type of the successful result of a method returning a `Result` or `RpcResult`, or `None` if it
+/// returns another type.
+pub(crate) fn ok_type(ty: &syn::Type) -> Option<&syn::Type> {
+	let seg = match ty {
+		syn::Type::Path(path) => path.path.segments.last()?,
+		_ => return None,
+	};
+	if seg.ident != "Result" && seg.ident != "RpcResult" {
+		return None;
+	}
+
+	match &seg.arguments {
+		syn::PathArguments::AngleBracketed(args) => args.args.iter().find_map(|arg| match arg {
+			syn::GenericArgument::Type(ty) => Some(ty),
+			_ => None,
+		}),
+		_ => None,
+	}
+}
+
 #[cfg(test)]
 mod tests {
-	use super::is_option;
+	use super::{doc_comment_text, is_option, ok_type};
 	use syn::parse_quote;
 
 	#[test]
@@ -194,4 +230,19 @@ mod tests {
 		assert!(is_option(&parse_quote!(std::option::Option<R>)));
 		assert!(!is_option(&parse_quote!(foo::bar::Option::Booyah)));
 	}
+
+	#[test]
+	fn ok_type_works() {
+		let u64_ty: syn::Type = parse_quote!(u64);
+		assert_eq!(ok_type(&parse_quote!(RpcResult<u64>)), Some(&u64_ty));
+		assert_eq!(ok_type(&parse_quote!(std::result::Result<u64, Error>)), Some(&u64_ty));
+		assert_eq!(ok_type(&parse_quote!(u64)), None);
+	}
+
+	#[test]
+	fn doc_comment_text_works() {
+		let attrs: Vec<syn::Attribute> = vec![parse_quote!(#[doc = " Say hello."]), parse_quote!(#[doc = " Twice."])];
+		assert_eq!(doc_comment_text(&attrs), Some("Say hello.\nTwice.".to_owned()));
+		assert_eq!(doc_comment_text(&[parse_quote!(#[deprecated])]), None);
+	}
 }
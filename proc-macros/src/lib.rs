@@ -33,6 +33,7 @@ use rpc_macro::RpcDescription;
 mod attributes;
 mod helpers;
 mod render_client;
+mod render_openrpc;
 mod render_server;
 mod rpc_macro;
 pub(crate) mod visitor;
@@ -144,6 +145,11 @@ pub(crate) mod visitor;
 ///   implementation's methods conveniently.
 /// - `namespace`: add a prefix to all the methods and subscriptions in this RPC. For example, with namespace `foo` and
 ///   method `spam`, the resulting method name will be `foo_spam`.
+/// - `openrpc`: add an `open_rpc_document` function to the `<Trait>Server` trait, returning the
+///   [OpenRPC](https://spec.open-rpc.org) document of the API as JSON. The JSON schemas of the params, results and
+///   subscription items are derived from their Rust types, which must implement `schemars::JsonSchema`. The doc
+///   comments of the methods are used as their descriptions and the version of the API is the version of the crate.
+///   Requires the `server` flag and the `openrpc` feature of `jsonrpsee`.
 ///
 /// **Trait requirements:**
 ///
@@ -166,7 +172,15 @@ pub(crate) mod visitor;
 ///              Aliases are processed ignoring the namespace, so add the complete name, including the
 ///              namespace.
 /// - `blocking`: when set method execution will always spawn on a dedicated thread. Only usable with non-`async` methods.
+/// - `deprecated`: deprecation notice of the method, for instance naming the method to use instead. The server logs
+///   a warning with the notice on each call and attaches it to the responses if deprecation warnings are enabled,
+///   see `RpcModule::deprecate_method`.
 /// - `param_kind`: kind of structure to use for parameter passing. Can be "array" or "map", defaults to "array".
+///   The generated client sends the params as a JSON array or as a JSON object keyed by the names
+///   of the parameters, the generated server accepts both.
+/// - `resources`: resources used by each call of the method, as a list of `"label" = units` pairs, e.g.
+///   `resources("CPU" = 2, "MEM" = 10)`. The labels must be registered on the server with `register_resource`,
+///   the units are claimed through the `MethodResourcesBuilder` of the method when calling `into_rpc()`.
 ///
 /// **Method requirements:**
 ///
@@ -202,6 +216,16 @@ pub(crate) mod visitor;
 ///
 /// - have input parameters or not.
 ///
+/// ### `param` attribute
+///
+/// `param` attribute can be put on the input parameters of `method` and `subscription` methods.
+///
+/// **Arguments:**
+///
+/// - `default`: expression used as the value of the parameter when the caller omits it or passes `null`.
+///
+/// Parameters of type `Option<T>` may be omitted by the caller as well, in which case they are `None`.
+///
 /// ## Full workflow example
 ///
 /// ```rust
@@ -156,6 +156,7 @@ impl RpcDescription {
 			});
 			match param_kind {
 				ParamKind::Map => {
+					let params_ser = self.jrps_client_item(quote! { types::ParamsSer });
 					// Extract parameter names.
 					let param_names = extract_param_names(&signature.sig);
 					// Combine parameter names and values into tuples.
@@ -165,7 +166,7 @@ impl RpcDescription {
 						quote! { (#param, #value) }
 					});
 					quote! {
-						Some(types::ParamsSer::Map(
+						Some(#params_ser::Map(
 							std::collections::BTreeMap::<&str, #serde_json::Value>::from(
 								[#(#params),*]
 								)
@@ -0,0 +1,182 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use super::RpcDescription;
+use crate::helpers::{is_option, ok_type};
+use proc_macro2::{TokenStream as TokenStream2, TokenTree};
+use quote::quote;
+
+/// Version of the OpenRPC specification followed by the generated documents.
+const OPENRPC_VERSION: &str = "1.2.6";
+
+impl RpcDescription {
+	/// Renders the `open_rpc_document` function of the server trait.
+	pub(super) fn render_openrpc(&self) -> TokenStream2 {
+		let serde_json = self.jrps_server_item(quote! { core::__reexports::serde_json });
+		let schemars = self.jrps_server_item(quote! { core::__reexports::schemars });
+		let title = self.trait_def.ident.to_string();
+
+		// Types a schema is generated for, the ones using type params of the trait must be bound by `JsonSchema`.
+		let mut schema_tys = Vec::new();
+		let mut methods = Vec::new();
+
+		for method in &self.methods {
+			let name = self.rpc_identifier(&method.name);
+			let params = render_params(&serde_json, &mut schema_tys, &method.params, &method.defaults);
+			let result = match method.returns.as_ref().map(|ty| ok_type(ty).unwrap_or(ty)) {
+				Some(ty) => render_schema(&mut schema_tys, ty),
+				None => quote! { #serde_json::json!({}) },
+			};
+			let deprecated = !method.deprecated.is_empty() || method.deprecation.is_some();
+			let description = render_optional(&serde_json, "description", method.description.as_ref());
+			let aliases = render_aliases(&serde_json, &method.aliases);
+
+			methods.push(quote! {{
+				let mut method = #serde_json::json!({
+					"name": #name,
+					"paramStructure": "either",
+					"params": #params,
+					"result": { "name": "result", "schema": #result },
+				});
+				if #deprecated {
+					method["deprecated"] = true.into();
+				}
+				#description
+				#aliases
+				method
+			}});
+		}
+
+		for sub in &self.subscriptions {
+			let name = self.rpc_identifier(&sub.name);
+			let notification = match &sub.notif_name_override {
+				Some(notif) => self.rpc_identifier(notif),
+				None => name.clone(),
+			};
+			let unsubscribe = self.rpc_identifier(&sub.unsubscribe);
+			let params = render_params(&serde_json, &mut schema_tys, &sub.params, &sub.defaults);
+			let item = render_schema(&mut schema_tys, &sub.item);
+			let description = render_optional(&serde_json, "description", sub.description.as_ref());
+			let aliases = render_aliases(&serde_json, &sub.aliases);
+			let unsubscribe_aliases = render_aliases(&serde_json, &sub.unsubscribe_aliases);
+
+			methods.push(quote! {{
+				let mut method = #serde_json::json!({
+					"name": #name,
+					"paramStructure": "either",
+					"params": #params,
+					"result": { "name": "subscription", "schema": {} },
+					"x-subscription": { "notification": #notification, "unsubscribe": #unsubscribe, "item": #item },
+				});
+				#description
+				#aliases
+				method
+			}});
+			methods.push(quote! {{
+				let mut method = #serde_json::json!({
+					"name": #unsubscribe,
+					"params": [{ "name": "subscription", "required": true, "schema": {} }],
+					"result": { "name": "result", "schema": { "type": "boolean" } },
+				});
+				#unsubscribe_aliases
+				method
+			}});
+		}
+
+		let type_params: Vec<_> = self.trait_def.generics.type_params().map(|param| param.ident.clone()).collect();
+		let where_clause = schema_tys
+			.iter()
+			.filter(|ty| mentions_any(quote!(#ty), &type_params))
+			.map(|ty| quote! { #ty: #schemars::JsonSchema });
+
+		let doc_comment = format!(
+			"Returns the [OpenRPC](https://spec.open-rpc.org) document of the `{}` RPC API, with the JSON schemas of \
+			the params and results of the methods and of the items of the subscriptions.",
+			title
+		);
+
+		quote! {
+			#[doc = #doc_comment]
+			fn open_rpc_document() -> #serde_json::Value where #(#where_clause,)* {
+				let mut settings = #schemars::gen::SchemaSettings::draft07();
+				settings.definitions_path = "#/components/schemas/".into();
+				let mut gen = settings.into_generator();
+				let methods: Vec<#serde_json::Value> = vec![#(#methods),*];
+
+				#serde_json::json!({
+					"openrpc": #OPENRPC_VERSION,
+					"info": { "title": #title, "version": env!("CARGO_PKG_VERSION") },
+					"methods": methods,
+					"components": { "schemas": gen.take_definitions() },
+				})
+			}
+		}
+	}
+}
+
+fn render_schema(schema_tys: &mut Vec<syn::Type>, ty: &syn::Type) -> TokenStream2 {
+	schema_tys.push(ty.clone());
+	quote! { gen.subschema_for::<#ty>() }
+}
+
+fn render_params(
+	serde_json: &TokenStream2,
+	schema_tys: &mut Vec<syn::Type>,
+	params: &[(syn::PatIdent, syn::Type)],
+	defaults: &[Option<syn::Expr>],
+) -> TokenStream2 {
+	let params = params.iter().zip(defaults).map(|((name, ty), default)| {
+		let name = name.ident.to_string();
+		let required = !is_option(ty) && default.is_none();
+		let schema = render_schema(schema_tys, ty);
+		quote! { #serde_json::json!({ "name": #name, "required": #required, "schema": #schema }) }
+	});
+	quote! { <Vec<#serde_json::Value>>::from([#(#params),*]) }
+}
+
+/// Checks whether the tokens contain any of the identifiers.
+fn mentions_any(tokens: TokenStream2, idents: &[syn::Ident]) -> bool {
+	tokens.into_iter().any(|token| match token {
+		TokenTree::Ident(ident) => idents.contains(&ident),
+		TokenTree::Group(group) => mentions_any(group.stream(), idents),
+		_ => false,
+	})
+}
+
+fn render_optional(serde_json: &TokenStream2, field: &str, value: Option<&String>) -> TokenStream2 {
+	match value {
+		Some(value) => quote! { method[#field] = #serde_json::Value::from(#value); },
+		None => TokenStream2::new(),
+	}
+}
+
+fn render_aliases(serde_json: &TokenStream2, aliases: &[String]) -> TokenStream2 {
+	if aliases.is_empty() {
+		return TokenStream2::new();
+	}
+
+	quote! { method["x-aliases"] = #serde_json::json!([#(#aliases),*]); }
+}
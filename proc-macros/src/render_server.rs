@@ -40,6 +40,7 @@ impl RpcDescription {
 
 		let method_impls = self.render_methods()?;
 		let into_rpc_impl = self.render_into_rpc()?;
+		let openrpc_impl = if self.needs_openrpc { self.render_openrpc() } else { TokenStream2::new() };
 		let async_trait = self.jrps_server_item(quote! { core::__reexports::async_trait });
 
 		// Doc-comment to be associated with the server.
@@ -51,6 +52,7 @@ impl RpcDescription {
 			pub trait #trait_name #impl_generics: Sized + Send + Sync + 'static #where_clause {
 				#method_impls
 				#into_rpc_impl
+				#openrpc_impl
 			}
 		};
 
@@ -124,7 +126,7 @@ impl RpcDescription {
 				// provided `Params` object.
 				// `params_seq` is the comma-delimited sequence of parameters we're passing to the rust function
 				// called..
-				let (parsing, params_seq) = self.render_params_decoding(&method.params);
+				let (parsing, params_seq) = self.render_params_decoding(&method.params, &method.defaults);
 
 				check_name(&rpc_method_name, rust_method_name.span());
 
@@ -182,7 +184,7 @@ impl RpcDescription {
 				// `parsing` is the code associated with parsing structure from the
 				// provided `Params` object.
 				// `params_seq` is the comma-delimited sequence of parameters.
-				let (parsing, params_seq) = self.render_params_decoding(&sub.params);
+				let (parsing, params_seq) = self.render_params_decoding(&sub.params, &sub.defaults);
 
 				check_name(&rpc_sub_name, rust_method_name.span());
 				check_name(&rpc_unsub_name, rust_method_name.span());
@@ -204,6 +206,19 @@ impl RpcDescription {
 			})
 			.collect::<Vec<_>>();
 
+		let method_deprecations = self
+			.methods
+			.iter()
+			.filter_map(|method| {
+				let rpc_name = self.rpc_identifier(&method.name);
+				let notice = method.deprecation.as_ref()?;
+
+				Some(handle_register_result(quote! {
+					rpc.deprecate_method(#rpc_name, #notice)
+				}))
+			})
+			.collect::<Vec<_>>();
+
 		let method_aliases = self
 			.methods
 			.iter()
@@ -278,6 +293,7 @@ impl RpcDescription {
 				#(#errors)*
 				#(#methods)*
 				#(#subscriptions)*
+				#(#method_deprecations)*
 				#(#method_aliases)*
 				#(#subscription_aliases)*
 
@@ -286,7 +302,11 @@ impl RpcDescription {
 		})
 	}
 
-	fn render_params_decoding(&self, params: &[(syn::PatIdent, syn::Type)]) -> (TokenStream2, TokenStream2) {
+	fn render_params_decoding(
+		&self,
+		params: &[(syn::PatIdent, syn::Type)],
+		defaults: &[Option<syn::Expr>],
+	) -> (TokenStream2, TokenStream2) {
 		if params.is_empty() {
 			return (TokenStream2::default(), TokenStream2::default());
 		}
@@ -297,8 +317,18 @@ impl RpcDescription {
 
 		// Code to decode sequence of parameters from a JSON array.
 		let decode_array = {
-			let decode_fields = params.iter().map(|(name, ty)| {
-				if is_option(ty) {
+			let decode_fields = params.iter().zip(defaults).map(|((name, ty), default)| {
+				if let Some(default) = default {
+					quote! {
+						let #name: #ty = match seq.optional_next::<#ty>() {
+							Ok(v) => v.unwrap_or_else(|| #default),
+							Err(e) => {
+								#tracing::error!(concat!("Error parsing optional \"", stringify!(#name), "\" as \"", stringify!(#ty), "\": {:?}"), e);
+								return Err(e.into())
+							}
+						};
+					}
+				} else if is_option(ty) {
 					quote! {
 						let #name: #ty = match seq.optional_next() {
 							Ok(v) => v,
@@ -337,8 +367,14 @@ impl RpcDescription {
 			let fields = params.iter().zip(generics.clone()).map(|((name, _), ty)| {
 				quote! { #name: #ty, }
 			});
-			let destruct = params.iter().map(|(name, _)| quote! { parsed.#name });
-			let types = params.iter().map(|(_, ty)| ty);
+			let destruct = params.iter().zip(defaults).map(|((name, _), default)| match default {
+				Some(default) => quote! { parsed.#name.unwrap_or_else(|| #default) },
+				None => quote! { parsed.#name },
+			});
+			let types = params.iter().zip(defaults).map(|((_, ty), default)| match default {
+				Some(_) => quote! { Option<#ty> },
+				None => quote! { #ty },
+			});
 
 			quote! {
 				#[derive(#serde::Deserialize)]
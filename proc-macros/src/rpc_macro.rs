@@ -31,7 +31,7 @@ use std::borrow::Cow;
 use crate::attributes::{
 	optional, parse_param_kind, Aliases, Argument, AttributeMeta, MissingArgument, NameMapping, ParamKind, Resource,
 };
-use crate::helpers::extract_doc_comments;
+use crate::helpers::{doc_comment_text, extract_doc_comments};
 use proc_macro2::TokenStream as TokenStream2;
 use quote::quote;
 use syn::spanned::Spanned;
@@ -42,8 +42,14 @@ pub struct RpcMethod {
 	pub name: String,
 	pub blocking: bool,
 	pub docs: TokenStream2,
+	/// Text of the doc comments, used as description in the OpenRPC document.
+	pub description: Option<String>,
 	pub deprecated: TokenStream2,
+	/// Deprecation notice logged and attached to the responses by the server, if any.
+	pub deprecation: Option<String>,
 	pub params: Vec<(syn::PatIdent, syn::Type)>,
+	/// Default values of the params, in the same order as `params`.
+	pub defaults: Vec<Option<syn::Expr>>,
 	pub param_kind: ParamKind,
 	pub returns: Option<syn::Type>,
 	pub signature: syn::TraitItemMethod,
@@ -53,17 +59,26 @@ pub struct RpcMethod {
 
 impl RpcMethod {
 	pub fn from_item(attr: Attribute, mut method: syn::TraitItemMethod) -> syn::Result<Self> {
-		let [aliases, blocking, name, param_kind, resources] =
-			AttributeMeta::parse(attr)?.retain(["aliases", "blocking", "name", "param_kind", "resources"])?;
+		let [aliases, blocking, deprecated, name, param_kind, resources] = AttributeMeta::parse(attr)?.retain([
+			"aliases",
+			"blocking",
+			"deprecated",
+			"name",
+			"param_kind",
+			"resources",
+		])?;
 
 		let aliases = parse_aliases(aliases)?;
 		let blocking = optional(blocking, Argument::flag)?.is_some();
+		let deprecation = optional(deprecated, Argument::string)?;
 		let name = name?.string()?;
 		let param_kind = parse_param_kind(param_kind)?;
 		let resources = optional(resources, Argument::group)?.unwrap_or_default();
 
+		let defaults = parse_param_defaults(&mut method.sig)?;
 		let sig = method.sig.clone();
 		let docs = extract_doc_comments(&method.attrs);
+		let description = doc_comment_text(&method.attrs);
 		let deprecated = match find_attr(&method.attrs, "deprecated") {
 			Some(attr) => quote!(#attr),
 			None => quote!(),
@@ -105,12 +120,15 @@ impl RpcMethod {
 			blocking,
 			name,
 			params,
+			defaults,
 			param_kind,
 			returns,
 			signature: method,
 			docs,
+			description,
 			resources,
 			deprecated,
+			deprecation,
 		})
 	}
 }
@@ -126,8 +144,12 @@ pub struct RpcSubscription {
 	/// If no override is provided, the subscription method name is used.
 	pub notif_name_override: Option<String>,
 	pub docs: TokenStream2,
+	/// Text of the doc comments, used as description in the OpenRPC document.
+	pub description: Option<String>,
 	pub unsubscribe: String,
 	pub params: Vec<(syn::PatIdent, syn::Type)>,
+	/// Default values of the params, in the same order as `params`.
+	pub defaults: Vec<Option<syn::Expr>>,
 	pub param_kind: ParamKind,
 	pub item: syn::Type,
 	pub signature: syn::TraitItemMethod,
@@ -148,15 +170,33 @@ impl RpcSubscription {
 		let param_kind = parse_param_kind(param_kind)?;
 		let unsubscribe_aliases = parse_aliases(unsubscribe_aliases)?;
 
+		let defaults = parse_param_defaults(&mut sub.sig)?;
 		let sig = sub.sig.clone();
 		let docs = extract_doc_comments(&sub.attrs);
+		let description = doc_comment_text(&sub.attrs);
 		let unsubscribe = match parse_subscribe(unsubscribe)? {
 			Some(unsub) => unsub,
-			None => build_unsubscribe_method(&name).unwrap_or_else(||
-				panic!("Could not generate the unsubscribe method with name '{}'. You need to provide the name manually using the `unsubscribe` attribute in your RPC API definition", name),
-			),
+			None => match build_unsubscribe_method(&name) {
+				Some(unsub) => unsub,
+				None => {
+					return Err(syn::Error::new(
+						sig.ident.span(),
+						format!(
+							"Could not generate the unsubscribe method with name '{}'. You need to provide the name manually using the `unsubscribe` attribute in your RPC API definition",
+							name
+						),
+					))
+				}
+			},
 		};
 
+		if unsubscribe == name {
+			return Err(syn::Error::new(
+				sig.ident.span(),
+				format!("The unsubscribe method must not have the same name as the subscription: '{}'", name),
+			));
+		}
+
 		let params: Vec<_> = sig
 			.inputs
 			.into_iter()
@@ -178,11 +218,13 @@ impl RpcSubscription {
 			unsubscribe,
 			unsubscribe_aliases,
 			params,
+			defaults,
 			param_kind,
 			item,
 			signature: sub,
 			aliases,
 			docs,
+			description,
 		})
 	}
 }
@@ -201,6 +243,8 @@ pub struct RpcDescription {
 	/// Assuming that trait to which attribute is applied is named `Foo`, the generated
 	/// client trait will have `FooClient` name.
 	pub(crate) needs_client: bool,
+	/// Switch denoting that the server trait must provide the OpenRPC document of the API.
+	pub(crate) needs_openrpc: bool,
 	/// Optional prefix for RPC namespace.
 	pub(crate) namespace: Option<String>,
 	/// Trait definition in which all the attributes were stripped.
@@ -213,15 +257,23 @@ pub struct RpcDescription {
 
 impl RpcDescription {
 	pub fn from_item(attr: Attribute, mut item: syn::ItemTrait) -> syn::Result<Self> {
-		let [client, server, namespace] = AttributeMeta::parse(attr)?.retain(["client", "server", "namespace"])?;
+		let [client, namespace, openrpc, server] =
+			AttributeMeta::parse(attr)?.retain(["client", "namespace", "openrpc", "server"])?;
 
 		let needs_server = optional(server, Argument::flag)?.is_some();
 		let needs_client = optional(client, Argument::flag)?.is_some();
+		let needs_openrpc = optional(openrpc, Argument::flag)?.is_some();
 		let namespace = optional(namespace, Argument::string)?;
 
 		if !needs_server && !needs_client {
 			return Err(syn::Error::new_spanned(&item.ident, "Either 'server' or 'client' attribute must be applied"));
 		}
+		if needs_openrpc && !needs_server {
+			return Err(syn::Error::new_spanned(
+				&item.ident,
+				"The 'openrpc' attribute requires the 'server' attribute",
+			));
+		}
 
 		let jsonrpsee_client_path = crate::helpers::find_jsonrpsee_client_crate().ok();
 		let jsonrpsee_server_path = crate::helpers::find_jsonrpsee_server_crate().ok();
@@ -290,6 +342,7 @@ impl RpcDescription {
 			jsonrpsee_server_path,
 			needs_server,
 			needs_client,
+			needs_openrpc,
 			namespace,
 			trait_def: item,
 			methods,
@@ -340,6 +393,29 @@ fn parse_aliases(arg: Result<Argument, MissingArgument>) -> syn::Result<Vec<Stri
 	Ok(aliases.map(|a| a.list.into_iter().map(|lit| lit.value()).collect()).unwrap_or_default())
 }
 
+/// Parses the `#[param(default = <expr>)]` attributes of the arguments of the method, and removes them from the
+/// signature. Returns the default value of each argument, `None` if it has none.
+fn parse_param_defaults(sig: &mut syn::Signature) -> syn::Result<Vec<Option<syn::Expr>>> {
+	let mut defaults = Vec::new();
+
+	for arg in sig.inputs.iter_mut() {
+		if let syn::FnArg::Typed(arg) = arg {
+			let default = match find_attr(&arg.attrs, "param") {
+				Some(attr) => {
+					let [default] = AttributeMeta::parse(attr.clone())?.retain(["default"])?;
+					Some(default?.value::<syn::Expr>()?)
+				}
+				None => None,
+			};
+
+			arg.attrs.retain(|attr| !attr.path.is_ident("param"));
+			defaults.push(default);
+		}
+	}
+
+	Ok(defaults)
+}
+
 fn parse_subscribe(arg: Result<Argument, MissingArgument>) -> syn::Result<Option<String>> {
 	let unsub = optional(arg, Argument::string)?;
 
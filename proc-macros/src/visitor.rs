@@ -168,9 +168,6 @@ impl FindSubscriptionParams {
 
 			syn::Type::Infer(_) | syn::Type::Never(_) | syn::Type::Verbatim(_) => {}
 
-			#[cfg(test)]
-			syn::Type::__TestExhaustive(_) => unimplemented!(),
-			#[cfg(not(test))]
 			_ => {}
 		}
 	}
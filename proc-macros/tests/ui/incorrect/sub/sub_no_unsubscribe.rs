@@ -0,0 +1,10 @@
+use jsonrpsee::proc_macros::rpc;
+
+// Unsubscribe method can't be generated from a name not starting with `subscribe`.
+#[rpc(client, server)]
+pub trait NoUnsubscribe {
+	#[subscription(name = "heads", item = u32)]
+	fn heads(&self);
+}
+
+fn main() {}
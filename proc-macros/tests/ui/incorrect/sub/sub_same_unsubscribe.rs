@@ -0,0 +1,10 @@
+use jsonrpsee::proc_macros::rpc;
+
+// Unsubscribe method must not have the same name as the subscription.
+#[rpc(client, server)]
+pub trait SameUnsubscribe {
+	#[subscription(name = "heads", unsubscribe = "heads", item = u32)]
+	fn heads(&self);
+}
+
+fn main() {}
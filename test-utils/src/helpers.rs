@@ -32,6 +32,7 @@ use hyper::service::{make_service_fn, service_fn};
 use hyper::{Request, Response, Server};
 use serde::Serialize;
 use serde_json::Value;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
 pub const PARSE_ERROR: &str = "Parse error";
 pub const INTERNAL_ERROR: &str = "Internal error";
@@ -194,3 +195,105 @@ pub async fn http_server_with_hardcoded_response(response: String) -> SocketAddr
 
 	rx.await.unwrap()
 }
+
+/// Spawn HTTP server that responds with a hardcoded response and headers, and sends the headers of each request it
+/// receives to the returned channel.
+pub async fn http_server_recording_headers(
+	response: String,
+	response_headers: hyper::HeaderMap,
+) -> (SocketAddr, futures_channel::mpsc::UnboundedReceiver<hyper::HeaderMap>) {
+	let (headers_tx, headers_rx) = futures_channel::mpsc::unbounded();
+	let make_service = make_service_fn(move |_| {
+		let (response, response_headers, headers_tx) = (response.clone(), response_headers.clone(), headers_tx.clone());
+		async move {
+			Ok::<_, Infallible>(service_fn(move |req: Request<Body>| {
+				let _ = headers_tx.unbounded_send(req.headers().clone());
+				let mut response = Response::new(hyper::Body::from(response.clone()));
+				*response.headers_mut() = response_headers.clone();
+				async move { Ok::<_, Infallible>(response) }
+			}))
+		}
+	});
+
+	let (tx, rx) = futures_channel::oneshot::channel::<SocketAddr>();
+
+	tokio::spawn(async {
+		let addr = SocketAddr::from(([127, 0, 0, 1], 0));
+		let server = Server::bind(&addr).serve(make_service);
+		tx.send(server.local_addr()).unwrap();
+		server.await.unwrap()
+	});
+
+	(rx.await.unwrap(), headers_rx)
+}
+
+/// Spawn HTTP proxy tunneling the connections with `CONNECT`, and sending the targets of the tunnels to the returned
+/// channel.
+pub async fn http_connect_proxy() -> (SocketAddr, futures_channel::mpsc::UnboundedReceiver<String>) {
+	spawn_proxy(|mut stream| async move {
+		let mut request = Vec::new();
+		while !request.ends_with(b"\r\n\r\n") {
+			request.push(stream.read_u8().await?);
+		}
+		let request = String::from_utf8_lossy(&request).into_owned();
+		let target = request.strip_prefix("CONNECT ").and_then(|r| r.split(' ').next()).unwrap_or_default().to_owned();
+		stream.write_all(b"HTTP/1.1 200 Connection established\r\n\r\n").await?;
+		Ok((stream, target))
+	})
+	.await
+}
+
+/// Spawn SOCKS5 proxy without authentication, and sending the targets of the connections to the returned channel.
+pub async fn socks5_proxy() -> (SocketAddr, futures_channel::mpsc::UnboundedReceiver<String>) {
+	spawn_proxy(|mut stream| async move {
+		let mut buf = [0; 2];
+		stream.read_exact(&mut buf).await?;
+		stream.read_exact(&mut vec![0; buf[1] as usize]).await?;
+		stream.write_all(&[5, 0]).await?;
+
+		let mut request = [0; 4];
+		stream.read_exact(&mut request).await?;
+		let host = match request[3] {
+			1 => {
+				let mut ip = [0; 4];
+				stream.read_exact(&mut ip).await?;
+				std::net::Ipv4Addr::from(ip).to_string()
+			}
+			3 => {
+				let mut host = vec![0; stream.read_u8().await? as usize];
+				stream.read_exact(&mut host).await?;
+				String::from_utf8_lossy(&host).into_owned()
+			}
+			_ => return Err(std::io::Error::other("Unsupported address type")),
+		};
+		let port = stream.read_u16().await?;
+		stream.write_all(&[5, 0, 0, 1, 0, 0, 0, 0, 0, 0]).await?;
+		Ok((stream, format!("{}:{}", host, port)))
+	})
+	.await
+}
+
+async fn spawn_proxy<F, Fut>(handshake: F) -> (SocketAddr, futures_channel::mpsc::UnboundedReceiver<String>)
+where
+	F: Fn(tokio::net::TcpStream) -> Fut + Send + Sync + 'static,
+	Fut: std::future::Future<Output = std::io::Result<(tokio::net::TcpStream, String)>> + Send,
+{
+	let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let addr = listener.local_addr().unwrap();
+	let (tx, rx) = futures_channel::mpsc::unbounded();
+	let handshake = std::sync::Arc::new(handshake);
+
+	tokio::spawn(async move {
+		while let Ok((stream, _)) = listener.accept().await {
+			let (handshake, tx) = (handshake.clone(), tx.clone());
+			tokio::spawn(async move {
+				let (mut stream, target) = handshake(stream).await?;
+				let mut upstream = tokio::net::TcpStream::connect(&target).await?;
+				let _ = tx.unbounded_send(target);
+				tokio::io::copy_bidirectional(&mut stream, &mut upstream).await
+			});
+		}
+	});
+
+	(addr, rx)
+}
@@ -102,8 +102,16 @@ impl From<io::Error> for WebSocketTestError {
 
 impl WebSocketTestClient {
 	pub async fn new(url: SocketAddr) -> Result<Self, WebSocketTestError> {
+		Self::with_protocols(url, &[]).await
+	}
+
+	/// Connect requesting the subprotocols `protocols`.
+	pub async fn with_protocols(url: SocketAddr, protocols: &[&str]) -> Result<Self, WebSocketTestError> {
 		let socket = TcpStream::connect(url).await?;
 		let mut client = handshake::Client::new(BufReader::new(BufWriter::new(socket.compat())), "test-client", "/");
+		for protocol in protocols {
+			client.add_protocol(protocol);
+		}
 		match client.handshake().await {
 			Ok(handshake::ServerResponse::Accepted { .. }) => {
 				let (tx, rx) = client.into_builder().finish();
@@ -133,6 +141,17 @@ impl WebSocketTestClient {
 		String::from_utf8(data).map_err(Into::into)
 	}
 
+	pub async fn send(&mut self, msg: impl AsRef<str>) -> Result<(), Error> {
+		self.tx.send_text(msg).await?;
+		self.tx.flush().await.map_err(Into::into)
+	}
+
+	pub async fn receive(&mut self) -> Result<String, Error> {
+		let mut data = Vec::new();
+		self.rx.receive_data(&mut data).await?;
+		String::from_utf8(data).map_err(Into::into)
+	}
+
 	pub async fn close(&mut self) -> Result<(), Error> {
 		self.tx.close().await.map_err(Into::into)
 	}
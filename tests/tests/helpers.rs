@@ -25,16 +25,60 @@
 // DEALINGS IN THE SOFTWARE.
 
 use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::time::Duration;
 
 use jsonrpsee::core::Error;
 use jsonrpsee::http_server::{AccessControl, HttpServerBuilder, HttpServerHandle};
+use jsonrpsee::ipc_server::{Framing, IpcServerBuilder, IpcServerHandle};
 use jsonrpsee::ws_server::{WsServerBuilder, WsServerHandle};
 use jsonrpsee::RpcModule;
+use tokio::io::{DuplexStream, ReadHalf, WriteHalf};
 
 pub async fn websocket_server_with_subscription() -> (SocketAddr, WsServerHandle) {
-	let server = WsServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+	websocket_server_with_subscription_at("127.0.0.1:0".parse().unwrap()).await
+}
+
+pub async fn websocket_server_with_subscription_at(addr: SocketAddr) -> (SocketAddr, WsServerHandle) {
+	let server = WsServerBuilder::default().build(addr).await.unwrap();
+	let addr = server.local_addr().unwrap();
+	let server_handle = server.start(module_with_subscription()).unwrap();
+
+	(addr, server_handle)
+}
+
+pub async fn ipc_server_with_subscription() -> (PathBuf, IpcServerHandle) {
+	let name = format!("jsonrpsee-integration-tests-{}", std::process::id());
+	#[cfg(unix)]
+	let path = std::env::temp_dir().join(name);
+	#[cfg(windows)]
+	let path = PathBuf::from(format!(r"\\.\pipe\{}", name));
+	let server = IpcServerBuilder::default().build(&path).await.unwrap();
+	let server_handle = server.start(module_with_subscription()).unwrap();
+
+	(path, server_handle)
+}
+
+/// Server of a single connection over pipes with the Language Server Protocol framing, returns the ends of the pipes
+/// for the client.
+pub fn stdio_server_with_subscription() -> ((ReadHalf<DuplexStream>, WriteHalf<DuplexStream>), IpcServerHandle) {
+	let (client, server) = tokio::io::duplex(4096);
+	let (reader, writer) = tokio::io::split(server);
+	let server = IpcServerBuilder::default().framing(Framing::ContentLength).build_with_io(reader, writer);
+	let server_handle = server.start(module_with_subscription()).unwrap();
+
+	(tokio::io::split(client), server_handle)
+}
+
+pub async fn tcp_server_with_subscription() -> (SocketAddr, IpcServerHandle) {
+	let server = IpcServerBuilder::default().build_tcp("127.0.0.1:0").await.unwrap();
+	let addr = server.local_addr().unwrap();
+	let server_handle = server.start(module_with_subscription()).unwrap();
+
+	(addr, server_handle)
+}
 
+fn module_with_subscription() -> RpcModule<()> {
 	let mut module = RpcModule::new(());
 	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
 
@@ -91,10 +135,7 @@ pub async fn websocket_server_with_subscription() -> (SocketAddr, WsServerHandle
 		})
 		.unwrap();
 
-	let addr = server.local_addr().unwrap();
-	let server_handle = server.start(module).unwrap();
-
-	(addr, server_handle)
+	module
 }
 
 pub async fn websocket_server() -> SocketAddr {
@@ -126,6 +167,23 @@ pub async fn http_server_with_access_control(acl: AccessControl) -> (SocketAddr,
 	let addr = server.local_addr().unwrap();
 	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
 	module.register_method("notif", |_, _| Ok("")).unwrap();
+	module
+		.register_async_method("slow_hello", |_, _| async {
+			tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+			Ok("hello")
+		})
+		.unwrap();
+
+	let handle = server.start(module).unwrap();
+	(addr, handle)
+}
+
+/// HTTP server answering its `name` to the `name` method.
+pub async fn http_server_with_name(name: &'static str) -> (SocketAddr, HttpServerHandle) {
+	let server = HttpServerBuilder::default().build("127.0.0.1:0").unwrap();
+	let mut module = RpcModule::new(());
+	let addr = server.local_addr().unwrap();
+	module.register_method("name", move |_, _| Ok(name)).unwrap();
 
 	let handle = server.start(module).unwrap();
 	(addr, handle)
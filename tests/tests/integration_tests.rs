@@ -31,13 +31,18 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use futures::TryStreamExt;
-use helpers::{http_server, http_server_with_access_control, websocket_server, websocket_server_with_subscription};
-use jsonrpsee::core::client::{ClientT, IdKind, Subscription, SubscriptionClientT};
+use helpers::{
+	http_server, http_server_with_access_control, http_server_with_name, ipc_server_with_subscription,
+	stdio_server_with_subscription, tcp_server_with_subscription, websocket_server, websocket_server_with_subscription,
+	websocket_server_with_subscription_at,
+};
+use jsonrpsee::core::client::{BatchRequestBuilder, ClientT, IdKind, Subscription, SubscriptionClientT};
 use jsonrpsee::core::error::{SubscriptionClosed, SubscriptionClosedReason};
 use jsonrpsee::core::{Error, JsonValue};
-use jsonrpsee::http_client::HttpClientBuilder;
+use jsonrpsee::http_client::{HttpClientBuilder, LoadBalancing};
+use jsonrpsee::ipc_client::{Framing, IpcClientBuilder};
 use jsonrpsee::rpc_params;
-use jsonrpsee::ws_client::WsClientBuilder;
+use jsonrpsee::ws_client::{Backoff, SubscriptionEvent, WsClientBuilder};
 use tokio::time::interval;
 use tokio_stream::wrappers::IntervalStream;
 
@@ -60,6 +65,106 @@ async fn ws_subscription_works() {
 	}
 }
 
+#[tokio::test]
+async fn ipc_method_call_and_subscription_work() {
+	let (path, _handle) = ipc_server_with_subscription().await;
+	let client = IpcClientBuilder::default().build(&path).await.unwrap();
+	let response: String = client.request("say_hello", None).await.unwrap();
+	assert_eq!(&response, "hello");
+
+	let mut add_one: Subscription<u64> =
+		client.subscribe("subscribe_add_one", rpc_params![1], "unsubscribe_add_one").await.unwrap();
+	for i in 2..4 {
+		let next = add_one.next().await.unwrap().unwrap();
+		assert_eq!(next, i);
+	}
+}
+
+#[tokio::test]
+async fn tcp_method_call_and_subscription_work() {
+	let (addr, _handle) = tcp_server_with_subscription().await;
+	let client = IpcClientBuilder::default().build_tcp(addr).await.unwrap();
+	let response: String = client.request("say_hello", None).await.unwrap();
+	assert_eq!(&response, "hello");
+
+	let mut add_one: Subscription<u64> =
+		client.subscribe("subscribe_add_one", rpc_params![1], "unsubscribe_add_one").await.unwrap();
+	for i in 2..4 {
+		let next = add_one.next().await.unwrap().unwrap();
+		assert_eq!(next, i);
+	}
+}
+
+#[tokio::test]
+async fn stdio_method_call_and_subscription_work() {
+	let ((reader, writer), handle) = stdio_server_with_subscription();
+	let client = IpcClientBuilder::default().framing(Framing::ContentLength).build_with_io(reader, writer);
+	let response: String = client.request("say_hello", None).await.unwrap();
+	assert_eq!(&response, "hello");
+
+	let mut add_one: Subscription<u64> =
+		client.subscribe("subscribe_add_one", rpc_params![1], "unsubscribe_add_one").await.unwrap();
+	for i in 2..4 {
+		let next = add_one.next().await.unwrap().unwrap();
+		assert_eq!(next, i);
+	}
+
+	// The server stops once the client is gone.
+	drop(add_one);
+	drop(client);
+	tokio::time::timeout(Duration::from_secs(5), handle).await.unwrap();
+}
+
+#[tokio::test]
+async fn ws_client_reconnects_and_resubscribes() {
+	let (addr, handle) = websocket_server_with_subscription().await;
+	let client = WsClientBuilder::default()
+		.reconnect_backoff(Backoff::Fixed(Duration::from_millis(50)))
+		.build_reconnecting(format!("ws://{}", addr))
+		.await
+		.unwrap();
+	let mut add_one =
+		client.subscribe_reconnecting::<u64>("subscribe_add_one", rpc_params![1], "unsubscribe_add_one").await.unwrap();
+	assert_eq!(add_one.next().await.unwrap().unwrap(), SubscriptionEvent::Notification(2));
+
+	handle.stop().unwrap().await;
+	let (_, _handle) = websocket_server_with_subscription_at(addr).await;
+
+	// The notifications received before the connection dropped come first.
+	let reconnected = async { while add_one.next().await.unwrap().unwrap() != SubscriptionEvent::Reconnected {} };
+	tokio::time::timeout(Duration::from_secs(10), reconnected).await.unwrap();
+	assert_eq!(add_one.next().await.unwrap().unwrap(), SubscriptionEvent::Notification(2));
+	let response: String = client.request("say_hello", None).await.unwrap();
+	assert_eq!(&response, "hello");
+}
+
+#[tokio::test]
+async fn ws_client_reconnecting_is_bounded_by_the_request_timeout() {
+	let (addr, handle) = websocket_server_with_subscription().await;
+	let client = WsClientBuilder::default()
+		.request_timeout(Duration::from_millis(300))
+		.reconnect_backoff(Backoff::Fixed(Duration::from_millis(50)))
+		.build_reconnecting(format!("ws://{}", addr))
+		.await
+		.unwrap();
+
+	handle.stop().unwrap().await;
+	while client.is_connected().await {
+		tokio::time::sleep(Duration::from_millis(10)).await;
+	}
+
+	let pending = {
+		let client = client.clone();
+		tokio::spawn(async move { client.request::<String>("say_hello", None).await })
+	};
+	tokio::time::sleep(Duration::from_millis(50)).await;
+	// The other calls aren't held up while reconnecting.
+	assert!(!tokio::time::timeout(Duration::from_millis(100), client.is_connected()).await.unwrap());
+
+	let res = tokio::time::timeout(Duration::from_secs(5), pending).await.unwrap().unwrap();
+	assert!(matches!(res, Err(Error::RequestTimeout)), "{:?}", res);
+}
+
 #[tokio::test]
 async fn ws_subscription_with_input_works() {
 	let (server_addr, _) = websocket_server_with_subscription().await;
@@ -486,6 +591,58 @@ async fn ws_batch_works() {
 	assert_eq!(responses, vec!["hello".to_string(), "hello".to_string()]);
 }
 
+#[tokio::test]
+async fn typed_batch_returns_the_result_of_each_call() {
+	let ws_url = format!("ws://{}", websocket_server().await);
+	let (http_addr, _handle) = http_server().await;
+	let ws_client = WsClientBuilder::default().build(&ws_url).await.unwrap();
+	let http_client = HttpClientBuilder::default().build(format!("http://{}", http_addr)).unwrap();
+
+	async fn check(client: &(impl ClientT + Sync)) {
+		let mut batch = BatchRequestBuilder::new();
+		let hello = batch.insert::<String>("say_hello", None);
+		let missing = batch.insert::<u64>("no_such_method", None);
+		let raw = batch.insert::<JsonValue>("say_hello", rpc_params![]);
+
+		let mut response = client.batch(batch).await.unwrap();
+		assert_eq!(response.len(), 3);
+		assert_eq!(response.num_failed(), 1);
+		assert_eq!(response.take(hello).unwrap(), "hello");
+		assert!(matches!(response.take(missing), Err(Error::Request(msg)) if msg.contains("Method not found")));
+		assert_eq!(response.take(raw).unwrap(), JsonValue::from("hello"));
+
+		let err =
+			client.batch_request::<String>(vec![("say_hello", None), ("no_such_method", None)]).await.unwrap_err();
+		assert!(matches!(err, Error::Request(msg) if msg.contains("Method not found")));
+	}
+
+	check(&ws_client).await;
+	check(&http_client).await;
+}
+
+#[tokio::test]
+async fn ws_deflate_works() {
+	use jsonrpsee::ws_server::{DeflateConfig, RpcModule, WsServerBuilder};
+
+	let mut module = RpcModule::new(());
+	module.register_method("echo", |params, _| params.one::<String>().map_err(Into::into)).unwrap();
+	let server = WsServerBuilder::default()
+		.set_deflate(DeflateConfig::default().max_window_bits(10).threshold(16))
+		.build("127.0.0.1:0")
+		.await
+		.unwrap();
+	let server_url = format!("ws://{}", server.local_addr().unwrap());
+	let _handle = server.start(module).unwrap();
+
+	let large = "compress me ".repeat(10_000);
+	let compressed = WsClientBuilder::default().deflate(DeflateConfig::default()).build(&server_url).await.unwrap();
+	let plain = WsClientBuilder::default().build(&server_url).await.unwrap();
+	for client in [&compressed, &plain] {
+		assert_eq!(client.request::<String>("echo", rpc_params!["short"]).await.unwrap(), "short");
+		assert_eq!(client.request::<String>("echo", rpc_params![&large]).await.unwrap(), large);
+	}
+}
+
 #[tokio::test]
 async fn http_unsupported_methods_dont_work() {
 	use hyper::{Body, Client, Method, Request};
@@ -622,3 +779,84 @@ fn comma_separated_header_values(headers: &hyper::HeaderMap, header: &str) -> Ve
 		.map(|header| header.to_ascii_lowercase())
 		.collect()
 }
+
+#[tokio::test]
+async fn http_multi_client_balances_and_fails_over() {
+	let (a, _a_handle) = http_server_with_name("a").await;
+	let (b, _b_handle) = http_server_with_name("b").await;
+	let (dead, dead_handle) = http_server_with_name("dead").await;
+	dead_handle.stop().unwrap().await.unwrap();
+	let [a, b, dead] = [a, b, dead].map(|addr| format!("http://{}", addr));
+
+	let client = HttpClientBuilder::default().build_multi([&a, &b]).unwrap();
+	let first: String = client.request("name", None).await.unwrap();
+	let second: String = client.request("name", None).await.unwrap();
+	assert_ne!(first, second);
+
+	let client =
+		HttpClientBuilder::default().load_balancing(LoadBalancing::Failover).build_multi([dead, a, b]).unwrap();
+	for _ in 0..3 {
+		assert_eq!(client.request::<String>("name", None).await.unwrap(), "a");
+	}
+	assert!(!client.is_healthy(0));
+	assert!(client.is_healthy(1));
+
+	assert!(HttpClientBuilder::default().build_multi(Vec::<String>::new()).is_err());
+}
+
+#[tokio::test]
+async fn request_with_timeout_overrides_the_client_timeout() {
+	let server_addr = websocket_server().await;
+	let client = WsClientBuilder::default()
+		.request_timeout(Duration::from_millis(100))
+		.build(format!("ws://{}", server_addr))
+		.await
+		.unwrap();
+	assert!(matches!(client.request::<String>("slow_hello", None).await, Err(Error::RequestTimeout)));
+	let hello: String = client.request_with_timeout("slow_hello", None, Duration::from_secs(10)).await.unwrap();
+	assert_eq!(hello, "hello");
+
+	let (server_addr, _handle) = http_server().await;
+	let client = HttpClientBuilder::default().build(format!("http://{}", server_addr)).unwrap();
+	let res = client.request_with_timeout::<String>("slow_hello", None, Duration::from_millis(100)).await;
+	assert!(matches!(res, Err(Error::RequestTimeout)));
+	assert_eq!(client.request::<String>("slow_hello", None).await.unwrap(), "hello");
+}
+
+#[tokio::test]
+async fn ws_subscription_close_discriminator_is_opt_in() {
+	use jsonrpsee::ws_server::WsServerBuilder;
+	use jsonrpsee::RpcModule;
+
+	let mut module = RpcModule::new(());
+	module
+		.register_subscription("sub_reason", "reason", "unsub_reason", |_, mut sink, _| {
+			// An item shaped like a close reason.
+			sink.send(&SubscriptionClosed::new(SubscriptionClosedReason::Server("item".into())))?;
+			sink.close(&SubscriptionClosed::new(SubscriptionClosedReason::Server("done".into())));
+			Ok(())
+		})
+		.unwrap();
+
+	for discriminator in [false, true] {
+		let server = WsServerBuilder::default()
+			.subscription_close_discriminator(discriminator)
+			.build("127.0.0.1:0")
+			.await
+			.unwrap();
+		let server_url = format!("ws://{}", server.local_addr().unwrap());
+		let _handle = server.start(module.clone()).unwrap();
+		let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+
+		let mut sub: Subscription<JsonValue> = client.subscribe("sub_reason", None, "unsub_reason").await.unwrap();
+		assert_eq!(sub.next().await.unwrap().unwrap(), serde_json::json!({ "reason": { "Server": "item" } }));
+		match sub.next().await.unwrap() {
+			// Without the discriminator, the close is decoded as an item too.
+			Ok(item) if !discriminator => assert_eq!(item, serde_json::json!({ "reason": { "Server": "done" } })),
+			Err(Error::SubscriptionClosed(closed)) if discriminator => {
+				assert_eq!(closed.close_reason(), &SubscriptionClosedReason::Server("done".into()))
+			}
+			other => panic!("Unexpected close with discriminator={}: {:?}", discriminator, other),
+		}
+	}
+}
@@ -29,13 +29,20 @@ use std::net::SocketAddr;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
-use jsonrpsee::core::{client::ClientT, middleware::Middleware, Error};
+use jsonrpsee::core::{
+	async_trait,
+	client::{ClientMiddleware, ClientT, MiddlewareClient},
+	middleware::{AsyncMiddleware, ConnectionDetails, Middleware, TransportKind},
+	Error, JsonRawValue,
+};
 use jsonrpsee::http_client::HttpClientBuilder;
 use jsonrpsee::http_server::{HttpServerBuilder, HttpServerHandle};
 use jsonrpsee::proc_macros::rpc;
+use jsonrpsee::types::error::CallError;
+use jsonrpsee::types::ParamsSer;
 use jsonrpsee::ws_client::WsClientBuilder;
 use jsonrpsee::ws_server::{WsServerBuilder, WsServerHandle};
-use jsonrpsee::RpcModule;
+use jsonrpsee::{rpc_params, RpcModule};
 use tokio::time::sleep;
 
 #[derive(Clone, Default)]
@@ -51,6 +58,12 @@ struct CounterInner {
 	requests: (u32, u32),
 	/// Mapping method names to (number of calls, ids of successfully completed calls)
 	calls: HashMap<String, (u32, Vec<u32>)>,
+	/// Method names of the calls that failed
+	failures: Vec<String>,
+	/// (Number of opened subscriptions, number of closed subscriptions)
+	subscriptions: (u32, u32),
+	/// Raw params, connection id and transport of each call
+	call_details: Vec<(Option<String>, usize, TransportKind)>,
 }
 
 impl Middleware for Counter {
@@ -70,16 +83,20 @@ impl Middleware for Counter {
 		n
 	}
 
-	fn on_call(&self, name: &str) {
+	fn on_call(&self, name: &str, params: Option<&JsonRawValue>, details: &ConnectionDetails) {
 		let mut inner = self.inner.lock().unwrap();
 		let entry = inner.calls.entry(name.into()).or_insert((0, Vec::new()));
 
 		entry.0 += 1;
+		inner.call_details.push((params.map(|params| params.get().to_owned()), details.conn_id(), details.transport()));
 	}
 
 	fn on_result(&self, name: &str, success: bool, n: u32) {
+		let mut inner = self.inner.lock().unwrap();
 		if success {
-			self.inner.lock().unwrap().calls.get_mut(name).unwrap().1.push(n);
+			inner.calls.get_mut(name).unwrap().1.push(n);
+		} else {
+			inner.failures.push(name.into());
 		}
 	}
 
@@ -90,6 +107,48 @@ impl Middleware for Counter {
 	fn on_disconnect(&self) {
 		self.inner.lock().unwrap().connections.1 += 1;
 	}
+
+	fn on_subscribe(&self) {
+		self.inner.lock().unwrap().subscriptions.0 += 1;
+	}
+
+	fn on_unsubscribe(&self) {
+		self.inner.lock().unwrap().subscriptions.1 += 1;
+	}
+}
+
+/// Method names of the calls along with a value recorded for each.
+type Calls<T> = Arc<Mutex<Vec<(String, T)>>>;
+
+/// Rejects the calls with `13` as first param, after pretending to ask a remote service.
+#[derive(Clone, Default)]
+struct Auth {
+	/// Transports of the authorized calls
+	authorized: Calls<TransportKind>,
+	/// Results of the calls
+	results: Calls<bool>,
+}
+
+#[async_trait]
+impl AsyncMiddleware for Auth {
+	async fn on_call(
+		&self,
+		name: &str,
+		params: Option<&JsonRawValue>,
+		details: &ConnectionDetails,
+	) -> Result<(), Error> {
+		sleep(Duration::from_millis(10)).await;
+
+		if params.is_some_and(|params| params.get().starts_with("[13")) {
+			return Err(Error::Call(CallError::Custom { code: -32001, message: "Unauthorized".into(), data: None }));
+		}
+		self.authorized.lock().unwrap().push((name.to_owned(), details.transport()));
+		Ok(())
+	}
+
+	async fn on_result(&self, name: &str, success: bool) {
+		self.results.lock().unwrap().push((name.to_owned(), success));
+	}
 }
 
 fn test_module() -> RpcModule<()> {
@@ -100,6 +159,11 @@ fn test_module() -> RpcModule<()> {
 			sleep(Duration::from_millis(50)).await;
 			Ok("hello")
 		}
+
+		#[method(name = "add")]
+		fn add(&self, a: u32, b: u32) -> Result<u32, Error> {
+			Ok(a + b)
+		}
 	}
 
 	impl RpcServer for () {}
@@ -158,6 +222,8 @@ async fn ws_server_middleware() {
 		assert_eq!(inner.requests, (5, 5));
 		assert_eq!(inner.calls["say_hello"], (3, vec![0, 2, 3]));
 		assert_eq!(inner.calls["unknown_method"], (2, vec![]));
+		// The results of unknown methods are not reported.
+		assert!(inner.failures.is_empty());
 	}
 
 	server_handle.stop().unwrap().await;
@@ -187,9 +253,323 @@ async fn http_server_middleware() {
 	assert_eq!(inner.requests, (5, 5));
 	assert_eq!(inner.calls["say_hello"], (3, vec![0, 2, 3]));
 	assert_eq!(inner.calls["unknown_method"], (2, vec![]));
+	// The results of unknown methods are not reported.
+	assert!(inner.failures.is_empty());
 
 	server_handle.stop().unwrap().await.unwrap();
 
 	// HTTP server doesn't track connections
 	assert_eq!(inner.connections, (0, 0));
 }
+
+#[tokio::test]
+async fn ws_server_middleware_sees_call_details() {
+	let counter = Counter::default();
+	let (server_addr, _server_handle) = websocket_server(test_module(), counter.clone()).await.unwrap();
+
+	let server_url = format!("ws://{}", server_addr);
+	let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+
+	assert_eq!(client.request::<u32>("add", rpc_params![1, 2]).await.unwrap(), 3);
+	let batch = vec![("add", rpc_params![3, 4]), ("say_hello", None)];
+	assert_eq!(client.batch_request::<serde_json::Value>(batch).await.unwrap().len(), 2);
+
+	let inner = counter.inner.lock().unwrap();
+	let (_, conn_id, _) = inner.call_details[0];
+
+	assert_eq!(
+		inner.call_details,
+		vec![
+			(Some("[1,2]".to_owned()), conn_id, TransportKind::WebSocket),
+			(Some("[3,4]".to_owned()), conn_id, TransportKind::WebSocket),
+			(None, conn_id, TransportKind::WebSocket),
+		]
+	);
+}
+
+#[tokio::test]
+async fn http_server_middleware_sees_call_details() {
+	let counter = Counter::default();
+	let (server_addr, _server_handle) = http_server(test_module(), counter.clone()).await.unwrap();
+
+	let server_url = format!("http://{}", server_addr);
+	let client = HttpClientBuilder::default().build(&server_url).unwrap();
+
+	assert_eq!(client.request::<u32>("add", rpc_params![1, 2]).await.unwrap(), 3);
+	let batch = vec![("add", rpc_params![3, 4]), ("say_hello", None)];
+	assert_eq!(client.batch_request::<serde_json::Value>(batch).await.unwrap().len(), 2);
+
+	let inner = counter.inner.lock().unwrap();
+	let (_, conn_id, _) = inner.call_details[0];
+
+	assert_eq!(
+		inner.call_details,
+		vec![
+			(Some("[1,2]".to_owned()), conn_id, TransportKind::Http),
+			(Some("[3,4]".to_owned()), conn_id, TransportKind::Http),
+			(None, conn_id, TransportKind::Http),
+		]
+	);
+}
+
+#[tokio::test]
+async fn ws_server_async_middleware() {
+	let auth = Auth::default();
+	let server = WsServerBuilder::default().set_async_middleware(auth.clone()).build("127.0.0.1:0").await.unwrap();
+	let server_url = format!("ws://{}", server.local_addr().unwrap());
+	let _handle = server.start(test_module()).unwrap();
+	let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+
+	assert_eq!(client.request::<u32>("add", rpc_params![1, 2]).await.unwrap(), 3);
+	match client.request::<u32>("add", rpc_params![13, 0]).await {
+		Err(Error::Request(err)) => assert!(err.contains("Unauthorized"), "{}", err),
+		other => panic!("Unexpected result: {:?}", other),
+	}
+	assert_eq!(client.request::<String>("say_hello", None).await.unwrap(), "hello");
+
+	assert_eq!(
+		*auth.authorized.lock().unwrap(),
+		vec![("add".to_owned(), TransportKind::WebSocket), ("say_hello".to_owned(), TransportKind::WebSocket)]
+	);
+	assert_eq!(
+		*auth.results.lock().unwrap(),
+		vec![("add".to_owned(), true), ("add".to_owned(), false), ("say_hello".to_owned(), true)]
+	);
+}
+
+#[tokio::test]
+async fn ws_server_async_middleware_authorizes_subscriptions() {
+	use jsonrpsee::core::client::SubscriptionClientT;
+
+	let mut module = RpcModule::new(());
+	module
+		.register_subscription("sub_n", "n", "unsub_n", |params, mut sink, _| {
+			let n: u32 = params.one()?;
+			sink.send(&n)?;
+			Ok(())
+		})
+		.unwrap();
+
+	let auth = Auth::default();
+	let server = WsServerBuilder::default().set_async_middleware(auth.clone()).build("127.0.0.1:0").await.unwrap();
+	let server_url = format!("ws://{}", server.local_addr().unwrap());
+	let _handle = server.start(module).unwrap();
+	let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+
+	let mut sub = client.subscribe::<u32>("sub_n", rpc_params![1], "unsub_n").await.unwrap();
+	assert_eq!(sub.next().await.unwrap().unwrap(), 1);
+	match client.subscribe::<u32>("sub_n", rpc_params![13], "unsub_n").await {
+		Err(Error::Request(err)) => assert!(err.contains("Unauthorized"), "{}", err),
+		other => panic!("Unexpected result: {:?}", other.map(|_| ())),
+	}
+
+	assert_eq!(*auth.authorized.lock().unwrap(), vec![("sub_n".to_owned(), TransportKind::WebSocket)]);
+	assert_eq!(*auth.results.lock().unwrap(), vec![("sub_n".to_owned(), true), ("sub_n".to_owned(), false)]);
+}
+
+#[tokio::test]
+async fn http_server_async_middleware() {
+	let auth = Auth::default();
+	let server = HttpServerBuilder::default().set_async_middleware(auth.clone()).build("127.0.0.1:0").unwrap();
+	let server_url = format!("http://{}", server.local_addr().unwrap());
+	let _handle = server.start(test_module()).unwrap();
+	let client = HttpClientBuilder::default().build(&server_url).unwrap();
+
+	let batch = vec![("add", rpc_params![1, 2]), ("add", rpc_params![13, 0])];
+	assert!(client.batch_request::<u32>(batch).await.is_err());
+	assert_eq!(client.request::<u32>("add", rpc_params![3, 4]).await.unwrap(), 7);
+
+	assert_eq!(
+		*auth.authorized.lock().unwrap(),
+		vec![("add".to_owned(), TransportKind::Http), ("add".to_owned(), TransportKind::Http)]
+	);
+	assert_eq!(auth.results.lock().unwrap().iter().filter(|(_, success)| !success).count(), 1);
+}
+
+#[tokio::test]
+async fn ws_server_middleware_counts_subscriptions() {
+	use jsonrpsee::core::client::SubscriptionClientT;
+
+	let mut module = RpcModule::new(());
+	module
+		.register_subscription("subscribe_hello", "subscribe_hello", "unsubscribe_hello", |_, mut sink, _| {
+			std::thread::spawn(move || loop {
+				if let Err(Error::SubscriptionClosed(_)) = sink.send(&"hello") {
+					break;
+				}
+				std::thread::sleep(Duration::from_millis(10));
+			});
+			Ok(())
+		})
+		.unwrap();
+
+	let counter = Counter::default();
+	let (server_addr, _server_handle) = websocket_server(module, counter.clone()).await.unwrap();
+	let client = WsClientBuilder::default().build(&format!("ws://{}", server_addr)).await.unwrap();
+
+	let mut sub = client.subscribe::<String>("subscribe_hello", None, "unsubscribe_hello").await.unwrap();
+	assert_eq!(sub.next().await.unwrap().unwrap(), "hello");
+	assert_eq!(counter.inner.lock().unwrap().subscriptions, (1, 0));
+
+	drop(sub);
+	for _ in 0..100 {
+		if counter.inner.lock().unwrap().subscriptions == (1, 1) {
+			return;
+		}
+		sleep(Duration::from_millis(10)).await;
+	}
+	panic!("Subscription not closed: {:?}", counter.inner.lock().unwrap().subscriptions);
+}
+
+/// Name, name of the parent and fields of a span.
+type RecordedSpan = (&'static str, Option<&'static str>, HashMap<String, String>);
+
+/// Records the spans created while it's the default subscriber.
+#[derive(Clone, Default)]
+struct SpanRecorder {
+	spans: Arc<Mutex<HashMap<u64, RecordedSpan>>>,
+}
+
+struct FieldVisitor<'a>(&'a mut HashMap<String, String>);
+
+impl tracing::field::Visit for FieldVisitor<'_> {
+	fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+		self.0.insert(field.name().to_owned(), value.to_owned());
+	}
+
+	fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+		self.0.insert(field.name().to_owned(), format!("{:?}", value));
+	}
+}
+
+impl<S> tracing_subscriber::Layer<S> for SpanRecorder
+where
+	S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+{
+	fn on_new_span(
+		&self,
+		attrs: &tracing::span::Attributes<'_>,
+		id: &tracing::span::Id,
+		ctx: tracing_subscriber::layer::Context<'_, S>,
+	) {
+		let parent = ctx.span(id).and_then(|span| span.parent()).map(|parent| parent.name());
+		let mut fields = HashMap::new();
+		attrs.record(&mut FieldVisitor(&mut fields));
+		self.spans.lock().unwrap().insert(id.into_u64(), (attrs.metadata().name(), parent, fields));
+	}
+
+	fn on_record(
+		&self,
+		id: &tracing::span::Id,
+		values: &tracing::span::Record<'_>,
+		_ctx: tracing_subscriber::layer::Context<'_, S>,
+	) {
+		if let Some((_, _, fields)) = self.spans.lock().unwrap().get_mut(&id.into_u64()) {
+			values.record(&mut FieldVisitor(fields));
+		}
+	}
+}
+
+#[tokio::test]
+async fn ws_server_call_spans() {
+	use jsonrpsee::core::client::SubscriptionClientT;
+	use tracing_subscriber::layer::SubscriberExt;
+
+	let recorder = SpanRecorder::default();
+	let _guard = tracing::subscriber::set_default(tracing_subscriber::registry().with(recorder.clone()));
+
+	let mut module = test_module();
+	module
+		.register_subscription("subscribe_hello", "subscribe_hello", "unsubscribe_hello", |_, mut sink, _| {
+			tokio::spawn(async move {
+				while sink.send(&"hello").is_ok() {
+					sleep(Duration::from_millis(10)).await;
+				}
+			});
+			Ok(())
+		})
+		.unwrap();
+	let server = WsServerBuilder::default().call_spans(true).build("127.0.0.1:0").await.unwrap();
+	let server_url = format!("ws://{}", server.local_addr().unwrap());
+	let _handle = server.start(module).unwrap();
+	let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+
+	assert_eq!(client.request::<u32>("add", rpc_params![1, 2]).await.unwrap(), 3);
+	assert!(client.request::<u32>("add", rpc_params!["one", 2]).await.is_err());
+	assert_eq!(client.request::<String>("say_hello", None).await.unwrap(), "hello");
+	let mut sub = client.subscribe::<String>("subscribe_hello", None, "unsubscribe_hello").await.unwrap();
+	assert_eq!(sub.next().await.unwrap().unwrap(), "hello");
+	drop(sub);
+
+	let spans = recorder.spans.lock().unwrap();
+	let mut calls: Vec<_> = spans
+		.values()
+		.filter(|(name, _, fields)| *name == "rpc_call" && fields["rpc.method"] != "unsubscribe_hello")
+		.map(|(_, _, fields)| {
+			(
+				fields["rpc.method"].clone(),
+				fields["rpc.jsonrpc.request_id"].clone(),
+				fields.get("otel.status_code").cloned().unwrap_or_default(),
+			)
+		})
+		.collect();
+	calls.sort();
+	assert_eq!(
+		calls,
+		vec![
+			("add".to_owned(), "0".to_owned(), "OK".to_owned()),
+			("add".to_owned(), "1".to_owned(), "ERROR".to_owned()),
+			("say_hello".to_owned(), "2".to_owned(), "OK".to_owned()),
+			("subscribe_hello".to_owned(), "3".to_owned(), "OK".to_owned()),
+		]
+	);
+	assert!(spans.values().all(|(name, _, fields)| *name != "rpc_call" || fields.contains_key("conn_id")));
+	assert!(spans.values().any(|(name, parent, fields)| *name == "rpc_notification"
+		&& *parent == Some("rpc_call")
+		&& fields["rpc.method"] == "subscribe_hello"));
+}
+
+/// Records the calls of a client along with their outcome, and adds a default `1` as second param of `add`.
+#[derive(Clone, Default)]
+struct ClientRecorder {
+	results: Calls<bool>,
+}
+
+impl ClientMiddleware for ClientRecorder {
+	type Instant = std::time::Instant;
+
+	fn on_request(&self, method: &str, params: &mut Option<ParamsSer<'_>>) -> Self::Instant {
+		if let ("add", Some(ParamsSer::Array(params))) = (method, params) {
+			if params.len() == 1 {
+				params.push(1.into());
+			}
+		}
+		std::time::Instant::now()
+	}
+
+	fn on_response(&self, method: &str, result: Result<(), &Error>, started_at: Self::Instant) {
+		assert!(started_at.elapsed() < Duration::from_secs(5));
+		self.results.lock().unwrap().push((method.to_owned(), result.is_ok()));
+	}
+}
+
+#[tokio::test]
+async fn client_middleware_sees_and_modifies_calls() {
+	let (server_addr, _handle) = http_server(test_module(), Counter::default()).await.unwrap();
+	let recorder = ClientRecorder::default();
+	let client = HttpClientBuilder::default().build(format!("http://{}", server_addr)).unwrap();
+	let client = MiddlewareClient::new(client, (recorder.clone(), ()));
+
+	assert_eq!(client.request::<u32>("add", rpc_params![41]).await.unwrap(), 42);
+	assert_eq!(client.request::<u32>("add", rpc_params![1, 2]).await.unwrap(), 3);
+	assert!(client.request::<String>("unknown_method", None).await.is_err());
+	let batch: Vec<u32> =
+		client.batch_request(vec![("add", rpc_params![1]), ("add", rpc_params![2, 2])]).await.unwrap();
+	assert_eq!(batch, vec![2, 4]);
+	// The wrapped client bypasses the middleware.
+	assert!(client.inner().request::<u32>("add", rpc_params![41]).await.is_err());
+
+	let results = recorder.results.lock().unwrap().clone();
+	let expected = [("add", true), ("add", true), ("unknown_method", false), ("add", true), ("add", true)];
+	assert_eq!(results, expected.map(|(method, ok)| (method.to_owned(), ok)));
+}
@@ -31,6 +31,7 @@ use std::net::SocketAddr;
 use jsonrpsee::core::Error;
 use jsonrpsee::http_client::HttpClientBuilder;
 use jsonrpsee::http_server::HttpServerBuilder;
+use jsonrpsee::types::error::CallError;
 use jsonrpsee::ws_client::*;
 use jsonrpsee::ws_server::WsServerBuilder;
 use serde_json::json;
@@ -64,6 +65,21 @@ mod rpc_impl {
 			Ok(format!("Called with: {}, {:?}, {:?}", a, b, c))
 		}
 
+		#[method(name = "default_params")]
+		fn default_params(
+			&self,
+			a: u32,
+			#[param(default = 10)] b: u32,
+			#[param(default = String::from("none"))] c: String,
+		) -> RpcResult<String> {
+			Ok(format!("Called with: {}, {}, {}", a, b, c))
+		}
+
+		#[method(name = "old_method", aliases = ["foo_older_method"], deprecated = "Use `foo_bar` instead")]
+		fn old_method(&self) -> RpcResult<u16> {
+			Ok(10)
+		}
+
 		#[method(name = "lifetimes")]
 		fn lifetimes(
 			&self,
@@ -127,6 +143,13 @@ mod rpc_impl {
 		fn sub(&self, hash: Input) -> RpcResult<()>;
 	}
 
+	/// Client for a server only accepting params by name.
+	#[rpc(client, namespace = "by_name")]
+	pub trait ByName {
+		#[method(name = "add", param_kind = map)]
+		async fn add(&self, a: u32, b: Option<u32>) -> RpcResult<u32>;
+	}
+
 	/// Trait to ensure that the trait bounds are correct.
 	#[rpc(client, server, namespace = "generic_with_where_clause")]
 	pub trait GenericWhereClause<I, R>
@@ -149,6 +172,29 @@ mod rpc_impl {
 		fn call(&self, input: I) -> RpcResult<R>;
 	}
 
+	#[derive(serde::Deserialize, schemars::JsonSchema)]
+	pub struct Transfer {
+		pub to: String,
+		pub amount: u64,
+	}
+
+	/// Trait to ensure that the OpenRPC document is generated, including for generic types.
+	#[rpc(server, namespace = "wallet", openrpc)]
+	pub trait Wallet<Balance> {
+		/// Get the balance of an account.
+		///
+		/// Defaults to the main account.
+		#[method(name = "balance", aliases = ["wallet_getBalance"])]
+		fn balance(&self, account: Option<String>) -> RpcResult<Balance>;
+
+		#[deprecated(note = "Use `wallet_transfer` instead")]
+		#[method(name = "send")]
+		async fn send(&self, transfer: Transfer) -> RpcResult<bool>;
+
+		#[subscription(name = "subscribeBalance", item = Balance)]
+		fn subscribe_balance(&self) -> RpcResult<()>;
+	}
+
 	pub struct RpcServerImpl;
 
 	#[async_trait]
@@ -181,6 +227,21 @@ mod rpc_impl {
 		}
 	}
 
+	#[async_trait]
+	impl WalletServer<u128> for RpcServerImpl {
+		fn balance(&self, _: Option<String>) -> RpcResult<u128> {
+			Ok(0)
+		}
+
+		async fn send(&self, transfer: Transfer) -> RpcResult<bool> {
+			Ok(transfer.amount > 0 && !transfer.to.is_empty())
+		}
+
+		fn subscribe_balance(&self, mut sink: SubscriptionSink) -> RpcResult<()> {
+			sink.send(&0_u128)
+		}
+	}
+
 	#[async_trait]
 	impl OnlyGenericSubscriptionServer<String, String> for RpcServerImpl {
 		fn sub(&self, mut sink: SubscriptionSink, _: String) -> RpcResult<()> {
@@ -255,6 +316,47 @@ async fn macro_optional_param_parsing() {
 	assert_eq!(resp, r#"{"jsonrpc":"2.0","result":"Called with: 22, None, Some(50)","id":0}"#);
 }
 
+#[tokio::test]
+async fn macro_default_param_parsing() {
+	let module = RpcServerImpl.into_rpc();
+
+	// Defaulted params omitted at tail
+	let res: String = module.call("foo_default_params", [42_u64]).await.unwrap();
+	assert_eq!(&res, "Called with: 42, 10, none");
+
+	// Defaulted param using `null`
+	let res: String = module.call("foo_default_params", [json!(42_u64), json!(null), json!("c")]).await.unwrap();
+	assert_eq!(&res, "Called with: 42, 10, c");
+
+	// Named params using a map
+	let (resp, _) = module
+		.raw_json_request(r#"{"jsonrpc":"2.0","method":"foo_default_params","params":{"a":22,"b":5},"id":0}"#)
+		.await
+		.unwrap();
+	assert_eq!(resp, r#"{"jsonrpc":"2.0","result":"Called with: 22, 5, none","id":0}"#);
+
+	// Params without a default are still required
+	match module.call_with_error::<_, String>("foo_default_params", Vec::<u32>::new()).await {
+		Err(Error::Call(CallError::Custom { code, .. })) => assert_eq!(code, -32602),
+		other => panic!("Unexpected result: {:?}", other),
+	}
+}
+
+#[tokio::test]
+async fn macro_deprecated_methods() {
+	use jsonrpsee::types::EmptyParams;
+
+	let module = RpcServerImpl.into_rpc();
+
+	for name in ["foo_old_method", "foo_older_method"] {
+		assert_eq!(module.method(name).unwrap().deprecation(), Some("Use `foo_bar` instead"));
+	}
+	assert_eq!(module.method("foo_bar").unwrap().deprecation(), None);
+
+	let res: u16 = module.call("foo_old_method", EmptyParams::new()).await.unwrap();
+	assert_eq!(res, 10);
+}
+
 #[tokio::test]
 async fn macro_lifetimes_parsing() {
 	let module = RpcServerImpl.into_rpc();
@@ -306,6 +408,70 @@ async fn multiple_blocking_calls_overlap() {
 	assert!(elapsed < Duration::from_millis(60), "Expected less than 60ms, got {:?}", elapsed);
 }
 
+#[tokio::test]
+async fn openrpc_document_is_generated() {
+	use rpc_impl::WalletServer;
+
+	let doc = <RpcServerImpl as WalletServer<u128>>::open_rpc_document();
+	assert_eq!(doc["openrpc"], "1.2.6");
+	assert_eq!(doc["info"], json!({ "title": "Wallet", "version": env!("CARGO_PKG_VERSION") }));
+
+	let methods = doc["methods"].as_array().unwrap();
+	let names: Vec<_> = methods.iter().map(|method| method["name"].as_str().unwrap()).collect();
+	assert_eq!(names, ["wallet_balance", "wallet_send", "wallet_subscribeBalance", "wallet_unsubscribeBalance"]);
+
+	assert_eq!(methods[0]["description"], "Get the balance of an account.\n\nDefaults to the main account.");
+	assert_eq!(methods[0]["x-aliases"], json!(["wallet_getBalance"]));
+	assert_eq!(methods[0]["params"][0]["name"], "account");
+	assert_eq!(methods[0]["params"][0]["required"], false);
+	assert_eq!(methods[0]["result"]["schema"]["type"], "integer");
+
+	assert_eq!(methods[1]["deprecated"], true);
+	assert_eq!(methods[1]["params"][0]["schema"], json!({ "$ref": "#/components/schemas/Transfer" }));
+	assert_eq!(methods[1]["result"]["schema"]["type"], "boolean");
+	assert_eq!(doc["components"]["schemas"]["Transfer"]["required"], json!(["amount", "to"]));
+
+	assert_eq!(methods[2]["x-subscription"]["unsubscribe"], "wallet_unsubscribeBalance");
+	assert_eq!(methods[2]["x-subscription"]["notification"], "wallet_subscribeBalance");
+	assert_eq!(methods[2]["x-subscription"]["item"]["type"], "integer");
+
+	// The document describes the methods actually registered.
+	let module = WalletServer::<u128>::into_rpc(RpcServerImpl);
+	let sent: bool = module.call("wallet_send", [json!({ "to": "bob", "amount": 1 })]).await.unwrap();
+	assert!(sent);
+}
+
+#[tokio::test]
+async fn map_params_are_sent_by_name() {
+	use jsonrpsee::RpcModule;
+	use rpc_impl::ByNameClient;
+
+	#[derive(serde::Deserialize)]
+	struct AddParams {
+		a: u32,
+		b: Option<u32>,
+	}
+
+	let mut module = RpcModule::new(());
+	module
+		.register_method("by_name_add", |params, _| {
+			if !params.is_object() {
+				return Err(Error::Custom("params must be passed by name".into()));
+			}
+			let params: AddParams = params.parse()?;
+			Ok(params.a + params.b.unwrap_or_default())
+		})
+		.unwrap();
+
+	let server = HttpServerBuilder::default().build("127.0.0.1:0").unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module).unwrap();
+	let client = HttpClientBuilder::default().build(format!("http://{}", addr)).unwrap();
+
+	assert_eq!(client.add(1, Some(2)).await.unwrap(), 3);
+	assert_eq!(client.add(1, None).await.unwrap(), 1);
+}
+
 #[tokio::test]
 async fn subscriptions_do_not_work_for_http_servers() {
 	let htserver = HttpServerBuilder::default().build("127.0.0.1:0").unwrap();
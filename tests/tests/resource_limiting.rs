@@ -217,3 +217,35 @@ async fn http_server_with_macro_module() {
 
 	run_tests_on_http_server(server_addr, server_handle).await;
 }
+
+#[tokio::test]
+async fn ws_server_with_per_connection_resources() {
+	let server = WsServerBuilder::default()
+		.register_resource_per_connection("CPU", 6, 4, 2)
+		.unwrap()
+		.register_resource("MEM", 10, 1)
+		.unwrap()
+		.build("127.0.0.1:0")
+		.await
+		.unwrap();
+	let server_url = format!("ws://{}", server.local_addr().unwrap());
+	let server_handle = server.start(module_manual().unwrap()).unwrap();
+
+	let greedy = WsClientBuilder::default().build(&server_url).await.unwrap();
+	let other = WsClientBuilder::default().build(&server_url).await.unwrap();
+
+	// 2 CPU units (default) per call, so the 3rd call of a connection exceeds its cap, but not the one of the server.
+	let (pass1, pass2, fail, pass_other) = tokio::join!(
+		greedy.request::<String>("say_hello", None),
+		greedy.request::<String>("say_hello", None),
+		greedy.request::<String>("say_hello", None),
+		other.request::<String>("say_hello", None),
+	);
+
+	assert!(pass1.is_ok());
+	assert!(pass2.is_ok());
+	assert_server_busy(fail);
+	assert!(pass_other.is_ok());
+
+	server_handle.stop().unwrap().await;
+}
@@ -26,6 +26,7 @@
 
 use std::collections::HashMap;
 
+use futures::{FutureExt, StreamExt};
 use jsonrpsee::core::error::{Error, SubscriptionClosed, SubscriptionClosedReason};
 use jsonrpsee::core::server::rpc_module::*;
 use jsonrpsee::types::{EmptyParams, Params};
@@ -82,6 +83,62 @@ fn rpc_register_alias() {
 	assert!(module.method("hello_foobar").is_some());
 }
 
+#[test]
+fn module_builder_reports_all_errors() {
+	use jsonrpsee::core::server::resource_limiting::Resources;
+
+	let mut resources = Resources::default();
+	resources.register("cpu", 10, 1).unwrap();
+
+	let module = ModuleBuilder::new(())
+		.validate_resources(&resources)
+		.method("hello_world", |_: Params, _| Ok(()))
+		.resource("cpu", 2)
+		.alias("hello_foobar", "hello_world")
+		.subscription("hi", "hi", "goodbye", |_, _, _| Ok(()))
+		.build()
+		.unwrap();
+
+	assert!(module.method("hello_world").is_some());
+	assert!(module.method("hello_foobar").is_some());
+	assert!(module.method("hi").is_some());
+	assert!(module.method("goodbye").is_some());
+	assert!(Methods::from(module).initialize_resources(&resources).is_ok());
+
+	let err = ModuleBuilder::new(())
+		.validate_resources(&resources)
+		.method("hello_world", |_: Params, _| Ok(()))
+		.resource("memory", 2)
+		.method("hello_world", |_: Params, _| Ok(()))
+		// Skipped, the registration it belongs to failed.
+		.resource("cpu", 1)
+		.subscription("hi", "hi", "hi", |_, _, _| Ok(()))
+		.alias("hello_foobar", "does_not_exist")
+		.build()
+		.unwrap_err();
+
+	match err {
+		Error::InvalidModule(errors) => {
+			assert_eq!(errors.len(), 4);
+			assert!(matches!(errors[0], Error::MethodAlreadyRegistered(_)));
+			assert!(matches!(errors[1], Error::SubscriptionNameConflict(_)));
+			assert!(matches!(errors[2], Error::ResourceNameNotFoundForMethod("memory", "hello_world")));
+			assert!(matches!(errors[3], Error::MethodNotFound(_)));
+		}
+		e => panic!("Expected `Error::InvalidModule`, got: {:?}", e),
+	}
+
+	let err =
+		ModuleBuilder::new(()).resource("cpu", 1).method("hello_world", |_: Params, _| Ok(())).build().unwrap_err();
+	match err {
+		Error::InvalidModule(errors) => {
+			assert_eq!(errors.len(), 1);
+			assert!(matches!(&errors[0], Error::Custom(msg) if msg == "Resource `cpu` is not attached to any method"));
+		}
+		e => panic!("Expected `Error::InvalidModule`, got: {:?}", e),
+	}
+}
+
 #[tokio::test]
 async fn calling_method_without_server() {
 	// Call sync method with no params
@@ -215,16 +272,536 @@ async fn subscribing_without_server() {
 
 	let mut my_sub = module.subscribe("my_sub", EmptyParams::new()).await.unwrap();
 	for i in (0..=2).rev() {
-		let (val, id) = my_sub.next::<char>().await.unwrap().unwrap();
+		let (val, id) = my_sub.next::<char>().await.into_item().unwrap();
 		assert_eq!(val, std::char::from_digit(i, 10).unwrap());
 		assert_eq!(&id, my_sub.subscription_id());
+		assert_eq!(id, my_sub.subscription_id_owned());
 	}
 
-	let sub_err = my_sub.next::<char>().await.unwrap().unwrap_err();
+	// The subscription IDs are numeric without a server.
+	assert!(my_sub.id_as_u64().is_some());
+	assert!(my_sub.id_as_str().is_none());
+
+	let sub_next = my_sub.next::<char>().await;
 	let exp = SubscriptionClosed::new(SubscriptionClosedReason::Server("No close reason provided".to_string()));
 
 	// The subscription is now closed by the server.
-	assert!(matches!(sub_err, Error::SubscriptionClosed(close_reason) if close_reason == exp));
+	assert!(matches!(sub_next, SubNext::Closed(close_reason) if close_reason == exp));
+}
+
+#[tokio::test]
+async fn async_subscription_awaits_its_setup() {
+	use tokio::sync::oneshot;
+
+	let (ready_tx, ready_rx) = oneshot::channel::<u64>();
+	let ready_rx = std::sync::Mutex::new(Some(ready_rx));
+	let mut module = RpcModule::new(());
+	module
+		.register_async_subscription("sub_state", "s_state", "unsub_state", move |params, pending, _| {
+			let ready_rx = ready_rx.lock().unwrap().take();
+			async move {
+				let offset: u64 = params.one()?;
+				let initial = ready_rx.expect("Subscribed once").await.unwrap();
+				if let Some(mut sink) = pending.accept() {
+					sink.send(&(initial + offset))?;
+				}
+				Ok(())
+			}
+		})
+		.unwrap();
+	module
+		.register_async_subscription("sub_fails", "s_fails", "unsub_fails", |params, pending, _| async move {
+			let offset: u64 = params.one()?;
+			let mut sink = pending.accept().expect("Subscriber connected");
+			sink.send(&offset)?;
+			Err(Error::Custom("Backend gone".into()))
+		})
+		.unwrap();
+
+	// The subscription ID is returned once the setup accepted the subscription.
+	let subscribe = module.subscribe("sub_state", [2]);
+	tokio::pin!(subscribe);
+	assert!(subscribe.as_mut().now_or_never().is_none());
+	ready_tx.send(40).unwrap();
+	let mut sub = subscribe.await.unwrap();
+	assert_eq!(sub.next::<u64>().await.into_item().unwrap().0, 42);
+
+	// The setup failed before accepting the subscription.
+	let (resp, _) = module
+		.raw_json_request(r#"{"jsonrpc":"2.0","method":"sub_fails","params":["not a number"],"id":0}"#)
+		.await
+		.unwrap();
+	assert!(resp.contains(r#""code":-32602"#), "{}", resp);
+
+	// The setup failed after accepting the subscription.
+	let mut sub = module.subscribe("sub_fails", [7]).await.unwrap();
+	assert_eq!(sub.next::<u64>().await.into_item().unwrap().0, 7);
+	let reason = SubscriptionClosedReason::Server("Custom error: Backend gone".into());
+	assert!(matches!(sub.next::<u64>().await, SubNext::Closed(closed) if closed.close_reason() == &reason));
+}
+
+#[tokio::test]
+async fn pending_subscriptions_are_accepted_or_rejected() {
+	use jsonrpsee::types::error::CallError;
+
+	let mut module = RpcModule::new(());
+	module
+		.register_pending_subscription("sub_topic", "s_topic", "unsub_topic", |params, pending, _| {
+			let topic: String = match params.one() {
+				Ok(topic) => topic,
+				Err(err) => return pending.reject(err),
+			};
+			match topic.as_str() {
+				"secret" => {
+					pending.reject(CallError::Custom { code: -32010, message: "Not authorized".into(), data: None })
+				}
+				// Neither accepted nor rejected.
+				"ignored" => drop(pending),
+				_ => {
+					// The subscription is accepted once the callback returned.
+					tokio::spawn(async move {
+						tokio::task::yield_now().await;
+						if let Some(mut sink) = pending.accept() {
+							sink.send(&topic).unwrap();
+						}
+					});
+				}
+			}
+		})
+		.unwrap();
+
+	let mut sub = module.subscribe("sub_topic", ["news"]).await.unwrap();
+	assert_eq!(sub.next::<String>().await.into_item().unwrap().0, "news");
+
+	let call = |params: &str| format!(r#"{{"jsonrpc":"2.0","method":"sub_topic","params":{},"id":0}}"#, params);
+	let (resp, _) = module.raw_json_request(&call(r#"["secret"]"#)).await.unwrap();
+	assert_eq!(resp, r#"{"jsonrpc":"2.0","error":{"code":-32010,"message":"Not authorized"},"id":0}"#);
+	let (resp, _) = module.raw_json_request(&call("[1]")).await.unwrap();
+	assert!(resp.contains(r#""code":-32602"#), "{}", resp);
+	let (resp, _) = module.raw_json_request(&call(r#"["ignored"]"#)).await.unwrap();
+	assert_eq!(resp, r#"{"jsonrpc":"2.0","error":{"code":-32000,"message":"Server error"},"id":0}"#);
+}
+
+#[tokio::test]
+async fn subscription_setup_errors_are_sent_to_the_subscriber() {
+	use jsonrpsee::types::error::{ErrorCode, ErrorObject};
+
+	let unknown_block = |block: u64| {
+		let data = serde_json::value::to_raw_value(&block).unwrap();
+		Error::from(ErrorObject {
+			code: ErrorCode::ServerError(-32011),
+			message: "Unknown block".into(),
+			data: Some(&data),
+		})
+	};
+	let expected = r#"{"jsonrpc":"2.0","error":{"code":-32011,"message":"Unknown block","data":7},"id":0}"#;
+
+	let mut module = RpcModule::new(());
+	module
+		.register_subscription("sub_block", "s_block", "unsub_block", move |params, _, _| {
+			Err(unknown_block(params.one()?))
+		})
+		.unwrap();
+	module
+		.register_pending_subscription("sub_pending", "s_pending", "unsub_pending", move |params, pending, _| {
+			match params.one() {
+				Ok(block) => pending.reject(unknown_block(block)),
+				Err(err) => pending.reject(err),
+			}
+		})
+		.unwrap();
+
+	// The subscription was accepted before the callback failed, it is only closed.
+	let (resp, mut rx) =
+		module.raw_json_request(r#"{"jsonrpc":"2.0","method":"sub_block","params":[7],"id":0}"#).await.unwrap();
+	assert!(serde_json::from_str::<jsonrpsee::types::Response<u64>>(&resp).is_ok(), "{}", resp);
+	assert!(rx.next().await.unwrap().contains("subscription_closed"));
+	assert!(rx.try_recv().is_err());
+
+	let (resp, _) =
+		module.raw_json_request(r#"{"jsonrpc":"2.0","method":"sub_pending","params":[7],"id":0}"#).await.unwrap();
+	assert_eq!(resp, expected);
+}
+
+#[tokio::test]
+async fn piped_try_streams_apply_the_error_policy() {
+	let mut module = RpcModule::new(());
+	module
+		.register_subscription("sub_blocks", "s_blocks", "unsub_blocks", |params, sink, _| {
+			let policy = match params.one::<String>()?.as_str() {
+				"close" => StreamErrorPolicy::Close,
+				"skip" => StreamErrorPolicy::Skip,
+				_ => StreamErrorPolicy::Abort,
+			};
+			let stream = futures::stream::iter(vec![Ok(1_u64), Err("Missing block 2"), Ok(3)]);
+			tokio::spawn(sink.pipe_from_try_stream(stream, policy));
+			Ok(())
+		})
+		.unwrap();
+	let closed = |reason: &str| SubscriptionClosed::new(SubscriptionClosedReason::Server(reason.into()));
+
+	let mut sub = module.subscribe("sub_blocks", ["close"]).await.unwrap();
+	assert_eq!(sub.next::<u64>().await.into_item().unwrap().0, 1);
+	assert!(matches!(sub.next::<u64>().await, SubNext::Closed(reason) if reason == closed("Missing block 2")));
+
+	let mut sub = module.subscribe("sub_blocks", ["skip"]).await.unwrap();
+	assert_eq!(sub.next::<u64>().await.into_item().unwrap().0, 1);
+	assert_eq!(sub.next::<u64>().await.into_item().unwrap().0, 3);
+
+	let mut sub = module.subscribe("sub_blocks", ["abort"]).await.unwrap();
+	assert_eq!(sub.next::<u64>().await.into_item().unwrap().0, 1);
+	assert!(matches!(sub.next::<u64>().await, SubNext::Closed(reason) if reason == closed("No close reason provided")));
+}
+
+#[tokio::test(start_paused = true)]
+async fn calls_running_too_long_time_out() {
+	use std::sync::{mpsc, Mutex};
+	use std::time::Duration;
+
+	// The blocking method runs until released.
+	let (release, released) = mpsc::channel();
+	let mut module = RpcModule::new(Mutex::new(released));
+	module
+		.register_async_method("slow", |_, _| async {
+			tokio::time::sleep(Duration::from_secs(60)).await;
+			Ok("done")
+		})
+		.unwrap()
+		.timeout(Duration::from_millis(50))
+		.unwrap();
+	module
+		.register_blocking_method("slow_blocking", |_, released| {
+			released.lock().unwrap().recv().unwrap();
+			Ok("done")
+		})
+		.unwrap()
+		.timeout(Duration::from_millis(50))
+		.unwrap();
+	module
+		.register_async_method("fast", |_, _| async { Ok("done") })
+		.unwrap()
+		.timeout(Duration::from_secs(60))
+		.unwrap();
+	let (accepted_tx, mut accepted_rx) = tokio::sync::mpsc::unbounded_channel();
+	module
+		.register_pending_subscription_with_timeout(
+			"sub",
+			"s",
+			"unsub",
+			Duration::from_millis(50),
+			move |_, pending, _| {
+				let accepted_tx = accepted_tx.clone();
+				tokio::spawn(async move {
+					tokio::time::sleep(Duration::from_millis(200)).await;
+					accepted_tx.send(pending.accept().is_some()).unwrap();
+				});
+			},
+		)
+		.unwrap();
+
+	let timed_out =
+		|id: u64| format!(r#"{{"jsonrpc":"2.0","error":{{"code":-32008,"message":"Request timeout"}},"id":{}}}"#, id);
+	let (resp, _) = module.raw_json_request(r#"{"jsonrpc":"2.0","method":"slow","id":1}"#).await.unwrap();
+	assert_eq!(resp, timed_out(1));
+	// Time doesn't advance on its own while a blocking task runs.
+	let call = tokio::spawn({
+		let module = module.clone();
+		async move { module.raw_json_request(r#"{"jsonrpc":"2.0","method":"slow_blocking","id":2}"#).await.unwrap() }
+	});
+	tokio::task::yield_now().await;
+	tokio::time::advance(Duration::from_millis(50)).await;
+	let (resp, mut rx) = call.await.unwrap();
+	assert_eq!(resp, timed_out(2));
+	// The response of the blocking method is discarded once it completes.
+	release.send(()).unwrap();
+	assert!(rx.next().await.is_none());
+	let (resp, _) = module.raw_json_request(r#"{"jsonrpc":"2.0","method":"fast","id":3}"#).await.unwrap();
+	assert_eq!(resp, r#"{"jsonrpc":"2.0","result":"done","id":3}"#);
+	let (resp, _) = module.raw_json_request(r#"{"jsonrpc":"2.0","method":"sub","id":4}"#).await.unwrap();
+	assert_eq!(resp, timed_out(4));
+	assert_eq!(accepted_rx.recv().await, Some(false));
+}
+
+#[test]
+fn synchronous_methods_cannot_time_out() {
+	let mut module = RpcModule::new(());
+	let err = module.register_method("sync", |_, _| Ok("done")).unwrap().timeout(std::time::Duration::from_secs(1));
+	assert!(matches!(err, Err(Error::TimeoutNotSupported("synchronous"))));
+}
+
+#[tokio::test]
+async fn raw_batch_requests_are_answered_in_order() {
+	let mut module = RpcModule::new(());
+	module.register_method("echo", |params, _| params.one::<u64>().map_err(Into::into)).unwrap();
+	module
+		.register_subscription("sub_hello", "s_hello", "unsub_hello", |_, mut sink, _| {
+			sink.send(&"hello")?;
+			Ok(())
+		})
+		.unwrap();
+
+	let batch = r#"[
+		{"jsonrpc":"2.0","method":"echo","params":[7],"id":1},
+		{"jsonrpc":"2.0","method":"sub_hello","id":2},
+		{"jsonrpc":"2.0","method":"echo","params":["seven"],"id":3}
+	]"#;
+	let (resp, mut notifs) = module.raw_batch_request(batch).await.unwrap();
+	let resp: Vec<serde_json::Value> = serde_json::from_str(&resp).unwrap();
+	assert_eq!(resp.len(), 3);
+	assert_eq!(resp[0], serde_json::json!({"jsonrpc":"2.0","result":7,"id":1}));
+	let sub_id = resp[1]["result"].clone();
+	assert_eq!(resp[2]["error"]["code"], -32602);
+
+	let notif: serde_json::Value = serde_json::from_str(&notifs.next().await.unwrap()).unwrap();
+	assert_eq!(notif["params"], serde_json::json!({"subscription": sub_id, "result": "hello"}));
+
+	let (resp, _) = module.raw_batch_request("[]").await.unwrap();
+	assert_eq!(resp, r#"{"jsonrpc":"2.0","error":{"code":-32600,"message":"Invalid request"},"id":null}"#);
+	assert!(module.raw_batch_request(r#"{"jsonrpc":"2.0","method":"echo","id":1}"#).await.is_err());
+}
+
+#[tokio::test]
+async fn raw_batch_request_answers_each_entry() {
+	let mut module = RpcModule::new(());
+	module.register_method("echo", |params, _| params.one::<u64>().map_err(Into::into)).unwrap();
+
+	// The notification isn't answered and the invalid entries are answered with an error each.
+	let batch = r#"[
+		{"jsonrpc":"2.0","method":"echo","params":[7],"id":1},
+		{"jsonrpc":"2.0","method":"echo","params":[8]},
+		{"jsonrpc":"2.0","params":[9],"id":3},
+		1
+	]"#;
+	let (resp, _) = module.raw_batch_request(batch).await.unwrap();
+	let resp: Vec<serde_json::Value> = serde_json::from_str(&resp).unwrap();
+	assert_eq!(
+		resp,
+		[
+			serde_json::json!({"jsonrpc":"2.0","result":7,"id":1}),
+			serde_json::json!({"jsonrpc":"2.0","error":{"code":-32600,"message":"Invalid request"},"id":3}),
+			serde_json::json!({"jsonrpc":"2.0","error":{"code":-32600,"message":"Invalid request"},"id":null}),
+		]
+	);
+
+	let (resp, _) = module.raw_batch_request(r#"[{"jsonrpc":"2.0","method":"echo","params":[8]}]"#).await.unwrap();
+	assert!(resp.is_empty());
+}
+
+#[tokio::test]
+async fn call_with_error_returns_the_error_object() {
+	use jsonrpsee::types::error::CallError;
+
+	let mut module = RpcModule::new(());
+	module.register_method("echo", |params, _| params.one::<u64>().map_err(Into::into)).unwrap();
+	module
+		.register_method::<(), _>("fails", |_, _| {
+			Err(CallError::Custom {
+				code: -32010,
+				message: "Not today".into(),
+				data: serde_json::value::to_raw_value(&[1, 2]).ok(),
+			}
+			.into())
+		})
+		.unwrap();
+
+	let res: u64 = module.call_with_error("echo", [3_u64]).await.unwrap();
+	assert_eq!(res, 3);
+
+	match module.call_with_error::<_, u64>("echo", [false]).await {
+		Err(Error::Call(CallError::Custom { code, data, .. })) => {
+			assert_eq!(code, -32602);
+			assert!(data.is_none());
+		}
+		other => panic!("Unexpected result: {:?}", other),
+	}
+
+	match module.call_with_error::<_, ()>("fails", EmptyParams::new()).await {
+		Err(Error::Call(CallError::Custom { code, message, data })) => {
+			assert_eq!(code, -32010);
+			assert_eq!(message, "Not today");
+			assert_eq!(data.unwrap().get(), "[1,2]");
+		}
+		other => panic!("Unexpected result: {:?}", other),
+	}
+
+	match module.call_with_error::<_, ()>("unknown", EmptyParams::new()).await {
+		Err(Error::Call(CallError::Custom { code, .. })) => assert_eq!(code, -32601),
+		other => panic!("Unexpected result: {:?}", other),
+	}
+}
+
+#[tokio::test]
+async fn handler_errors_carry_their_data() {
+	use jsonrpsee::types::error::ErrorWithData;
+
+	#[derive(Serialize)]
+	struct Invalid {
+		field: &'static str,
+		min: u64,
+	}
+
+	let mut module = RpcModule::new(());
+	module
+		.register_method("set_limit", |params, _| {
+			let limit: u64 = params.one()?;
+			if limit < 10 {
+				return Err(ErrorWithData::new(-32010, "Invalid limit", Invalid { field: "limit", min: 10 }).into());
+			}
+			Ok(limit)
+		})
+		.unwrap();
+
+	let (resp, _) =
+		module.raw_json_request(r#"{"jsonrpc":"2.0","method":"set_limit","params":[3],"id":1}"#).await.unwrap();
+	assert_eq!(
+		resp,
+		r#"{"jsonrpc":"2.0","error":{"code":-32010,"message":"Invalid limit","data":{"field":"limit","min":10}},"id":1}"#
+	);
+}
+
+#[tokio::test]
+async fn merged_methods_can_be_prefixed() {
+	// The sinks are kept alive, the subscriptions would be closed when dropped otherwise.
+	let mut other = RpcModule::new(std::sync::Mutex::new(Vec::new()));
+	other.register_method("hello", |_, _| Ok("hello")).unwrap();
+	other
+		.register_subscription("sub_ticks", "s_ticks", "unsub_ticks", |_, mut sink, sinks| {
+			sink.send(&"tick")?;
+			sinks.lock().unwrap().push(sink);
+			Ok(())
+		})
+		.unwrap();
+	other
+		.register_lazy(["echo_world"], |name| {
+			let word = name.strip_prefix("echo_")?.to_owned();
+			Some(MethodCallback::new_sync(std::sync::Arc::new(move |id, _, sink| sink.send_response(id, &word))))
+		})
+		.unwrap();
+
+	let mut module = RpcModule::new(());
+	module.register_method("admin_hello", |_, _| Ok("taken")).unwrap();
+	assert!(matches!(module.merge_with_prefix("admin_", other.clone()), Err(Error::MethodAlreadyRegistered(_))));
+	assert_eq!(module.method_names().collect::<Vec<_>>(), ["admin_hello"]);
+
+	// The prefixed names are interned, merging the same methods again reuses them.
+	let mut first = RpcModule::new(());
+	first.merge_with_prefix("admin_", other.clone()).unwrap();
+	let mut module = RpcModule::new(());
+	module.merge_with_prefix("admin_", other).unwrap();
+	let (name, _) = module.method_with_name("admin_hello").unwrap();
+	assert!(std::ptr::eq(name, first.method_with_name("admin_hello").unwrap().0));
+	assert!(module.method("hello").is_none());
+
+	let hello: String = module.call("admin_hello", EmptyParams::new()).await.unwrap();
+	assert_eq!(hello, "hello");
+	let word: String = module.call("admin_echo_world", EmptyParams::new()).await.unwrap();
+	assert_eq!(word, "world");
+	assert!(module.call::<_, String>("echo_world", EmptyParams::new()).await.is_err());
+
+	assert_eq!(
+		module.subscription_info("admin_sub_ticks"),
+		Some(SubscriptionTriple {
+			subscribe: "admin_sub_ticks",
+			notification: "s_ticks",
+			unsubscribe: "admin_unsub_ticks"
+		})
+	);
+	let mut sub = module.subscribe("admin_sub_ticks", EmptyParams::new()).await.unwrap();
+	let (tick, _) = sub.next::<String>().await.into_item().unwrap();
+	assert_eq!(tick, "tick");
+	let unsubscribed: bool = module.call("admin_unsub_ticks", [sub.subscription_id()]).await.unwrap();
+	assert!(unsubscribed);
+}
+
+#[tokio::test]
+async fn discover_returns_the_openrpc_document() {
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("lo")).unwrap();
+	module.register_alias("hello", "say_hello").unwrap();
+	module.register_alias("hi", "say_hello").unwrap();
+	module.register_method("old_hello", |_, _| Ok("lo")).unwrap();
+	module.deprecate_method("old_hello", "Use `say_hello` instead").unwrap();
+	module.register_subscription("sub_ticks", "s_ticks", "unsub_ticks", |_, _, _| Ok(())).unwrap();
+
+	let mut other = RpcModule::new(());
+	other.register_method("peers", |_, _| Ok(0)).unwrap();
+	module.merge_with_prefix("admin_", other).unwrap();
+	module.register_discover("Test API", "0.1.0").unwrap();
+	assert!(matches!(module.register_discover("Test API", "0.1.0"), Err(Error::MethodAlreadyRegistered(_))));
+
+	let doc: serde_json::Value = module.call(DISCOVER_METHOD_NAME, EmptyParams::new()).await.unwrap();
+	let result = serde_json::json!({ "name": "result", "schema": {} });
+	assert_eq!(
+		doc,
+		serde_json::json!({
+			"openrpc": "1.2.6",
+			"info": { "title": "Test API", "version": "0.1.0" },
+			"methods": [
+				{ "name": "admin_peers", "params": [], "result": result },
+				{ "name": "old_hello", "params": [], "result": result, "deprecated": true },
+				{ "name": "say_hello", "params": [], "result": result, "x-aliases": ["hello", "hi"] },
+				{
+					"name": "sub_ticks",
+					"params": [],
+					"result": result,
+					"x-subscription": { "notification": "s_ticks", "unsubscribe": "unsub_ticks" },
+				},
+				{ "name": "unsub_ticks", "params": [], "result": result },
+			],
+		})
+	);
+
+	// The aliases are removed along with their method.
+	module.remove_method("say_hello");
+	let doc = module.openrpc_document("Test API", "0.1.0");
+	let names: Vec<_> = doc["methods"].as_array().unwrap().iter().map(|method| method["name"].clone()).collect();
+	assert!(!names.contains(&"hello".into()) && !names.contains(&"hi".into()));
+	assert!(names.contains(&"rpc.discover".into()));
+	assert!(module.method("hello").is_none() && module.method("hi").is_none());
+}
+
+#[tokio::test]
+async fn removed_methods_are_no_longer_called() {
+	// The sinks are kept alive, the subscriptions would be closed when dropped otherwise.
+	let mut module = RpcModule::new(std::sync::Mutex::new(Vec::new()));
+	module.register_method("hello", |_, _| Ok("hello")).unwrap();
+	module
+		.register_subscription("sub_ticks", "s_ticks", "unsub_ticks", |_, mut sink, sinks| {
+			sink.send(&"tick")?;
+			sinks.lock().unwrap().push(sink);
+			Ok(())
+		})
+		.unwrap();
+
+	let mut sub = module.subscribe("sub_ticks", EmptyParams::new()).await.unwrap();
+	assert_eq!(sub.next::<String>().await.into_item().unwrap().0, "tick");
+
+	assert!(module.remove_method("hello").is_some());
+	assert!(module.remove_method("hello").is_none());
+	assert!(matches!(module.call::<_, String>("hello", EmptyParams::new()).await, Err(Error::Request(_))));
+
+	// Removing the subscribe method removes the unsubscribe method and closes the active subscriptions.
+	assert!(module.remove_method("sub_ticks").is_some());
+	assert!(module.subscription_info("sub_ticks").is_none());
+	assert!(module.method("unsub_ticks").is_none());
+	// Decoded as a `JsonValue`, the close is only told apart from a notification by its discriminator.
+	match sub.next::<serde_json::Value>().await {
+		SubNext::Closed(close) => {
+			assert_eq!(close.close_reason(), &SubscriptionClosedReason::Server("method removed".into()))
+		}
+		other => panic!("Expected the subscription to be closed, got: {:?}", other),
+	}
+
+	// Removing the unsubscribe method removes the subscribe method too.
+	module.register_subscription("sub_blocks", "s_blocks", "unsub_blocks", |_, _, _| Ok(())).unwrap();
+	assert!(module.remove_method("unsub_blocks").is_some());
+	assert!(module.subscription_info("sub_blocks").is_none());
+	assert!(module.method("sub_blocks").is_none());
+
+	// The names can be registered again.
+	module.register_method("hello", |_, _| Ok("hello again")).unwrap();
+	assert_eq!(module.call::<_, String>("hello", EmptyParams::new()).await.unwrap(), "hello again");
+	module.register_subscription("sub_blocks", "s_blocks", "unsub_blocks", |_, _, _| Ok(())).unwrap();
 }
 
 #[tokio::test]
@@ -254,7 +831,7 @@ async fn close_test_subscribing_without_server() {
 		.unwrap();
 
 	let mut my_sub = module.subscribe("my_sub", EmptyParams::new()).await.unwrap();
-	let (val, id) = my_sub.next::<String>().await.unwrap().unwrap();
+	let (val, id) = my_sub.next::<String>().await.into_item().unwrap();
 	assert_eq!(&val, "lo");
 	assert_eq!(&id, my_sub.subscription_id());
 	let mut my_sub2 = std::mem::ManuallyDrop::new(module.subscribe("my_sub", EmptyParams::new()).await.unwrap());
@@ -266,19 +843,1238 @@ async fn close_test_subscribing_without_server() {
 	// The first subscription was not closed using the unsubscribe method and
 	// it will be treated as the connection was closed.
 	let exp = SubscriptionClosed::new(SubscriptionClosedReason::ConnectionReset);
-	assert!(
-		matches!(my_sub.next::<String>().await, Some(Err(Error::SubscriptionClosed(close_reason))) if close_reason == exp)
-	);
+	assert!(matches!(my_sub.next::<String>().await, SubNext::Closed(close_reason) if close_reason == exp));
 
 	// The second subscription still works
-	let (val, _) = my_sub2.next::<String>().await.unwrap().unwrap();
+	let (val, _) = my_sub2.next::<String>().await.into_item().unwrap();
 	assert_eq!(val, "lo".to_string());
 	// Simulate a rude client that disconnects suddenly.
 	unsafe {
 		std::mem::ManuallyDrop::drop(&mut my_sub2);
 	}
 
-	assert!(
-		matches!(my_sub2.next::<String>().await, Some(Err(Error::SubscriptionClosed(close_reason))) if close_reason == exp)
-	);
+	assert!(matches!(my_sub2.next::<String>().await, SubNext::Closed(close_reason) if close_reason == exp));
+}
+
+#[tokio::test]
+async fn reusable_subscription_reuses_equivalent_params() {
+	let mut module = RpcModule::new(());
+	module
+		.register_reusable_subscription(
+			"my_sub",
+			"my_sub",
+			"my_unsub",
+			|params| params.one::<String>().ok(),
+			|params, mut sink, _| {
+				let topic: String = params.one()?;
+				std::thread::spawn(move || {
+					sink.send(&topic).unwrap();
+					while !sink.is_closed() {
+						std::thread::sleep(std::time::Duration::from_millis(100));
+					}
+				});
+				Ok(())
+			},
+		)
+		.unwrap();
+
+	let mut foo = module.subscribe("my_sub", ["foo"]).await.unwrap();
+	let (val, _) = foo.next::<String>().await.into_item().unwrap();
+	assert_eq!(&val, "foo");
+
+	let foo_again = module.subscribe("my_sub", ["foo"]).await.unwrap();
+	assert_eq!(foo.subscription_id(), foo_again.subscription_id());
+
+	let bar = module.subscribe("my_sub", ["bar"]).await.unwrap();
+	assert_ne!(foo.subscription_id(), bar.subscription_id());
+
+	// Once unsubscribed a new subscription is created.
+	let unsubscribed: bool = module.call("my_unsub", [foo.subscription_id_owned()]).await.unwrap();
+	assert!(unsubscribed);
+	let foo_new = module.subscribe("my_sub", ["foo"]).await.unwrap();
+	assert_ne!(foo.subscription_id(), foo_new.subscription_id());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn concurrent_equivalent_subscriptions_are_reused() {
+	let mut module = RpcModule::new(());
+	module
+		.register_reusable_subscription(
+			"my_sub",
+			"my_sub",
+			"my_unsub",
+			|params| params.one::<String>().ok(),
+			|_, sink, _| {
+				std::thread::spawn(move || {
+					while !sink.is_closed() {
+						std::thread::sleep(std::time::Duration::from_millis(100));
+					}
+				});
+				Ok(())
+			},
+		)
+		.unwrap();
+	let module = std::sync::Arc::new(module);
+
+	let subscribes = (0..16).map(|_| {
+		let module = module.clone();
+		tokio::spawn(async move { module.subscribe("my_sub", ["foo"]).await.unwrap() })
+	});
+	let subs: Vec<_> = futures::future::join_all(subscribes).await.into_iter().map(Result::unwrap).collect();
+
+	for sub in &subs {
+		assert_eq!(sub.subscription_id(), subs[0].subscription_id());
+	}
+}
+
+#[test]
+fn flow_controlled_streaming_method_registration_fails_as_a_whole() {
+	let mut module = RpcModule::new(());
+	module.register_method("taken", |_, _| Ok(())).unwrap();
+
+	for (method, credit) in [("taken", "credit"), ("read", "taken")] {
+		let result =
+			module.register_flow_controlled_streaming_method(method, "chunk", credit, 1, |_, _, _| async { Ok(()) });
+		assert!(matches!(result, Err(Error::MethodAlreadyRegistered(name)) if name == "taken"));
+	}
+
+	assert!(module.method("credit").is_none());
+	assert!(module.method("read").is_none());
+}
+
+#[tokio::test]
+async fn calling_streaming_method_without_server() {
+	let mut module = RpcModule::new(());
+	module
+		.register_streaming_method("split", "split_chunk", |params, sink, _| async move {
+			let words: String = params.one()?;
+			for word in words.split(' ') {
+				sink.send(&word)?;
+			}
+			Ok(())
+		})
+		.unwrap();
+	module
+		.register_streaming_method("fail_after_chunk", "fail_chunk", |_, sink, _| async move {
+			sink.send(&"partial")?;
+			Err::<(), _>(Error::Custom("Boom".into()))
+		})
+		.unwrap();
+
+	let chunks: Vec<String> = module.call_streaming("split", ["a stream of words"]).await.unwrap();
+	assert_eq!(chunks, vec!["a", "stream", "of", "words"]);
+
+	let empty: Vec<String> = module.call_streaming("split", [""]).await.unwrap();
+	assert_eq!(empty, vec![""]);
+
+	let err = module.call_streaming::<_, String>("fail_after_chunk", EmptyParams::new()).await.unwrap_err();
+	assert!(matches!(err, Error::Request(msg) if msg.contains("Boom")));
+}
+
+#[tokio::test]
+async fn lazy_methods_are_built_once() {
+	use std::sync::atomic::{AtomicUsize, Ordering};
+	use std::sync::Arc;
+
+	let built = Arc::new(AtomicUsize::new(0));
+	let built2 = built.clone();
+	let mut module = RpcModule::new(());
+	module
+		.register_lazy(["lazy_word", "lazy_other", "lazy_"], move |name| {
+			let len = name.strip_prefix("lazy_").filter(|word| !word.is_empty())?.len();
+			built2.fetch_add(1, Ordering::SeqCst);
+			Some(MethodCallback::new_sync(Arc::new(move |id, _, sink| sink.send_response(id, len))))
+		})
+		.unwrap();
+	assert!(matches!(module.register_lazy(["lazy_word"], |_| None), Err(Error::MethodAlreadyRegistered(_))));
+	assert!(matches!(module.register_method("lazy_other", |_, _| Ok(0_usize)), Err(Error::MethodAlreadyRegistered(_))));
+
+	let (a, b) = futures::future::join(
+		module.call::<_, usize>("lazy_word", EmptyParams::new()),
+		module.call::<_, usize>("lazy_word", EmptyParams::new()),
+	)
+	.await;
+	assert_eq!(a.unwrap(), 4);
+	assert_eq!(b.unwrap(), 4);
+	assert_eq!(module.call::<_, usize>("lazy_word", EmptyParams::new()).await.unwrap(), 4);
+	assert_eq!(built.load(Ordering::SeqCst), 1);
+
+	assert_eq!(module.call::<_, usize>("lazy_other", EmptyParams::new()).await.unwrap(), 5);
+	assert_eq!(built.load(Ordering::SeqCst), 2);
+
+	// The factory doesn't know the method.
+	assert!(matches!(module.call::<_, usize>("lazy_", EmptyParams::new()).await, Err(Error::Request(_))));
+	// The method isn't registered, the factory isn't called.
+	assert!(matches!(module.call::<_, usize>("lazy_random", EmptyParams::new()).await, Err(Error::Request(_))));
+	assert_eq!(built.load(Ordering::SeqCst), 2);
+	assert!(module.method_names().next().is_none());
+}
+
+#[tokio::test]
+async fn methods_finishing_without_a_response_return_an_internal_error() {
+	use std::sync::Arc;
+
+	let mut module = RpcModule::new(());
+	module
+		.register_lazy(["silent_sync", "silent_async"], |name| match name {
+			"silent_sync" => Some(MethodCallback::new_sync(Arc::new(|_, _, _| true))),
+			"silent_async" => Some(MethodCallback::new_async(Arc::new(|_, _, _sink, _, _| async { true }.boxed()))),
+			_ => None,
+		})
+		.unwrap();
+
+	let internal_error = r#"{"jsonrpc":"2.0","error":{"code":-32603,"message":"Internal error"},"id":0}"#;
+	for method in ["silent_sync", "silent_async"] {
+		let err = module.call::<_, ()>(method, EmptyParams::new()).await.unwrap_err();
+		assert!(matches!(err, Error::Request(err) if err == internal_error));
+	}
+
+	let (resp, _) = module.raw_json_request(r#"{"jsonrpc":"2.0","method":"silent_sync","id":7}"#).await.unwrap();
+	assert_eq!(resp, r#"{"jsonrpc":"2.0","error":{"code":-32603,"message":"Internal error"},"id":7}"#);
+}
+
+#[tokio::test]
+async fn call_counters_are_reported_and_reset() {
+	let mut module = RpcModule::new(());
+	module.register_method("a", |_, _| Ok(())).unwrap();
+	module.register_method("b", |_, _| Ok(())).unwrap();
+
+	module.call::<_, ()>("a", EmptyParams::new()).await.unwrap();
+	assert!(module.call_counters().is_empty());
+
+	module.enable_call_counters();
+	let methods: Methods = module.clone().into();
+	for _ in 0..3 {
+		methods.call::<_, ()>("a", EmptyParams::new()).await.unwrap();
+	}
+	module.call::<_, ()>("b", EmptyParams::new()).await.unwrap();
+	// Unknown methods aren't counted.
+	assert!(module.call::<_, ()>("c", EmptyParams::new()).await.is_err());
+
+	assert_eq!(module.call_counters(), HashMap::from([("a", 3), ("b", 1)]));
+
+	module.reset_counters();
+	assert!(methods.call_counters().is_empty());
+	module.call::<_, ()>("b", EmptyParams::new()).await.unwrap();
+	assert_eq!(methods.call_counters(), HashMap::from([("b", 1)]));
+}
+
+#[tokio::test]
+async fn subscription_sink_reports_the_requested_version() {
+	let mut module = RpcModule::new(());
+	module
+		.register_subscription("sub_version", "s_version", "unsub_version", |_, mut sink, _| {
+			sink.send(&sink.version())?;
+			Ok(())
+		})
+		.unwrap();
+
+	let cases = [
+		(r#"{"version":2}"#, serde_json::json!(2)),
+		(r#"{"version":2,"other":true}"#, serde_json::json!(2)),
+		(r#"{"version":"2"}"#, serde_json::Value::Null),
+		(r#"{}"#, serde_json::Value::Null),
+		(r#"[2]"#, serde_json::Value::Null),
+	];
+
+	for (params, expected) in cases {
+		let req = format!(r#"{{"jsonrpc":"2.0","method":"sub_version","params":{},"id":0}}"#, params);
+		let (_, mut rx) = module.raw_json_request(&req).await.unwrap();
+		let notif: serde_json::Value = serde_json::from_str(&rx.next().await.unwrap()).unwrap();
+		assert_eq!(notif["params"]["result"], expected, "params: {}", params);
+	}
+
+	let mut sub = module.subscribe("sub_version", EmptyParams::new()).await.unwrap();
+	assert_eq!(sub.next::<Option<u32>>().await.into_item().unwrap().0, None);
+}
+
+#[tokio::test]
+async fn subscription_item_shaped_like_close_reason_is_not_a_close() {
+	#[derive(Serialize, Deserialize, Debug, PartialEq)]
+	struct Item {
+		reason: SubscriptionClosedReason,
+	}
+
+	let mut module = RpcModule::new(());
+	module
+		.register_subscription("my_sub", "my_sub", "my_unsub", |_, mut sink, _| {
+			sink.send(&Item { reason: SubscriptionClosedReason::Unsubscribed }).unwrap();
+			sink.close(&SubscriptionClosed::new(SubscriptionClosedReason::Server("done".into())));
+			Ok(())
+		})
+		.unwrap();
+
+	let mut sub = module.subscribe("my_sub", EmptyParams::new()).await.unwrap();
+	let (item, _) = sub.next::<Item>().await.into_item().unwrap();
+	assert_eq!(item, Item { reason: SubscriptionClosedReason::Unsubscribed });
+
+	match sub.next::<Item>().await {
+		SubNext::Closed(close) => {
+			assert_eq!(close.close_reason(), &SubscriptionClosedReason::Server("done".into()))
+		}
+		other => panic!("Expected the subscription to be closed, got: {:?}", other),
+	}
+}
+
+#[tokio::test]
+async fn idle_subscription_is_closed() {
+	use std::time::Duration;
+
+	let mut module = RpcModule::new(());
+	module
+		.register_subscription_with_idle_timeout(
+			"my_sub",
+			"my_sub",
+			"my_unsub",
+			Duration::from_millis(200),
+			|params, mut sink, _| {
+				let count: usize = params.one()?;
+				tokio::spawn(async move {
+					for i in 0..count {
+						tokio::time::sleep(Duration::from_millis(50)).await;
+						sink.send(&i).unwrap();
+					}
+					// Keep the sink alive without sending anything.
+					futures::future::pending::<()>().await;
+				});
+				Ok(())
+			},
+		)
+		.unwrap();
+
+	// Notifications sent more often than the timeout keep the subscription alive.
+	let mut sub = module.subscribe("my_sub", [6]).await.unwrap();
+	for i in 0..6 {
+		let (val, _) = sub.next::<usize>().await.into_item().unwrap();
+		assert_eq!(val, i);
+	}
+
+	// Decoded as a `JsonValue`, the close is only told apart from a notification by its discriminator.
+	match sub.next::<serde_json::Value>().await {
+		SubNext::Closed(close) => {
+			assert_eq!(close.close_reason(), &SubscriptionClosedReason::Server("idle timeout".into()))
+		}
+		other => panic!("Expected the subscription to be closed, got: {:?}", other),
+	}
+}
+
+#[tokio::test]
+async fn health_method_reports_status() {
+	let mut module = RpcModule::new(0_usize);
+	module
+		.register_health("system_health", |peers| HealthStatus::new(*peers > 0).with_detail("peers", *peers))
+		.unwrap();
+	module
+		.register_health("fixed_health", |_| HealthStatus { uptime_secs: Some(42), ..HealthStatus::new(true) })
+		.unwrap();
+
+	let (resp, _) = module.raw_json_request(r#"{"jsonrpc":"2.0","method":"system_health","id":0}"#).await.unwrap();
+	assert_eq!(resp, r#"{"jsonrpc":"2.0","result":{"healthy":false,"uptime_secs":0,"peers":0},"id":0}"#);
+
+	let health: HealthStatus = module.call("fixed_health", EmptyParams::new()).await.unwrap();
+	assert_eq!(health, HealthStatus { uptime_secs: Some(42), ..HealthStatus::new(true) });
+}
+
+#[tokio::test]
+async fn deadline_is_available_to_async_methods() {
+	use jsonrpsee::core::server::deadline::Deadline;
+
+	let mut module = RpcModule::new(());
+	module
+		.register_async_method("remaining_ms", |_, _| async move {
+			Ok(Deadline::current().map(|deadline| deadline.remaining().as_millis() as u64))
+		})
+		.unwrap();
+
+	let (resp, _) = module
+		.raw_json_request(r#"{"jsonrpc":"2.0","method":"remaining_ms","id":0,"deadline_ms":5000}"#)
+		.await
+		.unwrap();
+	let remaining: Option<u64> = serde_json::from_str::<jsonrpsee::types::Response<_>>(&resp).unwrap().result;
+	assert!(matches!(remaining, Some(ms) if ms > 0 && ms <= 5000));
+
+	let remaining: Option<u64> = module.call("remaining_ms", EmptyParams::new()).await.unwrap();
+	assert_eq!(remaining, None);
+}
+
+fn expect_u64(result: &serde_json::Value) -> Result<(), String> {
+	match result.is_u64() {
+		true => Ok(()),
+		false => Err(format!("Expected an integer, got: {}", result)),
+	}
+}
+
+#[tokio::test]
+async fn response_check_accepts_valid_responses() {
+	let mut module = RpcModule::new(());
+	module.register_method("number", |_, _| Ok(42_u64)).unwrap();
+	module.register_async_method("async_number", |_, _| async move { Ok(42_u64) }).unwrap();
+	module.register_response_check("number", expect_u64).unwrap();
+	module.register_response_check("async_number", expect_u64).unwrap();
+
+	assert_eq!(module.call::<_, u64>("number", EmptyParams::new()).await.unwrap(), 42);
+	assert_eq!(module.call::<_, u64>("async_number", EmptyParams::new()).await.unwrap(), 42);
+	assert!(matches!(module.register_response_check("unknown", expect_u64), Err(Error::MethodNotFound(_))));
+}
+
+#[cfg(debug_assertions)]
+#[tokio::test]
+#[should_panic(expected = "failed the check: Expected an integer")]
+async fn response_check_panics_on_invalid_response() {
+	let mut module = RpcModule::new(());
+	module.register_async_method("not_a_number", |_, _| async move { Ok("42") }).unwrap();
+	module.register_response_check("not_a_number", expect_u64).unwrap();
+
+	let _ = module.call::<_, String>("not_a_number", EmptyParams::new()).await;
+}
+
+#[test]
+fn resource_map_reports_claimed_units() {
+	use jsonrpsee::core::server::resource_limiting::Resources;
+
+	let mut resources = Resources::default();
+	resources.register("cpu", 8, 1).unwrap();
+	resources.register("io", 4, 0).unwrap();
+
+	let mut module = RpcModule::new(());
+	module.register_method("light", |_, _| Ok(())).unwrap();
+	module.register_method("heavy", |_, _| Ok(())).unwrap().resource("cpu", 4).unwrap().resource("io", 2).unwrap();
+	module.register_method("unknown_label", |_, _| Ok(())).unwrap().resource("gpu", 1).unwrap();
+
+	assert_eq!(
+		module.resource_map_json(&resources),
+		serde_json::json!({
+			"capacities": { "cpu": 8, "io": 4 },
+			"methods": {
+				"light": { "cpu": 1, "io": 0 },
+				"heavy": { "cpu": 4, "io": 2 },
+				"unknown_label": { "error": "Resource name `gpu` not found for method `unknown_label`" },
+			}
+		})
+	);
+}
+
+#[tokio::test]
+async fn slow_subscriber_is_closed() {
+	use std::sync::{Arc, Mutex};
+	use tokio::sync::{oneshot, Notify};
+
+	let (results_tx, results_rx) = oneshot::channel();
+	let ctx = Arc::new((Notify::new(), Mutex::new(Some(results_tx))));
+
+	let mut module = RpcModule::new(ctx.clone());
+	module
+		.register_subscription_with_queue_limit("my_sub", "my_sub", "my_unsub", 2, |_, mut sink, ctx| {
+			tokio::spawn(async move {
+				let mut results = vec![sink.send(&1).is_ok(), sink.send(&2).is_ok()];
+				// Wait for the client to consume the notifications.
+				ctx.0.notified().await;
+				for i in 3..6 {
+					results.push(sink.send(&i).is_ok());
+				}
+				let _ = ctx.1.lock().unwrap().take().unwrap().send(results);
+			});
+			Ok(())
+		})
+		.unwrap();
+
+	let mut sub = module.subscribe("my_sub", EmptyParams::new()).await.unwrap();
+	assert_eq!(sub.next::<u32>().await.into_item().unwrap().0, 1);
+	assert_eq!(sub.next::<u32>().await.into_item().unwrap().0, 2);
+	ctx.0.notify_one();
+
+	// Only two notifications can wait to be delivered.
+	assert_eq!(results_rx.await.unwrap(), vec![true, true, true, true, false]);
+	assert_eq!(sub.next::<u32>().await.into_item().unwrap().0, 3);
+	assert_eq!(sub.next::<u32>().await.into_item().unwrap().0, 4);
+	match sub.next::<u32>().await {
+		SubNext::Closed(close) => {
+			assert_eq!(close.close_reason(), &SubscriptionClosedReason::Server("slow consumer".into()))
+		}
+		other => panic!("Expected the subscription to be closed, got: {:?}", other),
+	}
+}
+
+#[tokio::test]
+async fn lagging_subscriber_gets_the_newest_notifications_with_drop_oldest() {
+	use std::sync::{Arc, Mutex};
+	use tokio::sync::oneshot;
+
+	let (results_tx, results_rx) = oneshot::channel();
+	let ctx = Arc::new(Mutex::new(Some(results_tx)));
+
+	let mut module = RpcModule::new(ctx);
+	module
+		.register_subscription_with_buffer(
+			"my_sub",
+			"my_sub",
+			"my_unsub",
+			2,
+			LagPolicy::DropOldest,
+			|_, mut sink, ctx| {
+				let results: Vec<_> = (1..7).map(|i| sink.try_send(&i).unwrap()).collect();
+				let _ = ctx.lock().unwrap().take().unwrap().send(results);
+				// Keep the subscription open until the client read the notifications.
+				tokio::spawn(async move {
+					tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+					drop(sink);
+				});
+				Ok(())
+			},
+		)
+		.unwrap();
+
+	let mut sub = module.subscribe("my_sub", EmptyParams::new()).await.unwrap();
+
+	// One notification is handed to the transport and one is parked, which is replaced by each newer one.
+	assert_eq!(
+		results_rx.await.unwrap(),
+		vec![
+			SendOutcome::Sent,
+			SendOutcome::Sent,
+			SendOutcome::DroppedOldest,
+			SendOutcome::DroppedOldest,
+			SendOutcome::DroppedOldest,
+			SendOutcome::DroppedOldest
+		]
+	);
+	for expected in [1, 6] {
+		assert_eq!(sub.next::<u32>().await.into_item().unwrap().0, expected);
+	}
+}
+
+#[tokio::test]
+async fn lagging_subscriber_misses_the_newest_notifications_with_drop_newest() {
+	let mut module = RpcModule::new(());
+	module
+		.register_subscription_with_buffer(
+			"my_sub",
+			"my_sub",
+			"my_unsub",
+			2,
+			LagPolicy::DropNewest,
+			|_, mut sink, _| {
+				let outcomes: Vec<_> = (1..5).map(|i| sink.try_send(&i).unwrap()).collect();
+				assert_eq!(
+					outcomes,
+					vec![SendOutcome::Sent, SendOutcome::Sent, SendOutcome::Dropped, SendOutcome::Dropped]
+				);
+				// The subscription stays open.
+				sink.send(&5)?;
+				Ok(())
+			},
+		)
+		.unwrap();
+
+	let mut sub = module.subscribe("my_sub", EmptyParams::new()).await.unwrap();
+	assert_eq!(sub.next::<u32>().await.into_item().unwrap().0, 1);
+	assert_eq!(sub.next::<u32>().await.into_item().unwrap().0, 2);
+	assert!(matches!(sub.next::<u32>().await, SubNext::Closed(_)));
+}
+
+#[test]
+fn subscription_info_is_kept_when_merged() {
+	let mut module = RpcModule::new(());
+	module.register_subscription("sub_foo", "foo", "unsub_foo", |_, _, _| Ok(())).unwrap();
+	module.register_method("bar", |_, _| Ok(())).unwrap();
+
+	let mut other = RpcModule::new(());
+	other.register_subscription("sub_baz", "baz", "unsub_baz", |_, _, _| Ok(())).unwrap();
+	module.merge(other).unwrap();
+
+	assert_eq!(
+		module.subscription_info("sub_foo"),
+		Some(SubscriptionTriple { subscribe: "sub_foo", notification: "foo", unsubscribe: "unsub_foo" })
+	);
+	assert_eq!(module.subscription_info("sub_baz").map(|info| info.notification), Some("baz"));
+	assert_eq!(module.subscription_info("unsub_foo"), None);
+	assert_eq!(module.subscription_info("bar"), None);
+}
+
+#[tokio::test]
+async fn calls_with_scratch_are_independent() {
+	use std::fmt::Write;
+
+	let mut module = RpcModule::new(());
+	module.register_method("add", |params, _| Ok(params.parse::<Vec<u64>>()?.iter().sum::<u64>())).unwrap();
+	module.register_method("no_params", |params, _| Ok(params.is_object())).unwrap();
+
+	let mut scratch = CallScratch::new();
+	for n in 0..10_u64 {
+		scratch.reset();
+		write!(scratch.params_mut(), "[{}, {}]", n, n).unwrap();
+		let resp = module.call_with_scratch("add", &mut scratch).await.unwrap();
+		assert_eq!(resp, format!(r#"{{"jsonrpc":"2.0","result":{},"id":0}}"#, 2 * n));
+	}
+
+	scratch.reset();
+	let resp = module.call_with_scratch("no_params", &mut scratch).await.unwrap();
+	assert_eq!(resp, r#"{"jsonrpc":"2.0","result":false,"id":0}"#);
+
+	scratch.params_mut().push_str("[1,");
+	assert!(matches!(module.call_with_scratch("add", &mut scratch).await, Err(Error::ParseError(_))));
+}
+
+#[tokio::test]
+async fn call_value_returns_result_or_error_object() {
+	use jsonrpsee::types::error::CallError;
+	use serde_json::json;
+
+	let mut module = RpcModule::new(());
+	module.register_method("pair", |_, _| Ok(("a", 1))).unwrap();
+	module
+		.register_method::<(), _>("fail", |_, _| {
+			Err(CallError::Custom {
+				code: 1234,
+				message: "boom".into(),
+				data: serde_json::value::to_raw_value(&7).ok(),
+			}
+			.into())
+		})
+		.unwrap();
+
+	assert_eq!(module.call_value("pair", EmptyParams::new()).await.unwrap(), json!(["a", 1]));
+
+	match module.call_value("fail", EmptyParams::new()).await {
+		Err(Error::Call(CallError::Custom { code, message, data })) => {
+			assert_eq!(code, 1234);
+			assert_eq!(message, "boom");
+			assert_eq!(data.unwrap().get(), "7");
+		}
+		other => panic!("Unexpected result: {:?}", other),
+	}
+
+	match module.call_value("unknown", EmptyParams::new()).await {
+		Err(Error::Call(CallError::Custom { code, .. })) => assert_eq!(code, -32601),
+		other => panic!("Unexpected result: {:?}", other),
+	}
+}
+
+#[tokio::test]
+async fn deprecated_methods_keep_working() {
+	let mut module = RpcModule::new(());
+	module.register_method("old_hello", |_, _| Ok("lo")).unwrap();
+	module.register_alias("older_hello", "old_hello").unwrap();
+	assert!(module.method("old_hello").unwrap().deprecation().is_none());
+
+	module.deprecate_method("old_hello", "Use `say_hello` instead").unwrap();
+	assert_eq!(module.method("old_hello").unwrap().deprecation(), Some("Use `say_hello` instead"));
+	assert_eq!(module.method("older_hello").unwrap().deprecation(), Some("Use `say_hello` instead"));
+	assert!(module.deprecate_method("old_hello", "Really, stop").is_err());
+	assert!(matches!(module.deprecate_method("unknown", "Gone"), Err(Error::MethodNotFound(_))));
+
+	// Warnings are only attached by servers enabling them.
+	let (resp, _) = module.raw_json_request(r#"{"jsonrpc":"2.0","method":"old_hello","id":0}"#).await.unwrap();
+	assert_eq!(resp, r#"{"jsonrpc":"2.0","result":"lo","id":0}"#);
+}
+
+#[tokio::test]
+async fn call_bytes_hands_over_responses_and_notifications() {
+	use futures::StreamExt;
+
+	let mut module = RpcModule::new(());
+	module.register_method("echo", |params, _| params.one::<String>().map_err(Into::into)).unwrap();
+	module
+		.register_subscription("hi", "hi", "goodbye", |_, mut sink, _| {
+			sink.send(&"one answer").unwrap();
+			Ok(())
+		})
+		.unwrap();
+
+	let (resp, _) = module.call_bytes(br#"{"jsonrpc":"2.0","method":"echo","params":["hey"],"id":1}"#).await.unwrap();
+	assert_eq!(&resp[..], br#"{"jsonrpc":"2.0","result":"hey","id":1}"#);
+
+	let (resp, mut notifs) = module.call_bytes(br#"{"jsonrpc":"2.0","method":"hi","id":2}"#).await.unwrap();
+	let sub_id = serde_json::from_slice::<jsonrpsee::types::Response<u64>>(&resp).unwrap().result;
+	let notif = notifs.next().await.unwrap();
+	assert_eq!(
+		notif,
+		format!(r#"{{"jsonrpc":"2.0","method":"hi","params":{{"subscription":{},"result":"one answer"}}}}"#, sub_id)
+	);
+
+	assert!(matches!(module.call_bytes(b"{").await, Err(Error::ParseError(_))));
+}
+
+#[tokio::test]
+async fn guard_rejects_calls_before_the_callback() {
+	use jsonrpsee::types::error::{ErrorCode, ErrorObject};
+	use std::sync::atomic::{AtomicUsize, Ordering};
+	use std::sync::Arc;
+
+	fn bounded_range(params: &Params) -> Result<(), ErrorObject<'static>> {
+		match params.parse::<(u64, u64)>() {
+			Ok((from, to)) if to.saturating_sub(from) > 10 => {
+				Err(ErrorObject { code: ErrorCode::InvalidParams, message: "Range too large".into(), data: None })
+			}
+			_ => Ok(()),
+		}
+	}
+
+	let calls = Arc::new(AtomicUsize::new(0));
+	let mut module = RpcModule::new(calls.clone());
+	module
+		.register_method("range", |params, calls| {
+			calls.fetch_add(1, Ordering::SeqCst);
+			let (from, to): (u64, u64) = params.parse()?;
+			Ok(to - from)
+		})
+		.unwrap()
+		.guard(bounded_range);
+	module
+		.register_async_method("async_range", |params, calls| async move {
+			calls.fetch_add(1, Ordering::SeqCst);
+			let (from, to): (u64, u64) = params.parse()?;
+			Ok(to - from)
+		})
+		.unwrap()
+		.guard(bounded_range);
+
+	assert_eq!(module.call::<_, u64>("range", [1_u64, 5]).await.unwrap(), 4);
+	assert_eq!(module.call::<_, u64>("async_range", [1_u64, 5]).await.unwrap(), 4);
+	assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+	for method in ["range", "async_range"] {
+		let (resp, _) = module
+			.raw_json_request(&format!(r#"{{"jsonrpc":"2.0","method":"{}","params":[0,100],"id":0}}"#, method))
+			.await
+			.unwrap();
+		assert_eq!(resp, r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"Range too large"},"id":0}"#);
+	}
+	assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn rate_limit_rejects_calls_with_retry_after() {
+	use jsonrpsee::core::server::rate_limiting::RateLimit;
+
+	let mut module = RpcModule::new(());
+	module.register_method("drip", |_, _| Ok(true)).unwrap().rate_limit(RateLimit::per_second(1).burst(2));
+	module
+		.register_async_method("async_drip", |_, _| async move { Ok(true) })
+		.unwrap()
+		.rate_limit(RateLimit::per_second(1).burst(2));
+
+	for method in ["drip", "async_drip"] {
+		assert!(module.call::<_, bool>(method, EmptyParams::new()).await.unwrap());
+		assert!(module.call::<_, bool>(method, EmptyParams::new()).await.unwrap());
+
+		let (resp, _) =
+			module.raw_json_request(&format!(r#"{{"jsonrpc":"2.0","method":"{}","id":0}}"#, method)).await.unwrap();
+		let resp: serde_json::Value = serde_json::from_str(&resp).unwrap();
+		assert_eq!(resp["error"]["code"], -32009);
+		assert_eq!(resp["error"]["message"], "Rate limit exceeded, try again later");
+		let retry_after_ms = resp["error"]["data"]["retry_after_ms"].as_u64().unwrap();
+		assert!(retry_after_ms > 0 && retry_after_ms <= 1000);
+	}
+}
+
+#[tokio::test]
+async fn quiesce_waits_for_pending_calls() {
+	use std::sync::Arc;
+	use std::time::Duration;
+	use tokio::sync::Semaphore;
+
+	let release = Arc::new(Semaphore::new(0));
+	let mut module = RpcModule::new(release.clone());
+	module
+		.register_async_method("slow", |_, release| async move {
+			release.acquire().await.unwrap().forget();
+			Ok(true)
+		})
+		.unwrap();
+	let methods: Methods = module.into();
+	assert!(methods.quiesce(Duration::from_millis(10)).await);
+
+	let call = tokio::spawn({
+		let methods = methods.clone();
+		async move { methods.call::<_, bool>("slow", EmptyParams::new()).await }
+	});
+	while methods.pending_calls() == 0 {
+		tokio::task::yield_now().await;
+	}
+	assert!(!methods.quiesce(Duration::from_millis(50)).await);
+
+	let quiesce = tokio::spawn(methods.quiesce(Duration::from_secs(5)));
+	release.add_permits(1);
+	assert!(quiesce.await.unwrap());
+	assert!(call.await.unwrap().unwrap());
+	assert_eq!(methods.pending_calls(), 0);
+}
+
+#[tokio::test]
+async fn moved_method_names_the_new_one() {
+	let mut module = RpcModule::new(());
+	module.register_method("chain_getBlock", |_, _| Ok(1_u64)).unwrap();
+	module.register_moved("getBlock", "chain_getBlock").unwrap();
+	assert!(matches!(module.register_moved("chain_getBlock", "other"), Err(Error::MethodAlreadyRegistered(_))));
+
+	let (resp, _) = module.raw_json_request(r#"{"jsonrpc":"2.0","method":"getBlock","id":1}"#).await.unwrap();
+	assert_eq!(
+		resp,
+		r#"{"jsonrpc":"2.0","error":{"code":-32004,"message":"Method moved","data":{"moved_to":"chain_getBlock"}},"id":1}"#
+	);
+}
+
+#[tokio::test]
+async fn recording_methods_records_downstream_dispatches() {
+	use jsonrpsee::core::server::recording::RecordingMethods;
+	use serde_json::json;
+
+	let mut downstream = RpcModule::new(());
+	downstream.register_method("double", |params, _| Ok(params.one::<u64>()? * 2)).unwrap();
+	let downstream = RecordingMethods::new(downstream);
+
+	let mut upstream = RpcModule::new(downstream.clone());
+	upstream
+		.register_async_method("quadruple", |params, downstream| async move {
+			let n: u64 = params.one()?;
+			let n: u64 = downstream.call("double", [n]).await?;
+			downstream.call::<_, u64>("double", [n]).await
+		})
+		.unwrap();
+	let upstream = RecordingMethods::new(upstream);
+
+	assert_eq!(upstream.call::<_, u64>("quadruple", [3_u64]).await.unwrap(), 12);
+
+	let records = downstream.take_records();
+	assert_eq!(downstream.records(), vec![]);
+	assert_eq!(records.iter().map(|r| r.params.clone()).collect::<Vec<_>>(), vec![json!([3]), json!([6])]);
+	assert_eq!(records[1].response, json!({"jsonrpc": "2.0", "result": 12, "id": 0}));
+	assert!(records.iter().all(|r| r.method == "double"));
+
+	let (_, _) = upstream.raw_json_request(r#"{"jsonrpc":"2.0","method":"unknown","id":1}"#).await.unwrap();
+	assert_eq!(upstream.method_names(), vec!["quadruple", "unknown"]);
+	assert_eq!(upstream.records()[1].params, json!(null));
+	assert_eq!(upstream.records()[1].response["error"]["code"], json!(-32601));
+}
+
+#[tokio::test]
+async fn subscription_panics_can_be_caught() {
+	use futures::StreamExt;
+
+	let mut module = RpcModule::new(());
+	module.register_method("hello", |_, _| Ok("lo")).unwrap();
+	module
+		.register_subscription("sub_panics", "n", "unsub_panics", |_, _sink, _| -> Result<(), Error> {
+			panic!("Buggy subscription")
+		})
+		.unwrap();
+	module.catch_subscription_panics("sub_panics").unwrap();
+	assert!(matches!(module.catch_subscription_panics("hello"), Err(Error::MethodNotFound(_))));
+
+	let (resp, mut rx) = module.raw_json_request(r#"{"jsonrpc":"2.0","method":"sub_panics","id":1}"#).await.unwrap();
+	let sub_id = serde_json::from_str::<jsonrpsee::types::Response<u64>>(&resp).unwrap().result;
+
+	// Answered with the subscription ID already, the subscription is only closed.
+	let closed = rx.next().await.unwrap();
+	assert!(closed.contains(&format!(r#""subscription":{}"#, sub_id)), "{}", closed);
+	assert!(rx.try_recv().is_err());
+
+	let (resp, _) = module
+		.raw_json_request(&format!(r#"{{"jsonrpc":"2.0","method":"unsub_panics","params":[{}],"id":2}}"#, sub_id))
+		.await
+		.unwrap();
+	assert_eq!(resp, r#"{"jsonrpc":"2.0","result":false,"id":2}"#);
+}
+
+#[tokio::test]
+async fn heterogeneous_tuple_params_round_trip() {
+	use jsonrpsee::core::traits::ToRpcParams;
+
+	assert_eq!((1_u64, "x", true).to_rpc_params().unwrap().get(), r#"[1,"x",true]"#);
+
+	let mut module = RpcModule::new(());
+	module
+		.register_method("echo_tuple", |params, _| {
+			let (n, s, b): (u64, String, bool) = params.parse()?;
+			Ok((n, s, b))
+		})
+		.unwrap();
+
+	let echo: (u64, String, bool) = module.call("echo_tuple", (1_u64, "x", true)).await.unwrap();
+	assert_eq!(echo, (1, "x".to_owned(), true));
+}
+
+#[tokio::test]
+async fn disabled_methods_are_kept_registered() {
+	let mut module = RpcModule::new(());
+	module.register_method("hello", |_, _| Ok("lo")).unwrap();
+	module.register_alias("hi", "hello").unwrap();
+	let methods: Methods = module.into();
+	// Stands for the methods started by a server.
+	let started = methods.clone();
+
+	methods.set_enabled("hello", false).unwrap();
+	assert!(!started.method("hello").unwrap().is_enabled());
+	let disabled = r#"{"jsonrpc":"2.0","error":{"code":-32005,"message":"Method temporarily unavailable"},"id":0}"#;
+	for method in ["hello", "hi"] {
+		let (resp, _) =
+			started.raw_json_request(&format!(r#"{{"jsonrpc":"2.0","method":"{}","id":0}}"#, method)).await.unwrap();
+		assert_eq!(resp, disabled);
+	}
+
+	methods.set_enabled("hello", true).unwrap();
+	assert_eq!(started.call::<_, String>("hello", EmptyParams::new()).await.unwrap(), "lo");
+	assert!(matches!(methods.set_enabled("unknown", false), Err(Error::MethodNotFound(_))));
+}
+
+#[tokio::test]
+async fn call_described_returns_the_method_kind() {
+	let mut module = RpcModule::new(());
+	module.register_method("sync_hello", |_, _| Ok("lo")).unwrap();
+	module.register_async_method("async_hello", |_, _| async move { Ok("lo") }).unwrap();
+	module
+		.register_subscription("subscribe_hello", "hello", "unsubscribe_hello", |_, mut sink, _| {
+			sink.send(&"lo").unwrap();
+			Ok(())
+		})
+		.unwrap();
+
+	let (res, kind) = module.call_described::<_, String>("sync_hello", EmptyParams::new()).await.unwrap();
+	assert_eq!((res.as_str(), kind), ("lo", MethodKindTag::Sync));
+	let (res, kind) = module.call_described::<_, String>("async_hello", EmptyParams::new()).await.unwrap();
+	assert_eq!((res.as_str(), kind), ("lo", MethodKindTag::Async));
+	let (_, kind) = module.call_described::<_, serde_json::Value>("subscribe_hello", EmptyParams::new()).await.unwrap();
+	assert_eq!(kind, MethodKindTag::Subscription);
+	assert_eq!(module.method_kind("async_hello"), Some(MethodKindTag::Async));
+
+	assert!(matches!(module.call_described::<_, String>("unknown", EmptyParams::new()).await, Err(Error::Request(_))));
+}
+
+#[tokio::test]
+async fn methods_are_registered_from_specs_all_or_nothing() {
+	let mut module = RpcModule::new(5_u64);
+	module.register_method("existing", |_, _| Ok(())).unwrap();
+
+	let mut add = MethodSpec::new("add", MethodKindTag::Sync);
+	add.handler = Some(MethodSpecHandler::Sync(Box::new(|params, ctx| Ok((params.one::<u64>()? + ctx).into()))));
+	let mut double = MethodSpec::new("double", MethodKindTag::Async);
+	double.description = Some("Doubles the context");
+	double.resources.push(("cpu", 2));
+	double.handler = Some(MethodSpecHandler::Async(Box::new(|_, ctx: std::sync::Arc<u64>| {
+		async move { Ok((*ctx * 2).into()) }.boxed()
+	})));
+	let planned = MethodSpec::new("planned", MethodKindTag::Async);
+
+	// Invalid specs: a duplicate, an already registered method, a subscription and a mismatched handler.
+	let mut mismatched = MethodSpec::new("mismatched", MethodKindTag::Sync);
+	mismatched.handler = Some(MethodSpecHandler::Async(Box::new(|_, _| async { Ok(().into()) }.boxed())));
+	let invalid = vec![
+		MethodSpec::new("fine", MethodKindTag::Sync),
+		MethodSpec::new("fine", MethodKindTag::Sync),
+		MethodSpec::new("existing", MethodKindTag::Sync),
+		MethodSpec::new("sub", MethodKindTag::Subscription),
+		mismatched,
+	];
+	match module.register_all(invalid) {
+		Err(Error::InvalidModule(errors)) => assert_eq!(errors.len(), 4),
+		other => panic!("Expected `Error::InvalidModule`, got: {:?}", other),
+	}
+	assert!(module.method("fine").is_none());
+
+	module.register_all(vec![add, double, planned]).unwrap();
+	assert_eq!(module.call::<_, u64>("add", [1_u64]).await.unwrap(), 6);
+	assert_eq!(module.call::<_, u64>("double", EmptyParams::new()).await.unwrap(), 10);
+	assert_eq!(module.method("double").unwrap().description(), Some("Doubles the context"));
+	assert_eq!(module.method_kind("planned"), Some(MethodKindTag::Async));
+	let (resp, _) = module.raw_json_request(r#"{"jsonrpc":"2.0","method":"planned","id":0}"#).await.unwrap();
+	assert_eq!(
+		resp,
+		r#"{"jsonrpc":"2.0","error":{"code":-32000,"message":"Method `planned` is not implemented"},"id":0}"#
+	);
+}
+
+#[tokio::test]
+async fn methods_can_be_enabled_by_a_watched_value() {
+	let (tx, rx) = tokio::sync::watch::channel(vec!["hello"]);
+	let mut module = RpcModule::new(());
+	module.register_method("hello", |_, _| Ok("lo")).unwrap();
+	module.register_alias("hi", "hello").unwrap();
+	module.register_method("other", |_, _| Ok(())).unwrap();
+	module.bind_enabled("hello", rx.clone(), |enabled| enabled.contains(&"hello")).unwrap();
+	let methods: Methods = module.into();
+
+	assert_eq!(methods.call::<_, String>("hi", EmptyParams::new()).await.unwrap(), "lo");
+
+	tx.send(vec![]).unwrap();
+	let disabled = r#"{"jsonrpc":"2.0","error":{"code":-32005,"message":"Method temporarily unavailable"},"id":0}"#;
+	for method in ["hello", "hi"] {
+		let (resp, _) =
+			methods.raw_json_request(&format!(r#"{{"jsonrpc":"2.0","method":"{}","id":0}}"#, method)).await.unwrap();
+		assert_eq!(resp, disabled);
+	}
+	assert!(methods.method("other").unwrap().is_enabled());
+	assert!(matches!(methods.set_enabled("hello", true), Err(Error::Custom(_))));
+
+	tx.send(vec!["hello"]).unwrap();
+	assert_eq!(methods.call::<_, String>("hello", EmptyParams::new()).await.unwrap(), "lo");
+}
+
+#[tokio::test]
+async fn subscription_errors_keep_the_subscription_open() {
+	use jsonrpsee::core::client::{Subscription, SubscriptionClientT};
+	use jsonrpsee::core::server::in_memory::InMemoryClientBuilder;
+
+	let mut module = RpcModule::new(());
+	module
+		.register_subscription("subscribe_blocks", "block", "unsubscribe_blocks", |_, mut sink, _| {
+			sink.send(&1).unwrap();
+			sink.send_error(&serde_json::json!({ "message": "Skipped block 2" })).unwrap();
+			sink.send(&3).unwrap();
+			Ok(())
+		})
+		.unwrap();
+
+	let mut sub = module.subscribe("subscribe_blocks", EmptyParams::new()).await.unwrap();
+	assert_eq!(sub.next::<u64>().await.into_item().unwrap().0, 1);
+	match sub.next::<u64>().await {
+		SubNext::Error(err) => assert_eq!(err, serde_json::json!({ "message": "Skipped block 2" })),
+		other => panic!("Expected an error notification, got: {:?}", other),
+	}
+	assert_eq!(sub.next::<u64>().await.into_item().unwrap().0, 3);
+
+	// The clients yield the error and keep receiving the notifications.
+	let client = InMemoryClientBuilder::default().build(module);
+	let mut sub: Subscription<u64> = client.subscribe("subscribe_blocks", None, "unsubscribe_blocks").await.unwrap();
+	assert_eq!(sub.next().await.unwrap().unwrap(), 1);
+	match sub.next().await.unwrap() {
+		Err(Error::SubscriptionError(err)) => assert_eq!(err, serde_json::json!({ "message": "Skipped block 2" })),
+		other => panic!("Expected a subscription error, got: {:?}", other),
+	}
+	assert_eq!(sub.next().await.unwrap().unwrap(), 3);
+}
+
+#[tokio::test]
+async fn subscription_next_tells_decode_errors_from_closing() {
+	let mut module = RpcModule::new(());
+	module
+		.register_subscription("subscribe_blocks", "block", "unsubscribe_blocks", |_, mut sink, _| {
+			sink.send(&"not a number").unwrap();
+			sink.send(&2).unwrap();
+			Ok(())
+		})
+		.unwrap();
+
+	let mut sub = module.subscribe("subscribe_blocks", EmptyParams::new()).await.unwrap();
+	assert!(matches!(sub.next::<u64>().await, SubNext::DecodeError(_)));
+	assert!(matches!(sub.next::<u64>().await, SubNext::Item(2, _)));
+	// Dropping the sink closes the subscription, after which the channel ends.
+	assert!(matches!(sub.next::<u64>().await, SubNext::Closed(_)));
+	assert!(matches!(sub.next::<u64>().await, SubNext::Ended));
+}
+
+#[tokio::test]
+async fn prepared_notifications_match_the_regular_ones() {
+	let block = serde_json::json!({ "number": 12, "hash": "0x\"quoted\"" });
+	let prepared = PreparedNotification::new("new_heads", &block).unwrap();
+	let mut module = RpcModule::new((block, prepared));
+	module
+		.register_subscription("subscribe_heads", "new_heads", "unsubscribe_heads", |_, mut sink, ctx| {
+			let (block, prepared) = &*ctx;
+			sink.send(block)?;
+			sink.send_prepared(prepared)?;
+			let other = PreparedNotification::new("other", block)?;
+			assert!(matches!(sink.send_prepared(&other), Err(Error::Custom(_))));
+			Ok(())
+		})
+		.unwrap();
+
+	for id in 0..2 {
+		let req = format!(r#"{{"jsonrpc":"2.0","method":"subscribe_heads","id":{}}}"#, id);
+		let (_, mut rx) = module.raw_json_request(&req).await.unwrap();
+		let regular = rx.next().await.unwrap();
+		let prepared = rx.next().await.unwrap();
+		assert_eq!(regular, prepared);
+	}
+}
+
+#[tokio::test]
+async fn raw_notifications_match_the_regular_ones() {
+	let block = serde_json::json!({ "number": 12, "hash": "0x\"quoted\"" });
+	let mut module = RpcModule::new(block);
+	module
+		.register_subscription("subscribe_heads", "new_heads", "unsubscribe_heads", |_, mut sink, block| {
+			sink.send(&*block)?;
+			let raw = serde_json::value::RawValue::from_string(block.to_string())?;
+			sink.send_raw(&raw)
+		})
+		.unwrap();
+
+	let (_, mut rx) = module.raw_json_request(r#"{"jsonrpc":"2.0","method":"subscribe_heads","id":0}"#).await.unwrap();
+	let regular = rx.next().await.unwrap();
+	let raw = rx.next().await.unwrap();
+	assert_eq!(regular, raw);
+}
+
+#[tokio::test]
+async fn fanout_sends_the_items_to_the_matching_subscribers() {
+	let fanout = SubscriptionFanout::<String>::new();
+	let mut module = RpcModule::new(fanout.clone());
+	module
+		.register_subscription("subscribe_topic", "topic", "unsubscribe_topic", |params, sink, fanout| {
+			fanout.add(params.one()?, sink);
+			Ok(())
+		})
+		.unwrap();
+
+	let mut blocks = module.subscribe("subscribe_topic", ["blocks"]).await.unwrap();
+	let mut txs = module.subscribe("subscribe_topic", ["txs"]).await.unwrap();
+	let other_blocks = module.subscribe("subscribe_topic", ["blocks"]).await.unwrap();
+	assert_eq!(fanout.len(), 3);
+
+	assert_eq!(fanout.send_matching(&12, |topic| topic == "blocks").unwrap(), 2);
+	assert_eq!(fanout.send(&13).unwrap(), 3);
+	assert!(matches!(blocks.next::<u64>().await, SubNext::Item(12, _)));
+	assert!(matches!(blocks.next::<u64>().await, SubNext::Item(13, _)));
+	assert!(matches!(txs.next::<u64>().await, SubNext::Item(13, _)));
+
+	drop(other_blocks);
+	assert_eq!(fanout.send(&14).unwrap(), 2);
+	assert_eq!(fanout.len(), 2);
+}
+
+#[tokio::test]
+async fn in_memory_client_is_a_connection_of_its_own() {
+	use jsonrpsee::core::client::{ClientT, Subscription, SubscriptionClientT};
+	use jsonrpsee::core::server::in_memory::InMemoryClientBuilder;
+	use jsonrpsee::core::traits::IdProvider;
+	use jsonrpsee::rpc_params;
+	use jsonrpsee::types::SubscriptionId;
+	use std::sync::atomic::{AtomicU64, Ordering};
+	use std::time::Duration;
+	use tokio_stream::wrappers::IntervalStream;
+
+	#[derive(Debug)]
+	struct Sequential(AtomicU64);
+
+	impl IdProvider for Sequential {
+		fn next_id(&self) -> SubscriptionId<'static> {
+			SubscriptionId::Num(self.0.fetch_add(1, Ordering::Relaxed))
+		}
+	}
+
+	let (tx_closed, mut rx_closed) = futures::channel::mpsc::unbounded::<()>();
+	let mut module = RpcModule::new(tx_closed);
+	module.register_method("add", |params, _| Ok(params.parse::<Vec<u64>>()?.into_iter().sum::<u64>())).unwrap();
+	module
+		.register_subscription("sub_ticks", "tick", "unsub_ticks", |_, sink, closed| {
+			let ticks = IntervalStream::new(tokio::time::interval(Duration::from_millis(10))).map(|_| 1_u32);
+			tokio::spawn(async move {
+				sink.pipe_from_stream(ticks).await.unwrap();
+				closed.unbounded_send(()).unwrap();
+			});
+			Ok(())
+		})
+		.unwrap();
+	let client = InMemoryClientBuilder::default().set_id_provider(Sequential(AtomicU64::new(7))).build(module);
+
+	assert_eq!(client.request::<u64>("add", rpc_params![1, 2]).await.unwrap(), 3);
+	let sums: Vec<u64> = client.batch_request(vec![("add", rpc_params![1]), ("add", rpc_params![2, 3])]).await.unwrap();
+	assert_eq!(sums, vec![1, 5]);
+
+	// The subscription IDs come from the ID provider of the connection, and unsubscribing closes the subscription.
+	let sub_id: u64 = client.request("sub_ticks", None).await.unwrap();
+	assert_eq!(sub_id, 7);
+	assert!(client.request::<bool>("unsub_ticks", rpc_params![sub_id]).await.unwrap());
+	tokio::time::timeout(Duration::from_secs(5), rx_closed.next()).await.unwrap().unwrap();
+
+	// Dropping the client closes the connection and its subscriptions.
+	let mut ticks: Subscription<u32> = client.subscribe("sub_ticks", None, "unsub_ticks").await.unwrap();
+	assert_eq!(ticks.next().await.unwrap().unwrap(), 1);
+	drop(client);
+	tokio::time::timeout(Duration::from_secs(5), rx_closed.next()).await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn retry_policy_retries_the_listed_errors() {
+	use jsonrpsee::core::client::{Backoff, ClientT, Retry, RetryPolicy};
+	use jsonrpsee::core::server::in_memory::InMemoryClientBuilder;
+	use jsonrpsee::rpc_params;
+	use jsonrpsee::types::error::CallError;
+	use std::sync::atomic::{AtomicU32, Ordering};
+	use std::time::Duration;
+
+	// Fails the first calls with the given error code.
+	let mut module = RpcModule::new(AtomicU32::new(0));
+	module
+		.register_method("flaky", |params, calls| {
+			let (failures, code): (u32, i32) = params.parse()?;
+			match calls.fetch_add(1, Ordering::Relaxed) < failures {
+				true => Err(CallError::Custom { code, message: "Flaky".into(), data: None }.into()),
+				false => Ok(calls.swap(0, Ordering::Relaxed)),
+			}
+		})
+		.unwrap();
+	let policy = RetryPolicy::default().backoff(Backoff::Fixed(Duration::from_millis(1))).retry_on_codes([-32000]);
+	let retry = Retry::new(InMemoryClientBuilder::default().build(module.clone()), policy.clone().max_attempts(3));
+
+	assert_eq!(retry.request::<u32>("flaky", rpc_params![2, -32000]).await.unwrap(), 3);
+	// Out of attempts.
+	assert!(retry.request::<u32>("flaky", rpc_params![3, -32000]).await.is_err());
+	// Not retried.
+	let retry = Retry::new(InMemoryClientBuilder::default().build(module), policy);
+	retry.inner().request::<u32>("flaky", rpc_params![0, 0]).await.unwrap();
+	assert!(retry.request::<u32>("flaky", rpc_params![1, -32001]).await.is_err());
+	assert_eq!(retry.request::<u32>("flaky", rpc_params![0, 0]).await.unwrap(), 2);
+}
+
+#[tokio::test]
+async fn retry_policy_retries_unanswered_calls_only_if_enabled() {
+	use jsonrpsee::core::client::{Backoff, ClientT, Retry, RetryPolicy};
+	use jsonrpsee::core::server::in_memory::InMemoryClientBuilder;
+	use std::sync::atomic::{AtomicU32, Ordering};
+	use std::sync::Arc;
+	use std::time::Duration;
+
+	// Counts the calls, which all time out.
+	let calls = Arc::new(AtomicU32::new(0));
+	let mut module = RpcModule::new(calls.clone());
+	module
+		.register_async_method("slow", |_, calls| async move {
+			calls.fetch_add(1, Ordering::Relaxed);
+			tokio::time::sleep(Duration::from_secs(60)).await;
+			Ok(())
+		})
+		.unwrap();
+	let client = || InMemoryClientBuilder::default().request_timeout(Duration::from_millis(50)).build(module.clone());
+	let policy = RetryPolicy::default().backoff(Backoff::Fixed(Duration::from_millis(1))).max_attempts(3);
+
+	let retry = Retry::new(client(), policy.clone());
+	assert!(matches!(retry.request::<()>("slow", None).await, Err(Error::RequestTimeout)));
+	assert_eq!(calls.load(Ordering::Relaxed), 1);
+
+	let retry = Retry::new(client(), policy.retry_unanswered());
+	assert!(matches!(retry.request::<()>("slow", None).await, Err(Error::RequestTimeout)));
+	assert_eq!(calls.load(Ordering::Relaxed), 4);
+}
+
+#[tokio::test]
+async fn multi_client_fails_over_unanswered_calls_only_if_enabled() {
+	use jsonrpsee::core::client::{ClientT, LoadBalancing, MultiClient};
+	use jsonrpsee::core::server::in_memory::InMemoryClientBuilder;
+	use std::time::Duration;
+
+	// The first endpoint doesn't answer in time.
+	let mut slow = RpcModule::new(());
+	slow.register_async_method("whoami", |_, _| async move {
+		tokio::time::sleep(Duration::from_secs(60)).await;
+		Ok("slow")
+	})
+	.unwrap();
+	let mut fast = RpcModule::new(());
+	fast.register_method("whoami", |_, _| Ok("fast")).unwrap();
+	let clients = || {
+		let builder = InMemoryClientBuilder::default().request_timeout(Duration::from_millis(50));
+		vec![builder.clone().build(slow.clone()), builder.build(fast.clone())]
+	};
+
+	let client = MultiClient::new(clients()).balancing(LoadBalancing::Failover);
+	assert!(matches!(client.request::<String>("whoami", None).await, Err(Error::RequestTimeout)));
+	// The endpoint is skipped by the next calls.
+	assert!(!client.is_healthy(0));
+	assert_eq!(client.request::<String>("whoami", None).await.unwrap(), "fast");
+
+	let client = MultiClient::new(clients()).balancing(LoadBalancing::Failover).retry_unanswered();
+	assert_eq!(client.request::<String>("whoami", None).await.unwrap(), "fast");
+	assert!(!client.is_healthy(0));
 }
@@ -0,0 +1,228 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use jsonrpsee::http_server::{HttpServerBuilder, HttpServerHandle, TlsConfig};
+use jsonrpsee::ws_server::WsServerBuilder;
+use jsonrpsee::RpcModule;
+use soketto::handshake::{Client as WsClient, ServerResponse};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio_rustls::client::TlsStream;
+use tokio_rustls::rustls::client::WantsTransparencyPolicyOrClientCert;
+use tokio_rustls::rustls::{Certificate, ClientConfig, ConfigBuilder, PrivateKey, RootCertStore, ServerName};
+use tokio_rustls::TlsConnector;
+use tokio_util::compat::TokioAsyncReadCompatExt;
+
+const CA: &[u8] = include_bytes!("certs/ca.pem");
+const SERVER_CERT: &[u8] = include_bytes!("certs/server.pem");
+const SERVER_KEY: &[u8] = include_bytes!("certs/server.key");
+const CLIENT_CERT: &[u8] = include_bytes!("certs/client.pem");
+const CLIENT_KEY: &[u8] = include_bytes!("certs/client.key");
+
+const REQUEST: &str = r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#;
+const RESPONSE: &str = r#"{"jsonrpc":"2.0","result":"hello","id":1}"#;
+
+fn module() -> RpcModule<()> {
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	module
+}
+
+fn server_config() -> TlsConfig {
+	TlsConfig::from_pem(SERVER_CERT, SERVER_KEY).unwrap()
+}
+
+fn client_config_builder() -> ConfigBuilder<ClientConfig, WantsTransparencyPolicyOrClientCert> {
+	let mut roots = RootCertStore::empty();
+	for der in rustls_pemfile::certs(&mut &*CA).unwrap() {
+		roots.add(&Certificate(der)).unwrap();
+	}
+	ClientConfig::builder().with_safe_defaults().with_root_certificates(roots)
+}
+
+async fn connect(addr: SocketAddr, client_cert: bool) -> std::io::Result<TlsStream<TcpStream>> {
+	let builder = client_config_builder();
+	let config = if client_cert {
+		let certs = rustls_pemfile::certs(&mut &*CLIENT_CERT).unwrap().into_iter().map(Certificate).collect();
+		let key = PrivateKey(rustls_pemfile::pkcs8_private_keys(&mut &*CLIENT_KEY).unwrap().remove(0));
+		builder.with_single_cert(certs, key).unwrap()
+	} else {
+		builder.with_no_client_auth()
+	};
+
+	let socket = TcpStream::connect(addr).await?;
+	let server_name = ServerName::try_from("localhost").unwrap();
+	TlsConnector::from(Arc::new(config)).connect(server_name, socket).await
+}
+
+async fn https_server(config: TlsConfig) -> (SocketAddr, HttpServerHandle) {
+	let server = HttpServerBuilder::default().tls(config).build("127.0.0.1:0").unwrap();
+	let addr = server.local_addr().unwrap();
+	(addr, server.start(module()).unwrap())
+}
+
+/// Send a JSON-RPC request over HTTP/1.1 and return the whole response, headers included.
+async fn https_call(stream: &mut TlsStream<TcpStream>) -> std::io::Result<String> {
+	let request = format!(
+		"POST / HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+		REQUEST.len(),
+		REQUEST
+	);
+	stream.write_all(request.as_bytes()).await?;
+	let mut response = Vec::new();
+	stream.read_to_end(&mut response).await?;
+	Ok(String::from_utf8(response).unwrap())
+}
+
+#[tokio::test]
+async fn https_works() {
+	let (addr, _handle) = https_server(server_config()).await;
+
+	let mut stream = connect(addr, false).await.unwrap();
+	assert_eq!(stream.get_ref().1.alpn_protocol(), None);
+	let response = https_call(&mut stream).await.unwrap();
+	assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+	assert!(response.ends_with(RESPONSE), "{}", response);
+}
+
+#[tokio::test]
+async fn https_ignores_failed_handshakes() {
+	let (addr, _handle) = https_server(server_config()).await;
+
+	// A plain HTTP client fails the handshake, without stopping the server.
+	let mut socket = TcpStream::connect(addr).await.unwrap();
+	socket.write_all(b"POST / HTTP/1.1\r\nHost: localhost\r\n\r\n").await.unwrap();
+	let mut buf = Vec::new();
+	let _ = socket.read_to_end(&mut buf).await;
+	assert!(!String::from_utf8_lossy(&buf).contains("HTTP/1.1"));
+
+	let mut stream = connect(addr, false).await.unwrap();
+	assert!(https_call(&mut stream).await.unwrap().ends_with(RESPONSE));
+}
+
+#[tokio::test]
+async fn https_stalled_handshakes_time_out() {
+	let config = server_config().handshake_timeout(Duration::from_millis(200)).max_pending_handshakes(1);
+	let (addr, _handle) = https_server(config).await;
+
+	// A client which never starts its handshake holds the only handshake slot until it times out.
+	let mut stalled = TcpStream::connect(addr).await.unwrap();
+	let mut buf = Vec::new();
+	let closed = tokio::time::timeout(Duration::from_secs(5), stalled.read_to_end(&mut buf)).await;
+	assert!(matches!(closed, Ok(Ok(0))), "{:?}", closed);
+
+	let mut stream = connect(addr, false).await.unwrap();
+	assert!(https_call(&mut stream).await.unwrap().ends_with(RESPONSE));
+}
+
+#[tokio::test]
+async fn wss_stalled_handshakes_time_out() {
+	let config = server_config().handshake_timeout(Duration::from_millis(200));
+	let server = WsServerBuilder::default().tls(config).build("127.0.0.1:0").await.unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module()).unwrap();
+
+	let mut stalled = TcpStream::connect(addr).await.unwrap();
+	let mut buf = Vec::new();
+	let closed = tokio::time::timeout(Duration::from_secs(5), stalled.read_to_end(&mut buf)).await;
+	assert!(matches!(closed, Ok(Ok(0))), "{:?}", closed);
+}
+
+#[tokio::test]
+async fn wss_server_works() {
+	let server = WsServerBuilder::default().tls(server_config()).build("127.0.0.1:0").await.unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module()).unwrap();
+
+	let stream = connect(addr, false).await.unwrap();
+	let mut client = WsClient::new(stream.compat(), "localhost", "/");
+	match client.handshake().await.unwrap() {
+		ServerResponse::Accepted { .. } => {}
+		other => panic!("WebSocket handshake failed: {:?}", other),
+	}
+	let (mut sender, mut receiver) = client.into_builder().finish();
+	sender.send_text(REQUEST).await.unwrap();
+	sender.flush().await.unwrap();
+	let mut response = Vec::new();
+	receiver.receive_data(&mut response).await.unwrap();
+	assert_eq!(String::from_utf8(response).unwrap(), RESPONSE);
+}
+
+#[tokio::test]
+async fn required_client_certificates_are_verified() {
+	let config = server_config().client_auth_pem(CA, true).unwrap();
+	let (addr, _handle) = https_server(config).await;
+
+	let mut stream = connect(addr, true).await.unwrap();
+	assert!(https_call(&mut stream).await.unwrap().ends_with(RESPONSE));
+
+	// With TLS 1.3 the server may reject the client after the client considers the handshake done.
+	let rejected = match connect(addr, false).await {
+		Ok(mut stream) => https_call(&mut stream).await.map_or(true, |response| response.is_empty()),
+		Err(_) => true,
+	};
+	assert!(rejected);
+}
+
+#[tokio::test]
+async fn custom_alpn_protocols_are_offered() {
+	let config = server_config().alpn_protocols(vec![b"http/1.1".to_vec()]);
+	let (addr, _handle) = https_server(config).await;
+
+	let socket = TcpStream::connect(addr).await.unwrap();
+	let mut config = client_config_builder().with_no_client_auth();
+	config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+	let server_name = ServerName::try_from("localhost").unwrap();
+	let stream = TlsConnector::from(Arc::new(config)).connect(server_name, socket).await.unwrap();
+	assert_eq!(stream.get_ref().1.alpn_protocol(), Some(&b"http/1.1"[..]));
+}
+
+#[tokio::test]
+async fn https_negotiates_http2() {
+	let (addr, _handle) = https_server(server_config()).await;
+
+	let socket = TcpStream::connect(addr).await.unwrap();
+	let mut config = client_config_builder().with_no_client_auth();
+	config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+	let server_name = ServerName::try_from("localhost").unwrap();
+	let stream = TlsConnector::from(Arc::new(config)).connect(server_name, socket).await.unwrap();
+	assert_eq!(stream.get_ref().1.alpn_protocol(), Some(&b"h2"[..]));
+
+	let (mut sender, conn) = hyper::client::conn::Builder::new().http2_only(true).handshake(stream).await.unwrap();
+	tokio::spawn(conn);
+	let request = hyper::Request::post("https://localhost/")
+		.header(hyper::header::CONTENT_TYPE, "application/json")
+		.body(hyper::Body::from(REQUEST))
+		.unwrap();
+	let response = sender.send_request(request).await.unwrap();
+	assert_eq!(response.version(), hyper::Version::HTTP_2);
+	let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+	assert_eq!(body, RESPONSE);
+}
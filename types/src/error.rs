@@ -79,6 +79,49 @@ impl<'a> ErrorObject<'a> {
 	pub fn new(code: ErrorCode, data: Option<&'a RawValue>) -> ErrorObject<'a> {
 		Self { code, message: code.message().into(), data }
 	}
+
+	/// Convert into an [`ErrorObjectOwned`], which doesn't borrow from the deserialized message.
+	pub fn into_owned(self) -> ErrorObjectOwned {
+		ErrorObjectOwned { code: self.code, message: self.message.into_owned(), data: self.data.map(ToOwned::to_owned) }
+	}
+}
+
+/// Owned [`ErrorObject`], such as the error of a call in a batch response.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ErrorObjectOwned {
+	/// Code
+	pub code: ErrorCode,
+	/// Message
+	pub message: String,
+	/// Optional data
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub data: Option<Box<RawValue>>,
+}
+
+impl ErrorObjectOwned {
+	/// Borrow as an [`ErrorObject`].
+	pub fn borrow(&self) -> ErrorObject<'_> {
+		ErrorObject { code: self.code, message: self.message.as_str().into(), data: self.data.as_deref() }
+	}
+}
+
+impl fmt::Display for ErrorObjectOwned {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", serde_json::to_string(&self).expect("infallible; qed"))
+	}
+}
+
+impl PartialEq for ErrorObjectOwned {
+	fn eq(&self, other: &Self) -> bool {
+		self.borrow() == other.borrow()
+	}
+}
+
+impl From<ErrorObjectOwned> for CallError {
+	fn from(err: ErrorObjectOwned) -> Self {
+		CallError::Custom { code: err.code.code(), message: err.message, data: err.data }
+	}
 }
 
 impl<'a> From<ErrorCode> for ErrorObject<'a> {
@@ -87,6 +130,16 @@ impl<'a> From<ErrorCode> for ErrorObject<'a> {
 	}
 }
 
+impl<'a> From<ErrorObject<'a>> for CallError {
+	fn from(err: ErrorObject<'a>) -> Self {
+		CallError::Custom {
+			code: err.code.code(),
+			message: err.message.into_owned(),
+			data: err.data.map(ToOwned::to_owned),
+		}
+	}
+}
+
 impl<'a> PartialEq for ErrorObject<'a> {
 	fn eq(&self, other: &Self) -> bool {
 		let this_raw = self.data.map(|r| r.get());
@@ -117,6 +170,20 @@ pub const CALL_EXECUTION_FAILED_CODE: i32 = -32000;
 pub const UNKNOWN_ERROR_CODE: i32 = -32001;
 /// Invalid subscription error code.
 pub const INVALID_SUBSCRIPTION_CODE: i32 = -32002;
+/// Call cancelled error code.
+pub const CALL_CANCELLED_CODE: i32 = -32003;
+/// Method moved error code.
+pub const METHOD_MOVED_CODE: i32 = -32004;
+/// Method disabled error code.
+pub const METHOD_DISABLED_CODE: i32 = -32005;
+/// Cost budget exceeded error code.
+pub const COST_BUDGET_EXCEEDED_CODE: i32 = -32006;
+/// Too many subscriptions on the connection error code.
+pub const TOO_MANY_SUBSCRIPTIONS_CODE: i32 = -32007;
+/// Request timeout error code.
+pub const REQUEST_TIMEOUT_CODE: i32 = -32008;
+/// Rate limit exceeded error code.
+pub const RATE_LIMIT_EXCEEDED_CODE: i32 = -32009;
 
 /// Parse error message
 pub const PARSE_ERROR_MSG: &str = "Parse error";
@@ -134,6 +201,22 @@ pub const INVALID_REQUEST_MSG: &str = "Invalid request";
 pub const METHOD_NOT_FOUND_MSG: &str = "Method not found";
 /// Server is busy error message.
 pub const SERVER_IS_BUSY_MSG: &str = "Server is busy, try again later";
+/// Call cancelled error message.
+pub const CALL_CANCELLED_MSG: &str = "Call cancelled";
+/// Method moved error message.
+pub const METHOD_MOVED_MSG: &str = "Method moved";
+/// Method disabled error message.
+pub const METHOD_DISABLED_MSG: &str = "Method temporarily unavailable";
+/// Cost budget exceeded error message.
+pub const COST_BUDGET_EXCEEDED_MSG: &str = "Cost budget exceeded, try again later";
+/// Too many subscriptions on the connection error message.
+pub const TOO_MANY_SUBSCRIPTIONS_MSG: &str = "Too many subscriptions on the connection";
+/// Request timeout error message.
+pub const REQUEST_TIMEOUT_MSG: &str = "Request timeout";
+/// Rate limit exceeded error message.
+pub const RATE_LIMIT_EXCEEDED_MSG: &str = "Rate limit exceeded, try again later";
+/// Streaming call with the same ID already in progress error message.
+pub const STREAMING_CALL_IN_PROGRESS_MSG: &str = "A streaming call with this ID is already in progress";
 /// Reserved for implementation-defined server-errors.
 pub const SERVER_ERROR_MSG: &str = "Server error";
 
@@ -262,9 +345,43 @@ impl CallError {
 	}
 }
 
+/// Error with a JSON-RPC error code, message and a serializable `data` value, to be returned from method handlers.
+///
+/// The `data` value is serialized as is into the `data` field of the error object sent to the client, which is
+/// useful to pass details such as validation failures or revert reasons along with the error.
+///
+/// Converts into [`CallError::Custom`], or [`CallError::Failed`] if `data` can't be serialized.
+#[derive(Debug, Clone)]
+pub struct ErrorWithData<T> {
+	/// JSON-RPC error code
+	pub code: i32,
+	/// Short description of the error.
+	pub message: String,
+	/// A primitive or structured value that contains additional information about the error.
+	pub data: T,
+}
+
+impl<T> ErrorWithData<T> {
+	/// Create a new `ErrorWithData`.
+	pub fn new(code: i32, message: impl Into<String>, data: T) -> Self {
+		Self { code, message: message.into(), data }
+	}
+}
+
+impl<T: Serialize> From<ErrorWithData<T>> for CallError {
+	fn from(err: ErrorWithData<T>) -> Self {
+		match serde_json::value::to_raw_value(&err.data) {
+			Ok(data) => CallError::Custom { code: err.code, message: err.message, data: Some(data) },
+			Err(e) => {
+				CallError::Failed(anyhow::anyhow!("Failed to serialize the data of error `{}`: {}", err.message, e))
+			}
+		}
+	}
+}
+
 #[cfg(test)]
 mod tests {
-	use super::{ErrorCode, ErrorObject, ErrorResponse, Id, TwoPointZero};
+	use super::{CallError, ErrorCode, ErrorObject, ErrorResponse, ErrorWithData, Id, TwoPointZero};
 
 	#[test]
 	fn deserialize_works() {
@@ -291,6 +408,23 @@ mod tests {
 		assert_eq!(exp, err);
 	}
 
+	#[test]
+	fn error_with_data_keeps_the_data_value() {
+		#[derive(serde::Serialize)]
+		struct Revert {
+			reason: &'static str,
+		}
+
+		match CallError::from(ErrorWithData::new(3, "execution reverted", Revert { reason: "out of gas" })) {
+			CallError::Custom { code, message, data } => {
+				assert_eq!(code, 3);
+				assert_eq!(message, "execution reverted");
+				assert_eq!(data.unwrap().get(), r#"{"reason":"out of gas"}"#);
+			}
+			err => panic!("Unexpected error: {:?}", err),
+		}
+	}
+
 	#[test]
 	fn deserialized_error_with_quoted_str() {
 		let raw = r#"{
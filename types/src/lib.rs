@@ -43,10 +43,15 @@ pub mod response;
 /// JSON-RPC response error object related types.
 pub mod error;
 
-pub use error::ErrorResponse;
+pub use error::{ErrorObjectOwned, ErrorResponse};
+#[cfg(feature = "bytes")]
+pub use params::{Base64Bytes, HexBytes};
 pub use params::{Id, Params, ParamsSequence, ParamsSer, SubscriptionId, TwoPointZero};
 pub use request::{InvalidRequest, Notification, NotificationSer, Request, RequestSer};
-pub use response::{Response, SubscriptionPayload, SubscriptionResponse};
+pub use response::{
+	Response, StreamChunk, StreamResponse, SubscriptionErrorPayload, SubscriptionErrorResponse, SubscriptionPayload,
+	SubscriptionResponse,
+};
 
 /// Empty `RpcParams` type;
 pub type EmptyParams = Vec<()>;
@@ -135,6 +135,53 @@ impl<'a> Params<'a> {
 		self.parse::<[T; 1]>().map(|[res]| res)
 	}
 
+	/// Attempt to parse named parameters, i.e. a JSON object, into type `T`.
+	///
+	/// Omitted fields are handled by `T`'s own serde attributes such as `#[serde(default)]` and no params at all
+	/// are treated as an empty object. Unlike [`Params::parse`], a missing required field is reported by name.
+	///
+	/// ```
+	/// # use jsonrpsee_types::params::Params;
+	/// #[derive(Debug, serde::Deserialize)]
+	/// struct Query {
+	///     name: String,
+	///     #[serde(default)]
+	///     limit: u32,
+	/// }
+	///
+	/// let params = Params::new(Some(r#"{"name":"foo"}"#));
+	/// let query: Query = params.parse_map().unwrap();
+	/// assert_eq!(query.name, "foo");
+	/// assert_eq!(query.limit, 0);
+	///
+	/// let params = Params::new(Some(r#"{"limit":10}"#));
+	/// let err = params.parse_map::<Query>().unwrap_err();
+	/// assert_eq!(err.to_string(), "Invalid params in the call: missing required field `name`");
+	/// ```
+	pub fn parse_map<T>(&'a self) -> Result<T, CallError>
+	where
+		T: Deserialize<'a>,
+	{
+		let params = match self.0.as_ref() {
+			Some(json) if json.starts_with('{') => json.as_ref(),
+			Some(_) => return Err(CallError::InvalidParams(anyhow!("Expected named params as a JSON object"))),
+			None => "{}",
+		};
+
+		serde_json::from_str(params).map_err(|e| {
+			let msg = e.to_string();
+			match msg.strip_prefix("missing field `").and_then(|rest| rest.split_once('`')) {
+				Some((field, _)) => CallError::InvalidParams(anyhow!("missing required field `{}`", field)),
+				None => CallError::InvalidParams(e.into()),
+			}
+		})
+	}
+
+	/// Returns the raw JSON of the params, `None` if the call has no params.
+	pub fn as_str(&self) -> Option<&str> {
+		self.0.as_deref()
+	}
+
 	/// Convert `Params<'a>` to `Params<'static>` so that it can be moved across threads.
 	///
 	/// This will cause an allocation if the params internally are using a borrowed JSON slice.
@@ -400,9 +447,134 @@ impl<'a> Id<'a> {
 	}
 }
 
+#[cfg(feature = "bytes")]
+/// Bytes encoded as a hex string in JSON, with or without a `0x` prefix.
+///
+/// Serialized with the `0x` prefix. Decoding a malformed string fails with a message naming the issue, which makes
+/// [`Params::one`] and friends return a precise invalid params error.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct HexBytes(pub Vec<u8>);
+
+#[cfg(feature = "bytes")]
+struct HexBytesVisitor;
+
+#[cfg(feature = "bytes")]
+impl<'de> Visitor<'de> for HexBytesVisitor {
+	type Value = HexBytes;
+
+	fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+		formatter.write_str("a hex string")
+	}
+
+	fn visit_str<E>(self, s: &str) -> Result<Self::Value, E>
+	where
+		E: de::Error,
+	{
+		let digits = s.strip_prefix("0x").unwrap_or(s);
+		hex::decode(digits).map(HexBytes).map_err(|err| de::Error::custom(format_args!("invalid hex string: {}", err)))
+	}
+}
+
+#[cfg(feature = "bytes")]
+impl<'de> Deserialize<'de> for HexBytes {
+	fn deserialize<D>(deserializer: D) -> Result<HexBytes, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		deserializer.deserialize_str(HexBytesVisitor)
+	}
+}
+
+#[cfg(feature = "bytes")]
+impl Serialize for HexBytes {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		serializer.serialize_str(&format!("0x{}", hex::encode(&self.0)))
+	}
+}
+
+#[cfg(feature = "bytes")]
+impl From<Vec<u8>> for HexBytes {
+	fn from(bytes: Vec<u8>) -> Self {
+		Self(bytes)
+	}
+}
+
+#[cfg(feature = "bytes")]
+impl From<HexBytes> for Vec<u8> {
+	fn from(bytes: HexBytes) -> Self {
+		bytes.0
+	}
+}
+
+#[cfg(feature = "bytes")]
+/// Bytes encoded as a standard, padded base64 string in JSON.
+///
+/// Decoding a malformed string fails with a message naming the issue, which makes [`Params::one`] and friends
+/// return a precise invalid params error.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Base64Bytes(pub Vec<u8>);
+
+#[cfg(feature = "bytes")]
+struct Base64BytesVisitor;
+
+#[cfg(feature = "bytes")]
+impl<'de> Visitor<'de> for Base64BytesVisitor {
+	type Value = Base64Bytes;
+
+	fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+		formatter.write_str("a base64 string")
+	}
+
+	fn visit_str<E>(self, s: &str) -> Result<Self::Value, E>
+	where
+		E: de::Error,
+	{
+		base64::decode(s)
+			.map(Base64Bytes)
+			.map_err(|err| de::Error::custom(format_args!("invalid base64 string: {}", err)))
+	}
+}
+
+#[cfg(feature = "bytes")]
+impl<'de> Deserialize<'de> for Base64Bytes {
+	fn deserialize<D>(deserializer: D) -> Result<Base64Bytes, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		deserializer.deserialize_str(Base64BytesVisitor)
+	}
+}
+
+#[cfg(feature = "bytes")]
+impl Serialize for Base64Bytes {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		serializer.serialize_str(&base64::encode(&self.0))
+	}
+}
+
+#[cfg(feature = "bytes")]
+impl From<Vec<u8>> for Base64Bytes {
+	fn from(bytes: Vec<u8>) -> Self {
+		Self(bytes)
+	}
+}
+
+#[cfg(feature = "bytes")]
+impl From<Base64Bytes> for Vec<u8> {
+	fn from(bytes: Base64Bytes) -> Self {
+		bytes.0
+	}
+}
+
 #[cfg(test)]
 mod test {
-	use super::{Cow, Id, JsonValue, Params, ParamsSer, SubscriptionId, TwoPointZero};
+	use super::{CallError, Cow, Id, JsonValue, Params, ParamsSer, SubscriptionId, TwoPointZero};
 	use crate::response::SubscriptionPayload;
 
 	#[test]
@@ -484,6 +656,37 @@ mod test {
 		assert!(obj.is_ok());
 	}
 
+	#[test]
+	fn params_parse_map() {
+		#[derive(serde::Deserialize, Debug, PartialEq)]
+		struct Named {
+			a: u64,
+			#[serde(default)]
+			b: Option<String>,
+		}
+
+		let params = Params::new(Some(r#"{"a":1,"b":"foo"}"#));
+		assert_eq!(params.parse_map::<Named>().unwrap(), Named { a: 1, b: Some("foo".into()) });
+
+		let params = Params::new(Some(r#"{"a":1}"#));
+		assert_eq!(params.parse_map::<Named>().unwrap(), Named { a: 1, b: None });
+
+		let params = Params::new(Some(r#"{"b":"foo"}"#));
+		let err = params.parse_map::<Named>().unwrap_err();
+		assert!(matches!(err, CallError::InvalidParams(_)));
+		assert!(err.to_string().ends_with("missing required field `a`"));
+
+		let params = Params::new(Some(r#"[1, "foo"]"#));
+		assert!(matches!(params.parse_map::<Named>().unwrap_err(), CallError::InvalidParams(_)));
+
+		let params = Params::new(Some(r#"{"a":"not a number"}"#));
+		assert!(matches!(params.parse_map::<Named>().unwrap_err(), CallError::InvalidParams(_)));
+
+		let none = Params::new(None);
+		assert!(none.parse_map::<Named>().is_err());
+		assert_eq!(none.parse_map::<JsonValue>().unwrap(), serde_json::json!({}));
+	}
+
 	#[test]
 	fn params_parse_empty_json() {
 		let array_params = Params::new(Some("[]"));
@@ -588,4 +791,26 @@ mod test {
 		assert_eq!(seq.optional_next::<Vec<Vec<u32>>>().unwrap(), Some(vec![vec![5], vec![6, 7], vec![]]));
 		assert_eq!(seq.optional_next::<serde_json::Value>().unwrap(), Some(serde_json::json!({"named":7})));
 	}
+
+	#[cfg(feature = "bytes")]
+	#[test]
+	fn params_decode_bytes() {
+		use super::{Base64Bytes, HexBytes};
+
+		let params = Params::new(Some(r#"["0xdeadbeef", "DEADBEEF", "aGVsbG8="]"#));
+		let (prefixed, bare, base64): (HexBytes, HexBytes, Base64Bytes) = params.parse().unwrap();
+		assert_eq!(prefixed, HexBytes(vec![0xde, 0xad, 0xbe, 0xef]));
+		assert_eq!(bare, prefixed);
+		assert_eq!(base64.0, b"hello");
+
+		assert_eq!(serde_json::to_string(&prefixed).unwrap(), r#""0xdeadbeef""#);
+		assert_eq!(serde_json::to_string(&base64).unwrap(), r#""aGVsbG8=""#);
+
+		let err = Params::new(Some(r#"["0xabc"]"#)).one::<HexBytes>().unwrap_err();
+		assert!(matches!(err, CallError::InvalidParams(_)));
+		assert!(err.to_string().contains("invalid hex string: Odd number of digits"), "{}", err);
+
+		let err = Params::new(Some(r#"["not base64!"]"#)).one::<Base64Bytes>().unwrap_err();
+		assert!(err.to_string().contains("invalid base64 string"), "{}", err);
+	}
 }
@@ -47,12 +47,20 @@ pub struct Request<'a> {
 	/// Parameter values of the request.
 	#[serde(borrow)]
 	pub params: Option<&'a RawValue>,
+	/// Number of milliseconds the client is willing to wait for the response, an extension of the specification.
+	#[serde(default)]
+	deadline_ms: Option<u64>,
 }
 
 impl<'a> Request<'a> {
 	/// Create a new [`Request`].
 	pub fn new(method: Cow<'a, str>, params: Option<&'a RawValue>, id: Id<'a>) -> Self {
-		Self { jsonrpc: TwoPointZero, id, method, params }
+		Self { jsonrpc: TwoPointZero, id, method, params, deadline_ms: None }
+	}
+
+	/// Number of milliseconds the client is willing to wait for the response, if it sent a deadline hint.
+	pub fn deadline_ms(&self) -> Option<u64> {
+		self.deadline_ms
 	}
 }
 
@@ -63,6 +63,36 @@ pub struct SubscriptionPayload<'a, T> {
 /// Subscription response object, embedding a [`SubscriptionPayload`] in the `params` member.
 pub type SubscriptionResponse<'a, T> = Notification<'a, SubscriptionPayload<'a, T>>;
 
+/// Error reported by a subscription without ending it.
+///
+/// It has an `error` member instead of the `result` member of a [`SubscriptionPayload`].
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SubscriptionErrorPayload<'a, T> {
+	/// Subscription ID
+	#[serde(borrow)]
+	pub subscription: SubscriptionId<'a>,
+	/// Error.
+	pub error: T,
+}
+
+/// Subscription error object, embedding a [`SubscriptionErrorPayload`] in the `params` member.
+pub type SubscriptionErrorResponse<'a, T> = Notification<'a, SubscriptionErrorPayload<'a, T>>;
+
+/// A chunk of the result of a streaming method call.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct StreamChunk<'a, T> {
+	/// ID of the call the chunk belongs to.
+	#[serde(borrow)]
+	pub id: Id<'a>,
+	/// Chunk.
+	pub chunk: T,
+}
+
+/// Streaming method response object, embedding a [`StreamChunk`] in the `params` member.
+///
+/// The stream of chunks is terminated by the regular [`Response`] (or error) to the call.
+pub type StreamResponse<'a, T> = Notification<'a, StreamChunk<'a, T>>;
+
 #[cfg(test)]
 mod tests {
 	use super::{Id, Response, TwoPointZero};
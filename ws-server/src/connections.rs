@@ -0,0 +1,139 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Open connections of the server, to push notifications to them outside of subscriptions.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use jsonrpsee_core::middleware::ConnectionDetails;
+use jsonrpsee_core::server::helpers::MethodSink;
+use jsonrpsee_core::server::rpc_module::ConnectionId;
+use jsonrpsee_core::Error;
+use jsonrpsee_types::Notification;
+use serde::Serialize;
+
+/// Sinks and details of the open connections, by id.
+///
+/// The entries are shared, for the broadcasts to take a snapshot of the connections without holding the lock while
+/// sending to each of them.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Connections {
+	inner: Arc<Mutex<BTreeMap<ConnectionId, Entry>>>,
+}
+
+/// Sink and details of an open connection.
+type Entry = Arc<(MethodSink, ConnectionDetails)>;
+
+impl Connections {
+	/// Register the connection `conn_id` until the returned guard is dropped.
+	pub(crate) fn register(&self, conn_id: ConnectionId, sink: MethodSink, details: ConnectionDetails) -> Registered {
+		self.inner.lock().expect("Connections lock poisoned; qed").insert(conn_id, Arc::new((sink, details)));
+		Registered { connections: self.clone(), conn_id }
+	}
+
+	/// Handle to the connection `conn_id`, `None` if it's closed.
+	pub(crate) fn get(&self, conn_id: ConnectionId) -> Option<ConnectionHandle> {
+		let entry = self.inner.lock().expect("Connections lock poisoned; qed").get(&conn_id)?.clone();
+		let (sink, details) = &*entry;
+		Some(ConnectionHandle { conn_id, sink: sink.clone(), details: details.clone() })
+	}
+
+	/// Enqueue `msg` to the open connections `filter` returns `true` for, returns the number of connections it was
+	/// enqueued to.
+	///
+	/// The connections are enqueued to from a snapshot, so that `filter` and the sends don't hold up the connections
+	/// being opened or closed meanwhile.
+	pub(crate) fn broadcast(&self, msg: &str, mut filter: impl FnMut(&ConnectionDetails) -> bool) -> usize {
+		let snapshot: Vec<_> = self.inner.lock().expect("Connections lock poisoned; qed").values().cloned().collect();
+		snapshot
+			.iter()
+			.map(|entry| &**entry)
+			.filter(|(_, details)| filter(details))
+			.filter(|(sink, _)| sink.send_raw(msg.to_owned()).is_ok())
+			.count()
+	}
+
+	/// Ids of the open connections, in ascending order.
+	pub(crate) fn ids(&self) -> Vec<ConnectionId> {
+		self.inner.lock().expect("Connections lock poisoned; qed").keys().copied().collect()
+	}
+}
+
+/// Removes its connection from the [`Connections`] when dropped.
+#[derive(Debug)]
+pub(crate) struct Registered {
+	connections: Connections,
+	conn_id: ConnectionId,
+}
+
+impl Drop for Registered {
+	fn drop(&mut self) {
+		self.connections.inner.lock().expect("Connections lock poisoned; qed").remove(&self.conn_id);
+	}
+}
+
+/// Handle to an open connection of the server, to send it notifications which aren't tied to a subscription.
+///
+/// Obtained with [`WsServerHandle::connection`](crate::WsServerHandle::connection), the id of a connection is in the
+/// [`ConnectionDetails`](jsonrpsee_core::middleware::ConnectionDetails) passed to the middleware and stored in the
+/// connection state.
+#[derive(Debug, Clone)]
+pub struct ConnectionHandle {
+	conn_id: ConnectionId,
+	sink: MethodSink,
+	details: ConnectionDetails,
+}
+
+impl ConnectionHandle {
+	/// Id of the connection.
+	pub fn conn_id(&self) -> ConnectionId {
+		self.conn_id
+	}
+
+	/// Details of the connection.
+	pub fn details(&self) -> &ConnectionDetails {
+		&self.details
+	}
+
+	/// Whether the connection was closed, after which the notifications are discarded.
+	pub fn is_closed(&self) -> bool {
+		self.sink.is_closed()
+	}
+
+	/// Send the notification `{"jsonrpc":"2.0","method":<method>,"params":<params>}` to the client.
+	///
+	/// Fails if `params` couldn't be serialized or if the connection was closed.
+	pub fn notify<T: Serialize>(&self, method: &str, params: T) -> Result<(), Error> {
+		let msg = notification(method, params)?;
+		self.sink.send_raw(msg).map_err(|err| Error::Internal(err.into_send_error()))
+	}
+}
+
+/// Serialize the notification `{"jsonrpc":"2.0","method":<method>,"params":<params>}`.
+pub(crate) fn notification<T: Serialize>(method: &str, params: T) -> Result<String, Error> {
+	serde_json::to_string(&Notification::new(method.into(), params)).map_err(Into::into)
+}
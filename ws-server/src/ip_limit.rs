@@ -0,0 +1,152 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Limit of the connections open at the same time from the same client address.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+
+/// Where the address of a client is taken from, see
+/// [`WsServerBuilder::max_connections_per_ip`](crate::WsServerBuilder::max_connections_per_ip).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ClientIp {
+	/// The address of the peer of the TCP connection.
+	#[default]
+	PeerAddr,
+	/// The first address listed in this header of the handshake request, for instance `X-Forwarded-For`, falling back
+	/// to the address of the peer if the header is missing or invalid.
+	///
+	/// Only use this behind a reverse proxy setting the header, clients can send any value otherwise.
+	Header(String),
+}
+
+impl ClientIp {
+	/// Returns the address of the client connecting from `peer_addr` with the raw handshake `request`.
+	pub(crate) fn resolve(&self, peer_addr: IpAddr, request: &[u8]) -> IpAddr {
+		match self {
+			Self::PeerAddr => peer_addr,
+			Self::Header(name) => header_value(request, name)
+				.and_then(|value| value.split(',').next())
+				.and_then(|first| first.trim().parse().ok())
+				.unwrap_or(peer_addr),
+		}
+	}
+}
+
+/// Returns the value of the first header `name` of the raw HTTP `request`.
+pub(crate) fn header_value<'a>(request: &'a [u8], name: &str) -> Option<&'a str> {
+	let head = request.windows(4).position(|w| w == b"\r\n\r\n").map_or(request, |end| &request[..end]);
+	let head = std::str::from_utf8(head).ok()?;
+	head.split("\r\n").skip(1).find_map(|line| {
+		let (header, value) = line.split_once(':')?;
+		header.trim().eq_ignore_ascii_case(name).then(|| value.trim())
+	})
+}
+
+/// Number of connections open per client address.
+type OpenConnections = Arc<Mutex<HashMap<IpAddr, u32>>>;
+
+/// Connections open per client address, up to a max if any.
+#[derive(Debug, Clone)]
+pub(crate) struct ConnectionsPerIp {
+	max: Option<u32>,
+	open: OpenConnections,
+}
+
+impl ConnectionsPerIp {
+	pub(crate) fn new(max: Option<u32>) -> Self {
+		Self { max, open: Arc::default() }
+	}
+
+	/// Count a new connection from `ip` until the returned guard is dropped, `None` if the max is reached.
+	pub(crate) fn try_acquire(&self, ip: IpAddr) -> Option<IpConnectionGuard> {
+		let max = match self.max {
+			Some(max) => max,
+			None => return Some(IpConnectionGuard(None)),
+		};
+
+		let mut open = self.open.lock().expect("Lock poisoned");
+		let count = open.entry(ip).or_insert(0);
+		if *count >= max {
+			return None;
+		}
+		*count += 1;
+		Some(IpConnectionGuard(Some((ip, self.open.clone()))))
+	}
+}
+
+/// Counts a connection in the limit of its client address.
+#[derive(Debug)]
+pub(crate) struct IpConnectionGuard(Option<(IpAddr, OpenConnections)>);
+
+impl Drop for IpConnectionGuard {
+	fn drop(&mut self) {
+		if let Some((ip, open)) = self.0.take() {
+			let mut open = open.lock().expect("Lock poisoned");
+			if let Some(count) = open.get_mut(&ip) {
+				*count -= 1;
+				if *count == 0 {
+					open.remove(&ip);
+				}
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{ClientIp, ConnectionsPerIp};
+	use std::net::IpAddr;
+
+	const REQUEST: &[u8] = b"GET / HTTP/1.1\r\nHost: localhost\r\nx-forwarded-for: 10.0.0.1, 10.0.0.2\r\n\r\n";
+
+	#[test]
+	fn client_ip_is_resolved_from_the_header() {
+		let peer: IpAddr = "127.0.0.1".parse().unwrap();
+		let forwarded = ClientIp::Header("X-Forwarded-For".into());
+
+		assert_eq!(ClientIp::PeerAddr.resolve(peer, REQUEST), peer);
+		assert_eq!(forwarded.resolve(peer, REQUEST), "10.0.0.1".parse::<IpAddr>().unwrap());
+		assert_eq!(ClientIp::Header("X-Real-Ip".into()).resolve(peer, REQUEST), peer);
+		assert_eq!(forwarded.resolve(peer, b"GET / HTTP/1.1\r\nX-Forwarded-For: unknown\r\n\r\n"), peer);
+	}
+
+	#[test]
+	fn connections_are_limited_per_ip() {
+		let limit = ConnectionsPerIp::new(Some(2));
+		let a: IpAddr = "10.0.0.1".parse().unwrap();
+		let b: IpAddr = "10.0.0.2".parse().unwrap();
+
+		let first = limit.try_acquire(a).unwrap();
+		let _second = limit.try_acquire(a).unwrap();
+		assert!(limit.try_acquire(a).is_none());
+		assert!(limit.try_acquire(b).is_some());
+
+		drop(first);
+		assert!(limit.try_acquire(a).is_some());
+	}
+}
@@ -0,0 +1,171 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Detection of the dead or silent connections, with pings and an idle timeout.
+
+use std::fmt;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use tokio::sync::Notify;
+use tokio::time::Instant;
+
+/// Configuration of the keepalive of each connection.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct KeepAliveConfig {
+	/// Interval at which pings are sent, if enabled.
+	pub(crate) ping_interval: Option<Duration>,
+	/// Time within which a ping must be answered.
+	pub(crate) pong_timeout: Duration,
+	/// Time after which a connection that sent no message is closed, if enabled.
+	pub(crate) idle_timeout: Option<Duration>,
+}
+
+impl Default for KeepAliveConfig {
+	fn default() -> Self {
+		Self { ping_interval: None, pong_timeout: Duration::from_secs(30), idle_timeout: None }
+	}
+}
+
+/// Why a connection is considered dead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Expired {
+	/// A ping wasn't answered in time.
+	PongTimeout,
+	/// No message was received in time.
+	Idle,
+}
+
+impl fmt::Display for Expired {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::PongTimeout => f.write_str("ping not answered in time"),
+			Self::Idle => f.write_str("connection idle for too long"),
+		}
+	}
+}
+
+/// Keepalive of a connection, tracking the pings sent and the messages received on it.
+#[derive(Debug)]
+pub(crate) struct KeepAlive {
+	config: KeepAliveConfig,
+	state: Mutex<State>,
+	/// Notified when a ping is sent, which may bring the expiry closer.
+	ping_sent: Notify,
+}
+
+impl KeepAlive {
+	/// Create the keepalive of a new connection, `None` if neither pings nor the idle timeout are enabled.
+	pub(crate) fn new(config: KeepAliveConfig) -> Option<Self> {
+		if config.ping_interval.is_none() && config.idle_timeout.is_none() {
+			return None;
+		}
+		let state = Mutex::new(State { last_message: Instant::now(), ping_sent: None });
+		Some(Self { config, state, ping_sent: Notify::new() })
+	}
+
+	/// Record a ping sent to the client, which must be answered within the pong timeout.
+	pub(crate) fn ping_sent(&self) {
+		self.state.lock().expect("Lock poisoned").ping_sent.get_or_insert_with(Instant::now);
+		self.ping_sent.notify_one();
+	}
+
+	/// Record a pong received from the client, answering the pings sent so far.
+	pub(crate) fn pong_received(&self) {
+		self.state.lock().expect("Lock poisoned").ping_sent = None;
+	}
+
+	/// Record a message received from the client, restarting the idle timeout.
+	pub(crate) fn message_received(&self) {
+		self.state.lock().expect("Lock poisoned").last_message = Instant::now();
+	}
+
+	/// Resolves when the connection is considered dead.
+	pub(crate) async fn expired(&self) -> Expired {
+		loop {
+			let expiry = self.state.lock().expect("Lock poisoned").expiry(&self.config);
+			match expiry {
+				Some((at, expired)) if at <= Instant::now() => return expired,
+				Some((at, _)) => {
+					tokio::select! {
+						_ = tokio::time::sleep_until(at) => {}
+						_ = self.ping_sent.notified() => {}
+					}
+				}
+				None => self.ping_sent.notified().await,
+			}
+		}
+	}
+}
+
+#[derive(Debug)]
+struct State {
+	/// When the last message was received.
+	last_message: Instant,
+	/// When the oldest unanswered ping was sent, if any.
+	ping_sent: Option<Instant>,
+}
+
+impl State {
+	/// Returns when and why the connection expires, if it can.
+	fn expiry(&self, config: &KeepAliveConfig) -> Option<(Instant, Expired)> {
+		let pong = self.ping_sent.map(|sent| (sent + config.pong_timeout, Expired::PongTimeout));
+		let idle = config.idle_timeout.map(|timeout| (self.last_message + timeout, Expired::Idle));
+		match (pong, idle) {
+			(Some(pong), Some(idle)) => Some(if pong.0 <= idle.0 { pong } else { idle }),
+			(pong, idle) => pong.or(idle),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Expired, KeepAliveConfig, State};
+	use std::time::Duration;
+	use tokio::time::Instant;
+
+	#[test]
+	fn expiry_is_the_closest_deadline() {
+		let start = Instant::now();
+		let config = KeepAliveConfig {
+			ping_interval: Some(Duration::from_secs(10)),
+			pong_timeout: Duration::from_secs(5),
+			idle_timeout: Some(Duration::from_secs(60)),
+		};
+		let mut state = State { last_message: start, ping_sent: None };
+		assert_eq!(state.expiry(&config), Some((start + Duration::from_secs(60), Expired::Idle)));
+
+		state.ping_sent = Some(start + Duration::from_secs(10));
+		assert_eq!(state.expiry(&config), Some((start + Duration::from_secs(15), Expired::PongTimeout)));
+
+		state.ping_sent = Some(start + Duration::from_secs(58));
+		assert_eq!(state.expiry(&config), Some((start + Duration::from_secs(60), Expired::Idle)));
+
+		let config = KeepAliveConfig { idle_timeout: None, ..config };
+		state.ping_sent = None;
+		assert_eq!(state.expiry(&config), None);
+	}
+}
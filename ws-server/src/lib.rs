@@ -32,14 +32,27 @@
 
 extern crate alloc;
 
+mod connections;
 mod future;
+mod ip_limit;
+mod keepalive;
+mod scheduler;
 mod server;
+mod stream;
 
 #[cfg(test)]
 mod tests;
 
-pub use future::{ServerHandle as WsServerHandle, ShutdownWaiter as WsShutdownWaiter};
-pub use jsonrpsee_core::server::rpc_module::{RpcModule, SubscriptionSink};
+pub use connections::ConnectionHandle;
+pub use future::ServerHandle as WsServerHandle;
+pub use ip_limit::ClientIp;
+#[cfg(feature = "deflate")]
+pub use jsonrpsee_core::deflate::DeflateConfig;
+pub use jsonrpsee_core::server::future::ShutdownWaiter as WsShutdownWaiter;
+pub use jsonrpsee_core::server::helpers::BufferFullPolicy;
+pub use jsonrpsee_core::server::rpc_module::{PendingSubscription, RpcModule, SubscriptionSink};
+#[cfg(feature = "tls")]
+pub use jsonrpsee_core::server::tls::TlsConfig;
 pub use jsonrpsee_core::{id_providers::*, traits::IdProvider};
 pub use jsonrpsee_types as types;
 pub use server::{Builder as WsServerBuilder, Server as WsServer};
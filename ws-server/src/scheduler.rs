@@ -0,0 +1,302 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Fair scheduling of method calls across connections.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use futures_channel::oneshot;
+use jsonrpsee_core::middleware::ConnectionDetails;
+use jsonrpsee_core::server::rpc_module::ConnectionId;
+
+/// Weight of the connections in the fair scheduling given their details, see [`FairScheduler::connection`].
+#[derive(Clone)]
+pub(crate) struct ConnectionWeight(Arc<dyn Fn(&ConnectionDetails) -> u32 + Send + Sync>);
+
+impl ConnectionWeight {
+	pub(crate) fn new(weight: impl Fn(&ConnectionDetails) -> u32 + Send + Sync + 'static) -> Self {
+		Self(Arc::new(weight))
+	}
+
+	/// Weight of the connection with the given details.
+	pub(crate) fn of(&self, details: &ConnectionDetails) -> u32 {
+		(self.0)(details)
+	}
+}
+
+impl fmt::Debug for ConnectionWeight {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str("ConnectionWeight")
+	}
+}
+
+/// Limits the number of method calls executed at the same time, handing out free slots
+/// round-robin across the connections with calls waiting for one, weighted by their weight.
+///
+/// This prevents a single connection flooding the server with calls from starving the other connections.
+#[derive(Debug, Clone)]
+pub(crate) struct FairScheduler {
+	inner: Arc<Mutex<State>>,
+}
+
+#[derive(Debug)]
+struct State {
+	/// Number of free slots.
+	available: usize,
+	/// Calls waiting for a slot per connection, connections without waiting calls are removed.
+	waiting: BTreeMap<ConnectionId, Waiting>,
+	/// The connection to look for a waiting call from first when a slot is freed and `turn` is over.
+	next: ConnectionId,
+	/// Connection which got the last slot and the number of slots it can still get in a row.
+	turn: Option<(ConnectionId, u32)>,
+}
+
+#[derive(Debug)]
+struct Waiting {
+	weight: u32,
+	calls: VecDeque<oneshot::Sender<Permit>>,
+}
+
+impl State {
+	/// Start the turn of a connection which got a slot.
+	fn start_turn(&mut self, conn_id: ConnectionId, weight: u32) {
+		self.turn = Some((conn_id, weight - 1));
+		self.next = conn_id.wrapping_add(1);
+	}
+
+	fn next_waiter(&mut self) -> Option<oneshot::Sender<Permit>> {
+		let conn_id = match &mut self.turn {
+			Some((conn_id, left)) if *left > 0 && self.waiting.contains_key(conn_id) => {
+				*left -= 1;
+				*conn_id
+			}
+			_ => {
+				let (&conn_id, waiting) = match self.waiting.range(self.next..).next() {
+					Some(next) => next,
+					None => self.waiting.iter().next()?,
+				};
+				let weight = waiting.weight;
+				self.start_turn(conn_id, weight);
+				conn_id
+			}
+		};
+
+		let waiting = self.waiting.get_mut(&conn_id).expect("Key found above; qed");
+		let waiter = waiting.calls.pop_front();
+		if waiting.calls.is_empty() {
+			self.waiting.remove(&conn_id);
+		}
+
+		waiter
+	}
+}
+
+impl FairScheduler {
+	/// Create a new scheduler that allows `max_concurrent_calls` calls to be executed at the same time.
+	///
+	/// ## Panics
+	///
+	/// Panics if `max_concurrent_calls` is zero, no call could ever be executed.
+	pub(crate) fn new(max_concurrent_calls: usize) -> Self {
+		assert!(max_concurrent_calls > 0, "Fair scheduling needs at least one concurrent call");
+		let state = State { available: max_concurrent_calls, waiting: BTreeMap::new(), next: 0, turn: None };
+		Self { inner: Arc::new(Mutex::new(state)) }
+	}
+
+	/// Scheduler of the calls made on the given connection, which gets up to `weight` free slots in a row while
+	/// it has calls waiting for one. A weight of `0` counts as `1`.
+	pub(crate) fn connection(&self, conn_id: ConnectionId, weight: u32) -> ConnectionScheduler {
+		ConnectionScheduler { scheduler: self.clone(), conn_id, weight: weight.max(1) }
+	}
+
+	fn release(&self) {
+		loop {
+			let waiter = {
+				let mut state = self.inner.lock().expect("Lock poisoned");
+
+				match state.next_waiter() {
+					Some(waiter) => waiter,
+					None => {
+						state.available += 1;
+						return;
+					}
+				}
+			};
+
+			match waiter.send(Permit { scheduler: Some(self.clone()) }) {
+				Ok(()) => return,
+				// The waiting call was dropped, hand the slot to the next one instead.
+				Err(mut permit) => {
+					permit.scheduler.take();
+				}
+			}
+		}
+	}
+}
+
+/// [`FairScheduler`] of the calls made on a connection.
+#[derive(Debug, Clone)]
+pub(crate) struct ConnectionScheduler {
+	scheduler: FairScheduler,
+	conn_id: ConnectionId,
+	weight: u32,
+}
+
+impl ConnectionScheduler {
+	/// Take a slot to execute a call, waiting in line for one if none is free.
+	///
+	/// The call takes its place in line right away, in the order the calls are received, whereas the returned
+	/// future waits for its turn. The slot is freed when the returned [`Permit`] is dropped.
+	pub(crate) fn acquire(&self) -> impl Future<Output = Permit> + Send {
+		let scheduler = &self.scheduler;
+		let waiting = {
+			let mut state = scheduler.inner.lock().expect("Lock poisoned");
+
+			if state.available > 0 {
+				state.available -= 1;
+				state.start_turn(self.conn_id, self.weight);
+				Ok(Permit { scheduler: Some(scheduler.clone()) })
+			} else {
+				let (tx, rx) = oneshot::channel();
+				let waiting = state
+					.waiting
+					.entry(self.conn_id)
+					.or_insert_with(|| Waiting { weight: self.weight, calls: VecDeque::new() });
+				waiting.calls.push_back(tx);
+				Err(rx)
+			}
+		};
+
+		async move {
+			match waiting {
+				Ok(permit) => permit,
+				Err(rx) => rx.await.expect("Waiting senders are only dropped when sending a permit; qed"),
+			}
+		}
+	}
+}
+
+/// A slot to execute a call, freed when dropped.
+#[derive(Debug)]
+pub(crate) struct Permit {
+	scheduler: Option<FairScheduler>,
+}
+
+impl Drop for Permit {
+	fn drop(&mut self) {
+		if let Some(scheduler) = self.scheduler.take() {
+			scheduler.release();
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{FairScheduler, Permit};
+	use futures_util::future::BoxFuture;
+	use futures_util::{poll, FutureExt};
+
+	/// Release `permit` and then each permit handed out, returns the order in which the `waiting` calls of the
+	/// given connections got a slot.
+	async fn served_order(mut permit: Permit, mut waiting: Vec<(usize, BoxFuture<'static, Permit>)>) -> Vec<usize> {
+		for (_, fut) in waiting.iter_mut() {
+			assert!(poll!(fut).is_pending());
+		}
+
+		let mut order = Vec::new();
+		while !waiting.is_empty() {
+			drop(permit);
+
+			let mut next = None;
+			for (idx, (_, fut)) in waiting.iter_mut().enumerate() {
+				if let std::task::Poll::Ready(p) = poll!(fut) {
+					assert!(next.is_none(), "Only one slot is available");
+					next = Some((idx, p));
+				}
+			}
+
+			let (idx, p) = next.expect("The freed slot is handed to a waiting call");
+			order.push(waiting.remove(idx).0);
+			permit = p;
+		}
+		order
+	}
+
+	#[tokio::test]
+	async fn slots_are_handed_out_round_robin() {
+		let scheduler = FairScheduler::new(1);
+		let permit = scheduler.connection(0, 1).acquire().await;
+
+		// Connection 1 queues three calls before connection 2 and 3 queue one call each.
+		let waiting =
+			[1, 1, 1, 2, 3].into_iter().map(|conn| (conn, scheduler.connection(conn, 1).acquire().boxed())).collect();
+
+		assert_eq!(served_order(permit, waiting).await, vec![1, 2, 3, 1, 1]);
+	}
+
+	#[tokio::test]
+	async fn slots_are_handed_out_by_weight() {
+		let scheduler = FairScheduler::new(1);
+		let permit = scheduler.connection(0, 1).acquire().await;
+
+		// Connection 1 gets two slots in a row for each slot of connection 2.
+		let (heavy, light) = (scheduler.connection(1, 2), scheduler.connection(2, 1));
+		let waiting = [1, 1, 1, 1, 2, 2]
+			.into_iter()
+			.map(|conn| (conn, if conn == 1 { heavy.acquire().boxed() } else { light.acquire().boxed() }))
+			.collect();
+
+		assert_eq!(served_order(permit, waiting).await, vec![1, 1, 2, 1, 1, 2]);
+	}
+
+	#[test]
+	#[should_panic(expected = "Fair scheduling needs at least one concurrent call")]
+	fn zero_concurrent_calls_is_rejected() {
+		FairScheduler::new(0);
+	}
+
+	#[tokio::test]
+	async fn dropped_waiters_are_skipped() {
+		let scheduler = FairScheduler::new(1);
+		let permit = scheduler.connection(0, 1).acquire().await;
+
+		let mut dropped = scheduler.connection(1, 1).acquire().boxed();
+		let mut waiting = scheduler.connection(2, 1).acquire().boxed();
+		assert!(poll!(&mut dropped).is_pending());
+		assert!(poll!(&mut waiting).is_pending());
+
+		drop(dropped);
+		drop(permit);
+		let permit = waiting.await;
+		drop(permit);
+
+		// All slots are free again.
+		assert!(scheduler.connection(3, 1).acquire().now_or_never().is_some());
+	}
+}
@@ -25,54 +25,83 @@
 // DEALINGS IN THE SOFTWARE.
 
 use std::future::Future;
+use std::io;
 use std::net::SocketAddr;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
-
-use crate::future::{FutureDriver, ServerHandle, StopMonitor};
+use std::time::Duration;
+
+use crate::connections::Connections;
+use crate::future::ServerHandle;
+use crate::ip_limit::{header_value, ClientIp, ConnectionsPerIp};
+use crate::keepalive::{KeepAlive, KeepAliveConfig};
+use crate::scheduler::{ConnectionScheduler, ConnectionWeight, FairScheduler};
+use crate::stream::{Acceptor, ServerStream};
 use crate::types::error::ErrorCode;
-use crate::types::{Id, Request};
+use crate::types::Id;
 use futures_channel::mpsc;
-use futures_util::future::{join_all, FutureExt};
+use futures_util::future::FutureExt;
 use futures_util::io::{BufReader, BufWriter};
 use futures_util::stream::StreamExt;
+#[cfg(feature = "deflate")]
+use jsonrpsee_core::deflate::DeflateConfig;
 use jsonrpsee_core::id_providers::RandomIntegerIdProvider;
-use jsonrpsee_core::middleware::Middleware;
-use jsonrpsee_core::server::helpers::{collect_batch_response, prepare_error, MethodSink};
+use jsonrpsee_core::middleware::{AsyncMiddleware, ConnectionDetails, Middleware, TransportKind};
+use jsonrpsee_core::server::cost_budget::CostBudget;
+use jsonrpsee_core::server::dispatch::Dispatcher;
+use jsonrpsee_core::server::future::{FutureDriver, StopMonitor};
+use jsonrpsee_core::server::helpers::{BufferFullPolicy, MethodFilter, MethodSink};
+use jsonrpsee_core::server::live_methods::{LiveMethods, MethodsReceiver};
 use jsonrpsee_core::server::resource_limiting::Resources;
-use jsonrpsee_core::server::rpc_module::{ConnState, ConnectionId, MethodKind, Methods};
+use jsonrpsee_core::server::rpc_module::{ConnectionId, Methods};
+#[cfg(feature = "tls")]
+use jsonrpsee_core::server::tls::TlsConfig;
 use jsonrpsee_core::traits::IdProvider;
 use jsonrpsee_core::{Error, TEN_MB_SIZE_BYTES};
-use jsonrpsee_types::Params;
 use soketto::connection::Error as SokettoError;
+use soketto::data::ByteSlice125;
 use soketto::handshake::{server::Response, Server as SokettoServer};
-use soketto::Sender;
+use soketto::{Incoming as SokettoIncoming, Receiver, Sender};
 use tokio::net::{TcpListener, TcpStream, ToSocketAddrs};
-use tokio::sync::Notify;
 use tokio_util::compat::{Compat, TokioAsyncReadCompatExt};
 
 /// Default maximum connections allowed.
 const MAX_CONNECTIONS: u64 = 100;
 
+/// Default maximum subscriptions per connection allowed.
+const MAX_SUBSCRIPTIONS_PER_CONNECTION: u32 = 1024;
+
+/// Buffered stream of a connection.
+type Socket = BufReader<BufWriter<Compat<ServerStream>>>;
+
 /// A WebSocket JSON RPC server.
 pub struct Server<M> {
 	listener: TcpListener,
+	/// Terminates TLS on the accepted connections, if enabled.
+	acceptor: Acceptor,
 	cfg: Settings,
 	stop_monitor: StopMonitor,
 	resources: Resources,
 	middleware: M,
 	id_provider: Arc<dyn IdProvider>,
+	/// Methods dispatched to, which can be replaced while the server is running.
+	methods: LiveMethods,
+	/// Open connections, which notifications can be pushed to.
+	connections: Connections,
 }
 
 impl<M> std::fmt::Debug for Server<M> {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 		f.debug_struct("Server")
 			.field("listener", &self.listener)
+			.field("acceptor", &self.acceptor)
 			.field("cfg", &self.cfg)
 			.field("stop_monitor", &self.stop_monitor)
 			.field("id_provider", &self.id_provider)
 			.field("resources", &self.resources)
+			.field("methods", &self.methods)
+			.field("connections", &self.connections)
 			.finish()
 	}
 }
@@ -85,26 +114,32 @@ impl<M: Middleware> Server<M> {
 
 	/// Returns the handle to stop the running server.
 	pub fn server_handle(&self) -> ServerHandle {
-		self.stop_monitor.handle()
+		ServerHandle::new(self.stop_monitor.handle(self.methods.clone()), self.connections.clone())
 	}
 
 	/// Start responding to connections requests. This will run on the tokio runtime until the server is stopped.
+	///
+	/// The methods can be replaced while the server is running with [`ServerHandle::swap_methods`].
 	pub fn start(mut self, methods: impl Into<Methods>) -> Result<ServerHandle, Error> {
-		let methods = methods.into().initialize_resources(&self.resources)?;
+		self.methods.swap(methods)?;
 		let handle = self.server_handle();
 
 		match self.cfg.tokio_runtime.take() {
-			Some(rt) => rt.spawn(self.start_inner(methods)),
-			None => tokio::spawn(self.start_inner(methods)),
+			Some(rt) => rt.spawn(self.start_inner()),
+			None => tokio::spawn(self.start_inner()),
 		};
 
 		Ok(handle)
 	}
 
-	async fn start_inner(self, methods: Methods) {
+	async fn start_inner(self) {
+		let methods = self.methods;
 		let stop_monitor = self.stop_monitor;
 		let resources = self.resources;
 		let middleware = self.middleware;
+		let connections_per_ip = ConnectionsPerIp::new(self.cfg.max_connections_per_ip);
+		let registry = &self.connections;
+		let acceptor = &self.acceptor;
 
 		let mut id = 0;
 		let mut connections = FutureDriver::default();
@@ -112,7 +147,7 @@ impl<M: Middleware> Server<M> {
 
 		loop {
 			match connections.select_with(&mut incoming).await {
-				Ok((socket, _addr)) => {
+				Ok((socket, peer_addr)) => {
 					if let Err(e) = socket.set_nodelay(true) {
 						tracing::error!("Could not set NODELAY on socket: {:?}", e);
 						continue;
@@ -120,7 +155,11 @@ impl<M: Middleware> Server<M> {
 
 					if connections.count() >= self.cfg.max_connections as usize {
 						tracing::warn!("Too many connections. Try again in a while.");
-						connections.add(Box::pin(handshake(socket, HandshakeResponse::Reject { status_code: 429 })));
+						connections.add(Box::pin(handshake(
+							socket,
+							acceptor,
+							HandshakeResponse::Reject { status_code: 429 },
+						)));
 						continue;
 					}
 
@@ -130,14 +169,18 @@ impl<M: Middleware> Server<M> {
 
 					connections.add(Box::pin(handshake(
 						socket,
+						acceptor,
 						HandshakeResponse::Accept {
 							conn_id: id,
+							peer_addr,
 							methods,
 							resources: &resources,
 							cfg,
 							stop_monitor: &stop_monitor,
 							middleware: middleware.clone(),
 							id_provider,
+							connections_per_ip: &connections_per_ip,
+							registry,
 						},
 					)));
 
@@ -212,21 +255,26 @@ enum HandshakeResponse<'a, M> {
 	},
 	Accept {
 		conn_id: ConnectionId,
-		methods: &'a Methods,
+		peer_addr: SocketAddr,
+		methods: &'a LiveMethods,
 		resources: &'a Resources,
 		cfg: &'a Settings,
 		stop_monitor: &'a StopMonitor,
 		middleware: M,
 		id_provider: Arc<dyn IdProvider>,
+		connections_per_ip: &'a ConnectionsPerIp,
+		registry: &'a Connections,
 	},
 }
 
-async fn handshake<M>(socket: tokio::net::TcpStream, mode: HandshakeResponse<'_, M>) -> Result<(), Error>
+async fn handshake<M>(socket: TcpStream, acceptor: &Acceptor, mode: HandshakeResponse<'_, M>) -> Result<(), Error>
 where
 	M: Middleware,
 {
+	let stream = acceptor.accept(socket).await?;
+	let tls = stream.tls();
 	// For each incoming background_task we perform a handshake.
-	let mut server = SokettoServer::new(BufReader::new(BufWriter::new(socket.compat())));
+	let mut server = SokettoServer::new(BufReader::new(BufWriter::new(stream.compat())));
 
 	match mode {
 		HandshakeResponse::Reject { status_code } => {
@@ -241,8 +289,23 @@ where
 
 			Ok(())
 		}
-		HandshakeResponse::Accept { conn_id, methods, resources, cfg, stop_monitor, middleware, id_provider } => {
+		HandshakeResponse::Accept {
+			conn_id,
+			peer_addr,
+			methods,
+			resources,
+			cfg,
+			stop_monitor,
+			middleware,
+			id_provider,
+			connections_per_ip,
+			registry,
+		} => {
 			tracing::debug!("Accepting new connection: {}", conn_id);
+			#[cfg(feature = "deflate")]
+			if let Some(deflate) = &cfg.deflate {
+				server.add_extension(deflate.extension(soketto::Mode::Server));
+			}
 			let key = {
 				let req = server.receive_request().await?;
 				let host_check = cfg.allowed_hosts.verify("Host", Some(req.headers().host));
@@ -251,9 +314,26 @@ where
 				host_check.and(origin_check).map(|()| req.key())
 			};
 
+			// The raw request is still buffered, the client address may be read from one of its headers.
+			let request = server.take_buffer();
+			let client_ip = cfg.client_ip.resolve(peer_addr.ip(), &request);
+			// The first subprotocol requested by the client that the server supports is accepted.
+			let protocol = header_value(&request, "Sec-WebSocket-Protocol").and_then(|requested| {
+				requested.split(',').map(str::trim).find_map(|p| cfg.subprotocols.iter().copied().find(|s| *s == p))
+			});
+			server.set_buffer(request);
+			let _ip_guard = match connections_per_ip.try_acquire(client_ip) {
+				Some(guard) => guard,
+				None => {
+					tracing::warn!("Too many connections from {}. Try again in a while.", client_ip);
+					server.send_response(&Response::Reject { status_code: 429 }).await?;
+					return Ok(());
+				}
+			};
+
 			match key {
 				Ok(key) => {
-					let accept = Response::Accept { key, protocol: None };
+					let accept = Response::Accept { key, protocol };
 					server.send_response(&accept).await?;
 				}
 				Err(error) => {
@@ -264,16 +344,32 @@ where
 				}
 			}
 
-			let join_result = tokio::spawn(background_task(
+			let mut details = ConnectionDetails::new(conn_id, peer_addr, TransportKind::WebSocket);
+			if let Some(protocol) = protocol {
+				details = details.with_protocol(protocol);
+			}
+			if let Some(tls) = tls {
+				details = details.with_tls(tls);
+			}
+
+			let scheduler = cfg.fair_scheduler.as_ref().map(|scheduler| {
+				let weight = cfg.fair_scheduling_weight.as_ref().map_or(1, |weight| weight.of(&details));
+				scheduler.connection(conn_id, weight)
+			});
+
+			let join_result = tokio::spawn(background_task(BackgroundTask {
 				server,
 				conn_id,
-				methods.clone(),
-				resources.clone(),
-				cfg.max_request_body_size,
-				stop_monitor.clone(),
+				details,
+				live_methods: methods.receiver(),
+				resources: resources.for_connection(),
+				cfg: cfg.clone(),
+				stop_server: stop_monitor.clone(),
 				middleware,
 				id_provider,
-			))
+				scheduler,
+				registry: registry.clone(),
+			}))
 			.await;
 
 			match join_result {
@@ -284,38 +380,101 @@ where
 	}
 }
 
-async fn background_task(
-	server: SokettoServer<'_, BufReader<BufWriter<Compat<tokio::net::TcpStream>>>>,
+/// Accepted connection and what its task needs to serve it.
+struct BackgroundTask<'a, M> {
+	server: SokettoServer<'a, Socket>,
 	conn_id: ConnectionId,
-	methods: Methods,
+	details: ConnectionDetails,
+	live_methods: MethodsReceiver,
 	resources: Resources,
-	max_request_body_size: u32,
+	cfg: Settings,
 	stop_server: StopMonitor,
-	middleware: impl Middleware,
+	middleware: M,
 	id_provider: Arc<dyn IdProvider>,
-) -> Result<(), Error> {
+	scheduler: Option<ConnectionScheduler>,
+	registry: Connections,
+}
+
+async fn background_task(task: BackgroundTask<'_, impl Middleware>) -> Result<(), Error> {
+	let BackgroundTask {
+		server,
+		conn_id,
+		details,
+		mut live_methods,
+		resources,
+		cfg,
+		stop_server,
+		middleware,
+		id_provider,
+		scheduler,
+		registry,
+	} = task;
+
 	// And we can finally transition to a websocket background_task.
 	let mut builder = server.into_builder();
-	builder.set_max_message_size(max_request_body_size as usize);
+	builder.set_max_message_size(cfg.max_request_body_size as usize);
 	let (mut sender, mut receiver) = builder.finish();
 	let (tx, mut rx) = mpsc::unbounded::<String>();
-	let close_notify = Arc::new(Notify::new());
-	let close_notify_server_stop = close_notify.clone();
 
 	let stop_server2 = stop_server.clone();
-	let sink = MethodSink::new_with_limit(tx, max_request_body_size);
-
-	middleware.on_connect();
+	let mut sink = MethodSink::new_with_limit(tx, cfg.max_request_body_size);
+	if cfg.deprecation_warnings {
+		sink = sink.with_deprecation_warnings();
+	}
+	if cfg.echo_params_in_errors {
+		sink = sink.with_params_echo();
+	}
+	if cfg.close_discriminator {
+		sink = sink.with_close_discriminator();
+	}
+	if let Some((capacity, policy)) = cfg.message_buffer {
+		sink = sink.with_buffer_capacity(capacity, policy);
+	}
+	let delivered = sink.track_delivery();
+	let _registered = registry.register(conn_id, sink.clone(), details.clone());
+	let ping_interval = cfg.keepalive.ping_interval;
+	let keepalive = KeepAlive::new(cfg.keepalive).map(Arc::new);
+	let keepalive2 = keepalive.clone();
+	middleware.on_connect_with_details(&details);
+
+	let dispatcher = Dispatcher::new(details, sink.clone(), middleware.clone())
+		.with_resources(resources)
+		.with_id_provider(id_provider)
+		.with_max_subscriptions(cfg.max_subscriptions_per_connection)
+		.with_cost_budget(cfg.cost_budget)
+		.with_method_filter(cfg.method_filter)
+		.with_async_middleware(cfg.async_middleware)
+		.with_call_spans(cfg.call_spans);
+	let close_notify_server_stop = dispatcher.close_notify();
 
 	// Send results back to the client.
 	tokio::spawn(async move {
+		let mut pings =
+			ping_interval.map(|period| tokio::time::interval_at(tokio::time::Instant::now() + period, period));
+
 		while !stop_server2.shutdown_requested() {
-			if let Some(response) = rx.next().await {
+			let response = match (pings.as_mut(), keepalive2.as_ref()) {
+				(Some(pings), Some(keepalive)) => tokio::select! {
+					response = rx.next() => response,
+					_ = pings.tick() => {
+						if let Err(err) = send_ws_ping(&mut sender).await {
+							tracing::error!("WS transport error: {:?}; terminate connection", err);
+							break;
+						}
+						keepalive.ping_sent();
+						continue;
+					}
+				},
+				_ => rx.next().await,
+			};
+
+			if let Some(response) = response {
 				// If websocket message send fail then terminate the connection.
 				if let Err(err) = send_ws_message(&mut sender, response).await {
 					tracing::error!("WS transport error: {:?}; terminate connection", err);
 					break;
 				}
+				delivered.mark_delivered();
 			} else {
 				break;
 			}
@@ -332,14 +491,13 @@ async fn background_task(
 	// Buffer for incoming data.
 	let mut data = Vec::with_capacity(100);
 	let mut method_executors = FutureDriver::default();
-	let middleware = &middleware;
 
 	let result = loop {
 		data.clear();
 
 		{
 			// Need the extra scope to drop this pinned future and reclaim access to `data`
-			let receive = receiver.receive_data(&mut data);
+			let receive = receive_message(&mut receiver, &mut data, keepalive.as_deref());
 
 			tokio::pin!(receive);
 
@@ -350,6 +508,11 @@ async fn background_task(
 						sink.close();
 						break Ok(());
 					}
+					MonitoredError::Selector(SokettoError::Io(err)) if err.kind() == io::ErrorKind::TimedOut => {
+						tracing::debug!("WS connection {} timed out: {}; terminate connection", conn_id, err);
+						sink.close();
+						break Ok(());
+					}
 					MonitoredError::Selector(SokettoError::MessageTooLarge { current, maximum }) => {
 						tracing::warn!(
 							"WS transport error: outgoing message is too big error ({} bytes, max is {})",
@@ -372,229 +535,35 @@ async fn background_task(
 
 		tracing::debug!("recv {} bytes", data.len());
 
-		let request_start = middleware.on_request();
-
-		match data.get(0) {
-			Some(b'{') => {
-				if let Ok(req) = serde_json::from_slice::<Request>(&data) {
-					tracing::debug!("recv method call={}", req.method);
-					tracing::trace!("recv: req={:?}", req);
-
-					let id = req.id.clone();
-					let params = Params::new(req.params.map(|params| params.get()));
-
-					middleware.on_call(&req.method);
-
-					match methods.method_with_name(&req.method) {
-						None => {
-							sink.send_error(req.id, ErrorCode::MethodNotFound.into());
-							middleware.on_response(request_start);
-						}
-						Some((name, method)) => match &method.inner() {
-							MethodKind::Sync(callback) => match method.claim(name, &resources) {
-								Ok(guard) => {
-									let result = (callback)(id, params, &sink);
-
-									middleware.on_result(name, result, request_start);
-									middleware.on_response(request_start);
-									drop(guard);
-								}
-								Err(err) => {
-									tracing::error!(
-										"[Methods::execute_with_resources] failed to lock resources: {:?}",
-										err
-									);
-									sink.send_error(req.id, ErrorCode::ServerIsBusy.into());
-									middleware.on_result(name, false, request_start);
-									middleware.on_response(request_start);
-								}
-							},
-							MethodKind::Async(callback) => match method.claim(name, &resources) {
-								Ok(guard) => {
-									let sink = sink.clone();
-									let id = id.into_owned();
-									let params = params.into_owned();
-
-									let fut = async move {
-										let result = (callback)(id, params, sink, conn_id, Some(guard)).await;
-										middleware.on_result(name, result, request_start);
-										middleware.on_response(request_start);
-									};
-
-									method_executors.add(fut.boxed());
-								}
-								Err(err) => {
-									tracing::error!(
-										"[Methods::execute_with_resources] failed to lock resources: {:?}",
-										err
-									);
-									sink.send_error(req.id, ErrorCode::ServerIsBusy.into());
-									middleware.on_result(name, false, request_start);
-									middleware.on_response(request_start);
-								}
-							},
-							MethodKind::Subscription(callback) => match method.claim(&req.method, &resources) {
-								Ok(guard) => {
-									let cn = close_notify.clone();
-									let conn_state =
-										ConnState { conn_id, close_notify: cn, id_provider: &*id_provider };
-
-									let result = callback(id, params, &sink, conn_state);
-									middleware.on_result(name, result, request_start);
-									middleware.on_response(request_start);
-									drop(guard);
-								}
-								Err(err) => {
-									tracing::error!(
-										"[Methods::execute_with_resources] failed to lock resources: {:?}",
-										err
-									);
-									sink.send_error(req.id, ErrorCode::ServerIsBusy.into());
-									middleware.on_result(name, false, request_start);
-									middleware.on_response(request_start);
-								}
-							},
-						},
-					}
-				} else {
-					let (id, code) = prepare_error(&data);
-					sink.send_error(id, code.into());
-					middleware.on_response(request_start);
+		if let Some(call) = dispatcher.dispatch(live_methods.current(), &data) {
+			// The calls of a message, even a whole batch, are executed with a single slot from the scheduler.
+			let permit = scheduler.as_ref().map(ConnectionScheduler::acquire);
+			method_executors.add(
+				async move {
+					let _permit = match permit {
+						Some(permit) => Some(permit.await),
+						None => None,
+					};
+					call.await;
 				}
-			}
-			Some(b'[') => {
-				// Make sure the following variables are not moved into async closure below.
-				let d = std::mem::take(&mut data);
-				let resources = &resources;
-				let methods = &methods;
-				let sink = sink.clone();
-				let id_provider = id_provider.clone();
-				let close_notify2 = close_notify.clone();
-
-				let fut = async move {
-					// Batch responses must be sent back as a single message so we read the results from each
-					// request in the batch and read the results off of a new channel, `rx_batch`, and then send the
-					// complete batch response back to the client over `tx`.
-					let (tx_batch, mut rx_batch) = mpsc::unbounded();
-					let sink_batch = MethodSink::new_with_limit(tx_batch, max_request_body_size);
-					if let Ok(batch) = serde_json::from_slice::<Vec<Request>>(&d) {
-						tracing::debug!("recv batch len={}", batch.len());
-						tracing::trace!("recv: batch={:?}", batch);
-						if !batch.is_empty() {
-							join_all(batch.into_iter().filter_map(move |req| {
-								let id = req.id.clone();
-								let params = Params::new(req.params.map(|params| params.get()));
-								let name = &req.method;
-
-								match methods.method_with_name(name) {
-									None => {
-										sink_batch.send_error(req.id, ErrorCode::MethodNotFound.into());
-										None
-									}
-									Some((name, method_callback)) => match &method_callback.inner() {
-										MethodKind::Sync(callback) => match method_callback.claim(name, resources) {
-											Ok(guard) => {
-												let result = (callback)(id, params, &sink_batch);
-												middleware.on_result(name, result, request_start);
-												drop(guard);
-												None
-											}
-											Err(err) => {
-												tracing::error!(
-													"[Methods::execute_with_resources] failed to lock resources: {:?}",
-													err
-												);
-												sink_batch.send_error(req.id, ErrorCode::ServerIsBusy.into());
-												middleware.on_result(&req.method, false, request_start);
-												None
-											}
-										},
-										MethodKind::Async(callback) => match method_callback
-											.claim(&req.method, resources)
-										{
-											Ok(guard) => {
-												let sink_batch = sink_batch.clone();
-												let id = id.into_owned();
-												let params = params.into_owned();
-
-												Some(async move {
-													let result =
-														(callback)(id, params, sink_batch, conn_id, Some(guard)).await;
-													middleware.on_result(&req.method, result, request_start);
-												})
-											}
-											Err(err) => {
-												tracing::error!(
-													"[Methods::execute_with_resources] failed to lock resources: {:?}",
-													err
-												);
-												sink_batch.send_error(req.id, ErrorCode::ServerIsBusy.into());
-												middleware.on_result(&req.method, false, request_start);
-												None
-											}
-										},
-										MethodKind::Subscription(callback) => {
-											match method_callback.claim(&req.method, resources) {
-												Ok(guard) => {
-													let close_notify = close_notify2.clone();
-													let conn_state =
-														ConnState { conn_id, close_notify, id_provider: &*id_provider };
-
-													let result = callback(id, params, &sink_batch, conn_state);
-													middleware.on_result(&req.method, result, request_start);
-													drop(guard);
-													None
-												}
-												Err(err) => {
-													tracing::error!(
-														"[Methods::execute_with_resources] failed to lock resources: {:?}",
-														err
-													);
-
-													sink_batch.send_error(req.id, ErrorCode::ServerIsBusy.into());
-													middleware.on_result(&req.method, false, request_start);
-													None
-												}
-											}
-										}
-									},
-								}
-							}))
-							.await;
-
-							rx_batch.close();
-							let results = collect_batch_response(rx_batch).await;
-
-							if let Err(err) = sink.send_raw(results) {
-								tracing::error!("Error sending batch response to the client: {:?}", err)
-							} else {
-								middleware.on_response(request_start);
-							}
-						} else {
-							sink.send_error(Id::Null, ErrorCode::InvalidRequest.into());
-							middleware.on_response(request_start);
-						}
-					} else {
-						let (id, code) = prepare_error(&d);
-						sink.send_error(id, code.into());
-						middleware.on_response(request_start);
-					}
-				};
-
-				method_executors.add(Box::pin(fut));
-			}
-			_ => {
-				sink.send_error(Id::Null, ErrorCode::ParseError.into());
-			}
+				.boxed(),
+			);
 		}
 	};
 
 	middleware.on_disconnect();
-
-	// Drive all running methods to completion.
-	// **NOTE** Do not return early in this function. This `await` needs to run to guarantee
-	// proper drop behaviour.
-	method_executors.await;
+	dispatcher.close();
+
+	// **NOTE** Do not return early in this function. The running methods need to be driven to completion or dropped
+	// here to guarantee proper drop behaviour.
+	if stop_server.shutdown_requested() {
+		// Drive all running methods to completion.
+		method_executors.await;
+	} else {
+		// Nobody is left to read the responses, cancel the running methods.
+		tracing::debug!("Cancelling {} running methods of connection {}", method_executors.count(), conn_id);
+		drop(method_executors);
+	}
 
 	result
 }
@@ -626,12 +595,48 @@ struct Settings {
 	max_request_body_size: u32,
 	/// Maximum number of incoming connections allowed.
 	max_connections: u64,
+	/// Maximum number of subscriptions opened on a connection at the same time.
+	max_subscriptions_per_connection: u32,
 	/// Policy by which to accept or deny incoming requests based on the `Origin` header.
 	allowed_origins: AllowedValue,
 	/// Policy by which to accept or deny incoming requests based on the `Host` header.
 	allowed_hosts: AllowedValue,
+	/// Subprotocols the server accepts during the handshake.
+	subprotocols: Vec<&'static str>,
 	/// Custom tokio runtime to run the server on.
 	tokio_runtime: Option<tokio::runtime::Handle>,
+	/// Scheduler shared by all connections to execute calls fairly, if enabled.
+	fair_scheduler: Option<FairScheduler>,
+	/// Weight of each connection in the fair scheduling, `1` if not set.
+	fair_scheduling_weight: Option<ConnectionWeight>,
+	/// Whether to attach deprecation notices to responses.
+	deprecation_warnings: bool,
+	/// Whether to echo the params of the calls in their error responses.
+	echo_params_in_errors: bool,
+	/// Whether the close notifications of the subscriptions carry the `subscription_closed` discriminator.
+	close_discriminator: bool,
+	/// Cost budget of each connection, if enabled.
+	cost_budget: Option<CostBudget>,
+	/// Max number of messages waiting to be sent to each connection and what to do when it's reached, if bounded.
+	message_buffer: Option<(usize, BufferFullPolicy)>,
+	/// Filter of the methods that can be called on each connection, if any.
+	method_filter: Option<MethodFilter>,
+	/// Middleware awaited around the execution of the method calls, if any.
+	async_middleware: Option<Arc<dyn AsyncMiddleware>>,
+	/// Whether to execute each method call in its own tracing span.
+	call_spans: bool,
+	/// Pings and idle timeout of each connection.
+	keepalive: KeepAliveConfig,
+	/// Maximum number of connections open at the same time from the same client address, if limited.
+	max_connections_per_ip: Option<u32>,
+	/// Where the address of the clients is taken from.
+	client_ip: ClientIp,
+	/// Configuration of the `permessage-deflate` extension, if compression is offered.
+	#[cfg(feature = "deflate")]
+	deflate: Option<DeflateConfig>,
+	/// Configuration of the TLS termination, if enabled.
+	#[cfg(feature = "tls")]
+	tls: Option<TlsConfig>,
 }
 
 impl Default for Settings {
@@ -639,9 +644,28 @@ impl Default for Settings {
 		Self {
 			max_request_body_size: TEN_MB_SIZE_BYTES,
 			max_connections: MAX_CONNECTIONS,
+			max_subscriptions_per_connection: MAX_SUBSCRIPTIONS_PER_CONNECTION,
 			allowed_origins: AllowedValue::Any,
 			allowed_hosts: AllowedValue::Any,
+			subprotocols: Vec::new(),
 			tokio_runtime: None,
+			fair_scheduler: None,
+			fair_scheduling_weight: None,
+			deprecation_warnings: false,
+			echo_params_in_errors: false,
+			close_discriminator: false,
+			cost_budget: None,
+			message_buffer: None,
+			method_filter: None,
+			async_middleware: None,
+			call_spans: false,
+			keepalive: KeepAliveConfig::default(),
+			max_connections_per_ip: None,
+			client_ip: ClientIp::PeerAddr,
+			#[cfg(feature = "deflate")]
+			deflate: None,
+			#[cfg(feature = "tls")]
+			tls: None,
 		}
 	}
 }
@@ -686,6 +710,57 @@ impl<M> Builder<M> {
 		self
 	}
 
+	/// Send a ping to each connection at this interval, closing the connections not answering it within the
+	/// [`pong timeout`](Self::pong_timeout). Closing a connection frees its subscriptions and resources.
+	///
+	/// Default: no pings.
+	pub fn ping_interval(mut self, interval: Duration) -> Self {
+		self.settings.keepalive.ping_interval = Some(interval);
+		self
+	}
+
+	/// Set the time within which the connections must answer the pings sent at the
+	/// [`ping interval`](Self::ping_interval). Default is 30 seconds.
+	pub fn pong_timeout(mut self, timeout: Duration) -> Self {
+		self.settings.keepalive.pong_timeout = timeout;
+		self
+	}
+
+	/// Close the connections that sent no message for this long, answers to pings don't count.
+	///
+	/// Clients only listening to subscriptions are silent too, so they must send a message now and then to stay
+	/// connected.
+	///
+	/// Default: connections are never considered idle.
+	pub fn idle_timeout(mut self, timeout: Duration) -> Self {
+		self.settings.keepalive.idle_timeout = Some(timeout);
+		self
+	}
+
+	/// Set the maximum number of connections open at the same time from the same client address, the handshakes of
+	/// the connections exceeding it are rejected with a `429 Too Many Requests` status. The address is taken from
+	/// [`client_ip`](Self::client_ip).
+	///
+	/// Default: no limit per address, only [`max_connections`](Self::max_connections) applies.
+	pub fn max_connections_per_ip(mut self, max: u32) -> Self {
+		self.settings.max_connections_per_ip = Some(max);
+		self
+	}
+
+	/// Set where the address of the clients is taken from for [`max_connections_per_ip`](Self::max_connections_per_ip),
+	/// for instance from the `X-Forwarded-For` header set by a reverse proxy. Default is the peer address.
+	pub fn client_ip(mut self, source: ClientIp) -> Self {
+		self.settings.client_ip = source;
+		self
+	}
+
+	/// Set the maximum number of subscriptions a connection can have open at the same time, the subscription calls
+	/// exceeding it are answered with a `Too many subscriptions on the connection` error. Default is 1024.
+	pub fn max_subscriptions_per_connection(mut self, max: u32) -> Self {
+		self.settings.max_subscriptions_per_connection = max;
+		self
+	}
+
 	/// Register a new resource kind. Errors if `label` is already registered, or if the number of
 	/// registered resources on this server instance would exceed 8.
 	///
@@ -696,6 +771,19 @@ impl<M> Builder<M> {
 		Ok(self)
 	}
 
+	/// Register a new resource kind like [`Self::register_resource`], of which the calls of a single connection can
+	/// claim at most `connection_capacity` units at the same time, so that one connection can't starve the others.
+	pub fn register_resource_per_connection(
+		mut self,
+		label: &'static str,
+		capacity: u16,
+		connection_capacity: u16,
+		default: u16,
+	) -> Result<Self, Error> {
+		self.resources.register_per_connection(label, capacity, connection_capacity, default)?;
+		Ok(self)
+	}
+
 	/// Set a list of allowed origins. During the handshake, the `Origin` header will be
 	/// checked against the list, connections without a matching origin will be denied.
 	/// Values should be hostnames with protocol.
@@ -714,7 +802,7 @@ impl<M> Builder<M> {
 		List: IntoIterator<Item = Origin>,
 		Origin: Into<String>,
 	{
-		let list: Box<_> = list.into_iter().map(Into::into).collect();
+		let list: Box<[String]> = list.into_iter().map(Into::into).collect();
 
 		if list.len() == 0 {
 			return Err(Error::EmptyAllowList("Origin"));
@@ -725,6 +813,24 @@ impl<M> Builder<M> {
 		Ok(self)
 	}
 
+	/// Set the subprotocols the server supports. During the handshake, the first subprotocol requested by the client
+	/// that the server supports is accepted, and the connections requesting none of them are accepted without a
+	/// subprotocol.
+	///
+	/// The subprotocol of a connection is available in its
+	/// [`ConnectionDetails`](../jsonrpsee_core/middleware/struct.ConnectionDetails.html#method.protocol).
+	///
+	/// ```rust
+	/// # let mut builder = jsonrpsee_ws_server::WsServerBuilder::default();
+	/// builder.set_subprotocols(["jsonrpc-2.0"]);
+	/// ```
+	///
+	/// By default no subprotocol is accepted.
+	pub fn set_subprotocols(mut self, protocols: impl IntoIterator<Item = &'static str>) -> Self {
+		self.settings.subprotocols = protocols.into_iter().collect();
+		self
+	}
+
 	/// Add a middleware to the builder [`Middleware`](../jsonrpsee_core/middleware/trait.Middleware.html).
 	///
 	/// ```
@@ -779,7 +885,7 @@ impl<M> Builder<M> {
 		List: IntoIterator<Item = Host>,
 		Host: Into<String>,
 	{
-		let list: Box<_> = list.into_iter().map(Into::into).collect();
+		let list: Box<[String]> = list.into_iter().map(Into::into).collect();
 
 		if list.len() == 0 {
 			return Err(Error::EmptyAllowList("Host"));
@@ -805,6 +911,171 @@ impl<M> Builder<M> {
 		self
 	}
 
+	/// Enable fair scheduling of method calls across connections.
+	///
+	/// At most `max_concurrent_calls` async method calls and batches are executed at the same time, and
+	/// when that limit is reached the next free slot is handed out round-robin across the connections with
+	/// calls waiting for one. This prevents a single connection sending many requests from starving the others.
+	///
+	/// Synchronous method calls and subscriptions are not affected.
+	///
+	/// Default: disabled, calls are executed as soon as they are received.
+	///
+	/// ## Panics
+	///
+	/// Panics if `max_concurrent_calls` is zero.
+	pub fn fair_scheduling(mut self, max_concurrent_calls: usize) -> Self {
+		self.settings.fair_scheduler = Some(FairScheduler::new(max_concurrent_calls));
+		self
+	}
+
+	/// Weight the connections in the [fair scheduling](Builder::fair_scheduling) of the calls, given their
+	/// details: while its calls are waiting for a slot, a connection with weight `n` gets up to `n` free slots in a
+	/// row before the next connection gets its turn. A weight of `0` counts as `1`.
+	///
+	/// Default: all connections have a weight of `1`.
+	pub fn fair_scheduling_weight(
+		mut self,
+		weight: impl Fn(&ConnectionDetails) -> u32 + Send + Sync + 'static,
+	) -> Self {
+		self.settings.fair_scheduling_weight = Some(ConnectionWeight::new(weight));
+		self
+	}
+
+	/// Enable a cost budget per connection, rejecting the calls exceeding it with a `Cost budget exceeded` error.
+	///
+	/// Each call takes the cost of the method it calls from the budget of its connection, see
+	/// [`MethodResourcesBuilder::cost`](../jsonrpsee_core/server/rpc_module/struct.MethodResourcesBuilder.html#method.cost).
+	/// The budget of a connection starts with `capacity` units, and `refill_per_sec` units are added back to it every
+	/// second up to the capacity. Unlike limiting the number of requests, this lets the expensive methods use more of
+	/// the budget than the cheap ones. Methods costing more than `capacity` can't be called at all.
+	///
+	/// The HTTP server has no such budget, the methods served over both transports are only limited over WebSocket.
+	///
+	/// Default: disabled, the calls are not limited.
+	pub fn cost_budget(mut self, capacity: u32, refill_per_sec: u32) -> Self {
+		self.settings.cost_budget = Some(CostBudget { capacity, refill_per_sec });
+		self
+	}
+
+	/// Limit the number of messages waiting to be sent to each connection to `capacity`.
+	///
+	/// The subscriptions piping a stream with
+	/// [`SubscriptionSink::pipe_from_stream`](../jsonrpsee_core/server/rpc_module/struct.SubscriptionSink.html#method.pipe_from_stream)
+	/// wait for room before sending each item, while `policy` tells what to do with the other messages sent when
+	/// the buffer of a connection is full. With [`BufferFullPolicy::Queue`] these messages are still buffered,
+	/// beyond `capacity`; [`BufferFullPolicy::Disconnect`] closes the connection of a client too slow to consume its
+	/// messages instead, which is the only way to bound the memory used by a connection.
+	///
+	/// Default: unbounded.
+	pub fn message_buffer_capacity(mut self, capacity: usize, policy: BufferFullPolicy) -> Self {
+		self.settings.message_buffer = Some((capacity, policy));
+		self
+	}
+
+	/// Only expose the methods for which `filter` returns `true` to a connection, given the method name and the
+	/// details of the connection. Calls to the other methods are answered with a `Method not found` error, as if
+	/// they didn't exist.
+	///
+	/// This applies to subscriptions too, where the subscribe and unsubscribe methods are filtered on their own.
+	///
+	/// Default: all methods can be called.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use jsonrpsee_ws_server::WsServerBuilder;
+	///
+	/// // Hide the `admin_` methods from the clients not connecting from the local host.
+	/// let builder = WsServerBuilder::default()
+	///     .method_filter(|name, details| !name.starts_with("admin_") || details.peer_addr().ip().is_loopback());
+	/// ```
+	pub fn method_filter(mut self, filter: impl Fn(&str, &ConnectionDetails) -> bool + Send + Sync + 'static) -> Self {
+		self.settings.method_filter = Some(MethodFilter::new(filter));
+		self
+	}
+
+	/// Await the hooks of an [`AsyncMiddleware`] around the execution of each method call, for instance to authorize
+	/// the calls with a remote service. Calls rejected by [`AsyncMiddleware::on_call`] are answered with its error.
+	///
+	/// The hooks are awaited in the task executing the call, so they don't block the connection. Subscriptions are
+	/// not covered.
+	///
+	/// Default: no asynchronous middleware.
+	pub fn set_async_middleware(mut self, middleware: impl AsyncMiddleware) -> Self {
+		self.settings.async_middleware = Some(Arc::new(middleware));
+		self
+	}
+
+	/// Execute each method call in its own `rpc_call` tracing span, with the method name, the connection ID and the
+	/// request ID as fields and an `otel.status_code` set from the result of the call.
+	///
+	/// The spans follow the OpenTelemetry conventions, so they can be exported with `tracing-opentelemetry`. The
+	/// notifications of a subscription are traced in spans nested under the span of its subscription call.
+	///
+	/// Default: disabled.
+	pub fn call_spans(mut self, enabled: bool) -> Self {
+		self.settings.call_spans = enabled;
+		self
+	}
+
+	/// Compress the messages of the connections negotiating the `permessage-deflate` extension, see
+	/// [`DeflateConfig`] for the window size and the size below which messages are sent uncompressed.
+	///
+	/// Clients not offering the extension are served uncompressed.
+	///
+	/// Default: compression disabled.
+	#[cfg(feature = "deflate")]
+	pub fn set_deflate(mut self, config: DeflateConfig) -> Self {
+		self.settings.deflate = Some(config);
+		self
+	}
+
+	/// Terminate TLS on the connections with the given certificate, serving `wss://` URLs. The server offers
+	/// `http/1.1` with ALPN unless [`TlsConfig::alpn_protocols`] says otherwise.
+	///
+	/// Default: plain connections.
+	#[cfg(feature = "tls")]
+	pub fn tls(mut self, config: TlsConfig) -> Self {
+		self.settings.tls = Some(config);
+		self
+	}
+
+	/// Attach the deprecation notice of deprecated methods to their responses as a `warning` field, see
+	/// [`RpcModule::deprecate_method`](../jsonrpsee_core/server/rpc_module/struct.RpcModule.html#method.deprecate_method).
+	///
+	/// The field is not part of the JSON-RPC specification, so only enable this if the clients tolerate it.
+	///
+	/// Default is false.
+	pub fn deprecation_warnings(mut self, enabled: bool) -> Self {
+		self.settings.deprecation_warnings = enabled;
+		self
+	}
+
+	/// Serialize the `subscription_closed` discriminator field in the close notifications of the subscriptions, for
+	/// the clients to reliably tell them apart from the notifications of items shaped like a close reason, see
+	/// [`SubscriptionClosed::with_discriminator`](../jsonrpsee_core/error/struct.SubscriptionClosed.html#method.with_discriminator).
+	///
+	/// Clients denying unknown fields reject the field, so only enable this if the clients tolerate it.
+	///
+	/// Default is false.
+	pub fn subscription_close_discriminator(mut self, enabled: bool) -> Self {
+		self.settings.close_discriminator = enabled;
+		self
+	}
+
+	/// Echo the params of the calls in their error responses as the `params` member of the error `data`, see
+	/// [`MethodSink::with_echoed_params`](../jsonrpsee_core/server/helpers/struct.MethodSink.html#method.with_echoed_params).
+	///
+	/// This is meant for debugging failing calls during development: the params may contain sensitive input which
+	/// then ends up in the error responses and possibly in the logs of the clients.
+	///
+	/// Default is false.
+	pub fn echo_params_in_errors(mut self, enabled: bool) -> Self {
+		self.settings.echo_params_in_errors = enabled;
+		self
+	}
+
 	/// Configure custom `subscription ID` provider for the server to use
 	/// to when getting new subscription calls.
 	///
@@ -850,10 +1121,20 @@ impl<M> Builder<M> {
 		let listener = TcpListener::bind(addrs).await?;
 		let stop_monitor = StopMonitor::new();
 		let resources = self.resources;
+		#[cfg(feature = "tls")]
+		let acceptor = match &self.settings.tls {
+			Some(tls) => Acceptor::tls(tls.acceptor(&[b"http/1.1"])?),
+			None => Acceptor::default(),
+		};
+		#[cfg(not(feature = "tls"))]
+		let acceptor = Acceptor::default();
 		Ok(Server {
 			listener,
+			acceptor,
 			cfg: self.settings,
 			stop_monitor,
+			methods: LiveMethods::new(resources.clone()),
+			connections: Connections::default(),
 			resources,
 			middleware: self.middleware,
 			id_provider: self.id_provider,
@@ -861,10 +1142,46 @@ impl<M> Builder<M> {
 	}
 }
 
-async fn send_ws_message(
-	sender: &mut Sender<BufReader<BufWriter<Compat<TcpStream>>>>,
-	response: String,
-) -> Result<(), Error> {
+/// Receive the next data message of the connection into `data`, recording the activity of the client on the
+/// keepalive if any. Fails with a [`io::ErrorKind::TimedOut`] error if the keepalive expires first.
+async fn receive_message(
+	receiver: &mut Receiver<Socket>,
+	data: &mut Vec<u8>,
+	keepalive: Option<&KeepAlive>,
+) -> Result<(), SokettoError> {
+	let keepalive = match keepalive {
+		Some(keepalive) => keepalive,
+		None => return receiver.receive_data(data).await.map(|_| ()),
+	};
+
+	let receive = async {
+		loop {
+			match receiver.receive(data).await? {
+				SokettoIncoming::Data(_) => {
+					keepalive.message_received();
+					return Ok(());
+				}
+				SokettoIncoming::Pong(_) => keepalive.pong_received(),
+				// The next call fails with `SokettoError::Closed`.
+				SokettoIncoming::Closed(_) => {}
+			}
+		}
+	};
+
+	tokio::select! {
+		result = receive => result,
+		expired = keepalive.expired() => Err(SokettoError::Io(io::Error::new(io::ErrorKind::TimedOut, expired.to_string()))),
+	}
+}
+
+async fn send_ws_ping(sender: &mut Sender<Socket>) -> Result<(), Error> {
+	tracing::debug!("send ping");
+	let payload = ByteSlice125::try_from(&[][..]).expect("An empty payload is within 125 bytes; qed");
+	sender.send_ping(payload).await?;
+	sender.flush().await.map_err(Into::into)
+}
+
+async fn send_ws_message(sender: &mut Sender<Socket>, response: String) -> Result<(), Error> {
 	tracing::debug!("send {} bytes", response.len());
 	tracing::trace!("send: {}", response);
 	sender.send_text_owned(response).await?;
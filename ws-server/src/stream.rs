@@ -0,0 +1,130 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Streams of the connections, over plain TCP or TLS.
+
+use std::fmt;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::TcpStream;
+
+use jsonrpsee_core::middleware::TlsDetails;
+#[cfg(feature = "tls")]
+use jsonrpsee_core::server::tls::{self, TlsAcceptor};
+
+/// Stream of a connection.
+pub(crate) enum ServerStream {
+	/// Plain TCP stream.
+	Plain(TcpStream),
+	/// TLS stream.
+	#[cfg(feature = "tls")]
+	Tls(Box<tokio_rustls::server::TlsStream<TcpStream>>),
+}
+
+/// Turns the accepted sockets into the streams of the connections, terminating TLS if enabled.
+#[derive(Clone, Default)]
+pub(crate) struct Acceptor {
+	#[cfg(feature = "tls")]
+	tls: Option<TlsAcceptor>,
+}
+
+impl fmt::Debug for Acceptor {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		#[cfg(feature = "tls")]
+		let tls = self.tls.is_some();
+		#[cfg(not(feature = "tls"))]
+		let tls = false;
+		f.debug_struct("Acceptor").field("tls", &tls).finish()
+	}
+}
+
+impl Acceptor {
+	/// Create an acceptor terminating TLS.
+	#[cfg(feature = "tls")]
+	pub(crate) fn tls(acceptor: TlsAcceptor) -> Self {
+		Self { tls: Some(acceptor) }
+	}
+
+	/// Returns the stream of the connection on `socket`, after the TLS handshake if enabled, failing if the client
+	/// doesn't complete it within the handshake timeout.
+	pub(crate) async fn accept(&self, socket: TcpStream) -> io::Result<ServerStream> {
+		#[cfg(feature = "tls")]
+		if let Some(tls) = &self.tls {
+			return tls.accept(socket).await.map(|stream| ServerStream::Tls(Box::new(stream)));
+		}
+		Ok(ServerStream::Plain(socket))
+	}
+}
+
+impl ServerStream {
+	/// Returns the details of the TLS session, if TLS was terminated.
+	pub(crate) fn tls(&self) -> Option<TlsDetails> {
+		match self {
+			Self::Plain(_) => None,
+			#[cfg(feature = "tls")]
+			Self::Tls(stream) => Some(tls::session_details(stream)),
+		}
+	}
+}
+
+impl AsyncRead for ServerStream {
+	fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+		match self.get_mut() {
+			Self::Plain(stream) => Pin::new(stream).poll_read(cx, buf),
+			#[cfg(feature = "tls")]
+			Self::Tls(stream) => Pin::new(stream).poll_read(cx, buf),
+		}
+	}
+}
+
+impl AsyncWrite for ServerStream {
+	fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+		match self.get_mut() {
+			Self::Plain(stream) => Pin::new(stream).poll_write(cx, buf),
+			#[cfg(feature = "tls")]
+			Self::Tls(stream) => Pin::new(stream).poll_write(cx, buf),
+		}
+	}
+
+	fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+		match self.get_mut() {
+			Self::Plain(stream) => Pin::new(stream).poll_flush(cx),
+			#[cfg(feature = "tls")]
+			Self::Tls(stream) => Pin::new(stream).poll_flush(cx),
+		}
+	}
+
+	fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+		match self.get_mut() {
+			Self::Plain(stream) => Pin::new(stream).poll_shutdown(cx),
+			#[cfg(feature = "tls")]
+			Self::Tls(stream) => Pin::new(stream).poll_shutdown(cx),
+		}
+	}
+}
@@ -31,7 +31,7 @@ use std::time::Duration;
 
 use crate::types::error::CallError;
 use crate::types::{Response, SubscriptionId};
-use crate::{future::ServerHandle, RpcModule, WsServerBuilder};
+use crate::{future::ServerHandle, BufferFullPolicy, RpcModule, WsServerBuilder};
 use anyhow::anyhow;
 use futures_util::future::join;
 use jsonrpsee_core::{traits::IdProvider, DeserializeOwned, Error};
@@ -664,3 +664,841 @@ async fn custom_subscription_id_works() {
 	let unsub = client.send_request_text(call("unsubscribe_hello", vec!["0xdeadbeef"], Id::Num(1))).await.unwrap();
 	assert_eq!(&unsub, r#"{"jsonrpc":"2.0","result":true,"id":1}"#);
 }
+
+#[tokio::test]
+async fn in_flight_call_can_be_cancelled() {
+	init_logger();
+	let server = WsServerBuilder::default().build("127.0.0.1:0").with_default_timeout().await.unwrap().unwrap();
+	let addr = server.local_addr().unwrap();
+	let mut module = RpcModule::new(());
+	module
+		.register_async_method("sleep_forever", |_, _| async move {
+			futures_util::future::pending::<()>().await;
+			Ok("never")
+		})
+		.unwrap();
+	module.register_cancel_method("cancel").unwrap();
+	server.start(module).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	client.send(call("sleep_forever", Vec::<()>::new(), Id::Num(1))).with_default_timeout().await.unwrap().unwrap();
+	// Not in flight.
+	let cancelled =
+		client.send_request_text(call("cancel", vec![99], Id::Num(2))).with_default_timeout().await.unwrap();
+	assert_eq!(cancelled.unwrap(), ok_response(false.into(), Id::Num(2)));
+
+	client.send(call("cancel", vec![1], Id::Num(3))).with_default_timeout().await.unwrap().unwrap();
+	let mut responses = vec![
+		client.receive().with_default_timeout().await.unwrap().unwrap(),
+		client.receive().with_default_timeout().await.unwrap().unwrap(),
+	];
+	responses.sort();
+
+	assert_eq!(
+		responses,
+		vec![
+			r#"{"jsonrpc":"2.0","error":{"code":-32003,"message":"Call cancelled"},"id":1}"#.to_string(),
+			ok_response(true.into(), Id::Num(3)),
+		]
+	);
+}
+
+#[tokio::test]
+async fn in_flight_call_stays_cancellable_when_its_id_is_reused() {
+	init_logger();
+	let server = WsServerBuilder::default().build("127.0.0.1:0").with_default_timeout().await.unwrap().unwrap();
+	let addr = server.local_addr().unwrap();
+	let mut module = RpcModule::new(());
+	module
+		.register_async_method("sleep_forever", |_, _| async move {
+			futures_util::future::pending::<()>().await;
+			Ok("never")
+		})
+		.unwrap();
+	module.register_async_method("answer", |_, _| async move { Ok(42) }).unwrap();
+	module.register_cancel_method("cancel").unwrap();
+	server.start(module).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	client.send(call("sleep_forever", Vec::<()>::new(), Id::Num(1))).with_default_timeout().await.unwrap().unwrap();
+	// Completing a call with the same ID leaves the first one in flight.
+	let answer =
+		client.send_request_text(call("answer", Vec::<()>::new(), Id::Num(1))).with_default_timeout().await.unwrap();
+	assert_eq!(answer.unwrap(), ok_response(42.into(), Id::Num(1)));
+
+	client.send(call("cancel", vec![1], Id::Num(2))).with_default_timeout().await.unwrap().unwrap();
+	let mut responses = vec![
+		client.receive().with_default_timeout().await.unwrap().unwrap(),
+		client.receive().with_default_timeout().await.unwrap().unwrap(),
+	];
+	responses.sort();
+
+	assert_eq!(
+		responses,
+		vec![
+			r#"{"jsonrpc":"2.0","error":{"code":-32003,"message":"Call cancelled"},"id":1}"#.to_string(),
+			ok_response(true.into(), Id::Num(2)),
+		]
+	);
+}
+
+#[tokio::test]
+async fn cancel_method_of_merged_module_cancels_all_calls() {
+	init_logger();
+	let server = WsServerBuilder::default().build("127.0.0.1:0").with_default_timeout().await.unwrap().unwrap();
+	let addr = server.local_addr().unwrap();
+	let mut module = RpcModule::new(());
+	module
+		.register_async_method("sleep_forever", |_, _| async move {
+			futures_util::future::pending::<()>().await;
+			Ok("never")
+		})
+		.unwrap();
+	module.register_cancel_method("cancel").unwrap();
+	let mut other = RpcModule::new(());
+	other.register_cancel_method("other_cancel").unwrap();
+	module.merge(other).unwrap();
+	server.start(module).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	client.send(call("sleep_forever", Vec::<()>::new(), Id::Num(1))).with_default_timeout().await.unwrap().unwrap();
+	client.send(call("other_cancel", vec![1], Id::Num(2))).with_default_timeout().await.unwrap().unwrap();
+	let mut responses = vec![
+		client.receive().with_default_timeout().await.unwrap().unwrap(),
+		client.receive().with_default_timeout().await.unwrap().unwrap(),
+	];
+	responses.sort();
+
+	assert_eq!(
+		responses,
+		vec![
+			r#"{"jsonrpc":"2.0","error":{"code":-32003,"message":"Call cancelled"},"id":1}"#.to_string(),
+			ok_response(true.into(), Id::Num(2)),
+		]
+	);
+}
+
+#[tokio::test]
+async fn fair_scheduling_does_not_starve_connections() {
+	use std::sync::{Arc, Mutex};
+	use tokio::sync::Semaphore;
+
+	init_logger();
+	let server = WsServerBuilder::default()
+		.fair_scheduling(1)
+		.build("127.0.0.1:0")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let gate = Arc::new(Semaphore::new(0));
+	let started = Arc::new(Mutex::new(Vec::new()));
+	let mut module = RpcModule::new((gate.clone(), started.clone()));
+	module
+		.register_async_method("wait", |params, ctx| async move {
+			let label: String = params.one()?;
+			ctx.1.lock().unwrap().push(label);
+			ctx.0.acquire().await.unwrap().forget();
+			Ok(())
+		})
+		.unwrap();
+	module.register_method("ping", |_, _| Ok("pong")).unwrap();
+	server.start(module).unwrap();
+
+	let mut greedy = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let mut other = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+
+	// The calls are queued once the ping sent after them is answered, which is not scheduled.
+	for id in 0..3 {
+		greedy.send(call("wait", vec!["greedy"], Id::Num(id))).with_default_timeout().await.unwrap().unwrap();
+	}
+	let pong = greedy.send_request_text(call::<u64>("ping", vec![], Id::Num(3))).with_default_timeout().await;
+	assert_eq!(pong.unwrap().unwrap(), ok_response("pong".into(), Id::Num(3)));
+	other.send(call("wait", vec!["other"], Id::Num(0))).with_default_timeout().await.unwrap().unwrap();
+	let pong = other.send_request_text(call::<u64>("ping", vec![], Id::Num(1))).with_default_timeout().await;
+	assert_eq!(pong.unwrap().unwrap(), ok_response("pong".into(), Id::Num(1)));
+
+	gate.add_permits(4);
+	for _ in 0..3 {
+		greedy.receive().with_default_timeout().await.unwrap().unwrap();
+	}
+	other.receive().with_default_timeout().await.unwrap().unwrap();
+
+	// The call from the other connection is executed right after the call of the greedy connection holding the slot.
+	assert_eq!(*started.lock().unwrap(), ["greedy", "other", "greedy", "greedy"]);
+}
+
+#[tokio::test]
+async fn deadline_is_propagated_to_async_methods() {
+	use jsonrpsee_core::server::deadline::Deadline;
+
+	init_logger();
+	let server = WsServerBuilder::default().build("127.0.0.1:0").with_default_timeout().await.unwrap().unwrap();
+	let addr = server.local_addr().unwrap();
+	let mut module = RpcModule::new(());
+	module.register_async_method("has_deadline", |_, _| async move { Ok(Deadline::current().is_some()) }).unwrap();
+	server.start(module).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let with_deadline = r#"{"jsonrpc":"2.0","method":"has_deadline","id":1,"deadline_ms":1000}"#;
+	let response = client.send_request_text(with_deadline).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, ok_response(true.into(), Id::Num(1)));
+
+	let batch = r#"[{"jsonrpc":"2.0","method":"has_deadline","id":2,"deadline_ms":1000},{"jsonrpc":"2.0","method":"has_deadline","id":3}]"#;
+	let response = client.send_request_text(batch).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, r#"[{"jsonrpc":"2.0","result":true,"id":2},{"jsonrpc":"2.0","result":false,"id":3}]"#);
+}
+
+#[tokio::test]
+async fn connection_state_is_kept_per_connection() {
+	init_logger();
+	let server = WsServerBuilder::default().build("127.0.0.1:0").with_default_timeout().await.unwrap().unwrap();
+	let addr = server.local_addr().unwrap();
+	let mut module = RpcModule::new(());
+	module
+		.register_method_with_connection_state("login", |params, state, _| {
+			state.insert(params.one::<String>()?);
+			Ok(true)
+		})
+		.unwrap();
+	module.register_method_with_connection_state("whoami", |_, state, _| Ok(state.get::<String>())).unwrap();
+	server.start(module).unwrap();
+
+	let mut alice = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let mut bob = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+
+	let login = r#"{"jsonrpc":"2.0","method":"login","params":["alice"],"id":1}"#;
+	let response = alice.send_request_text(login).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, ok_response(true.into(), Id::Num(1)));
+
+	let whoami = r#"{"jsonrpc":"2.0","method":"whoami","id":2}"#;
+	let response = alice.send_request_text(whoami).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, ok_response("alice".into(), Id::Num(2)));
+	let batch = r#"[{"jsonrpc":"2.0","method":"whoami","id":3}]"#;
+	let response = alice.send_request_text(batch).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, r#"[{"jsonrpc":"2.0","result":"alice","id":3}]"#);
+
+	let response = bob.send_request_text(whoami).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, ok_response(JsonValue::Null, Id::Num(2)));
+}
+
+#[tokio::test]
+async fn connection_state_is_available_to_all_callbacks() {
+	use jsonrpsee_core::server::connection_state::ConnectionState;
+
+	init_logger();
+	let server = WsServerBuilder::default().build("127.0.0.1:0").with_default_timeout().await.unwrap().unwrap();
+	let addr = server.local_addr().unwrap();
+	let user = || ConnectionState::current().and_then(|state| state.get::<String>());
+	let mut module = RpcModule::new(());
+	module
+		.register_method("login", |params, _| {
+			let state = ConnectionState::current().expect("Called on a connection");
+			state.insert(params.one::<String>()?);
+			Ok(true)
+		})
+		.unwrap();
+	module.register_async_method("whoami", move |_, _| async move { Ok(user()) }).unwrap();
+	module
+		.register_subscription("sub_user", "s_user", "unsub_user", move |_, mut sink, _| {
+			sink.send(&user())?;
+			Ok(())
+		})
+		.unwrap();
+	server.start(module).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let login = r#"[{"jsonrpc":"2.0","method":"login","params":["alice"],"id":1}]"#;
+	let response = client.send_request_text(login).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, r#"[{"jsonrpc":"2.0","result":true,"id":1}]"#);
+
+	let whoami = r#"{"jsonrpc":"2.0","method":"whoami","id":2}"#;
+	let response = client.send_request_text(whoami).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, ok_response("alice".into(), Id::Num(2)));
+
+	let sub = r#"{"jsonrpc":"2.0","method":"sub_user","id":3}"#;
+	client.send_request_text(sub).with_default_timeout().await.unwrap().unwrap();
+	let notif: JsonValue =
+		serde_json::from_str(&client.receive().with_default_timeout().await.unwrap().unwrap()).unwrap();
+	assert_eq!(notif["params"]["result"], "alice");
+}
+
+#[tokio::test]
+async fn connection_details_are_available_to_callbacks() {
+	use jsonrpsee_core::server::connection_state::ConnectionState;
+
+	init_logger();
+	let server = WsServerBuilder::default()
+		.set_subprotocols(["v1", "v2"])
+		.build("127.0.0.1:0")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let mut module = RpcModule::new(());
+	module
+		.register_method("peer", |_, _| {
+			let state = ConnectionState::current().expect("Called on a connection");
+			let details = state.details().expect("Set by the server");
+			Ok((
+				details.peer_addr().ip().to_string(),
+				format!("{:?}", details.transport()),
+				details.protocol().map(str::to_owned),
+			))
+		})
+		.unwrap();
+	server.start(module).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let req = r#"{"jsonrpc":"2.0","method":"peer","id":1}"#;
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, ok_response(serde_json::json!(["127.0.0.1", "WebSocket", null]), Id::Num(1)));
+
+	let mut client =
+		WebSocketTestClient::with_protocols(addr, &["v3", "v2", "v1"]).with_default_timeout().await.unwrap().unwrap();
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, ok_response(serde_json::json!(["127.0.0.1", "WebSocket", "v2"]), Id::Num(1)));
+}
+
+#[tokio::test]
+async fn filtered_methods_are_not_found() {
+	init_logger();
+	let server = WsServerBuilder::default()
+		.method_filter(|name, details| !name.starts_with("admin_") && details.peer_addr().ip().is_loopback())
+		.build("127.0.0.1:0")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("lo")).unwrap();
+	module.register_method("admin_peers", |_, _| Ok(0)).unwrap();
+	server.start(module).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#;
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, ok_response("lo".into(), Id::Num(1)));
+
+	let req = r#"{"jsonrpc":"2.0","method":"admin_peers","id":2}"#;
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, method_not_found(Id::Num(2)));
+
+	let req = r#"[{"jsonrpc":"2.0","method":"admin_peers","id":3},{"jsonrpc":"2.0","method":"say_hello","id":4}]"#;
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, format!("[{},{}]", method_not_found(Id::Num(3)), ok_response("lo".into(), Id::Num(4))));
+}
+
+#[tokio::test]
+async fn deprecation_warnings_are_attached_to_responses() {
+	init_logger();
+	let server = WsServerBuilder::default()
+		.deprecation_warnings(true)
+		.build("127.0.0.1:0")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("old_hello", |_, _| Ok("lo")).unwrap();
+	module.register_async_method("old_async_hello", |_, _| async move { Ok("lo") }).unwrap();
+	module.register_method("say_hello", |_, _| Ok("lo")).unwrap();
+	module.register_alias("older_hello", "old_hello").unwrap();
+	module.deprecate_method("old_hello", "Use `say_hello` instead").unwrap();
+	module.deprecate_method("old_async_hello", "Use `say_hello` instead").unwrap();
+	server.start(module).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let req = r#"{"jsonrpc":"2.0","method":"old_hello","id":1}"#;
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, r#"{"jsonrpc":"2.0","result":"lo","id":1,"warning":"Use `say_hello` instead"}"#);
+
+	let batch =
+		r#"[{"jsonrpc":"2.0","method":"say_hello","id":2},{"jsonrpc":"2.0","method":"old_async_hello","id":3}]"#;
+	let response = client.send_request_text(batch).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(
+		response,
+		r#"[{"jsonrpc":"2.0","result":"lo","id":2},{"jsonrpc":"2.0","result":"lo","id":3,"warning":"Use `say_hello` instead"}]"#
+	);
+
+	// The aliases registered before the deprecation are deprecated too.
+	let req = r#"{"jsonrpc":"2.0","method":"older_hello","id":4}"#;
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, r#"{"jsonrpc":"2.0","result":"lo","id":4,"warning":"Use `say_hello` instead"}"#);
+}
+
+#[tokio::test]
+async fn flow_controlled_streams_wait_for_credit() {
+	init_logger();
+	let server = WsServerBuilder::default().build("127.0.0.1:0").with_default_timeout().await.unwrap().unwrap();
+	let addr = server.local_addr().unwrap();
+	let mut module = RpcModule::new(());
+	module
+		.register_flow_controlled_streaming_method("count", "count_chunk", "count_credit", 1, |_, sink, _| async move {
+			for n in 1..=3 {
+				sink.send_when_ready(&n).await?;
+			}
+			Ok("done")
+		})
+		.unwrap();
+	server.start(module).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	client.send(r#"{"jsonrpc":"2.0","method":"count","id":1}"#).with_default_timeout().await.unwrap().unwrap();
+	let chunk = client.receive().with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(chunk, r#"{"jsonrpc":"2.0","method":"count_chunk","params":{"id":1,"chunk":1}}"#);
+
+	// The credit calls couldn't tell two streaming calls with the same ID apart.
+	let duplicate = r#"{"jsonrpc":"2.0","method":"count","id":1}"#;
+	let response = client.send_request_text(duplicate).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(
+		response,
+		r#"{"jsonrpc":"2.0","error":{"code":-32600,"message":"A streaming call with this ID is already in progress"},"id":1}"#
+	);
+
+	// Unknown calls don't take credit.
+	let credit = r#"{"jsonrpc":"2.0","method":"count_credit","params":[7, 2],"id":2}"#;
+	let response = client.send_request_text(credit).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, ok_response(false.into(), Id::Num(2)));
+
+	let credit = r#"{"jsonrpc":"2.0","method":"count_credit","params":[1, 2],"id":3}"#;
+	client.send(credit).with_default_timeout().await.unwrap().unwrap();
+	let mut received = Vec::new();
+	for _ in 0..4 {
+		received.push(client.receive().with_default_timeout().await.unwrap().unwrap());
+	}
+	let mut expected = vec![
+		ok_response(true.into(), Id::Num(3)),
+		ok_response("done".into(), Id::Num(1)),
+		r#"{"jsonrpc":"2.0","method":"count_chunk","params":{"id":1,"chunk":2}}"#.to_string(),
+		r#"{"jsonrpc":"2.0","method":"count_chunk","params":{"id":1,"chunk":3}}"#.to_string(),
+	];
+	// The response to the credit call races with the chunks it releases.
+	received.sort();
+	expected.sort();
+	assert_eq!(received, expected);
+}
+
+#[tokio::test]
+async fn params_are_echoed_in_errors_if_enabled() {
+	init_logger();
+	let server = WsServerBuilder::default()
+		.echo_params_in_errors(true)
+		.build("127.0.0.1:0")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("fail", |_, _| Err::<(), _>(anyhow!("Unknown block").into())).unwrap();
+	module.register_method("hello", |_, _| Ok("lo")).unwrap();
+	server.start(module).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let req = r#"{"jsonrpc":"2.0","method":"fail","params":{"block":1234},"id":1}"#;
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(
+		response,
+		r#"{"jsonrpc":"2.0","error":{"code":-32000,"message":"Unknown block","data":{"params":{"block":1234}}},"id":1}"#
+	);
+
+	// Successful calls and errors not returned by the method are left as is.
+	let req = r#"{"jsonrpc":"2.0","method":"hello","params":[1],"id":2}"#;
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, ok_response("lo".into(), Id::Num(2)));
+	let req = r#"{"jsonrpc":"2.0","method":"unknown","params":[1],"id":3}"#;
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, method_not_found(Id::Num(3)));
+}
+
+#[tokio::test]
+async fn calls_exceeding_the_cost_budget_are_rejected() {
+	init_logger();
+	let server = WsServerBuilder::default()
+		.cost_budget(3, 0)
+		.build("127.0.0.1:0")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("expensive", |_, _| Ok("done")).unwrap().cost(2);
+	module.register_method("cheap", |_, _| Ok("done")).unwrap();
+	server.start(module).unwrap();
+
+	let exceeded = |id| {
+		format!(
+			r#"{{"jsonrpc":"2.0","error":{{"code":-32006,"message":"Cost budget exceeded, try again later"}},"id":{}}}"#,
+			id
+		)
+	};
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let call = |method, id| format!(r#"{{"jsonrpc":"2.0","method":"{}","id":{}}}"#, method, id);
+
+	assert_eq!(
+		client.send_request_text(call("expensive", 1)).with_default_timeout().await.unwrap().unwrap(),
+		ok_response("done".into(), Id::Num(1))
+	);
+	assert_eq!(
+		client.send_request_text(call("expensive", 2)).with_default_timeout().await.unwrap().unwrap(),
+		exceeded(2)
+	);
+	assert_eq!(
+		client.send_request_text(call("cheap", 3)).with_default_timeout().await.unwrap().unwrap(),
+		ok_response("done".into(), Id::Num(3))
+	);
+	let batch = r#"[{"jsonrpc":"2.0","method":"cheap","id":4}]"#;
+	let response = client.send_request_text(batch).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, format!("[{}]", exceeded(4)));
+
+	// The budget is per connection.
+	let mut other = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let req = r#"{"jsonrpc":"2.0","method":"expensive","id":5}"#;
+	let response = other.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, ok_response("done".into(), Id::Num(5)));
+}
+
+#[tokio::test]
+async fn piped_subscriptions_wait_for_room_in_the_buffer() {
+	init_logger();
+	let server = WsServerBuilder::default()
+		.message_buffer_capacity(1, BufferFullPolicy::Disconnect)
+		.build("127.0.0.1:0")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let mut module = RpcModule::new(());
+	module
+		.register_subscription("sub_numbers", "s_numbers", "unsub_numbers", |_, sink, _| {
+			tokio::spawn(sink.pipe_from_stream(futures_util::stream::iter(0_u64..50)));
+			Ok(())
+		})
+		.unwrap();
+	server.start(module).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let req = r#"{"jsonrpc":"2.0","method":"sub_numbers","id":1}"#;
+	client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+
+	// The notifications are neither dropped nor do they get the client disconnected.
+	for expected in 0_u64..50 {
+		let notif = client.receive().with_default_timeout().await.unwrap().unwrap();
+		let notif: serde_json::Value = serde_json::from_str(&notif).unwrap();
+		assert_eq!(notif["params"]["result"], expected);
+	}
+}
+
+#[tokio::test]
+async fn open_connections_pick_up_swapped_methods() {
+	init_logger();
+	let server = WsServerBuilder::default().build("127.0.0.1:0").with_default_timeout().await.unwrap().unwrap();
+	let addr = server.local_addr().unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("version", |_, _| Ok("v1")).unwrap();
+	let handle = server.start(module).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let req = r#"{"jsonrpc":"2.0","method":"version","id":1}"#;
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, ok_response("v1".into(), Id::Num(1)));
+
+	handle
+		.patch_methods(|methods| {
+			methods.remove_method("version");
+			let mut module = RpcModule::new(());
+			module.register_method("version", |_, _| Ok("v2")).unwrap();
+			methods.merge(module)
+		})
+		.unwrap();
+
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, ok_response("v2".into(), Id::Num(1)));
+	let batch = r#"[{"jsonrpc":"2.0","method":"version","id":2}]"#;
+	let response = client.send_request_text(batch).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, format!("[{}]", ok_response("v2".into(), Id::Num(2))));
+
+	handle.swap_methods(RpcModule::new(())).unwrap();
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, method_not_found(Id::Num(1)));
+}
+
+#[tokio::test]
+async fn subscriptions_per_connection_are_limited() {
+	init_logger();
+	let server = WsServerBuilder::default()
+		.max_subscriptions_per_connection(2)
+		.build("127.0.0.1:0")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	// The sinks are kept alive, the subscriptions would be closed when dropped otherwise.
+	let mut module = RpcModule::new(std::sync::Mutex::new(Vec::new()));
+	module
+		.register_subscription("subscribe_hello", "subscribe_hello", "unsubscribe_hello", |_, sink, sinks| {
+			sinks.lock().unwrap().push(sink);
+			Ok(())
+		})
+		.unwrap();
+	server.start(module).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let sub_call = |id| call("subscribe_hello", Vec::<()>::new(), Id::Num(id));
+	let sub_id: u64 = deser_call(client.send_request_text(sub_call(0)).await.unwrap());
+	let _: u64 = deser_call(client.send_request_text(sub_call(1)).await.unwrap());
+
+	let response = client.send_request_text(sub_call(2)).await.unwrap();
+	assert_eq!(
+		response,
+		r#"{"jsonrpc":"2.0","error":{"code":-32007,"message":"Too many subscriptions on the connection"},"id":2}"#
+	);
+
+	// Other connections have their own limit.
+	let mut other = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let _: u64 = deser_call(other.send_request_text(sub_call(0)).await.unwrap());
+
+	// Closing a subscription makes room for a new one.
+	let unsub: bool =
+		deser_call(client.send_request_text(call("unsubscribe_hello", vec![sub_id], Id::Num(3))).await.unwrap());
+	assert!(unsub);
+	let _: u64 = deser_call(client.send_request_text(sub_call(4)).await.unwrap());
+}
+
+#[tokio::test]
+async fn running_calls_are_cancelled_when_the_client_disconnects() {
+	use jsonrpsee_core::server::connection_state::ConnectionState;
+	use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+
+	// Reports that the call was dropped before completing.
+	struct Cancelled(UnboundedSender<&'static str>);
+	impl Drop for Cancelled {
+		fn drop(&mut self) {
+			let _ = self.0.send("cancelled");
+		}
+	}
+
+	init_logger();
+	let server = WsServerBuilder::default().build("127.0.0.1:0").with_default_timeout().await.unwrap().unwrap();
+	let addr = server.local_addr().unwrap();
+	let (events_tx, mut events_rx) = unbounded_channel();
+	let mut module = RpcModule::new(events_tx);
+	module
+		.register_async_method("wait_forever", |_, events| async move {
+			let state = ConnectionState::current().expect("Called on a connection");
+			let closed_events = events.clone();
+			tokio::spawn(async move {
+				state.closed().await;
+				let _ = closed_events.send("closed");
+			});
+
+			let _cancelled = Cancelled((*events).clone());
+			futures_util::future::pending::<()>().await;
+			Ok(())
+		})
+		.unwrap();
+	server.start(module).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	client.send(r#"{"jsonrpc":"2.0","method":"wait_forever","id":1}"#).with_default_timeout().await.unwrap().unwrap();
+	client.close().with_default_timeout().await.unwrap().unwrap();
+
+	let mut events = vec![
+		events_rx.recv().with_default_timeout().await.unwrap().unwrap(),
+		events_rx.recv().with_default_timeout().await.unwrap().unwrap(),
+	];
+	events.sort_unstable();
+	assert_eq!(events, ["cancelled", "closed"]);
+}
+
+#[tokio::test(start_paused = true)]
+async fn unresponsive_connections_are_closed() {
+	use jsonrpsee::core::client::ClientT;
+	use jsonrpsee::ws_client::WsClientBuilder;
+
+	init_logger();
+	let server = WsServerBuilder::default()
+		.ping_interval(Duration::from_secs(1))
+		.pong_timeout(Duration::from_secs(2))
+		.build("127.0.0.1:0")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	let _handle = server.start(module).unwrap();
+
+	// The client answers the pings in the background, the test client only while it's reading.
+	let responsive = WsClientBuilder::default().build(&format!("ws://{}", addr)).await.unwrap();
+	let mut unresponsive = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	tokio::time::sleep(Duration::from_secs(10)).await;
+
+	assert_eq!(responsive.request::<String>("say_hello", None).await.unwrap(), "hello");
+	let response = unresponsive.send_request_text(call("say_hello", Vec::<()>::new(), Id::Num(1)));
+	assert!(response.with_default_timeout().await.unwrap().is_err());
+}
+
+#[tokio::test(start_paused = true)]
+async fn idle_connections_are_closed() {
+	init_logger();
+	let server = WsServerBuilder::default()
+		.idle_timeout(Duration::from_secs(10))
+		.build("127.0.0.1:0")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	let _handle = server.start(module).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let hello = |id| call("say_hello", Vec::<()>::new(), Id::Num(id));
+
+	// Each message restarts the idle timeout.
+	for id in 0..3 {
+		tokio::time::sleep(Duration::from_secs(5)).await;
+		let response = client.send_request_text(hello(id)).with_default_timeout().await.unwrap().unwrap();
+		assert_eq!(response, ok_response("hello".into(), Id::Num(id)));
+	}
+
+	tokio::time::sleep(Duration::from_secs(20)).await;
+	assert!(client.send_request_text(hello(3)).with_default_timeout().await.unwrap().is_err());
+}
+
+#[tokio::test]
+async fn connections_per_ip_are_limited() {
+	init_logger();
+	let server = WsServerBuilder::default()
+		.max_connections_per_ip(1)
+		.build("127.0.0.1:0")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(RpcModule::new(())).unwrap();
+
+	let first = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let second = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap();
+	assert!(matches!(second, Err(WebSocketTestError::RejectedWithStatusCode(429))));
+
+	// Closing the connection makes room for a new one.
+	drop(first);
+	for _ in 0..100 {
+		if WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().is_ok() {
+			return;
+		}
+		tokio::time::sleep(Duration::from_millis(10)).await;
+	}
+	panic!("Connection not accepted after the first one was closed");
+}
+
+#[tokio::test]
+async fn connections_per_forwarded_ip_are_limited() {
+	use crate::ClientIp;
+	use jsonrpsee::ws_client::WsClientBuilder;
+
+	init_logger();
+	let server = WsServerBuilder::default()
+		.max_connections_per_ip(1)
+		.client_ip(ClientIp::Header("X-Forwarded-For".into()))
+		.build("127.0.0.1:0")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let url = format!("ws://{}", server.local_addr().unwrap());
+	let _handle = server.start(RpcModule::new(())).unwrap();
+
+	let client = |ip| WsClientBuilder::default().add_header("X-Forwarded-For", ip).build(&url);
+	let _first = client("10.0.0.1").await.unwrap();
+	let _other = client("10.0.0.2, 10.0.0.1").await.unwrap();
+	assert!(client("10.0.0.1").await.is_err());
+}
+
+#[tokio::test]
+async fn connections_can_be_notified_by_id() {
+	use jsonrpsee::core::client::{ClientT, SubscriptionClientT};
+	use jsonrpsee::ws_client::WsClientBuilder;
+
+	init_logger();
+	let server = WsServerBuilder::default().build("127.0.0.1:0").with_default_timeout().await.unwrap().unwrap();
+	let url = format!("ws://{}", server.local_addr().unwrap());
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	let handle = server.start(module).unwrap();
+
+	let first = WsClientBuilder::default().build(&url).await.unwrap();
+	let mut first_notifs = first.subscribe_to_method::<String>("shutdown").await.unwrap();
+	let _: String = first.request("say_hello", None).await.unwrap();
+	let second = WsClientBuilder::default().build(&url).await.unwrap();
+	let mut second_notifs = second.subscribe_to_method::<String>("shutdown").await.unwrap();
+	let _: String = second.request("say_hello", None).await.unwrap();
+
+	let ids = handle.connection_ids();
+	assert_eq!(ids.len(), 2);
+	let connection = handle.connection(ids[0]).unwrap();
+	assert_eq!(connection.conn_id(), ids[0]);
+	connection.notify("shutdown", "in 30s").unwrap();
+
+	let notif = first_notifs.next().with_default_timeout().await.unwrap().unwrap().unwrap();
+	assert_eq!(notif, "in 30s");
+	assert!(second_notifs.next().with_timeout(Duration::from_millis(200)).await.is_err());
+
+	drop(first_notifs);
+	drop(first);
+	for _ in 0..100 {
+		if handle.connection(ids[0]).is_none() {
+			break;
+		}
+		tokio::time::sleep(Duration::from_millis(10)).await;
+	}
+	assert!(handle.connection(ids[0]).is_none());
+	assert_eq!(handle.connection_ids(), vec![ids[1]]);
+}
+
+#[tokio::test]
+async fn notifications_are_broadcast_to_the_connections() {
+	use jsonrpsee::core::client::{ClientT, Subscription, SubscriptionClientT};
+	use jsonrpsee::ws_client::{WsClient, WsClientBuilder};
+
+	init_logger();
+	let server = WsServerBuilder::default().build("127.0.0.1:0").with_default_timeout().await.unwrap().unwrap();
+	let url = format!("ws://{}", server.local_addr().unwrap());
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	let handle = server.start(module).unwrap();
+
+	let mut clients: Vec<(WsClient, Subscription<String>)> = Vec::new();
+	for _ in 0..3 {
+		let client = WsClientBuilder::default().build(&url).await.unwrap();
+		let notifs = client.subscribe_to_method::<String>("maintenance").await.unwrap();
+		let _: String = client.request("say_hello", None).await.unwrap();
+		clients.push((client, notifs));
+	}
+
+	assert_eq!(handle.broadcast("maintenance", "at noon").unwrap(), 3);
+	for (_, notifs) in &mut clients {
+		assert_eq!(notifs.next().with_default_timeout().await.unwrap().unwrap().unwrap(), "at noon");
+	}
+
+	let first = handle.connection_ids()[0];
+	let sent = handle.broadcast_filtered("maintenance", "tomorrow", |details| details.conn_id() != first).unwrap();
+	assert_eq!(sent, 2);
+	assert!(clients[0].1.next().with_timeout(Duration::from_millis(200)).await.is_err());
+	for (_, notifs) in &mut clients[1..] {
+		assert_eq!(notifs.next().with_default_timeout().await.unwrap().unwrap().unwrap(), "tomorrow");
+	}
+}